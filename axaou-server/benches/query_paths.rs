@@ -0,0 +1,66 @@
+//! Criterion benchmarks of the main query paths
+//!
+//! Replays `bench/query_mix.tsv` against a running server, one benchmark per
+//! query, so criterion can report regressions between runs:
+//!
+//! ```text
+//! AXAOU_BENCH_URL=http://localhost:3001 cargo bench --bench query_paths
+//! ```
+//!
+//! The server caches responses, so these measure the warm path; use
+//! `axaou-server bench --admin-token ...` for uncached query latencies. The
+//! benchmarks are skipped when no server answers at `AXAOU_BENCH_URL`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::time::Duration;
+
+const QUERY_MIX: &str = include_str!("../bench/query_mix.tsv");
+
+fn query_mix() -> Vec<(&'static str, &'static str)> {
+    QUERY_MIX
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(label, path)| (label.trim(), path.trim()))
+        .collect()
+}
+
+fn query_paths(c: &mut Criterion) {
+    let base = std::env::var("AXAOU_BENCH_URL").unwrap_or_else(|_| "http://localhost:3001".to_string());
+    let base = base.trim_end_matches('/').to_string();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(60))
+        .build()
+        .unwrap();
+
+    let healthy = runtime.block_on(async {
+        client
+            .get(format!("{}/healthz", base))
+            .send()
+            .await
+            .is_ok_and(|r| r.status().is_success())
+    });
+    if !healthy {
+        eprintln!("No server at {}; set AXAOU_BENCH_URL to benchmark query paths", base);
+        return;
+    }
+
+    let mut group = c.benchmark_group("query_paths");
+    group.sample_size(20);
+    for (label, path) in query_mix() {
+        let url = format!("{}{}", base, path);
+        group.bench_function(label, |b| {
+            b.to_async(&runtime).iter(|| async {
+                let response = client.get(&url).send().await.unwrap();
+                assert!(response.status().is_success(), "GET {} returned {}", url, response.status());
+                response.bytes().await.unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, query_paths);
+criterion_main!(benches);
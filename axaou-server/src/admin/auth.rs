@@ -0,0 +1,90 @@
+//! Bearer-token authentication for the admin API.
+//!
+//! Every `/api/admin/*` route requires `Authorization: Bearer <token>`
+//! matching the dataset's `admin_token` (or `AXAOU_ADMIN_TOKEN`). Without a
+//! configured token the admin API is disabled and answers 403.
+
+use crate::error::AppError;
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+/// Token admin requests must present; `None` disables the admin API
+#[derive(Debug, Clone, Default)]
+pub struct AdminToken(Option<Arc<str>>);
+
+impl AdminToken {
+    pub fn new(token: Option<&str>) -> Self {
+        Self(token.filter(|t| !t.is_empty()).map(Arc::from))
+    }
+
+    /// Check the request's bearer token
+    fn verify(&self, headers: &HeaderMap) -> Result<(), AppError> {
+        let expected = self.0.as_deref().ok_or_else(|| {
+            AppError::Forbidden("Admin API is disabled: no admin token configured".to_string())
+        })?;
+        let presented = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .ok_or_else(|| AppError::Unauthorized("Missing bearer token".to_string()))?;
+        if constant_time_eq(presented.trim().as_bytes(), expected.as_bytes()) {
+            Ok(())
+        } else {
+            Err(AppError::Unauthorized("Invalid admin token".to_string()))
+        }
+    }
+}
+
+/// Compare without short-circuiting on the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Middleware rejecting admin requests without the configured bearer token
+pub async fn require_admin(
+    State(token): State<AdminToken>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match token.verify(request.headers()) {
+        Ok(()) => next.run(request).await,
+        Err(e) => e.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(authorization: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static(authorization));
+        headers
+    }
+
+    #[test]
+    fn test_verify() {
+        let token = AdminToken::new(Some("s3cret"));
+        assert!(token.verify(&headers("Bearer s3cret")).is_ok());
+        assert!(matches!(
+            token.verify(&headers("Bearer guess")),
+            Err(AppError::Unauthorized(_))
+        ));
+        assert!(matches!(
+            token.verify(&HeaderMap::new()),
+            Err(AppError::Unauthorized(_))
+        ));
+
+        let disabled = AdminToken::new(Some(""));
+        assert!(matches!(
+            disabled.verify(&headers("Bearer ")),
+            Err(AppError::Forbidden(_))
+        ));
+    }
+}
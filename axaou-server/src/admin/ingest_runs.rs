@@ -0,0 +1,46 @@
+//! Ingest provenance endpoint.
+//!
+//! Lists the `ingest_runs` records written by `axaou-server ingest`, so the
+//! source freeze behind each table can be checked from the admin UI.
+
+use crate::api::AppState;
+use crate::cli::history::{history_query, IngestRun};
+use crate::clickhouse::QueryExt;
+use crate::error::AppError;
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Query parameters for GET /api/admin/ingest-runs
+#[derive(Debug, Deserialize)]
+pub struct IngestRunsQuery {
+    /// Only runs for this table
+    pub table: Option<String>,
+    /// Maximum runs to return (default 50, max 1000)
+    pub limit: Option<u64>,
+}
+
+/// Handler for GET /api/admin/ingest-runs
+///
+/// Returns recorded ingest runs, newest first. Empty until the first
+/// ingest has created the `ingest_runs` table.
+pub async fn list_ingest_runs(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<IngestRunsQuery>,
+) -> Result<Json<Vec<IngestRun>>, AppError> {
+    let limit = params.limit.unwrap_or(50).min(1000);
+
+    let mut query = state.clickhouse.query(&history_query(params.table.as_deref()));
+    if let Some(table) = &params.table {
+        query = query.bind(table);
+    }
+    match query.bind(limit).fetch_all_with::<IngestRun>(&state.executor).await {
+        Ok(runs) => Ok(Json(runs)),
+        // UNKNOWN_TABLE: nothing has been ingested with provenance yet
+        Err(AppError::UpstreamClickHouse(msg)) if msg.contains("Code: 60.") => Ok(Json(Vec::new())),
+        Err(e) => Err(e),
+    }
+}
@@ -1,3 +1,8 @@
-//! Admin endpoints for pipeline monitoring and management.
+//! Admin endpoints for pipeline monitoring and management, behind the
+//! bearer-token check in [`auth`].
 
+pub mod auth;
+pub mod ingest_runs;
 pub mod pipeline;
+pub mod slow_queries;
+pub mod tasks;
@@ -4,6 +4,7 @@
 //! viewing storage metrics, and generating projections.
 
 use crate::api::AppState;
+use crate::clickhouse::QueryExt;
 use crate::error::AppError;
 use axum::{extract::State, Json};
 use serde::{Deserialize, Serialize};
@@ -93,7 +94,7 @@ pub async fn get_pipeline_stats(
     let rows = state
         .clickhouse
         .query(query)
-        .fetch_all::<StatusDbRow>()
+        .fetch_all_with::<StatusDbRow>(&state.executor)
         .await
         .unwrap_or_default();
 
@@ -108,7 +109,7 @@ pub async fn get_pipeline_stats(
     let size_info = state
         .clickhouse
         .query(size_query)
-        .fetch_all::<TableSizeRow>()
+        .fetch_all_with::<TableSizeRow>(&state.executor)
         .await
         .unwrap_or_default();
 
@@ -0,0 +1,70 @@
+//! Slow-query report.
+//!
+//! Serves the per-fingerprint ClickHouse statistics recorded by the query
+//! executor (see [`crate::clickhouse::query_log`]), to find which queries,
+//! and so which endpoints, load the database. Statistics cover the time
+//! since startup or the last reset.
+
+use crate::api::AppState;
+use crate::clickhouse::query_log::{QueryStats, QueryStatsSort};
+use crate::error::AppError;
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Query parameters for GET /api/admin/slow-queries
+#[derive(Debug, Deserialize)]
+pub struct SlowQueriesQuery {
+    /// Ordering: "max" (default), "total", "mean" or "calls"
+    #[serde(default)]
+    pub sort: QueryStatsSort,
+    /// Maximum fingerprints to return (default 20, max 500)
+    pub limit: Option<usize>,
+}
+
+/// Response of GET /api/admin/slow-queries
+#[derive(Debug, Serialize)]
+pub struct SlowQueriesResponse {
+    /// Threshold above which queries are logged as slow
+    pub slow_query_ms: u64,
+    /// Distinct fingerprints recorded
+    pub fingerprints: usize,
+    /// Queries not recorded because the fingerprint table was full
+    pub dropped: u64,
+    pub queries: Vec<QueryStats>,
+}
+
+/// Handler for GET /api/admin/slow-queries
+///
+/// Returns the top fingerprints, slowest single call first unless `sort`
+/// says otherwise.
+pub async fn get_slow_queries(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<SlowQueriesQuery>,
+) -> Result<Json<SlowQueriesResponse>, AppError> {
+    let limit = params.limit.unwrap_or(20).min(500);
+    let log = &state.executor.queries;
+    let (fingerprints, dropped) = log.counts();
+    Ok(Json(SlowQueriesResponse {
+        slow_query_ms: log.slow_threshold().as_millis() as u64,
+        fingerprints,
+        dropped,
+        queries: log.top(params.sort, limit),
+    }))
+}
+
+/// Handler for DELETE /api/admin/slow-queries
+///
+/// Resets the recorded statistics, e.g. before a load test.
+pub async fn reset_slow_queries(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    state.executor.queries.reset();
+    Ok(Json(serde_json::json!({
+        "status": "success",
+        "message": "Query statistics reset"
+    })))
+}
@@ -0,0 +1,662 @@
+//! Long-running admin tasks with streamed progress.
+//!
+//! Operations such as asset refresh, cache clears and ingest runs are queued
+//! with `POST /api/admin/tasks` and executed in the background, at most
+//! [`MAX_CONCURRENT_TASKS`] at a time, instead of blocking the HTTP handler.
+//! Each task keeps a record of its latest state (listed at
+//! `GET /api/admin/tasks`) and broadcasts [`TaskEvent`]s, which clients follow
+//! over Server-Sent Events at `GET /api/admin/tasks/:task_id/events`.
+//!
+//! ```json
+//! POST /api/admin/tasks
+//! { "kind": "ingest", "table": "gene_models", "init_strategy": "replace" }
+//! ```
+
+use crate::api::AppState;
+use crate::error::AppError;
+use crate::cli::{IngestArgs, IngestTable, InitStrategy};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use futures::{stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::{RwLock, Semaphore};
+use tracing::{info, warn};
+
+/// Finished tasks kept for status lookups
+const MAX_FINISHED_TASKS: usize = 100;
+
+/// Tasks allowed to run at once; the rest wait in FIFO order
+pub const MAX_CONCURRENT_TASKS: usize = 2;
+
+/// Kind of work a task performs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskKind {
+    /// Re-run GCS asset discovery and swap in the result
+    AssetRefresh,
+    /// Clear the in-memory API response and plot cache
+    CacheClear,
+    /// Load a table into ClickHouse
+    Ingest,
+}
+
+/// Task lifecycle state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// One progress report from a running task
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressUpdate {
+    /// Phase of the operation, e.g. "ancestry" or "phenotypes"
+    pub stage: String,
+    pub current: u64,
+    pub total: Option<u64>,
+    pub message: String,
+}
+
+/// Event streamed to SSE subscribers
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TaskEvent {
+    Started,
+    Progress(ProgressUpdate),
+    Completed { result: serde_json::Value },
+    Failed { error: String },
+}
+
+impl TaskEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            TaskEvent::Started => "started",
+            TaskEvent::Progress(_) => "progress",
+            TaskEvent::Completed { .. } => "completed",
+            TaskEvent::Failed { .. } => "failed",
+        }
+    }
+
+    fn is_terminal(&self) -> bool {
+        matches!(self, TaskEvent::Completed { .. } | TaskEvent::Failed { .. })
+    }
+
+    fn to_sse(&self) -> Event {
+        let json = serde_json::to_string(self).unwrap_or_default();
+        Event::default().event(self.name()).data(json)
+    }
+}
+
+/// Point-in-time view of a task
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskSnapshot {
+    pub task_id: String,
+    pub kind: TaskKind,
+    pub status: TaskStatus,
+    pub queued_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub progress: Option<ProgressUpdate>,
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+impl TaskSnapshot {
+    /// Event a late subscriber should see first
+    fn current_event(&self) -> Option<TaskEvent> {
+        match self.status {
+            TaskStatus::Queued => None,
+            TaskStatus::Running => Some(
+                self.progress
+                    .clone()
+                    .map_or(TaskEvent::Started, TaskEvent::Progress),
+            ),
+            TaskStatus::Succeeded => Some(TaskEvent::Completed {
+                result: self.result.clone().unwrap_or(serde_json::Value::Null),
+            }),
+            TaskStatus::Failed => Some(TaskEvent::Failed {
+                error: self.error.clone().unwrap_or_default(),
+            }),
+        }
+    }
+}
+
+struct TaskEntry {
+    snapshot: Mutex<TaskSnapshot>,
+    events: broadcast::Sender<TaskEvent>,
+}
+
+impl TaskEntry {
+    fn publish(&self, event: TaskEvent) {
+        {
+            let mut snapshot = self.snapshot.lock().unwrap();
+            match &event {
+                TaskEvent::Started => {
+                    snapshot.status = TaskStatus::Running;
+                    snapshot.started_at = Some(Utc::now());
+                }
+                TaskEvent::Progress(update) => snapshot.progress = Some(update.clone()),
+                TaskEvent::Completed { result } => {
+                    snapshot.status = TaskStatus::Succeeded;
+                    snapshot.result = Some(result.clone());
+                    snapshot.finished_at = Some(Utc::now());
+                }
+                TaskEvent::Failed { error } => {
+                    snapshot.status = TaskStatus::Failed;
+                    snapshot.error = Some(error.clone());
+                    snapshot.finished_at = Some(Utc::now());
+                }
+            }
+        }
+        // No subscribers is fine; the snapshot keeps the latest state
+        let _ = self.events.send(event);
+    }
+}
+
+/// Handle passed to a running task for reporting progress
+#[derive(Clone)]
+pub struct TaskProgress {
+    entry: Arc<TaskEntry>,
+}
+
+impl TaskProgress {
+    /// ID of the task this handle reports for
+    pub fn task_id(&self) -> String {
+        self.entry.snapshot.lock().unwrap().task_id.clone()
+    }
+
+    pub fn report(&self, stage: &str, current: u64, total: Option<u64>, message: impl Into<String>) {
+        self.entry.publish(TaskEvent::Progress(ProgressUpdate {
+            stage: stage.to_string(),
+            current,
+            total,
+            message: message.into(),
+        }));
+    }
+}
+
+/// In-process queue and registry of admin tasks
+pub struct TaskRegistry {
+    tasks: RwLock<HashMap<String, Arc<TaskEntry>>>,
+    slots: Arc<Semaphore>,
+}
+
+impl Default for TaskRegistry {
+    fn default() -> Self {
+        Self::new(MAX_CONCURRENT_TASKS)
+    }
+}
+
+impl TaskRegistry {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            tasks: RwLock::new(HashMap::new()),
+            slots: Arc::new(Semaphore::new(max_concurrent.max(1))),
+        }
+    }
+
+    /// Queue `job` and return its task ID; it starts once a slot is free
+    pub async fn spawn<F, Fut>(&self, kind: TaskKind, job: F) -> String
+    where
+        F: FnOnce(TaskProgress) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<serde_json::Value, AppError>> + Send + 'static,
+    {
+        self.spawn_task(kind, job, false).await
+    }
+
+    /// Like [`Self::spawn`], but while a task of `kind` is queued or running,
+    /// return its ID instead of queueing a duplicate
+    pub async fn spawn_or_join<F, Fut>(&self, kind: TaskKind, job: F) -> String
+    where
+        F: FnOnce(TaskProgress) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<serde_json::Value, AppError>> + Send + 'static,
+    {
+        self.spawn_task(kind, job, true).await
+    }
+
+    async fn spawn_task<F, Fut>(&self, kind: TaskKind, job: F, join_active: bool) -> String
+    where
+        F: FnOnce(TaskProgress) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<serde_json::Value, AppError>> + Send + 'static,
+    {
+        let task_id = uuid::Uuid::new_v4().to_string();
+        let (events, _) = broadcast::channel(256);
+        let entry = Arc::new(TaskEntry {
+            snapshot: Mutex::new(TaskSnapshot {
+                task_id: task_id.clone(),
+                kind,
+                status: TaskStatus::Queued,
+                queued_at: Utc::now(),
+                started_at: None,
+                finished_at: None,
+                progress: None,
+                result: None,
+                error: None,
+            }),
+            events,
+        });
+
+        {
+            let mut tasks = self.tasks.write().await;
+            if join_active {
+                let active = tasks.iter().find(|(_, entry)| {
+                    let snapshot = entry.snapshot.lock().unwrap();
+                    snapshot.kind == kind && snapshot.finished_at.is_none()
+                });
+                if let Some((active_id, _)) = active {
+                    info!("Joining active {:?} task {}", kind, active_id);
+                    return active_id.clone();
+                }
+            }
+            prune_finished(&mut tasks);
+            tasks.insert(task_id.clone(), Arc::clone(&entry));
+        }
+
+        let slots = Arc::clone(&self.slots);
+        let id = task_id.clone();
+        tokio::spawn(async move {
+            // The semaphore is never closed, so acquiring only waits
+            let _permit = slots.acquire_owned().await.ok();
+            info!("Task {} ({:?}) started", id, kind);
+            entry.publish(TaskEvent::Started);
+
+            let progress = TaskProgress {
+                entry: Arc::clone(&entry),
+            };
+            match job(progress).await {
+                Ok(result) => {
+                    info!("Task {} ({:?}) completed", id, kind);
+                    entry.publish(TaskEvent::Completed { result });
+                }
+                Err(e) => {
+                    warn!("Task {} ({:?}) failed: {}", id, kind, e);
+                    entry.publish(TaskEvent::Failed {
+                        error: e.to_string(),
+                    });
+                }
+            }
+        });
+
+        task_id
+    }
+
+    async fn get(&self, task_id: &str) -> Option<Arc<TaskEntry>> {
+        self.tasks.read().await.get(task_id).cloned()
+    }
+
+    /// Current snapshot of a task
+    pub async fn snapshot(&self, task_id: &str) -> Option<TaskSnapshot> {
+        let entry = self.get(task_id).await?;
+        let snapshot = entry.snapshot.lock().unwrap().clone();
+        Some(snapshot)
+    }
+
+    /// Snapshots of all retained tasks, newest first
+    pub async fn list(&self) -> Vec<TaskSnapshot> {
+        let tasks = self.tasks.read().await;
+        let mut snapshots: Vec<TaskSnapshot> = tasks
+            .values()
+            .map(|entry| entry.snapshot.lock().unwrap().clone())
+            .collect();
+        snapshots.sort_by(|a, b| b.queued_at.cmp(&a.queued_at));
+        snapshots
+    }
+}
+
+/// Drop the oldest finished tasks beyond [`MAX_FINISHED_TASKS`]
+fn prune_finished(tasks: &mut HashMap<String, Arc<TaskEntry>>) {
+    let mut finished: Vec<(DateTime<Utc>, String)> = tasks
+        .iter()
+        .filter_map(|(id, entry)| {
+            let snapshot = entry.snapshot.lock().unwrap();
+            snapshot.finished_at.map(|t| (t, id.clone()))
+        })
+        .collect();
+    if finished.len() <= MAX_FINISHED_TASKS {
+        return;
+    }
+    finished.sort();
+    for (_, id) in finished.iter().take(finished.len() - MAX_FINISHED_TASKS) {
+        tasks.remove(id);
+    }
+}
+
+/// Events for one task: the current state first, then live updates until
+/// the task completes or fails.
+fn task_event_stream(entry: Arc<TaskEntry>) -> impl Stream<Item = TaskEvent> {
+    // Subscribe before reading the snapshot so no event falls in between
+    let rx = entry.events.subscribe();
+    let initial = entry.snapshot.lock().unwrap().current_event();
+    let finished = initial.as_ref().is_some_and(TaskEvent::is_terminal);
+
+    let live = stream::unfold((rx, finished), |(mut rx, done)| async move {
+        if done {
+            return None;
+        }
+        loop {
+            match rx.recv().await {
+                Ok(event) => {
+                    let terminal = event.is_terminal();
+                    return Some((event, (rx, terminal)));
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    stream::iter(initial).chain(live)
+}
+
+/// GET /api/admin/tasks/:task_id
+pub async fn get_task(
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<String>,
+) -> Result<Json<TaskSnapshot>, AppError> {
+    state
+        .tasks
+        .snapshot(&task_id)
+        .await
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Task {} not found", task_id)))
+}
+
+/// GET /api/admin/tasks/:task_id/events (SSE)
+///
+/// Streams `progress` events followed by a final `completed` or `failed`
+/// event, then closes. Subscribing to a finished task yields just its final
+/// event.
+pub async fn stream_task_events(
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let entry = state
+        .tasks
+        .get(&task_id)
+        .await
+        .ok_or_else(|| AppError::NotFound(format!("Task {} not found", task_id)))?;
+
+    let events = task_event_stream(entry).map(|event| Ok(event.to_sse()));
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// Body of `POST /api/admin/tasks`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TaskRequest {
+    AssetRefresh,
+    CacheClear,
+    Ingest {
+        table: IngestTable,
+        /// Hail Table path (default: the table's standard source)
+        input: Option<String>,
+        init_strategy: Option<InitStrategy>,
+        /// Row limit for test loads
+        limit: Option<u64>,
+        /// Worker pool to submit the export to
+        pool: Option<String>,
+        /// Continue an interrupted load from its checkpoints
+        #[serde(default)]
+        resume: bool,
+    },
+}
+
+impl TaskRequest {
+    pub fn kind(&self) -> TaskKind {
+        match self {
+            TaskRequest::AssetRefresh => TaskKind::AssetRefresh,
+            TaskRequest::CacheClear => TaskKind::CacheClear,
+            TaskRequest::Ingest { .. } => TaskKind::Ingest,
+        }
+    }
+}
+
+/// Execute a queued task
+async fn run_task(
+    state: Arc<AppState>,
+    request: TaskRequest,
+    progress: TaskProgress,
+) -> Result<serde_json::Value, AppError> {
+    match request {
+        TaskRequest::AssetRefresh => {
+            let diff = crate::analysis_assets::rediscover(
+                &state.config,
+                &state.metadata,
+                &state.assets,
+                Some(progress),
+            )
+            .await?;
+            Ok(serde_json::json!({
+                "added": diff.added.len(),
+                "removed": diff.removed.len(),
+            }))
+        }
+        TaskRequest::CacheClear => {
+            let entries = state.api_cache.entry_count();
+            state.api_cache.invalidate_all();
+            state.api_cache.run_pending_tasks().await;
+            Ok(serde_json::json!({ "cleared_entries": entries }))
+        }
+        TaskRequest::Ingest {
+            table,
+            input,
+            init_strategy,
+            limit,
+            pool,
+            resume,
+        } => {
+            let database = crate::clickhouse::client::database_name(
+                state.config.clickhouse_database.as_deref(),
+            );
+            let mut args = IngestArgs::new(crate::clickhouse::client::url(), database);
+            args.input = input;
+            args.limit = limit;
+            args.pool = pool;
+            args.resume = resume;
+            args.operator = Some(format!("admin-task:{}", progress.task_id()));
+            if let Some(strategy) = init_strategy {
+                args.init_strategy = strategy;
+            }
+
+            progress.report("ingest", 0, None, format!("Loading {:?}", table));
+            // The pipeline shells out synchronously; keep it off the async workers
+            let handle = tokio::runtime::Handle::current();
+            let rows = tokio::task::spawn_blocking(move || {
+                handle.block_on(crate::cli::ingest_table(table, &args))
+            })
+            .await?
+            .map_err(|e| AppError::Internal(format!("Ingest failed: {:#}", e)))?;
+            Ok(serde_json::json!({ "rows": rows }))
+        }
+    }
+}
+
+/// Queue a task and return its initial snapshot
+///
+/// Asset refreshes are coalesced: while one is queued or running, further
+/// requests get its snapshot rather than starting a second GCS scan.
+async fn enqueue(state: &Arc<AppState>, request: TaskRequest) -> TaskSnapshot {
+    let task_state = Arc::clone(state);
+    let kind = request.kind();
+    let job = move |progress| run_task(task_state, request, progress);
+    let task_id = match kind {
+        TaskKind::AssetRefresh => state.tasks.spawn_or_join(kind, job).await,
+        _ => state.tasks.spawn(kind, job).await,
+    };
+    state
+        .tasks
+        .snapshot(&task_id)
+        .await
+        .expect("task was just registered")
+}
+
+/// POST /api/admin/tasks
+///
+/// Queues a task and returns `202 Accepted` with its record; follow it at
+/// `/api/admin/tasks/:task_id/events`.
+pub async fn create_task(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<TaskRequest>,
+) -> (StatusCode, Json<TaskSnapshot>) {
+    let snapshot = enqueue(&state, request).await;
+    (StatusCode::ACCEPTED, Json(snapshot))
+}
+
+/// Query parameters for GET /api/admin/tasks
+#[derive(Debug, Deserialize)]
+pub struct TaskListQuery {
+    pub kind: Option<TaskKind>,
+    pub status: Option<TaskStatus>,
+}
+
+/// GET /api/admin/tasks
+pub async fn list_tasks(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TaskListQuery>,
+) -> Json<Vec<TaskSnapshot>> {
+    let tasks = state
+        .tasks
+        .list()
+        .await
+        .into_iter()
+        .filter(|t| query.kind.is_none_or(|k| t.kind == k))
+        .filter(|t| query.status.is_none_or(|s| t.status == s))
+        .collect();
+    Json(tasks)
+}
+
+/// POST /api/admin/assets/refresh (alias: /api/admin/assets/rediscover)
+///
+/// Shorthand for queueing an `asset_refresh` task, which re-runs discovery in
+/// the background and swaps in the result.
+pub async fn start_rediscovery(
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<TaskSnapshot>) {
+    let snapshot = enqueue(&state, TaskRequest::AssetRefresh).await;
+    (StatusCode::ACCEPTED, Json(snapshot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_task_events_end_with_completion() {
+        let registry = TaskRegistry::default();
+        let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let task_id = registry
+            .spawn(TaskKind::CacheClear, |progress| async move {
+                progress.report("step", 1, Some(2), "first");
+                release_rx.await.ok();
+                progress.report("step", 2, Some(2), "second");
+                Ok(serde_json::json!({ "done": true }))
+            })
+            .await;
+
+        // Subscribe while the task is blocked after its first report
+        tokio::task::yield_now().await;
+        let entry = registry.get(&task_id).await.unwrap();
+        let stream = task_event_stream(Arc::clone(&entry));
+        release_tx.send(()).unwrap();
+
+        let events: Vec<TaskEvent> = stream.collect().await;
+        let last = events.last().unwrap();
+        assert!(matches!(last, TaskEvent::Completed { .. }));
+        assert!(events.iter().filter(|e| e.is_terminal()).count() == 1);
+
+        let snapshot = registry.snapshot(&task_id).await.unwrap();
+        assert_eq!(snapshot.status, TaskStatus::Succeeded);
+        assert_eq!(snapshot.progress.unwrap().current, 2);
+
+        // Late subscribers get only the final event
+        let events: Vec<TaskEvent> = task_event_stream(entry).collect().await;
+        assert_eq!(events.len(), 1);
+        assert!(events[0].is_terminal());
+    }
+
+    #[tokio::test]
+    async fn test_spawn_or_join_coalesces_active_tasks() {
+        let registry = TaskRegistry::default();
+        let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let first = registry
+            .spawn_or_join(TaskKind::AssetRefresh, |_| async move {
+                release_rx.await.ok();
+                Ok(serde_json::Value::Null)
+            })
+            .await;
+        let joined = registry
+            .spawn_or_join(TaskKind::AssetRefresh, |_| async { Ok(serde_json::Value::Null) })
+            .await;
+        assert_eq!(joined, first);
+        assert_eq!(registry.list().await.len(), 1);
+
+        // Once the active task finishes, the next request starts a new one
+        release_tx.send(()).unwrap();
+        let entry = registry.get(&first).await.unwrap();
+        let _: Vec<TaskEvent> = task_event_stream(entry).collect().await;
+        let next = registry
+            .spawn_or_join(TaskKind::AssetRefresh, |_| async { Ok(serde_json::Value::Null) })
+            .await;
+        assert_ne!(next, first);
+    }
+
+    #[tokio::test]
+    async fn test_failed_task_records_error() {
+        let registry = TaskRegistry::default();
+        let task_id = registry
+            .spawn(TaskKind::CacheClear, |_| async {
+                Err(AppError::Internal("boom".to_string()))
+            })
+            .await;
+
+        let entry = registry.get(&task_id).await.unwrap();
+        let events: Vec<TaskEvent> = task_event_stream(entry).collect().await;
+        assert!(matches!(events.last(), Some(TaskEvent::Failed { .. })));
+        let snapshot = registry.snapshot(&task_id).await.unwrap();
+        assert_eq!(snapshot.status, TaskStatus::Failed);
+        assert!(snapshot.error.unwrap().contains("boom"));
+    }
+
+    #[tokio::test]
+    async fn test_tasks_queue_beyond_concurrency_limit() {
+        let registry = TaskRegistry::new(1);
+        let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let first = registry
+            .spawn(TaskKind::Ingest, |_| async move {
+                release_rx.await.ok();
+                Ok(serde_json::Value::Null)
+            })
+            .await;
+        let second = registry
+            .spawn(TaskKind::CacheClear, |_| async { Ok(serde_json::Value::Null) })
+            .await;
+
+        tokio::task::yield_now().await;
+        assert_eq!(registry.snapshot(&first).await.unwrap().status, TaskStatus::Running);
+        assert_eq!(registry.snapshot(&second).await.unwrap().status, TaskStatus::Queued);
+
+        release_tx.send(()).unwrap();
+        let entry = registry.get(&second).await.unwrap();
+        let events: Vec<TaskEvent> = task_event_stream(entry).collect().await;
+        assert!(matches!(events.first(), Some(TaskEvent::Started)));
+        assert!(matches!(events.last(), Some(TaskEvent::Completed { .. })));
+
+        assert_eq!(registry.list().await.len(), 2);
+    }
+}
@@ -3,33 +3,47 @@
 //! This module discovers per-phenotype result files (Hail Tables) from GCS
 //! by scanning the directory structure:
 //!
-//! gs://aou_results/414k/ht_results/{ANCESTRY}/{phenotype}/
+//! gs://{results_bucket}/{results_prefix}/{ANCESTRY}/{phenotype}/
 //!   - exome_variant_results.ht           (single-variant exome associations)
 //!   - genome_variant_results.ht          (single-variant genome/ACAF associations)
 //!   - exome_variant_results_approx_cdf_expected_p.ht  (Q-Q plot data)
 //!   - genome_variant_results_approx_cdf_expected_p.ht (Q-Q plot data)
 //!   - gene_results.ht                    (gene-level burden tests)
-
+//!
+//! The bucket and prefix come from [`Config`] (v8/414k defaults:
+//! `gs://aou_results/414k/ht_results`).
+//!
+//! A full GCS scan takes minutes, so `discover --to-clickhouse` also stores
+//! the snapshot in the `analysis_assets` table; the server loads it from there
+//! first and only scans GCS when the table is missing or empty.
+
+use crate::admin::tasks::TaskProgress;
+use crate::cli::sql::SqlClient;
+use crate::clickhouse::executor::{QueryExecutor, QueryExt};
+use crate::config::Config;
 use crate::error::AppError;
 use crate::models::{
-    AnalysisAsset, AnalysisAssetType, AnalysisAssets, AncestryGroup, SequencingType,
+    AnalysisAsset, AnalysisAssetType, AnalysisAssets, AnalysisMetadata, AncestryGroup, AssetDiff,
+    SequencingType,
 };
+use crate::webhooks::{self, Event};
+use anyhow::Context;
 use futures::{stream, StreamExt, TryStreamExt};
 use object_store::gcp::GoogleCloudStorageBuilder;
 use object_store::path::Path as ObjectPath;
 use object_store::ObjectStore;
-use std::collections::HashSet;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
-/// Base GCS path for per-phenotype Hail Tables (v8/414k dataset)
-/// From: gs://aou_results/414k/ht_results
-const PER_PHENOTYPE_BASE: &str = "414k/ht_results";
-const BUCKET: &str = "aou_results";
+const ANALYSIS_ASSETS_DDL: &str = include_str!("sql/analysis_assets.sql");
 
 /// Result files to look for in each phenotype directory
 /// Based on actual v8/414k GCS structure:
-/// gs://aou_results/414k/ht_results/{ANCESTRY}/{phenotype}/
+/// gs://{results_bucket}/{results_prefix}/{ANCESTRY}/{phenotype}/
 const ASSET_FILES: &[(&str, AnalysisAssetType, Option<SequencingType>)] = &[
     // Variant results (full)
     (
@@ -55,33 +69,101 @@ const ASSET_FILES: &[(&str, AnalysisAssetType, Option<SequencingType>)] = &[
     ),
     // Gene-level burden results
     ("gene_results.ht", AnalysisAssetType::Gene, None),
+    // Stepwise conditional analysis, only produced for some phenotypes
+    (
+        "exome_conditional_results.ht",
+        AnalysisAssetType::Conditional,
+        Some(SequencingType::Exomes),
+    ),
+    (
+        "genome_conditional_results.ht",
+        AnalysisAssetType::Conditional,
+        Some(SequencingType::Genomes),
+    ),
+    // Fine-mapping credible sets, only produced for some phenotypes
+    (
+        "exome_credible_sets.ht",
+        AnalysisAssetType::CredibleSet,
+        Some(SequencingType::Exomes),
+    ),
+    (
+        "genome_credible_sets.ht",
+        AnalysisAssetType::CredibleSet,
+        Some(SequencingType::Genomes),
+    ),
 ];
 
 /// Query for discovering analysis assets
 pub struct AssetDiscovery {
     store: Arc<dyn ObjectStore>,
+    bucket: String,
+    base_prefix: String,
+    progress: Option<TaskProgress>,
 }
 
 impl AssetDiscovery {
-    /// Create a new asset discovery instance
-    pub fn new() -> Result<Self, AppError> {
+    /// Create a new asset discovery instance for the configured results bucket
+    pub fn new(config: &Config) -> Result<Self, AppError> {
         let store = GoogleCloudStorageBuilder::new()
-            .with_bucket_name(BUCKET)
+            .with_bucket_name(&config.results_bucket)
             .build()
-            .map_err(|e| AppError::DataTransformError(format!("Failed to create GCS client: {}", e)))?;
+            .map_err(|e| AppError::UpstreamGcs(format!("Failed to create GCS client: {}", e)))?;
 
         Ok(Self {
             store: Arc::new(store),
+            bucket: config.results_bucket.clone(),
+            base_prefix: config.results_prefix(),
+            progress: None,
         })
     }
 
+    /// Report per-ancestry and per-phenotype progress to an admin task
+    pub fn with_progress(mut self, progress: TaskProgress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
     /// Discover all analysis assets from GCS
     ///
     /// This scans the directory structure to find all available result files.
     /// It filters by valid ancestry groups and checks which result files exist.
     /// Uses parallel processing for ancestry groups to speed up discovery.
     pub async fn discover_all(&self, valid_phenotypes: Option<&HashSet<String>>) -> Result<AnalysisAssets, AppError> {
-        info!("Starting analysis asset discovery from gs://{}/{}", BUCKET, PER_PHENOTYPE_BASE);
+        info!("Starting analysis asset discovery from gs://{}/{}", self.bucket, self.base_prefix);
+        let (assets, _) = self.discover(valid_phenotypes, None).await?;
+        Ok(assets)
+    }
+
+    /// Refresh a previous discovery snapshot and report what changed
+    ///
+    /// Makes the same requests as a full scan, one listing per phenotype
+    /// prefix, and compares the tables listed under each prefix with the
+    /// ones recorded in `previous`. Prefixes that disappeared are dropped.
+    pub async fn discover_incremental(
+        &self,
+        previous: &AnalysisAssets,
+        valid_phenotypes: Option<&HashSet<String>>,
+    ) -> Result<(AnalysisAssets, DiscoveryReport), AppError> {
+        info!(
+            "Starting incremental asset discovery from gs://{}/{} ({} previous assets)",
+            self.bucket,
+            self.base_prefix,
+            previous.assets.len()
+        );
+        let (assets, counts) = self.discover(valid_phenotypes, Some(previous)).await?;
+        let report = DiscoveryReport {
+            unchanged_prefixes: counts.unchanged,
+            changed_prefixes: counts.changed,
+            diff: previous.diff(&assets),
+        };
+        Ok((assets, report))
+    }
+
+    async fn discover(
+        &self,
+        valid_phenotypes: Option<&HashSet<String>>,
+        previous: Option<&AnalysisAssets>,
+    ) -> Result<(AnalysisAssets, PrefixCounts), AppError> {
         let start = std::time::Instant::now();
 
         // Clone valid_phenotypes for sharing across tasks
@@ -91,27 +173,42 @@ impl AssetDiscovery {
         let mut handles = Vec::new();
         for ancestry in AncestryGroup::all() {
             let store = Arc::clone(&self.store);
+            let bucket = self.bucket.clone();
+            let base_prefix = self.base_prefix.clone();
             let valid = valid_phenotypes_arc.clone();
             let ancestry = *ancestry;
+            let known = previous.map(|p| previous_by_phenotype(p, ancestry));
+            let progress = self.progress.clone();
 
             let handle = tokio::spawn(async move {
-                let discovery = AssetDiscoveryWorker { store };
-                discovery.discover_for_ancestry(ancestry, valid.as_deref()).await
+                let discovery = AssetDiscoveryWorker {
+                    store,
+                    bucket,
+                    base_prefix,
+                    progress,
+                };
+                discovery
+                    .discover_for_ancestry(ancestry, valid.as_deref(), known.as_ref())
+                    .await
             });
             handles.push((ancestry, handle));
         }
 
         // Collect results from all tasks
         let mut all_assets = Vec::new();
-        for (ancestry, handle) in handles {
+        let mut counts = PrefixCounts::default();
+        let total_ancestries = handles.len() as u64;
+        for (done, (ancestry, handle)) in handles.into_iter().enumerate() {
             match handle.await {
-                Ok(Ok(assets)) => {
+                Ok(Ok((assets, ancestry_counts))) => {
                     info!(
                         "Found {} assets for ancestry {}",
                         assets.len(),
                         ancestry.dir_name()
                     );
                     all_assets.extend(assets);
+                    counts.unchanged += ancestry_counts.unchanged;
+                    counts.changed += ancestry_counts.changed;
                 }
                 Ok(Err(e)) => {
                     warn!("Error discovering assets for {}: {}", ancestry.dir_name(), e);
@@ -120,18 +217,67 @@ impl AssetDiscovery {
                     warn!("Task panicked for {}: {}", ancestry.dir_name(), e);
                 }
             }
+            if let Some(progress) = &self.progress {
+                progress.report(
+                    "ancestry",
+                    done as u64 + 1,
+                    Some(total_ancestries),
+                    format!("{} processed ({} assets so far)", ancestry.dir_name(), all_assets.len()),
+                );
+            }
         }
 
         let elapsed = start.elapsed();
         info!("Total assets discovered: {} in {:.2}s", all_assets.len(), elapsed.as_secs_f64());
-        Ok(AnalysisAssets { assets: all_assets })
+        Ok((
+            AnalysisAssets {
+                assets: all_assets,
+                ..Default::default()
+            },
+            counts,
+        ))
     }
+}
+
+/// Change report emitted by incremental discovery
+#[derive(Debug, Serialize)]
+pub struct DiscoveryReport {
+    /// Phenotype prefixes whose tables match the previous snapshot
+    pub unchanged_prefixes: usize,
+    /// Phenotype prefixes that are new or gained or lost tables
+    pub changed_prefixes: usize,
+    /// Assets added/removed relative to the previous snapshot
+    #[serde(flatten)]
+    pub diff: AssetDiff,
+}
 
+#[derive(Debug, Default)]
+struct PrefixCounts {
+    unchanged: usize,
+    changed: usize,
+}
+
+/// Previous assets for one ancestry, keyed by analysis ID
+fn previous_by_phenotype(
+    previous: &AnalysisAssets,
+    ancestry: AncestryGroup,
+) -> HashMap<String, Vec<AnalysisAsset>> {
+    let mut by_phenotype: HashMap<String, Vec<AnalysisAsset>> = HashMap::new();
+    for asset in previous.assets.iter().filter(|a| a.ancestry_group == ancestry) {
+        by_phenotype
+            .entry(asset.analysis_id.clone())
+            .or_default()
+            .push(asset.clone());
+    }
+    by_phenotype
 }
 
 /// Worker for parallel asset discovery (can be sent across task boundaries)
 struct AssetDiscoveryWorker {
     store: Arc<dyn ObjectStore>,
+    bucket: String,
+    base_prefix: String,
+    progress: Option<TaskProgress>,
 }
 
 /// Max concurrent GCS requests per ancestry group
@@ -143,15 +289,19 @@ impl AssetDiscoveryWorker {
     /// Strategy: Use list_with_delimiter at each level to avoid descending into .ht directories
     /// - Level 1: List phenotype directories under ancestry (1 call)
     /// - Level 2: List .ht directories under each phenotype (parallel with concurrency limit)
+    ///
+    /// With `known` assets from a previous snapshot, each phenotype's listed
+    /// tables are also compared with the recorded ones.
     async fn discover_for_ancestry(
         &self,
         ancestry: AncestryGroup,
         valid_phenotypes: Option<&HashSet<String>>,
-    ) -> Result<Vec<AnalysisAsset>, AppError> {
+        known: Option<&HashMap<String, Vec<AnalysisAsset>>>,
+    ) -> Result<(Vec<AnalysisAsset>, PrefixCounts), AppError> {
         use std::sync::atomic::{AtomicUsize, Ordering};
 
         let start = std::time::Instant::now();
-        let ancestry_prefix = ObjectPath::from(format!("{}/{}", PER_PHENOTYPE_BASE, ancestry.dir_name()));
+        let ancestry_prefix = ObjectPath::from(format!("{}/{}", self.base_prefix, ancestry.dir_name()));
 
         info!("[{}] Listing phenotype directories...", ancestry.dir_name());
 
@@ -159,7 +309,7 @@ impl AssetDiscoveryWorker {
         let phenotype_list = self.store
             .list_with_delimiter(Some(&ancestry_prefix))
             .await
-            .map_err(|e| AppError::DataTransformError(format!("Failed to list {}: {}", ancestry_prefix, e)))?;
+            .map_err(|e| AppError::UpstreamGcs(format!("Failed to list {}: {}", ancestry_prefix, e)))?;
 
         let phenotype_dirs: Vec<_> = phenotype_list.common_prefixes;
         let total_phenotypes = phenotype_dirs.len();
@@ -191,40 +341,37 @@ impl AssetDiscoveryWorker {
 
         // Progress counter
         let processed = AtomicUsize::new(0);
+        let unchanged = AtomicUsize::new(0);
         let store = Arc::clone(&self.store);
+        let bucket = self.bucket.as_str();
 
         // Step 2: Process phenotypes in parallel with concurrency limit
         let results: Vec<Vec<AnalysisAsset>> = stream::iter(filtered_phenotypes)
             .map(|(phenotype_path, _phenotype_name, analysis_id)| {
                 let store = Arc::clone(&store);
                 let processed = &processed;
+                let unchanged = &unchanged;
                 let ancestry = ancestry;
+                let previous = known.and_then(|k| k.get(&analysis_id));
+                let progress = self.progress.as_ref();
 
                 async move {
-                    let mut assets = Vec::new();
-
-                    // List .ht directories within this phenotype
-                    if let Ok(result) = store.list_with_delimiter(Some(&phenotype_path)).await {
-                        for ht_dir in result.common_prefixes {
-                            let filename = ht_dir.filename().map(|s| s.to_string()).unwrap_or_default();
-
-                            if let Some((asset_type, seq_type)) = match_asset_file(&filename) {
-                                let uri = format!("gs://{}/{}", BUCKET, ht_dir.as_ref().trim_end_matches('/'));
-
-                                assets.push(AnalysisAsset {
-                                    ancestry_group: ancestry,
-                                    analysis_id: analysis_id.clone(),
-                                    uri,
-                                    asset_type,
-                                    sequencing_type: seq_type,
-                                });
-                            }
-                        }
+                    let assets = scan_phenotype(store.as_ref(), bucket, ancestry, &phenotype_path, &analysis_id).await;
+                    if known.is_some() && same_tables(previous, &assets) {
+                        unchanged.fetch_add(1, Ordering::Relaxed);
                     }
 
                     let count = processed.fetch_add(1, Ordering::Relaxed) + 1;
                     if count % 500 == 0 || count == filtered_count {
                         debug!("[{}] Processed {}/{} phenotypes", ancestry.dir_name(), count, filtered_count);
+                        if let Some(progress) = progress {
+                            progress.report(
+                                "phenotypes",
+                                count as u64,
+                                Some(filtered_count as u64),
+                                format!("[{}] scanned {}/{} phenotypes", ancestry.dir_name(), count, filtered_count),
+                            );
+                        }
                     }
 
                     assets
@@ -236,18 +383,60 @@ impl AssetDiscoveryWorker {
 
         // Flatten results
         let assets: Vec<AnalysisAsset> = results.into_iter().flatten().collect();
+        let unchanged = unchanged.into_inner();
 
         let elapsed = start.elapsed().as_secs_f64();
         let rate = filtered_count as f64 / elapsed;
         info!(
-            "[{}] Complete: {} phenotypes → {} assets in {:.1}s ({:.0} phenotypes/sec)",
-            ancestry.dir_name(), filtered_count, assets.len(), elapsed, rate
+            "[{}] Complete: {} phenotypes ({} unchanged) → {} assets in {:.1}s ({:.0} phenotypes/sec)",
+            ancestry.dir_name(), filtered_count, unchanged, assets.len(), elapsed, rate
         );
 
-        Ok(assets)
+        let counts = PrefixCounts {
+            unchanged,
+            changed: filtered_count - unchanged,
+        };
+        Ok((assets, counts))
     }
 }
 
+/// List the .ht directories within one phenotype prefix
+async fn scan_phenotype(
+    store: &dyn ObjectStore,
+    bucket: &str,
+    ancestry: AncestryGroup,
+    phenotype_path: &ObjectPath,
+    analysis_id: &str,
+) -> Vec<AnalysisAsset> {
+    let mut assets = Vec::new();
+
+    if let Ok(result) = store.list_with_delimiter(Some(phenotype_path)).await {
+        for ht_dir in result.common_prefixes {
+            let filename = ht_dir.filename().map(|s| s.to_string()).unwrap_or_default();
+
+            if let Some((asset_type, seq_type)) = match_asset_file(&filename) {
+                let uri = format!("gs://{}/{}", bucket, ht_dir.as_ref().trim_end_matches('/'));
+
+                assets.push(AnalysisAsset {
+                    ancestry_group: ancestry,
+                    analysis_id: analysis_id.to_string(),
+                    uri,
+                    asset_type,
+                    sequencing_type: seq_type,
+                });
+            }
+        }
+    }
+
+    assets
+}
+
+/// Whether a phenotype's listed tables are the ones recorded for it
+fn same_tables(previous: Option<&Vec<AnalysisAsset>>, current: &[AnalysisAsset]) -> bool {
+    let uris = |assets: &[AnalysisAsset]| assets.iter().map(|a| a.uri.clone()).collect::<BTreeSet<_>>();
+    previous.map_or(current.is_empty(), |previous| uris(previous) == uris(current))
+}
+
 /// Parse an object path to extract phenotype and filename
 /// Path format: 414k/ht_results/{ANCESTRY}/{phenotype}/{filename}.ht/...
 fn parse_asset_path(path: &str, ancestry_dir: &str) -> Option<(String, String)> {
@@ -291,6 +480,200 @@ fn normalize_analysis_id(raw_id: &str) -> String {
         .to_string()
 }
 
+/// Re-run discovery and swap in the new snapshot.
+///
+/// The cached assets are replaced only when discovery succeeds, so transient
+/// GCS errors keep serving the previous snapshot. An empty result while assets
+/// are already cached is treated as a failed listing and ignored.
+pub async fn rediscover(
+    config: &Config,
+    metadata: &RwLock<Vec<AnalysisMetadata>>,
+    assets: &RwLock<Option<AnalysisAssets>>,
+    progress: Option<TaskProgress>,
+) -> Result<AssetDiff, AppError> {
+    let valid_phenotypes = {
+        let metadata = metadata.read().await;
+        (!metadata.is_empty()).then(|| get_valid_phenotypes(&metadata))
+    };
+
+    let mut discovery = AssetDiscovery::new(config)?;
+    if let Some(progress) = progress {
+        discovery = discovery.with_progress(progress);
+    }
+    let discovered = discovery.discover_all(valid_phenotypes.as_ref()).await?;
+
+    let mut current = assets.write().await;
+    let previous = current.take().unwrap_or_default();
+    if discovered.assets.is_empty() && !previous.assets.is_empty() {
+        *current = Some(previous);
+        return Err(AppError::UpstreamGcs(
+            "Re-discovery returned no assets; keeping previous snapshot".to_string(),
+        ));
+    }
+
+    let diff = previous.diff(&discovered);
+    if diff.is_empty() {
+        info!("Asset re-discovery: no changes ({} assets)", discovered.assets.len());
+    } else {
+        info!(
+            "Asset re-discovery: {} added, {} removed ({} total)",
+            diff.added.len(),
+            diff.removed.len(),
+            discovered.assets.len()
+        );
+    }
+    let total = discovered.assets.len();
+    *current = Some(discovered);
+    drop(current);
+
+    if !diff.is_empty() {
+        let phenotypes = |assets: &[AnalysisAsset]| -> Vec<String> {
+            let ids: BTreeSet<String> = assets.iter().map(|a| a.analysis_id.clone()).collect();
+            ids.into_iter().take(100).collect()
+        };
+        let event = Event::AssetsChanged {
+            dataset: config.dataset_version.clone(),
+            added: diff.added.len(),
+            removed: diff.removed.len(),
+            total,
+            added_phenotypes: phenotypes(&diff.added),
+            removed_phenotypes: phenotypes(&diff.removed),
+        };
+        webhooks::spawn(&config.webhooks, event);
+    }
+    Ok(diff)
+}
+
+/// Periodically run [`rediscover`] in the background.
+pub fn spawn_periodic_rediscovery(
+    config: Arc<Config>,
+    metadata: Arc<RwLock<Vec<AnalysisMetadata>>>,
+    assets: Arc<RwLock<Option<AnalysisAssets>>>,
+    interval: Duration,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick fires immediately; startup discovery is handled elsewhere
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            info!("Periodic asset re-discovery starting...");
+            if let Err(e) = rediscover(&config, &metadata, &assets, None).await {
+                warn!("Periodic asset re-discovery failed: {}", e);
+            }
+        }
+    });
+}
+
+/// One row of `analysis_assets`; enums are stored as their serde names
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, clickhouse::Row)]
+struct AnalysisAssetRow {
+    analysis_id: String,
+    ancestry_group: String,
+    asset_type: String,
+    sequencing_type: Option<String>,
+    uri: String,
+}
+
+/// Serde name of a unit enum variant (e.g. `variant_exp_p`)
+fn serde_name<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(s)) => s,
+        _ => String::new(),
+    }
+}
+
+fn from_serde_name<T: DeserializeOwned>(name: &str) -> Option<T> {
+    serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+}
+
+impl From<&AnalysisAsset> for AnalysisAssetRow {
+    fn from(asset: &AnalysisAsset) -> Self {
+        Self {
+            analysis_id: asset.analysis_id.clone(),
+            ancestry_group: serde_name(&asset.ancestry_group),
+            asset_type: serde_name(&asset.asset_type),
+            sequencing_type: asset.sequencing_type.as_ref().map(serde_name),
+            uri: asset.uri.clone(),
+        }
+    }
+}
+
+impl AnalysisAssetRow {
+    /// Convert back to an asset; `None` for values this build doesn't know
+    fn into_asset(self) -> Option<AnalysisAsset> {
+        let sequencing_type = match &self.sequencing_type {
+            Some(name) => Some(from_serde_name(name)?),
+            None => None,
+        };
+        Some(AnalysisAsset {
+            ancestry_group: from_serde_name(&self.ancestry_group)?,
+            asset_type: from_serde_name(&self.asset_type)?,
+            sequencing_type,
+            analysis_id: self.analysis_id,
+            uri: self.uri,
+        })
+    }
+}
+
+/// Replace the `analysis_assets` snapshot with `assets`
+pub async fn save_to_clickhouse(sql: &SqlClient, assets: &AnalysisAssets) -> anyhow::Result<()> {
+    sql.execute(ANALYSIS_ASSETS_DDL).await?;
+    sql.execute_statement("TRUNCATE TABLE analysis_assets").await?;
+    let mut insert = sql.client().insert::<AnalysisAssetRow>("analysis_assets")?;
+    for asset in &assets.assets {
+        insert.write(&AnalysisAssetRow::from(asset)).await?;
+    }
+    insert
+        .end()
+        .await
+        .context("Failed to insert analysis assets")
+}
+
+/// Load the snapshot written by `discover --to-clickhouse`
+///
+/// Returns `None` when the table doesn't exist or is empty, so callers can
+/// fall back to scanning GCS.
+pub async fn load_from_clickhouse(
+    client: &clickhouse::Client,
+    executor: &QueryExecutor,
+) -> Result<Option<AnalysisAssets>, AppError> {
+    let query = r#"
+        SELECT analysis_id, ancestry_group, asset_type, sequencing_type, uri
+        FROM analysis_assets
+    "#;
+    let rows = match client
+        .query(query)
+        .fetch_all_with::<AnalysisAssetRow>(executor)
+        .await
+    {
+        Ok(rows) => rows,
+        // UNKNOWN_TABLE: assets were never written to ClickHouse
+        Err(AppError::UpstreamClickHouse(msg)) if msg.contains("Code: 60.") => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    if rows.is_empty() {
+        return Ok(None);
+    }
+
+    let total = rows.len();
+    let assets: Vec<AnalysisAsset> = rows
+        .into_iter()
+        .filter_map(AnalysisAssetRow::into_asset)
+        .collect();
+    if assets.len() < total {
+        warn!(
+            "Skipped {} analysis_assets rows with unknown ancestry, asset or sequencing type",
+            total - assets.len()
+        );
+    }
+    Ok(Some(AnalysisAssets {
+        assets,
+        ..Default::default()
+    }))
+}
+
 /// Load the set of valid phenotype names from the metadata
 /// This is used to filter the asset discovery to only known phenotypes
 pub fn get_valid_phenotypes(metadata: &[crate::models::AnalysisMetadata]) -> HashSet<String> {
@@ -306,6 +689,11 @@ pub fn get_valid_phenotypes(metadata: &[crate::models::AnalysisMetadata]) -> Has
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::factories::asset;
+
+    fn gene(analysis_id: &str) -> AnalysisAsset {
+        asset(analysis_id, AncestryGroup::Meta, AnalysisAssetType::Gene, None)
+    }
 
     #[test]
     fn test_normalize_analysis_id() {
@@ -313,4 +701,76 @@ mod tests {
         assert_eq!(normalize_analysis_id("height"), "height");
         assert_eq!(normalize_analysis_id("phenotype_S01AA"), "S01AA");
     }
+
+    #[test]
+    fn test_assets_diff() {
+        let old = AnalysisAssets { assets: vec![gene("height"), gene("bmi")], ..Default::default() };
+        let new = AnalysisAssets { assets: vec![gene("height"), gene("ldl")], ..Default::default() };
+        let diff = old.diff(&new);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].analysis_id, "ldl");
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].analysis_id, "bmi");
+        assert!(new.diff(&new).is_empty());
+    }
+
+    #[test]
+    fn test_asset_row_round_trip() {
+        let mut exome = asset(
+            "height",
+            AncestryGroup::Meta,
+            AnalysisAssetType::VariantExpP,
+            Some(SequencingType::Exomes),
+        );
+
+        let row = AnalysisAssetRow::from(&exome);
+        assert_eq!(row.ancestry_group, "meta");
+        assert_eq!(row.asset_type, "variant_exp_p");
+        assert_eq!(row.sequencing_type.as_deref(), Some("exomes"));
+
+        let back = row.into_asset().unwrap();
+        assert_eq!(back.asset_type, AnalysisAssetType::VariantExpP);
+        assert_eq!(back.sequencing_type, Some(SequencingType::Exomes));
+        assert_eq!(AnalysisAssetRow::from(&gene("bmi")).into_asset().unwrap().sequencing_type, None);
+
+        let mut unknown = AnalysisAssetRow::from(&exome);
+        unknown.asset_type = "polygenic_score".to_string();
+        assert!(unknown.into_asset().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_incremental_discovery_compares_listed_tables() {
+        let store = Arc::new(object_store::memory::InMemory::new());
+        let table = |filename: &str| {
+            ObjectPath::from(format!("414k/ht_results/META/phenotype_height/{}/_SUCCESS", filename))
+        };
+        store.put(&table("gene_results.ht"), "".into()).await.unwrap();
+        let worker = AssetDiscoveryWorker {
+            store: store.clone(),
+            bucket: "aou_results".to_string(),
+            base_prefix: "414k/ht_results".to_string(),
+            progress: None,
+        };
+        let known = HashMap::from([("height".to_string(), vec![gene("height")])]);
+
+        let (assets, counts) = worker.discover_for_ancestry(AncestryGroup::Meta, None, Some(&known)).await.unwrap();
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].uri, gene("height").uri);
+        assert_eq!((counts.unchanged, counts.changed), (1, 0));
+
+        // A table missing from the snapshot marks the prefix changed
+        store.put(&table("exome_credible_sets.ht"), "".into()).await.unwrap();
+        let (assets, counts) = worker.discover_for_ancestry(AncestryGroup::Meta, None, Some(&known)).await.unwrap();
+        assert_eq!(assets.len(), 2);
+        assert_eq!((counts.unchanged, counts.changed), (0, 1));
+    }
+
+    #[test]
+    fn test_previous_by_phenotype() {
+        let eur = asset("bmi", AncestryGroup::Eur, AnalysisAssetType::Gene, None);
+        let previous = AnalysisAssets { assets: vec![gene("height"), gene("height"), eur], ..Default::default() };
+        let meta = previous_by_phenotype(&previous, AncestryGroup::Meta);
+        assert_eq!(meta.len(), 1);
+        assert_eq!(meta["height"].len(), 2);
+    }
 }
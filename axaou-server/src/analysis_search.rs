@@ -0,0 +1,247 @@
+//! Fuzzy search over analysis metadata
+//!
+//! Backs `GET /api/analyses/search?q=` so the frontend no longer downloads all
+//! metadata records to search client-side. Matching is done in memory against
+//! the loaded metadata using token containment plus trigram similarity, which
+//! tolerates typos ("cholestrol") and word order ("ldl cholesterol").
+
+use crate::api::AppState;
+use crate::error::AppError;
+use crate::models::AnalysisMetadata;
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Results scoring below this are dropped
+const MIN_SCORE: f64 = 0.35;
+
+/// Query parameters for analysis search
+#[derive(Debug, Deserialize)]
+pub struct AnalysisSearchQuery {
+    /// Search text
+    pub q: String,
+    /// Restrict to one ancestry group; by default each analysis appears once
+    /// (preferring the meta-analysis record)
+    pub ancestry_group: Option<String>,
+    /// Maximum number of results (default: 20, max: 200)
+    pub limit: Option<usize>,
+}
+
+/// Ranked search hit
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisSearchResult {
+    pub analysis_id: String,
+    pub ancestry_group: String,
+    pub description: String,
+    pub category: String,
+    pub trait_type: String,
+    pub n_cases: i64,
+    pub n_controls: Option<i64>,
+    /// Relevance in [0, 1]
+    pub score: f64,
+}
+
+/// Lowercased alphanumeric tokens
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_string())
+        .collect()
+}
+
+/// Word trigrams, with each word padded as in pg_trgm ("  w", " wo", ..., "rd ")
+fn trigrams(text: &str) -> HashSet<[char; 3]> {
+    let mut grams = HashSet::new();
+    for token in tokenize(text) {
+        let padded: Vec<char> = "  ".chars().chain(token.chars()).chain(" ".chars()).collect();
+        for window in padded.windows(3) {
+            grams.insert([window[0], window[1], window[2]]);
+        }
+    }
+    grams
+}
+
+/// Fraction of the query's trigrams found in the target
+fn trigram_coverage(query: &HashSet<[char; 3]>, target: &HashSet<[char; 3]>) -> f64 {
+    if query.is_empty() {
+        return 0.0;
+    }
+    query.intersection(target).count() as f64 / query.len() as f64
+}
+
+/// Relevance of a metadata record for a query, in [0, 1]
+fn score(metadata: &AnalysisMetadata, query: &str, query_trigrams: &HashSet<[char; 3]>) -> f64 {
+    let query_lower = query.to_lowercase();
+    let id = metadata.analysis_id.to_lowercase();
+
+    if id == query_lower {
+        return 1.0;
+    }
+    if id.starts_with(&query_lower) {
+        return 0.95;
+    }
+
+    let haystack = format!(
+        "{} {} {}",
+        id,
+        metadata.description.to_lowercase(),
+        metadata.category.to_lowercase()
+    );
+
+    // Every query token appears somewhere: strong match, boosted for
+    // description prefixes
+    let tokens = tokenize(query);
+    let matched = tokens.iter().filter(|t| haystack.contains(t.as_str())).count();
+    let token_score = if tokens.is_empty() {
+        0.0
+    } else if matched == tokens.len() {
+        if metadata.description.to_lowercase().starts_with(&query_lower) {
+            0.9
+        } else {
+            0.8
+        }
+    } else {
+        0.6 * matched as f64 / tokens.len() as f64
+    };
+
+    let fuzzy_score = 0.7 * trigram_coverage(query_trigrams, &trigrams(&haystack));
+
+    token_score.max(fuzzy_score)
+}
+
+/// Rank metadata records against a query
+pub fn search(
+    metadata: &[AnalysisMetadata],
+    query: &str,
+    ancestry_group: Option<&str>,
+    limit: usize,
+) -> Vec<AnalysisSearchResult> {
+    let query_trigrams = trigrams(query);
+
+    let candidates: Vec<&AnalysisMetadata> = match ancestry_group {
+        Some(ancestry) => metadata
+            .iter()
+            .filter(|m| m.ancestry_group.eq_ignore_ascii_case(ancestry))
+            .collect(),
+        None => {
+            // One record per analysis, preferring the meta-analysis
+            let mut by_id: HashMap<&str, &AnalysisMetadata> = HashMap::new();
+            for m in metadata {
+                by_id
+                    .entry(m.analysis_id.as_str())
+                    .and_modify(|existing| {
+                        if m.ancestry_group.eq_ignore_ascii_case("meta") {
+                            *existing = m;
+                        }
+                    })
+                    .or_insert(m);
+            }
+            by_id.into_values().collect()
+        }
+    };
+
+    let mut results: Vec<AnalysisSearchResult> = candidates
+        .into_iter()
+        .filter_map(|m| {
+            let score = score(m, query, &query_trigrams);
+            (score >= MIN_SCORE).then(|| AnalysisSearchResult {
+                analysis_id: m.analysis_id.clone(),
+                ancestry_group: m.ancestry_group.clone(),
+                description: m.description.clone(),
+                category: m.category.clone(),
+                trait_type: m.trait_type.clone(),
+                n_cases: m.n_cases,
+                n_controls: m.n_controls,
+                score,
+            })
+        })
+        .collect();
+
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(b.n_cases.cmp(&a.n_cases))
+            .then(a.analysis_id.cmp(&b.analysis_id))
+    });
+    results.truncate(limit);
+    results
+}
+
+/// GET /api/analyses/search?q=
+///
+/// Fuzzy search over analysis ID, description and category, ranked by
+/// relevance and then by case count.
+pub async fn search_analyses(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AnalysisSearchQuery>,
+) -> Result<Json<Vec<AnalysisSearchResult>>, AppError> {
+    let q = params.q.trim();
+    if q.is_empty() {
+        return Err(AppError::BadRequest("Query parameter 'q' must not be empty".to_string()));
+    }
+    let limit = params.limit.unwrap_or(20).clamp(1, 200);
+
+    let metadata = state.metadata.read().await;
+    Ok(Json(search(&metadata, q, params.ancestry_group.as_deref(), limit)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::factories::analysis_metadata;
+
+    fn metadata(analysis_id: &str, ancestry: &str, description: &str, category: &str, n_cases: i64) -> AnalysisMetadata {
+        AnalysisMetadata {
+            description: description.to_string(),
+            category: category.to_string(),
+            ..analysis_metadata(analysis_id, ancestry, "continuous", n_cases)
+        }
+    }
+
+    fn records() -> Vec<AnalysisMetadata> {
+        vec![
+            metadata("height", "meta", "Height", "physical_measurement", 400_000),
+            metadata("height", "eur", "Height", "physical_measurement", 200_000),
+            metadata("3027114", "meta", "Cholesterol in LDL [Mass/volume]", "lab_measurement", 90_000),
+            metadata("3007070", "meta", "Cholesterol in HDL [Mass/volume]", "lab_measurement", 95_000),
+            metadata("E11", "meta", "Type 2 diabetes mellitus", "icd10", 30_000),
+        ]
+    }
+
+    #[test]
+    fn exact_id_ranks_first_and_dedupes_ancestries() {
+        let results = search(&records(), "height", None, 10);
+        assert_eq!(results[0].analysis_id, "height");
+        assert_eq!(results[0].ancestry_group, "meta");
+        assert_eq!(results.iter().filter(|r| r.analysis_id == "height").count(), 1);
+    }
+
+    #[test]
+    fn tokens_match_in_any_order() {
+        let results = search(&records(), "ldl cholesterol", None, 10);
+        assert_eq!(results[0].analysis_id, "3027114");
+    }
+
+    #[test]
+    fn tolerates_typos() {
+        let results = search(&records(), "cholestrol", None, 10);
+        assert!(results.len() >= 2);
+        assert!(results.iter().all(|r| r.description.starts_with("Cholesterol")));
+        // Ties broken by case count
+        assert_eq!(results[0].analysis_id, "3007070");
+    }
+
+    #[test]
+    fn ancestry_filter_and_no_match() {
+        let results = search(&records(), "height", Some("EUR"), 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].ancestry_group, "eur");
+        assert!(search(&records(), "zzzzqqq", None, 10).is_empty());
+    }
+}
@@ -1,19 +1,27 @@
 //! API route handlers for the AxAoU server
 
-use crate::error::AppError;
+use crate::clickhouse::QueryExt;
+use crate::error::{AppError, ErrorResponse};
 use crate::gene_queries::GeneQueryEngine;
+use crate::genes::gene_test::GeneTest;
+use crate::metadata::{MetadataClickHouse, MetadataFilter, MetadataSortField, SortOrder};
 use crate::models::{
     AnalysisAsset, AnalysisAssets, AnalysisDetail, AnalysisMetadata, AncestryGroup,
-    GeneAssociationResponse, GeneModel, GeneQueryParams, LoadedAnalysis,
+    GeneAssociationResponse, GeneModel, GeneQueryParams, LoadedAnalysis, Transcript,
+};
+use crate::response::{
+    CountResult, EffectMetadata, EffectScale, FieldSelection, FieldsQuery, FormatQuery, QueryTimer,
+    ResponseFormat,
 };
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     Json,
 };
 use serde::Deserialize;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use utoipa::{IntoParams, ToSchema};
 
 /// Application state shared across all handlers
 pub struct AppState {
@@ -25,47 +33,193 @@ pub struct AppState {
     pub gene_queries: GeneQueryEngine,
     /// ClickHouse client for variant queries
     pub clickhouse: clickhouse::Client,
+    /// Timeout/retry/circuit-breaker policy for ClickHouse queries
+    pub executor: Arc<crate::clickhouse::executor::QueryExecutor>,
     /// Hail Table client for slow-path queries (directly from GCS)
     pub hail_client: genohype_core::genomic::HailClient,
     /// In-memory cache for Manhattan plot data, images, and API JSON responses
     pub api_cache: moka::future::Cache<String, Vec<u8>>,
+    /// Identical expensive queries in flight, keyed by `api_cache` key
+    pub inflight: crate::single_flight::SingleFlight<Vec<u8>>,
+    /// Disk cache of plot PNGs proxied from GCS (`plot_delivery.cache_dir`)
+    pub plot_cache: Option<crate::phenotype::plot_cache::PlotDiskCache>,
+    /// Disk cache of server-rendered plot PNGs (`render_cache_dir`)
+    pub render_cache: Option<crate::phenotype::plot_cache::PlotDiskCache>,
     /// Current data version string extracted from config
     pub data_version: Option<String>,
+    /// Dataset bucket/path configuration
+    pub config: Arc<crate::config::Config>,
+    /// Set once analysis metadata has been loaded (drives the readiness probe)
+    pub metadata_loaded: std::sync::atomic::AtomicBool,
+    /// Background admin tasks (asset re-discovery, ingest) and their progress
+    pub tasks: crate::admin::tasks::TaskRegistry,
+    /// GRCh37 <-> GRCh38 chain maps loaded from `config.liftover`
+    pub liftover: crate::liftover::Liftover,
+    /// Gene model lookups, backend chosen with `--gene-models-backend`
+    pub gene_models: Arc<dyn crate::gene_models::GeneModelBackend>,
+    /// Latest row counts and modification times of ClickHouse tables (`/metrics`)
+    pub table_stats: crate::table_metrics::TableStatsCache,
 }
 
 /// Query parameters for the /api/analyses endpoint
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct AnalysisQuery {
     /// Filter by ancestry group (case-insensitive)
     /// e.g., "meta", "EUR", "AFR", etc.
     pub ancestry_group: Option<String>,
+    /// Filter by category (exact match)
+    pub category: Option<String>,
+    /// Filter by trait type, e.g. "continuous" or "categorical"
+    pub trait_type: Option<String>,
+    /// Minimum number of cases
+    pub min_n_cases: Option<i64>,
+    /// Only analyses with (true) or without (false) gene burden results
+    pub has_gene_results: Option<bool>,
+    /// Only analyses with (true) or without (false) a heritability estimate
+    pub has_heritability: Option<bool>,
+    /// Sort field (default: analysis_id)
+    pub sort_by: Option<MetadataSortField>,
+    /// Sort direction, "asc" or "desc" (default: asc)
+    pub order: Option<SortOrder>,
+    /// Maximum number of records to return (default: all)
+    pub limit: Option<u64>,
+    /// Number of records to skip (default: 0)
+    pub offset: Option<u64>,
+}
+
+impl AnalysisQuery {
+    fn to_filter(&self) -> MetadataFilter {
+        MetadataFilter {
+            ancestry_group: self.ancestry_group.clone(),
+            category: self.category.clone(),
+            trait_type: self.trait_type.clone(),
+            min_n_cases: self.min_n_cases,
+            has_gene_results: self.has_gene_results,
+            has_heritability: self.has_heritability,
+            sort_by: self.sort_by,
+            descending: self.order == Some(SortOrder::Desc),
+            limit: self.limit,
+            offset: self.offset,
+        }
+    }
 }
 
 /// Handler for GET /api/analyses
 ///
-/// Returns all analysis metadata, optionally filtered by ancestry_group.
+/// Returns analysis metadata, optionally filtered (ancestry_group, category,
+/// trait_type, min_n_cases, has_gene_results, has_heritability), sorted and paginated.
 /// The frontend typically requests `?ancestry_group=meta` to get meta-analysis results.
+/// Filtering runs in ClickHouse; if the query fails the in-memory copy loaded at
+/// startup is filtered instead, except for `has_gene_results`, which needs the
+/// `gene_associations` table and fails with the query.
+#[utoipa::path(
+    get,
+    path = "/api/analyses",
+    tag = "analyses",
+    params(AnalysisQuery),
+    responses((status = 200, description = "Analysis metadata", body = Vec<AnalysisMetadata>))
+)]
 pub async fn get_analyses(
     State(state): State<Arc<AppState>>,
     Query(params): Query<AnalysisQuery>,
-) -> (StatusCode, Json<Vec<AnalysisMetadata>>) {
-    let metadata = state.metadata.read().await;
-    let filtered_data: Vec<AnalysisMetadata> = if let Some(ref ancestry) = params.ancestry_group {
-        metadata
-            .iter()
-            .filter(|m| m.ancestry_group.eq_ignore_ascii_case(ancestry))
-            .cloned()
-            .collect()
-    } else {
-        metadata.clone()
+) -> Result<axum::response::Response, AppError> {
+    let filter = params.to_filter();
+    let dv = state.data_version.as_deref().unwrap_or("none");
+    let cache_key = format!("analyses:{}:{}", filter.cache_key(), dv);
+
+    if let Some(cached_bytes) = state.api_cache.get(&cache_key).await {
+        return Ok(json_response(cached_bytes));
+    }
+
+    let json_bytes = match MetadataClickHouse::new(&state.clickhouse, &state.executor).query(&filter).await {
+        Ok(rows) => {
+            let bytes = serde_json::to_vec(&rows)
+                .map_err(|e| AppError::Internal(e.to_string()))?;
+            state.api_cache.insert(cache_key, bytes.clone()).await;
+            bytes
+        }
+        Err(e) if filter.requires_clickhouse() => return Err(e),
+        Err(e) => {
+            tracing::warn!("Metadata query failed, serving from memory: {}", e);
+            let metadata = state.metadata.read().await;
+            serde_json::to_vec(&filter.apply(&metadata))
+                .map_err(|e| AppError::Internal(e.to_string()))?
+        }
     };
 
-    (StatusCode::OK, Json(filtered_data))
+    Ok(json_response(json_bytes))
 }
 
-/// Application configuration returned to the frontend
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct AxaouConfig {
+/// Query parameters for the /api/analyses/heritability endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HeritabilityQuery {
+    /// Ancestry group (default: "meta")
+    pub ancestry_group: Option<String>,
+    /// Filter by category (exact match)
+    pub category: Option<String>,
+    /// Filter by trait type, e.g. "continuous" or "binary"
+    pub trait_type: Option<String>,
+    /// Minimum number of cases
+    pub min_n_cases: Option<i64>,
+    /// Maximum number of records to return (default: 100, max: 1000)
+    pub limit: Option<u64>,
+    /// Number of records to skip (default: 0)
+    pub offset: Option<u64>,
+}
+
+/// Handler for GET /api/analyses/heritability
+///
+/// Analyses with a heritability estimate, most heritable first. Uses the
+/// same ClickHouse query and in-memory fallback as `/api/analyses`.
+#[utoipa::path(
+    get,
+    path = "/api/analyses/heritability",
+    tag = "analyses",
+    params(HeritabilityQuery),
+    responses((status = 200, description = "Analyses ranked by heritability", body = Vec<AnalysisMetadata>))
+)]
+pub async fn get_heritability_ranking(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HeritabilityQuery>,
+) -> Result<Json<Vec<AnalysisMetadata>>, AppError> {
+    let filter = MetadataFilter {
+        ancestry_group: Some(params.ancestry_group.unwrap_or_else(|| "meta".to_string())),
+        category: params.category,
+        trait_type: params.trait_type,
+        min_n_cases: params.min_n_cases,
+        has_heritability: Some(true),
+        sort_by: Some(MetadataSortField::Heritability),
+        descending: true,
+        limit: Some(params.limit.unwrap_or(100).clamp(1, 1000)),
+        offset: params.offset,
+        ..Default::default()
+    };
+
+    match MetadataClickHouse::new(&state.clickhouse, &state.executor).query(&filter).await {
+        Ok(rows) => Ok(Json(rows)),
+        Err(e) => {
+            tracing::warn!("Heritability query failed, serving from memory: {}", e);
+            let metadata = state.metadata.read().await;
+            Ok(Json(filter.apply(&metadata)))
+        }
+    }
+}
+
+fn json_response(bytes: Vec<u8>) -> axum::response::Response {
+    axum::response::Response::builder()
+        .status(StatusCode::OK)
+        .header(axum::http::header::CONTENT_TYPE, "application/json")
+        .body(axum::body::Body::from(bytes))
+        .unwrap()
+}
+
+/// `[frontend]` config section: burden sets, thresholds and test fixtures
+/// served by `/api/config`. Defaults match the v8/414k release.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct FrontendConfig {
     pub ancestry_codes: Vec<String>,
     pub burden_sets: Vec<String>,
     pub burden_pvalue_fields: Vec<String>,
@@ -77,6 +231,46 @@ pub struct AxaouConfig {
     pub test_intervals: Vec<String>,
     pub variant_pvalue_threshold: f64,
     pub top_gene_associations_threshold: f64,
+    /// Significance line drawn on variant Manhattan plots
+    pub genome_wide_threshold: f64,
+    /// Significance line drawn on gene burden plots
+    pub gene_burden_threshold: f64,
+}
+
+impl Default for FrontendConfig {
+    fn default() -> Self {
+        let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect();
+        Self {
+            ancestry_codes: strings(&["afr", "amr", "eas", "eur", "mid", "sas", "meta"]),
+            burden_sets: strings(&["pLoF", "missenseLC", "synonymous"]),
+            burden_pvalue_fields: strings(&["pvalue", "pvalue_burden", "pvalue_skat"]),
+            default_max_maf: "0.001".to_string(),
+            reference_genome: "GRCh38".to_string(),
+            test_analyses: strings(&["height"]),
+            test_ancestry_codes: strings(&["eur", "meta"]),
+            test_gene_symbols: strings(&["FGFR2", "GDF5", "SHOX"]),
+            test_intervals: strings(&[
+                "chr10:121478332-121598458",
+                "chr20:35433347-35454746",
+                "chrX:624344-659411",
+            ]),
+            variant_pvalue_threshold: 1.0,
+            top_gene_associations_threshold: 1e-6,
+            genome_wide_threshold: 5e-8,
+            gene_burden_threshold: 2.5e-6,
+        }
+    }
+}
+
+/// Application configuration returned to the frontend
+#[derive(Debug, Clone, serde::Serialize, ToSchema)]
+pub struct AxaouConfig {
+    #[serde(flatten)]
+    pub frontend: FrontendConfig,
+    /// Data freeze identifier of the dataset (e.g., "414k")
+    pub dataset_version: String,
+    /// Release date of the data freeze, when configured
+    pub data_freeze_date: Option<String>,
     pub data_version: Option<String>,
 }
 
@@ -138,50 +332,25 @@ pub fn extract_data_version() -> Option<String> {
 
 /// Handler for GET /api/config
 ///
-/// Returns static application configuration for the frontend.
-pub async fn get_config() -> Json<AxaouConfig> {
+/// Returns the dataset's `[frontend]` configuration with its version and
+/// data freeze date.
+#[utoipa::path(
+    get,
+    path = "/api/config",
+    tag = "config",
+    responses((status = 200, description = "Frontend configuration", body = AxaouConfig))
+)]
+pub async fn get_config(State(state): State<Arc<AppState>>) -> Json<AxaouConfig> {
     Json(AxaouConfig {
-        ancestry_codes: vec![
-            "afr".to_string(),
-            "amr".to_string(),
-            "eas".to_string(),
-            "eur".to_string(),
-            "mid".to_string(),
-            "sas".to_string(),
-            "meta".to_string(),
-        ],
-        burden_sets: vec![
-            "pLoF".to_string(),
-            "missenseLC".to_string(),
-            "synonymous".to_string(),
-        ],
-        burden_pvalue_fields: vec![
-            "pvalue".to_string(),
-            "pvalue_burden".to_string(),
-            "pvalue_skat".to_string(),
-        ],
-        default_max_maf: "0.001".to_string(),
-        reference_genome: "GRCh38".to_string(),
-        test_analyses: vec!["height".to_string()],
-        test_ancestry_codes: vec!["eur".to_string(), "meta".to_string()],
-        test_gene_symbols: vec![
-            "FGFR2".to_string(),
-            "GDF5".to_string(),
-            "SHOX".to_string(),
-        ],
-        test_intervals: vec![
-            "chr10:121478332-121598458".to_string(),
-            "chr20:35433347-35454746".to_string(),
-            "chrX:624344-659411".to_string(),
-        ],
-        variant_pvalue_threshold: 1.0,
-        top_gene_associations_threshold: 1e-6,
-        data_version: extract_data_version(),
+        frontend: state.config.frontend.clone(),
+        dataset_version: state.config.dataset_version.clone(),
+        data_freeze_date: state.config.data_freeze_date.clone(),
+        data_version: state.data_version.clone(),
     })
 }
 
 /// Category summary derived from analysis metadata
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, ToSchema)]
 pub struct AnalysisCategory {
     pub category: String,
     pub color: String,
@@ -223,6 +392,12 @@ const TABLEAU_20: &[&str] = &[
 ///
 /// Returns category summaries derived from analysis metadata.
 /// Each category includes the list of analyses and counts.
+#[utoipa::path(
+    get,
+    path = "/api/categories",
+    tag = "analyses",
+    responses((status = 200, description = "Phenotype categories", body = Vec<AnalysisCategory>))
+)]
 pub async fn get_categories(
     State(state): State<Arc<AppState>>,
 ) -> Json<Vec<AnalysisCategory>> {
@@ -257,19 +432,70 @@ pub async fn get_categories(
         })
         .collect();
 
-    // Sort by category name, then assign colors sequentially for guaranteed uniqueness
+    // Sort by category name, then assign colors from the shared palette mapping
     categories.sort_by(|a, b| a.category.cmp(&b.category));
-    for (i, cat) in categories.iter_mut().enumerate() {
-        cat.color = TABLEAU_20[i % TABLEAU_20.len()].to_string();
+    let colors = category_color_map(&metadata);
+    for cat in categories.iter_mut() {
+        cat.color = colors.get(&cat.category).cloned().unwrap_or_default();
     }
 
     Json(categories)
 }
 
+/// Map each metadata category to its palette color.
+///
+/// Categories are sorted by name and assigned colors sequentially, so every
+/// endpoint that colors by category agrees with `/api/categories`.
+pub fn category_color_map(
+    metadata: &[AnalysisMetadata],
+) -> std::collections::HashMap<String, String> {
+    let mut names: Vec<&str> = metadata.iter().map(|m| m.category.as_str()).collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| (name.to_string(), TABLEAU_20[i % TABLEAU_20.len()].to_string()))
+        .collect()
+}
+
+/// Effect-size metadata of an analysis from its `trait_type`
+///
+/// Prefers the record for `ancestry`, since trait types are shared across
+/// ancestries. None for unknown analyses; `NotReady` until metadata has
+/// loaded, so the effect unit is never silently dropped.
+pub async fn effect_metadata(
+    state: &AppState,
+    analysis_id: &str,
+    ancestry: &str,
+    scale: EffectScale,
+) -> Result<Option<EffectMetadata>, AppError> {
+    if !state.metadata_loaded.load(std::sync::atomic::Ordering::Acquire) {
+        return Err(AppError::NotReady("Analysis metadata is still loading".to_string()));
+    }
+    let metadata = state.metadata.read().await;
+    let mut records = metadata.iter().filter(|m| m.analysis_id == analysis_id);
+    let record = records
+        .clone()
+        .find(|m| m.ancestry_group.eq_ignore_ascii_case(ancestry))
+        .or_else(|| records.next());
+    Ok(record.map(|r| EffectMetadata::new(&r.trait_type, scale)))
+}
+
 /// Handler for GET /api/analyses/:analysis_id
 ///
 /// Returns a single analysis metadata record by its ID (wrapped in array for frontend compatibility).
 /// Filters by ancestry_group query parameter; defaults to "meta" if not provided.
+#[utoipa::path(
+    get,
+    path = "/api/analyses/{analysis_id}",
+    tag = "analyses",
+    params(("analysis_id" = String, Path, description = "Analysis (phenotype) ID"), AnalysisQuery),
+    responses(
+        (status = 200, description = "Matching analysis metadata", body = Vec<AnalysisMetadata>),
+        (status = 404, description = "Analysis not found", body = ErrorResponse)
+    )
+)]
 pub async fn get_analysis_by_id(
     State(state): State<Arc<AppState>>,
     Path(analysis_id): Path<String>,
@@ -300,15 +526,25 @@ pub async fn get_analysis_by_id(
 /// Handler for GET /api/genes/model/{gene_id}
 ///
 /// Returns the gene model for a specific gene ID (e.g., "ENSG00000139618").
+/// `fields=` trims the payload, e.g. "gene_id,symbol,chrom,start,stop".
+#[utoipa::path(
+    get,
+    path = "/api/genes/model/{gene_id}",
+    tag = "genes",
+    params(("gene_id" = String, Path, description = "Ensembl gene ID"), FieldsQuery),
+    responses(
+        (status = 200, description = "Gene model", body = Vec<GeneModel>),
+        (status = 404, description = "Gene not found", body = ErrorResponse)
+    )
+)]
 pub async fn get_gene_model(
     State(state): State<Arc<AppState>>,
     Path(gene_id): Path<String>,
-) -> Result<Json<Vec<GeneModel>>, AppError> {
-    // Use ClickHouse for fast queries
-    let gene_models = crate::gene_models::GeneModelsClickHouse::new(state.clickhouse.clone());
-
-    match gene_models.get_by_gene_id(&gene_id).await? {
-        Some(model) => Ok(Json(vec![model])),
+    Query(params): Query<FieldsQuery>,
+) -> Result<Json<Vec<serde_json::Value>>, AppError> {
+    let selection = FieldSelection::parse(params.fields.as_deref());
+    match state.gene_models.get_by_gene_id(&gene_id).await? {
+        Some(model) => Ok(Json(vec![selection.project(&model)?])),
         None => Err(AppError::NotFound(format!("Gene not found"))),
     }
 }
@@ -316,16 +552,147 @@ pub async fn get_gene_model(
 /// Handler for GET /api/genes/model/interval/{interval}
 ///
 /// Returns all gene models within a genomic interval.
-/// Interval format: "chr1:12345-67890" or "1:12345-67890"
+/// Interval format: "chr1:12345-67890" or "1:12345-67890", or any form accepted by
+/// `genomics::parse_region` (gene symbol, ENSG ID, position +/- flank, chromosome)
+/// `fields=` selects a subset of each model, as for `/genes/model/{gene_id}`.
+/// A `.gtf` suffix on the interval (`chr1:12345-67890.gtf`) returns the gene,
+/// transcript, exon, CDS and UTR records as GTF instead.
+#[utoipa::path(
+    get,
+    path = "/api/genes/model/interval/{interval}",
+    tag = "genes",
+    params(
+        ("interval" = String, Path, description = "Genomic interval, e.g. chr1:12345-67890; append .gtf for GTF"),
+        FieldsQuery
+    ),
+    responses(
+        (status = 200, description = "Gene models overlapping the interval (GTF text for .gtf)", body = Vec<GeneModel>),
+        (status = 400, description = "Malformed interval", body = ErrorResponse)
+    )
+)]
 pub async fn get_gene_models_in_interval(
     State(state): State<Arc<AppState>>,
     Path(interval): Path<String>,
-) -> Result<Json<Vec<GeneModel>>, AppError> {
-    // Use ClickHouse for fast queries
-    let gene_models = crate::gene_models::GeneModelsClickHouse::new(state.clickhouse.clone());
+    Query(params): Query<FieldsQuery>,
+) -> Result<axum::response::Response, AppError> {
+    use axum::response::IntoResponse;
+
+    if let Some(interval) = interval.strip_suffix(".gtf") {
+        let region = crate::genomics::resolve_region(state.gene_models.as_ref(), interval).await?;
+        let genes = state.gene_models.get_in_interval(&region.to_string()).await?;
+        let filename = format!("genes_{}.gtf", region.to_string().replace([':', '-'], "_"));
+        return Ok((
+            [
+                (
+                    axum::http::header::CONTENT_TYPE,
+                    "text/plain; charset=utf-8".to_string(),
+                ),
+                (
+                    axum::http::header::CONTENT_DISPOSITION,
+                    format!("inline; filename=\"{}\"", filename),
+                ),
+            ],
+            crate::genes::gtf::render_gtf(&genes),
+        )
+            .into_response());
+    }
+
+    let selection = FieldSelection::parse(params.fields.as_deref());
+    let region = crate::genomics::resolve_region(state.gene_models.as_ref(), &interval).await?;
+    let genes = state.gene_models.get_in_interval(&region.to_string()).await?;
+    Ok(Json(selection.project_all(&genes)?).into_response())
+}
+
+/// Query parameters for the /api/genes/model/search endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GeneModelSearchQuery {
+    /// Symbol, alias, previous symbol or Ensembl ID (prefixes match)
+    pub q: String,
+    /// Maximum number of gene models (default 10, max 50)
+    pub limit: Option<u32>,
+    /// Comma-separated fields to return, see [`FieldSelection`]
+    pub fields: Option<String>,
+}
+
+/// Handler for GET /api/genes/model/search
+///
+/// Returns full gene models matching `q` on symbol, alias symbols, previous
+/// symbols, search terms or Ensembl ID, so retired symbols (e.g. C9orf72's
+/// aliases) still resolve. Use `fields=` to trim the payload.
+#[utoipa::path(
+    get,
+    path = "/api/genes/model/search",
+    tag = "genes",
+    params(GeneModelSearchQuery),
+    responses(
+        (status = 200, description = "Matching gene models, best match first", body = Vec<GeneModel>),
+        (status = 400, description = "Empty query", body = ErrorResponse)
+    )
+)]
+pub async fn search_gene_models(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<GeneModelSearchQuery>,
+) -> Result<Json<Vec<serde_json::Value>>, AppError> {
+    if params.q.trim().is_empty() {
+        return Err(AppError::BadRequest("Query parameter 'q' must not be empty".to_string()));
+    }
+    let limit = params.limit.unwrap_or(10).clamp(1, 50);
+    let selection = FieldSelection::parse(params.fields.as_deref());
+    let genes = state.gene_models.search(&params.q, limit).await?;
+    Ok(Json(selection.project_all(&genes)?))
+}
 
-    let genes = gene_models.get_in_interval(&interval).await?;
-    Ok(Json(genes))
+/// Handler for GET /api/genes/model/{gene_id}/transcripts
+///
+/// Returns the transcripts of a gene with their exon structure, without the
+/// rest of the gene model.
+#[utoipa::path(
+    get,
+    path = "/api/genes/model/{gene_id}/transcripts",
+    tag = "genes",
+    params(("gene_id" = String, Path, description = "Ensembl gene ID"), FieldsQuery),
+    responses(
+        (status = 200, description = "Transcripts of the gene", body = Vec<Transcript>),
+        (status = 404, description = "Gene not found", body = ErrorResponse)
+    )
+)]
+pub async fn get_gene_transcripts(
+    State(state): State<Arc<AppState>>,
+    Path(gene_id): Path<String>,
+    Query(params): Query<FieldsQuery>,
+) -> Result<Json<Vec<serde_json::Value>>, AppError> {
+    let selection = FieldSelection::parse(params.fields.as_deref());
+    match state.gene_models.get_transcripts(&gene_id).await? {
+        Some(transcripts) => Ok(Json(selection.project_all(&transcripts)?)),
+        None => Err(AppError::NotFound(format!("Gene '{}' not found", gene_id))),
+    }
+}
+
+/// Handler for GET /api/transcripts/{transcript_id}
+///
+/// Returns a single transcript with its exons. The ID may include a version
+/// ("ENST00000380152.8"), in which case the version must match.
+#[utoipa::path(
+    get,
+    path = "/api/transcripts/{transcript_id}",
+    tag = "genes",
+    params(("transcript_id" = String, Path, description = "Ensembl transcript ID"), FieldsQuery),
+    responses(
+        (status = 200, description = "Transcript", body = Transcript),
+        (status = 404, description = "Transcript not found", body = ErrorResponse)
+    )
+)]
+pub async fn get_transcript(
+    State(state): State<Arc<AppState>>,
+    Path(transcript_id): Path<String>,
+    Query(params): Query<FieldsQuery>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let selection = FieldSelection::parse(params.fields.as_deref());
+    match state.gene_models.get_transcript(&transcript_id).await? {
+        Some(transcript) => Ok(Json(selection.project(&transcript)?)),
+        None => Err(AppError::NotFound(format!("Transcript '{}' not found", transcript_id))),
+    }
 }
 
 // ============================================================================
@@ -343,41 +710,18 @@ pub struct AssetsQuery {
     pub sequencing_type: Option<String>,
     /// Filter by analysis ID (phenotype name)
     pub analysis_id: Option<String>,
-    /// Force refresh of cached assets
-    #[serde(default)]
-    pub refresh: bool,
 }
 
 /// Handler for GET /api/assets
 ///
 /// Returns discovered analysis assets (per-phenotype result files).
-/// Assets are lazily discovered on first request and cached.
+/// Assets are lazily discovered on first request and cached; re-discovery
+/// is an admin operation (`POST /api/admin/assets/refresh`).
 pub async fn get_assets(
     State(state): State<Arc<AppState>>,
     Query(params): Query<AssetsQuery>,
 ) -> Result<Json<Vec<AnalysisAsset>>, AppError> {
-    // Check if we need to discover assets
-    let needs_discovery = {
-        let assets = state.assets.read().await;
-        assets.is_none() || params.refresh
-    };
-
-    if needs_discovery {
-        // Perform discovery
-        tracing::info!("Discovering analysis assets from GCS...");
-        let discovery = crate::analysis_assets::AssetDiscovery::new()?;
-
-        // Get valid phenotypes from metadata for filtering
-        let metadata = state.metadata.read().await;
-        let valid_phenotypes = crate::analysis_assets::get_valid_phenotypes(&metadata);
-
-        let discovered = discovery.discover_all(Some(&valid_phenotypes)).await?;
-        tracing::info!("Discovered {} assets", discovered.assets.len());
-
-        // Cache the results
-        let mut assets_lock = state.assets.write().await;
-        *assets_lock = Some(discovered);
-    }
+    ensure_assets_loaded(&state).await?;
 
     // Read from cache and filter
     let assets = state.assets.read().await;
@@ -429,22 +773,7 @@ pub async fn get_assets(
 pub async fn get_assets_summary(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<AssetsSummary>, AppError> {
-    // Ensure assets are loaded
-    let needs_discovery = {
-        let assets = state.assets.read().await;
-        assets.is_none()
-    };
-
-    if needs_discovery {
-        tracing::info!("Discovering analysis assets from GCS for summary...");
-        let discovery = crate::analysis_assets::AssetDiscovery::new()?;
-        let metadata = state.metadata.read().await;
-        let valid_phenotypes = crate::analysis_assets::get_valid_phenotypes(&metadata);
-        let discovered = discovery.discover_all(Some(&valid_phenotypes)).await?;
-
-        let mut assets_lock = state.assets.write().await;
-        *assets_lock = Some(discovered);
-    }
+    ensure_assets_loaded(&state).await?;
 
     let assets = state.assets.read().await;
     let assets_ref = assets.as_ref().unwrap();
@@ -502,12 +831,15 @@ pub struct AssetsSummary {
 /// Query parameters for the gene association endpoints
 #[derive(Debug, Deserialize)]
 pub struct GeneAssocQuery {
-    /// Filter by ancestry group (default: "meta")
+    /// Filter by ancestry group (default: "meta"; "all" for every group)
     pub ancestry: Option<String>,
     /// Filter by annotation type (e.g., "pLoF", "missenseLC")
     pub annotation: Option<String>,
     /// Filter by max MAF (default: 0.001)
     pub max_maf: Option<f64>,
+    /// "mask" (default), "cauchy" or "all"
+    #[serde(default)]
+    pub test: GeneTest,
 }
 
 impl GeneAssocQuery {
@@ -517,8 +849,13 @@ impl GeneAssocQuery {
                 .ancestry
                 .as_ref()
                 .and_then(|s| AncestryGroup::from_dir_name(s)),
+            all_ancestries: self
+                .ancestry
+                .as_deref()
+                .is_some_and(|s| s.eq_ignore_ascii_case("all")),
             annotation: self.annotation.clone(),
             max_maf: self.max_maf,
+            test: self.test,
         }
     }
 }
@@ -532,12 +869,50 @@ pub struct GeneListQuery {
     pub annotation: Option<String>,
     /// Filter by max MAF (default: 0.001)
     pub max_maf: Option<f64>,
+    /// "mask" (default), "cauchy" or "all"
+    #[serde(default)]
+    pub test: GeneTest,
     /// Maximum number of results to return (default: 1000)
     pub limit: Option<usize>,
     /// Number of results to skip (default: 0)
     pub offset: Option<usize>,
 }
 
+impl GeneListQuery {
+    /// `max_maf` condition for the selected test, defaulting the mask MAF to 0.001
+    fn test_sql(&self) -> (&'static str, Option<f64>) {
+        self.test.sql(Some(self.max_maf.unwrap_or(0.001)))
+    }
+
+    /// WHERE clause shared by the gene list and count endpoints
+    fn filter(&self) -> String {
+        let (test, _) = self.test_sql();
+        if self.annotation.is_some() {
+            format!("phenotype = ? AND ancestry = ? AND {} AND annotation = ?", test)
+        } else {
+            format!("phenotype = ? AND ancestry = ? AND {}", test)
+        }
+    }
+
+    /// Bind the parameters of [`Self::filter`]
+    fn bind_filter(
+        &self,
+        query: clickhouse::query::Query,
+        analysis_id: &str,
+    ) -> clickhouse::query::Query {
+        let mut query = query
+            .bind(analysis_id)
+            .bind(self.ancestry.as_deref().unwrap_or("meta"));
+        if let (_, Some(max_maf)) = self.test_sql() {
+            query = query.bind(max_maf);
+        }
+        match &self.annotation {
+            Some(annotation) => query.bind(annotation),
+            None => query,
+        }
+    }
+}
+
 impl GeneListQuery {
     fn to_params(&self) -> GeneQueryParams {
         GeneQueryParams {
@@ -545,8 +920,10 @@ impl GeneListQuery {
                 .ancestry
                 .as_ref()
                 .and_then(|s| AncestryGroup::from_dir_name(s)),
+            all_ancestries: false,
             annotation: self.annotation.clone(),
             max_maf: self.max_maf,
+            test: self.test,
         }
     }
 }
@@ -574,71 +951,125 @@ pub async fn get_gene_associations(
 /// Handler for GET /api/phenotype/{analysis_id}/genes
 ///
 /// Returns all gene association results for a phenotype.
-/// Uses ClickHouse for fast queries (milliseconds vs 30s with Hail Tables).
+/// Uses ClickHouse for fast queries (milliseconds vs 30s with Hail Tables);
+/// phenotypes not yet in `gene_associations` fall back to scanning their
+/// gene_results.ht, with the same filters, ordering and pagination.
 /// Useful for building gene-level Manhattan plots or tables.
+/// `test=cauchy|all` returns the Cauchy combination rows instead of, or
+/// alongside, the `max_maf` mask results.
+/// `format=arrow|parquet` (or the matching `Accept`) returns the rows as an
+/// Arrow IPC stream or Parquet file.
 pub async fn list_gene_associations(
     State(state): State<Arc<AppState>>,
     Path(analysis_id): Path<String>,
     Query(params): Query<GeneListQuery>,
-) -> Result<Json<Vec<crate::models::GeneAssociationApi>>, AppError> {
-    let ancestry = params.ancestry.clone().unwrap_or_else(|| "meta".to_string());
-    // Default to 0.001 if no max_maf provided
-    let max_maf = params.max_maf.unwrap_or(0.001);
-
+    Query(format): Query<FormatQuery>,
+    headers: HeaderMap,
+) -> Result<axum::response::Response, AppError> {
+    let format = ResponseFormat::negotiate(format.format, &headers);
     // Set a high limit so we get all points for the Manhattan plot instead of capping at 1000
-    let limit = params.limit.unwrap_or(50000) as u64;
+    let limit = params.limit.unwrap_or(50000);
+    let offset = params.offset.unwrap_or(0);
 
-    // Build query with optional annotation filter
-    let base_query = if params.annotation.is_some() {
-        r#"
-        SELECT gene_id, gene_symbol, annotation, max_maf, phenotype, ancestry,
-               pvalue, pvalue_burden, pvalue_skat, beta_burden, mac,
-               contig, gene_start_position, xpos
-        FROM gene_associations
-        WHERE phenotype = ? AND ancestry = ? AND max_maf = ? AND annotation = ?
-        ORDER BY pvalue ASC
-        LIMIT ?
-        "#
-    } else {
+    let query = format!(
         r#"
         SELECT gene_id, gene_symbol, annotation, max_maf, phenotype, ancestry,
                pvalue, pvalue_burden, pvalue_skat, beta_burden, mac,
                contig, gene_start_position, xpos
         FROM gene_associations
-        WHERE phenotype = ? AND ancestry = ? AND max_maf = ?
-        ORDER BY pvalue ASC
-        LIMIT ?
-        "#
+        WHERE {}
+        ORDER BY pvalue ASC, gene_id, annotation, max_maf
+        LIMIT ? OFFSET ?
+        "#,
+        params.filter()
+    );
+
+    let rows = params
+        .bind_filter(state.clickhouse.query(&query), &analysis_id)
+        .bind(limit as u64)
+        .bind(offset as u64)
+        .fetch_all_with::<crate::clickhouse::models::GeneAssociationRow>(&state.executor)
+        .await;
+
+    let rows = match rows {
+        Ok(rows) => Some(rows),
+        // UNKNOWN_TABLE: nothing ingested yet
+        Err(AppError::UpstreamClickHouse(msg)) if msg.contains("Code: 60.") => None,
+        Err(e) => return Err(e),
+    };
+    let in_clickhouse = match &rows {
+        Some(rows) => !rows.is_empty() || gene_associations_has_phenotype(&state, &analysis_id).await?,
+        None => false,
     };
 
-    let rows = if let Some(ref annotation) = params.annotation {
-        state
-            .clickhouse
-            .query(base_query)
-            .bind(&analysis_id)
-            .bind(&ancestry)
-            .bind(max_maf)
-            .bind(annotation)
-            .bind(limit)
-            .fetch_all::<crate::clickhouse::models::GeneAssociationRow>()
-            .await
-            .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?
+    let api_rows: Vec<crate::models::GeneAssociationApi> = if in_clickhouse {
+        rows.unwrap_or_default().into_iter().map(|r| r.to_api()).collect()
     } else {
-        state
-            .clickhouse
-            .query(base_query)
-            .bind(&analysis_id)
-            .bind(&ancestry)
-            .bind(max_maf)
-            .bind(limit)
-            .fetch_all::<crate::clickhouse::models::GeneAssociationRow>()
-            .await
-            .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?
+        list_gene_associations_from_hail(&state, &analysis_id, &params, limit, offset).await?
     };
+    format.rows_response(api_rows)
+}
+
+/// Whether `gene_associations` holds any rows for the phenotype
+async fn gene_associations_has_phenotype(
+    state: &AppState,
+    analysis_id: &str,
+) -> Result<bool, AppError> {
+    let found = state
+        .clickhouse
+        .query("SELECT 1 FROM gene_associations WHERE phenotype = ? LIMIT 1")
+        .bind(analysis_id)
+        .fetch_optional_with::<u8>(&state.executor)
+        .await?;
+    Ok(found.is_some())
+}
+
+/// Deprecated Hail Table scan for phenotypes missing from `gene_associations`
+async fn list_gene_associations_from_hail(
+    state: &AppState,
+    analysis_id: &str,
+    params: &GeneListQuery,
+    limit: usize,
+    offset: usize,
+) -> Result<Vec<crate::models::GeneAssociationApi>, AppError> {
+    tracing::warn!(
+        "Phenotype {} not in gene_associations; falling back to a Hail Table scan",
+        analysis_id
+    );
+    ensure_assets_loaded(state).await?;
+    match state
+        .gene_queries
+        .query_all_genes(analysis_id, params.to_params(), Some(limit), Some(offset))
+        .await
+    {
+        Ok(results) => Ok(results.into_iter().map(|r| r.to_api()).collect()),
+        // No gene results anywhere: an empty list, as from ClickHouse
+        Err(AppError::NotFound(_)) => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Handler for GET /api/phenotype/{analysis_id}/genes/count
+///
+/// Number of gene associations `/api/phenotype/{analysis_id}/genes` would
+/// return with the same ancestry/annotation/max_maf/test filters.
+pub async fn count_gene_associations(
+    State(state): State<Arc<AppState>>,
+    Path(analysis_id): Path<String>,
+    Query(params): Query<GeneListQuery>,
+) -> Result<Json<CountResult>, AppError> {
+    let timer = QueryTimer::start();
+    let query = format!("SELECT count() FROM gene_associations WHERE {}", params.filter());
 
-    let api_rows: Vec<crate::models::GeneAssociationApi> =
-        rows.into_iter().map(|r| r.to_api()).collect();
-    Ok(Json(api_rows))
+    let count = params
+        .bind_filter(state.clickhouse.query(&query), &analysis_id)
+        .fetch_one_with::<u64>(&state.executor)
+        .await?;
+
+    Ok(Json(CountResult {
+        count,
+        time: timer.elapsed(),
+    }))
 }
 
 /// Handler for GET /api/analyses-loaded
@@ -692,8 +1123,9 @@ pub async fn get_analyses_loaded(
 }
 
 /// Ensure assets are loaded (discover if needed)
-/// Uses double-checked locking to avoid redundant GCS discovery.
-async fn ensure_assets_loaded(state: &AppState) -> Result<(), AppError> {
+/// Uses double-checked locking to avoid redundant discovery. The snapshot in
+/// the `analysis_assets` table is used when present; otherwise GCS is scanned.
+pub(crate) async fn ensure_assets_loaded(state: &AppState) -> Result<(), AppError> {
     // Fast path: check with read lock
     {
         let assets = state.assets.read().await;
@@ -710,8 +1142,26 @@ async fn ensure_assets_loaded(state: &AppState) -> Result<(), AppError> {
     }
 
     // We hold the write lock, so we're the only one doing discovery
+    match crate::analysis_assets::load_from_clickhouse(&state.clickhouse, &state.executor).await {
+        Ok(Some(loaded)) => {
+            tracing::info!("Loaded {} analysis assets from ClickHouse", loaded.assets.len());
+            *assets_lock = Some(loaded);
+            return Ok(());
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Failed to load analysis assets from ClickHouse: {}", e),
+    }
+
+    // Discovery filters by the known phenotypes; without metadata it would
+    // cache an empty snapshot
+    if !state.metadata_loaded.load(std::sync::atomic::Ordering::Acquire) {
+        return Err(AppError::NotReady(
+            "Analysis metadata is still loading; asset discovery will run once it has".to_string(),
+        ));
+    }
+
     tracing::info!("Discovering analysis assets from GCS...");
-    let discovery = crate::analysis_assets::AssetDiscovery::new()?;
+    let discovery = crate::analysis_assets::AssetDiscovery::new(&state.config)?;
     let metadata = state.metadata.read().await;
     let valid_phenotypes = crate::analysis_assets::get_valid_phenotypes(&metadata);
     drop(metadata); // release read lock before long discovery operation
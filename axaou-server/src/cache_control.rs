@@ -0,0 +1,253 @@
+//! Per-route `Cache-Control` policy
+//!
+//! Routes are grouped into classes by their matched path pattern, and each
+//! class gets a `Cache-Control` header from the `[cache_control]` section of
+//! the server config:
+//!
+//! ```toml
+//! [cache_control.immutable]     # plot images under /api/v/:dataset
+//! max_age = 31536000
+//! immutable = true
+//!
+//! [cache_control.metadata]      # config, analyses, categories, gene models
+//! max_age = 3600
+//! s_maxage = 86400
+//!
+//! [cache_control.dynamic]       # everything else
+//! max_age = 60
+//! s_maxage = 300
+//! ```
+//!
+//! Plot images are only immutable under a dataset mount (`/api/v/:dataset`),
+//! whose data and renders do not change; the unversioned `/api` aliases get
+//! the dynamic rule so new ingests and rendering changes show up. Error
+//! responses and admin/health routes are always `no-store`. A header already
+//! set by the handler is left alone.
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Caching rule for one route class
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct CacheRule {
+    /// Browser max-age in seconds
+    pub max_age: u32,
+    /// Shared (CDN) cache lifetime in seconds; defaults to `max_age`
+    pub s_maxage: Option<u32>,
+    /// Mark the response as never changing for its URL
+    pub immutable: bool,
+}
+
+impl Default for CacheRule {
+    fn default() -> Self {
+        Self {
+            max_age: 60,
+            s_maxage: None,
+            immutable: false,
+        }
+    }
+}
+
+impl CacheRule {
+    fn header_value(&self) -> String {
+        let mut value = format!("public, max-age={}", self.max_age);
+        if let Some(s_maxage) = self.s_maxage {
+            value.push_str(&format!(", s-maxage={}", s_maxage));
+        }
+        if self.immutable {
+            value.push_str(", immutable");
+        }
+        value
+    }
+}
+
+/// `[cache_control]` config section
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct CacheControlConfig {
+    pub immutable: CacheRule,
+    pub metadata: CacheRule,
+    pub dynamic: CacheRule,
+}
+
+impl Default for CacheControlConfig {
+    fn default() -> Self {
+        Self {
+            immutable: CacheRule {
+                max_age: 31_536_000,
+                s_maxage: None,
+                immutable: true,
+            },
+            metadata: CacheRule {
+                max_age: 3600,
+                s_maxage: Some(86_400),
+                immutable: false,
+            },
+            dynamic: CacheRule {
+                max_age: 60,
+                s_maxage: Some(300),
+                immutable: false,
+            },
+        }
+    }
+}
+
+/// Route classes with distinct caching behaviour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheClass {
+    Immutable,
+    Metadata,
+    Dynamic,
+    NoStore,
+}
+
+/// Route patterns (relative to the API mount point) served as metadata
+const METADATA_ROUTES: &[&str] = &[
+    "/config",
+    "/analyses",
+    "/analyses/:analysis_id",
+    "/analyses-loaded",
+    "/categories",
+    "/assets",
+    "/assets/summary",
+    "/phenotypes/summary",
+    "/genes/summary",
+    "/genes/all-symbols",
+    "/genes/model/:gene_id",
+    "/genes/model/interval/:interval",
+];
+
+/// Plot image routes, immutable when served under a dataset mount
+const IMAGE_ROUTES: &[&str] = &[
+    "/manhattan/image",
+    "/manhattan/thumbnail",
+    "/plot/image",
+    "/qq/image",
+];
+
+/// Classify a matched route pattern (which may include the mount prefix)
+pub fn classify(matched_path: &str) -> CacheClass {
+    let path = matched_path.trim_end_matches('/');
+    if path.contains("/admin/") || path.ends_with("/health") || path.ends_with("/health/detail") {
+        CacheClass::NoStore
+    } else if IMAGE_ROUTES.iter().any(|r| path.ends_with(r)) {
+        if path.starts_with("/api/v/") {
+            CacheClass::Immutable
+        } else {
+            CacheClass::Dynamic
+        }
+    } else if METADATA_ROUTES.iter().any(|r| path.ends_with(r)) {
+        CacheClass::Metadata
+    } else {
+        CacheClass::Dynamic
+    }
+}
+
+impl CacheControlConfig {
+    /// Header value for a route class
+    pub fn header_value(&self, class: CacheClass) -> String {
+        match class {
+            CacheClass::Immutable => self.immutable.header_value(),
+            CacheClass::Metadata => self.metadata.header_value(),
+            CacheClass::Dynamic => self.dynamic.header_value(),
+            CacheClass::NoStore => "no-store".to_string(),
+        }
+    }
+}
+
+/// Axum middleware setting `Cache-Control` from the policy
+///
+/// Mounted with `route_layer` so the matched route pattern is available.
+pub async fn apply(
+    State(policy): State<Arc<CacheControlConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let class = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| classify(p.as_str()))
+        .unwrap_or(CacheClass::NoStore);
+
+    let mut response = next.run(request).await;
+    // Handlers with response-specific lifetimes (e.g. signed URLs) set their own
+    if response.headers().contains_key(header::CACHE_CONTROL) {
+        return response;
+    }
+    let status = response.status();
+    let value = if status.is_success() || status == StatusCode::NOT_MODIFIED {
+        policy.header_value(class)
+    } else {
+        "no-store".to_string()
+    };
+    if let Ok(value) = HeaderValue::from_str(&value) {
+        response.headers_mut().insert(header::CACHE_CONTROL, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("/api/v/414k/phenotype/:analysis_id/manhattan/image"), CacheClass::Immutable);
+        assert_eq!(classify("/api/v/414k/phenotype/:analysis_id/qq/image"), CacheClass::Immutable);
+        // Unversioned aliases change with new ingests and renderer releases
+        assert_eq!(classify("/api/phenotype/:analysis_id/manhattan/image"), CacheClass::Dynamic);
+        assert_eq!(classify("/api/phenotype/:analysis_id/qq/image"), CacheClass::Dynamic);
+        assert_eq!(
+            classify("/api/phenotype/:analysis_id/loci/:locus_id/plot/image"),
+            CacheClass::Dynamic
+        );
+        assert_eq!(classify("/api/phenotype/:analysis_id/manhattan/thumbnail"), CacheClass::Dynamic);
+        assert_eq!(
+            classify("/api/v/414k/phenotype/:analysis_id/manhattan/thumbnail"),
+            CacheClass::Immutable
+        );
+        assert_eq!(classify("/api/analyses"), CacheClass::Metadata);
+        assert_eq!(classify("/api/analyses/:analysis_id"), CacheClass::Metadata);
+        assert_eq!(classify("/api/genes/model/interval/:interval"), CacheClass::Metadata);
+        assert_eq!(classify("/api/analyses/search"), CacheClass::Dynamic);
+        assert_eq!(classify("/api/phenotype/:analysis_id/region/render"), CacheClass::Dynamic);
+        assert_eq!(classify("/api/admin/cache/clear"), CacheClass::NoStore);
+        assert_eq!(classify("/api/health"), CacheClass::NoStore);
+        assert_eq!(classify("/api/v/414k/health/detail"), CacheClass::NoStore);
+    }
+
+    #[test]
+    fn test_header_values() {
+        let policy = CacheControlConfig::default();
+        assert_eq!(
+            policy.header_value(CacheClass::Immutable),
+            "public, max-age=31536000, immutable"
+        );
+        assert_eq!(
+            policy.header_value(CacheClass::Metadata),
+            "public, max-age=3600, s-maxage=86400"
+        );
+        assert_eq!(policy.header_value(CacheClass::NoStore), "no-store");
+    }
+
+    #[test]
+    fn test_partial_toml_keeps_defaults() {
+        let policy: CacheControlConfig = toml::from_str(
+            r#"
+            [dynamic]
+            max_age = 0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(policy.dynamic.max_age, 0);
+        assert_eq!(policy.dynamic.s_maxage, None);
+        assert_eq!(policy.metadata, CacheControlConfig::default().metadata);
+    }
+}
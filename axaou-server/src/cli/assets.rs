@@ -0,0 +1,327 @@
+//! Assets manifest tooling
+//!
+//! `assets diff old.json new.json` compares two `discover` snapshots by
+//! phenotype and asset type, for checking a new data freeze before it is
+//! deployed. Phenotypes that disappear and reappear under an ID differing
+//! only in case or punctuation (`Height` -> `height`, `blood-pressure` ->
+//! `blood_pressure`) are reported as renamed rather than removed and added.
+
+use crate::models::{AnalysisAsset, AnalysisAssets, ASSETS_MANIFEST_VERSION};
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Assets subcommands
+#[derive(Debug, Subcommand)]
+pub enum AssetsCommand {
+    /// Report phenotypes and asset types added, removed or renamed between two manifests
+    Diff {
+        /// Manifest currently deployed
+        old: PathBuf,
+
+        /// Candidate manifest
+        new: PathBuf,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Run an assets subcommand
+pub async fn run_assets(command: AssetsCommand) -> Result<()> {
+    match command {
+        AssetsCommand::Diff { old, new, json } => {
+            let old = load_manifest(&old).await?;
+            let new = load_manifest(&new).await?;
+            let diff = ManifestDiff::new(&old, &new);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print_diff(&diff);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn load_manifest(path: &Path) -> Result<AnalysisAssets> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let assets: AnalysisAssets = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse assets manifest {}", path.display()))?;
+    if assets.version > ASSETS_MANIFEST_VERSION {
+        warn!(
+            "{} has manifest version {}, newer than this build supports ({})",
+            path.display(),
+            assets.version,
+            ASSETS_MANIFEST_VERSION
+        );
+    }
+    Ok(assets)
+}
+
+/// Header fields of one manifest
+#[derive(Debug, Serialize)]
+pub struct ManifestInfo {
+    pub version: u32,
+    pub generated_at: Option<String>,
+    pub dataset: Option<String>,
+    pub phenotypes: usize,
+    pub assets: usize,
+}
+
+impl ManifestInfo {
+    fn new(manifest: &AnalysisAssets) -> Self {
+        Self {
+            version: manifest.version,
+            generated_at: manifest.generated_at.clone(),
+            dataset: manifest.dataset.clone(),
+            phenotypes: manifest.analysis_ids().len(),
+            assets: manifest.assets.len(),
+        }
+    }
+}
+
+/// A phenotype whose ID changed between manifests
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PhenotypeRename {
+    pub from: String,
+    pub to: String,
+}
+
+/// Asset count of one asset type (e.g., "variant:exomes") in each manifest
+#[derive(Debug, PartialEq, Serialize)]
+pub struct AssetTypeCount {
+    pub asset_type: String,
+    pub old: usize,
+    pub new: usize,
+}
+
+/// Differences between two assets manifests
+#[derive(Debug, Serialize)]
+pub struct ManifestDiff {
+    pub old: ManifestInfo,
+    pub new: ManifestInfo,
+    pub added_phenotypes: Vec<String>,
+    pub removed_phenotypes: Vec<String>,
+    pub renamed_phenotypes: Vec<PhenotypeRename>,
+    pub added_asset_types: Vec<String>,
+    pub removed_asset_types: Vec<String>,
+    /// Asset types whose count changed
+    pub changed_asset_types: Vec<AssetTypeCount>,
+}
+
+impl ManifestDiff {
+    pub fn new(old: &AnalysisAssets, new: &AnalysisAssets) -> Self {
+        let old_ids: BTreeSet<String> = old.analysis_ids().into_iter().collect();
+        let new_ids: BTreeSet<String> = new.analysis_ids().into_iter().collect();
+        let mut added: Vec<String> = new_ids.difference(&old_ids).cloned().collect();
+        let mut removed: Vec<String> = old_ids.difference(&new_ids).cloned().collect();
+
+        // Pair removed and added IDs that share a rename key, when unambiguous
+        let mut added_by_key: HashMap<String, Vec<&String>> = HashMap::new();
+        for id in &added {
+            added_by_key.entry(rename_key(id)).or_default().push(id);
+        }
+        let mut removed_by_key: HashMap<String, usize> = HashMap::new();
+        for id in &removed {
+            *removed_by_key.entry(rename_key(id)).or_default() += 1;
+        }
+        let renamed: Vec<PhenotypeRename> = removed
+            .iter()
+            .filter_map(|from| {
+                let key = rename_key(from);
+                match added_by_key.get(&key).map(Vec::as_slice) {
+                    Some([to]) if removed_by_key[&key] == 1 => Some(PhenotypeRename {
+                        from: from.clone(),
+                        to: (*to).clone(),
+                    }),
+                    _ => None,
+                }
+            })
+            .collect();
+        removed.retain(|id| !renamed.iter().any(|r| &r.from == id));
+        added.retain(|id| !renamed.iter().any(|r| &r.to == id));
+
+        let old_types = type_counts(old);
+        let new_types = type_counts(new);
+        let added_asset_types = new_types
+            .keys()
+            .filter(|t| !old_types.contains_key(*t))
+            .cloned()
+            .collect();
+        let removed_asset_types = old_types
+            .keys()
+            .filter(|t| !new_types.contains_key(*t))
+            .cloned()
+            .collect();
+        let changed_asset_types = old_types
+            .iter()
+            .filter_map(|(asset_type, &old_count)| {
+                let new_count = *new_types.get(asset_type)?;
+                (new_count != old_count).then(|| AssetTypeCount {
+                    asset_type: asset_type.clone(),
+                    old: old_count,
+                    new: new_count,
+                })
+            })
+            .collect();
+
+        Self {
+            old: ManifestInfo::new(old),
+            new: ManifestInfo::new(new),
+            added_phenotypes: added,
+            removed_phenotypes: removed,
+            renamed_phenotypes: renamed,
+            added_asset_types,
+            removed_asset_types,
+            changed_asset_types,
+        }
+    }
+
+    /// True when both manifests cover the same phenotypes and asset types
+    pub fn is_empty(&self) -> bool {
+        self.added_phenotypes.is_empty()
+            && self.removed_phenotypes.is_empty()
+            && self.renamed_phenotypes.is_empty()
+            && self.added_asset_types.is_empty()
+            && self.removed_asset_types.is_empty()
+            && self.changed_asset_types.is_empty()
+    }
+}
+
+/// Phenotype ID ignoring case and punctuation
+fn rename_key(id: &str) -> String {
+    id.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Asset type label, qualified by sequencing type (e.g., "variant_exp_p:genomes")
+fn type_label(asset: &AnalysisAsset) -> String {
+    let asset_type = match serde_json::to_value(asset.asset_type) {
+        Ok(serde_json::Value::String(s)) => s,
+        _ => format!("{:?}", asset.asset_type),
+    };
+    match asset.sequencing_type {
+        Some(seq) => format!("{}:{}", asset_type, seq),
+        None => asset_type,
+    }
+}
+
+fn type_counts(manifest: &AnalysisAssets) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for asset in &manifest.assets {
+        *counts.entry(type_label(asset)).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn print_diff(diff: &ManifestDiff) {
+    let describe = |info: &ManifestInfo| {
+        format!(
+            "v{} dataset={} generated_at={} ({} phenotypes, {} assets)",
+            info.version,
+            info.dataset.as_deref().unwrap_or("-"),
+            info.generated_at.as_deref().unwrap_or("-"),
+            info.phenotypes,
+            info.assets
+        )
+    };
+    println!("Old: {}", describe(&diff.old));
+    println!("New: {}", describe(&diff.new));
+
+    if diff.is_empty() {
+        println!("\nNo phenotype or asset type changes.");
+        return;
+    }
+
+    let list = |title: &str, items: &[String], sign: char| {
+        if !items.is_empty() {
+            println!("\n{} ({}):", title, items.len());
+            for item in items {
+                println!("  {} {}", sign, item);
+            }
+        }
+    };
+    list("Added phenotypes", &diff.added_phenotypes, '+');
+    list("Removed phenotypes", &diff.removed_phenotypes, '-');
+    if !diff.renamed_phenotypes.is_empty() {
+        println!("\nRenamed phenotypes ({}):", diff.renamed_phenotypes.len());
+        for rename in &diff.renamed_phenotypes {
+            println!("  {} -> {}", rename.from, rename.to);
+        }
+    }
+    list("Added asset types", &diff.added_asset_types, '+');
+    list("Removed asset types", &diff.removed_asset_types, '-');
+    if !diff.changed_asset_types.is_empty() {
+        println!("\nAsset counts changed:");
+        for count in &diff.changed_asset_types {
+            println!("  {:<28} {:>8} -> {}", count.asset_type, count.old, count.new);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AnalysisAssetType, AncestryGroup, SequencingType};
+    use crate::test_support::factories::asset;
+
+    fn manifest(assets: Vec<AnalysisAsset>) -> AnalysisAssets {
+        AnalysisAssets {
+            assets,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_manifest_diff() {
+        let old = manifest(vec![
+            asset("height", AncestryGroup::Meta, AnalysisAssetType::Gene, None),
+            asset("Blood-Pressure", AncestryGroup::Meta, AnalysisAssetType::Gene, None),
+            asset("bmi", AncestryGroup::Meta, AnalysisAssetType::Gene, None),
+            asset("bmi", AncestryGroup::Meta, AnalysisAssetType::VariantExpP, Some(SequencingType::Exomes)),
+        ]);
+        let new = manifest(vec![
+            asset("height", AncestryGroup::Meta, AnalysisAssetType::Gene, None),
+            asset("height", AncestryGroup::Meta, AnalysisAssetType::Variant, Some(SequencingType::Genomes)),
+            asset("blood_pressure", AncestryGroup::Meta, AnalysisAssetType::Gene, None),
+            asset("ldl", AncestryGroup::Meta, AnalysisAssetType::Gene, None),
+        ]);
+        let diff = ManifestDiff::new(&old, &new);
+
+        assert_eq!(diff.added_phenotypes, vec!["ldl"]);
+        assert_eq!(diff.removed_phenotypes, vec!["bmi"]);
+        assert_eq!(
+            diff.renamed_phenotypes,
+            vec![PhenotypeRename {
+                from: "Blood-Pressure".to_string(),
+                to: "blood_pressure".to_string()
+            }]
+        );
+        assert_eq!(diff.added_asset_types, vec!["variant:genomes"]);
+        assert_eq!(diff.removed_asset_types, vec!["variant_exp_p:exomes"]);
+        assert!(diff.changed_asset_types.is_empty());
+        assert!(!diff.is_empty());
+        assert!(ManifestDiff::new(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_manifest_header_defaults() {
+        let legacy: AnalysisAssets = serde_json::from_str(r#"{"assets": []}"#).unwrap();
+        assert_eq!(legacy.version, 0);
+        assert_eq!(legacy.dataset, None);
+
+        let stamped = legacy.with_manifest_header("414k");
+        assert_eq!(stamped.version, ASSETS_MANIFEST_VERSION);
+        assert_eq!(stamped.dataset.as_deref(), Some("414k"));
+        assert!(stamped.generated_at.is_some());
+    }
+}
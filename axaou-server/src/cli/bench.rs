@@ -0,0 +1,254 @@
+//! Query-path benchmark against a running server
+//!
+//! `bench` replays a recorded query mix (`bench/query_mix.tsv`: one
+//! `label<TAB>path` per line) against a live server and reports p50/p95/p99
+//! latency per query, so changes to queries and indexes can be compared
+//! before and after. The server caches responses, so with `--admin-token`
+//! the API cache is cleared before every round and each round measures the
+//! uncached query path; without it, rounds after the first hit the cache.
+
+use crate::loadtest::runner::percentile;
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use futures::{stream, StreamExt};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tracing::{info, warn};
+
+/// Arguments for `bench`
+#[derive(Debug, Args, Clone)]
+pub struct BenchArgs {
+    /// Base URL of the server under test
+    #[arg(long, default_value = "http://localhost:3001")]
+    pub url: String,
+
+    /// Query mix file (`label<TAB>path` per line, `#` comments)
+    #[arg(long, default_value = "bench/query_mix.tsv")]
+    pub queries: PathBuf,
+
+    /// Rounds over the whole mix
+    #[arg(long, default_value = "10")]
+    pub rounds: usize,
+
+    /// Requests in flight at once within a round
+    #[arg(long, default_value = "1")]
+    pub concurrency: usize,
+
+    /// Admin token used to clear the API cache before each round
+    /// (default: $AXAOU_ADMIN_TOKEN)
+    #[arg(long, env = "AXAOU_ADMIN_TOKEN", hide_env_values = true)]
+    pub admin_token: Option<String>,
+
+    /// Also write the report as JSON to this file
+    #[arg(long)]
+    pub json: Option<PathBuf>,
+}
+
+/// One request of the query mix
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchQuery {
+    pub label: String,
+    pub path: String,
+}
+
+/// Parse a query mix file
+pub fn parse_query_mix(contents: &str) -> Result<Vec<BenchQuery>> {
+    let mut queries = Vec::new();
+    for (line_no, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((label, path)) = line.split_once('\t') else {
+            bail!("line {}: expected label<TAB>path", line_no + 1);
+        };
+        let path = path.trim();
+        if !path.starts_with('/') {
+            bail!("line {}: path must start with '/'", line_no + 1);
+        }
+        queries.push(BenchQuery {
+            label: label.trim().to_string(),
+            path: path.to_string(),
+        });
+    }
+    Ok(queries)
+}
+
+/// Latency summary of one query
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryLatency {
+    pub label: String,
+    pub path: String,
+    pub requests: usize,
+    pub errors: usize,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+}
+
+impl QueryLatency {
+    fn new(query: &BenchQuery, mut latencies: Vec<u64>, errors: usize) -> Self {
+        latencies.sort_unstable();
+        Self {
+            label: query.label.clone(),
+            path: query.path.clone(),
+            requests: latencies.len() + errors,
+            errors,
+            p50_ms: percentile(&latencies, 0.50),
+            p95_ms: percentile(&latencies, 0.95),
+            p99_ms: percentile(&latencies, 0.99),
+            max_ms: latencies.last().copied().unwrap_or(0),
+        }
+    }
+}
+
+/// Report written by `bench --json`
+#[derive(Debug, Serialize)]
+pub struct BenchReport {
+    pub url: String,
+    pub rounds: usize,
+    pub concurrency: usize,
+    pub cache_cleared: bool,
+    pub queries: Vec<QueryLatency>,
+}
+
+/// Run `bench`
+pub async fn run_bench(args: &BenchArgs) -> Result<()> {
+    let queries = load_query_mix(&args.queries).await?;
+    let base = args.url.trim_end_matches('/');
+    let client = reqwest::Client::new();
+    info!(
+        "Replaying {} queries x {} rounds against {} (concurrency {})",
+        queries.len(),
+        args.rounds,
+        base,
+        args.concurrency
+    );
+
+    let mut latencies: Vec<Vec<u64>> = vec![Vec::new(); queries.len()];
+    let mut errors = vec![0usize; queries.len()];
+    for round in 0..args.rounds {
+        if let Some(token) = &args.admin_token {
+            clear_cache(&client, base, token).await?;
+        }
+        let results: Vec<(usize, Result<u64>)> = stream::iter(queries.iter().enumerate())
+            .map(|(i, query)| {
+                let client = &client;
+                async move { (i, timed_get(client, &format!("{}{}", base, query.path)).await) }
+            })
+            .buffer_unordered(args.concurrency.max(1))
+            .collect()
+            .await;
+        for (i, result) in results {
+            match result {
+                Ok(ms) => latencies[i].push(ms),
+                Err(e) => {
+                    warn!("round {}: {} failed: {:#}", round + 1, queries[i].label, e);
+                    errors[i] += 1;
+                }
+            }
+        }
+    }
+
+    let report = BenchReport {
+        url: base.to_string(),
+        rounds: args.rounds,
+        concurrency: args.concurrency,
+        cache_cleared: args.admin_token.is_some(),
+        queries: queries
+            .iter()
+            .zip(latencies.into_iter().zip(errors))
+            .map(|(query, (lats, errs))| QueryLatency::new(query, lats, errs))
+            .collect(),
+    };
+    print_report(&report);
+    if let Some(path) = &args.json {
+        tokio::fs::write(path, serde_json::to_string_pretty(&report)?).await?;
+        info!("Saved benchmark report to {}", path.display());
+    }
+    Ok(())
+}
+
+async fn load_query_mix(path: &Path) -> Result<Vec<BenchQuery>> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read query mix {}", path.display()))?;
+    let queries = parse_query_mix(&contents)
+        .with_context(|| format!("Invalid query mix {}", path.display()))?;
+    if queries.is_empty() {
+        bail!("Query mix {} is empty", path.display());
+    }
+    Ok(queries)
+}
+
+/// GET `url` to completion, returning the latency in milliseconds
+async fn timed_get(client: &reqwest::Client, url: &str) -> Result<u64> {
+    let start = Instant::now();
+    let response = client.get(url).send().await?;
+    let status = response.status();
+    response.bytes().await?;
+    if !status.is_success() {
+        bail!("HTTP {}", status);
+    }
+    Ok(start.elapsed().as_millis() as u64)
+}
+
+async fn clear_cache(client: &reqwest::Client, base: &str, token: &str) -> Result<()> {
+    let response = client
+        .post(format!("{}/api/admin/cache/clear", base))
+        .bearer_auth(token)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        bail!("Clearing the API cache failed: HTTP {}", response.status());
+    }
+    Ok(())
+}
+
+fn print_report(report: &BenchReport) {
+    println!(
+        "\n{:<26} {:>8} {:>8} {:>8} {:>8} {:>8}",
+        "query", "p50 ms", "p95 ms", "p99 ms", "max ms", "errors"
+    );
+    for q in &report.queries {
+        println!(
+            "{:<26} {:>8} {:>8} {:>8} {:>8} {:>8}",
+            q.label, q.p50_ms, q.p95_ms, q.p99_ms, q.max_ms, q.errors
+        );
+    }
+    if !report.cache_cleared {
+        println!("\n(API cache not cleared between rounds; pass --admin-token to measure uncached queries)");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query_mix() {
+        let mix = "# comment\n\nloci\t/api/phenotype/height/loci\nphewas\t /api/genes/phewas/BRCA2 \n";
+        let queries = parse_query_mix(mix).unwrap();
+        assert_eq!(queries.len(), 2);
+        assert_eq!(queries[1].label, "phewas");
+        assert_eq!(queries[1].path, "/api/genes/phewas/BRCA2");
+
+        assert!(parse_query_mix("loci /api/phenotype/height/loci").is_err());
+        assert!(parse_query_mix("loci\tapi/phenotype/height/loci").is_err());
+    }
+
+    #[test]
+    fn test_query_latency() {
+        let query = BenchQuery {
+            label: "loci".to_string(),
+            path: "/api/phenotype/height/loci".to_string(),
+        };
+        let latency = QueryLatency::new(&query, (1..=100).rev().collect(), 2);
+        assert_eq!(latency.requests, 102);
+        assert_eq!(latency.p50_ms, 51);
+        assert_eq!(latency.p99_ms, 100);
+        assert_eq!(latency.max_ms, 100);
+    }
+}
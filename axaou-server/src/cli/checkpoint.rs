@@ -0,0 +1,179 @@
+//! Partition checkpoints for resumable ingests
+//!
+//! The Hail Table export runs in chunks of partitions. Each chunk is exported
+//! to a scratch table, which is then renamed to `<staging>_part_<start>_<end>`
+//! with the input path as its comment. The `RENAME` is atomic, so a part
+//! table either holds all rows of its chunk or does not exist: the part
+//! tables are the checkpoints. `ingest <table> --resume` skips the chunks
+//! that already have a part table for the same input path, and the staging
+//! table is rebuilt from all part tables once the export is complete, so
+//! re-running any step after a crash cannot duplicate rows.
+
+use super::sql::SqlClient;
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+/// Checkpoints for one (staging table, input path) load
+pub struct Checkpoints<'a> {
+    sql: &'a SqlClient,
+    staging_table: &'a str,
+    input_path: &'a str,
+}
+
+impl<'a> Checkpoints<'a> {
+    pub fn new(sql: &'a SqlClient, staging_table: &'a str, input_path: &'a str) -> Self {
+        Self {
+            sql,
+            staging_table,
+            input_path,
+        }
+    }
+
+    fn part_prefix(&self) -> String {
+        format!("{}_part_", self.staging_table)
+    }
+
+    /// Table holding the rows of one exported chunk
+    pub fn part_table(&self, partitions: &Range<u32>) -> String {
+        format!("{}{}_{}", self.part_prefix(), partitions.start, partitions.end)
+    }
+
+    /// Part tables of this staging table; with `input_path`, only those
+    /// exported from it
+    async fn part_tables(&self, input_path: Option<&str>) -> Result<Vec<String>> {
+        self.sql
+            .query(
+                "SELECT name FROM system.tables \
+                 WHERE database = currentDatabase() AND startsWith(name, ?) AND (? OR comment = ?) \
+                 ORDER BY name",
+            )
+            .bind(self.part_prefix())
+            .bind(input_path.is_none())
+            .bind(input_path.unwrap_or_default())
+            .fetch_all::<String>()
+            .await
+            .context("Failed to read ingest checkpoints")
+    }
+
+    /// Partitions already exported from the input path
+    pub async fn completed(&self) -> Result<BTreeSet<u32>> {
+        let prefix = self.part_prefix();
+        Ok(self
+            .part_tables(Some(self.input_path))
+            .await?
+            .iter()
+            .filter_map(|name| part_range(&prefix, name))
+            .flatten()
+            .collect())
+    }
+
+    /// Commit an exported chunk: label `scratch_table` with the input path
+    /// and rename it to the chunk's part table in one atomic step
+    pub async fn record(&self, scratch_table: &str, partitions: &Range<u32>) -> Result<()> {
+        self.sql
+            .query(&format!("ALTER TABLE {} MODIFY COMMENT ?", scratch_table))
+            .bind(self.input_path)
+            .execute()
+            .await
+            .context("Failed to label ingest checkpoint")?;
+        self.sql
+            .execute_statement(&format!(
+                "RENAME TABLE {} TO {}",
+                scratch_table,
+                self.part_table(partitions)
+            ))
+            .await
+            .context("Failed to record ingest checkpoint")
+    }
+
+    /// Recreate the staging table from all part tables
+    ///
+    /// Starts from an empty staging table every time, so an interrupted
+    /// assembly is simply repeated.
+    pub async fn assemble(&self) -> Result<()> {
+        let parts = self.part_tables(Some(self.input_path)).await?;
+        let Some(first) = parts.first() else {
+            bail!("No exported partitions to assemble into {}", self.staging_table);
+        };
+        self.sql
+            .execute_statement(&format!("DROP TABLE IF EXISTS {}", self.staging_table))
+            .await?;
+        self.sql
+            .execute_statement(&format!("CREATE TABLE {} AS {}", self.staging_table, first))
+            .await?;
+        for part in &parts {
+            self.sql
+                .execute_statement(&format!("INSERT INTO {} SELECT * FROM {}", self.staging_table, part))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Drop all part tables, e.g. once the staging table is consumed or dropped
+    pub async fn clear(&self) -> Result<()> {
+        for part in self.part_tables(None).await? {
+            self.sql
+                .execute_statement(&format!("DROP TABLE IF EXISTS {}", part))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Partition range encoded in a part table name
+fn part_range(prefix: &str, name: &str) -> Option<Range<u32>> {
+    let (start, end) = name.strip_prefix(prefix)?.split_once('_')?;
+    Some(start.parse().ok()?..end.parse().ok()?)
+}
+
+/// Contiguous runs of at most `chunk_size` partitions in `0..total` that are
+/// not yet in `completed`
+pub fn pending_chunks(total: u32, completed: &BTreeSet<u32>, chunk_size: u32) -> Vec<Range<u32>> {
+    let chunk_size = chunk_size.max(1);
+    let mut chunks = Vec::new();
+    let mut current: Option<Range<u32>> = None;
+
+    for partition in (0..total).filter(|p| !completed.contains(p)) {
+        match &mut current {
+            Some(range) if range.end == partition && range.len() < chunk_size as usize => {
+                range.end += 1;
+            }
+            _ => {
+                chunks.extend(current.take());
+                current = Some(partition..partition + 1);
+            }
+        }
+    }
+    chunks.extend(current);
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending_chunks() {
+        let none = BTreeSet::new();
+        assert_eq!(pending_chunks(5, &none, 2), vec![0..2, 2..4, 4..5]);
+
+        let completed: BTreeSet<u32> = [0, 1, 2, 5].into_iter().collect();
+        assert_eq!(pending_chunks(8, &completed, 10), vec![3..5, 6..8]);
+
+        let all: BTreeSet<u32> = (0..4).collect();
+        assert!(pending_chunks(4, &all, 2).is_empty());
+    }
+
+    #[test]
+    fn test_part_range() {
+        let prefix = "staging_loci_raw_part_";
+        assert_eq!(part_range(prefix, "staging_loci_raw_part_64_128"), Some(64..128));
+        assert_eq!(part_range(prefix, "staging_loci_raw_part_x_1"), None);
+        assert_eq!(part_range(prefix, "staging_loci_raw_chunk"), None);
+        // Round trip with the name written by `record`
+        let sql = SqlClient::new("http://localhost:8123", "default");
+        let checkpoints = Checkpoints::new(&sql, "staging_loci_raw", "gs://b/t.ht");
+        assert_eq!(part_range(prefix, &checkpoints.part_table(&(3..5))), Some(3..5));
+    }
+}
@@ -3,10 +3,10 @@
 //! Unlike `ingest` which loads external Hail Tables, `derive` creates tables
 //! by running aggregation queries over already-ingested data.
 
-use anyhow::{bail, Context, Result};
+use super::sql::SqlClient;
+use anyhow::Result;
 use clap::{Args, Subcommand};
-use std::process::Command;
-use tracing::info;
+use tracing::{info, warn};
 
 /// SQL files are embedded at compile time
 const TOP_VARIANTS_AGGREGATED_DDL: &str = include_str!("../sql/top_variants_aggregated.sql");
@@ -138,7 +138,7 @@ pub async fn run_derive(command: DeriveCommand) -> Result<()> {
             for config in DerivedTableConfig::all() {
                 info!("--- Building {} ---", config.name);
                 if let Err(e) = build_derived_table(&config, &args).await {
-                    tracing::warn!("Failed to build {}: {}", config.name, e);
+                    warn!("Failed to build {}: {}", config.name, e);
                 }
             }
         }
@@ -152,28 +152,25 @@ pub async fn run_derive(command: DeriveCommand) -> Result<()> {
 /// Build a single derived table
 async fn build_derived_table(config: &DerivedTableConfig, args: &DeriveArgs) -> Result<()> {
     info!("Building derived table '{}'...", config.name);
+    let sql = SqlClient::new(&args.clickhouse_url, &args.database);
 
     // Step 1: Prepare table (drop if replacing)
     if args.replace {
         info!("Step 1: Dropping existing table '{}'...", config.name);
-        execute_sql(
-            &args.clickhouse_url,
-            &args.database,
-            &format!("DROP TABLE IF EXISTS {}", config.name),
-        )
-        .await?;
+        sql.execute_statement(&format!("DROP TABLE IF EXISTS {}", config.name))
+            .await?;
     }
 
     // Step 2: Create table
     info!("Step 2: Creating table '{}'...", config.name);
-    execute_sql(&args.clickhouse_url, &args.database, config.ddl_sql).await?;
+    sql.execute(config.ddl_sql).await?;
 
     // Step 3: Populate from source tables
     info!("Step 3: Populating from source tables...");
-    execute_sql(&args.clickhouse_url, &args.database, config.populate_sql).await?;
+    sql.execute(config.populate_sql).await?;
 
     // Step 4: Verify
-    let count = get_row_count(&args.clickhouse_url, &args.database, config.name).await?;
+    let count = sql.row_count(config.name).await?;
     info!("Built '{}' with {} rows", config.name, count);
 
     Ok(())
@@ -181,12 +178,18 @@ async fn build_derived_table(config: &DerivedTableConfig, args: &DeriveArgs) ->
 
 /// Show status of all derived tables
 async fn show_status(url: &str) -> Result<()> {
-    let database = "default";
+    let sql = SqlClient::new(url, "default");
 
     println!("\n=== Derived Table Status ===\n");
 
     for config in DerivedTableConfig::all() {
-        let count = get_row_count(url, database, config.name).await.unwrap_or(0);
+        let count = match sql.row_count(config.name).await {
+            Ok(count) => count,
+            Err(e) => {
+                warn!("{:#}", e);
+                0
+            }
+        };
         let status = if count > 0 {
             format!("{:>12} rows", format_number(count))
         } else {
@@ -199,71 +202,6 @@ async fn show_status(url: &str) -> Result<()> {
     Ok(())
 }
 
-/// Execute SQL against ClickHouse using curl
-async fn execute_sql(url: &str, database: &str, sql: &str) -> Result<()> {
-    let statements = split_sql_statements(sql);
-    for statement in &statements {
-        let full_url = format!("{}/?database={}", url, database);
-        let output = Command::new("curl")
-            .arg("-sS")
-            .arg("--fail-with-body")
-            .arg(&full_url)
-            .arg("-d")
-            .arg(statement)
-            .output()
-            .context("Failed to execute curl command")?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            bail!(
-                "ClickHouse SQL failed:\nSQL: {}\nstderr: {}\nstdout: {}",
-                statement.chars().take(200).collect::<String>(),
-                stderr,
-                stdout
-            );
-        }
-    }
-    Ok(())
-}
-
-/// Split SQL text into individual statements
-fn split_sql_statements(sql: &str) -> Vec<String> {
-    sql.split(';')
-        .map(|s| s.trim())
-        .filter(|s| {
-            !s.is_empty()
-                && !s
-                    .lines()
-                    .all(|line| {
-                        let trimmed = line.trim();
-                        trimmed.is_empty() || trimmed.starts_with("--")
-                    })
-        })
-        .map(|s| s.to_string())
-        .collect()
-}
-
-/// Get row count from a table
-async fn get_row_count(url: &str, database: &str, table: &str) -> Result<u64> {
-    let full_url = format!("{}/?database={}", url, database);
-    let sql = format!("SELECT count() FROM {}", table);
-    let output = Command::new("curl")
-        .arg("-sS")
-        .arg(&full_url)
-        .arg("-d")
-        .arg(&sql)
-        .output()
-        .context("Failed to execute curl command")?;
-
-    if !output.status.success() {
-        return Ok(0);
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    stdout.trim().parse().context("Failed to parse row count")
-}
-
 /// Format a number with thousands separators
 fn format_number(n: u64) -> String {
     let s = n.to_string();
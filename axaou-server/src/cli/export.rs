@@ -0,0 +1,408 @@
+//! Export commands
+//!
+//! `export track-hub` writes a UCSC track hub for one or more phenotypes, so
+//! AoU results can be loaded in the UCSC Genome Browser or IGV:
+//!
+//! ```text
+//! <out>/hub.txt
+//! <out>/genomes.txt
+//! <out>/hg38/trackDb.txt
+//! <out>/hg38/<analysis_id>.<ancestry>.neglog10p.bw   -log10(p) per position
+//! <out>/hg38/<analysis_id>.<ancestry>.loci.bb        significant loci (BED6)
+//! ```
+//!
+//! The signal track is read from the phenotype's variant results Hail Table
+//! (the maximum -log10(p) at each position); loci come from the ClickHouse
+//! `loci` table. bigWig and bigBed files are written with bigtools. With a
+//! `gs://` output, the hub is loaded from
+//! `https://storage.googleapis.com/<bucket>/<prefix>/hub.txt`.
+
+use crate::api::AppState;
+use crate::clickhouse::models::{LocusRow, LOCUS_COLUMNS};
+use crate::clickhouse::QueryExt;
+use crate::cli::export_static::Sink;
+use crate::genomics::Contig;
+use crate::phenotype::loci::loci_bed;
+use anyhow::{anyhow, Context, Result};
+use bigtools::beddata::BedParserStreamingIterator;
+use bigtools::{BedEntry, BigBedWrite, BigWigWrite, Value};
+use clap::{Args, Subcommand};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::info;
+
+/// Genome-wide significance line drawn on the signal track
+const GENOME_WIDE_PVALUE: f64 = 5e-8;
+
+/// autoSql of the loci bigBed (BED6)
+const LOCI_AUTOSQL: &str = r#"table loci
+"Significant loci of a phenotype"
+(
+string chrom;      "Chromosome"
+uint   chromStart; "Start position (0-based)"
+uint   chromEnd;   "End position"
+string name;       "Lead variant"
+uint   score;      "Lead -log10(p) x 100, capped at 1000"
+char[1] strand;    "Strand (always .)"
+)
+"#;
+
+#[derive(Subcommand, Debug)]
+pub enum ExportCommand {
+    /// UCSC track hub (bigWig -log10(p) signal and bigBed loci) for phenotypes
+    TrackHub(TrackHubArgs),
+}
+
+/// Arguments for `export track-hub`
+#[derive(Debug, Args, Clone)]
+pub struct TrackHubArgs {
+    /// Phenotype to include (repeat for several)
+    #[arg(long = "analysis-id", required = true)]
+    pub analysis_ids: Vec<String>,
+
+    /// Output directory, or `gs://bucket/prefix`
+    #[arg(long)]
+    pub out: String,
+
+    /// Ancestry group
+    #[arg(long, default_value = "meta")]
+    pub ancestry: String,
+
+    /// Variant results to read the signal from: genome or exome
+    #[arg(long, default_value = "genome")]
+    pub sequencing_type: String,
+
+    /// Leave out positions below this -log10(p) to shrink the bigWig
+    #[arg(long, default_value = "0")]
+    pub min_neg_log10_p: f64,
+
+    /// Contact email shown by the genome browser
+    #[arg(long, default_value = "")]
+    pub email: String,
+
+    /// Path to dataset config TOML; defaults to $AXAOU_CONFIG or built-in 414k paths
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+}
+
+pub async fn run_export(command: ExportCommand) -> Result<()> {
+    match command {
+        ExportCommand::TrackHub(args) => run_track_hub(&args).await,
+    }
+}
+
+/// Labels of one phenotype's tracks
+struct HubTrack {
+    analysis_id: String,
+    description: String,
+}
+
+impl HubTrack {
+    /// UCSC track names allow letters, digits and underscores only
+    fn name(&self, suffix: &str) -> String {
+        let base: String = self
+            .analysis_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        format!("axaou_{}_{}", base, suffix)
+    }
+
+    fn file(&self, ancestry: &str, suffix: &str) -> String {
+        format!("{}.{}.{}", self.analysis_id, ancestry, suffix)
+    }
+}
+
+/// Run `export track-hub`
+pub async fn run_track_hub(args: &TrackHubArgs) -> Result<()> {
+    if !matches!(args.sequencing_type.as_str(), "genome" | "exome") {
+        anyhow::bail!("--sequencing-type must be genome or exome");
+    }
+    let ancestry = crate::phenotype::manhattan::ancestry_param(Some(&args.ancestry))?.to_string();
+    let config = crate::config::Config::load(args.config.as_deref())?;
+    let state = crate::build_state(config, None, crate::gene_models::GeneModelsBackendKind::Auto);
+    let sink = Sink::new(&args.out)?;
+
+    let metadata = crate::metadata::load_metadata(&state.clickhouse, &state.executor, &state.config).await?;
+    let tracks: Vec<HubTrack> = args
+        .analysis_ids
+        .iter()
+        .map(|id| HubTrack {
+            analysis_id: id.clone(),
+            description: metadata
+                .iter()
+                .find(|m| &m.analysis_id == id)
+                .map(|m| m.description.clone())
+                .filter(|d| !d.is_empty())
+                .unwrap_or_else(|| id.clone()),
+        })
+        .collect();
+
+    let staging = std::env::temp_dir().join(format!("axaou-track-hub-{}", uuid::Uuid::new_v4()));
+    tokio::fs::create_dir_all(&staging).await?;
+    let result = write_tracks(&state, args, &ancestry, &tracks, &sink, &staging).await;
+    let _ = tokio::fs::remove_dir_all(&staging).await;
+    result?;
+
+    sink.put("hub.txt", hub_txt(&tracks, &ancestry, &args.email).into_bytes())
+        .await?;
+    sink.put("genomes.txt", b"genome hg38\ntrackDb hg38/trackDb.txt\n".to_vec())
+        .await?;
+    sink.put("hg38/trackDb.txt", track_db(&tracks, &ancestry).into_bytes())
+        .await?;
+
+    match args.out.strip_prefix("gs://") {
+        Some(path) => info!(
+            "Track hub written; load https://storage.googleapis.com/{}/hub.txt",
+            path.trim_end_matches('/')
+        ),
+        None => info!("Track hub written to {}", args.out),
+    }
+    Ok(())
+}
+
+/// Build and upload the bigWig and bigBed of every phenotype
+async fn write_tracks(
+    state: &Arc<AppState>,
+    args: &TrackHubArgs,
+    ancestry: &str,
+    tracks: &[HubTrack],
+    sink: &Sink,
+    staging: &Path,
+) -> Result<()> {
+    for track in tracks {
+        let id = &track.analysis_id;
+        let signal = load_signal(state, id, ancestry, &args.sequencing_type, args.min_neg_log10_p).await?;
+        info!(
+            "{}: {} signal positions",
+            id,
+            signal.iter().map(|(_, values)| values.len()).sum::<usize>()
+        );
+        let bigwig = staging.join(track.file(ancestry, "neglog10p.bw"));
+        let path = bigwig.clone();
+        tokio::task::spawn_blocking(move || write_bigwig(&path, signal)).await??;
+
+        let loci: Vec<LocusRow> = state
+            .clickhouse
+            .query(&format!(
+                "SELECT {} FROM loci WHERE phenotype = ? AND ancestry = ?",
+                LOCUS_COLUMNS
+            ))
+            .bind(id)
+            .bind(ancestry)
+            .fetch_all_with::<LocusRow>(&state.executor)
+            .await?;
+        info!("{}: {} loci", id, loci.len());
+        let bed = loci_bed(id, ancestry, loci, false);
+        let bigbed = staging.join(track.file(ancestry, "loci.bb"));
+        let path = bigbed.clone();
+        tokio::task::spawn_blocking(move || write_bigbed(&path, &bed)).await??;
+
+        for file in [bigwig, bigbed] {
+            let name = file.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            let bytes = tokio::fs::read(&file).await?;
+            sink.put(&format!("hg38/{}", name), bytes).await?;
+        }
+    }
+    Ok(())
+}
+
+/// -log10(p) per position for each chromosome, as (1-based position, value)
+/// sorted by position; the largest value wins at multi-allelic sites
+async fn load_signal(
+    state: &AppState,
+    analysis_id: &str,
+    ancestry: &str,
+    sequencing_type: &str,
+    min_neg_log10_p: f64,
+) -> Result<Vec<(Contig, Vec<(u32, f32)>)>> {
+    let ht_path = state
+        .config
+        .variant_results_uri(ancestry, analysis_id, sequencing_type);
+    let mut signal = Vec::new();
+    for contig in Contig::all() {
+        let associations = state
+            .hail_client
+            .query_interval_typed(&ht_path, contig.chr(), 1, contig.length() as i32)
+            .await
+            .map_err(|e| anyhow!("Hail query of {} ({}) failed: {}", ht_path, contig.chr(), e))?;
+        let values = position_maxima(
+            associations
+                .iter()
+                .map(|a| (a.position as u32, neg_log10(a.pvalue)))
+                .filter(|(_, value)| *value >= min_neg_log10_p),
+        );
+        if !values.is_empty() {
+            signal.push((contig, values));
+        }
+    }
+    Ok(signal)
+}
+
+/// -log10(p), with p = 0 mapped to the smallest positive double
+fn neg_log10(pvalue: f64) -> f64 {
+    -pvalue.max(f64::MIN_POSITIVE).log10()
+}
+
+/// Sort by position, keeping the largest value at each position
+fn position_maxima(values: impl Iterator<Item = (u32, f64)>) -> Vec<(u32, f32)> {
+    let mut values: Vec<(u32, f64)> = values.filter(|(_, v)| v.is_finite()).collect();
+    values.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.total_cmp(&a.1)));
+    values.dedup_by_key(|(position, _)| *position);
+    values.into_iter().map(|(p, v)| (p, v as f32)).collect()
+}
+
+fn chrom_sizes() -> HashMap<String, u32> {
+    Contig::all().map(|c| (c.chr().to_string(), c.length())).collect()
+}
+
+/// bigtools drives its writers with a runtime of its own
+fn writer_runtime() -> Result<tokio::runtime::Runtime> {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .build()
+        .context("Failed to start bigtools runtime")
+}
+
+fn write_bigwig(path: &Path, signal: Vec<(Contig, Vec<(u32, f32)>)>) -> Result<()> {
+    let values = signal.into_iter().flat_map(|(contig, values)| {
+        values.into_iter().map(move |(position, value)| {
+            (
+                contig.chr().to_string(),
+                Value {
+                    start: position - 1,
+                    end: position,
+                    value,
+                },
+            )
+        })
+    });
+    let data = BedParserStreamingIterator::wrap_infallible_iter(values, true);
+    BigWigWrite::create_file(path, chrom_sizes())?
+        .write(data, writer_runtime()?)
+        .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+}
+
+fn write_bigbed(path: &Path, bed: &str) -> Result<()> {
+    let entries = bed_entries(bed)?;
+    let data = BedParserStreamingIterator::wrap_infallible_iter(entries.into_iter(), true);
+    let mut writer = BigBedWrite::create_file(path, chrom_sizes())?;
+    writer.autosql = Some(LOCI_AUTOSQL.to_string());
+    writer
+        .write(data, writer_runtime()?)
+        .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Parse BED lines into bigtools entries (chrom, start, end, rest)
+fn bed_entries(bed: &str) -> Result<Vec<(String, BedEntry)>> {
+    bed.lines()
+        .filter(|line| !line.is_empty() && !line.starts_with("track"))
+        .map(|line| {
+            let mut fields = line.splitn(4, '\t');
+            let mut next = || fields.next().with_context(|| format!("Short BED line: {}", line));
+            let chrom = next()?.to_string();
+            let start = next()?.parse()?;
+            let end = next()?.parse()?;
+            let rest = fields.next().unwrap_or_default().to_string();
+            Ok((chrom, BedEntry { start, end, rest }))
+        })
+        .collect()
+}
+
+fn hub_txt(tracks: &[HubTrack], ancestry: &str, email: &str) -> String {
+    let label = match tracks {
+        [track] => format!("AoU {} ({})", track.description, ancestry),
+        _ => format!("AoU all-by-all: {} phenotypes ({})", tracks.len(), ancestry),
+    };
+    format!(
+        "hub axaou_all_by_all\nshortLabel AoU all-by-all\nlongLabel {}\ngenomesFile genomes.txt\nemail {}\n",
+        label, email
+    )
+}
+
+fn track_db(tracks: &[HubTrack], ancestry: &str) -> String {
+    let mut db = String::new();
+    for track in tracks {
+        let short = truncate_label(&track.description, 17);
+        db.push_str(&format!(
+            "track {name}\n\
+             type bigWig\n\
+             bigDataUrl {file}\n\
+             shortLabel {short} p\n\
+             longLabel {description} ({ancestry}): -log10(p)\n\
+             visibility full\n\
+             autoScale on\n\
+             alwaysZero on\n\
+             maxHeightPixels 100:60:16\n\
+             color 30,60,200\n\
+             yLineMark {gws}\n\
+             yLineOnOff on\n\n",
+            name = track.name("signal"),
+            file = track.file(ancestry, "neglog10p.bw"),
+            description = track.description,
+            gws = neg_log10(GENOME_WIDE_PVALUE),
+        ));
+        db.push_str(&format!(
+            "track {name}\n\
+             type bigBed 6\n\
+             bigDataUrl {file}\n\
+             shortLabel {short} loci\n\
+             longLabel {description} ({ancestry}): significant loci\n\
+             visibility pack\n\
+             useScore 1\n\n",
+            name = track.name("loci"),
+            file = track.file(ancestry, "loci.bb"),
+            description = track.description,
+        ));
+    }
+    db
+}
+
+/// Shorten a label to `max` characters for UCSC short labels
+fn truncate_label(label: &str, max: usize) -> String {
+    if label.chars().count() <= max {
+        label.to_string()
+    } else {
+        label.chars().take(max - 1).chain(std::iter::once('…')).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_maxima() {
+        let values = position_maxima(
+            [(200, 1.0), (100, 3.0), (100, 5.0), (300, f64::NAN)].into_iter(),
+        );
+        assert_eq!(values, vec![(100, 5.0), (200, 1.0)]);
+        assert!((neg_log10(1e-8) - 8.0).abs() < 1e-9);
+        assert!(neg_log10(0.0).is_finite());
+    }
+
+    #[test]
+    fn test_bed_entries() {
+        let entries = bed_entries("track name=x\nchr1\t99\t500\tchr1-100-A-G\t8\t.\n").unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, "chr1");
+        assert_eq!((entries[0].1.start, entries[0].1.end), (99, 500));
+        assert_eq!(entries[0].1.rest, "chr1-100-A-G\t8\t.");
+        assert!(bed_entries("chr1\t99\n").is_err());
+    }
+
+    #[test]
+    fn test_track_db() {
+        let tracks = vec![HubTrack {
+            analysis_id: "3013429".to_string(),
+            description: "Low density lipoprotein cholesterol".to_string(),
+        }];
+        let db = track_db(&tracks, "meta");
+        assert!(db.contains("track axaou_3013429_signal\ntype bigWig\n"));
+        assert!(db.contains("bigDataUrl 3013429.meta.neglog10p.bw\n"));
+        assert!(db.contains("shortLabel Low density lipo… p\n"));
+        assert!(db.contains("track axaou_3013429_loci\ntype bigBed 6\nbigDataUrl 3013429.meta.loci.bb\n"));
+        assert!(hub_txt(&tracks, "meta", "").contains("genomesFile genomes.txt\n"));
+    }
+}
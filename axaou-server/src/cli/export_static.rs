@@ -0,0 +1,349 @@
+//! Static JSON snapshots and sitemap for prerendering and archival
+//!
+//! `export-static` builds the same application state as `serve` and sends
+//! requests through the API router in-process, so each snapshot is exactly
+//! what the live endpoint returns. It writes, under `--out` (a directory or
+//! `gs://bucket/prefix`):
+//!
+//! - `phenotype/<analysis_id>/{analysis,overview,top-loci}.json`
+//! - `gene/<gene_id>/{model,phewas}.json`
+//! - `sitemap.xml` with the frontend page of every exported phenotype and
+//!   gene (split into `sitemap-N.xml` files under an index past 50,000 URLs)
+//! - `manifest.json` with the data version, counts and failed requests
+
+use crate::phenotype::manhattan::parse_gcs_uri;
+use anyhow::{bail, Context, Result};
+use axum::{body::Body, http::Request, http::StatusCode, Router};
+use clap::Args;
+use futures::{stream, StreamExt};
+use object_store::gcp::GoogleCloudStorageBuilder;
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tower::ServiceExt;
+use tracing::{info, warn};
+
+/// URLs per sitemap file (sitemaps.org limit)
+const SITEMAP_MAX_URLS: usize = 50_000;
+
+/// Arguments for `export-static`
+#[derive(Debug, Args, Clone)]
+pub struct ExportStaticArgs {
+    /// Output directory, or `gs://bucket/prefix`
+    #[arg(long)]
+    pub out: String,
+
+    /// Public URL of the frontend, used for sitemap entries
+    #[arg(long)]
+    pub site_url: String,
+
+    /// Path to dataset config TOML; defaults to $AXAOU_CONFIG or built-in 414k paths
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Frontend page of a phenotype (`{id}` is replaced)
+    #[arg(long, default_value = "/phenotype/{id}")]
+    pub phenotype_page: String,
+
+    /// Frontend page of a gene (`{id}` is replaced)
+    #[arg(long, default_value = "/gene/{id}")]
+    pub gene_page: String,
+
+    /// Export at most this many phenotypes (default: all)
+    #[arg(long)]
+    pub limit_phenotypes: Option<usize>,
+
+    /// Export at most this many genes (default: all in gene_summary)
+    #[arg(long)]
+    pub limit_genes: Option<usize>,
+
+    /// Requests in flight at once
+    #[arg(long, default_value = "8")]
+    pub concurrency: usize,
+}
+
+/// API requests snapshotted per phenotype: (file name, path template)
+const PHENOTYPE_SNAPSHOTS: &[(&str, &str)] = &[
+    ("analysis.json", "/analyses/{id}"),
+    ("overview.json", "/phenotype/{id}/overview"),
+    ("top-loci.json", "/phenotype/{id}/loci/top"),
+];
+
+/// API requests snapshotted per gene: (file name, path template)
+const GENE_SNAPSHOTS: &[(&str, &str)] = &[
+    ("model.json", "/genes/model/{id}"),
+    ("phewas.json", "/genes/phewas/{id}"),
+];
+
+/// Where snapshots are written
+pub(crate) enum Sink {
+    Dir(PathBuf),
+    Gcs {
+        store: Arc<dyn ObjectStore>,
+        prefix: String,
+    },
+}
+
+impl Sink {
+    pub(crate) fn new(out: &str) -> Result<Self> {
+        if out.starts_with("gs://") {
+            let (bucket, prefix) = parse_gcs_uri(out)
+                .or_else(|| Some((out.strip_prefix("gs://")?.to_string(), String::new())))
+                .context("Invalid GCS output URI")?;
+            let store = GoogleCloudStorageBuilder::new()
+                .with_bucket_name(&bucket)
+                .build()
+                .context("Failed to create GCS client")?;
+            Ok(Sink::Gcs {
+                store: Arc::new(store),
+                prefix: prefix.trim_end_matches('/').to_string(),
+            })
+        } else {
+            Ok(Sink::Dir(PathBuf::from(out)))
+        }
+    }
+
+    pub(crate) async fn put(&self, relative: &str, bytes: Vec<u8>) -> Result<()> {
+        match self {
+            Sink::Dir(dir) => {
+                let path = dir.join(relative);
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                tokio::fs::write(&path, bytes)
+                    .await
+                    .with_context(|| format!("Failed to write {}", path.display()))
+            }
+            Sink::Gcs { store, prefix } => {
+                let location = match prefix.as_str() {
+                    "" => ObjectPath::from(relative),
+                    prefix => ObjectPath::from(format!("{}/{}", prefix, relative)),
+                };
+                store.put(&location, bytes.into()).await?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// One snapshot to take
+struct Snapshot {
+    file: String,
+    api_path: String,
+}
+
+fn snapshots(kind: &str, ids: &[String], templates: &[(&str, &str)]) -> Vec<Snapshot> {
+    ids.iter()
+        .flat_map(|id| {
+            let encoded = url::form_urlencoded::byte_serialize(id.as_bytes()).collect::<String>();
+            templates.iter().map(move |(file, template)| Snapshot {
+                file: format!("{}/{}/{}", kind, encoded, file),
+                api_path: template.replace("{id}", &encoded),
+            })
+        })
+        .collect()
+}
+
+/// `manifest.json`
+#[derive(Debug, Serialize)]
+struct Manifest {
+    generated_at: String,
+    dataset_version: String,
+    data_version: Option<String>,
+    phenotypes: usize,
+    genes: usize,
+    snapshots: usize,
+    failed: Vec<String>,
+}
+
+/// Run `export-static`
+pub async fn run_export_static(args: &ExportStaticArgs) -> Result<()> {
+    let config = crate::config::Config::load(args.config.as_deref())?;
+    let state = crate::build_state(config, None, crate::gene_models::GeneModelsBackendKind::Auto);
+    let sink = Sink::new(&args.out)?;
+
+    let metadata = crate::metadata::load_metadata(&state.clickhouse, &state.executor, &state.config).await?;
+    let mut phenotypes: Vec<String> = metadata
+        .iter()
+        .map(|m| m.analysis_id.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    crate::metadata::install(&state, metadata).await;
+    if let Some(limit) = args.limit_phenotypes {
+        phenotypes.truncate(limit);
+    }
+
+    let mut genes: Vec<String> = state
+        .clickhouse
+        .query("SELECT DISTINCT gene_id FROM gene_summary ORDER BY gene_id")
+        .fetch_all()
+        .await
+        .context("Failed to list genes from gene_summary")?;
+    if let Some(limit) = args.limit_genes {
+        genes.truncate(limit);
+    }
+
+    let mut todo = snapshots("phenotype", &phenotypes, PHENOTYPE_SNAPSHOTS);
+    todo.extend(snapshots("gene", &genes, GENE_SNAPSHOTS));
+    info!(
+        "Exporting {} snapshots for {} phenotypes and {} genes to {}",
+        todo.len(),
+        phenotypes.len(),
+        genes.len(),
+        args.out
+    );
+
+    let app: Router = crate::api_router().with_state(Arc::clone(&state));
+    let total = todo.len();
+    let results: Vec<(String, Result<()>)> = stream::iter(todo)
+        .map(|snapshot| {
+            let app = app.clone();
+            let sink = &sink;
+            async move {
+                let result = match fetch(app, &snapshot.api_path).await {
+                    Ok(bytes) => sink.put(&snapshot.file, bytes).await,
+                    Err(e) => Err(e),
+                };
+                (snapshot.api_path, result)
+            }
+        })
+        .buffer_unordered(args.concurrency.max(1))
+        .collect()
+        .await;
+
+    let mut failed = Vec::new();
+    for (api_path, result) in results {
+        if let Err(e) = result {
+            warn!("{}: {:#}", api_path, e);
+            failed.push(api_path);
+        }
+    }
+    failed.sort();
+
+    let mut pages: Vec<String> = phenotypes
+        .iter()
+        .map(|id| page_url(&args.site_url, &args.phenotype_page, id))
+        .collect();
+    pages.extend(genes.iter().map(|id| page_url(&args.site_url, &args.gene_page, id)));
+    for (file, xml) in sitemap_files(&args.site_url, &pages) {
+        sink.put(&file, xml.into_bytes()).await?;
+    }
+
+    let manifest = Manifest {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        dataset_version: state.config.dataset_version.clone(),
+        data_version: state.data_version.clone(),
+        phenotypes: phenotypes.len(),
+        genes: genes.len(),
+        snapshots: total - failed.len(),
+        failed,
+    };
+    sink.put("manifest.json", serde_json::to_vec_pretty(&manifest)?).await?;
+    info!(
+        "Exported {}/{} snapshots and {} sitemap URLs",
+        manifest.snapshots,
+        total,
+        pages.len()
+    );
+    Ok(())
+}
+
+/// Send one GET through the router and return the body of a 200
+async fn fetch(app: Router, api_path: &str) -> Result<Vec<u8>> {
+    let request = Request::get(api_path).body(Body::empty())?;
+    let response = app.oneshot(request).await?;
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    if status != StatusCode::OK {
+        bail!("HTTP {}: {}", status, String::from_utf8_lossy(&body));
+    }
+    Ok(body.to_vec())
+}
+
+fn page_url(site_url: &str, template: &str, id: &str) -> String {
+    let encoded = url::form_urlencoded::byte_serialize(id.as_bytes()).collect::<String>();
+    format!("{}{}", site_url.trim_end_matches('/'), template.replace("{id}", &encoded))
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Sitemap file names and contents; an index plus numbered files past
+/// [`SITEMAP_MAX_URLS`]
+fn sitemap_files(site_url: &str, pages: &[String]) -> Vec<(String, String)> {
+    let urlset = |urls: &[String]| {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+        );
+        for url in urls {
+            xml.push_str(&format!("  <url><loc>{}</loc></url>\n", escape_xml(url)));
+        }
+        xml.push_str("</urlset>\n");
+        xml
+    };
+
+    if pages.len() <= SITEMAP_MAX_URLS {
+        return vec![("sitemap.xml".to_string(), urlset(pages))];
+    }
+
+    let mut files = Vec::new();
+    let mut index = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for (i, chunk) in pages.chunks(SITEMAP_MAX_URLS).enumerate() {
+        let file = format!("sitemap-{}.xml", i + 1);
+        index.push_str(&format!(
+            "  <sitemap><loc>{}/{}</loc></sitemap>\n",
+            escape_xml(site_url.trim_end_matches('/')),
+            file
+        ));
+        files.push((file, urlset(chunk)));
+    }
+    index.push_str("</sitemapindex>\n");
+    files.insert(0, ("sitemap.xml".to_string(), index));
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_paths() {
+        let todo = snapshots("phenotype", &["height".to_string()], PHENOTYPE_SNAPSHOTS);
+        assert_eq!(todo.len(), PHENOTYPE_SNAPSHOTS.len());
+        assert_eq!(todo[1].file, "phenotype/height/overview.json");
+        assert_eq!(todo[1].api_path, "/phenotype/height/overview");
+        assert_eq!(
+            page_url("https://example.org/", "/gene/{id}", "ENSG00000169174"),
+            "https://example.org/gene/ENSG00000169174"
+        );
+    }
+
+    #[test]
+    fn test_sitemap_files() {
+        let pages = vec!["https://example.org/phenotype/a&b".to_string()];
+        let files = sitemap_files("https://example.org", &pages);
+        assert_eq!(files.len(), 1);
+        assert!(files[0].1.contains("<loc>https://example.org/phenotype/a&amp;b</loc>"));
+
+        let many: Vec<String> = (0..SITEMAP_MAX_URLS + 1)
+            .map(|i| format!("https://example.org/gene/{}", i))
+            .collect();
+        let files = sitemap_files("https://example.org", &many);
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0].0, "sitemap.xml");
+        assert!(files[0].1.contains("<loc>https://example.org/sitemap-2.xml</loc>"));
+    }
+}
@@ -0,0 +1,372 @@
+//! Bulk ingest of gene-level association results
+//!
+//! `ingest gene-associations` walks every `gene_results.ht` listed in a
+//! discovered assets file (see `discover`). Tables are grouped by the
+//! ancestry partition of the serving tables (see `partition_load`); for
+//! every ancestry:
+//! 1. Stage the partition of `gene_associations` and `top_gene_associations`
+//! 2. Per phenotype, export the Hail Table to its own staging table,
+//!    transform it into the staged partitions and record its gene test
+//!    counts in `analysis_test_counts`
+//! 3. Copy the other phenotypes' rows and swap each staged partition in with
+//!    `REPLACE PARTITION`
+//! 4. Drop the staging tables
+//!
+//! Ancestries load concurrently (`--concurrency`); failures are reported at
+//! the end without stopping the remaining loads, and keep the phenotype's
+//! current rows. Rebuild `gene_associations_by_gene` afterwards with
+//! `derive gene-associations-by-gene`.
+
+use super::ingest::{retarget_sql, IngestArgs};
+use super::partition_load::{
+    export_to_staging, group_by_partition, loader_export_args, new_run_id, read_assets,
+    table_name_part, PartitionStage,
+};
+use super::sql::SqlClient;
+use crate::models::{AnalysisAsset, AnalysisAssetType, AnalysisAssets};
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use futures::{stream, StreamExt};
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+const GENE_ASSOCIATIONS_DDL: &str = include_str!("../sql/gene_associations.sql");
+const TOP_GENE_ASSOCIATIONS_DDL: &str = include_str!("../sql/top_gene_associations.sql");
+const GENE_ASSOCIATIONS_TRANSFORM: &str = include_str!("../sql/gene_associations_transform.sql");
+const TOP_GENE_ASSOCIATIONS_TRANSFORM: &str =
+    include_str!("../sql/top_gene_associations_transform.sql");
+const ANALYSIS_TEST_COUNTS_DDL: &str = include_str!("../sql/analysis_test_counts.sql");
+const GENE_TEST_COUNTS_TRANSFORM: &str = include_str!("../sql/gene_test_counts_transform.sql");
+
+/// Arguments for `ingest gene-associations`
+#[derive(Debug, Args, Clone)]
+pub struct GeneAssociationsArgs {
+    /// Discovered assets JSON listing the gene result tables
+    #[arg(long, default_value = "assets.json")]
+    pub assets_file: PathBuf,
+
+    /// Only load these phenotypes (comma-separated analysis IDs)
+    #[arg(long, value_delimiter = ',')]
+    pub phenotypes: Vec<String>,
+
+    /// Only load these ancestry groups (comma-separated, e.g. meta,eur)
+    #[arg(long, value_delimiter = ',')]
+    pub ancestries: Vec<String>,
+
+    /// Ancestry partitions loaded at once
+    #[arg(long, default_value = "4")]
+    pub concurrency: usize,
+
+    /// ClickHouse URL for local operations (DDL, transforms)
+    #[arg(long, default_value = "http://localhost:8123")]
+    pub clickhouse_url: String,
+
+    /// ClickHouse URL for remote/pool workers (used by hail-decoder export)
+    #[arg(long)]
+    pub remote_clickhouse_url: Option<String>,
+
+    /// ClickHouse database name
+    #[arg(long, default_value = "default")]
+    pub database: String,
+
+    /// Path to genohype binary
+    #[arg(long, default_value = "genohype")]
+    pub hail_decoder: String,
+
+    /// Submit exports to a worker pool instead of running locally
+    #[arg(long)]
+    pub pool: Option<String>,
+
+    /// Keep per-table and per-partition staging tables for debugging
+    #[arg(long)]
+    pub keep_staging: bool,
+}
+
+/// One phenotype/ancestry gene_results.ht
+#[derive(Debug, Clone, PartialEq)]
+struct GeneResultTable {
+    phenotype: String,
+    ancestry: String,
+    uri: String,
+}
+
+impl GeneResultTable {
+    fn from_asset(asset: &AnalysisAsset) -> Option<Self> {
+        if asset.asset_type != AnalysisAssetType::Gene {
+            return None;
+        }
+        Some(Self {
+            phenotype: asset.analysis_id.clone(),
+            ancestry: asset.ancestry_group.dir_name().to_lowercase(),
+            uri: asset.uri.clone(),
+        })
+    }
+
+    fn label(&self) -> String {
+        format!("{}/{}", self.phenotype, self.ancestry)
+    }
+
+    /// Export staging table unique to this phenotype/ancestry and run
+    fn staging_table(&self, run_id: &str) -> String {
+        format!(
+            "staging_genes_{}_{}_{}",
+            table_name_part(&self.phenotype),
+            self.ancestry,
+            run_id
+        )
+    }
+}
+
+/// Gene result tables in `assets` matching the filters, in stable order
+fn select_tables(assets: &AnalysisAssets, args: &GeneAssociationsArgs) -> Vec<GeneResultTable> {
+    let mut tables: Vec<GeneResultTable> = assets
+        .assets
+        .iter()
+        .filter_map(GeneResultTable::from_asset)
+        .filter(|t| args.phenotypes.is_empty() || args.phenotypes.contains(&t.phenotype))
+        .filter(|t| {
+            args.ancestries.is_empty()
+                || args
+                    .ancestries
+                    .iter()
+                    .any(|a| a.eq_ignore_ascii_case(&t.ancestry))
+        })
+        .collect();
+    tables.sort_by(|a, b| a.label().cmp(&b.label()));
+    tables.dedup();
+    tables
+}
+
+/// Run `ingest gene-associations`
+pub async fn run_gene_associations(args: &GeneAssociationsArgs) -> Result<()> {
+    let assets = read_assets(&args.assets_file)?;
+    let tables = select_tables(&assets, args);
+    if tables.is_empty() {
+        bail!("No gene result tables match the given filters");
+    }
+    let total = tables.len();
+    let partitions = group_by_partition(tables, |t| t.ancestry.clone());
+    let run_id = new_run_id();
+    info!(
+        "Loading {} gene result tables in {} ancestry partitions ({} at a time, run {})",
+        total,
+        partitions.len(),
+        args.concurrency,
+        run_id
+    );
+
+    let sql = SqlClient::new(&args.clickhouse_url, &args.database);
+    sql.execute(GENE_ASSOCIATIONS_DDL).await?;
+    sql.execute(TOP_GENE_ASSOCIATIONS_DDL).await?;
+    sql.execute(ANALYSIS_TEST_COUNTS_DDL).await?;
+
+    let failures: Vec<String> = stream::iter(partitions)
+        .map(|(ancestry, tables)| {
+            let (sql, run_id) = (sql.clone(), run_id.clone());
+            async move {
+                let labels: Vec<String> = tables.iter().map(GeneResultTable::label).collect();
+                match load_partition(&sql, &run_id, &ancestry, &tables, args).await {
+                    Ok(failed) => failed,
+                    Err(e) => {
+                        warn!("Failed to load partition {}: {:#}", ancestry, e);
+                        labels
+                    }
+                }
+            }
+        })
+        .buffer_unordered(args.concurrency.max(1))
+        .flat_map(stream::iter)
+        .collect()
+        .await;
+
+    info!(
+        "Loaded {} of {} gene result tables",
+        total - failures.len(),
+        total
+    );
+    if !failures.is_empty() {
+        bail!("{} tables failed: {}", failures.len(), failures.join(", "));
+    }
+    info!("Run `derive gene-associations-by-gene` to refresh gene lookups");
+    Ok(())
+}
+
+/// Load the gene result tables of one ancestry and swap the rebuilt
+/// partitions in; returns the labels of the tables that failed
+async fn load_partition(
+    sql: &SqlClient,
+    run_id: &str,
+    ancestry: &str,
+    tables: &[GeneResultTable],
+    args: &GeneAssociationsArgs,
+) -> Result<Vec<String>> {
+    let key = || vec![("ancestry", ancestry.to_string())];
+    let stages = [
+        PartitionStage::create(sql, "gene_associations", run_id, key()).await?,
+        PartitionStage::create(sql, "top_gene_associations", run_id, key()).await?,
+    ];
+    let export_args = loader_export_args(
+        &args.clickhouse_url,
+        &args.database,
+        args.remote_clickhouse_url.as_deref(),
+        &args.hail_decoder,
+        args.pool.as_deref(),
+    );
+
+    let mut loaded = Vec::new();
+    let mut failed = Vec::new();
+    for table in tables {
+        match load_table(sql, table, &stages, run_id, &export_args, args).await {
+            Ok(()) => loaded.push(table.phenotype.clone()),
+            Err(e) => {
+                warn!("Failed to load {}: {:#}", table.label(), e);
+                for stage in &stages {
+                    stage.discard(sql, &table.phenotype).await?;
+                }
+                failed.push(table.label());
+            }
+        }
+    }
+
+    if !loaded.is_empty() {
+        for stage in &stages {
+            let rows = stage.swap(sql, &loaded).await?;
+            info!(
+                "[{}] Swapped in {} ({} rows)",
+                ancestry,
+                stage.table(),
+                rows
+            );
+        }
+    }
+    if !args.keep_staging {
+        for stage in &stages {
+            stage.drop(sql).await?;
+        }
+    }
+    Ok(failed)
+}
+
+/// Export one gene_results.ht and transform it into the staged partitions
+async fn load_table(
+    sql: &SqlClient,
+    table: &GeneResultTable,
+    [genes_stage, top_stage]: &[PartitionStage; 2],
+    run_id: &str,
+    export_args: &IngestArgs,
+    args: &GeneAssociationsArgs,
+) -> Result<()> {
+    let staging = table.staging_table(run_id);
+    let result = async {
+        info!("[{}] Exporting {} -> {}", table.label(), table.uri, staging);
+        export_to_staging(sql, &staging, &table.uri, export_args).await?;
+
+        let rows = sql.row_count(&staging).await?;
+        if rows == 0 {
+            bail!("Export of {} produced no rows", table.uri);
+        }
+
+        let transform = retarget_sql(
+            GENE_ASSOCIATIONS_TRANSFORM,
+            genes_stage.table(),
+            genes_stage.staging(),
+        )
+        .replace("{staging}", &staging);
+        sql.query(&transform)
+            .bind(&table.phenotype)
+            .bind(&table.ancestry)
+            .execute()
+            .await
+            .with_context(|| format!("gene_associations transform failed for {}", table.label()))?;
+
+        let top = retarget_sql(
+            TOP_GENE_ASSOCIATIONS_TRANSFORM,
+            top_stage.table(),
+            top_stage.staging(),
+        )
+        .replace("{staging}", genes_stage.staging());
+        sql.query(&top)
+            .bind(&table.phenotype)
+            .execute()
+            .await
+            .with_context(|| {
+                format!(
+                    "top_gene_associations transform failed for {}",
+                    table.label()
+                )
+            })?;
+
+        // Test counts replace the previous row, so they go in last
+        sql.query(&GENE_TEST_COUNTS_TRANSFORM.replace("{staging}", &staging))
+            .bind(&table.phenotype)
+            .bind(&table.ancestry)
+            .execute()
+            .await
+            .with_context(|| format!("Failed to record test counts for {}", table.label()))?;
+        Ok(rows)
+    }
+    .await;
+
+    if !args.keep_staging {
+        sql.execute_statement(&format!("DROP TABLE IF EXISTS {}", staging))
+            .await?;
+    }
+    let rows = result?;
+    info!("[{}] Staged {} gene results", table.label(), rows);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AncestryGroup, SequencingType};
+    use crate::test_support::factories::asset;
+
+    #[test]
+    fn test_select_tables() {
+        let assets = AnalysisAssets {
+            assets: vec![
+                asset("height", AncestryGroup::Meta, AnalysisAssetType::Gene, None),
+                asset("height", AncestryGroup::Eur, AnalysisAssetType::Gene, None),
+                asset(
+                    "height",
+                    AncestryGroup::Meta,
+                    AnalysisAssetType::Variant,
+                    Some(SequencingType::Exomes),
+                ),
+                asset(
+                    "icd10-E11",
+                    AncestryGroup::Afr,
+                    AnalysisAssetType::Gene,
+                    None,
+                ),
+            ],
+            ..Default::default()
+        };
+        let args = GeneAssociationsArgs {
+            assets_file: PathBuf::from("assets.json"),
+            phenotypes: Vec::new(),
+            ancestries: vec!["META".to_string(), "afr".to_string()],
+            concurrency: 4,
+            clickhouse_url: String::new(),
+            remote_clickhouse_url: None,
+            database: "default".to_string(),
+            hail_decoder: "genohype".to_string(),
+            pool: None,
+            keep_staging: false,
+        };
+
+        let tables = select_tables(&assets, &args);
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].label(), "height/meta");
+        assert_eq!(
+            tables[1].staging_table("1a2b3c4d"),
+            "staging_genes_icd10_e11_afr_1a2b3c4d"
+        );
+
+        // One partition per ancestry
+        let partitions = group_by_partition(tables, |t| t.ancestry.clone());
+        assert_eq!(partitions.len(), 2);
+        assert_eq!(partitions[1].0, "afr");
+    }
+}
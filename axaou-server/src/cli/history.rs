@@ -0,0 +1,240 @@
+//! Ingest provenance: the `ingest_runs` table
+//!
+//! Every `ingest <table>` run is recorded with its source URI, row count,
+//! duration, tool versions and operator, so it is always possible to tell
+//! which data freeze populated which table. Listed by `ingest history` and
+//! `GET /api/admin/ingest-runs`.
+
+use super::sql::SqlClient;
+use crate::webhooks::{self, Event, WebhookConfig};
+use anyhow::{Context, Result};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::time::SystemTime;
+use tracing::warn;
+
+const INGEST_RUNS_DDL: &str = include_str!("../sql/ingest_runs.sql");
+
+/// Columns selected when listing runs
+pub const INGEST_RUN_COLUMNS: &str = "run_id, table_name, source_uri, init_strategy, status, \
+    row_count, duration_secs, hail_decoder_version, git_sha, operator, error_message, \
+    toString(started_at) AS started_at, toString(finished_at) AS finished_at";
+
+/// One row of `ingest_runs`
+#[derive(Debug, Clone, Serialize, Deserialize, clickhouse::Row)]
+pub struct IngestRun {
+    pub run_id: String,
+    pub table_name: String,
+    pub source_uri: String,
+    pub init_strategy: String,
+    /// "succeeded" or "failed"
+    pub status: String,
+    pub row_count: u64,
+    pub duration_secs: f64,
+    pub hail_decoder_version: String,
+    pub git_sha: String,
+    pub operator: String,
+    pub error_message: Option<String>,
+    pub started_at: String,
+    pub finished_at: String,
+}
+
+/// Arguments for `ingest history`
+#[derive(Debug, Args, Clone)]
+pub struct HistoryArgs {
+    /// ClickHouse URL
+    #[arg(long, default_value = "http://localhost:8123")]
+    pub clickhouse_url: String,
+
+    /// ClickHouse database name
+    #[arg(long, default_value = "default")]
+    pub database: String,
+
+    /// Only show runs for this table
+    #[arg(long)]
+    pub table: Option<String>,
+
+    /// Maximum number of runs to show
+    #[arg(long, default_value = "20")]
+    pub limit: u64,
+
+    /// Print JSON instead of a table
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Details of a run, filled in as the load progresses
+pub struct RunRecord {
+    pub run_id: String,
+    pub table_name: String,
+    pub source_uri: String,
+    pub init_strategy: String,
+    pub hail_decoder_version: String,
+    pub operator: String,
+    pub started_at: SystemTime,
+}
+
+impl RunRecord {
+    /// Write the finished run to `ingest_runs`
+    pub async fn save(&self, sql: &SqlClient, result: &Result<u64>) -> Result<()> {
+        sql.execute(INGEST_RUNS_DDL).await?;
+
+        let duration = self.started_at.elapsed().unwrap_or_default();
+        let started_at = self
+            .started_at
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as u32;
+        let (status, row_count, error) = match result {
+            Ok(rows) => ("succeeded", *rows, None),
+            Err(e) => ("failed", 0, Some(format!("{:#}", e))),
+        };
+
+        sql.query(
+            "INSERT INTO ingest_runs (run_id, table_name, source_uri, init_strategy, status, \
+             row_count, duration_secs, hail_decoder_version, git_sha, operator, error_message, \
+             started_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, toDateTime(?))",
+        )
+        .bind(&self.run_id)
+        .bind(&self.table_name)
+        .bind(&self.source_uri)
+        .bind(&self.init_strategy)
+        .bind(status)
+        .bind(row_count)
+        .bind(duration.as_secs_f64())
+        .bind(&self.hail_decoder_version)
+        .bind(git_sha())
+        .bind(&self.operator)
+        .bind(error)
+        .bind(started_at)
+        .execute()
+        .await
+        .context("Failed to record ingest run")
+    }
+
+    /// Record the finished run and announce it to the configured webhooks;
+    /// failures to record are logged, not returned
+    pub async fn finish(&self, sql: &SqlClient, result: &Result<u64>) {
+        if let Err(e) = self.save(sql, result).await {
+            warn!("Failed to record ingest run {}: {:#}", self.run_id, e);
+        }
+        let (status, rows, error) = match result {
+            Ok(rows) => ("succeeded", *rows, None),
+            Err(e) => ("failed", 0, Some(format!("{:#}", e))),
+        };
+        let event = Event::IngestFinished {
+            run_id: self.run_id.clone(),
+            table: self.table_name.clone(),
+            source_uri: self.source_uri.clone(),
+            status: status.to_string(),
+            rows,
+            duration_secs: self.started_at.elapsed().unwrap_or_default().as_secs_f64(),
+            error,
+        };
+        webhooks::send(&WebhookConfig::from_environment(), event).await;
+    }
+}
+
+/// Version reported by the hail-decoder binary, or "unknown"
+pub fn hail_decoder_version(binary: &str) -> String {
+    Command::new(binary)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Git commit of this build: `GIT_SHA` at compile time or runtime, else the
+/// checkout the binary runs from
+pub fn git_sha() -> String {
+    if let Some(sha) = option_env!("GIT_SHA") {
+        return sha.to_string();
+    }
+    if let Ok(sha) = std::env::var("GIT_SHA") {
+        return sha;
+    }
+    Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Operator name for runs that don't pass `--operator`
+pub fn default_operator() -> String {
+    std::env::var("USER").unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// SQL listing recent runs, optionally for one table (bind the table first,
+/// then the limit)
+pub fn history_query(table: Option<&str>) -> String {
+    format!(
+        "SELECT {} FROM ingest_runs {} ORDER BY started_at DESC LIMIT ?",
+        INGEST_RUN_COLUMNS,
+        if table.is_some() { "WHERE table_name = ?" } else { "" }
+    )
+}
+
+/// Run `ingest history`
+pub async fn show_history(args: &HistoryArgs) -> Result<()> {
+    let sql = SqlClient::new(&args.clickhouse_url, &args.database);
+    sql.execute(INGEST_RUNS_DDL).await?;
+
+    let mut query = sql.query(&history_query(args.table.as_deref()));
+    if let Some(table) = &args.table {
+        query = query.bind(table);
+    }
+    let runs = query
+        .bind(args.limit)
+        .fetch_all::<IngestRun>()
+        .await
+        .context("Failed to read ingest history")?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&runs)?);
+        return Ok(());
+    }
+
+    println!("\n=== Ingest History ===\n");
+    for run in &runs {
+        println!(
+            "  {}  {:<22} {:<9} {:>14} rows  {:>8.0}s  {:<8} {} ({}, {})",
+            run.started_at,
+            run.table_name,
+            run.status,
+            super::ingest::format_number(run.row_count),
+            run.duration_secs,
+            run.init_strategy,
+            run.source_uri,
+            run.operator,
+            run.git_sha
+        );
+        if let Some(error) = &run.error_message {
+            println!("      error: {}", error.lines().next().unwrap_or_default());
+        }
+    }
+    if runs.is_empty() {
+        println!("  (no runs recorded)");
+    }
+    println!();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_query() {
+        assert!(!history_query(None).contains("WHERE"));
+        let sql = history_query(Some("gene_models"));
+        assert!(sql.contains("WHERE table_name = ?"));
+        assert!(sql.ends_with("LIMIT ?"));
+    }
+}
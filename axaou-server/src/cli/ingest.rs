@@ -2,12 +2,33 @@
 //!
 //! Orchestrates the ETL pipeline:
 //! 1. Create/replace target table
-//! 2. Load raw data to staging table via hail-decoder
+//! 2. Load raw data to staging table via hail-decoder, in checkpointed
+//!    chunks of partitions (see [`super::checkpoint`])
 //! 3. Transform staging -> target using SQL
 //! 4. Drop staging table
-
+//!
+//! If the export fails midway, re-running with `--resume` keeps the chunks
+//! already exported and exports only the partitions not yet checkpointed.
+
+use super::checkpoint::{pending_chunks, Checkpoints};
+use super::gene_associations::{run_gene_associations, GeneAssociationsArgs};
+use super::history::{default_operator, hail_decoder_version, show_history, HistoryArgs, RunRecord};
+use super::known_hits::{run_known_hits, KnownHitsArgs};
+use super::recombination::{run_recombination_rates, RecombinationArgs};
+use super::sql::SqlClient;
+use super::validate::{run_validate, ValidateArgs};
+use super::variant_results::{run_variant_results, VariantResultsArgs};
+use crate::phenotype::manhattan::parse_gcs_uri;
 use anyhow::{bail, Context, Result};
 use clap::{Args, Subcommand};
+use flate2::read::GzDecoder;
+use object_store::gcp::GoogleCloudStorageBuilder;
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::io::Read;
+use std::ops::Range;
 use std::process::Command;
 use tracing::{info, warn};
 
@@ -20,6 +41,12 @@ const GENE_MODELS_DDL: &str = include_str!("../sql/gene_models.sql");
 const GENE_MODELS_TRANSFORM: &str = include_str!("../sql/gene_models_transform.sql");
 const ANALYSIS_METADATA_DDL: &str = include_str!("../sql/analysis_metadata.sql");
 const ANALYSIS_METADATA_TRANSFORM: &str = include_str!("../sql/analysis_metadata_transform.sql");
+const ANALYSIS_CATEGORIES_POPULATE: &str = include_str!("../sql/analysis_categories_populate.sql");
+const GENETIC_CORRELATIONS_DDL: &str = include_str!("../sql/genetic_correlations.sql");
+const GENETIC_CORRELATIONS_TRANSFORM: &str =
+    include_str!("../sql/genetic_correlations_transform.sql");
+const LD_PAIRS_DDL: &str = include_str!("../sql/ld_pairs.sql");
+const LD_PAIRS_TRANSFORM: &str = include_str!("../sql/ld_pairs_transform.sql");
 
 /// Default source paths for each table
 const DEFAULT_EXOME_ANNOTATIONS_PATH: &str =
@@ -30,6 +57,9 @@ const DEFAULT_GENE_MODELS_PATH: &str =
     "gs://axaou-browser-common/reference-data/genes_grch38_annotated_6.ht";
 const DEFAULT_ANALYSIS_METADATA_PATH: &str =
     "gs://aou_results/414k/utils/aou_phenotype_meta_info.ht";
+const DEFAULT_GENETIC_CORRELATIONS_PATH: &str =
+    "gs://aou_results/414k/utils/aou_phenotype_genetic_correlations.ht";
+const DEFAULT_LD_PAIRS_PATH: &str = "gs://aou_results/414k/utils/aou_ld_pairs.ht";
 
 /// Table configuration
 #[derive(Debug, Clone)]
@@ -39,6 +69,8 @@ struct TableConfig {
     default_path: &'static str,
     ddl_sql: &'static str,
     transform_sql: &'static str,
+    /// Runs against the live table once the load is complete
+    post_load_sql: Option<&'static str>,
 }
 
 impl TableConfig {
@@ -49,6 +81,7 @@ impl TableConfig {
             default_path: DEFAULT_EXOME_ANNOTATIONS_PATH,
             ddl_sql: EXOME_ANNOTATIONS_DDL,
             transform_sql: EXOME_ANNOTATIONS_TRANSFORM,
+            post_load_sql: None,
         }
     }
 
@@ -59,6 +92,7 @@ impl TableConfig {
             default_path: DEFAULT_GENOME_ANNOTATIONS_PATH,
             ddl_sql: GENOME_ANNOTATIONS_DDL,
             transform_sql: GENOME_ANNOTATIONS_TRANSFORM,
+            post_load_sql: None,
         }
     }
 
@@ -69,6 +103,7 @@ impl TableConfig {
             default_path: DEFAULT_GENE_MODELS_PATH,
             ddl_sql: GENE_MODELS_DDL,
             transform_sql: GENE_MODELS_TRANSFORM,
+            post_load_sql: None,
         }
     }
 
@@ -79,6 +114,54 @@ impl TableConfig {
             default_path: DEFAULT_ANALYSIS_METADATA_PATH,
             ddl_sql: ANALYSIS_METADATA_DDL,
             transform_sql: ANALYSIS_METADATA_TRANSFORM,
+            post_load_sql: Some(ANALYSIS_CATEGORIES_POPULATE),
+        }
+    }
+
+    fn genetic_correlations() -> Self {
+        Self {
+            name: "genetic_correlations",
+            staging_name: "staging_genetic_correlations_raw",
+            default_path: DEFAULT_GENETIC_CORRELATIONS_PATH,
+            ddl_sql: GENETIC_CORRELATIONS_DDL,
+            transform_sql: GENETIC_CORRELATIONS_TRANSFORM,
+            post_load_sql: None,
+        }
+    }
+
+    fn ld_pairs() -> Self {
+        Self {
+            name: "ld_pairs",
+            staging_name: "staging_ld_pairs_raw",
+            default_path: DEFAULT_LD_PAIRS_PATH,
+            ddl_sql: LD_PAIRS_DDL,
+            transform_sql: LD_PAIRS_TRANSFORM,
+            post_load_sql: None,
+        }
+    }
+}
+
+/// Tables that can be loaded by the ingest pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestTable {
+    ExomeAnnotations,
+    GenomeAnnotations,
+    GeneModels,
+    AnalysisMetadata,
+    GeneticCorrelations,
+    LdPairs,
+}
+
+impl IngestTable {
+    fn config(self) -> TableConfig {
+        match self {
+            IngestTable::ExomeAnnotations => TableConfig::exome_annotations(),
+            IngestTable::GenomeAnnotations => TableConfig::genome_annotations(),
+            IngestTable::GeneModels => TableConfig::gene_models(),
+            IngestTable::AnalysisMetadata => TableConfig::analysis_metadata(),
+            IngestTable::GeneticCorrelations => TableConfig::genetic_correlations(),
+            IngestTable::LdPairs => TableConfig::ld_pairs(),
         }
     }
 }
@@ -98,9 +181,33 @@ pub enum IngestCommand {
     /// Load analysis metadata (phenotype info)
     AnalysisMetadata(IngestArgs),
 
+    /// Load pairwise phenotype genetic correlations (LDSC rg)
+    GeneticCorrelations(IngestArgs),
+
+    /// Load precomputed pairwise LD (r²) per ancestry
+    LdPairs(IngestArgs),
+
     /// Load all tables
     All(IngestArgs),
 
+    /// Load gene burden results from every gene_results.ht into gene_associations
+    GeneAssociations(GeneAssociationsArgs),
+
+    /// Load per-phenotype variant results into significant_variants and loci_variants
+    VariantResults(VariantResultsArgs),
+
+    /// Load previously reported associations (GWAS Catalog TSV) into known_associations
+    KnownHits(KnownHitsArgs),
+
+    /// Load a recombination rate map (genetic map TSV) into recombination_rates
+    RecombinationRates(RecombinationArgs),
+
+    /// Run post-ingest schema and sanity checks and emit a JSON report
+    Validate(ValidateArgs),
+
+    /// List recorded ingest runs (source, row count, duration, operator)
+    History(HistoryArgs),
+
     /// Show row counts for all managed tables
     Status {
         /// ClickHouse URL
@@ -162,14 +269,52 @@ pub struct IngestArgs {
     /// Batch size for pool workers (partitions per worker assignment)
     #[arg(long)]
     pub batch_size: Option<u32>,
+
+    /// Skip partitions already loaded by a previous, interrupted run of the
+    /// same table and input
+    #[arg(long)]
+    pub resume: bool,
+
+    /// Partitions exported per checkpointed chunk
+    #[arg(long, default_value = "64")]
+    pub checkpoint_partitions: u32,
+
+    /// Name recorded in the ingest history (default: $USER)
+    #[arg(long)]
+    pub operator: Option<String>,
+}
+
+impl IngestArgs {
+    /// Arguments matching the CLI defaults, for ingest runs started from the API
+    pub fn new(clickhouse_url: String, database: String) -> Self {
+        Self {
+            clickhouse_url,
+            remote_clickhouse_url: None,
+            init_strategy: InitStrategy::default(),
+            input: None,
+            limit: None,
+            keep_staging: false,
+            hail_decoder: "genohype".to_string(),
+            database,
+            pool: None,
+            force: false,
+            redeploy_binary: false,
+            batch_size: None,
+            resume: false,
+            checkpoint_partitions: 64,
+            operator: None,
+        }
+    }
 }
 
 /// Initialization strategy for table loading
-#[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
+#[derive(Debug, Clone, Copy, Default, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum InitStrategy {
     /// Create table if it doesn't exist, fail if it does
     Create,
-    /// Drop and recreate table
+    /// Load into `{table}_new` and swap it in once validated, so the live
+    /// table keeps serving during the load
     #[default]
     Replace,
     /// Append to existing table
@@ -195,6 +340,14 @@ pub async fn run_ingest(command: IngestCommand) -> Result<()> {
             let config = TableConfig::analysis_metadata();
             orchestrate_table_load(&config, &args).await?;
         }
+        IngestCommand::GeneticCorrelations(args) => {
+            let config = TableConfig::genetic_correlations();
+            orchestrate_table_load(&config, &args).await?;
+        }
+        IngestCommand::LdPairs(args) => {
+            let config = TableConfig::ld_pairs();
+            orchestrate_table_load(&config, &args).await?;
+        }
         IngestCommand::All(args) => {
             info!("Loading all tables...");
 
@@ -212,6 +365,24 @@ pub async fn run_ingest(command: IngestCommand) -> Result<()> {
                 }
             }
         }
+        IngestCommand::GeneAssociations(args) => {
+            run_gene_associations(&args).await?;
+        }
+        IngestCommand::VariantResults(args) => {
+            run_variant_results(&args).await?;
+        }
+        IngestCommand::KnownHits(args) => {
+            run_known_hits(&args).await?;
+        }
+        IngestCommand::RecombinationRates(args) => {
+            run_recombination_rates(&args).await?;
+        }
+        IngestCommand::Validate(args) => {
+            run_validate(&args).await?;
+        }
+        IngestCommand::History(args) => {
+            show_history(&args).await?;
+        }
         IngestCommand::Status { clickhouse_url } => {
             show_status(&clickhouse_url).await?;
         }
@@ -220,54 +391,145 @@ pub async fn run_ingest(command: IngestCommand) -> Result<()> {
     Ok(())
 }
 
-/// Orchestrate the full ETL pipeline for a single table
-async fn orchestrate_table_load(config: &TableConfig, args: &IngestArgs) -> Result<()> {
+/// Load one table and return its final row count
+///
+/// Runs the same pipeline as `ingest <table>`; used by admin ingest tasks.
+pub async fn ingest_table(table: IngestTable, args: &IngestArgs) -> Result<u64> {
+    orchestrate_table_load(&table.config(), args).await
+}
+
+/// Orchestrate the full ETL pipeline for a single table and record the run
+/// in `ingest_runs`
+async fn orchestrate_table_load(config: &TableConfig, args: &IngestArgs) -> Result<u64> {
     let input_path = args
         .input
         .as_deref()
         .unwrap_or(config.default_path);
+    let sql = SqlClient::new(&args.clickhouse_url, &args.database);
+
+    let record = RunRecord {
+        run_id: uuid::Uuid::new_v4().to_string(),
+        table_name: config.name.to_string(),
+        source_uri: input_path.to_string(),
+        init_strategy: format!("{:?}", args.init_strategy).to_lowercase(),
+        hail_decoder_version: hail_decoder_version(&args.hail_decoder),
+        operator: args.operator.clone().unwrap_or_else(default_operator),
+        started_at: std::time::SystemTime::now(),
+    };
 
+    let result = load_table(&sql, config, args, input_path).await;
+    record.finish(&sql, &result).await;
+    result
+}
+
+/// Run the ETL steps for a single table
+async fn load_table(
+    sql: &SqlClient,
+    config: &TableConfig,
+    args: &IngestArgs,
+    input_path: &str,
+) -> Result<u64> {
     info!(
         "Loading {} from {} -> {}",
         config.name, input_path, args.clickhouse_url
     );
+    let checkpoints = Checkpoints::new(sql, config.staging_name, input_path);
+    let completed = if args.resume {
+        checkpoints.completed().await?
+    } else {
+        BTreeSet::new()
+    };
+
+    // Replace loads into a shadow table that is swapped in at the end
+    let load_table = match args.init_strategy {
+        InitStrategy::Replace => shadow_table_name(config.name),
+        InitStrategy::Create | InitStrategy::Append => config.name.to_string(),
+    };
 
     // Step 1: Prepare target table based on init strategy
-    info!("Step 1: Preparing target table '{}'...", config.name);
-    prepare_target_table(config, args).await?;
+    info!("Step 1: Preparing target table '{}'...", load_table);
+    prepare_target_table(sql, config, args, &load_table).await?;
 
-    // Step 2: Drop old staging table if exists
-    info!(
-        "Step 2: Dropping staging table '{}' if exists...",
-        config.staging_name
-    );
-    execute_clickhouse_sql(
-        &args.clickhouse_url,
-        &args.database,
-        &format!("DROP TABLE IF EXISTS {}", config.staging_name),
-    )
-    .await?;
+    // Step 2: Drop old staging table if exists (kept when resuming)
+    if completed.is_empty() {
+        info!(
+            "Step 2: Dropping staging table '{}' if exists...",
+            config.staging_name
+        );
+        sql.execute_statement(&format!("DROP TABLE IF EXISTS {}", config.staging_name))
+            .await?;
+        checkpoints.clear().await?;
+    } else {
+        info!(
+            "Step 2: Resuming with {} partitions already exported for '{}'",
+            completed.len(),
+            config.staging_name
+        );
+    }
 
     // Step 3: Load raw data to staging via hail-decoder
     info!(
         "Step 3: Loading raw data to staging table '{}'...",
         config.staging_name
     );
-    run_hail_decoder_export(config, args, input_path)?;
+    if args.limit.is_some() {
+        // Test loads take the first rows of the table; nothing to checkpoint
+        run_hail_decoder_export(config.staging_name, args, input_path, None)?;
+    } else {
+        export_checkpointed(sql, &checkpoints, config, args, input_path, &completed).await?;
+    }
 
     // Step 4: Transform staging -> target
-    info!("Step 4: Transforming staging -> target...");
-    execute_clickhouse_sql(&args.clickhouse_url, &args.database, config.transform_sql).await?;
+    info!("Step 4: Transforming staging -> '{}'...", load_table);
+    sql.execute(&retarget_sql(config.transform_sql, config.name, &load_table))
+        .await?;
 
     // Step 5: Verify row counts
     info!("Step 5: Verifying row counts...");
-    let staging_count = get_row_count(&args.clickhouse_url, &args.database, config.staging_name).await?;
-    let target_count = get_row_count(&args.clickhouse_url, &args.database, config.name).await?;
+    let staging_count = sql.row_count(config.staging_name).await?;
+    let target_count = sql.row_count(&load_table).await?;
     info!(
         "  Staging table '{}': {} rows",
         config.staging_name, staging_count
     );
-    info!("  Target table '{}': {} rows", config.name, target_count);
+    info!("  Target table '{}': {} rows", load_table, target_count);
+
+    if load_table != config.name {
+        if target_count == 0 {
+            bail!(
+                "'{}' is empty after transform; leaving live table '{}' untouched",
+                load_table,
+                config.name
+            );
+        }
+        match source_row_count(input_path).await? {
+            Some(source_count) => {
+                let expected = args.limit.map_or(source_count, |limit| limit.min(source_count));
+                info!("  Source '{}': {} rows", input_path, source_count);
+                if staging_count != expected {
+                    bail!(
+                        "Staging table '{}' has {} rows but the source has {}; \
+                         leaving live table '{}' untouched",
+                        config.staging_name,
+                        staging_count,
+                        expected,
+                        config.name
+                    );
+                }
+            }
+            None => warn!(
+                "  No partition counts in {}; swapping without a source row count check",
+                input_path
+            ),
+        }
+        info!("  Swapping '{}' into '{}'...", load_table, config.name);
+        swap_tables(sql, config, &load_table).await?;
+    }
+
+    if let Some(post_load_sql) = config.post_load_sql {
+        info!("  Running post-load SQL for '{}'...", config.name);
+        sql.execute(post_load_sql).await?;
+    }
 
     // Step 6: Drop staging table (unless --keep-staging)
     if args.keep_staging {
@@ -277,126 +539,212 @@ async fn orchestrate_table_load(config: &TableConfig, args: &IngestArgs) -> Resu
         );
     } else {
         info!("Step 6: Dropping staging table '{}'...", config.staging_name);
-        execute_clickhouse_sql(
-            &args.clickhouse_url,
-            &args.database,
-            &format!("DROP TABLE IF EXISTS {}", config.staging_name),
-        )
-        .await?;
+        sql.execute_statement(&format!("DROP TABLE IF EXISTS {}", config.staging_name))
+            .await?;
     }
 
+    // Staging has been consumed; the next run starts fresh
+    checkpoints.clear().await?;
+
     info!("Successfully loaded {} ({} rows)", config.name, target_count);
-    Ok(())
+    Ok(target_count)
 }
 
 /// Prepare the target table based on init strategy
-async fn prepare_target_table(config: &TableConfig, args: &IngestArgs) -> Result<()> {
+async fn prepare_target_table(
+    sql: &SqlClient,
+    config: &TableConfig,
+    args: &IngestArgs,
+    load_table: &str,
+) -> Result<()> {
     match args.init_strategy {
         InitStrategy::Create => {
             // Just run DDL - it has IF NOT EXISTS
-            execute_clickhouse_sql(&args.clickhouse_url, &args.database, config.ddl_sql).await?;
+            sql.execute(config.ddl_sql).await?;
         }
         InitStrategy::Replace => {
-            // Drop and recreate
-            execute_clickhouse_sql(
-                &args.clickhouse_url,
-                &args.database,
-                &format!("DROP TABLE IF EXISTS {}", config.name),
-            )
-            .await?;
-            execute_clickhouse_sql(&args.clickhouse_url, &args.database, config.ddl_sql).await?;
+            // Fresh shadow table; the live table is not touched
+            sql.execute_statement(&format!("DROP TABLE IF EXISTS {}", load_table))
+                .await?;
+            sql.execute(&retarget_sql(config.ddl_sql, config.name, load_table))
+                .await?;
         }
         InitStrategy::Append => {
             // Ensure table exists, don't drop
-            execute_clickhouse_sql(&args.clickhouse_url, &args.database, config.ddl_sql).await?;
+            sql.execute(config.ddl_sql).await?;
         }
     }
     Ok(())
 }
 
-/// Execute SQL against ClickHouse using curl
-/// Handles multi-statement SQL by splitting on semicolons
-async fn execute_clickhouse_sql(url: &str, database: &str, sql: &str) -> Result<()> {
-    // Split SQL into individual statements
-    let statements = split_sql_statements(sql);
+fn shadow_table_name(table: &str) -> String {
+    format!("{}_new", table)
+}
 
-    for statement in &statements {
-        execute_single_sql(url, database, statement).await?;
+/// Atomically replace the live table with the loaded shadow table, then drop
+/// the previous data
+///
+/// Uses `EXCHANGE TABLES` (Atomic databases); falls back to a multi-table
+/// `RENAME`, which ClickHouse also applies atomically.
+async fn swap_tables(sql: &SqlClient, config: &TableConfig, shadow: &str) -> Result<()> {
+    // First load: make sure there is a live table to exchange with
+    sql.execute(config.ddl_sql).await?;
+
+    let exchange = format!("EXCHANGE TABLES {} AND {}", config.name, shadow);
+    if let Err(e) = sql.execute_statement(&exchange).await {
+        warn!("EXCHANGE TABLES failed ({:#}); falling back to RENAME", e);
+        let old = format!("{}_old", config.name);
+        sql.execute_statement(&format!("DROP TABLE IF EXISTS {}", old))
+            .await?;
+        sql.execute_statement(&format!(
+            "RENAME TABLE {live} TO {old}, {shadow} TO {live}",
+            live = config.name,
+            old = old,
+            shadow = shadow
+        ))
+        .await?;
+        return sql
+            .execute_statement(&format!("DROP TABLE IF EXISTS {}", old))
+            .await;
     }
 
-    Ok(())
+    // After the exchange the shadow name holds the previous data
+    sql.execute_statement(&format!("DROP TABLE IF EXISTS {}", shadow))
+        .await
+}
+
+/// Point DDL/transform SQL written for `table` at `target` instead
+///
+/// Only whole identifiers are replaced, so e.g. `staging_{table}_raw` is left
+/// alone.
+pub(super) fn retarget_sql(sql: &str, table: &str, target: &str) -> String {
+    if table == target {
+        return sql.to_string();
+    }
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut out = String::with_capacity(sql.len());
+    let mut rest = sql;
+    while let Some(pos) = rest.find(table) {
+        let before = rest[..pos].chars().next_back();
+        let after = rest[pos + table.len()..].chars().next();
+        out.push_str(&rest[..pos]);
+        if before.is_some_and(is_ident) || after.is_some_and(is_ident) {
+            out.push_str(table);
+        } else {
+            out.push_str(target);
+        }
+        rest = &rest[pos + table.len()..];
+    }
+    out.push_str(rest);
+    out
 }
 
-/// Split SQL text into individual statements by semicolons
-fn split_sql_statements(sql: &str) -> Vec<String> {
-    // Simple approach: split on semicolons, filter out empty/comment-only chunks
-    sql.split(';')
-        .map(|s| s.trim())
-        .filter(|s| {
-            // Keep non-empty statements that aren't just comments
-            !s.is_empty() && !s.lines().all(|line| {
-                let trimmed = line.trim();
-                trimmed.is_empty() || trimmed.starts_with("--")
-            })
-        })
-        .map(|s| s.to_string())
-        .collect()
+/// Number of partitions in a Hail Table
+async fn partition_count(input_path: &str) -> Result<u32> {
+    let path = input_path.to_string();
+    let count = tokio::task::spawn_blocking(move || {
+        genohype_core::query::QueryEngine::open_path(&path).map(|engine| engine.num_partitions())
+    })
+    .await?
+    .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", input_path, e))?;
+    Ok(count as u32)
 }
 
-/// Execute a single SQL statement
-async fn execute_single_sql(url: &str, database: &str, sql: &str) -> Result<()> {
-    let full_url = format!("{}/?database={}", url, database);
-
-    let output = Command::new("curl")
-        .arg("-sS")
-        .arg("--fail-with-body")
-        .arg(&full_url)
-        .arg("-d")
-        .arg(sql)
-        .output()
-        .context("Failed to execute curl command")?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        bail!(
-            "ClickHouse SQL failed:\nSQL: {}\nstderr: {}\nstdout: {}",
-            sql.chars().take(200).collect::<String>(),
-            stderr,
-            stdout
-        );
-    }
+/// Number of rows in a Hail Table, from the partition counts in its
+/// `metadata.json.gz` (local path or `gs://`); None when the table doesn't
+/// record them
+async fn source_row_count(input_path: &str) -> Result<Option<u64>> {
+    let path = format!("{}/metadata.json.gz", input_path.trim_end_matches('/'));
+    let compressed = if let Some((bucket, object)) = parse_gcs_uri(&path) {
+        let store = GoogleCloudStorageBuilder::new()
+            .with_bucket_name(&bucket)
+            .build()
+            .context("Failed to create GCS client")?;
+        store
+            .get(&ObjectPath::from(object))
+            .await?
+            .bytes()
+            .await?
+            .to_vec()
+    } else {
+        tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("Failed to read {}", path))?
+    };
+    let mut json = String::new();
+    GzDecoder::new(compressed.as_slice())
+        .read_to_string(&mut json)
+        .with_context(|| format!("Failed to decompress {}", path))?;
+    let metadata: serde_json::Value =
+        serde_json::from_str(&json).with_context(|| format!("Failed to parse {}", path))?;
+    Ok(partition_counts_total(&metadata))
+}
 
-    Ok(())
+/// Sum of `components.partition_counts.counts` in Hail Table metadata
+fn partition_counts_total(metadata: &serde_json::Value) -> Option<u64> {
+    metadata
+        .pointer("/components/partition_counts/counts")?
+        .as_array()?
+        .iter()
+        .map(serde_json::Value::as_u64)
+        .sum()
 }
 
-/// Get row count from a table
-async fn get_row_count(url: &str, database: &str, table: &str) -> Result<u64> {
-    let full_url = format!("{}/?database={}", url, database);
-    let sql = format!("SELECT count() FROM {}", table);
-
-    let output = Command::new("curl")
-        .arg("-sS")
-        .arg(&full_url)
-        .arg("-d")
-        .arg(&sql)
-        .output()
-        .context("Failed to execute curl command")?;
-
-    if !output.status.success() {
-        // Table might not exist
-        return Ok(0);
+/// Export the Hail Table to staging in chunks of partitions, skipping chunks
+/// in `completed`
+///
+/// Each chunk is exported to a scratch table that is committed as a part
+/// table only once complete, so a chunk that fails midway leaves no partial
+/// rows behind. Staging is assembled from the part tables at the end.
+async fn export_checkpointed(
+    sql: &SqlClient,
+    checkpoints: &Checkpoints<'_>,
+    config: &TableConfig,
+    args: &IngestArgs,
+    input_path: &str,
+    completed: &BTreeSet<u32>,
+) -> Result<()> {
+    let total = partition_count(input_path).await?;
+    let chunks = pending_chunks(total, completed, args.checkpoint_partitions);
+    let chunk_table = format!("{}_chunk", config.staging_name);
+    info!(
+        "  {} partitions, {} already loaded, {} chunks to export",
+        total,
+        completed.len(),
+        chunks.len()
+    );
+
+    for (i, chunk) in chunks.into_iter().enumerate() {
+        info!(
+            "  Chunk {}: partitions {}..{}",
+            i + 1,
+            chunk.start,
+            chunk.end
+        );
+        sql.execute_statement(&format!("DROP TABLE IF EXISTS {}", chunk_table))
+            .await?;
+        run_hail_decoder_export(&chunk_table, args, input_path, Some(chunk.clone()))
+            .with_context(|| {
+                format!(
+                    "Export failed at partitions {}..{}; re-run with --resume to continue",
+                    chunk.start, chunk.end
+                )
+            })?;
+        checkpoints.record(&chunk_table, &chunk).await?;
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    stdout
-        .trim()
-        .parse()
-        .context("Failed to parse row count")
+    checkpoints.assemble().await
 }
 
 /// Run hail-decoder export clickhouse command (locally or via pool)
-fn run_hail_decoder_export(config: &TableConfig, args: &IngestArgs, input_path: &str) -> Result<()> {
+///
+/// `partitions` restricts the export to a range of Hail Table partitions.
+pub(super) fn run_hail_decoder_export(
+    target_table: &str,
+    args: &IngestArgs,
+    input_path: &str,
+    partitions: Option<Range<u32>>,
+) -> Result<()> {
     let mut cmd = Command::new(&args.hail_decoder);
 
     // Determine which ClickHouse URL to use for hail-decoder
@@ -434,23 +782,23 @@ fn run_hail_decoder_export(config: &TableConfig, args: &IngestArgs, input_path:
         .arg("clickhouse")
         .arg(input_path)
         .arg(export_clickhouse_url)
-        .arg(config.staging_name);
+        .arg(target_table);
 
     // Add optional arguments
     if let Some(limit) = args.limit {
         cmd.arg("--limit").arg(limit.to_string());
     }
+    if let Some(range) = partitions {
+        cmd.arg("--partitions")
+            .arg(format!("{}-{}", range.start, range.end - 1));
+    }
 
     info!("Running: {:?}", cmd);
 
     let status = cmd.status().context("Failed to run hail-decoder")?;
 
     if !status.success() {
-        warn!(
-            "hail-decoder export exited with status: {} — some partitions may have failed. \
-             Continuing with transform (staging data may be incomplete).",
-            status
-        );
+        bail!("hail-decoder export exited with status: {}", status);
     }
 
     Ok(())
@@ -458,7 +806,7 @@ fn run_hail_decoder_export(config: &TableConfig, args: &IngestArgs, input_path:
 
 /// Show status of all managed tables
 async fn show_status(url: &str) -> Result<()> {
-    let database = "default";
+    let sql = SqlClient::new(url, "default");
 
     println!("\n=== ClickHouse Table Status ===\n");
 
@@ -469,11 +817,24 @@ async fn show_status(url: &str) -> Result<()> {
         ("analysis_metadata", "Analysis/phenotype metadata"),
         ("analysis_categories", "Analysis categories (derived)"),
         ("top_variants_aggregated", "Aggregated top variants (derived)"),
+        ("genetic_correlations", "Phenotype genetic correlations"),
+        ("ld_pairs", "Precomputed LD (r²) pairs"),
+        ("known_associations", "Known associations (GWAS Catalog)"),
+        ("recombination_rates", "Recombination rate map"),
+        ("conditional_variants", "Stepwise conditional results"),
+        ("credible_sets", "Fine-mapping PIPs and credible sets"),
+        ("analysis_test_counts", "Per-phenotype test counts"),
         ("variant_annotations", "Legacy combined annotations"),
     ];
 
     for (table, description) in tables {
-        let count = get_row_count(url, database, table).await.unwrap_or(0);
+        let count = match sql.row_count(table).await {
+            Ok(count) => count,
+            Err(e) => {
+                warn!("{:#}", e);
+                0
+            }
+        };
         let status = if count > 0 {
             format!("{:>12} rows", format_number(count))
         } else {
@@ -487,7 +848,7 @@ async fn show_status(url: &str) -> Result<()> {
 }
 
 /// Format a number with thousands separators
-fn format_number(n: u64) -> String {
+pub(super) fn format_number(n: u64) -> String {
     let s = n.to_string();
     let mut result = String::new();
     for (i, c) in s.chars().rev().enumerate() {
@@ -503,6 +864,30 @@ fn format_number(n: u64) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_retarget_sql() {
+        let sql = "CREATE TABLE IF NOT EXISTS analysis_metadata (\n  x UInt8\n);\n\
+                   INSERT INTO analysis_metadata\nSELECT * FROM staging_analysis_metadata_raw;";
+        let retargeted = retarget_sql(sql, "analysis_metadata", "analysis_metadata_new");
+        assert!(retargeted.contains("CREATE TABLE IF NOT EXISTS analysis_metadata_new ("));
+        assert!(retargeted.contains("INSERT INTO analysis_metadata_new\n"));
+        assert!(retargeted.contains("FROM staging_analysis_metadata_raw;"));
+        assert_eq!(retarget_sql(sql, "analysis_metadata", "analysis_metadata"), sql);
+    }
+
+    #[test]
+    fn test_partition_counts_total() {
+        let metadata = serde_json::json!({
+            "name": "TableSpec",
+            "components": {
+                "rows": {"name": "RVDComponentSpec", "rel_path": "rows"},
+                "partition_counts": {"name": "PartitionCountsComponentSpec", "counts": [3, 4, 0]}
+            }
+        });
+        assert_eq!(partition_counts_total(&metadata), Some(7));
+        assert_eq!(partition_counts_total(&serde_json::json!({"components": {}})), None);
+    }
+
     #[test]
     fn test_format_number() {
         assert_eq!(format_number(1234567890), "1,234,567,890");
@@ -0,0 +1,308 @@
+//! Ingest of previously reported associations (GWAS Catalog)
+//!
+//! `ingest known-hits` loads a GWAS Catalog associations TSV (optionally
+//! gzipped) into `known_associations`, replacing earlier rows of the same
+//! `--source`. Rows without a single GRCh38 position or a numeric p-value
+//! (haplotypes, SNP x SNP interactions, unmapped variants) are skipped.
+//!
+//! `--trait-map` loads a two-column TSV of `analysis_id<TAB>EFO term` into
+//! `known_association_traits`, which decides which catalog traits
+//! `/api/phenotype/:analysis_id/known-hits` reports for each phenotype.
+
+use super::history::{default_operator, RunRecord};
+use super::sql::SqlClient;
+use crate::clickhouse::models::KnownAssociationRow;
+use crate::genomics::Contig;
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+const KNOWN_ASSOCIATIONS_DDL: &str = include_str!("../sql/known_associations.sql");
+
+/// Arguments for `ingest known-hits`
+#[derive(Debug, Args, Clone)]
+pub struct KnownHitsArgs {
+    /// GWAS Catalog associations TSV ("All associations", with ontology
+    /// annotations); `.gz` files are decompressed
+    #[arg(long)]
+    pub input: PathBuf,
+
+    /// Two-column TSV mapping analysis IDs to EFO terms; replaces the
+    /// current mapping when given
+    #[arg(long)]
+    pub trait_map: Option<PathBuf>,
+
+    /// Source label stored with the rows; earlier rows with it are replaced
+    #[arg(long, default_value = "gwas_catalog")]
+    pub source: String,
+
+    /// ClickHouse URL
+    #[arg(long, default_value = "http://localhost:8123")]
+    pub clickhouse_url: String,
+
+    /// ClickHouse database name
+    #[arg(long, default_value = "default")]
+    pub database: String,
+
+    /// Name recorded in the ingest history (default: $USER)
+    #[arg(long)]
+    pub operator: Option<String>,
+}
+
+/// Run `ingest known-hits` and record it in `ingest_runs`
+pub async fn run_known_hits(args: &KnownHitsArgs) -> Result<()> {
+    let sql = SqlClient::new(&args.clickhouse_url, &args.database);
+    let record = RunRecord {
+        run_id: uuid::Uuid::new_v4().to_string(),
+        table_name: "known_associations".to_string(),
+        source_uri: args.input.display().to_string(),
+        init_strategy: "replace".to_string(),
+        hail_decoder_version: String::new(),
+        operator: args.operator.clone().unwrap_or_else(default_operator),
+        started_at: std::time::SystemTime::now(),
+    };
+
+    let result = load_known_hits(&sql, args).await;
+    record.finish(&sql, &result).await;
+    let rows = result?;
+    info!("Loaded {} known associations from {}", rows, args.source);
+    Ok(())
+}
+
+async fn load_known_hits(sql: &SqlClient, args: &KnownHitsArgs) -> Result<u64> {
+    sql.execute(KNOWN_ASSOCIATIONS_DDL).await?;
+
+    let (rows, skipped) = parse_gwas_catalog(open_input(&args.input)?, &args.source)?;
+    info!(
+        "Parsed {} associations from {} ({} rows skipped)",
+        rows.len(),
+        args.input.display(),
+        skipped
+    );
+    if rows.is_empty() {
+        bail!("No associations parsed from {}", args.input.display());
+    }
+
+    sql.query("DELETE FROM known_associations WHERE source = ?")
+        .bind(&args.source)
+        .execute()
+        .await
+        .context("Failed to clear previous known associations")?;
+    let mut insert = sql
+        .client()
+        .insert::<KnownAssociationRow>("known_associations")?;
+    for row in &rows {
+        insert.write(row).await?;
+    }
+    insert
+        .end()
+        .await
+        .context("Failed to insert known associations")?;
+
+    if let Some(path) = &args.trait_map {
+        let traits = parse_trait_map(open_input(path)?)?;
+        info!(
+            "Loading {} phenotype trait mappings from {}",
+            traits.len(),
+            path.display()
+        );
+        sql.execute_statement("TRUNCATE TABLE known_association_traits")
+            .await?;
+        let mut insert = sql
+            .client()
+            .insert::<TraitMappingRow>("known_association_traits")?;
+        for row in &traits {
+            insert.write(row).await?;
+        }
+        insert
+            .end()
+            .await
+            .context("Failed to insert trait mappings")?;
+    }
+
+    Ok(rows.len() as u64)
+}
+
+/// Row of `known_association_traits`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, clickhouse::Row)]
+struct TraitMappingRow {
+    phenotype: String,
+    efo_id: String,
+}
+
+fn open_input(path: &Path) -> Result<Box<dyn BufRead>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let reader: Box<dyn Read> = if path.extension().is_some_and(|ext| ext == "gz") {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    Ok(Box::new(BufReader::new(reader)))
+}
+
+/// Ontology ID from a trait URI, e.g. ".../efo/EFO_0004340" -> "EFO_0004340"
+fn efo_id(uri: &str) -> Option<String> {
+    let id = uri.trim().rsplit('/').next()?.trim();
+    (!id.is_empty()).then(|| id.to_string())
+}
+
+/// Split a comma-separated catalog list, dropping blanks and "NR"
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty() && *v != "NR")
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parse a GWAS Catalog associations TSV; returns the rows and the number of
+/// data lines skipped
+fn parse_gwas_catalog<R: BufRead>(
+    reader: R,
+    source: &str,
+) -> Result<(Vec<KnownAssociationRow>, usize)> {
+    let mut lines = reader.lines();
+    let header = lines.next().context("Empty GWAS Catalog file")??;
+    let columns: HashMap<&str, usize> = header
+        .split('\t')
+        .enumerate()
+        .map(|(i, name)| (name.trim(), i))
+        .collect();
+    let column = |name: &str| {
+        columns
+            .get(name)
+            .copied()
+            .with_context(|| format!("GWAS Catalog file has no '{}' column", name))
+    };
+    let chr = column("CHR_ID")?;
+    let pos = column("CHR_POS")?;
+    let pvalue = column("P-VALUE")?;
+    let snps = column("SNPS")?;
+    let risk = column("STRONGEST SNP-RISK ALLELE")?;
+    let reported_trait = column("DISEASE/TRAIT")?;
+    let pubmed = column("PUBMEDID")?;
+    let genes = column("REPORTED GENE(S)")?;
+    let mapped_trait = column("MAPPED_TRAIT").ok();
+    let mapped_uri = column("MAPPED_TRAIT_URI").ok();
+    let study = column("STUDY ACCESSION").ok();
+
+    let mut rows = Vec::new();
+    let mut skipped = 0;
+    for line in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        let field = |i: usize| fields.get(i).map(|f| f.trim()).unwrap_or("");
+        let optional = |i: Option<usize>| i.map(field).unwrap_or("");
+
+        let contig = Contig::parse(field(chr));
+        let position = field(pos).parse::<u32>().ok();
+        let p = field(pvalue).parse::<f64>().ok();
+        let (Some(contig), Some(position), Some(p)) = (contig, position, p) else {
+            skipped += 1;
+            continue;
+        };
+
+        let risk_allele = field(risk)
+            .rsplit_once('-')
+            .map(|(_, allele)| allele.trim().to_string())
+            .filter(|allele| !allele.is_empty())
+            .unwrap_or_else(|| "?".to_string());
+        rows.push(KnownAssociationRow {
+            source: source.to_string(),
+            study_accession: optional(study).to_string(),
+            pubmed_id: field(pubmed).to_string(),
+            reported_trait: field(reported_trait).to_string(),
+            mapped_traits: split_list(optional(mapped_trait)),
+            efo_ids: optional(mapped_uri).split(',').filter_map(efo_id).collect(),
+            rsid: field(snps).to_string(),
+            contig: contig.chr().to_string(),
+            position,
+            xpos: contig.xpos(position),
+            risk_allele,
+            pvalue: p,
+            reported_genes: split_list(field(genes)),
+        });
+    }
+    Ok((rows, skipped))
+}
+
+/// Parse `analysis_id<TAB>EFO term` lines; the term may be an ID or a URI.
+/// Blank lines, `#` comments and a header row starting with "analysis_id"
+/// are ignored.
+fn parse_trait_map<R: BufRead>(reader: R) -> Result<Vec<TraitMappingRow>> {
+    let mut rows = Vec::new();
+    for (n, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("analysis_id") {
+            continue;
+        }
+        let Some((phenotype, term)) = line.split_once('\t') else {
+            bail!(
+                "Trait map line {}: expected analysis_id<TAB>EFO term",
+                n + 1
+            );
+        };
+        let efo_id =
+            efo_id(term).with_context(|| format!("Trait map line {}: missing EFO term", n + 1))?;
+        rows.push(TraitMappingRow {
+            phenotype: phenotype.trim().to_string(),
+            efo_id,
+        });
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "PUBMEDID\tDISEASE/TRAIT\tCHR_ID\tCHR_POS\tREPORTED GENE(S)\t\
+        STRONGEST SNP-RISK ALLELE\tSNPS\tP-VALUE\tMAPPED_TRAIT\tMAPPED_TRAIT_URI\tSTUDY ACCESSION";
+
+    #[test]
+    fn test_parse_gwas_catalog() {
+        let tsv = format!(
+            "{}\n\
+             30595370\tLDL cholesterol\t1\t55039974\tPCSK9, NR\trs11591147-T\trs11591147\t2E-300\t\
+             low density lipoprotein cholesterol measurement\thttp://www.ebi.ac.uk/efo/EFO_0004611\tGCST007141\n\
+             30595370\tLDL cholesterol\t1;1\t55039974;55040000\tPCSK9\trs1-A; rs2-G\trs1 x rs2\t1E-10\t\t\t\n\
+             30595370\tLDL cholesterol\tX\t1000\t\trs3-?\trs3\tNA\t\t\t\n",
+            HEADER
+        );
+        let (rows, skipped) = parse_gwas_catalog(tsv.as_bytes(), "gwas_catalog").unwrap();
+        assert_eq!(skipped, 2);
+        assert_eq!(rows.len(), 1);
+
+        let row = &rows[0];
+        assert_eq!(row.contig, "chr1");
+        assert_eq!(row.xpos, 1_055_039_974);
+        assert_eq!(row.risk_allele, "T");
+        assert_eq!(row.pvalue, 2e-300);
+        assert_eq!(row.efo_ids, vec!["EFO_0004611"]);
+        assert_eq!(row.reported_genes, vec!["PCSK9"]);
+        assert_eq!(row.study_accession, "GCST007141");
+    }
+
+    #[test]
+    fn test_parse_trait_map() {
+        let tsv = "analysis_id\tefo\n# lipids\n\
+                   3028288\tEFO_0004611\n\
+                   3028288\thttp://www.ebi.ac.uk/efo/EFO_0004574\n";
+        let rows = parse_trait_map(tsv.as_bytes()).unwrap();
+        assert_eq!(
+            rows.iter().map(|r| r.efo_id.as_str()).collect::<Vec<_>>(),
+            vec!["EFO_0004611", "EFO_0004574"]
+        );
+        assert!(parse_trait_map("3028288 EFO_0004611\n".as_bytes()).is_err());
+    }
+}
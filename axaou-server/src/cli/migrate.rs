@@ -0,0 +1,334 @@
+//! Versioned ClickHouse schema migrations
+//!
+//! Migrations are SQL files under `src/sql/migrations`, embedded at build
+//! time and listed in [`MIGRATIONS`] in version order. `migrate up` applies
+//! the ones not yet recorded in `schema_migrations`, so existing tables can
+//! gain columns and indexes in place instead of being dropped and reloaded.
+//!
+//! Migrations must be idempotent (`IF NOT EXISTS` / `IF EXISTS`) because a
+//! migration that fails partway is retried from its first statement. When a
+//! migration changes a table that ingest creates, update that table's DDL in
+//! `src/sql` as well so fresh loads match migrated databases.
+
+use super::history::default_operator;
+use super::sql::SqlClient;
+use anyhow::{bail, Context, Result};
+use clap::{Args, Subcommand};
+use clickhouse::Row;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+const SCHEMA_MIGRATIONS_DDL: &str = include_str!("../sql/schema_migrations.sql");
+
+/// An embedded migration
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// All migrations, in ascending version order
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "gene_associations_gene_id_index",
+        sql: include_str!("../sql/migrations/0001_gene_associations_gene_id_index.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "top_associations_and_peaks_views",
+        // Table DDL is shared with ingest; the migration adds the backfills
+        sql: concat!(
+            include_str!("../sql/top_gene_associations.sql"),
+            ";\n",
+            include_str!("../sql/phenotype_peaks.sql"),
+            ";\n",
+            include_str!("../sql/migrations/0002_top_associations_and_peaks_views.sql"),
+        ),
+    },
+    Migration {
+        version: 3,
+        name: "annotation_predictor_scores",
+        sql: include_str!("../sql/migrations/0003_annotation_predictor_scores.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "annotation_ancestry_frequencies",
+        sql: include_str!("../sql/migrations/0004_annotation_ancestry_frequencies.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "analysis_metadata_heritability",
+        sql: include_str!("../sql/migrations/0005_analysis_metadata_heritability.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "partition_variant_tables",
+        sql: include_str!("../sql/migrations/0006_partition_variant_tables.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "partition_gene_association_tables",
+        sql: include_str!("../sql/migrations/0007_partition_gene_association_tables.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "gene_models_transcript_ids",
+        sql: include_str!("../sql/migrations/0008_gene_models_transcript_ids.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "annotation_rsids",
+        sql: include_str!("../sql/migrations/0009_annotation_rsids.sql"),
+    },
+];
+
+impl Migration {
+    /// Stable checksum of the migration SQL (FNV-1a, hex)
+    pub fn checksum(&self) -> String {
+        let hash = self.sql.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        });
+        format!("{:016x}", hash)
+    }
+}
+
+/// Migrate subcommands
+#[derive(Debug, Subcommand)]
+pub enum MigrateCommand {
+    /// Apply pending migrations
+    Up(MigrateArgs),
+
+    /// List migrations and whether each has been applied
+    Status(MigrateArgs),
+}
+
+/// Common arguments for migrate commands
+#[derive(Debug, Args, Clone)]
+pub struct MigrateArgs {
+    /// ClickHouse URL
+    #[arg(long, default_value = "http://localhost:8123")]
+    pub clickhouse_url: String,
+
+    /// ClickHouse database name
+    #[arg(long, default_value = "default")]
+    pub database: String,
+
+    /// Stop after applying this version
+    #[arg(long)]
+    pub target: Option<u32>,
+
+    /// Print pending migrations without applying them
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// One row of `schema_migrations`
+#[derive(Debug, Clone, Deserialize, Row)]
+struct AppliedMigration {
+    version: u32,
+    checksum: String,
+}
+
+/// Run the migrate command
+pub async fn run_migrate(command: MigrateCommand) -> Result<()> {
+    match command {
+        MigrateCommand::Up(args) => migrate_up(&args).await,
+        MigrateCommand::Status(args) => show_status(&args).await,
+    }
+}
+
+async fn applied_migrations(sql: &SqlClient) -> Result<Vec<AppliedMigration>> {
+    sql.execute(SCHEMA_MIGRATIONS_DDL).await?;
+    sql.query("SELECT version, checksum FROM schema_migrations FINAL ORDER BY version")
+        .fetch_all::<AppliedMigration>()
+        .await
+        .context("Failed to read schema_migrations")
+}
+
+/// Migrations not yet applied, up to and including `target`
+fn pending<'a>(
+    migrations: &'a [Migration],
+    applied: &[AppliedMigration],
+    target: Option<u32>,
+) -> Vec<&'a Migration> {
+    migrations
+        .iter()
+        .filter(|m| !applied.iter().any(|a| a.version == m.version))
+        .filter(|m| target.is_none_or(|t| m.version <= t))
+        .collect()
+}
+
+/// Warn about applied migrations whose embedded SQL has since changed
+fn check_checksums(applied: &[AppliedMigration]) {
+    for row in applied {
+        match MIGRATIONS.iter().find(|m| m.version == row.version) {
+            Some(m) if m.checksum() != row.checksum => warn!(
+                "Migration {:04} ({}) changed after it was applied",
+                m.version, m.name
+            ),
+            None => warn!(
+                "Migration {:04} is recorded but not known to this build",
+                row.version
+            ),
+            _ => {}
+        }
+    }
+}
+
+async fn migrate_up(args: &MigrateArgs) -> Result<()> {
+    let sql = SqlClient::new(&args.clickhouse_url, &args.database);
+    let applied = applied_migrations(&sql).await?;
+    check_checksums(&applied);
+
+    let pending = pending(MIGRATIONS, &applied, args.target);
+    if pending.is_empty() {
+        info!("Schema is up to date");
+        return Ok(());
+    }
+
+    let operator = default_operator();
+    for migration in pending {
+        if args.dry_run {
+            println!(
+                "-- {:04}_{}\n{}\n",
+                migration.version,
+                migration.name,
+                migration.sql.trim()
+            );
+            continue;
+        }
+
+        info!(
+            "Applying migration {:04}_{}",
+            migration.version, migration.name
+        );
+        if let Err(e) = sql.execute(migration.sql).await {
+            bail!(
+                "Migration {:04}_{} failed: {:#}",
+                migration.version,
+                migration.name,
+                e
+            );
+        }
+
+        sql.query(
+            "INSERT INTO schema_migrations (version, name, checksum, applied_by) VALUES (?, ?, ?, ?)",
+        )
+        .bind(migration.version)
+        .bind(migration.name)
+        .bind(migration.checksum())
+        .bind(&operator)
+        .execute()
+        .await
+        .with_context(|| format!("Failed to record migration {:04}", migration.version))?;
+    }
+
+    info!("Migrations complete");
+    Ok(())
+}
+
+async fn show_status(args: &MigrateArgs) -> Result<()> {
+    let sql = SqlClient::new(&args.clickhouse_url, &args.database);
+    let applied = applied_migrations(&sql).await?;
+    check_checksums(&applied);
+
+    println!("\n=== Schema Migrations ===\n");
+    for migration in MIGRATIONS {
+        let state = if applied.iter().any(|a| a.version == migration.version) {
+            "applied"
+        } else {
+            "pending"
+        };
+        println!(
+            "  {:04}  {:<8}  {}",
+            migration.version, state, migration.name
+        );
+    }
+    println!();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        for pair in MIGRATIONS.windows(2) {
+            assert!(pair[0].version < pair[1].version);
+        }
+    }
+
+    #[test]
+    fn test_views_migration_creates_tables_from_ingest_ddl() {
+        let statements = crate::cli::sql::split_sql_statements(MIGRATIONS[1].sql);
+        assert_eq!(statements.len(), 4);
+        assert!(statements[0].contains("CREATE TABLE IF NOT EXISTS top_gene_associations"));
+        assert!(statements[1].contains("CREATE TABLE IF NOT EXISTS phenotype_peaks"));
+        assert!(statements[2..].iter().all(|s| s.contains("INSERT INTO")));
+    }
+
+    #[test]
+    fn test_predictor_migration_matches_annotation_ddl() {
+        let statements = crate::cli::sql::split_sql_statements(MIGRATIONS[2].sql);
+        let tables = [
+            ("exome_annotations", include_str!("../sql/exome_annotations.sql")),
+            ("genome_annotations", include_str!("../sql/genome_annotations.sql")),
+        ];
+        for (table, ddl) in tables {
+            let alter = statements
+                .iter()
+                .find(|s| s.contains(&format!("ALTER TABLE {}", table)))
+                .unwrap();
+            for column in [
+                "cadd_phred",
+                "revel_max",
+                "spliceai_ds_max",
+                "alphamissense_score",
+                "alphamissense_class",
+            ] {
+                assert!(ddl.contains(column), "{} DDL lacks {}", table, column);
+                assert!(alter.contains(&format!("ADD COLUMN IF NOT EXISTS {} ", column)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_pending_respects_applied_and_target() {
+        let migrations = [
+            Migration {
+                version: 1,
+                name: "a",
+                sql: "SELECT 1",
+            },
+            Migration {
+                version: 2,
+                name: "b",
+                sql: "SELECT 2",
+            },
+            Migration {
+                version: 3,
+                name: "c",
+                sql: "SELECT 3",
+            },
+        ];
+        let applied = vec![AppliedMigration {
+            version: 1,
+            checksum: migrations[0].checksum(),
+        }];
+
+        let versions: Vec<u32> = pending(&migrations, &applied, None)
+            .iter()
+            .map(|m| m.version)
+            .collect();
+        assert_eq!(versions, vec![2, 3]);
+
+        let versions: Vec<u32> = pending(&migrations, &applied, Some(2))
+            .iter()
+            .map(|m| m.version)
+            .collect();
+        assert_eq!(versions, vec![2]);
+    }
+}
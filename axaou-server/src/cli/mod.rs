@@ -2,11 +2,28 @@
 //!
 //! Contains orchestration commands for data loading and maintenance tasks.
 
+pub mod assets;
+pub mod bench;
+pub mod checkpoint;
 pub mod derive;
+pub mod export;
+pub mod export_static;
+pub mod gene_associations;
+pub mod history;
 pub mod ingest;
+pub mod known_hits;
+pub mod migrate;
+pub mod partition_load;
+pub mod recombination;
+pub mod sql;
+pub mod validate;
+pub mod variant_results;
 
+pub use assets::{run_assets, AssetsCommand};
 pub use derive::*;
+pub use export::{run_export, ExportCommand};
 pub use ingest::*;
+pub use migrate::{run_migrate, MigrateCommand};
 
 /// Run the load test from a CLI config file path.
 pub async fn run_loadtest(config: std::path::PathBuf) -> anyhow::Result<()> {
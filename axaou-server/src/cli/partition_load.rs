@@ -0,0 +1,244 @@
+//! Atomic partition loads for the per-phenotype result loaders
+//!
+//! `ingest variant-results` and `ingest gene-associations` never delete a
+//! phenotype's rows from a serving table while the server reads it. The
+//! serving tables are partitioned on low-cardinality keys (ancestry, and
+//! sequencing type for variant tables), and every partition a run touches is
+//! rebuilt in a staging table: first the new rows of the phenotypes being
+//! loaded, then the current rows of every other phenotype. `ALTER TABLE ...
+//! REPLACE PARTITION` then swaps the whole partition in at once, so readers
+//! see either the old or the new partition.
+//!
+//! Partitions are the unit of scheduling: different partitions load
+//! concurrently and the phenotypes of one partition are loaded in turn, so
+//! no two loads of a run rebuild the same partition. Staging tables carry a
+//! per-run ID, so overlapping runs never share one.
+
+use super::ingest::{run_hail_decoder_export, IngestArgs};
+use super::sql::SqlClient;
+use crate::models::AnalysisAssets;
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+/// Read a discovered assets file (see `discover`)
+pub fn read_assets(path: &Path) -> Result<AnalysisAssets> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Short random ID that keeps one run's staging tables apart from another's
+pub fn new_run_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..8].to_string()
+}
+
+/// Lowercase `value` with every non-alphanumeric character replaced by `_`,
+/// for use in a table name
+pub fn table_name_part(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Export a Hail Table into a fresh `staging` table
+pub async fn export_to_staging(
+    sql: &SqlClient,
+    staging: &str,
+    uri: &str,
+    export_args: &IngestArgs,
+) -> Result<()> {
+    sql.execute_statement(&format!("DROP TABLE IF EXISTS {}", staging))
+        .await?;
+    let (table, uri, export_args) = (staging.to_string(), uri.to_string(), export_args.clone());
+    tokio::task::spawn_blocking(move || run_hail_decoder_export(&table, &export_args, &uri, None))
+        .await?
+}
+
+/// Export settings shared by the loaders' per-table exports
+pub fn loader_export_args(
+    clickhouse_url: &str,
+    database: &str,
+    remote_clickhouse_url: Option<&str>,
+    hail_decoder: &str,
+    pool: Option<&str>,
+) -> IngestArgs {
+    let mut export_args = IngestArgs::new(clickhouse_url.to_string(), database.to_string());
+    export_args.remote_clickhouse_url = remote_clickhouse_url.map(str::to_string);
+    export_args.hail_decoder = hail_decoder.to_string();
+    export_args.pool = pool.map(str::to_string);
+    export_args.force = true;
+    export_args
+}
+
+/// One partition of a serving table, rebuilt in its own staging table
+pub struct PartitionStage {
+    table: &'static str,
+    staging: String,
+    /// (partition key column, value), in partition key order
+    key: Vec<(&'static str, String)>,
+}
+
+impl PartitionStage {
+    /// Create an empty staging table shaped like `table`
+    ///
+    /// Fails if `table` is not partitioned, since `REPLACE PARTITION` would
+    /// then replace the whole table; `migrate up` partitions older tables.
+    pub async fn create(
+        sql: &SqlClient,
+        table: &'static str,
+        run_id: &str,
+        key: Vec<(&'static str, String)>,
+    ) -> Result<Self> {
+        let partition_key = sql
+            .query(
+                "SELECT partition_key FROM system.tables \
+                 WHERE database = currentDatabase() AND name = ?",
+            )
+            .bind(table)
+            .fetch_one::<String>()
+            .await
+            .with_context(|| format!("Failed to look up the partition key of {}", table))?;
+        if partition_key.is_empty() {
+            bail!("{} is not partitioned; run `migrate up` first", table);
+        }
+
+        let suffix: Vec<String> = key.iter().map(|(_, v)| table_name_part(v)).collect();
+        let staging = format!("{}__{}_{}", table, run_id, suffix.join("_"));
+        sql.execute_statement(&format!("DROP TABLE IF EXISTS {}", staging))
+            .await?;
+        sql.execute_statement(&format!("CREATE TABLE {} AS {}", staging, table))
+            .await?;
+        Ok(Self {
+            table,
+            staging,
+            key,
+        })
+    }
+
+    /// Serving table this stage replaces a partition of
+    pub fn table(&self) -> &'static str {
+        self.table
+    }
+
+    /// Staging table to insert new rows into
+    pub fn staging(&self) -> &str {
+        &self.staging
+    }
+
+    /// Drop a phenotype's staged rows, e.g. after its load failed partway
+    pub async fn discard(&self, sql: &SqlClient, phenotype: &str) -> Result<()> {
+        sql.query(&format!("DELETE FROM {} WHERE phenotype = ?", self.staging))
+            .bind(phenotype)
+            .execute()
+            .await
+            .with_context(|| format!("Failed to discard {} from {}", phenotype, self.staging))
+    }
+
+    /// Copy the partition's rows of every phenotype not in `loaded`, then
+    /// replace the live partition with the staged one
+    ///
+    /// Returns the number of rows in the new partition.
+    pub async fn swap(&self, sql: &SqlClient, loaded: &[String]) -> Result<u64> {
+        let key_filter: Vec<String> = self
+            .key
+            .iter()
+            .map(|(column, _)| format!("{} = ?", column))
+            .collect();
+        let mut copy = sql.query(&format!(
+            "INSERT INTO {} SELECT * FROM {} WHERE {} AND NOT has(?, phenotype)",
+            self.staging,
+            self.table,
+            key_filter.join(" AND ")
+        ));
+        for (_, value) in &self.key {
+            copy = copy.bind(value);
+        }
+        copy.bind(loaded).execute().await.with_context(|| {
+            format!(
+                "Failed to copy kept rows of {} into {}",
+                self.table, self.staging
+            )
+        })?;
+        let rows = sql.row_count(&self.staging).await?;
+
+        let placeholders = vec!["?"; self.key.len()].join(", ");
+        let partition = if self.key.len() == 1 {
+            placeholders
+        } else {
+            format!("tuple({})", placeholders)
+        };
+        let mut replace = sql.query(&format!(
+            "ALTER TABLE {} REPLACE PARTITION {} FROM {}",
+            self.table, partition, self.staging
+        ));
+        for (_, value) in &self.key {
+            replace = replace.bind(value);
+        }
+        replace
+            .execute()
+            .await
+            .with_context(|| format!("Failed to swap {} into {}", self.staging, self.table))?;
+        Ok(rows)
+    }
+
+    /// Drop the staging table
+    pub async fn drop(&self, sql: &SqlClient) -> Result<()> {
+        sql.execute_statement(&format!("DROP TABLE IF EXISTS {}", self.staging))
+            .await
+    }
+}
+
+/// Group `items` by partition key, keeping the first-seen order of both the
+/// partitions and the items within each
+pub fn group_by_partition<T, K: PartialEq>(
+    items: Vec<T>,
+    key: impl Fn(&T) -> K,
+) -> Vec<(K, Vec<T>)> {
+    let mut groups: Vec<(K, Vec<T>)> = Vec::new();
+    for item in items {
+        let k = key(&item);
+        match groups.iter_mut().find(|(existing, _)| *existing == k) {
+            Some((_, group)) => group.push(item),
+            None => groups.push((k, vec![item])),
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_name_part() {
+        assert_eq!(table_name_part("icd10-E11.9"), "icd10_e11_9");
+        assert_eq!(table_name_part("meta"), "meta");
+        assert_eq!(new_run_id().len(), 8);
+        assert_ne!(new_run_id(), new_run_id());
+    }
+
+    #[test]
+    fn test_group_by_partition() {
+        let items = vec![
+            ("height", "eur"),
+            ("bmi", "meta"),
+            ("bmi", "eur"),
+            ("ldl", "meta"),
+        ];
+        let groups = group_by_partition(items, |(_, ancestry)| *ancestry);
+        assert_eq!(
+            groups,
+            vec![
+                ("eur", vec![("height", "eur"), ("bmi", "eur")]),
+                ("meta", vec![("bmi", "meta"), ("ldl", "meta")]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! Ingest of a recombination rate map
+//!
+//! `ingest recombination-rates` loads a HapMap-style genetic map into
+//! `recombination_rates`, replacing the previous map. Input lines are
+//! whitespace-separated `chromosome position rate_cM_per_Mb [map_cM]`, the
+//! layout of the HapMap II / 1000 Genomes maps and of LocusZoom's own
+//! recombination file; `.gz` files are decompressed. When the map column is
+//! absent it is accumulated from the rates. A header line and `#` comments
+//! are ignored.
+
+use super::history::{default_operator, RunRecord};
+use super::sql::SqlClient;
+use crate::clickhouse::models::RecombinationRateRow;
+use crate::genomics::Contig;
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+const RECOMBINATION_RATES_DDL: &str = include_str!("../sql/recombination_rates.sql");
+
+/// Arguments for `ingest recombination-rates`
+#[derive(Debug, Args, Clone)]
+pub struct RecombinationArgs {
+    /// Genetic map (GRCh38) with chromosome, position, rate and optional map
+    /// columns; `.gz` files are decompressed
+    #[arg(long)]
+    pub input: PathBuf,
+
+    /// ClickHouse URL
+    #[arg(long, default_value = "http://localhost:8123")]
+    pub clickhouse_url: String,
+
+    /// ClickHouse database name
+    #[arg(long, default_value = "default")]
+    pub database: String,
+
+    /// Name recorded in the ingest history (default: $USER)
+    #[arg(long)]
+    pub operator: Option<String>,
+}
+
+/// Run `ingest recombination-rates` and record it in `ingest_runs`
+pub async fn run_recombination_rates(args: &RecombinationArgs) -> Result<()> {
+    let sql = SqlClient::new(&args.clickhouse_url, &args.database);
+    let record = RunRecord {
+        run_id: uuid::Uuid::new_v4().to_string(),
+        table_name: "recombination_rates".to_string(),
+        source_uri: args.input.display().to_string(),
+        init_strategy: "replace".to_string(),
+        hail_decoder_version: String::new(),
+        operator: args.operator.clone().unwrap_or_else(default_operator),
+        started_at: std::time::SystemTime::now(),
+    };
+
+    let result = load_recombination_rates(&sql, args).await;
+    record.finish(&sql, &result).await;
+    let rows = result?;
+    info!("Loaded {} recombination map points", rows);
+    Ok(())
+}
+
+async fn load_recombination_rates(sql: &SqlClient, args: &RecombinationArgs) -> Result<u64> {
+    sql.execute(RECOMBINATION_RATES_DDL).await?;
+
+    let (rows, skipped) = parse_genetic_map(open_input(&args.input)?)?;
+    info!(
+        "Parsed {} map points from {} ({} lines skipped)",
+        rows.len(),
+        args.input.display(),
+        skipped
+    );
+    if rows.is_empty() {
+        bail!(
+            "No recombination rates parsed from {}",
+            args.input.display()
+        );
+    }
+
+    sql.execute_statement("TRUNCATE TABLE recombination_rates")
+        .await?;
+    let mut insert = sql
+        .client()
+        .insert::<RecombinationRateRow>("recombination_rates")?;
+    for row in &rows {
+        insert.write(row).await?;
+    }
+    insert
+        .end()
+        .await
+        .context("Failed to insert recombination rates")?;
+
+    Ok(rows.len() as u64)
+}
+
+fn open_input(path: &Path) -> Result<Box<dyn BufRead>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let reader: Box<dyn Read> = if path.extension().is_some_and(|ext| ext == "gz") {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    Ok(Box::new(BufReader::new(reader)))
+}
+
+/// Parse a genetic map; returns the points sorted by xpos and the number of
+/// data lines skipped (unknown contigs, malformed numbers)
+fn parse_genetic_map<R: BufRead>(reader: R) -> Result<(Vec<RecombinationRateRow>, usize)> {
+    let mut rows: Vec<RecombinationRateRow> = Vec::new();
+    let mut skipped = 0;
+    let mut seen_data = false;
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let contig = fields.first().and_then(|f| Contig::parse(f));
+        let position = fields.get(1).and_then(|f| f.parse::<u32>().ok());
+        let rate = fields.get(2).and_then(|f| f.parse::<f64>().ok());
+        let (Some(contig), Some(position), Some(rate)) = (contig, position, rate) else {
+            // The first line of most maps is a header
+            if seen_data {
+                skipped += 1;
+            }
+            seen_data = true;
+            continue;
+        };
+        seen_data = true;
+        let map_cm = match fields.get(3) {
+            Some(f) => match f.parse::<f64>() {
+                Ok(cm) => Some(cm),
+                Err(_) => {
+                    skipped += 1;
+                    continue;
+                }
+            },
+            None => None,
+        };
+        rows.push(RecombinationRateRow {
+            contig: contig.chr().to_string(),
+            position,
+            xpos: contig.xpos(position),
+            rate,
+            genetic_map_cm: map_cm.unwrap_or(f64::NAN),
+        });
+    }
+    rows.sort_by_key(|row| row.xpos);
+
+    // Fill in map positions the file did not give from the preceding rate
+    for i in 0..rows.len() {
+        if rows[i].genetic_map_cm.is_nan() {
+            rows[i].genetic_map_cm = match i.checked_sub(1).map(|j| &rows[j]) {
+                Some(prev) if prev.contig == rows[i].contig => {
+                    prev.genetic_map_cm
+                        + prev.rate * f64::from(rows[i].position - prev.position) / 1e6
+                }
+                _ => 0.0,
+            };
+        }
+    }
+    Ok((rows, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_genetic_map() {
+        let map = "Chromosome\tPosition(bp)\tRate(cM/Mb)\tMap(cM)\n\
+                   chr1\t55550\t2.981822\t0.000000\n\
+                   chr1\t82571\t2.082414\t0.080572\n\
+                   chrUn\t100\t1.0\t0.0\n";
+        let (rows, skipped) = parse_genetic_map(map.as_bytes()).unwrap();
+        assert_eq!(skipped, 1);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].contig, "chr1");
+        assert_eq!(rows[1].xpos, 1_000_082_571);
+        assert_eq!(rows[1].genetic_map_cm, 0.080572);
+
+        // Without the map column, positions are accumulated per contig
+        let map = "X 2000000 0.5\nX 1000000 2.0\n2 10 1.0\n";
+        let (rows, skipped) = parse_genetic_map(map.as_bytes()).unwrap();
+        assert_eq!(skipped, 0);
+        let cm: Vec<f64> = rows.iter().map(|r| r.genetic_map_cm).collect();
+        assert_eq!(cm, vec![0.0, 0.0, 2.0]);
+        assert_eq!(rows[2].contig, "chrX");
+    }
+}
@@ -0,0 +1,258 @@
+//! ClickHouse SQL execution for the ingest and derive pipelines
+//!
+//! Statements run over the native `clickhouse` HTTP client rather than
+//! shelling out to `curl`, so the pipelines work in minimal containers, pick
+//! up `CLICKHOUSE_USER` / `CLICKHOUSE_PASSWORD`, and surface server errors
+//! with the failing statement attached.
+
+use anyhow::{Context, Result};
+use clickhouse::Client;
+
+/// ClickHouse `UNKNOWN_TABLE` error code
+const UNKNOWN_TABLE: &str = "Code: 60.";
+
+/// Runs pipeline SQL against one ClickHouse database
+#[derive(Clone)]
+pub struct SqlClient {
+    client: Client,
+}
+
+impl SqlClient {
+    pub fn new(url: &str, database: &str) -> Self {
+        let client = Client::default().with_url(url).with_database(database);
+        Self {
+            client: crate::clickhouse::client::with_credentials(client),
+        }
+    }
+
+    /// Underlying client, for code shared with the server
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Query builder for statements that need bound parameters; comments are
+    /// stripped so only real placeholders remain
+    pub fn query(&self, sql: &str) -> clickhouse::query::Query {
+        self.client.query(&strip_sql_comments(sql))
+    }
+
+    /// Execute SQL text that may contain several `;`-separated statements
+    pub async fn execute(&self, sql: &str) -> Result<()> {
+        for statement in split_sql_statements(sql) {
+            self.execute_statement(&statement).await?;
+        }
+        Ok(())
+    }
+
+    /// Execute a single statement
+    pub async fn execute_statement(&self, sql: &str) -> Result<()> {
+        self.client
+            .query(&strip_sql_comments(sql))
+            .execute()
+            .await
+            .with_context(|| format!("ClickHouse SQL failed: {}", truncate(sql)))
+    }
+
+    /// Whether `table` exists in the current database
+    pub async fn table_exists(&self, table: &str) -> Result<bool> {
+        let count = self
+            .client
+            .query("SELECT count() FROM system.tables WHERE database = currentDatabase() AND name = ?")
+            .bind(table)
+            .fetch_one::<u64>()
+            .await
+            .with_context(|| format!("Failed to look up table {}", table))?;
+        Ok(count > 0)
+    }
+
+    /// Row count of `table`, or 0 if it does not exist
+    pub async fn row_count(&self, table: &str) -> Result<u64> {
+        let sql = format!("SELECT count() FROM {}", table);
+        match self.client.query(&sql).fetch_one::<u64>().await {
+            Ok(count) => Ok(count),
+            Err(clickhouse::error::Error::BadResponse(msg)) if msg.contains(UNKNOWN_TABLE) => Ok(0),
+            Err(e) => Err(e).with_context(|| format!("Failed to count rows in {}", table)),
+        }
+    }
+}
+
+fn truncate(sql: &str) -> String {
+    sql.chars().take(200).collect()
+}
+
+/// Remove `--` and `/* */` comments outside quoted strings and identifiers.
+/// The `clickhouse` client reads every `?` as a bind placeholder, so a `?`
+/// in a comment would otherwise fail the query as an unbound argument.
+pub fn strip_sql_comments(sql: &str) -> String {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Code,
+        Quoted(char),
+        LineComment,
+        BlockComment,
+    }
+
+    let mut out = String::with_capacity(sql.len());
+    let mut state = State::Code;
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match state {
+            State::Code => match c {
+                '\'' | '"' | '`' => {
+                    state = State::Quoted(c);
+                    out.push(c);
+                }
+                '-' if chars.peek() == Some(&'-') => state = State::LineComment,
+                '/' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    state = State::BlockComment;
+                }
+                _ => out.push(c),
+            },
+            State::Quoted(quote) => {
+                out.push(c);
+                if c == '\\' {
+                    if let Some(escaped) = chars.next() {
+                        out.push(escaped);
+                    }
+                } else if c == quote {
+                    state = State::Code;
+                }
+            }
+            State::LineComment => {
+                if c == '\n' {
+                    out.push(c);
+                    state = State::Code;
+                }
+            }
+            State::BlockComment => {
+                if c == '*' && chars.peek() == Some(&'/') {
+                    chars.next();
+                    // Keep tokens on either side of the comment apart
+                    out.push(' ');
+                    state = State::Code;
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Split SQL text into statements on `;`, ignoring semicolons inside quoted
+/// strings and identifiers. Comments are stripped first (see
+/// [`strip_sql_comments`]) and empty statements are dropped.
+pub fn split_sql_statements(sql: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let stripped = strip_sql_comments(sql);
+    let mut chars = stripped.chars();
+
+    while let Some(c) = chars.next() {
+        match quote {
+            None => match c {
+                ';' => {
+                    if !current.trim().is_empty() {
+                        statements.push(current.trim().to_string());
+                    }
+                    current.clear();
+                    continue;
+                }
+                '\'' | '"' | '`' => quote = Some(c),
+                _ => {}
+            },
+            Some(q) => {
+                if c == '\\' {
+                    current.push(c);
+                    if let Some(escaped) = chars.next() {
+                        current.push(escaped);
+                    }
+                    continue;
+                }
+                if c == q {
+                    quote = None;
+                }
+            }
+        }
+        current.push(c);
+    }
+
+    if !current.trim().is_empty() {
+        statements.push(current.trim().to_string());
+    }
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_sql_statements() {
+        let sql = "-- header comment\nCREATE TABLE t (x String);\n\nINSERT INTO t VALUES ('a;b');\n-- trailing comment;\n";
+        let statements = split_sql_statements(sql);
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0], "CREATE TABLE t (x String)");
+        assert_eq!(statements[1], "INSERT INTO t VALUES ('a;b')");
+    }
+
+    #[test]
+    fn test_split_ignores_semicolons_in_comments_and_escapes() {
+        let sql = "SELECT 'it\\'s; fine' /* a; b */ FROM t -- c; d\n;SELECT 1";
+        let statements = split_sql_statements(sql);
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0], "SELECT 'it\\'s; fine'   FROM t");
+        assert_eq!(statements[1], "SELECT 1");
+    }
+
+    #[test]
+    fn test_strip_sql_comments_keeps_literals() {
+        let sql = "SELECT '-- not? a comment', \"/*x*/\" -- WHERE id = ?\nFROM t /* ? */";
+        assert_eq!(
+            strip_sql_comments(sql),
+            "SELECT '-- not? a comment', \"/*x*/\" \nFROM t  "
+        );
+    }
+
+    /// Transforms run through [`SqlClient::query`] with bound arguments
+    const BOUND_SQL_FILES: &[&str] = &[
+        "conditional_variants_transform.sql",
+        "credible_sets_transform.sql",
+        "gene_associations_transform.sql",
+        "gene_test_counts_transform.sql",
+        "loci_variants_transform.sql",
+        "phenotype_peaks_transform.sql",
+        "significant_variants_transform.sql",
+        "top_gene_associations_transform.sql",
+        "variant_test_counts_transform.sql",
+    ];
+
+    /// Every embedded .sql file that runs unbound through [`SqlClient::execute`]
+    /// must be left without placeholders once comments are stripped
+    #[test]
+    fn test_embedded_sql_has_no_unbound_placeholders() {
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/sql");
+        let mut checked = 0;
+        for dir in [root.clone(), root.join("migrations")] {
+            for entry in std::fs::read_dir(&dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.extension().and_then(|e| e.to_str()) != Some("sql") {
+                    continue;
+                }
+                let name = path.file_name().unwrap().to_str().unwrap();
+                let sql = std::fs::read_to_string(&path).unwrap();
+                let statements = split_sql_statements(&sql);
+                let placeholders = statements.iter().any(|s| s.contains('?'));
+                assert_eq!(
+                    placeholders,
+                    BOUND_SQL_FILES.contains(&name),
+                    "{}: placeholders outside the bound transforms",
+                    name
+                );
+                checked += 1;
+            }
+        }
+        assert!(checked > 30);
+    }
+}
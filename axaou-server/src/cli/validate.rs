@@ -0,0 +1,488 @@
+//! Post-ingest data validation (`ingest validate`)
+//!
+//! Runs schema and sanity checks over the loaded tables and emits a JSON
+//! report:
+//! - key columns are non-null and non-empty
+//! - `xpos` agrees with `position` and is monotonic within each contig
+//! - p-values lie in (0, 1] (zeros from underflow are reported as warnings)
+//! - row counts are within `--max-row-change` of the previous validated load
+//! - every `gene_associations.gene_id` exists in `gene_models`
+//!
+//! Row counts of each run are recorded in `ingest_validation_runs` for the
+//! next comparison. The command fails if any check fails.
+
+use super::sql::SqlClient;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use clap::Args;
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+const INGEST_VALIDATION_RUNS_DDL: &str = include_str!("../sql/ingest_validation_runs.sql");
+
+/// Arguments for `ingest validate`
+#[derive(Debug, Args, Clone)]
+pub struct ValidateArgs {
+    /// ClickHouse URL
+    #[arg(long, default_value = "http://localhost:8123")]
+    pub clickhouse_url: String,
+
+    /// ClickHouse database name
+    #[arg(long, default_value = "default")]
+    pub database: String,
+
+    /// Only validate these tables (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    pub tables: Vec<String>,
+
+    /// Write the JSON report here instead of stdout
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// Allowed relative change in row count vs the previous validated load
+    #[arg(long, default_value = "0.2")]
+    pub max_row_change: f64,
+
+    /// Don't record this run's row counts
+    #[arg(long)]
+    pub no_record: bool,
+}
+
+/// Checks applied to one table
+struct TableSpec {
+    name: &'static str,
+    key_columns: &'static [&'static str],
+    pvalue_columns: &'static [&'static str],
+    /// Has `xpos`, `contig` and `position` columns
+    has_xpos: bool,
+}
+
+const TABLES: &[TableSpec] = &[
+    TableSpec {
+        name: "exome_annotations",
+        key_columns: &["xpos", "contig", "ref", "alt"],
+        pvalue_columns: &[],
+        has_xpos: true,
+    },
+    TableSpec {
+        name: "genome_annotations",
+        key_columns: &["xpos", "contig", "ref", "alt"],
+        pvalue_columns: &[],
+        has_xpos: true,
+    },
+    TableSpec {
+        name: "gene_models",
+        key_columns: &["gene_id", "symbol"],
+        pvalue_columns: &[],
+        has_xpos: false,
+    },
+    TableSpec {
+        name: "analysis_metadata",
+        key_columns: &["analysis_id", "ancestry_group"],
+        pvalue_columns: &[],
+        has_xpos: false,
+    },
+    TableSpec {
+        name: "gene_associations",
+        key_columns: &["gene_id", "phenotype", "ancestry", "annotation"],
+        pvalue_columns: &["pvalue", "pvalue_burden", "pvalue_skat"],
+        has_xpos: false,
+    },
+    TableSpec {
+        name: "significant_variants",
+        key_columns: &["phenotype", "ancestry", "xpos", "ref", "alt"],
+        pvalue_columns: &["pvalue"],
+        has_xpos: true,
+    },
+];
+
+/// Outcome of one check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    /// Offending rows (or values) found
+    pub violations: u64,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new(name: impl Into<String>, status: CheckStatus, violations: u64, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            violations,
+            detail: detail.into(),
+        }
+    }
+
+    /// Pass when there are no violations, otherwise `on_violation`
+    fn counted(name: impl Into<String>, violations: u64, on_violation: CheckStatus, detail: impl Into<String>) -> Self {
+        let status = if violations == 0 {
+            CheckStatus::Pass
+        } else {
+            on_violation
+        };
+        Self::new(name, status, violations, detail)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TableReport {
+    pub table: String,
+    pub exists: bool,
+    pub row_count: u64,
+    pub previous_row_count: Option<u64>,
+    pub checks: Vec<CheckResult>,
+}
+
+impl TableReport {
+    fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.status != CheckStatus::Fail)
+    }
+}
+
+/// Machine-readable validation report
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationReport {
+    pub generated_at: DateTime<Utc>,
+    pub database: String,
+    pub passed: bool,
+    pub tables: Vec<TableReport>,
+}
+
+#[derive(Debug, Row, Deserialize)]
+struct ContigRange {
+    contig: String,
+    min_xpos: i64,
+    max_xpos: i64,
+}
+
+/// Run `ingest validate`
+pub async fn run_validate(args: &ValidateArgs) -> Result<()> {
+    let sql = SqlClient::new(&args.clickhouse_url, &args.database);
+    sql.execute(INGEST_VALIDATION_RUNS_DDL).await?;
+
+    let mut tables = Vec::new();
+    for spec in TABLES {
+        if !args.tables.is_empty() && !args.tables.iter().any(|t| t == spec.name) {
+            continue;
+        }
+        info!("Validating {}...", spec.name);
+        tables.push(validate_table(&sql, spec, args.max_row_change).await?);
+    }
+
+    let report = ValidationReport {
+        generated_at: Utc::now(),
+        database: args.database.clone(),
+        passed: tables.iter().all(TableReport::passed),
+        tables,
+    };
+
+    if !args.no_record {
+        record_run(&sql, &report).await?;
+    }
+
+    let json = serde_json::to_string_pretty(&report)?;
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+            info!("Wrote validation report to {}", path.display());
+        }
+        None => println!("{}", json),
+    }
+
+    if !report.passed {
+        bail!("Validation failed");
+    }
+    Ok(())
+}
+
+async fn validate_table(sql: &SqlClient, spec: &TableSpec, max_row_change: f64) -> Result<TableReport> {
+    let mut report = TableReport {
+        table: spec.name.to_string(),
+        exists: sql.table_exists(spec.name).await?,
+        row_count: 0,
+        previous_row_count: None,
+        checks: Vec::new(),
+    };
+    if !report.exists {
+        warn!("Table {} does not exist; skipping", spec.name);
+        report
+            .checks
+            .push(CheckResult::new("exists", CheckStatus::Skipped, 0, "table not found"));
+        return Ok(report);
+    }
+
+    report.row_count = sql.row_count(spec.name).await?;
+    report.previous_row_count = previous_row_count(sql, spec.name).await?;
+    report.checks.push(row_count_check(
+        report.row_count,
+        report.previous_row_count,
+        max_row_change,
+    ));
+
+    for column in spec.key_columns {
+        let nulls = count(
+            sql,
+            &format!(
+                "SELECT countIf(isNull({c}) OR toString({c}) = '') FROM {t}",
+                c = column,
+                t = spec.name
+            ),
+        )
+        .await?;
+        report.checks.push(CheckResult::counted(
+            format!("key_coverage:{}", column),
+            nulls,
+            CheckStatus::Fail,
+            format!("{} rows with null or empty {}", nulls, column),
+        ));
+    }
+
+    if spec.has_xpos {
+        report.checks.extend(xpos_checks(sql, spec.name).await?);
+    }
+
+    for column in spec.pvalue_columns {
+        report.checks.extend(pvalue_checks(sql, spec.name, column).await?);
+    }
+
+    if spec.name == "gene_associations" {
+        report.checks.push(orphan_gene_check(sql).await?);
+    }
+
+    Ok(report)
+}
+
+async fn count(sql: &SqlClient, query: &str) -> Result<u64> {
+    sql.query(query)
+        .fetch_one::<u64>()
+        .await
+        .with_context(|| format!("Validation query failed: {}", query))
+}
+
+/// Compare the current row count with the last validated load
+fn row_count_check(current: u64, previous: Option<u64>, max_change: f64) -> CheckResult {
+    if current == 0 {
+        return CheckResult::new("row_count", CheckStatus::Fail, 0, "table is empty");
+    }
+    let Some(previous) = previous.filter(|&p| p > 0) else {
+        return CheckResult::new(
+            "row_count",
+            CheckStatus::Pass,
+            0,
+            format!("{} rows (no previous load to compare)", current),
+        );
+    };
+    let change = (current as f64 - previous as f64) / previous as f64;
+    let status = if change.abs() > max_change {
+        CheckStatus::Fail
+    } else {
+        CheckStatus::Pass
+    };
+    CheckResult::new(
+        "row_count",
+        status,
+        0,
+        format!(
+            "{} rows vs {} previously ({:+.1}%, limit ±{:.1}%)",
+            current,
+            previous,
+            change * 100.0,
+            max_change * 100.0
+        ),
+    )
+}
+
+async fn previous_row_count(sql: &SqlClient, table: &str) -> Result<Option<u64>> {
+    sql.query(
+        "SELECT row_count FROM ingest_validation_runs \
+         WHERE table_name = ? AND passed = 1 \
+         ORDER BY validated_at DESC LIMIT 1",
+    )
+    .bind(table)
+    .fetch_optional::<u64>()
+    .await
+    .context("Failed to read previous validation runs")
+}
+
+async fn record_run(sql: &SqlClient, report: &ValidationReport) -> Result<()> {
+    for table in report.tables.iter().filter(|t| t.exists) {
+        sql.query("INSERT INTO ingest_validation_runs (table_name, row_count, passed) VALUES (?, ?, ?)")
+            .bind(&table.table)
+            .bind(table.row_count)
+            .bind(table.passed() as u8)
+            .execute()
+            .await
+            .context("Failed to record validation run")?;
+    }
+    Ok(())
+}
+
+async fn xpos_checks(sql: &SqlClient, table: &str) -> Result<Vec<CheckResult>> {
+    let mismatched = count(
+        sql,
+        &format!(
+            "SELECT countIf(intDiv(xpos, 1000000000) = 0 OR modulo(xpos, 1000000000) != position) FROM {}",
+            table
+        ),
+    )
+    .await?;
+
+    let ranges = sql
+        .query(&format!(
+            "SELECT contig, min(xpos) AS min_xpos, max(xpos) AS max_xpos FROM {} GROUP BY contig",
+            table
+        ))
+        .fetch_all::<ContigRange>()
+        .await
+        .context("Failed to read contig xpos ranges")?;
+    let overlapping = overlapping_contigs(&ranges);
+
+    Ok(vec![
+        CheckResult::counted(
+            "xpos_position",
+            mismatched,
+            CheckStatus::Fail,
+            format!("{} rows where xpos does not encode position", mismatched),
+        ),
+        CheckResult::counted(
+            "xpos_monotonic",
+            overlapping.len() as u64,
+            CheckStatus::Fail,
+            if overlapping.is_empty() {
+                "xpos ranges of contigs are disjoint".to_string()
+            } else {
+                format!("overlapping xpos ranges: {}", overlapping.join(", "))
+            },
+        ),
+    ])
+}
+
+/// Contig pairs whose xpos ranges overlap, which means xpos does not increase
+/// monotonically through the genome
+fn overlapping_contigs(ranges: &[ContigRange]) -> Vec<String> {
+    let mut sorted: Vec<&ContigRange> = ranges.iter().collect();
+    sorted.sort_by_key(|r| r.min_xpos);
+    sorted
+        .windows(2)
+        .filter(|pair| pair[1].min_xpos <= pair[0].max_xpos)
+        .map(|pair| format!("{}/{}", pair[0].contig, pair[1].contig))
+        .collect()
+}
+
+async fn pvalue_checks(sql: &SqlClient, table: &str, column: &str) -> Result<Vec<CheckResult>> {
+    let out_of_range = count(
+        sql,
+        &format!(
+            "SELECT countIf(isNotNull({c}) AND (isNaN({c}) OR {c} < 0 OR {c} > 1)) FROM {t}",
+            c = column,
+            t = table
+        ),
+    )
+    .await?;
+    let zeros = count(
+        sql,
+        &format!("SELECT countIf({c} = 0) FROM {t}", c = column, t = table),
+    )
+    .await?;
+
+    Ok(vec![
+        CheckResult::counted(
+            format!("pvalue_range:{}", column),
+            out_of_range,
+            CheckStatus::Fail,
+            format!("{} values outside [0, 1] or NaN", out_of_range),
+        ),
+        CheckResult::counted(
+            format!("pvalue_zero:{}", column),
+            zeros,
+            CheckStatus::Warn,
+            format!("{} values of exactly 0 (underflow)", zeros),
+        ),
+    ])
+}
+
+async fn orphan_gene_check(sql: &SqlClient) -> Result<CheckResult> {
+    if !sql.table_exists("gene_models").await? {
+        return Ok(CheckResult::new(
+            "orphan_gene_ids",
+            CheckStatus::Skipped,
+            0,
+            "gene_models not loaded",
+        ));
+    }
+    let orphans = count(
+        sql,
+        "SELECT uniqExact(gene_id) FROM gene_associations \
+         WHERE gene_id NOT IN (SELECT gene_id FROM gene_models)",
+    )
+    .await?;
+    let sample = if orphans > 0 {
+        sql.query(
+            "SELECT DISTINCT gene_id FROM gene_associations \
+             WHERE gene_id NOT IN (SELECT gene_id FROM gene_models) LIMIT 10",
+        )
+        .fetch_all::<String>()
+        .await
+        .context("Failed to sample orphaned gene_ids")?
+    } else {
+        Vec::new()
+    };
+    Ok(CheckResult::counted(
+        "orphan_gene_ids",
+        orphans,
+        CheckStatus::Fail,
+        if sample.is_empty() {
+            "all gene_ids present in gene_models".to_string()
+        } else {
+            format!("{} gene_ids missing from gene_models, e.g. {}", orphans, sample.join(", "))
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_row_count_check() {
+        assert_eq!(row_count_check(0, Some(10), 0.2).status, CheckStatus::Fail);
+        assert_eq!(row_count_check(100, None, 0.2).status, CheckStatus::Pass);
+        assert_eq!(row_count_check(110, Some(100), 0.2).status, CheckStatus::Pass);
+        assert_eq!(row_count_check(70, Some(100), 0.2).status, CheckStatus::Fail);
+        assert_eq!(row_count_check(150, Some(100), 0.2).status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn test_overlapping_contigs() {
+        let range = |contig: &str, min_xpos: i64, max_xpos: i64| ContigRange {
+            contig: contig.to_string(),
+            min_xpos,
+            max_xpos,
+        };
+        let ranges = vec![
+            range("chr2", 2_000_000_100, 2_000_900_000),
+            range("chr1", 1_000_000_100, 1_000_900_000),
+        ];
+        assert!(overlapping_contigs(&ranges).is_empty());
+
+        let ranges = vec![
+            range("chr1", 1_000_000_100, 2_000_000_500),
+            range("chr2", 2_000_000_100, 2_000_900_000),
+        ];
+        assert_eq!(overlapping_contigs(&ranges), vec!["chr1/chr2"]);
+    }
+}
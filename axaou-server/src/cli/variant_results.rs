@@ -0,0 +1,643 @@
+//! Per-phenotype sharded ingest of variant association results
+//!
+//! `ingest variant-results` walks the variant result tables listed in a
+//! discovered assets file (see `discover`) and loads each
+//! (phenotype, ancestry, sequencing type) shard. Shards are grouped by the
+//! (ancestry, sequencing type) partition of the serving tables (see
+//! `partition_load`); for every partition:
+//! 1. Stage the partition of `significant_variants`, `loci_variants`,
+//!    `phenotype_peaks`, `conditional_variants` and `credible_sets`
+//! 2. Per shard, export its Hail Table to its own staging table and transform
+//!    it into the staged partitions: significant variants (p < threshold),
+//!    variants inside `loci`, their per-bin peaks and, if the phenotype has
+//!    conditional results or fine-mapped credible sets for the same ancestry
+//!    and sequencing type, those rows too; then record its variant test
+//!    counts in `analysis_test_counts`
+//! 3. Copy the other phenotypes' rows and swap each staged partition in with
+//!    `REPLACE PARTITION`
+//! 4. Persist annotated peaks to `phenotype_peak_annotations`
+//! 5. Drop the staging tables
+//!
+//! Partitions load concurrently (`--concurrency`). A failing shard keeps its
+//! phenotype's current rows and does not stop the rest.
+
+use super::ingest::{retarget_sql, IngestArgs};
+use super::partition_load::{
+    export_to_staging, group_by_partition, loader_export_args, new_run_id, read_assets,
+    table_name_part, PartitionStage,
+};
+use super::sql::SqlClient;
+use crate::models::{AnalysisAsset, AnalysisAssetType, AnalysisAssets, SequencingType};
+use crate::phenotype::manhattan::persist_peak_annotations;
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use futures::{stream, StreamExt};
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+const SIGNIFICANT_VARIANTS_DDL: &str = include_str!("../sql/significant_variants.sql");
+const SIGNIFICANT_VARIANTS_TRANSFORM: &str =
+    include_str!("../sql/significant_variants_transform.sql");
+const LOCI_VARIANTS_DDL: &str = include_str!("../sql/loci_variants.sql");
+const LOCI_VARIANTS_TRANSFORM: &str = include_str!("../sql/loci_variants_transform.sql");
+const CONDITIONAL_VARIANTS_DDL: &str = include_str!("../sql/conditional_variants.sql");
+const CONDITIONAL_VARIANTS_TRANSFORM: &str =
+    include_str!("../sql/conditional_variants_transform.sql");
+const CREDIBLE_SETS_DDL: &str = include_str!("../sql/credible_sets.sql");
+const CREDIBLE_SETS_TRANSFORM: &str = include_str!("../sql/credible_sets_transform.sql");
+const PHENOTYPE_PEAKS_DDL: &str = include_str!("../sql/phenotype_peaks.sql");
+const PHENOTYPE_PEAKS_TRANSFORM: &str = include_str!("../sql/phenotype_peaks_transform.sql");
+const PHENOTYPE_PEAK_ANNOTATIONS_DDL: &str = include_str!("../sql/phenotype_peak_annotations.sql");
+const ANALYSIS_TEST_COUNTS_DDL: &str = include_str!("../sql/analysis_test_counts.sql");
+const VARIANT_TEST_COUNTS_TRANSFORM: &str =
+    include_str!("../sql/variant_test_counts_transform.sql");
+
+/// Arguments for `ingest variant-results`
+#[derive(Debug, Args, Clone)]
+pub struct VariantResultsArgs {
+    /// Discovered assets JSON listing the variant result tables
+    #[arg(long, default_value = "assets.json")]
+    pub assets_file: PathBuf,
+
+    /// Only load these phenotypes (comma-separated analysis IDs)
+    #[arg(long, value_delimiter = ',')]
+    pub phenotypes: Vec<String>,
+
+    /// Only load these ancestry groups (comma-separated, e.g. meta,eur)
+    #[arg(long, value_delimiter = ',')]
+    pub ancestries: Vec<String>,
+
+    /// Only load this sequencing type (exomes or genomes)
+    #[arg(long)]
+    pub sequencing_type: Option<String>,
+
+    /// (ancestry, sequencing type) partitions loaded at once
+    #[arg(long, default_value = "4")]
+    pub concurrency: usize,
+
+    /// P-value threshold for significant_variants and is_significant
+    #[arg(long, default_value = "5e-8")]
+    pub significance_threshold: f64,
+
+    /// Cap on the effective number of independent variant tests per shard,
+    /// accounting for LD (1e6 gives the conventional 5e-8)
+    #[arg(long, default_value = "1e6")]
+    pub max_effective_tests: f64,
+
+    /// ClickHouse URL for local operations (DDL, transforms)
+    #[arg(long, default_value = "http://localhost:8123")]
+    pub clickhouse_url: String,
+
+    /// ClickHouse URL for remote/pool workers (used by hail-decoder export)
+    #[arg(long)]
+    pub remote_clickhouse_url: Option<String>,
+
+    /// ClickHouse database name
+    #[arg(long, default_value = "default")]
+    pub database: String,
+
+    /// Path to genohype binary
+    #[arg(long, default_value = "genohype")]
+    pub hail_decoder: String,
+
+    /// Submit exports to a worker pool instead of running locally
+    #[arg(long)]
+    pub pool: Option<String>,
+
+    /// Keep per-shard and per-partition staging tables for debugging
+    #[arg(long)]
+    pub keep_staging: bool,
+}
+
+/// One phenotype/ancestry/sequencing-type result table
+#[derive(Debug, Clone, PartialEq)]
+struct Shard {
+    phenotype: String,
+    ancestry: String,
+    /// "exome" or "genome", as stored in the serving tables
+    sequencing_type: &'static str,
+    uri: String,
+    /// Stepwise conditional results for the same shard, when produced
+    conditional_uri: Option<String>,
+    /// SuSiE/FINEMAP credible sets for the same shard, when produced
+    credible_set_uri: Option<String>,
+}
+
+impl Shard {
+    fn from_asset(asset: &AnalysisAsset) -> Option<Self> {
+        if asset.asset_type != AnalysisAssetType::Variant {
+            return None;
+        }
+        let sequencing_type = match asset.sequencing_type? {
+            SequencingType::Exomes => "exome",
+            SequencingType::Genomes => "genome",
+        };
+        Some(Self {
+            phenotype: asset.analysis_id.clone(),
+            ancestry: asset.ancestry_group.dir_name().to_lowercase(),
+            sequencing_type,
+            uri: asset.uri.clone(),
+            conditional_uri: None,
+            credible_set_uri: None,
+        })
+    }
+
+    fn label(&self) -> String {
+        format!(
+            "{}/{}/{}",
+            self.phenotype, self.ancestry, self.sequencing_type
+        )
+    }
+
+    /// Export staging table unique to this shard and run
+    fn staging_table(&self, run_id: &str) -> String {
+        format!(
+            "staging_variants_{}_{}_{}_{}",
+            table_name_part(&self.phenotype),
+            self.ancestry,
+            self.sequencing_type,
+            run_id
+        )
+    }
+}
+
+/// URI of the `asset_type` table produced alongside `shard`'s variant results
+fn companion_uri(
+    assets: &AnalysisAssets,
+    shard: &Shard,
+    asset_type: AnalysisAssetType,
+) -> Option<String> {
+    assets
+        .assets
+        .iter()
+        .filter(|a| a.asset_type == asset_type)
+        .filter(|a| a.analysis_id == shard.phenotype)
+        .filter(|a| {
+            a.ancestry_group
+                .dir_name()
+                .eq_ignore_ascii_case(&shard.ancestry)
+        })
+        .find(|a| match a.sequencing_type {
+            Some(SequencingType::Exomes) => shard.sequencing_type == "exome",
+            Some(SequencingType::Genomes) => shard.sequencing_type == "genome",
+            None => false,
+        })
+        .map(|a| a.uri.clone())
+}
+
+/// Variant result shards in `assets` matching the filters, in stable order
+fn select_shards(assets: &AnalysisAssets, args: &VariantResultsArgs) -> Vec<Shard> {
+    let wanted_seq = args
+        .sequencing_type
+        .as_deref()
+        .map(|s| s.trim_end_matches('s').to_lowercase());
+    let mut shards: Vec<Shard> = assets
+        .assets
+        .iter()
+        .filter_map(Shard::from_asset)
+        .filter(|s| args.phenotypes.is_empty() || args.phenotypes.contains(&s.phenotype))
+        .filter(|s| {
+            args.ancestries.is_empty()
+                || args
+                    .ancestries
+                    .iter()
+                    .any(|a| a.eq_ignore_ascii_case(&s.ancestry))
+        })
+        .filter(|s| wanted_seq.as_deref().is_none_or(|w| w == s.sequencing_type))
+        .collect();
+    shards.sort_by(|a, b| a.label().cmp(&b.label()));
+    shards.dedup();
+
+    for shard in &mut shards {
+        shard.conditional_uri = companion_uri(assets, shard, AnalysisAssetType::Conditional);
+        shard.credible_set_uri = companion_uri(assets, shard, AnalysisAssetType::CredibleSet);
+    }
+    shards
+}
+
+/// Staged partitions of the variant serving tables
+struct VariantStages {
+    significant_variants: PartitionStage,
+    loci_variants: PartitionStage,
+    phenotype_peaks: PartitionStage,
+    conditional_variants: PartitionStage,
+    credible_sets: PartitionStage,
+}
+
+impl VariantStages {
+    async fn create(
+        sql: &SqlClient,
+        run_id: &str,
+        ancestry: &str,
+        sequencing_type: &str,
+    ) -> Result<Self> {
+        let key = || {
+            vec![
+                ("ancestry", ancestry.to_string()),
+                ("sequencing_type", sequencing_type.to_string()),
+            ]
+        };
+        Ok(Self {
+            significant_variants: PartitionStage::create(
+                sql,
+                "significant_variants",
+                run_id,
+                key(),
+            )
+            .await?,
+            loci_variants: PartitionStage::create(sql, "loci_variants", run_id, key()).await?,
+            phenotype_peaks: PartitionStage::create(sql, "phenotype_peaks", run_id, key()).await?,
+            conditional_variants: PartitionStage::create(
+                sql,
+                "conditional_variants",
+                run_id,
+                key(),
+            )
+            .await?,
+            credible_sets: PartitionStage::create(sql, "credible_sets", run_id, key()).await?,
+        })
+    }
+
+    fn all(&self) -> [&PartitionStage; 5] {
+        [
+            &self.significant_variants,
+            &self.loci_variants,
+            &self.phenotype_peaks,
+            &self.conditional_variants,
+            &self.credible_sets,
+        ]
+    }
+}
+
+/// Run `ingest variant-results`
+pub async fn run_variant_results(args: &VariantResultsArgs) -> Result<()> {
+    let assets = read_assets(&args.assets_file)?;
+    let shards = select_shards(&assets, args);
+    if shards.is_empty() {
+        bail!("No variant result tables match the given filters");
+    }
+    let total = shards.len();
+    let partitions = group_by_partition(shards, |s| (s.ancestry.clone(), s.sequencing_type));
+    let run_id = new_run_id();
+    info!(
+        "Loading {} variant result shards in {} partitions ({} at a time, run {})",
+        total,
+        partitions.len(),
+        args.concurrency,
+        run_id
+    );
+
+    let sql = SqlClient::new(&args.clickhouse_url, &args.database);
+    sql.execute(SIGNIFICANT_VARIANTS_DDL).await?;
+    sql.execute(LOCI_VARIANTS_DDL).await?;
+    sql.execute(CONDITIONAL_VARIANTS_DDL).await?;
+    sql.execute(CREDIBLE_SETS_DDL).await?;
+    sql.execute(PHENOTYPE_PEAKS_DDL).await?;
+    sql.execute(PHENOTYPE_PEAK_ANNOTATIONS_DDL).await?;
+    sql.execute(ANALYSIS_TEST_COUNTS_DDL).await?;
+
+    let failures: Vec<String> = stream::iter(partitions)
+        .map(|((ancestry, sequencing_type), shards)| {
+            let (sql, run_id) = (sql.clone(), run_id.clone());
+            async move {
+                let labels: Vec<String> = shards.iter().map(Shard::label).collect();
+                let loaded =
+                    load_partition(&sql, &run_id, &ancestry, sequencing_type, &shards, args).await;
+                match loaded {
+                    Ok(failed) => failed,
+                    Err(e) => {
+                        warn!(
+                            "Failed to load partition {}/{}: {:#}",
+                            ancestry, sequencing_type, e
+                        );
+                        labels
+                    }
+                }
+            }
+        })
+        .buffer_unordered(args.concurrency.max(1))
+        .flat_map(stream::iter)
+        .collect()
+        .await;
+
+    info!("Loaded {} of {} shards", total - failures.len(), total);
+    if !failures.is_empty() {
+        bail!("{} shards failed: {}", failures.len(), failures.join(", "));
+    }
+    Ok(())
+}
+
+/// Load the shards of one (ancestry, sequencing type) partition and swap the
+/// rebuilt partitions in; returns the labels of the shards that failed
+async fn load_partition(
+    sql: &SqlClient,
+    run_id: &str,
+    ancestry: &str,
+    sequencing_type: &str,
+    shards: &[Shard],
+    args: &VariantResultsArgs,
+) -> Result<Vec<String>> {
+    let stages = VariantStages::create(sql, run_id, ancestry, sequencing_type).await?;
+    let export_args = loader_export_args(
+        &args.clickhouse_url,
+        &args.database,
+        args.remote_clickhouse_url.as_deref(),
+        &args.hail_decoder,
+        args.pool.as_deref(),
+    );
+
+    let mut loaded = Vec::new();
+    let mut failed = Vec::new();
+    for shard in shards {
+        match load_shard(sql, shard, &stages, run_id, &export_args, args).await {
+            Ok(()) => loaded.push(shard.phenotype.clone()),
+            Err(e) => {
+                warn!("Failed to load {}: {:#}", shard.label(), e);
+                for stage in stages.all() {
+                    stage.discard(sql, &shard.phenotype).await?;
+                }
+                failed.push(shard.label());
+            }
+        }
+    }
+
+    if !loaded.is_empty() {
+        for stage in stages.all() {
+            let rows = stage.swap(sql, &loaded).await?;
+            info!(
+                "[{}/{}] Swapped in {} ({} rows)",
+                ancestry,
+                sequencing_type,
+                stage.table(),
+                rows
+            );
+        }
+
+        // Peaks are a serving optimization; the server computes them live if missing
+        for shard in shards.iter().filter(|s| loaded.contains(&s.phenotype)) {
+            match persist_peak_annotations(
+                sql.client(),
+                &shard.phenotype,
+                &shard.ancestry,
+                shard.sequencing_type,
+            )
+            .await
+            {
+                Ok(count) => info!("[{}] Persisted {} peaks", shard.label(), count),
+                Err(e) => warn!(
+                    "[{}] Failed to persist peak annotations: {}",
+                    shard.label(),
+                    e
+                ),
+            }
+        }
+    }
+
+    if !args.keep_staging {
+        for stage in stages.all() {
+            stage.drop(sql).await?;
+        }
+    }
+    Ok(failed)
+}
+
+/// Export one shard and transform it into the staged partitions
+async fn load_shard(
+    sql: &SqlClient,
+    shard: &Shard,
+    stages: &VariantStages,
+    run_id: &str,
+    export_args: &IngestArgs,
+    args: &VariantResultsArgs,
+) -> Result<()> {
+    let staging = shard.staging_table(run_id);
+    let companions = [
+        (
+            &shard.conditional_uri,
+            &stages.conditional_variants,
+            CONDITIONAL_VARIANTS_TRANSFORM,
+        ),
+        (
+            &shard.credible_set_uri,
+            &stages.credible_sets,
+            CREDIBLE_SETS_TRANSFORM,
+        ),
+    ];
+
+    let result = async {
+        info!("[{}] Exporting {} -> {}", shard.label(), shard.uri, staging);
+        export_to_staging(sql, &staging, &shard.uri, export_args).await?;
+
+        let transform = |sql_text: &str, stage: &PartitionStage| {
+            retarget_sql(sql_text, stage.table(), stage.staging()).replace("{staging}", &staging)
+        };
+        sql.query(&transform(
+            SIGNIFICANT_VARIANTS_TRANSFORM,
+            &stages.significant_variants,
+        ))
+        .bind(&shard.phenotype)
+        .bind(&shard.ancestry)
+        .bind(shard.sequencing_type)
+        .bind(args.significance_threshold)
+        .execute()
+        .await
+        .with_context(|| {
+            format!(
+                "significant_variants transform failed for {}",
+                shard.label()
+            )
+        })?;
+
+        sql.query(&transform(LOCI_VARIANTS_TRANSFORM, &stages.loci_variants))
+            .bind(&shard.phenotype)
+            .bind(&shard.ancestry)
+            .bind(shard.sequencing_type)
+            .bind(args.significance_threshold)
+            .bind(&shard.phenotype)
+            .bind(&shard.ancestry)
+            .execute()
+            .await
+            .with_context(|| format!("loci_variants transform failed for {}", shard.label()))?;
+
+        let peaks = retarget_sql(
+            PHENOTYPE_PEAKS_TRANSFORM,
+            stages.phenotype_peaks.table(),
+            stages.phenotype_peaks.staging(),
+        )
+        .replace("{staging}", stages.loci_variants.staging());
+        sql.query(&peaks)
+            .bind(&shard.phenotype)
+            .execute()
+            .await
+            .with_context(|| format!("phenotype_peaks transform failed for {}", shard.label()))?;
+
+        for (uri, stage, transform) in companions {
+            if let Some(uri) = uri {
+                load_companion(sql, shard, uri, &staging, stage, transform, export_args).await?;
+            }
+        }
+
+        // Test counts replace the previous row, so they go in last, once
+        // every staged insert has succeeded
+        sql.query(&VARIANT_TEST_COUNTS_TRANSFORM.replace("{staging}", &staging))
+            .bind(&shard.phenotype)
+            .bind(&shard.ancestry)
+            .bind(shard.sequencing_type)
+            .bind(args.max_effective_tests)
+            .execute()
+            .await
+            .with_context(|| format!("Failed to record test counts for {}", shard.label()))
+    }
+    .await;
+
+    if !args.keep_staging {
+        sql.execute_statement(&format!("DROP TABLE IF EXISTS {}", staging))
+            .await?;
+        for (uri, stage, _) in companions {
+            if uri.is_some() {
+                sql.execute_statement(&format!(
+                    "DROP TABLE IF EXISTS {}_{}",
+                    staging,
+                    stage.table()
+                ))
+                .await?;
+            }
+        }
+    }
+    if result.is_ok() {
+        info!("[{}] Staged", shard.label());
+    }
+    result
+}
+
+/// Transform the shard's per-locus companion table (conditional results,
+/// credible sets) into its staged partition; runs after loci_variants so
+/// the loci are current
+async fn load_companion(
+    sql: &SqlClient,
+    shard: &Shard,
+    uri: &str,
+    shard_staging: &str,
+    stage: &PartitionStage,
+    transform: &str,
+    export_args: &IngestArgs,
+) -> Result<()> {
+    let staging = format!("{}_{}", shard_staging, stage.table());
+    info!("[{}] Exporting {} -> {}", shard.label(), uri, staging);
+    export_to_staging(sql, &staging, uri, export_args).await?;
+
+    sql.query(
+        &retarget_sql(transform, stage.table(), stage.staging()).replace("{staging}", &staging),
+    )
+    .bind(&shard.phenotype)
+    .bind(&shard.ancestry)
+    .bind(shard.sequencing_type)
+    .bind(&shard.phenotype)
+    .bind(&shard.ancestry)
+    .execute()
+    .await
+    .with_context(|| format!("{} transform failed for {}", stage.table(), shard.label()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AncestryGroup;
+    use crate::test_support::factories::asset;
+
+    fn args(phenotypes: &[&str], sequencing_type: Option<&str>) -> VariantResultsArgs {
+        VariantResultsArgs {
+            assets_file: PathBuf::from("assets.json"),
+            phenotypes: phenotypes.iter().map(|p| p.to_string()).collect(),
+            ancestries: Vec::new(),
+            sequencing_type: sequencing_type.map(String::from),
+            concurrency: 4,
+            significance_threshold: 5e-8,
+            max_effective_tests: 1e6,
+            clickhouse_url: String::new(),
+            remote_clickhouse_url: None,
+            database: "default".to_string(),
+            hail_decoder: "genohype".to_string(),
+            pool: None,
+            keep_staging: false,
+        }
+    }
+
+    #[test]
+    fn test_select_shards() {
+        let assets = AnalysisAssets {
+            assets: vec![
+                asset(
+                    "height",
+                    AncestryGroup::Meta,
+                    AnalysisAssetType::Variant,
+                    Some(SequencingType::Exomes),
+                ),
+                asset(
+                    "height",
+                    AncestryGroup::Meta,
+                    AnalysisAssetType::Variant,
+                    Some(SequencingType::Genomes),
+                ),
+                asset("height", AncestryGroup::Meta, AnalysisAssetType::Gene, None),
+                asset(
+                    "height",
+                    AncestryGroup::Meta,
+                    AnalysisAssetType::Conditional,
+                    Some(SequencingType::Genomes),
+                ),
+                asset(
+                    "bmi",
+                    AncestryGroup::Eur,
+                    AnalysisAssetType::CredibleSet,
+                    Some(SequencingType::Genomes),
+                ),
+                asset(
+                    "bmi",
+                    AncestryGroup::Eur,
+                    AnalysisAssetType::Variant,
+                    Some(SequencingType::Genomes),
+                ),
+            ],
+            ..Default::default()
+        };
+
+        let all = select_shards(&assets, &args(&[], None));
+        assert_eq!(all.len(), 3);
+
+        let shards = select_shards(&assets, &args(&["height"], Some("genomes")));
+        assert_eq!(shards.len(), 1);
+        assert_eq!(shards[0].ancestry, "meta");
+        assert_eq!(shards[0].sequencing_type, "genome");
+        assert_eq!(
+            shards[0].staging_table("1a2b3c4d"),
+            "staging_variants_height_meta_genome_1a2b3c4d"
+        );
+        assert_eq!(
+            shards[0].conditional_uri.as_deref(),
+            Some("gs://aou_results/414k/ht_results/META/phenotype_height/genome_conditional_results.ht")
+        );
+        // Only the genome shard of height has conditional results
+        assert_eq!(all[1].label(), "height/meta/exome");
+        assert_eq!(all[1].conditional_uri, None);
+        assert_eq!(all[0].conditional_uri, None);
+        assert_eq!(
+            all[0].credible_set_uri.as_deref(),
+            Some("gs://aou_results/414k/ht_results/EUR/phenotype_bmi/genome_credible_sets.ht")
+        );
+        assert_eq!(shards[0].credible_set_uri, None);
+
+        // One partition per (ancestry, sequencing type), shards in order
+        let partitions = group_by_partition(all, |s| (s.ancestry.clone(), s.sequencing_type));
+        let keys: Vec<(String, &str)> = partitions.iter().map(|(k, _)| k.clone()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                ("eur".to_string(), "genome"),
+                ("meta".to_string(), "exome"),
+                ("meta".to_string(), "genome"),
+            ]
+        );
+    }
+}
@@ -3,25 +3,89 @@
 use crate::error::AppError;
 use clickhouse::Client;
 use std::env;
+use std::time::Duration;
+
+/// Longest a health check ping may take before ClickHouse counts as down
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Create a ClickHouse client connection
 ///
 /// Reads configuration from environment variables:
 /// - `CLICKHOUSE_URL`: Connection URL (default: `http://localhost:8123`)
 /// - `CLICKHOUSE_DATABASE`: Database name (default: `default`)
+/// - `CLICKHOUSE_USER` / `CLICKHOUSE_PASSWORD`: Credentials (optional)
 pub fn connect() -> Client {
-    let url = env::var("CLICKHOUSE_URL").unwrap_or_else(|_| "http://localhost:8123".to_string());
-    let database = env::var("CLICKHOUSE_DATABASE").unwrap_or_else(|_| "default".to_string());
+    connect_to_database(None)
+}
 
-    Client::default().with_url(url).with_database(database)
+/// Create a ClickHouse client for a specific database
+///
+/// Uses `CLICKHOUSE_URL` for the server; `database` overrides `CLICKHOUSE_DATABASE`
+/// so several datasets can share one ClickHouse server.
+pub fn connect_to_database(database: Option<&str>) -> Client {
+    with_credentials(
+        Client::default()
+            .with_url(url())
+            .with_database(database_name(database)),
+    )
 }
 
-/// Check ClickHouse connectivity
-pub async fn health_check(client: &Client) -> Result<(), AppError> {
+/// Apply `CLICKHOUSE_USER` / `CLICKHOUSE_PASSWORD` when set
+pub fn with_credentials(mut client: Client) -> Client {
+    if let Ok(user) = env::var("CLICKHOUSE_USER") {
+        client = client.with_user(user);
+    }
+    if let Ok(password) = env::var("CLICKHOUSE_PASSWORD") {
+        client = client.with_password(password);
+    }
     client
-        .query("SELECT 1")
-        .fetch_one::<u8>()
+}
+
+/// ClickHouse server URL from `CLICKHOUSE_URL`
+pub fn url() -> String {
+    env::var("CLICKHOUSE_URL").unwrap_or_else(|_| "http://localhost:8123".to_string())
+}
+
+/// Database name: `database` if given, else `CLICKHOUSE_DATABASE`, else "default"
+pub fn database_name(database: Option<&str>) -> String {
+    database
+        .map(|d| d.to_string())
+        .or_else(|| env::var("CLICKHOUSE_DATABASE").ok())
+        .unwrap_or_else(|| "default".to_string())
+}
+
+/// Check ClickHouse connectivity, failing after [`HEALTH_CHECK_TIMEOUT`] so a
+/// hung connection can't block readiness
+pub async fn health_check(client: &Client) -> Result<(), AppError> {
+    health_check_within(client, HEALTH_CHECK_TIMEOUT).await
+}
+
+async fn health_check_within(client: &Client, timeout: Duration) -> Result<(), AppError> {
+    tokio::time::timeout(timeout, client.query("SELECT 1").fetch_one::<u8>())
         .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse health check failed: {}", e)))?;
+        .map_err(|_| AppError::Timeout(format!("ClickHouse health check exceeded {:?}", timeout)))?
+        .map_err(|e| AppError::UpstreamClickHouse(format!("ClickHouse health check failed: {}", e)))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_health_check_times_out_on_hung_server() {
+        // Accepts connections and never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
+            }
+        });
+
+        let client = Client::default().with_url(url);
+        let result = health_check_within(&client, Duration::from_millis(100)).await;
+        assert!(matches!(result, Err(AppError::Timeout(_))));
+    }
+}
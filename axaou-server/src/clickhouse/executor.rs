@@ -0,0 +1,494 @@
+//! Query execution policy: timeouts, retries and circuit breaking
+//!
+//! Handlers run queries through [`QueryExt`], e.g.
+//!
+//! ```ignore
+//! let rows = state
+//!     .clickhouse
+//!     .query(sql)
+//!     .bind(&analysis_id)
+//!     .fetch_all_with::<LocusRow>(&state.executor)
+//!     .await?;
+//! ```
+//!
+//! Each attempt is bounded by a timeout. Transient failures (network errors,
+//! timeouts, ClickHouse overload codes) are retried with jittered exponential
+//! backoff; after `breaker_threshold` consecutive transient failures the
+//! breaker opens and queries fail fast with [`AppError::NotReady`] (503 with
+//! `Retry-After`) until the cooldown elapses and a trial query succeeds. Non-transient errors (bad SQL,
+//! row decoding) are returned immediately and do not trip the breaker. A
+//! query whose final attempt timed out surfaces as [`AppError::Timeout`].
+//!
+//! Every query is also recorded in the executor's [`QueryLog`] by
+//! fingerprint, with its total duration (including retries) and row count,
+//! and counted towards the current request's [`DbUsage`].
+//!
+//! Configured per dataset via the `[query_policy]` config section.
+
+use super::query_log::QueryLog;
+use crate::error::AppError;
+use clickhouse::query::Query;
+use clickhouse::Row;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use axum::{extract::Request, middleware::Next, response::Response};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// `[query_policy]` config section
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct QueryPolicyConfig {
+    /// Per-attempt timeout in seconds
+    pub timeout_secs: u64,
+    /// Retries after the first attempt for transient errors
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled per retry
+    pub base_backoff_ms: u64,
+    /// Upper bound on a single backoff
+    pub max_backoff_ms: u64,
+    /// Consecutive transient failures that open the breaker
+    pub breaker_threshold: u32,
+    /// Seconds the breaker stays open before allowing a trial query
+    pub breaker_cooldown_secs: u64,
+    /// Queries taking at least this long are logged as slow
+    pub slow_query_ms: u64,
+}
+
+impl Default for QueryPolicyConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 30,
+            max_retries: 2,
+            base_backoff_ms: 100,
+            max_backoff_ms: 2000,
+            breaker_threshold: 5,
+            breaker_cooldown_secs: 30,
+            slow_query_ms: 1000,
+        }
+    }
+}
+
+/// ClickHouse server error codes worth retrying
+const TRANSIENT_CODES: &[&str] = &[
+    "Code: 159.", // TIMEOUT_EXCEEDED
+    "Code: 202.", // TOO_MANY_SIMULTANEOUS_QUERIES
+    "Code: 209.", // SOCKET_TIMEOUT
+    "Code: 210.", // NETWORK_ERROR
+    "Code: 242.", // TABLE_IS_READ_ONLY
+    "Code: 252.", // TOO_MANY_PARTS
+];
+
+/// Whether a failed query may succeed if retried
+fn is_transient(error: &clickhouse::error::Error) -> bool {
+    use clickhouse::error::Error;
+    match error {
+        Error::Network(_) | Error::TimedOut => true,
+        Error::BadResponse(msg) => TRANSIENT_CODES.iter().any(|code| msg.contains(code)),
+        _ => false,
+    }
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+/// Outcome of a single attempt
+enum Attempt<T> {
+    Ok(T),
+    Transient(String),
+    TimedOut,
+    Fatal(AppError),
+}
+
+tokio::task_local! {
+    static DB_USAGE: Arc<DbUsage>;
+}
+
+/// ClickHouse queries issued while serving one request
+///
+/// Overlapping queries (e.g. under `join!`) count once towards `db_time`,
+/// which is the wall time during which at least one query was running.
+#[derive(Debug, Default)]
+pub struct DbUsage {
+    inner: Mutex<DbUsageInner>,
+}
+
+#[derive(Debug, Default)]
+struct DbUsageInner {
+    queries: u32,
+    in_flight: u32,
+    busy_since: Option<Instant>,
+    busy: Duration,
+}
+
+impl DbUsage {
+    /// Usage of the request being served by the current task, if tracked
+    pub fn current() -> Option<Arc<DbUsage>> {
+        DB_USAGE.try_with(Arc::clone).ok()
+    }
+
+    /// Number of queries started so far
+    pub fn queries(&self) -> u32 {
+        self.inner.lock().unwrap().queries
+    }
+
+    /// Seconds spent waiting on ClickHouse so far
+    pub fn db_time(&self) -> f64 {
+        let inner = self.inner.lock().unwrap();
+        let running = inner.busy_since.map(|since| since.elapsed()).unwrap_or_default();
+        (inner.busy + running).as_secs_f64()
+    }
+
+    fn begin(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.queries += 1;
+        if inner.in_flight == 0 {
+            inner.busy_since = Some(Instant::now());
+        }
+        inner.in_flight += 1;
+    }
+
+    fn end(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.in_flight = inner.in_flight.saturating_sub(1);
+        if inner.in_flight == 0 {
+            if let Some(since) = inner.busy_since.take() {
+                inner.busy += since.elapsed();
+            }
+        }
+    }
+}
+
+/// Middleware tracking the [`DbUsage`] of each request, read by
+/// [`crate::response::QueryTimer`]
+pub async fn track_db_usage(request: Request, next: Next) -> Response {
+    DB_USAGE
+        .scope(Arc::new(DbUsage::default()), next.run(request))
+        .await
+}
+
+/// Executes queries under a [`QueryPolicyConfig`]; shared per dataset
+#[derive(Debug)]
+pub struct QueryExecutor {
+    policy: QueryPolicyConfig,
+    breaker: Mutex<BreakerState>,
+    /// Per-fingerprint statistics of the queries run
+    pub queries: QueryLog,
+}
+
+impl QueryExecutor {
+    pub fn new(policy: QueryPolicyConfig) -> Self {
+        Self {
+            queries: QueryLog::new(Duration::from_millis(policy.slow_query_ms)),
+            policy,
+            breaker: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    /// Whether the breaker is currently rejecting queries
+    pub fn is_open(&self) -> bool {
+        let breaker = self.breaker.lock().unwrap();
+        breaker.open_until.is_some_and(|until| Instant::now() < until)
+    }
+
+    fn record_success(&self) {
+        let mut breaker = self.breaker.lock().unwrap();
+        breaker.consecutive_failures = 0;
+        breaker.open_until = None;
+    }
+
+    fn record_transient_failure(&self) {
+        let mut breaker = self.breaker.lock().unwrap();
+        breaker.consecutive_failures += 1;
+        if breaker.consecutive_failures >= self.policy.breaker_threshold {
+            if breaker.open_until.is_none() {
+                warn!(
+                    "ClickHouse circuit breaker opened after {} consecutive failures",
+                    breaker.consecutive_failures
+                );
+            }
+            breaker.open_until =
+                Some(Instant::now() + Duration::from_secs(self.policy.breaker_cooldown_secs));
+        }
+    }
+
+    /// Jittered exponential backoff before retry `retry` (0-based)
+    fn backoff(&self, retry: u32) -> Duration {
+        let exp = self
+            .policy
+            .base_backoff_ms
+            .saturating_mul(1u64 << retry.min(16))
+            .min(self.policy.max_backoff_ms);
+        // Equal jitter: half fixed, half random
+        let jitter = (rand::random::<f64>() * (exp / 2) as f64) as u64;
+        Duration::from_millis(exp / 2 + jitter)
+    }
+
+    /// Run `attempt` (which re-issues the query) under the policy
+    async fn run<T, F, Fut>(&self, attempt: F) -> Result<T, AppError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, clickhouse::error::Error>>,
+    {
+        if self.is_open() {
+            return Err(AppError::NotReady(
+                "ClickHouse unavailable (circuit breaker open)".to_string(),
+            ));
+        }
+
+        let timeout = Duration::from_secs(self.policy.timeout_secs);
+        let mut last_error = None;
+        for retry in 0..=self.policy.max_retries {
+            if retry > 0 {
+                tokio::time::sleep(self.backoff(retry - 1)).await;
+            }
+
+            let outcome = match tokio::time::timeout(timeout, attempt()).await {
+                Ok(Ok(value)) => Attempt::Ok(value),
+                Ok(Err(e)) if is_transient(&e) => Attempt::Transient(e.to_string()),
+                Ok(Err(e)) => Attempt::Fatal(AppError::UpstreamClickHouse(format!(
+                    "query error: {}",
+                    e
+                ))),
+                Err(_) => Attempt::TimedOut,
+            };
+
+            match outcome {
+                Attempt::Ok(value) => {
+                    self.record_success();
+                    return Ok(value);
+                }
+                Attempt::Fatal(e) => return Err(e),
+                Attempt::Transient(msg) => {
+                    warn!("Transient ClickHouse error (attempt {}): {}", retry + 1, msg);
+                    last_error = Some(AppError::UpstreamClickHouse(format!("query failed: {}", msg)));
+                }
+                Attempt::TimedOut => {
+                    warn!("ClickHouse query timed out after {:?} (attempt {})", timeout, retry + 1);
+                    last_error = Some(AppError::Timeout(format!(
+                        "ClickHouse query exceeded {:?}",
+                        timeout
+                    )));
+                }
+            }
+
+            self.record_transient_failure();
+            if self.is_open() {
+                break;
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| AppError::UpstreamClickHouse("query failed".to_string())))
+    }
+
+    /// Run `attempt` under the policy and record it in [`Self::queries`]
+    async fn run_logged<T, F, Fut>(
+        &self,
+        sql: &str,
+        rows: impl Fn(&T) -> usize,
+        attempt: F,
+    ) -> Result<T, AppError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, clickhouse::error::Error>>,
+    {
+        let usage = DbUsage::current();
+        if let Some(usage) = &usage {
+            usage.begin();
+        }
+        let start = Instant::now();
+        let result = self.run(attempt).await;
+        if let Some(usage) = &usage {
+            usage.end();
+        }
+        self.queries
+            .record(sql, start.elapsed(), result.as_ref().ok().map(&rows));
+        result
+    }
+}
+
+/// Policy-aware fetch methods for ClickHouse queries
+pub trait QueryExt {
+    fn fetch_all_with<T>(self, executor: &QueryExecutor) -> impl Future<Output = Result<Vec<T>, AppError>>
+    where
+        T: Row + DeserializeOwned;
+
+    fn fetch_one_with<T>(self, executor: &QueryExecutor) -> impl Future<Output = Result<T, AppError>>
+    where
+        T: Row + DeserializeOwned;
+
+    fn fetch_optional_with<T>(
+        self,
+        executor: &QueryExecutor,
+    ) -> impl Future<Output = Result<Option<T>, AppError>>
+    where
+        T: Row + DeserializeOwned;
+}
+
+impl QueryExt for Query {
+    async fn fetch_all_with<T>(self, executor: &QueryExecutor) -> Result<Vec<T>, AppError>
+    where
+        T: Row + DeserializeOwned,
+    {
+        let sql = self.sql_display().to_string();
+        executor
+            .run_logged(&sql, Vec::len, || self.clone().fetch_all::<T>())
+            .await
+    }
+
+    async fn fetch_one_with<T>(self, executor: &QueryExecutor) -> Result<T, AppError>
+    where
+        T: Row + DeserializeOwned,
+    {
+        let sql = self.sql_display().to_string();
+        executor
+            .run_logged(&sql, |_| 1, || self.clone().fetch_one::<T>())
+            .await
+    }
+
+    async fn fetch_optional_with<T>(self, executor: &QueryExecutor) -> Result<Option<T>, AppError>
+    where
+        T: Row + DeserializeOwned,
+    {
+        let sql = self.sql_display().to_string();
+        executor
+            .run_logged(&sql, |row| row.is_some() as usize, || self.clone().fetch_optional::<T>())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn policy() -> QueryPolicyConfig {
+        QueryPolicyConfig {
+            timeout_secs: 5,
+            max_retries: 2,
+            base_backoff_ms: 1,
+            max_backoff_ms: 2,
+            breaker_threshold: 3,
+            breaker_cooldown_secs: 60,
+            slow_query_ms: 1000,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_errors() {
+        let executor = QueryExecutor::new(policy());
+        let calls = AtomicU32::new(0);
+        let result = executor
+            .run(|| async {
+                if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                    Err(clickhouse::error::Error::TimedOut)
+                } else {
+                    Ok(42)
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(!executor.is_open());
+    }
+
+    #[tokio::test]
+    async fn test_fatal_errors_are_not_retried() {
+        let executor = QueryExecutor::new(policy());
+        let calls = AtomicU32::new(0);
+        let result: Result<u8, _> = executor
+            .run(|| async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(clickhouse::error::Error::BadResponse(
+                    "Code: 62. DB::Exception: Syntax error".to_string(),
+                ))
+            })
+            .await;
+        assert!(matches!(result, Err(AppError::UpstreamClickHouse(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_breaker_opens_and_fails_fast() {
+        let executor = QueryExecutor::new(policy());
+        let calls = AtomicU32::new(0);
+        let failing = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err::<u8, _>(clickhouse::error::Error::TimedOut)
+        };
+
+        // Three attempts (1 + 2 retries) reach the threshold
+        assert!(matches!(executor.run(failing).await, Err(AppError::UpstreamClickHouse(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(executor.is_open());
+
+        // Open breaker rejects without calling
+        assert!(matches!(executor.run(failing).await, Err(AppError::NotReady(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_queries_are_recorded() {
+        let executor = QueryExecutor::new(policy());
+        let rows = executor
+            .run_logged("SELECT id FROM t WHERE id = 'a'", Vec::len, || async {
+                Ok(vec![1, 2])
+            })
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+
+        let stats = executor.queries.top(Default::default(), 10);
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].fingerprint, "SELECT id FROM t WHERE id = ?");
+        assert_eq!(stats[0].rows, 2);
+    }
+
+    #[tokio::test]
+    async fn test_db_usage_is_tracked_per_task() {
+        let executor = QueryExecutor::new(policy());
+        let query = || async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(vec![1])
+        };
+
+        let usage = Arc::new(DbUsage::default());
+        DB_USAGE
+            .scope(usage.clone(), async {
+                // Overlapping queries count once towards db_time
+                let (a, b) = tokio::join!(
+                    executor.run_logged("SELECT 1", Vec::len, query),
+                    executor.run_logged("SELECT 2", Vec::len, query),
+                );
+                a.unwrap();
+                b.unwrap();
+            })
+            .await;
+        assert_eq!(usage.queries(), 2);
+        assert!(usage.db_time() >= 0.05 && usage.db_time() < 0.1);
+
+        // Outside a tracked request nothing is recorded
+        assert!(DbUsage::current().is_none());
+        executor.run_logged("SELECT 3", Vec::len, query).await.unwrap();
+        assert_eq!(usage.queries(), 2);
+    }
+
+    #[test]
+    fn test_backoff_is_bounded() {
+        let executor = QueryExecutor::new(QueryPolicyConfig {
+            base_backoff_ms: 100,
+            max_backoff_ms: 1000,
+            ..Default::default()
+        });
+        for retry in 0..10 {
+            let backoff = executor.backoff(retry);
+            assert!(backoff <= Duration::from_millis(1000));
+        }
+        assert!(executor.backoff(0) >= Duration::from_millis(50));
+    }
+}
@@ -3,9 +3,12 @@
 //! Provides connectivity to ClickHouse for variant and locus queries.
 
 pub mod client;
+pub mod executor;
 pub mod models;
+pub mod query_log;
 pub mod xpos;
 
 pub use client::connect;
+pub use executor::QueryExt;
 pub use models::*;
 pub use xpos::*;
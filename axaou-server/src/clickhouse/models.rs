@@ -4,17 +4,22 @@
 
 use crate::clickhouse::xpos::{make_variant_id, make_variant_id_from_xpos};
 use crate::models::{
-    Exon, GeneAssociationApi, GeneModel, GnomadConstraint, Locus, ManeSelectTranscript, Transcript,
-    VariantAnnotationApi, VariantAssociationApi,
+    AncestryFrequency, AncestryGroup, Exon, GeneAssociationApi, GeneModel, GnomadConstraint, Locus,
+    ManeSelectTranscript, Transcript, VariantAnnotationApi, VariantAssociationApi,
 };
 use clickhouse::Row;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Columns of [`LocusRow`], in field order
+pub const LOCUS_COLUMNS: &str = "locus_id, phenotype, ancestry, contig, start, stop, xstart, xstop, \
+    source, lead_variant, lead_pvalue, exome_count, genome_count, plot_gcs_uri";
 
 /// Locus metadata from the `loci` table
 ///
 /// Contains summary information about a genomic locus including
 /// lead variant, variant counts, and plot URIs.
-#[derive(Debug, Clone, Serialize, Deserialize, Row)]
+#[derive(Debug, Clone, Serialize, Deserialize, Row, ToSchema)]
 pub struct LocusRow {
     pub locus_id: String,
     pub phenotype: String,
@@ -36,7 +41,7 @@ pub struct LocusRow {
 ///
 /// Contains the minimal data needed for Manhattan plot rendering:
 /// position, p-value, and significance flag.
-#[derive(Debug, Clone, Serialize, Deserialize, Row)]
+#[derive(Debug, Clone, Serialize, Deserialize, Row, ToSchema)]
 pub struct LocusVariantRow {
     pub xpos: i64,
     pub position: i32,
@@ -45,10 +50,35 @@ pub struct LocusVariantRow {
     pub is_significant: bool,
 }
 
+/// Locus variant with its LD to the locus lead variant
+///
+/// `lead_r2` is set for significant variants in LD with the lead variant at
+/// or above the requested r² (from `ld_pairs`), and null otherwise. `pip`
+/// and `credible_set` are not queried with the row; they are merged in from
+/// `credible_sets` when the locus was fine-mapped.
+#[derive(Debug, Clone, Serialize, Deserialize, Row, ToSchema)]
+pub struct LocusVariantLdRow {
+    pub xpos: i64,
+    #[serde(rename = "ref")]
+    pub ref_allele: String,
+    pub alt: String,
+    pub position: i32,
+    pub pvalue: f64,
+    pub neg_log10_p: f32,
+    pub is_significant: bool,
+    pub lead_r2: Option<f32>,
+    /// Fine-mapping posterior inclusion probability
+    #[serde(default, skip_deserializing)]
+    pub pip: Option<f64>,
+    /// Credible set of the variant within the locus
+    #[serde(default, skip_deserializing)]
+    pub credible_set: Option<i32>,
+}
+
 /// Extended locus variant with locus context
 ///
 /// Includes locus_id for queries that return variants across multiple loci.
-#[derive(Debug, Clone, Serialize, Deserialize, Row)]
+#[derive(Debug, Clone, Serialize, Deserialize, Row, ToSchema)]
 pub struct LocusVariantExtendedRow {
     pub locus_id: String,
     pub xpos: i64,
@@ -213,6 +243,12 @@ impl VariantAnnotationRow {
             polyphen2: None,
             amino_acids: None,
             lof: None,
+            cadd_phred: None,
+            revel_max: None,
+            spliceai_ds_max: None,
+            alphamissense_score: None,
+            alphamissense_class: None,
+            ancestry_frequencies: None,
         }
     }
 }
@@ -237,6 +273,12 @@ impl VariantAnnotationExtendedRow {
             polyphen2: self.polyphen2.clone(),
             amino_acids: self.amino_acids.clone(),
             lof: self.lof.clone(),
+            cadd_phred: self.cadd_phred,
+            revel_max: self.revel_max,
+            spliceai_ds_max: self.spliceai_ds_max,
+            alphamissense_score: self.alphamissense_score,
+            alphamissense_class: self.alphamissense_class.clone(),
+            ancestry_frequencies: None,
         }
     }
 }
@@ -288,7 +330,7 @@ pub struct GeneAssociationRow {
 
 impl GeneAssociationRow {
     /// Compute -log10(p) with cap for underflowed values
-    fn compute_neg_log10_p(p: Option<f64>) -> Option<f64> {
+    pub(crate) fn compute_neg_log10_p(p: Option<f64>) -> Option<f64> {
         p.map(|v| {
             if v <= 0.0 {
                 350.0 // Cap for underflowed p-values
@@ -317,6 +359,9 @@ impl GeneAssociationRow {
             mac: self.mac,
             contig: self.contig.clone(),
             gene_start_position: self.gene_start_position,
+            carrier_frequency: None,
+            expected_carriers: None,
+            power_class: None,
         }
     }
 }
@@ -324,7 +369,7 @@ impl GeneAssociationRow {
 /// Point for Q-Q plot from the `qq_points` table
 ///
 /// Contains observed and expected p-values for Q-Q plot rendering.
-#[derive(Debug, Clone, Serialize, Deserialize, Row)]
+#[derive(Debug, Clone, Serialize, Deserialize, Row, ToSchema)]
 pub struct QQRow {
     pub phenotype: String,
     pub ancestry: String,
@@ -382,9 +427,102 @@ pub struct VariantAnnotationExtendedRow {
     pub amino_acids: Option<String>,
     pub polyphen2: Option<String>,
     pub lof: Option<String>,
+    pub cadd_phred: Option<f32>,
+    pub revel_max: Option<f32>,
+    pub spliceai_ds_max: Option<f32>,
+    pub alphamissense_score: Option<f32>,
+    pub alphamissense_class: Option<String>,
     pub filters: Vec<String>,
 }
 
+/// Per-ancestry allele counts and frequencies of a variant from
+/// exome_annotations or genome_annotations
+///
+/// Only the single-variant endpoint returns these, so they are kept out of
+/// [`VariantAnnotationExtendedRow`] and the bulk queries.
+#[derive(Debug, Clone, Serialize, Deserialize, Row)]
+pub struct AncestryFrequencyRow {
+    pub ac_afr: Option<u32>,
+    pub af_afr: Option<f64>,
+    pub an_afr: Option<u32>,
+    pub ac_amr: Option<u32>,
+    pub af_amr: Option<f64>,
+    pub an_amr: Option<u32>,
+    pub ac_eas: Option<u32>,
+    pub af_eas: Option<f64>,
+    pub an_eas: Option<u32>,
+    pub ac_eur: Option<u32>,
+    pub af_eur: Option<f64>,
+    pub an_eur: Option<u32>,
+    pub ac_mid: Option<u32>,
+    pub af_mid: Option<f64>,
+    pub an_mid: Option<u32>,
+    pub ac_sas: Option<u32>,
+    pub af_sas: Option<f64>,
+    pub an_sas: Option<u32>,
+}
+
+impl AncestryFrequencyRow {
+    /// Per-ancestry allele counts and frequencies, in [`AncestryGroup::all`] order
+    pub fn ancestry_frequencies(&self) -> Vec<AncestryFrequency> {
+        [
+            (AncestryGroup::Afr, self.ac_afr, self.an_afr, self.af_afr),
+            (AncestryGroup::Amr, self.ac_amr, self.an_amr, self.af_amr),
+            (AncestryGroup::Eas, self.ac_eas, self.an_eas, self.af_eas),
+            (AncestryGroup::Eur, self.ac_eur, self.an_eur, self.af_eur),
+            (AncestryGroup::Mid, self.ac_mid, self.an_mid, self.af_mid),
+            (AncestryGroup::Sas, self.ac_sas, self.an_sas, self.af_sas),
+        ]
+        .into_iter()
+        .map(|(ancestry, ac, an, af)| AncestryFrequency {
+            ancestry: ancestry.to_string(),
+            allele_count: ac,
+            allele_number: an,
+            allele_frequency: af,
+        })
+        .collect()
+    }
+}
+
+/// Previously reported association from the `known_associations` table
+///
+/// Written by `ingest known-hits` from a GWAS Catalog associations TSV.
+#[derive(Debug, Clone, Serialize, Deserialize, Row, ToSchema)]
+pub struct KnownAssociationRow {
+    /// Catalog the row came from, e.g. "gwas_catalog"
+    pub source: String,
+    pub study_accession: String,
+    pub pubmed_id: String,
+    /// Trait as reported by the study
+    pub reported_trait: String,
+    /// Ontology trait labels
+    pub mapped_traits: Vec<String>,
+    /// Ontology trait IDs, e.g. "EFO_0004340"
+    pub efo_ids: Vec<String>,
+    pub rsid: String,
+    pub contig: String,
+    pub position: u32,
+    pub xpos: i64,
+    /// Reported risk allele ("?" when not reported)
+    pub risk_allele: String,
+    pub pvalue: f64,
+    pub reported_genes: Vec<String>,
+}
+
+/// Point of the recombination map from the `recombination_rates` table
+///
+/// Written by `ingest recombination-rates` from a HapMap-style genetic map.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Row, ToSchema)]
+pub struct RecombinationRateRow {
+    pub contig: String,
+    pub position: u32,
+    pub xpos: i64,
+    /// Recombination rate in cM/Mb
+    pub rate: f64,
+    /// Cumulative genetic map position in cM
+    pub genetic_map_cm: f64,
+}
+
 /// Gene model row from the gene_models ClickHouse table
 ///
 /// Maps to the flattened schema with Nested exons and JSON transcripts.
@@ -530,12 +668,7 @@ impl GeneModelRow {
 
     /// Parse transcripts JSON string to Vec<Transcript>
     fn transcripts_to_vec(&self) -> Vec<Transcript> {
-        if self.transcripts_json.is_empty() || self.transcripts_json == "[]" {
-            return Vec::new();
-        }
-
-        // Try to parse as JSON
-        serde_json::from_str(&self.transcripts_json).unwrap_or_default()
+        parse_transcripts_json(&self.transcripts_json)
     }
 
     /// Build GnomadConstraint from flattened fields
@@ -590,6 +723,28 @@ impl GeneModelRow {
     }
 }
 
+/// Parse a `gene_models.transcripts_json` value; malformed JSON yields no transcripts
+pub fn parse_transcripts_json(json: &str) -> Vec<Transcript> {
+    if json.is_empty() || json == "[]" {
+        return Vec::new();
+    }
+    serde_json::from_str(json).unwrap_or_default()
+}
+
+/// Gene ID and transcripts only, for transcript lookups that skip the rest of
+/// the gene model
+#[derive(Debug, Clone, Serialize, Deserialize, Row)]
+pub struct GeneTranscriptsRow {
+    pub gene_id: String,
+    pub transcripts_json: String,
+}
+
+impl GeneTranscriptsRow {
+    pub fn transcripts(&self) -> Vec<Transcript> {
+        parse_transcripts_json(&self.transcripts_json)
+    }
+}
+
 /// Aggregated variant result from the `significant_variants` table
 ///
 /// Contains top phenotype and total number of associations for a variant.
@@ -701,6 +856,9 @@ pub struct AnalysisMetadataRow {
     pub keep_pheno_burden: u8,
     pub keep_pheno_skat: u8,
     pub keep_pheno_skato: u8,
+    pub heritability: Option<f64>,
+    pub heritability_se: Option<f64>,
+    pub heritability_method: Option<String>,
 }
 
 impl AnalysisMetadataRow {
@@ -725,6 +883,9 @@ impl AnalysisMetadataRow {
             keep_pheno_burden: self.keep_pheno_burden != 0,
             keep_pheno_skat: self.keep_pheno_skat != 0,
             keep_pheno_skato: self.keep_pheno_skato != 0,
+            heritability: self.heritability,
+            heritability_se: self.heritability_se,
+            heritability_method: self.heritability_method.clone(),
         }
     }
 }
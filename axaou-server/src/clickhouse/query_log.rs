@@ -0,0 +1,333 @@
+//! Per-fingerprint ClickHouse query statistics and slow-query logging
+//!
+//! Every query run through [`super::QueryExt`] is recorded under a
+//! fingerprint: the SQL with literals replaced by `?`, `IN` lists collapsed
+//! and whitespace normalized, so the same handler query with different
+//! phenotypes or intervals lands in one bucket. Queries slower than
+//! `query_policy.slow_query_ms` are logged with their full SQL. The
+//! aggregates are served at `GET /api/admin/slow-queries`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::warn;
+
+/// Distinct fingerprints tracked; further new ones are counted but not kept
+const MAX_FINGERPRINTS: usize = 2000;
+
+/// Longest SQL kept as the example of a fingerprint
+const MAX_EXAMPLE_LEN: usize = 2000;
+
+/// Normalize SQL into a fingerprint: literals become `?`, `IN (...)` lists
+/// become `(?+)`, whitespace collapses and keywords are not case-folded
+pub fn fingerprint(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                // String literal, with '' and \' escapes
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '\'' if chars.peek() == Some(&'\'') => {
+                            chars.next();
+                        }
+                        '\'' => break,
+                        _ => {}
+                    }
+                }
+                out.push('?');
+            }
+            c if c.is_ascii_digit() && !ends_with_identifier(&out) => {
+                // Digits, decimals, hex and exponents (1.5e-8)
+                let mut prev = c;
+                while let Some(&next) = chars.peek() {
+                    let exponent_sign = matches!(next, '-' | '+') && matches!(prev, 'e' | 'E');
+                    if !(next.is_ascii_alphanumeric() || next == '.' || next == '_' || exponent_sign) {
+                        break;
+                    }
+                    prev = next;
+                    chars.next();
+                }
+                // Fold the sign of negative numbers into the placeholder
+                if out.ends_with('-') && !ends_with_operand(&out[..out.len() - 1]) {
+                    out.pop();
+                }
+                out.push('?');
+            }
+            c if c.is_whitespace() => {
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                if !out.is_empty() {
+                    out.push(' ');
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    collapse_lists(out.trim_end())
+}
+
+fn ends_with_identifier(s: &str) -> bool {
+    s.chars()
+        .last()
+        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '`')
+}
+
+fn ends_with_operand(s: &str) -> bool {
+    s.trim_end()
+        .chars()
+        .last()
+        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == ')' || c == '?')
+}
+
+/// Replace parenthesized and bracketed lists of placeholders with `(?+)`/`[?+]`
+fn collapse_lists(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(pos) = rest.find(['(', '[']) {
+        out.push_str(&rest[..pos]);
+        let close = if rest.as_bytes()[pos] == b'(' { ')' } else { ']' };
+        let after = &rest[pos + 1..];
+        let list = after.find(close).map(|end| &after[..end]);
+        match list {
+            Some(list)
+                if list.contains(',')
+                    && list.split(',').all(|item| item.trim() == "?") =>
+            {
+                out.push(rest.as_bytes()[pos] as char);
+                out.push_str("?+");
+                out.push(close);
+                rest = &after[list.len() + 1..];
+            }
+            _ => {
+                out.push(rest.as_bytes()[pos] as char);
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Stable short identifier of a fingerprint (FNV-1a), used in log lines
+pub fn fingerprint_id(fingerprint: &str) -> String {
+    let hash = fingerprint
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |h, b| (h ^ b as u64).wrapping_mul(0x100000001b3));
+    format!("{:016x}", hash)
+}
+
+#[derive(Debug, Clone)]
+struct FingerprintStats {
+    example: String,
+    calls: u64,
+    errors: u64,
+    slow: u64,
+    total_ms: u64,
+    max_ms: u64,
+    rows: u64,
+    last_seen: chrono::DateTime<chrono::Utc>,
+}
+
+/// Aggregated statistics of one fingerprint, as served by the admin API
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryStats {
+    pub id: String,
+    pub fingerprint: String,
+    /// Most recent SQL with this fingerprint
+    pub example: String,
+    pub calls: u64,
+    pub errors: u64,
+    /// Calls over the slow-query threshold
+    pub slow: u64,
+    pub total_ms: u64,
+    pub mean_ms: f64,
+    pub max_ms: u64,
+    /// Rows returned, summed over calls
+    pub rows: u64,
+    pub last_seen: String,
+}
+
+/// Ordering of [`QueryLog::top`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryStatsSort {
+    /// Slowest single call first
+    #[default]
+    Max,
+    /// Most total database time first
+    Total,
+    /// Highest mean duration first
+    Mean,
+    /// Most calls first
+    Calls,
+}
+
+/// Query statistics keyed by fingerprint
+#[derive(Debug)]
+pub struct QueryLog {
+    slow_threshold: Duration,
+    stats: Mutex<HashMap<String, FingerprintStats>>,
+    dropped: Mutex<u64>,
+}
+
+impl QueryLog {
+    pub fn new(slow_threshold: Duration) -> Self {
+        Self {
+            slow_threshold,
+            stats: Mutex::new(HashMap::new()),
+            dropped: Mutex::new(0),
+        }
+    }
+
+    pub fn slow_threshold(&self) -> Duration {
+        self.slow_threshold
+    }
+
+    /// Record one query; `rows` is `None` when it failed
+    pub fn record(&self, sql: &str, elapsed: Duration, rows: Option<usize>) {
+        let fingerprint = fingerprint(sql);
+        let ms = elapsed.as_millis() as u64;
+        let slow = elapsed >= self.slow_threshold;
+        if slow {
+            warn!(
+                fingerprint = %fingerprint_id(&fingerprint),
+                duration_ms = ms,
+                rows = rows.unwrap_or(0),
+                failed = rows.is_none(),
+                "Slow ClickHouse query: {}",
+                truncate(sql, MAX_EXAMPLE_LEN)
+            );
+        }
+
+        let mut stats = self.stats.lock().unwrap();
+        if !stats.contains_key(&fingerprint) && stats.len() >= MAX_FINGERPRINTS {
+            *self.dropped.lock().unwrap() += 1;
+            return;
+        }
+        let entry = stats
+            .entry(fingerprint)
+            .or_insert_with(|| FingerprintStats {
+                example: String::new(),
+                calls: 0,
+                errors: 0,
+                slow: 0,
+                total_ms: 0,
+                max_ms: 0,
+                rows: 0,
+                last_seen: chrono::Utc::now(),
+            });
+        entry.example = truncate(sql, MAX_EXAMPLE_LEN).to_string();
+        entry.calls += 1;
+        entry.errors += rows.is_none() as u64;
+        entry.slow += slow as u64;
+        entry.total_ms += ms;
+        entry.max_ms = entry.max_ms.max(ms);
+        entry.rows += rows.unwrap_or(0) as u64;
+        entry.last_seen = chrono::Utc::now();
+    }
+
+    /// The `limit` top fingerprints by `sort`
+    pub fn top(&self, sort: QueryStatsSort, limit: usize) -> Vec<QueryStats> {
+        let stats = self.stats.lock().unwrap();
+        let mut top: Vec<QueryStats> = stats
+            .iter()
+            .map(|(fingerprint, s)| QueryStats {
+                id: fingerprint_id(fingerprint),
+                fingerprint: fingerprint.clone(),
+                example: s.example.clone(),
+                calls: s.calls,
+                errors: s.errors,
+                slow: s.slow,
+                total_ms: s.total_ms,
+                mean_ms: s.total_ms as f64 / s.calls as f64,
+                max_ms: s.max_ms,
+                rows: s.rows,
+                last_seen: s.last_seen.to_rfc3339(),
+            })
+            .collect();
+        top.sort_by(|a, b| match sort {
+            QueryStatsSort::Max => b.max_ms.cmp(&a.max_ms),
+            QueryStatsSort::Total => b.total_ms.cmp(&a.total_ms),
+            QueryStatsSort::Mean => b.mean_ms.total_cmp(&a.mean_ms),
+            QueryStatsSort::Calls => b.calls.cmp(&a.calls),
+        });
+        top.truncate(limit);
+        top
+    }
+
+    /// Fingerprints tracked, and new fingerprints dropped at the cap
+    pub fn counts(&self) -> (usize, u64) {
+        (self.stats.lock().unwrap().len(), *self.dropped.lock().unwrap())
+    }
+
+    /// Forget all recorded statistics
+    pub fn reset(&self) {
+        self.stats.lock().unwrap().clear();
+        *self.dropped.lock().unwrap() = 0;
+    }
+}
+
+fn truncate(s: &str, max: usize) -> &str {
+    match s.char_indices().nth(max) {
+        Some((i, _)) => &s[..i],
+        None => s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint() {
+        let a = "SELECT * FROM loci_variants\n  WHERE phenotype = 'height' AND xpos >= 1055039974 AND pvalue < 5e-8 LIMIT 100";
+        let b = "SELECT * FROM loci_variants WHERE phenotype = 'it''s' AND xpos >= 2000 AND pvalue < 1.2e-3 LIMIT 10";
+        assert_eq!(fingerprint(a), fingerprint(b));
+        assert_eq!(
+            fingerprint(a),
+            "SELECT * FROM loci_variants WHERE phenotype = ? AND xpos >= ? AND pvalue < ? LIMIT ?"
+        );
+
+        // Identifiers with digits are kept, IN lists of any length collapse
+        assert_eq!(
+            fingerprint("SELECT log10(p) FROM t1 WHERE id IN ('a', 'b', 'c') AND x = -5"),
+            "SELECT log10(p) FROM t1 WHERE id IN (?+) AND x = ?"
+        );
+        assert_eq!(
+            fingerprint("SELECT 1 FROM t WHERE id IN ('a','b')"),
+            fingerprint("SELECT 1 FROM t WHERE id IN ('a', 'b', 'c', 'd')")
+        );
+        assert_eq!(fingerprint("SELECT x - 1 FROM t"), "SELECT x - ? FROM t");
+    }
+
+    #[test]
+    fn test_query_log_aggregates() {
+        let log = QueryLog::new(Duration::from_millis(100));
+        log.record("SELECT * FROM t WHERE id = 'a'", Duration::from_millis(10), Some(3));
+        log.record("SELECT * FROM t WHERE id = 'b'", Duration::from_millis(250), Some(5));
+        log.record("SELECT count() FROM u", Duration::from_millis(50), None);
+        log.record("SELECT count() FROM u", Duration::from_millis(60), Some(1));
+
+        let top = log.top(QueryStatsSort::Max, 10);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].fingerprint, "SELECT * FROM t WHERE id = ?");
+        assert_eq!(top[0].calls, 2);
+        assert_eq!(top[0].slow, 1);
+        assert_eq!(top[0].rows, 8);
+        assert_eq!(top[0].example, "SELECT * FROM t WHERE id = 'b'");
+        assert_eq!(top[1].errors, 1);
+
+        let by_calls = log.top(QueryStatsSort::Calls, 1);
+        assert_eq!(by_calls.len(), 1);
+
+        log.reset();
+        assert_eq!(log.counts(), (0, 0));
+    }
+}
@@ -10,26 +10,19 @@
 //! - 23: X chromosome
 //! - 24: Y chromosome
 //! - 25: MT/M chromosome
+//!
+//! Contig names are normalized by [`Contig`]; see `genomics::contig` for
+//! which tables store `chr`-prefixed names.
 
 use crate::error::AppError;
+use crate::genomics::Contig;
 
 /// Convert chromosome and position to xpos (legacy gnomAD style int64)
 ///
-/// Formula: `contig_num * 1_000_000_000 + position`
+/// Formula: `contig_num * 1_000_000_000 + position`. Returns 0 for an
+/// unknown contig.
 pub fn compute_xpos(contig: &str, position: u32) -> i64 {
-    let contig_normalized = contig.trim_start_matches("chr");
-    let contig_num = match contig_normalized.to_uppercase().as_str() {
-        "X" => 23,
-        "Y" => 24,
-        "M" | "MT" => 25,
-        _ => contig_normalized.parse::<i64>().unwrap_or(0),
-    };
-
-    if contig_num == 0 {
-        return 0; // Invalid contig
-    }
-
-    contig_num * 1_000_000_000 + position as i64
+    Contig::parse(contig).map_or(0, |c| c.xpos(position))
 }
 
 /// Parse variant ID "chr1-12345-A-T" or "1-12345-A-T" -> (xpos, ref, alt)
@@ -64,26 +57,20 @@ pub fn parse_variant_id(variant_id: &str) -> Result<(i64, String, String), AppEr
 ///
 /// Formula: `contig_num = xpos / 1_000_000_000`, `position = xpos % 1_000_000_000`
 pub fn reverse_xpos(xpos: i64) -> (String, u32) {
-    let contig_num = xpos / 1_000_000_000;
     let position = (xpos % 1_000_000_000) as u32;
-
-    let contig = match contig_num {
-        1..=22 => contig_num.to_string(),
-        23 => "X".to_string(),
-        24 => "Y".to_string(),
-        25 => "M".to_string(),
-        _ => "?".to_string(),
-    };
-
-    (contig, position)
+    let contig = Contig::from_xpos(xpos).map_or("?", Contig::bare);
+    (contig.to_string(), position)
 }
 
 /// Generate a variant ID string from components
 ///
 /// Format: "chr{contig}-{position}-{ref}-{alt}"
 pub fn make_variant_id(contig: &str, position: u32, ref_allele: &str, alt_allele: &str) -> String {
-    let normalized = contig.trim_start_matches("chr");
-    format!("chr{}-{}-{}-{}", normalized, position, ref_allele, alt_allele)
+    let normalized = match Contig::parse(contig) {
+        Some(c) => c.chr().to_string(),
+        None => format!("chr{}", contig.trim_start_matches("chr")),
+    };
+    format!("{}-{}-{}-{}", normalized, position, ref_allele, alt_allele)
 }
 
 /// Generate a variant ID string from xpos and alleles
@@ -123,9 +110,7 @@ pub fn parse_partial_variant_id(query: &str) -> Option<PartialVariantId> {
     }
 
     // Validate contig is real
-    if compute_xpos(contig, 1) == 0 {
-        return None;
-    }
+    Contig::parse(contig)?;
 
     let ref_allele = parts.get(2).unwrap_or(&"").to_uppercase();
     let alt_allele = parts.get(3).unwrap_or(&"").to_uppercase();
@@ -138,44 +123,6 @@ pub fn parse_partial_variant_id(query: &str) -> Option<PartialVariantId> {
     })
 }
 
-pub fn parse_interval_to_xpos(interval: &str) -> Result<(i64, i64), AppError> {
-    let parts: Vec<&str> = interval.split(':').collect();
-    if parts.len() != 2 {
-        return Err(AppError::InvalidInterval(format!(
-            "Invalid interval format '{}'. Expected chr:start-end",
-            interval
-        )));
-    }
-
-    let contig = parts[0];
-    let range_parts: Vec<&str> = parts[1].split('-').collect();
-    if range_parts.len() != 2 {
-        return Err(AppError::InvalidInterval(format!(
-            "Invalid range format in interval '{}'. Expected start-end",
-            interval
-        )));
-    }
-
-    let start: u32 = range_parts[0].parse().map_err(|_| {
-        AppError::InvalidInterval(format!("Invalid start position: {}", range_parts[0]))
-    })?;
-    let end: u32 = range_parts[1].parse().map_err(|_| {
-        AppError::InvalidInterval(format!("Invalid end position: {}", range_parts[1]))
-    })?;
-
-    let xpos_start = compute_xpos(contig, start);
-    let xpos_end = compute_xpos(contig, end);
-
-    if xpos_start == 0 || xpos_end == 0 {
-        return Err(AppError::InvalidInterval(format!(
-            "Invalid chromosome in interval: {}",
-            contig
-        )));
-    }
-
-    Ok((xpos_start, xpos_end))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -189,6 +136,10 @@ mod tests {
         assert_eq!(compute_xpos("chrX", 5000), 23_000_005_000);
         assert_eq!(compute_xpos("Y", 100), 24_000_000_100);
         assert_eq!(compute_xpos("MT", 1), 25_000_000_001);
+        assert_eq!(compute_xpos("chrY", 100), 24_000_000_100);
+        assert_eq!(compute_xpos("chrM", 1), 25_000_000_001);
+        assert_eq!(compute_xpos("chrx", 5000), 23_000_005_000);
+        assert_eq!(compute_xpos("chrZ", 1), 0);
     }
 
     #[test]
@@ -202,13 +153,23 @@ mod tests {
         assert_eq!(xpos, 22_000_001_000);
         assert_eq!(ref_a, "ACGT");
         assert_eq!(alt_a, "G");
+
+        assert_eq!(parse_variant_id("chrY-100-A-T").unwrap().0, 24_000_000_100);
+        assert_eq!(parse_variant_id("MT-73-A-G").unwrap().0, 25_000_000_073);
+        assert!(parse_variant_id("chrZ-100-A-T").is_err());
     }
 
     #[test]
     fn test_parse_interval() {
-        let (start, end) = parse_interval_to_xpos("chr1:100-200").unwrap();
+        let crate::genomics::RegionQuery::Interval(region) =
+            crate::genomics::parse_region("chr1:100-200").unwrap()
+        else {
+            panic!("expected an interval");
+        };
+        let (start, end) = region.xpos_range();
         assert_eq!(start, 1_000_000_100);
         assert_eq!(end, 1_000_000_200);
+        assert_eq!(start, compute_xpos("chr1", 100));
     }
 
     #[test]
@@ -230,6 +191,8 @@ mod tests {
             make_variant_id("chr22", 1000, "ACGT", "G"),
             "chr22-1000-ACGT-G"
         );
+        assert_eq!(make_variant_id("MT", 73, "A", "G"), "chrM-73-A-G");
+        assert_eq!(make_variant_id("x", 5000, "C", "G"), "chrX-5000-C-G");
     }
 
     #[test]
@@ -266,5 +229,13 @@ mod tests {
             make_variant_id_from_xpos(23_000_005_000, "C", "G"),
             "chrX-5000-C-G"
         );
+        assert_eq!(
+            make_variant_id_from_xpos(24_000_000_100, "A", "T"),
+            "chrY-100-A-T"
+        );
+        assert_eq!(
+            make_variant_id_from_xpos(25_000_000_073, "A", "G"),
+            "chrM-73-A-G"
+        );
     }
 }
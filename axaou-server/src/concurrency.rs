@@ -0,0 +1,274 @@
+//! Per-endpoint-class concurrency limits with load shedding
+//!
+//! Requests are grouped by path into classes that compete for different
+//! resources: plot image, thumbnail and download proxies (GCS bandwidth),
+//! ClickHouse queries, and Hail-backed queries (Hail Tables read from GCS and
+//! decoded in-process, `query_mode=slow` and region renders). Each class has its own
+//! cap on requests in flight. A request arriving while its class is full is
+//! shed at once with 503 and `Retry-After` instead of queueing, so a crawl of
+//! plot images cannot tie up the connections interactive queries need.
+//! Streamed responses (e.g. download tarballs) keep their slot until the body
+//! has been sent.
+//!
+//! Health, config, version and admin routes are never limited.
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::StreamExt;
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Seconds a shed client is asked to wait before retrying
+const RETRY_AFTER_SECS: u64 = 1;
+
+/// Resource class of an endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndpointClass {
+    /// Plot images and downloads proxied from GCS
+    Images,
+    /// Queries answered by ClickHouse
+    Queries,
+    /// Queries answered from Hail Tables in GCS
+    Hail,
+}
+
+impl EndpointClass {
+    fn name(self) -> &'static str {
+        match self {
+            EndpointClass::Images => "images",
+            EndpointClass::Queries => "queries",
+            EndpointClass::Hail => "hail",
+        }
+    }
+}
+
+/// Classify a request; `None` for routes that are never limited
+pub fn classify(uri: &Uri) -> Option<EndpointClass> {
+    let path = uri.path().strip_prefix("/api/")?;
+    // Dataset-scoped mounts: /api/v/:dataset/...
+    let path = match path.strip_prefix("v/") {
+        Some(scoped) => scoped.split_once('/').map_or("", |(_, rest)| rest),
+        None => path,
+    };
+
+    let unlimited = ["health", "config", "version", "datasets"];
+    if unlimited.contains(&path)
+        || path.starts_with("health/")
+        || path.starts_with("admin/")
+        || path.starts_with("loadtest/")
+    {
+        return None;
+    }
+
+    let slow_mode = uri
+        .query()
+        .is_some_and(|q| q.split('&').any(|pair| pair == "query_mode=slow"));
+    let htsget_block = path.starts_with("htsget/") && path.ends_with("/data");
+    if slow_mode || htsget_block || path.contains("/region/render") || is_hail_gene_route(path) {
+        Some(EndpointClass::Hail)
+    } else if path.ends_with("/image") || path.ends_with("/thumbnail") || path.starts_with("downloads/") {
+        Some(EndpointClass::Images)
+    } else {
+        Some(EndpointClass::Queries)
+    }
+}
+
+/// `phenotype/:id/genes/:gene_id` reads the gene's Hail Tables and
+/// `phenotype/:id/genes` falls back to them; `genes/count` is ClickHouse only
+fn is_hail_gene_route(path: &str) -> bool {
+    let segments: Vec<&str> = path.split('/').collect();
+    match segments.as_slice() {
+        ["phenotype", _, "genes"] => true,
+        ["phenotype", _, "genes", gene_id] => *gene_id != "count",
+        _ => false,
+    }
+}
+
+/// Requests in flight allowed per class (from CLI flags; 0 = unlimited)
+#[derive(Debug, Clone, Copy)]
+pub struct ConcurrencyConfig {
+    pub images: usize,
+    pub queries: usize,
+    pub hail: usize,
+}
+
+/// Shared semaphores, one per limited class
+pub struct ConcurrencyLimits {
+    images: Option<Arc<Semaphore>>,
+    queries: Option<Arc<Semaphore>>,
+    hail: Option<Arc<Semaphore>>,
+}
+
+impl ConcurrencyLimits {
+    pub fn new(config: ConcurrencyConfig) -> Arc<Self> {
+        let semaphore = |permits: usize| (permits > 0).then(|| Arc::new(Semaphore::new(permits)));
+        Arc::new(Self {
+            images: semaphore(config.images),
+            queries: semaphore(config.queries),
+            hail: semaphore(config.hail),
+        })
+    }
+
+    fn semaphore(&self, class: EndpointClass) -> Option<&Arc<Semaphore>> {
+        match class {
+            EndpointClass::Images => self.images.as_ref(),
+            EndpointClass::Queries => self.queries.as_ref(),
+            EndpointClass::Hail => self.hail.as_ref(),
+        }
+    }
+}
+
+/// Middleware enforcing the per-class limits
+pub async fn limit_concurrency(
+    State(limits): State<Arc<ConcurrencyLimits>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(class) = classify(request.uri()) else {
+        return next.run(request).await;
+    };
+    let Some(semaphore) = limits.semaphore(class) else {
+        return next.run(request).await;
+    };
+
+    match Arc::clone(semaphore).try_acquire_owned() {
+        Ok(permit) => hold_until_sent(next.run(request).await, permit),
+        Err(_) => {
+            tracing::debug!("Shedding {} request ({} limit reached)", request.uri(), class.name());
+            let mut response = (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({
+                    "code": "overloaded",
+                    "error": format!("Too many concurrent {} requests", class.name())
+                })),
+            )
+                .into_response();
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(RETRY_AFTER_SECS),
+            );
+            response
+        }
+    }
+}
+
+/// Release `permit` once the response body has been sent
+///
+/// Bodies of known size are complete when the handler returns; streamed ones
+/// carry the permit until the stream ends or the client goes away.
+fn hold_until_sent(response: Response, permit: OwnedSemaphorePermit) -> Response {
+    if response.body().size_hint().exact().is_some() {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let stream = body.into_data_stream().map(move |chunk| {
+        let _held = &permit;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+    use tower::ServiceExt;
+
+    fn class(uri: &str) -> Option<EndpointClass> {
+        classify(&uri.parse().unwrap())
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(class("/api/phenotype/height/manhattan/image"), Some(EndpointClass::Images));
+        assert_eq!(class("/api/v/414k/phenotype/height/qq/image"), Some(EndpointClass::Images));
+        assert_eq!(class("/api/downloads/height/a1/tar"), Some(EndpointClass::Images));
+        assert_eq!(class("/api/phenotype/height/manhattan/thumbnail?width=300"), Some(EndpointClass::Images));
+        assert_eq!(class("/api/phenotype/height/region/render?start=1"), Some(EndpointClass::Hail));
+        assert_eq!(class("/api/htsget/variants/height/data?referenceName=chr1"), Some(EndpointClass::Hail));
+        assert_eq!(class("/api/htsget/variants/height?referenceName=chr1"), Some(EndpointClass::Queries));
+        assert_eq!(
+            class("/api/variants/associations/gene/PCSK9?query_mode=slow&analysis_id=height"),
+            Some(EndpointClass::Hail)
+        );
+        assert_eq!(class("/api/phenotype/height/loci"), Some(EndpointClass::Queries));
+        assert_eq!(class("/api/phenotype/height/genes"), Some(EndpointClass::Hail));
+        assert_eq!(class("/api/v/414k/phenotype/height/genes/ENSG00000169174"), Some(EndpointClass::Hail));
+        assert_eq!(class("/api/phenotype/height/genes/count?annotation=pLoF"), Some(EndpointClass::Queries));
+        assert_eq!(class("/api/v/414k/genes/phewas/BRCA2"), Some(EndpointClass::Queries));
+        assert_eq!(class("/api/health"), None);
+        assert_eq!(class("/api/v/414k/health/detail"), None);
+        assert_eq!(class("/api/v/414k/config"), None);
+        assert_eq!(class("/api/admin/cache/clear"), None);
+        assert_eq!(class("/healthz"), None);
+    }
+
+    #[tokio::test]
+    async fn test_sheds_when_class_is_full() {
+        let limits = ConcurrencyLimits::new(ConcurrencyConfig {
+            images: 1,
+            queries: 0,
+            hail: 1,
+        });
+        let app = Router::new()
+            .route("/api/phenotype/height/manhattan/image", get(|| async { "png" }))
+            .route("/api/phenotype/height/loci", get(|| async { "loci" }))
+            .layer(axum::middleware::from_fn_with_state(
+                limits.clone(),
+                limit_concurrency,
+            ));
+        let request = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+
+        // Hold the only image permit, as an in-flight image request would
+        let _held = limits.images.clone().unwrap().try_acquire_owned().unwrap();
+        let shed = app
+            .clone()
+            .oneshot(request("/api/phenotype/height/manhattan/image"))
+            .await
+            .unwrap();
+        assert_eq!(shed.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(shed.headers()[header::RETRY_AFTER], "1");
+
+        // Other classes are unaffected
+        let ok = app.oneshot(request("/api/phenotype/height/loci")).await.unwrap();
+        assert_eq!(ok.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_body_holds_permit() {
+        let limits = ConcurrencyLimits::new(ConcurrencyConfig {
+            images: 1,
+            queries: 0,
+            hail: 0,
+        });
+        let app = Router::new()
+            .route(
+                "/api/downloads/height/a1/tar",
+                get(|| async {
+                    let chunks = ["tar", "ball"].map(Ok::<_, std::io::Error>);
+                    Body::from_stream(futures::stream::iter(chunks))
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                limits.clone(),
+                limit_concurrency,
+            ));
+        let request = || Request::get("/api/downloads/height/a1/tar").body(Body::empty()).unwrap();
+
+        let streaming = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(streaming.status(), StatusCode::OK);
+        let shed = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(shed.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let body = axum::body::to_bytes(streaming.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"tarball");
+        let next = app.oneshot(request()).await.unwrap();
+        assert_eq!(next.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,368 @@
+//! Server configuration for GCS buckets and dataset paths
+//!
+//! Defaults match the v8/414k data freeze. A new freeze can be served without
+//! recompiling by pointing `--config` (or `AXAOU_CONFIG`) at a TOML file and/or
+//! overriding individual values with environment variables:
+//!
+//! ```toml
+//! dataset_version = "414k"
+//! # Optional, shown with the dataset version in /api/config
+//! data_freeze_date = "2025-02-01"
+//! results_bucket = "aou_results"
+//! reference_bucket = "axaou-browser-common"
+//! # Optional, derived from dataset_version when omitted
+//! results_prefix = "414k/ht_results"
+//! utils_prefix = "414k/utils"
+//! metadata_table = "aou_phenotype_meta_info.ht"
+//! gene_models_table = "reference-data/genes_grch38_annotated_6.ht"
+//! # Optional, overrides CLICKHOUSE_DATABASE for this dataset
+//! clickhouse_database = "default"
+//! # Optional, local directory for server-rendered Manhattan PNGs, with
+//! # least recently used renders removed past render_cache_max_mb
+//! render_cache_dir = "/var/cache/axaou/plots"
+//! render_cache_max_mb = 1024
+//! # Optional, bearer token for /api/admin (or AXAOU_ADMIN_TOKEN); the
+//! # admin API is disabled without one
+//! admin_token = "..."
+//! # Optional, public scheme and host of the API for absolute links such as
+//! # htsget ticket URLs (or AXAOU_PUBLIC_URL)
+//! public_url = "https://allbyall.researchallofus.org"
+//!
+//! # Optional, per-route Cache-Control policy (see `cache_control`)
+//! [cache_control.metadata]
+//! max_age = 3600
+//! s_maxage = 86400
+//!
+//! # Optional, serve GCS plot images via signed URLs (see `plot_delivery`)
+//! [plot_delivery]
+//! mode = "redirect"
+//! # or, when proxying, keep fetched images on disk (see `plot_cache`)
+//! # cache_dir = "/var/cache/axaou/gcs-plots"
+//!
+//! # Optional, ClickHouse timeout/retry/circuit-breaker policy
+//! [query_policy]
+//! timeout_secs = 30
+//! max_retries = 2
+//! # Log queries slower than this, see /api/admin/slow-queries
+//! slow_query_ms = 1000
+//!
+//! # Optional, UCSC chain files for GRCh37 <-> GRCh38 liftover (see `liftover`)
+//! [liftover]
+//! grch38_to_grch37 = "/data/chains/hg38ToHg19.over.chain.gz"
+//! grch37_to_grch38 = "/data/chains/hg19ToHg38.over.chain.gz"
+//!
+//! # Optional, burden sets, thresholds and test fixtures for /api/config
+//! [frontend]
+//! burden_sets = ["pLoF", "missenseLC", "synonymous"]
+//! genome_wide_threshold = 5e-8
+//! gene_burden_threshold = 2.5e-6
+//!
+//! # Optional, event notifications to Slack/ops tooling (see `webhooks`)
+//! [webhooks]
+//! urls = ["https://hooks.slack.com/services/..."]
+//! secret = "..."
+//! ```
+//!
+//! Every top-level string key above can be overridden by `AXAOU_` plus the
+//! key in uppercase (`AXAOU_DATASET_VERSION`, `AXAOU_GENE_MODELS_TABLE`, ...),
+//! and `[webhooks]` by `AXAOU_WEBHOOK_URLS` / `AXAOU_WEBHOOK_SECRET`; the
+//! environment wins over the file.
+
+use crate::api::FrontendConfig;
+use crate::cache_control::CacheControlConfig;
+use crate::clickhouse::executor::QueryPolicyConfig;
+use crate::liftover::LiftoverConfig;
+use crate::phenotype::plot_delivery::PlotDeliveryConfig;
+use crate::webhooks::WebhookConfig;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Environment variable naming the TOML config file
+pub const CONFIG_PATH_ENV: &str = "AXAOU_CONFIG";
+
+/// Dataset location configuration shared by discovery, metadata loading,
+/// gene model lookup and Hail Table slow-path queries.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Data freeze identifier (e.g., "414k")
+    pub dataset_version: String,
+    /// Release date of the data freeze (e.g., "2025-02-01")
+    pub data_freeze_date: Option<String>,
+    /// Bucket holding per-phenotype results and utility tables
+    pub results_bucket: String,
+    /// Bucket holding shared reference data (gene models)
+    pub reference_bucket: String,
+    /// Prefix of per-phenotype Hail Tables (default: "{dataset_version}/ht_results")
+    pub results_prefix: Option<String>,
+    /// Prefix of utility tables (default: "{dataset_version}/utils")
+    pub utils_prefix: Option<String>,
+    /// Metadata Hail Table name under `utils_prefix`
+    pub metadata_table: String,
+    /// Gene models Hail Table path within `reference_bucket`
+    pub gene_models_table: String,
+    /// ClickHouse database for this dataset (default: `CLICKHOUSE_DATABASE`)
+    pub clickhouse_database: Option<String>,
+    /// Directory for caching server-rendered plot PNGs (default: no disk cache)
+    pub render_cache_dir: Option<PathBuf>,
+    /// Size budget of `render_cache_dir` in MB
+    pub render_cache_max_mb: u64,
+    /// Bearer token required by `/api/admin` routes (default: admin API disabled)
+    pub admin_token: Option<String>,
+    /// Public base URL of the server (scheme and host), for absolute links
+    pub public_url: Option<String>,
+    /// Cache-Control policy per route class
+    pub cache_control: CacheControlConfig,
+    /// How pre-rendered plot images in GCS are served
+    pub plot_delivery: PlotDeliveryConfig,
+    /// Timeout, retry and circuit-breaker settings for ClickHouse queries
+    pub query_policy: QueryPolicyConfig,
+    /// Chain files for variant liftover (default: liftover disabled)
+    pub liftover: LiftoverConfig,
+    /// Burden sets, thresholds and test fixtures served by `/api/config`
+    pub frontend: FrontendConfig,
+    /// Outbound event notifications (default: none)
+    pub webhooks: WebhookConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            dataset_version: "414k".to_string(),
+            data_freeze_date: None,
+            results_bucket: "aou_results".to_string(),
+            reference_bucket: "axaou-browser-common".to_string(),
+            results_prefix: None,
+            utils_prefix: None,
+            metadata_table: "aou_phenotype_meta_info.ht".to_string(),
+            gene_models_table: "reference-data/genes_grch38_annotated_6.ht".to_string(),
+            clickhouse_database: None,
+            render_cache_dir: None,
+            render_cache_max_mb: 1024,
+            admin_token: None,
+            public_url: None,
+            cache_control: CacheControlConfig::default(),
+            plot_delivery: PlotDeliveryConfig::default(),
+            query_policy: QueryPolicyConfig::default(),
+            liftover: LiftoverConfig::default(),
+            frontend: FrontendConfig::default(),
+            webhooks: WebhookConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Load configuration from an optional TOML file, then apply env overrides.
+    ///
+    /// If `path` is `None`, the file named by `AXAOU_CONFIG` is used when set.
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let env_path = std::env::var(CONFIG_PATH_ENV).ok();
+        let path = path.or(env_path.as_deref().map(Path::new));
+
+        let mut config = match path {
+            Some(p) => {
+                info!("Loading server config from {:?}", p);
+                let contents = std::fs::read_to_string(p)?;
+                Self::from_toml(&contents)?
+            }
+            None => Self::default(),
+        };
+
+        config.apply_env_overrides(|key| std::env::var(key).ok());
+        info!(
+            "Dataset {} (results: gs://{}/{}, reference: gs://{})",
+            config.dataset_version,
+            config.results_bucket,
+            config.results_prefix(),
+            config.reference_bucket
+        );
+        Ok(config)
+    }
+
+    /// Parse configuration from TOML; missing keys fall back to defaults.
+    pub fn from_toml(contents: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    /// Override fields from `AXAOU_*` environment variables
+    fn apply_env_overrides(&mut self, get: impl Fn(&str) -> Option<String>) {
+        if let Some(v) = get("AXAOU_DATASET_VERSION") {
+            self.dataset_version = v;
+        }
+        if let Some(v) = get("AXAOU_DATA_FREEZE_DATE") {
+            self.data_freeze_date = Some(v);
+        }
+        if let Some(v) = get("AXAOU_RESULTS_BUCKET") {
+            self.results_bucket = v;
+        }
+        if let Some(v) = get("AXAOU_REFERENCE_BUCKET") {
+            self.reference_bucket = v;
+        }
+        if let Some(v) = get("AXAOU_RESULTS_PREFIX") {
+            self.results_prefix = Some(v);
+        }
+        if let Some(v) = get("AXAOU_UTILS_PREFIX") {
+            self.utils_prefix = Some(v);
+        }
+        if let Some(v) = get("AXAOU_METADATA_TABLE") {
+            self.metadata_table = v;
+        }
+        if let Some(v) = get("AXAOU_GENE_MODELS_TABLE") {
+            self.gene_models_table = v;
+        }
+        if let Some(v) = get("AXAOU_CLICKHOUSE_DATABASE") {
+            self.clickhouse_database = Some(v);
+        }
+        if let Some(v) = get("AXAOU_RENDER_CACHE_DIR") {
+            self.render_cache_dir = Some(PathBuf::from(v));
+        }
+        if let Some(v) = get("AXAOU_ADMIN_TOKEN") {
+            self.admin_token = Some(v);
+        }
+        if let Some(v) = get("AXAOU_PUBLIC_URL") {
+            self.public_url = Some(v);
+        }
+        self.webhooks.apply_env_overrides(get);
+    }
+
+    /// Prefix of per-phenotype result directories within `results_bucket`
+    pub fn results_prefix(&self) -> String {
+        self.results_prefix
+            .clone()
+            .unwrap_or_else(|| format!("{}/ht_results", self.dataset_version))
+    }
+
+    /// Prefix of utility tables within `results_bucket`
+    pub fn utils_prefix(&self) -> String {
+        self.utils_prefix
+            .clone()
+            .unwrap_or_else(|| format!("{}/utils", self.dataset_version))
+    }
+
+    /// Full URI of the analysis metadata Hail Table
+    pub fn metadata_ht_uri(&self) -> String {
+        format!(
+            "gs://{}/{}/{}",
+            self.results_bucket,
+            self.utils_prefix(),
+            self.metadata_table
+        )
+    }
+
+    /// Full URI of the gene models Hail Table
+    pub fn gene_models_ht_uri(&self) -> String {
+        format!("gs://{}/{}", self.reference_bucket, self.gene_models_table)
+    }
+
+    /// Full URI of a per-phenotype variant results table.
+    ///
+    /// `sequencing_type` is "exome" or "genome"; ancestry codes in GCS are uppercase.
+    pub fn variant_results_uri(
+        &self,
+        ancestry: &str,
+        analysis_id: &str,
+        sequencing_type: &str,
+    ) -> String {
+        format!(
+            "gs://{}/{}/{}/phenotype_{}/{}_variant_results.ht",
+            self.results_bucket,
+            self.results_prefix(),
+            ancestry.to_uppercase(),
+            analysis_id,
+            sequencing_type
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_match_414k_paths() {
+        let config = Config::default();
+        assert_eq!(
+            config.metadata_ht_uri(),
+            "gs://aou_results/414k/utils/aou_phenotype_meta_info.ht"
+        );
+        assert_eq!(
+            config.gene_models_ht_uri(),
+            "gs://axaou-browser-common/reference-data/genes_grch38_annotated_6.ht"
+        );
+        assert_eq!(
+            config.variant_results_uri("meta", "height", "genome"),
+            "gs://aou_results/414k/ht_results/META/phenotype_height/genome_variant_results.ht"
+        );
+    }
+
+    #[test]
+    fn test_from_toml_derives_prefixes() {
+        let config = Config::from_toml(
+            r#"
+            dataset_version = "500k"
+            results_bucket = "aou_results_v9"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.results_prefix(), "500k/ht_results");
+        assert_eq!(
+            config.metadata_ht_uri(),
+            "gs://aou_results_v9/500k/utils/aou_phenotype_meta_info.ht"
+        );
+        assert_eq!(config.reference_bucket, "axaou-browser-common");
+    }
+
+    #[test]
+    fn test_frontend_overrides() {
+        let config = Config::from_toml(
+            r#"
+            data_freeze_date = "2025-02-01"
+
+            [frontend]
+            burden_sets = ["pLoF", "missenseLC"]
+            gene_burden_threshold = 6.7e-7
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.data_freeze_date.as_deref(), Some("2025-02-01"));
+        assert_eq!(config.frontend.burden_sets, vec!["pLoF", "missenseLC"]);
+        assert_eq!(config.frontend.gene_burden_threshold, 6.7e-7);
+        assert_eq!(config.frontend.genome_wide_threshold, 5e-8);
+        assert_eq!(config.frontend.default_max_maf, "0.001");
+        assert_eq!(Config::default().frontend, FrontendConfig::default());
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let mut config = Config::default();
+        config.apply_env_overrides(|key| match key {
+            "AXAOU_RESULTS_BUCKET" => Some("other_bucket".to_string()),
+            "AXAOU_RESULTS_PREFIX" => Some("custom/prefix".to_string()),
+            "AXAOU_ADMIN_TOKEN" => Some("s3cret".to_string()),
+            _ => None,
+        });
+        assert_eq!(config.admin_token.as_deref(), Some("s3cret"));
+        assert_eq!(config.results_bucket, "other_bucket");
+        assert_eq!(config.results_prefix(), "custom/prefix");
+        assert_eq!(config.utils_prefix(), "414k/utils");
+    }
+
+    #[test]
+    fn test_env_overrides_table_paths() {
+        let mut config = Config::from_toml(r#"metadata_table = "from_file.ht""#).unwrap();
+        config.apply_env_overrides(|key| match key {
+            "AXAOU_DATASET_VERSION" => Some("500k".to_string()),
+            "AXAOU_DATA_FREEZE_DATE" => Some("2026-01-15".to_string()),
+            "AXAOU_REFERENCE_BUCKET" => Some("ref_bucket".to_string()),
+            "AXAOU_METADATA_TABLE" => Some("meta_v9.ht".to_string()),
+            "AXAOU_GENE_MODELS_TABLE" => Some("genes_v2.ht".to_string()),
+            "AXAOU_CLICKHOUSE_DATABASE" => Some("aou_500k".to_string()),
+            _ => None,
+        });
+        assert_eq!(config.data_freeze_date.as_deref(), Some("2026-01-15"));
+        assert_eq!(config.metadata_ht_uri(), "gs://aou_results/500k/utils/meta_v9.ht");
+        assert_eq!(config.gene_models_ht_uri(), "gs://ref_bucket/genes_v2.ht");
+        assert_eq!(config.clickhouse_database.as_deref(), Some("aou_500k"));
+    }
+}
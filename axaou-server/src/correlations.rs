@@ -0,0 +1,327 @@
+//! Phenotype genetic correlation handlers
+//!
+//! Serves pairwise LDSC genetic correlations (rg) from `genetic_correlations`,
+//! loaded by `ingest genetic-correlations`: the most correlated traits of one
+//! analysis, and an rg matrix over a set of analyses for heatmaps.
+
+use crate::api::AppState;
+use crate::clickhouse::QueryExt;
+use crate::error::{AppError, ErrorResponse};
+use crate::models::AnalysisMetadata;
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+/// Most analyses accepted by the matrix endpoint
+const MAX_MATRIX_ANALYSES: usize = 200;
+
+/// Query parameters for the correlated-traits endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CorrelationsQuery {
+    /// Ancestry group (default: "meta")
+    pub ancestry: Option<String>,
+    /// Maximum correlation p-value
+    pub max_p: Option<f64>,
+    /// Minimum |rg|
+    pub min_abs_rg: Option<f64>,
+    /// Maximum number of results (default: 50, max: 1000)
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Row)]
+struct CorrelationRow {
+    phenotype2: String,
+    rg: f64,
+    rg_se: Option<f64>,
+    z: Option<f64>,
+    pvalue: Option<f64>,
+}
+
+/// Genetic correlation with one other trait
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct GeneticCorrelation {
+    pub analysis_id: String,
+    /// Phenotype description (display name applied)
+    pub description: String,
+    pub category: String,
+    pub rg: f64,
+    pub rg_se: Option<f64>,
+    pub z: Option<f64>,
+    pub pvalue: Option<f64>,
+}
+
+/// Metadata record per analysis, preferring the requested ancestry
+pub(crate) fn metadata_by_analysis<'a>(
+    metadata: &'a [AnalysisMetadata],
+    ancestry: &str,
+) -> HashMap<&'a str, &'a AnalysisMetadata> {
+    let mut by_analysis = HashMap::new();
+    for meta in metadata {
+        let exact = meta.ancestry_group.eq_ignore_ascii_case(ancestry);
+        if exact || !by_analysis.contains_key(meta.analysis_id.as_str()) {
+            by_analysis.insert(meta.analysis_id.as_str(), meta);
+        }
+    }
+    by_analysis
+}
+
+fn display_description(analysis_id: &str, meta: Option<&&AnalysisMetadata>) -> String {
+    crate::phenotype_display_names::apply_display_name(
+        analysis_id,
+        meta.map(|m| m.description.as_str()).unwrap_or_default(),
+    )
+}
+
+/// GET /api/analyses/:analysis_id/correlations
+///
+/// Traits most genetically correlated with an analysis, most significant
+/// first (ties broken by |rg|), with descriptions from analysis metadata.
+#[utoipa::path(
+    get,
+    path = "/api/analyses/{analysis_id}/correlations",
+    tag = "analyses",
+    params(("analysis_id" = String, Path, description = "Analysis (phenotype) ID"), CorrelationsQuery),
+    responses(
+        (status = 200, description = "Correlated traits", body = Vec<GeneticCorrelation>),
+        (status = 500, description = "ClickHouse query failed", body = ErrorResponse)
+    )
+)]
+pub async fn get_analysis_correlations(
+    State(state): State<Arc<AppState>>,
+    Path(analysis_id): Path<String>,
+    Query(params): Query<CorrelationsQuery>,
+) -> Result<Json<Vec<GeneticCorrelation>>, AppError> {
+    let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
+    let limit = params.limit.unwrap_or(50).clamp(1, 1000);
+
+    let mut filters = String::new();
+    if params.max_p.is_some() {
+        filters.push_str(" AND pvalue <= ?");
+    }
+    if params.min_abs_rg.is_some() {
+        filters.push_str(" AND abs(rg) >= ?");
+    }
+    let query = format!(
+        r#"
+        SELECT phenotype2, rg, rg_se, z, pvalue
+        FROM genetic_correlations
+        WHERE ancestry = ? AND phenotype1 = ?{}
+        ORDER BY pvalue ASC NULLS LAST, abs(rg) DESC
+        LIMIT ?
+        "#,
+        filters
+    );
+
+    let mut q = state
+        .clickhouse
+        .query(&query)
+        .bind(&ancestry)
+        .bind(&analysis_id);
+    if let Some(max_p) = params.max_p {
+        q = q.bind(max_p);
+    }
+    if let Some(min_abs_rg) = params.min_abs_rg {
+        q = q.bind(min_abs_rg);
+    }
+    let rows = q
+        .bind(limit)
+        .fetch_all_with::<CorrelationRow>(&state.executor)
+        .await?;
+
+    let metadata = state.metadata.read().await;
+    let by_analysis = metadata_by_analysis(&metadata, &ancestry);
+    let correlations = rows
+        .into_iter()
+        .map(|row| {
+            let meta = by_analysis.get(row.phenotype2.as_str());
+            GeneticCorrelation {
+                description: display_description(&row.phenotype2, meta),
+                category: meta.map(|m| m.category.clone()).unwrap_or_default(),
+                analysis_id: row.phenotype2,
+                rg: row.rg,
+                rg_se: row.rg_se,
+                z: row.z,
+                pvalue: row.pvalue,
+            }
+        })
+        .collect();
+
+    Ok(Json(correlations))
+}
+
+/// Query parameters for the correlation matrix endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CorrelationMatrixQuery {
+    /// Comma-separated analysis IDs (at most 200)
+    pub analysis_ids: String,
+    /// Ancestry group (default: "meta")
+    pub ancestry: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Row)]
+struct CorrelationPairRow {
+    phenotype1: String,
+    phenotype2: String,
+    rg: f64,
+    pvalue: Option<f64>,
+}
+
+/// Square rg / p-value matrix over the requested analyses, in request order
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CorrelationMatrix {
+    pub analysis_ids: Vec<String>,
+    /// Phenotype descriptions, parallel to `analysis_ids`
+    pub descriptions: Vec<String>,
+    /// `rg[i][j]` between `analysis_ids[i]` and `analysis_ids[j]`; 1 on the
+    /// diagonal, null for untested pairs
+    pub rg: Vec<Vec<Option<f64>>>,
+    /// P-values, laid out like `rg`
+    pub pvalue: Vec<Vec<Option<f64>>>,
+}
+
+/// Distinct, non-empty IDs from a comma-separated list, in order
+fn parse_analysis_ids(analysis_ids: &str) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::new();
+    for id in analysis_ids.split(',').map(str::trim) {
+        if !id.is_empty() && !ids.iter().any(|seen| seen == id) {
+            ids.push(id.to_string());
+        }
+    }
+    ids
+}
+
+/// Lay out pair rows as square matrices over `ids`
+fn build_matrix(
+    ids: &[String],
+    rows: &[CorrelationPairRow],
+) -> (Vec<Vec<Option<f64>>>, Vec<Vec<Option<f64>>>) {
+    let index: HashMap<&str, usize> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (id.as_str(), i))
+        .collect();
+    let n = ids.len();
+    let mut rg = vec![vec![None; n]; n];
+    let mut pvalue = vec![vec![None; n]; n];
+    for (i, row) in rg.iter_mut().enumerate() {
+        row[i] = Some(1.0);
+    }
+    for row in rows {
+        if let (Some(&i), Some(&j)) = (
+            index.get(row.phenotype1.as_str()),
+            index.get(row.phenotype2.as_str()),
+        ) {
+            rg[i][j] = Some(row.rg);
+            pvalue[i][j] = row.pvalue;
+        }
+    }
+    (rg, pvalue)
+}
+
+/// GET /api/correlations/matrix?analysis_ids=
+///
+/// Pairwise genetic correlations among the given analyses, for heatmaps.
+#[utoipa::path(
+    get,
+    path = "/api/correlations/matrix",
+    tag = "analyses",
+    params(CorrelationMatrixQuery),
+    responses(
+        (status = 200, description = "rg and p-value matrices", body = CorrelationMatrix),
+        (status = 400, description = "No or too many analysis IDs", body = ErrorResponse)
+    )
+)]
+pub async fn get_correlation_matrix(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CorrelationMatrixQuery>,
+) -> Result<Json<CorrelationMatrix>, AppError> {
+    let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
+    let ids = parse_analysis_ids(&params.analysis_ids);
+    if ids.is_empty() {
+        return Err(AppError::BadRequest(
+            "analysis_ids must list at least one analysis".to_string(),
+        ));
+    }
+    if ids.len() > MAX_MATRIX_ANALYSES {
+        return Err(AppError::BadRequest(format!(
+            "At most {} analysis_ids are allowed, got {}",
+            MAX_MATRIX_ANALYSES,
+            ids.len()
+        )));
+    }
+
+    let query = r#"
+        SELECT phenotype1, phenotype2, rg, pvalue
+        FROM genetic_correlations
+        WHERE ancestry = ? AND has(?, phenotype1) AND has(?, phenotype2)
+    "#;
+    let rows = state
+        .clickhouse
+        .query(query)
+        .bind(&ancestry)
+        .bind(&ids)
+        .bind(&ids)
+        .fetch_all_with::<CorrelationPairRow>(&state.executor)
+        .await?;
+
+    let (rg, pvalue) = build_matrix(&ids, &rows);
+    let metadata = state.metadata.read().await;
+    let by_analysis = metadata_by_analysis(&metadata, &ancestry);
+    let descriptions = ids
+        .iter()
+        .map(|id| display_description(id, by_analysis.get(id.as_str())))
+        .collect();
+
+    Ok(Json(CorrelationMatrix {
+        analysis_ids: ids,
+        descriptions,
+        rg,
+        pvalue,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_matrix() {
+        let ids = parse_analysis_ids("a, b,,a,c");
+        assert_eq!(ids, vec!["a", "b", "c"]);
+
+        let rows = vec![
+            CorrelationPairRow {
+                phenotype1: "a".to_string(),
+                phenotype2: "b".to_string(),
+                rg: 0.4,
+                pvalue: Some(1e-5),
+            },
+            CorrelationPairRow {
+                phenotype1: "b".to_string(),
+                phenotype2: "a".to_string(),
+                rg: 0.4,
+                pvalue: Some(1e-5),
+            },
+            CorrelationPairRow {
+                phenotype1: "a".to_string(),
+                phenotype2: "z".to_string(),
+                rg: -0.9,
+                pvalue: None,
+            },
+        ];
+        let (rg, pvalue) = build_matrix(&ids, &rows);
+        assert_eq!(rg[0], vec![Some(1.0), Some(0.4), None]);
+        assert_eq!(rg[1][0], Some(0.4));
+        assert_eq!(rg[2], vec![None, None, Some(1.0)]);
+        assert_eq!(pvalue[0][1], Some(1e-5));
+        assert_eq!(pvalue[1][1], None);
+    }
+}
@@ -3,24 +3,23 @@
 //! This module handles fetching, parsing, and transforming data from the Hail Table
 //! using hail-decoder to stream data directly from GCS.
 
+use crate::config::Config;
 use crate::error::AppError;
 use crate::models::AnalysisMetadata;
 use genohype_core::codec::EncodedValue;
 use genohype_core::query::QueryEngine;
 use tracing::info;
 
-/// GCS path to the v8/414k analysis metadata Hail Table
-const METADATA_HT_PATH: &str = "gs://aou_results/414k/utils/aou_phenotype_meta_info.ht";
-
-/// Fetches and transforms all analysis metadata from the Hail Table.
+/// Fetches and transforms all analysis metadata from the configured Hail Table.
 ///
 /// This runs in a blocking task to avoid blocking the Tokio runtime,
 /// since hail-decoder performs synchronous I/O.
-pub async fn load_all_metadata() -> Result<Vec<AnalysisMetadata>, AppError> {
-    info!("Loading metadata from {}", METADATA_HT_PATH);
+pub async fn load_all_metadata(config: &Config) -> Result<Vec<AnalysisMetadata>, AppError> {
+    let metadata_path = config.metadata_ht_uri();
+    info!("Loading metadata from {}", metadata_path);
 
     let metadata_task = tokio::task::spawn_blocking(move || {
-        let engine = QueryEngine::open_path(METADATA_HT_PATH)?;
+        let engine = QueryEngine::open_path(&metadata_path)?;
 
         info!(
             "Opened table with {} partitions, key fields: {:?}",
@@ -61,7 +60,7 @@ pub async fn load_all_metadata() -> Result<Vec<AnalysisMetadata>, AppError> {
 /// The field names used here match the v8/414k dataset schema.
 fn transform_encoded_value(value: EncodedValue) -> Result<AnalysisMetadata, AppError> {
     let EncodedValue::Struct(fields) = value else {
-        return Err(AppError::DataTransformError(
+        return Err(AppError::Internal(
             "Expected Struct at top level".to_string(),
         ));
     };
@@ -78,6 +77,9 @@ fn transform_encoded_value(value: EncodedValue) -> Result<AnalysisMetadata, AppE
     let mut lambda_gc_exome_hq: Option<f64> = None;
     let mut lambda_gc_acaf_hq: Option<f64> = None;
     let mut lambda_gc_gene_burden_001: Option<f64> = None;
+    let mut ldsc_h2: Option<f64> = None;
+    let mut ldsc_h2_se: Option<f64> = None;
+    let mut saige_h2: Option<f64> = None;
 
     for (key, val) in fields {
         match key.as_str() {
@@ -101,16 +103,20 @@ fn transform_encoded_value(value: EncodedValue) -> Result<AnalysisMetadata, AppE
             "lambda_gc_gene_burden_001" => {
                 lambda_gc_gene_burden_001 = extract_float(&val);
             }
+            // Heritability fields are only present in some releases
+            "ldsc_h2" => ldsc_h2 = extract_float(&val),
+            "ldsc_h2_se" => ldsc_h2_se = extract_float(&val),
+            "saige_h2" => saige_h2 = extract_float(&val),
             _ => {} // Ignore other fields
         }
     }
 
     // Required fields
     let raw_phenoname = phenoname.ok_or_else(|| {
-        AppError::DataTransformError("Missing required field: phenoname".to_string())
+        AppError::Internal("Missing required field: phenoname".to_string())
     })?;
     let ancestry_group = ancestry.ok_or_else(|| {
-        AppError::DataTransformError("Missing required field: ancestry/pop".to_string())
+        AppError::Internal("Missing required field: ancestry/pop".to_string())
     })?;
 
     // Normalize analysis_id: remove "phenotype_" prefix if present
@@ -122,6 +128,15 @@ fn transform_encoded_value(value: EncodedValue) -> Result<AnalysisMetadata, AppE
     // Use raw category name (no prefix needed for new flat selector)
     let category_formatted = category.unwrap_or_else(|| "Unknown".to_string());
 
+    // Prefer LDSC heritability, as the ClickHouse transform does
+    let ldsc_h2 = ldsc_h2.filter(|h2| h2.is_finite());
+    let saige_h2 = saige_h2.filter(|h2| h2.is_finite());
+    let (heritability, heritability_se, heritability_method) = match (ldsc_h2, saige_h2) {
+        (Some(h2), _) => (Some(h2), ldsc_h2_se, Some("ldsc".to_string())),
+        (None, Some(h2)) => (Some(h2), None, Some("saige".to_string())),
+        (None, None) => (None, None, None),
+    };
+
     Ok(AnalysisMetadata {
         analysis_id,
         ancestry_group,
@@ -139,6 +154,9 @@ fn transform_encoded_value(value: EncodedValue) -> Result<AnalysisMetadata, AppE
         keep_pheno_burden: true,
         keep_pheno_skat: true,
         keep_pheno_skato: true,
+        heritability,
+        heritability_se,
+        heritability_method,
     })
 }
 
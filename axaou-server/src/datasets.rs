@@ -0,0 +1,171 @@
+//! Dataset registry for serving several data freezes side by side
+//!
+//! Each dataset (e.g., "250k", "414k") has its own [`Config`] — ClickHouse
+//! database and GCS asset roots — and is mounted under `/api/v/:dataset`.
+//! The default dataset is additionally served at `/api`.
+//!
+//! ```toml
+//! default = "414k"
+//!
+//! [datasets."414k"]
+//! dataset_version = "414k"
+//!
+//! [datasets."250k"]
+//! dataset_version = "250k"
+//! results_bucket = "aou_results_250k"
+//! clickhouse_database = "axaou_250k"
+//! ```
+
+use crate::config::Config;
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Named dataset configurations plus the one served at `/api`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DatasetRegistry {
+    /// Name of the dataset mounted at `/api`
+    pub default: String,
+    /// Dataset configurations keyed by URL name
+    pub datasets: BTreeMap<String, Config>,
+}
+
+impl DatasetRegistry {
+    /// Registry with a single dataset named after its `dataset_version`
+    pub fn single(config: Config) -> Self {
+        let name = config.dataset_version.clone();
+        let mut datasets = BTreeMap::new();
+        datasets.insert(name.clone(), config);
+        Self {
+            default: name,
+            datasets,
+        }
+    }
+
+    /// Load a registry from a TOML file
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::from_toml(&contents)
+    }
+
+    /// Parse and validate a registry from TOML
+    pub fn from_toml(contents: &str) -> anyhow::Result<Self> {
+        let registry: Self = toml::from_str(contents)?;
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.datasets.is_empty() {
+            bail!("Dataset registry must define at least one dataset");
+        }
+        if !self.datasets.contains_key(&self.default) {
+            bail!("Default dataset '{}' is not defined", self.default);
+        }
+        for name in self.datasets.keys() {
+            let valid = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+            if !valid {
+                bail!("Dataset name '{}' is not URL-safe", name);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Response for GET /api/datasets
+#[derive(Debug, Clone, Serialize)]
+pub struct DatasetList {
+    pub default: String,
+    pub datasets: Vec<DatasetInfo>,
+}
+
+/// Public summary of a mounted dataset
+#[derive(Debug, Clone, Serialize)]
+pub struct DatasetInfo {
+    pub name: String,
+    pub dataset_version: String,
+    /// Route prefix for this dataset's API
+    pub base_path: String,
+}
+
+impl DatasetList {
+    pub fn from_registry(registry: &DatasetRegistry) -> Self {
+        Self {
+            default: registry.default.clone(),
+            datasets: registry
+                .datasets
+                .iter()
+                .map(|(name, config)| DatasetInfo {
+                    name: name.clone(),
+                    dataset_version: config.dataset_version.clone(),
+                    base_path: format!("/api/v/{}", name),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_registry() {
+        let registry = DatasetRegistry::from_toml(
+            r#"
+            default = "414k"
+
+            [datasets."414k"]
+            dataset_version = "414k"
+
+            [datasets."250k"]
+            dataset_version = "250k"
+            clickhouse_database = "axaou_250k"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(registry.datasets.len(), 2);
+        assert_eq!(
+            registry.datasets["250k"].clickhouse_database.as_deref(),
+            Some("axaou_250k")
+        );
+        assert_eq!(
+            registry.datasets["250k"].metadata_ht_uri(),
+            "gs://aou_results/250k/utils/aou_phenotype_meta_info.ht"
+        );
+    }
+
+    #[test]
+    fn test_rejects_unknown_default() {
+        let err = DatasetRegistry::from_toml(
+            r#"
+            default = "500k"
+            [datasets."414k"]
+            "#,
+        );
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_rejects_unsafe_names() {
+        let err = DatasetRegistry::from_toml(
+            r#"
+            default = "a/b"
+            [datasets."a/b"]
+            "#,
+        );
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_single() {
+        let registry = DatasetRegistry::single(Config::default());
+        assert_eq!(registry.default, "414k");
+        let list = DatasetList::from_registry(&registry);
+        assert_eq!(list.datasets[0].base_path, "/api/v/414k");
+    }
+}
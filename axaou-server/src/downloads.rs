@@ -0,0 +1,429 @@
+//! Bulk download handlers
+//!
+//! Lists the raw Hail Table exports of an analysis (from discovered assets)
+//! with their files and sizes, optionally with V4 signed URLs per file, and
+//! streams a whole table as an uncompressed tar so power users can fetch
+//! complete summary statistics rather than paging the API.
+//!
+//! Tables are only read from the mounted dataset's `results_bucket`, and tar
+//! downloads are rate limited per client (`--download-rate-per-hour`).
+
+use crate::api::{ensure_assets_loaded, AppState};
+use crate::config::Config;
+use crate::error::{AppError, ErrorResponse};
+use crate::models::AnalysisAsset;
+use crate::phenotype::manhattan::parse_gcs_uri;
+use axum::{
+    body::{Body, Bytes},
+    extract::{OriginalUri, Path, Query, State},
+    http::{header, Method},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::{future, stream, StreamExt, TryStreamExt};
+use object_store::gcp::{GoogleCloudStorage, GoogleCloudStorageBuilder};
+use object_store::path::Path as ObjectPath;
+use object_store::signer::Signer;
+use object_store::{ObjectMeta, ObjectStore};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::{IntoParams, ToSchema};
+
+/// Size of a tar header / block
+const TAR_BLOCK: usize = 512;
+/// Largest entry size the 11-digit octal ustar size field can hold
+const TAR_MAX_SIZE: u64 = 0o77777777777;
+
+/// Query parameters for the downloads listing
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DownloadsQuery {
+    /// Only list assets of this ancestry group (e.g. "eur", "meta")
+    pub ancestry: Option<String>,
+    /// Include a signed URL for every file (default: false)
+    #[serde(default)]
+    pub sign: bool,
+}
+
+/// One object of a Hail Table directory
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DownloadFile {
+    /// Path relative to the table directory, e.g. "rows/parts/part-0"
+    pub path: String,
+    pub size: u64,
+    /// V4 signed GET URL, with `sign=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// Raw result table available for download
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DownloadAsset {
+    /// Asset ID used by the tar endpoint
+    pub asset_id: String,
+    /// "variant", "variant_exp_p", "gene", ...
+    pub asset_type: String,
+    pub ancestry_group: String,
+    /// "exomes" or "genomes"; null for gene-level tables
+    pub sequencing_type: Option<String>,
+    /// GCS URI of the table directory
+    pub uri: String,
+    /// Total size of all files in bytes
+    pub size_bytes: u64,
+    pub file_count: usize,
+    /// Path of the tar download of this table
+    pub tar_url: String,
+    pub files: Vec<DownloadFile>,
+}
+
+/// Raw result files of an analysis
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DownloadListing {
+    pub analysis_id: String,
+    pub total_size_bytes: u64,
+    /// Lifetime of the signed URLs in seconds, with `sign=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in: Option<u64>,
+    pub assets: Vec<DownloadAsset>,
+}
+
+/// Discovered assets of an analysis, optionally for one ancestry
+async fn analysis_assets(
+    state: &AppState,
+    analysis_id: &str,
+    ancestry: Option<&str>,
+) -> Result<Vec<AnalysisAsset>, AppError> {
+    ensure_assets_loaded(state).await?;
+    let assets = state.assets.read().await;
+    let matching: Vec<AnalysisAsset> = assets
+        .as_ref()
+        .map(|a| a.assets.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter(|a| a.analysis_id == analysis_id)
+        .filter(|a| {
+            ancestry.map_or(true, |anc| {
+                a.ancestry_group.to_string().eq_ignore_ascii_case(anc)
+            })
+        })
+        .cloned()
+        .collect();
+    if matching.is_empty() {
+        return Err(AppError::NotFound(format!(
+            "No result tables found for analysis '{}'",
+            analysis_id
+        )));
+    }
+    Ok(matching)
+}
+
+/// GCS client for the dataset's results bucket and the table's directory
+/// path in it; tables outside that bucket are never proxied
+fn table_store(config: &Config, uri: &str) -> Result<(GoogleCloudStorage, ObjectPath), AppError> {
+    let (bucket, path) = parse_gcs_uri(uri)
+        .ok_or_else(|| AppError::Internal(format!("Invalid GCS URI: {}", uri)))?;
+    if bucket != config.results_bucket {
+        return Err(AppError::NotFound(format!(
+            "{} is not in the results bucket of dataset {}",
+            uri, config.dataset_version
+        )));
+    }
+    let store = GoogleCloudStorageBuilder::new()
+        .with_bucket_name(&config.results_bucket)
+        .build()
+        .map_err(|e| AppError::UpstreamGcs(format!("Failed to create GCS client: {}", e)))?;
+    Ok((store, ObjectPath::from(path.trim_end_matches('/'))))
+}
+
+/// Every object under a table directory, in path order
+async fn list_table(
+    store: &GoogleCloudStorage,
+    prefix: &ObjectPath,
+) -> Result<Vec<ObjectMeta>, AppError> {
+    let mut objects: Vec<ObjectMeta> = store
+        .list(Some(prefix))
+        .try_collect()
+        .await
+        .map_err(|e| AppError::UpstreamGcs(format!("Failed to list {}: {}", prefix, e)))?;
+    objects.sort_by(|a, b| a.location.as_ref().cmp(b.location.as_ref()));
+    Ok(objects)
+}
+
+/// Path of an object relative to its table directory
+fn relative_path(prefix: &ObjectPath, location: &ObjectPath) -> String {
+    location
+        .as_ref()
+        .strip_prefix(prefix.as_ref())
+        .unwrap_or(location.as_ref())
+        .trim_start_matches('/')
+        .to_string()
+}
+
+/// Directory name of a table, e.g. "gene_results.ht"
+fn table_name(prefix: &ObjectPath) -> String {
+    prefix
+        .parts()
+        .last()
+        .map(|part| part.as_ref().to_string())
+        .unwrap_or_else(|| "table".to_string())
+}
+
+fn asset_type_name(asset: &AnalysisAsset) -> String {
+    serde_json::to_value(asset.asset_type)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// GET /api/downloads/:analysis_id
+///
+/// Raw result tables of an analysis with every file and its size. With
+/// `sign=true` each file carries a signed URL valid for the configured
+/// `plot_delivery.ttl_secs`; each table can also be fetched whole from its
+/// `tar_url`, which points at the same dataset mount as the listing.
+#[utoipa::path(
+    get,
+    path = "/api/downloads/{analysis_id}",
+    tag = "analyses",
+    params(("analysis_id" = String, Path, description = "Analysis (phenotype) ID"), DownloadsQuery),
+    responses(
+        (status = 200, description = "Downloadable result tables", body = DownloadListing),
+        (status = 404, description = "No result tables for the analysis", body = ErrorResponse),
+        (status = 502, description = "Listing or signing in GCS failed", body = ErrorResponse)
+    )
+)]
+pub async fn get_downloads(
+    State(state): State<Arc<AppState>>,
+    Path(analysis_id): Path<String>,
+    Query(params): Query<DownloadsQuery>,
+    OriginalUri(uri): OriginalUri,
+) -> Result<Response, AppError> {
+    let assets = analysis_assets(&state, &analysis_id, params.ancestry.as_deref()).await?;
+    let ttl = state.config.plot_delivery.ttl_secs;
+
+    let mut listing = DownloadListing {
+        analysis_id: analysis_id.clone(),
+        total_size_bytes: 0,
+        expires_in: params.sign.then_some(ttl),
+        assets: Vec::with_capacity(assets.len()),
+    };
+    for asset in &assets {
+        let (store, prefix) = table_store(&state.config, &asset.uri)?;
+        let objects = list_table(&store, &prefix).await?;
+
+        let urls = if params.sign {
+            let locations: Vec<ObjectPath> = objects.iter().map(|o| o.location.clone()).collect();
+            let urls = store
+                .signed_urls(Method::GET, &locations, Duration::from_secs(ttl))
+                .await
+                .map_err(|e| AppError::UpstreamGcs(format!("Failed to sign GCS URLs: {}", e)))?;
+            urls.into_iter().map(|u| Some(u.to_string())).collect()
+        } else {
+            vec![None; objects.len()]
+        };
+
+        let files: Vec<DownloadFile> = objects
+            .iter()
+            .zip(urls)
+            .map(|(object, url)| DownloadFile {
+                path: relative_path(&prefix, &object.location),
+                size: object.size as u64,
+                url,
+            })
+            .collect();
+        let size_bytes = files.iter().map(|f| f.size).sum();
+        listing.total_size_bytes += size_bytes;
+
+        let asset_id = asset.hash_id();
+        listing.assets.push(DownloadAsset {
+            tar_url: format!("{}/{}/tar", uri.path().trim_end_matches('/'), asset_id),
+            asset_id,
+            asset_type: asset_type_name(asset),
+            ancestry_group: asset.ancestry_group.to_string(),
+            sequencing_type: asset.sequencing_type.map(|s| s.to_string()),
+            uri: asset.uri.clone(),
+            size_bytes,
+            file_count: files.len(),
+            files,
+        });
+    }
+
+    // Signed URLs expire, so the listing must not outlive them in caches
+    if params.sign {
+        Ok(([(header::CACHE_CONTROL, "no-store")], Json(listing)).into_response())
+    } else {
+        Ok(Json(listing).into_response())
+    }
+}
+
+/// ustar header for a regular file, splitting long names into prefix/name
+fn tar_header(name: &str, size: u64) -> Result<[u8; TAR_BLOCK], AppError> {
+    if size > TAR_MAX_SIZE {
+        return Err(AppError::Internal(format!(
+            "{} is too large for a tar entry ({} bytes)",
+            name, size
+        )));
+    }
+    let (prefix, short_name) = if name.len() <= 100 {
+        ("", name)
+    } else {
+        name.char_indices()
+            .filter(|&(i, c)| c == '/' && i <= 155 && name.len() - i - 1 <= 100)
+            .map(|(i, _)| (&name[..i], &name[i + 1..]))
+            .next()
+            .ok_or_else(|| AppError::Internal(format!("Path too long for tar: {}", name)))?
+    };
+
+    let mut header = [0u8; TAR_BLOCK];
+    header[..short_name.len()].copy_from_slice(short_name.as_bytes());
+    header[100..108].copy_from_slice(b"0000644\0");
+    header[108..116].copy_from_slice(b"0000000\0");
+    header[116..124].copy_from_slice(b"0000000\0");
+    header[124..136].copy_from_slice(format!("{:011o}\0", size).as_bytes());
+    header[136..148].copy_from_slice(b"00000000000\0");
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    // Checksum is computed with its own field filled with spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|&b| b as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    Ok(header)
+}
+
+/// Zero bytes that pad an entry of `size` bytes to a whole block
+fn tar_padding(size: u64) -> usize {
+    (TAR_BLOCK - (size % TAR_BLOCK as u64) as usize) % TAR_BLOCK
+}
+
+/// GET /api/downloads/:analysis_id/:asset_id/tar
+///
+/// Streams every file of one result table as an uncompressed tar, entries
+/// named `<table>.ht/<path>`. Objects are read from GCS one at a time, so
+/// memory stays flat regardless of the table size.
+#[utoipa::path(
+    get,
+    path = "/api/downloads/{analysis_id}/{asset_id}/tar",
+    tag = "analyses",
+    params(
+        ("analysis_id" = String, Path, description = "Analysis (phenotype) ID"),
+        ("asset_id" = String, Path, description = "Asset ID from the downloads listing")
+    ),
+    responses(
+        (status = 200, description = "Tar archive of the table", content_type = "application/x-tar"),
+        (status = 404, description = "Unknown analysis or asset", body = ErrorResponse),
+        (status = 429, description = "Download quota of the client exhausted")
+    )
+)]
+pub async fn get_download_tar(
+    State(state): State<Arc<AppState>>,
+    Path((analysis_id, asset_id)): Path<(String, String)>,
+) -> Result<Response, AppError> {
+    let asset = analysis_assets(&state, &analysis_id, None)
+        .await?
+        .into_iter()
+        .find(|a| a.hash_id() == asset_id)
+        .ok_or_else(|| {
+            AppError::NotFound(format!(
+                "Asset '{}' not found for analysis '{}'",
+                asset_id, analysis_id
+            ))
+        })?;
+
+    let (store, prefix) = table_store(&state.config, &asset.uri)?;
+    let objects = list_table(&store, &prefix).await?;
+    let name = table_name(&prefix);
+
+    // Build every header up front so naming errors surface before streaming
+    let mut content_length = 2 * TAR_BLOCK as u64;
+    let mut entries = Vec::with_capacity(objects.len());
+    for object in objects {
+        let size = object.size as u64;
+        let entry_name = format!("{}/{}", name, relative_path(&prefix, &object.location));
+        let block = tar_header(&entry_name, size)?;
+        content_length += (TAR_BLOCK + tar_padding(size)) as u64 + size;
+        entries.push((object.location, block, size));
+    }
+
+    let store = Arc::new(store);
+    let body = stream::iter(entries)
+        .then(move |(location, block, size)| {
+            let store = store.clone();
+            async move {
+                let data = store.get(&location).await?.into_stream();
+                let padding = Bytes::from(vec![0u8; tar_padding(size)]);
+                Ok::<_, object_store::Error>(
+                    stream::once(future::ready(Ok(Bytes::copy_from_slice(&block))))
+                        .chain(data)
+                        .chain(stream::once(future::ready(Ok(padding)))),
+                )
+            }
+        })
+        .try_flatten()
+        .chain(stream::once(future::ready(Ok(Bytes::from(vec![
+            0u8;
+            2 * TAR_BLOCK
+        ])))));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-tar".to_string()),
+            (header::CONTENT_LENGTH, content_length.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}_{}.tar\"", analysis_id, name),
+            ),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tar_header() {
+        let header = tar_header("gene_results.ht/metadata.json.gz", 1000).unwrap();
+        assert_eq!(&header[..32], b"gene_results.ht/metadata.json.gz");
+        assert_eq!(&header[124..136], b"00000001750\0");
+        assert_eq!(&header[257..263], b"ustar\0");
+
+        let stored: u32 =
+            u32::from_str_radix(std::str::from_utf8(&header[148..154]).unwrap(), 8).unwrap();
+        let mut blank = header;
+        blank[148..156].copy_from_slice(b"        ");
+        assert_eq!(stored, blank.iter().map(|&b| b as u32).sum::<u32>());
+
+        let long = format!("{}/rows/parts/{}", "a".repeat(90), "p".repeat(90));
+        let header = tar_header(&long, 0).unwrap();
+        let name = format!("parts/{}", "p".repeat(90));
+        assert_eq!(&header[..name.len()], name.as_bytes());
+        assert_eq!(&header[345..345 + 95], long[..95].as_bytes());
+        assert!(tar_header(&"x".repeat(300), 0).is_err());
+
+        assert_eq!(tar_padding(1000), 24);
+        assert_eq!(tar_padding(1024), 0);
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let prefix = ObjectPath::from("414k/ht_results/META/height/gene_results.ht");
+        let location =
+            ObjectPath::from("414k/ht_results/META/height/gene_results.ht/rows/parts/part-0");
+        assert_eq!(relative_path(&prefix, &location), "rows/parts/part-0");
+        assert_eq!(table_name(&prefix), "gene_results.ht");
+    }
+
+    #[test]
+    fn test_table_store_rejects_other_buckets() {
+        let config = Config::default();
+        let err = table_store(&config, "gs://someone-else/height/gene_results.ht").unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+    }
+}
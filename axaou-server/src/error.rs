@@ -1,42 +1,157 @@
 //! Custom error handling for the AxAoU server
+//!
+//! Every error response carries a stable, machine-readable `code` alongside
+//! the human-readable message, so clients can tell a bad request from a
+//! ClickHouse outage or a GCS failure without parsing text.
 
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
+use utoipa::ToSchema;
+
+/// `Retry-After` sent with [`AppError::NotReady`]
+const RETRY_AFTER_SECS: u64 = 5;
+
+/// JSON body returned for every error response (documented in the OpenAPI spec)
+#[derive(Debug, serde::Serialize, ToSchema)]
+pub struct ErrorResponse {
+    /// Stable error code, e.g. "not_found" or "upstream_clickhouse"
+    pub code: String,
+    pub error: String,
+}
 
 #[derive(thiserror::Error, Debug)]
 pub enum AppError {
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+
+    #[error("Invalid interval: {0}")]
+    InvalidInterval(String),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    /// Missing or invalid credentials for a protected route
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// The route is disabled for every caller
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    /// ClickHouse query failed or ClickHouse is unavailable
+    #[error("ClickHouse error: {0}")]
+    UpstreamClickHouse(String),
+
+    /// Reading, listing or signing a GCS object failed
+    #[error("GCS error: {0}")]
+    UpstreamGcs(String),
+
+    /// An upstream call exceeded its deadline
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    /// Data the route needs is still loading (`serve --lazy-metadata`), or
+    /// the ClickHouse circuit breaker is open
+    #[error("Not ready: {0}")]
+    NotReady(String),
+
     #[error("Hail Decoder Error: {0}")]
     HailDecoder(#[from] genohype_core::HailError),
 
-    #[error("Failed to transform data: {0}")]
-    DataTransformError(String),
-
     #[error("Internal task error: {0}")]
     JoinError(#[from] tokio::task::JoinError),
 
-    #[error("Invalid interval: {0}")]
-    InvalidInterval(String),
+    /// Unexpected server-side failure (bad data, encoding, rendering)
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
 
-    #[error("Not found: {0}")]
-    NotFound(String),
+impl AppError {
+    /// Stable machine-readable code
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "bad_request",
+            AppError::InvalidInterval(_) => "invalid_interval",
+            AppError::NotFound(_) => "not_found",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::UpstreamClickHouse(_) => "upstream_clickhouse",
+            AppError::UpstreamGcs(_) => "upstream_gcs",
+            AppError::Timeout(_) => "timeout",
+            AppError::NotReady(_) => "not_ready",
+            AppError::HailDecoder(_) => "hail_decoder",
+            AppError::JoinError(_) | AppError::Internal(_) => "internal",
+        }
+    }
+
+    /// HTTP status for this error
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) | AppError::InvalidInterval(_) => StatusCode::BAD_REQUEST,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::UpstreamClickHouse(_) | AppError::UpstreamGcs(_) => StatusCode::BAD_GATEWAY,
+            AppError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            AppError::NotReady(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::HailDecoder(_) | AppError::JoinError(_) | AppError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, error_message) = match &self {
-            AppError::HailDecoder(_) | AppError::DataTransformError(_) => {
-                (StatusCode::INTERNAL_SERVER_ERROR, self.to_string())
-            }
-            AppError::JoinError(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
-            AppError::InvalidInterval(_) => (StatusCode::BAD_REQUEST, self.to_string()),
-            AppError::NotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
-        };
+        let body = Json(ErrorResponse {
+            code: self.code().to_string(),
+            error: self.to_string(),
+        });
+        let mut response = (self.status(), body).into_response();
+        if matches!(self, AppError::NotReady(_)) {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(RETRY_AFTER_SECS));
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_and_statuses() {
+        let cases = [
+            (AppError::BadRequest("q".into()), "bad_request", StatusCode::BAD_REQUEST),
+            (AppError::NotFound("x".into()), "not_found", StatusCode::NOT_FOUND),
+            (AppError::Unauthorized("t".into()), "unauthorized", StatusCode::UNAUTHORIZED),
+            (
+                AppError::UpstreamClickHouse("down".into()),
+                "upstream_clickhouse",
+                StatusCode::BAD_GATEWAY,
+            ),
+            (AppError::UpstreamGcs("403".into()), "upstream_gcs", StatusCode::BAD_GATEWAY),
+            (AppError::Timeout("30s".into()), "timeout", StatusCode::GATEWAY_TIMEOUT),
+            (
+                AppError::NotReady("metadata".into()),
+                "not_ready",
+                StatusCode::SERVICE_UNAVAILABLE,
+            ),
+            (AppError::Internal("png".into()), "internal", StatusCode::INTERNAL_SERVER_ERROR),
+        ];
+        for (error, code, status) in cases {
+            assert_eq!(error.code(), code);
+            assert_eq!(error.status(), status);
+        }
 
-        let body = Json(json!({ "error": error_message }));
-        (status, body).into_response()
+        let response = AppError::NotReady("metadata".into()).into_response();
+        assert_eq!(response.headers()[header::RETRY_AFTER], "5");
+        let response = AppError::NotFound("x".into()).into_response();
+        assert!(!response.headers().contains_key(header::RETRY_AFTER));
     }
 }
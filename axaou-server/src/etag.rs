@@ -0,0 +1,130 @@
+//! ETag / conditional request middleware
+//!
+//! Wraps cacheable GET routes (config, analyses, categories, gene models and
+//! plot images): successful responses get a content-hash ETag, and requests
+//! whose `If-None-Match` matches it are answered with an empty 304 so the
+//! frontend and CDNs can revalidate instead of re-downloading.
+//!
+//! The tag is weak (`W/"..."`) because the compression layer sits outside
+//! this middleware, so the same tag covers the encoded variants.
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+/// 64-bit FNV-1a; stable across processes so replicas agree on tags
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// Weak ETag for a response body
+pub fn compute_etag(body: &[u8]) -> String {
+    format!("W/\"{:x}-{:016x}\"", body.len(), fnv1a(body))
+}
+
+/// Whether an `If-None-Match` header value matches `etag` (weak comparison)
+fn if_none_match_matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let target = opaque(etag);
+    if_none_match
+        .split(',')
+        .any(|candidate| candidate.trim() == "*" || opaque(candidate) == target)
+}
+
+fn not_modified(etag: HeaderValue, original: &HeaderMap) -> Response {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = StatusCode::NOT_MODIFIED;
+    let headers = response.headers_mut();
+    headers.insert(header::ETAG, etag);
+    // 304s must repeat the caching headers a 200 would have sent
+    for name in [header::CACHE_CONTROL, header::VARY, header::EXPIRES] {
+        if let Some(value) = original.get(&name) {
+            headers.insert(name, value.clone());
+        }
+    }
+    response
+}
+
+/// Axum middleware adding an ETag and honouring `If-None-Match`
+pub async fn conditional(request: Request, next: Next) -> Response {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return next.run(request).await;
+    }
+    let is_head = request.method() == Method::HEAD;
+    let if_none_match = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    // Handlers may set their own tag (e.g. from object metadata)
+    let (mut parts, body) = response.into_parts();
+    let (etag, body) = match parts.headers.get(header::ETAG).cloned() {
+        Some(etag) => (etag, body),
+        None => {
+            let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    tracing::warn!("Failed to buffer response for ETag: {}", e);
+                    parts.status = StatusCode::INTERNAL_SERVER_ERROR;
+                    return Response::from_parts(parts, Body::empty());
+                }
+            };
+            // HEAD answered from metadata (e.g. proxied plot images) has no body to hash
+            if is_head && bytes.is_empty() {
+                return Response::from_parts(parts, Body::empty());
+            }
+            let etag = HeaderValue::from_str(&compute_etag(&bytes)).expect("ETag is ASCII");
+            parts.headers.insert(header::ETAG, etag.clone());
+            (etag, Body::from(bytes))
+        }
+    };
+
+    let matched = match (&if_none_match, etag.to_str()) {
+        (Some(inm), Ok(tag)) => if_none_match_matches(inm, tag),
+        _ => false,
+    };
+    if matched {
+        return not_modified(etag, &parts.headers);
+    }
+
+    Response::from_parts(parts, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_etag_is_stable_and_content_sensitive() {
+        let a = compute_etag(b"{\"analyses\":[]}");
+        assert_eq!(a, compute_etag(b"{\"analyses\":[]}"));
+        assert_ne!(a, compute_etag(b"{\"analyses\":[1]}"));
+        assert!(a.starts_with("W/\""));
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let tag = compute_etag(b"payload");
+        assert!(if_none_match_matches(&tag, &tag));
+        // Strong form of the same opaque tag still matches (weak comparison)
+        assert!(if_none_match_matches(tag.trim_start_matches("W/"), &tag));
+        assert!(if_none_match_matches(&format!("\"other\", {}", tag), &tag));
+        assert!(if_none_match_matches("*", &tag));
+        assert!(!if_none_match_matches("\"other\"", &tag));
+    }
+}
@@ -0,0 +1,168 @@
+//! Arrow IPC / Parquet serialization of API rows
+//!
+//! Bulk endpoints can answer with an Arrow IPC stream or a Parquet file
+//! instead of JSON, so pandas/polars users load results without a JSON parse.
+//! Rows go through their serde representation: the schema is inferred from
+//! the serialized rows (nested objects become struct columns) and the rows
+//! are decoded into a single record batch by arrow-json.
+
+use crate::error::AppError;
+use arrow::array::RecordBatch;
+use arrow::datatypes::Schema;
+use arrow::ipc::writer::StreamWriter;
+use arrow::json::reader::{infer_json_schema_from_iterator, ReaderBuilder};
+use axum::{
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Media type of an Arrow IPC stream
+pub const ARROW_STREAM_MIME: &str = "application/vnd.apache.arrow.stream";
+/// Media type of a Parquet file
+pub const PARQUET_MIME: &str = "application/vnd.apache.parquet";
+
+/// Binary table encodings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableEncoding {
+    ArrowStream,
+    Parquet,
+}
+
+impl TableEncoding {
+    /// Encoding requested by an `Accept` header, if any
+    pub fn from_accept(headers: &HeaderMap) -> Option<Self> {
+        let accept = headers.get(header::ACCEPT)?.to_str().ok()?;
+        if accept.contains(ARROW_STREAM_MIME) {
+            Some(TableEncoding::ArrowStream)
+        } else if accept.contains(PARQUET_MIME) {
+            Some(TableEncoding::Parquet)
+        } else {
+            None
+        }
+    }
+
+    pub fn mime(self) -> &'static str {
+        match self {
+            TableEncoding::ArrowStream => ARROW_STREAM_MIME,
+            TableEncoding::Parquet => PARQUET_MIME,
+        }
+    }
+
+    /// Encode `rows` as a response body with the matching content type
+    pub fn respond<T: Serialize>(self, rows: &[T]) -> Result<Response, AppError> {
+        let batch = record_batch(rows)?;
+        let bytes = match self {
+            TableEncoding::ArrowStream => arrow_stream_bytes(&batch)?,
+            TableEncoding::Parquet => parquet_bytes(&batch)?,
+        };
+        Ok(([(header::CONTENT_TYPE, self.mime())], bytes).into_response())
+    }
+}
+
+fn arrow_error(e: impl std::fmt::Display) -> AppError {
+    AppError::Internal(format!("Arrow encoding failed: {}", e))
+}
+
+/// One record batch holding `rows`, schema inferred from their serde form
+pub fn record_batch<T: Serialize>(rows: &[T]) -> Result<RecordBatch, AppError> {
+    let values = rows
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(arrow_error)?;
+    let schema: Arc<Schema> =
+        Arc::new(infer_json_schema_from_iterator(values.iter().map(Ok)).map_err(arrow_error)?);
+    if values.is_empty() {
+        return Ok(RecordBatch::new_empty(schema));
+    }
+
+    let mut decoder = ReaderBuilder::new(schema.clone())
+        .with_batch_size(values.len())
+        .build_decoder()
+        .map_err(arrow_error)?;
+    decoder.serialize(&values).map_err(arrow_error)?;
+    Ok(decoder
+        .flush()
+        .map_err(arrow_error)?
+        .unwrap_or_else(|| RecordBatch::new_empty(schema)))
+}
+
+/// Arrow IPC stream (schema message, one batch, end-of-stream marker)
+pub fn arrow_stream_bytes(batch: &RecordBatch) -> Result<Vec<u8>, AppError> {
+    let mut writer = StreamWriter::try_new(Vec::new(), &batch.schema()).map_err(arrow_error)?;
+    writer.write(batch).map_err(arrow_error)?;
+    writer.into_inner().map_err(arrow_error)
+}
+
+/// Snappy-compressed Parquet file with a single row group
+pub fn parquet_bytes(batch: &RecordBatch) -> Result<Vec<u8>, AppError> {
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer =
+        ArrowWriter::try_new(Vec::new(), batch.schema(), Some(props)).map_err(arrow_error)?;
+    writer.write(batch).map_err(arrow_error)?;
+    writer.into_inner().map_err(arrow_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::ipc::reader::StreamReader;
+
+    #[derive(Serialize)]
+    struct Point {
+        gene_id: String,
+        pvalue: f64,
+        beta: Option<f64>,
+    }
+
+    #[test]
+    fn test_record_batch_roundtrip() {
+        let rows = vec![
+            Point {
+                gene_id: "ENSG1".to_string(),
+                pvalue: 1e-8,
+                beta: Some(0.2),
+            },
+            Point {
+                gene_id: "ENSG2".to_string(),
+                pvalue: 0.5,
+                beta: None,
+            },
+        ];
+        let batch = record_batch(&rows).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.num_columns(), 3);
+
+        let bytes = arrow_stream_bytes(&batch).unwrap();
+        let batches: Vec<RecordBatch> = StreamReader::try_new(bytes.as_slice(), None)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(batches, vec![batch.clone()]);
+
+        let parquet = parquet_bytes(&batch).unwrap();
+        assert_eq!(&parquet[..4], b"PAR1");
+
+        assert_eq!(record_batch::<Point>(&[]).unwrap().num_rows(), 0);
+    }
+
+    #[test]
+    fn test_from_accept() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(TableEncoding::from_accept(&headers), None);
+        headers.insert(header::ACCEPT, ARROW_STREAM_MIME.parse().unwrap());
+        assert_eq!(
+            TableEncoding::from_accept(&headers),
+            Some(TableEncoding::ArrowStream)
+        );
+        headers.insert(header::ACCEPT, "application/json".parse().unwrap());
+        assert_eq!(TableEncoding::from_accept(&headers), None);
+    }
+}
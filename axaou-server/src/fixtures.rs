@@ -0,0 +1,220 @@
+//! Fixture mode for local development and e2e tests
+//!
+//! `serve --fixtures <dir>` answers every request from canned files instead of
+//! GCS and ClickHouse, so the frontend can be developed and tested without
+//! cloud credentials. The request path maps onto the directory:
+//!
+//! ```text
+//! GET /api/config                   -> <dir>/api/config.json
+//! GET /api/genes/model/PCSK9        -> <dir>/api/genes/model/PCSK9.json
+//! GET /api/phenotype/height/qq.png  -> <dir>/api/phenotype/height/qq.png
+//! GET /api/variants?ancestry=eur    -> <dir>/api/variants__ancestry=eur.json
+//!                                      (falls back to <dir>/api/variants.json)
+//! ```
+//!
+//! Query-specific fixtures use the sorted `key=value` pairs joined by `&`,
+//! with characters other than ASCII alphanumerics and `-_.=&,` replaced by
+//! `_`. Requests without a fixture get the usual 404 error body.
+//!
+//! The `fixtures` directory at the crate root ships responses for the
+//! `height` phenotype and the PCSK9 gene, matching the endpoint test data in
+//! `src/test_support`.
+
+use crate::error::AppError;
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tower_http::cors::{Any, CorsLayer};
+use tracing::{debug, info};
+
+/// Root directory of the fixture files
+#[derive(Debug, Clone)]
+pub struct FixtureStore {
+    dir: PathBuf,
+}
+
+impl FixtureStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Candidate files for a request, most specific first; empty when the
+    /// path would escape the fixture directory
+    fn candidates(&self, path: &str, query: Option<&str>) -> Vec<PathBuf> {
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        if segments.is_empty() || segments.iter().any(|s| *s == ".." || s.contains('\\')) {
+            return Vec::new();
+        }
+        let base = segments
+            .iter()
+            .fold(self.dir.clone(), |dir, segment| dir.join(segment));
+
+        let mut candidates = Vec::new();
+        if base.extension().is_some() {
+            candidates.push(base.clone());
+        }
+        if let Some(query) = query.map(query_key).filter(|q| !q.is_empty()) {
+            candidates.push(with_suffix(&base, &format!("__{}.json", query)));
+        }
+        candidates.push(with_suffix(&base, ".json"));
+        candidates
+    }
+
+    async fn load(&self, path: &str, query: Option<&str>) -> Option<(PathBuf, Vec<u8>)> {
+        for candidate in self.candidates(path, query) {
+            if let Ok(body) = tokio::fs::read(&candidate).await {
+                return Some((candidate, body));
+            }
+        }
+        None
+    }
+}
+
+/// Normalized query string used in fixture file names
+fn query_key(query: &str) -> String {
+    let mut pairs: Vec<&str> = query.split('&').filter(|p| !p.is_empty()).collect();
+    pairs.sort_unstable();
+    pairs
+        .join("&")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "-_.=&,".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn with_suffix(base: &Path, suffix: &str) -> PathBuf {
+    let mut path = base.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("png") => "image/png",
+        Some("svg") => "image/svg+xml",
+        Some("tsv") => "text/tab-separated-values",
+        Some("csv") => "text/csv",
+        Some("txt") => "text/plain",
+        _ => "application/json",
+    }
+}
+
+/// Fallback handler serving the fixture for any request
+async fn serve_fixture(State(store): State<Arc<FixtureStore>>, request: Request) -> Response {
+    let uri = request.uri();
+    match store.load(uri.path(), uri.query()).await {
+        Some((file, body)) => {
+            debug!("{} -> {}", uri, file.display());
+            (
+                StatusCode::OK,
+                [(header::CONTENT_TYPE, content_type(&file))],
+                body,
+            )
+                .into_response()
+        }
+        None => AppError::NotFound(format!("No fixture for {}", uri)).into_response(),
+    }
+}
+
+/// Router answering every request from the fixtures in `dir`
+pub fn fixture_router(dir: PathBuf) -> Router {
+    Router::new()
+        .route("/healthz", get(crate::health::healthz))
+        .route("/readyz", get(crate::health::healthz))
+        .fallback(serve_fixture)
+        .with_state(Arc::new(FixtureStore::new(dir)))
+        .layer(
+            CorsLayer::new()
+                .allow_origin(Any)
+                .allow_methods(Any)
+                .allow_headers(Any),
+        )
+}
+
+/// Run the HTTP server in fixture mode
+pub async fn run_fixture_server(port: u16, dir: PathBuf) -> anyhow::Result<()> {
+    anyhow::ensure!(dir.is_dir(), "Fixture directory {} does not exist", dir.display());
+    info!("Serving fixtures from {} (GCS and ClickHouse disabled)", dir.display());
+
+    let app = fixture_router(dir);
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    info!("Server listening on http://{}", addr);
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates() {
+        let store = FixtureStore::new(PathBuf::from("/fixtures"));
+        assert_eq!(
+            store.candidates("/api/genes/model/PCSK9", None),
+            vec![PathBuf::from("/fixtures/api/genes/model/PCSK9.json")]
+        );
+        assert_eq!(
+            store.candidates("/api/variants", Some("limit=10&ancestry=eur")),
+            vec![
+                PathBuf::from("/fixtures/api/variants__ancestry=eur&limit=10.json"),
+                PathBuf::from("/fixtures/api/variants.json"),
+            ]
+        );
+        assert_eq!(
+            store.candidates("/api/phenotype/height/qq.png", None),
+            vec![
+                PathBuf::from("/fixtures/api/phenotype/height/qq.png"),
+                PathBuf::from("/fixtures/api/phenotype/height/qq.png.json"),
+            ]
+        );
+        assert!(store.candidates("/api/../../etc/passwd", None).is_empty());
+        assert!(store.candidates("/", None).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_shipped_fixtures() {
+        use axum::body::Body;
+        use tower::ServiceExt;
+
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let get = |uri: &'static str| {
+            fixture_router(dir.clone()).oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        };
+
+        let response = get("/api/config").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let config: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(config["dataset_version"], "414k");
+
+        // Any query falls back to the unqualified fixture
+        let response = get("/api/phenotype/height/significant?sequencing_type=exome")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = get("/api/genes/model/NOT_A_GENE").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_query_key() {
+        assert_eq!(query_key("b=2&a=1"), "a=1&b=2");
+        assert_eq!(query_key("region=1%3A100-200"), "region=1_3A100-200");
+    }
+}
@@ -3,148 +3,424 @@
 //! Provides two query backends:
 //! - `GeneModelsQuery`: Direct Hail Table queries via hail-decoder (legacy)
 //! - `GeneModelsClickHouse`: ClickHouse queries (preferred after migration)
-
-use crate::clickhouse::models::GeneModelRow;
+//!
+//! Handlers go through `AppState::gene_models`, a [`GeneModelBackend`] chosen at
+//! startup with `--gene-models-backend`. `auto` (the default) serves from
+//! ClickHouse and falls back to the Hail Table when ClickHouse errors, e.g.
+//! because `gene_models` has not been ingested yet.
+
+use crate::clickhouse::executor::{QueryExecutor, QueryExt};
+use crate::clickhouse::models::{GeneModelRow, GeneTranscriptsRow};
+use crate::config::Config;
 use crate::error::AppError;
+use crate::genomics::Contig;
 use crate::models::{Exon, GeneModel, GnomadConstraint, ManeSelectTranscript, Transcript};
 use clickhouse::Client;
+use futures::future::BoxFuture;
+use futures::FutureExt;
 use genohype_core::codec::EncodedValue;
 use genohype_core::query::QueryEngine;
 use std::collections::HashMap;
-use std::sync::Mutex;
-use tracing::info;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::OnceCell;
+use tracing::{info, warn};
+
+/// Which gene model backend handlers use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum GeneModelsBackendKind {
+    /// ClickHouse `gene_models` table only
+    #[value(name = "clickhouse")]
+    ClickHouse,
+    /// Hail Table from `Config::gene_models_ht_uri` only
+    Hail,
+    /// ClickHouse, falling back to the Hail Table on ClickHouse errors
+    #[default]
+    Auto,
+}
+
+/// Gene model lookups shared by all handlers
+///
+/// Search and transcript lookups need ClickHouse's `transcripts_json` and
+/// symbol indexes; the Hail backend answers them from full gene models or not
+/// at all.
+pub trait GeneModelBackend: Send + Sync {
+    /// Backend name for logs and error messages
+    fn name(&self) -> &'static str;
+
+    fn get_by_gene_id<'a>(
+        &'a self,
+        gene_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<GeneModel>, AppError>>;
+
+    fn get_by_symbol<'a>(
+        &'a self,
+        symbol: &'a str,
+    ) -> BoxFuture<'a, Result<Option<GeneModel>, AppError>>;
+
+    fn get_in_interval<'a>(
+        &'a self,
+        interval: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<GeneModel>, AppError>>;
+
+    fn search<'a>(
+        &'a self,
+        q: &'a str,
+        limit: u32,
+    ) -> BoxFuture<'a, Result<Vec<GeneModel>, AppError>> {
+        let _ = (q, limit);
+        unsupported(self.name(), "gene search")
+    }
 
-/// GCS path to the gene models reference Hail Table
-const GENE_MODELS_HT_PATH: &str =
-    "gs://axaou-browser-common/reference-data/genes_grch38_annotated_6.ht";
+    fn get_transcripts<'a>(
+        &'a self,
+        gene_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Vec<Transcript>>, AppError>> {
+        async move {
+            let gene = self.get_by_gene_id(gene_id).await?;
+            Ok(gene.map(|g| g.transcripts))
+        }
+        .boxed()
+    }
+
+    fn get_transcript<'a>(
+        &'a self,
+        transcript_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Transcript>, AppError>> {
+        let _ = transcript_id;
+        unsupported(self.name(), "transcript lookup by ID")
+    }
+}
+
+fn unsupported<'a, T: Send + 'a>(
+    backend: &'static str,
+    what: &str,
+) -> BoxFuture<'a, Result<T, AppError>> {
+    let message = format!(
+        "{} is not supported by the {} gene model backend",
+        what, backend
+    );
+    async move { Err(AppError::BadRequest(message)) }.boxed()
+}
+
+/// Build the configured backend (the Hail Table is opened on first use)
+pub fn backend(
+    kind: GeneModelsBackendKind,
+    client: Client,
+    executor: Arc<QueryExecutor>,
+    config: &Config,
+) -> Arc<dyn GeneModelBackend> {
+    let clickhouse = GeneModelsClickHouse::new(client, executor);
+    let hail = LazyGeneModelsQuery::new(config);
+    info!("Gene model backend: {:?}", kind);
+    match kind {
+        GeneModelsBackendKind::ClickHouse => Arc::new(clickhouse),
+        GeneModelsBackendKind::Hail => Arc::new(hail),
+        GeneModelsBackendKind::Auto => Arc::new(FallbackGeneModels {
+            primary: clickhouse,
+            fallback: hail,
+        }),
+    }
+}
+
+/// Idle engines kept per gene models table; more are opened on demand under load
+const MAX_IDLE_ENGINES: usize = 4;
 
 /// On-demand gene model query engine
+///
+/// hail-decoder's `QueryEngine` is synchronous, so each query checks an engine
+/// out of a small pool and runs on the blocking thread pool. Concurrent lookups
+/// get their own engines instead of queueing behind one lock.
 pub struct GeneModelsQuery {
-    engine: Mutex<QueryEngine>,
+    pool: Arc<EnginePool>,
+}
+
+struct EnginePool {
+    path: String,
+    idle: Mutex<Vec<QueryEngine>>,
+}
+
+impl EnginePool {
+    fn checkout(&self) -> Result<QueryEngine, AppError> {
+        let idle = self
+            .idle
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop();
+        match idle {
+            Some(engine) => Ok(engine),
+            None => Ok(QueryEngine::open_path(&self.path)?),
+        }
+    }
+
+    fn checkin(&self, engine: QueryEngine) {
+        let mut idle = self.idle.lock().unwrap_or_else(PoisonError::into_inner);
+        if idle.len() < MAX_IDLE_ENGINES {
+            idle.push(engine);
+        }
+    }
 }
 
 impl GeneModelsQuery {
-    /// Open the gene models table (fast - just reads metadata)
-    pub fn open() -> Result<Self, AppError> {
-        info!("Opening gene models table at {}", GENE_MODELS_HT_PATH);
-        let engine = QueryEngine::open_path(GENE_MODELS_HT_PATH)?;
+    /// Open the configured gene models table (fast - just reads metadata)
+    pub fn open(config: &Config) -> Result<Self, AppError> {
+        let path = config.gene_models_ht_uri();
+        info!("Opening gene models table at {}", path);
+        let engine = QueryEngine::open_path(&path)?;
         info!(
             "Gene models table ready: {} partitions, keys: {:?}",
             engine.num_partitions(),
             engine.key_fields()
         );
         Ok(Self {
-            engine: Mutex::new(engine),
+            pool: Arc::new(EnginePool {
+                path,
+                idle: Mutex::new(vec![engine]),
+            }),
         })
     }
 
-    /// Query a gene by gene_id (e.g., "ENSG00000139618")
-    pub fn get_by_gene_id(&self, gene_id: &str) -> Result<Option<GeneModel>, AppError> {
-        let engine = self.engine.lock().map_err(|e| {
-            AppError::DataTransformError(format!("Failed to acquire lock: {}", e))
-        })?;
+    /// Run `f` with a pooled engine on the blocking thread pool
+    async fn with_engine<T, F>(&self, f: F) -> Result<T, AppError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&QueryEngine) -> Result<T, AppError> + Send + 'static,
+    {
+        let pool = Arc::clone(&self.pool);
+        tokio::task::spawn_blocking(move || {
+            let engine = pool.checkout()?;
+            let result = f(&engine);
+            pool.checkin(engine);
+            result
+        })
+        .await?
+    }
 
+    /// Query a gene by gene_id (e.g., "ENSG00000139618")
+    pub async fn get_by_gene_id(&self, gene_id: &str) -> Result<Option<GeneModel>, AppError> {
         // Query with gene_id as the first key field
         let key_ranges = vec![genohype_core::query::KeyRange::point(
             "gene_id".to_string(),
             genohype_core::query::KeyValue::String(gene_id.to_string()),
         )];
 
-        let mut results = Vec::new();
-        for row_result in engine.query_iter(&key_ranges)? {
-            let encoded_row = row_result?;
-            if let Ok(model) = transform_to_gene_model(encoded_row) {
-                results.push(model);
+        self.with_engine(move |engine| {
+            for row_result in engine.query_iter(&key_ranges)? {
+                let encoded_row = row_result?;
+                if let Ok(model) = transform_to_gene_model(encoded_row) {
+                    return Ok(Some(model));
+                }
             }
-        }
-
-        Ok(results.into_iter().next())
+            Ok(None)
+        })
+        .await
     }
 
     /// Query a gene by symbol (scans all partitions - slower)
-    pub fn get_by_symbol(&self, symbol: &str) -> Result<Option<GeneModel>, AppError> {
-        let engine = self.engine.lock().map_err(|e| {
-            AppError::DataTransformError(format!("Failed to acquire lock: {}", e))
-        })?;
-
+    pub async fn get_by_symbol(&self, symbol: &str) -> Result<Option<GeneModel>, AppError> {
         let symbol_upper = symbol.to_uppercase();
 
-        // Full scan - no key filter (symbol is not a key field)
-        for row_result in engine.query_iter(&[])? {
-            let encoded_row = row_result?;
-            if let Ok(model) = transform_to_gene_model(encoded_row) {
-                if model.symbol_upper_case == symbol_upper {
-                    return Ok(Some(model));
+        self.with_engine(move |engine| {
+            // Full scan - no key filter (symbol is not a key field)
+            for row_result in engine.query_iter(&[])? {
+                let encoded_row = row_result?;
+                if let Ok(model) = transform_to_gene_model(encoded_row) {
+                    if model.symbol_upper_case == symbol_upper {
+                        return Ok(Some(model));
+                    }
                 }
             }
-        }
-
-        Ok(None)
+            Ok(None)
+        })
+        .await
     }
 
     /// Get genes in a genomic interval (scans relevant partitions)
-    pub fn get_in_interval(&self, interval: &str) -> Result<Vec<GeneModel>, AppError> {
+    pub async fn get_in_interval(&self, interval: &str) -> Result<Vec<GeneModel>, AppError> {
         let (chrom, start, stop) = parse_interval(interval)?;
 
-        let engine = self.engine.lock().map_err(|e| {
-            AppError::DataTransformError(format!("Failed to acquire lock: {}", e))
-        })?;
-
-        let mut genes = Vec::new();
-
-        // Full scan for now - could optimize with interval index
-        for row_result in engine.query_iter(&[])? {
-            let encoded_row = row_result?;
-            if let Ok(model) = transform_to_gene_model(encoded_row) {
-                let model_chrom = normalize_chrom(&model.chrom);
-                if model_chrom == chrom && model.stop >= start && model.start <= stop {
-                    genes.push(model);
+        self.with_engine(move |engine| {
+            let mut genes = Vec::new();
+
+            // Full scan for now - could optimize with interval index
+            for row_result in engine.query_iter(&[])? {
+                let encoded_row = row_result?;
+                if let Ok(model) = transform_to_gene_model(encoded_row) {
+                    let same_chrom = Contig::parse(&model.chrom).map(Contig::bare) == Some(&*chrom);
+                    if same_chrom && model.stop >= start && model.start <= stop {
+                        genes.push(model);
+                    }
                 }
             }
+
+            Ok(genes)
+        })
+        .await
+    }
+}
+
+/// `GeneModelsQuery` opened on first use, so startup doesn't wait on GCS
+pub struct LazyGeneModelsQuery {
+    config: Config,
+    query: OnceCell<GeneModelsQuery>,
+}
+
+impl LazyGeneModelsQuery {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: config.clone(),
+            query: OnceCell::new(),
         }
+    }
 
-        Ok(genes)
+    async fn get(&self) -> Result<&GeneModelsQuery, AppError> {
+        self.query
+            .get_or_try_init(|| async {
+                let config = self.config.clone();
+                tokio::task::spawn_blocking(move || GeneModelsQuery::open(&config)).await?
+            })
+            .await
     }
 }
 
-/// Parse genomic interval string into (chrom, start, stop)
-fn parse_interval(interval: &str) -> Result<(String, i64, i64), AppError> {
-    let parts: Vec<&str> = interval.split(':').collect();
-    if parts.len() != 2 {
-        return Err(AppError::InvalidInterval(format!(
-            "Invalid interval format: {}",
-            interval
-        )));
+impl GeneModelBackend for LazyGeneModelsQuery {
+    fn name(&self) -> &'static str {
+        "hail"
+    }
+
+    fn get_by_gene_id<'a>(
+        &'a self,
+        gene_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<GeneModel>, AppError>> {
+        async move { self.get().await?.get_by_gene_id(gene_id).await }.boxed()
+    }
+
+    fn get_by_symbol<'a>(
+        &'a self,
+        symbol: &'a str,
+    ) -> BoxFuture<'a, Result<Option<GeneModel>, AppError>> {
+        async move { self.get().await?.get_by_symbol(symbol).await }.boxed()
+    }
+
+    fn get_in_interval<'a>(
+        &'a self,
+        interval: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<GeneModel>, AppError>> {
+        async move { self.get().await?.get_in_interval(interval).await }.boxed()
+    }
+}
+
+/// ClickHouse first, Hail Table when ClickHouse fails
+struct FallbackGeneModels {
+    primary: GeneModelsClickHouse,
+    fallback: LazyGeneModelsQuery,
+}
+
+/// Run `primary`, retrying with `fallback` when it fails upstream
+async fn with_fallback<'a, T>(
+    what: &str,
+    primary: BoxFuture<'a, Result<T, AppError>>,
+    fallback: impl FnOnce() -> BoxFuture<'a, Result<T, AppError>>,
+) -> Result<T, AppError> {
+    match primary.await {
+        Err(AppError::UpstreamClickHouse(e)) | Err(AppError::Timeout(e)) => {
+            warn!(
+                "ClickHouse gene models {} failed, using Hail Table: {}",
+                what, e
+            );
+            fallback().await
+        }
+        result => result,
+    }
+}
+
+impl GeneModelBackend for FallbackGeneModels {
+    fn name(&self) -> &'static str {
+        "auto"
     }
 
-    let chrom = normalize_chrom(parts[0]);
-    let range_parts: Vec<&str> = parts[1].split('-').collect();
-    if range_parts.len() != 2 {
-        return Err(AppError::InvalidInterval(format!(
-            "Invalid interval format: {}",
-            interval
-        )));
+    fn get_by_gene_id<'a>(
+        &'a self,
+        gene_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<GeneModel>, AppError>> {
+        with_fallback(
+            "lookup",
+            self.primary.get_by_gene_id(gene_id).boxed(),
+            || self.fallback.get_by_gene_id(gene_id),
+        )
+        .boxed()
+    }
+
+    fn get_by_symbol<'a>(
+        &'a self,
+        symbol: &'a str,
+    ) -> BoxFuture<'a, Result<Option<GeneModel>, AppError>> {
+        with_fallback(
+            "symbol lookup",
+            self.primary.get_by_symbol(symbol).boxed(),
+            || self.fallback.get_by_symbol(symbol),
+        )
+        .boxed()
+    }
+
+    fn get_in_interval<'a>(
+        &'a self,
+        interval: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<GeneModel>, AppError>> {
+        with_fallback(
+            "interval query",
+            self.primary.get_in_interval(interval).boxed(),
+            || self.fallback.get_in_interval(interval),
+        )
+        .boxed()
     }
 
-    let start: i64 = range_parts[0].parse().map_err(|_| {
-        AppError::InvalidInterval(format!("Invalid start position: {}", range_parts[0]))
-    })?;
-    let stop: i64 = range_parts[1].parse().map_err(|_| {
-        AppError::InvalidInterval(format!("Invalid stop position: {}", range_parts[1]))
-    })?;
+    fn search<'a>(
+        &'a self,
+        q: &'a str,
+        limit: u32,
+    ) -> BoxFuture<'a, Result<Vec<GeneModel>, AppError>> {
+        self.primary.search(q, limit).boxed()
+    }
 
-    Ok((chrom, start, stop))
+    fn get_transcripts<'a>(
+        &'a self,
+        gene_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Vec<Transcript>>, AppError>> {
+        with_fallback(
+            "transcripts lookup",
+            self.primary.get_transcripts(gene_id).boxed(),
+            || GeneModelBackend::get_transcripts(&self.fallback, gene_id),
+        )
+        .boxed()
+    }
+
+    fn get_transcript<'a>(
+        &'a self,
+        transcript_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Transcript>, AppError>> {
+        self.primary.get_transcript(transcript_id).boxed()
+    }
 }
 
-/// Normalize chromosome name (remove "chr" prefix)
-fn normalize_chrom(chrom: &str) -> String {
-    chrom.strip_prefix("chr").unwrap_or(chrom).to_string()
+/// Parse genomic interval string into (chrom, start, stop)
+///
+/// Accepts the coordinate forms of [`crate::genomics::parse_region`]; gene
+/// names must be resolved by the caller first.
+fn parse_interval(interval: &str) -> Result<(String, i64, i64), AppError> {
+    match crate::genomics::parse_region(interval)? {
+        crate::genomics::RegionQuery::Interval(region) => {
+            Ok((region.chrom, region.start as i64, region.stop as i64))
+        }
+        crate::genomics::RegionQuery::Gene { name, .. } => Err(AppError::InvalidInterval(
+            format!("Expected coordinates, got gene '{}'", name),
+        )),
+    }
 }
 
 /// Transform an EncodedValue row into a GeneModel
 fn transform_to_gene_model(value: EncodedValue) -> Result<GeneModel, AppError> {
     let EncodedValue::Struct(fields) = value else {
-        return Err(AppError::DataTransformError(
+        return Err(AppError::Internal(
             "Expected Struct at top level".to_string(),
         ));
     };
@@ -227,7 +503,7 @@ fn transform_to_gene_model(value: EncodedValue) -> Result<GeneModel, AppError> {
 fn get_string(map: &HashMap<String, EncodedValue>, key: &str) -> Result<String, AppError> {
     map.get(key)
         .and_then(|v| v.as_string())
-        .ok_or_else(|| AppError::DataTransformError(format!("Missing required field: {}", key)))
+        .ok_or_else(|| AppError::Internal(format!("Missing required field: {}", key)))
 }
 
 fn get_string_opt(map: &HashMap<String, EncodedValue>, key: &str) -> Option<String> {
@@ -237,7 +513,7 @@ fn get_string_opt(map: &HashMap<String, EncodedValue>, key: &str) -> Option<Stri
 fn get_i64(map: &HashMap<String, EncodedValue>, key: &str) -> Result<i64, AppError> {
     map.get(key)
         .and_then(|v| extract_i64(v))
-        .ok_or_else(|| AppError::DataTransformError(format!("Missing required field: {}", key)))
+        .ok_or_else(|| AppError::Internal(format!("Missing required field: {}", key)))
 }
 
 fn get_i64_opt(map: &HashMap<String, EncodedValue>, key: &str) -> Option<i64> {
@@ -432,18 +708,37 @@ fn get_f64(map: &HashMap<String, EncodedValue>, key: &str) -> f64 {
 // ClickHouse-based Gene Model Queries
 // ============================================================================
 
+/// Rank of a `gene_models` row as a match for the uppercase query `q`, lower
+/// is better: exact symbol or ID, symbol prefix, exact alias, alias prefix,
+/// previous symbol prefix, search term, Ensembl ID prefix. Shared by
+/// [`GeneModelsClickHouse::search`] and `/api/genes/search`.
+pub(crate) const GENE_SEARCH_RANK_SQL: &str = "toUInt8(multiIf(
+    symbol_upper_case = q OR gene_id = q, 0,
+    startsWith(symbol_upper_case, q), 1,
+    arrayExists(a -> upper(a) = q, alias_symbols), 2,
+    arrayExists(a -> startsWith(upper(a), q), alias_symbols), 3,
+    arrayExists(a -> startsWith(upper(ifNull(a, '')), q), previous_symbols), 4,
+    arrayExists(t -> upper(t) = q, search_terms), 5,
+    startsWith(gene_id, q), 6,
+    7))";
+
+/// [`GENE_SEARCH_RANK_SQL`] of rows that match none of the names or IDs
+pub(crate) const GENE_SEARCH_NO_MATCH: u8 = 7;
+
 /// ClickHouse-based gene model query engine
 ///
 /// Queries the `gene_models` table in ClickHouse for gene model data.
 /// This is the preferred backend after migration from Hail Tables.
 pub struct GeneModelsClickHouse {
     client: Client,
+    /// The dataset's executor, so lookups are logged and counted per request
+    executor: Arc<QueryExecutor>,
 }
 
 impl GeneModelsClickHouse {
     /// Create a new ClickHouse gene models query engine
-    pub fn new(client: Client) -> Self {
-        Self { client }
+    pub fn new(client: Client, executor: Arc<QueryExecutor>) -> Self {
+        Self { client, executor }
     }
 
     /// Query a gene by gene_id (e.g., "ENSG00000139618")
@@ -454,9 +749,8 @@ impl GeneModelsClickHouse {
             .client
             .query(&query)
             .bind(gene_id)
-            .fetch_optional::<GeneModelRow>()
-            .await
-            .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+            .fetch_optional_with::<GeneModelRow>(&self.executor)
+            .await?;
 
         Ok(result.map(|row| row.to_api_model()))
     }
@@ -469,9 +763,8 @@ impl GeneModelsClickHouse {
             .client
             .query(&query)
             .bind(symbol.to_uppercase())
-            .fetch_optional::<GeneModelRow>()
-            .await
-            .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+            .fetch_optional_with::<GeneModelRow>(&self.executor)
+            .await?;
 
         Ok(result.map(|row| row.to_api_model()))
     }
@@ -486,26 +779,95 @@ impl GeneModelsClickHouse {
             "WHERE chrom = ? AND stop >= ? AND start <= ? ORDER BY start",
         );
 
-        // Normalize chromosome (remove 'chr' prefix - gene_models uses "4" not "chr4")
-        let chrom_normalized = if chrom.starts_with("chr") {
-            chrom.strip_prefix("chr").unwrap().to_string()
-        } else {
-            chrom
-        };
-
+        // parse_interval yields the bare form gene_models stores ("4", "X")
         let results = self
             .client
             .query(&query)
-            .bind(&chrom_normalized)
+            .bind(&chrom)
             .bind(start)
             .bind(stop)
-            .fetch_all::<GeneModelRow>()
-            .await
-            .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+            .fetch_all_with::<GeneModelRow>(&self.executor)
+            .await?;
+
+        Ok(results.into_iter().map(|row| row.to_api_model()).collect())
+    }
+
+    /// Search genes by symbol, Ensembl ID, alias, previous symbol or search term
+    ///
+    /// `q` is matched case-insensitively. Results are ranked exact match first,
+    /// then symbol prefix, alias, previous symbol, search term and Ensembl ID
+    /// prefix; ties are broken by symbol length.
+    pub async fn search(&self, q: &str, limit: u32) -> Result<Vec<GeneModel>, AppError> {
+        let q = q.trim().to_uppercase();
+        if q.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let query = format!(
+            "WITH ? AS q {}",
+            Self::build_select_query(&format!(
+                r#"
+                WHERE symbol != '' AND {rank} < {no_match}
+                ORDER BY {rank} ASC, length(symbol) ASC, symbol ASC
+                LIMIT ?
+                "#,
+                rank = GENE_SEARCH_RANK_SQL,
+                no_match = GENE_SEARCH_NO_MATCH
+            ))
+        );
+
+        let results = self
+            .client
+            .query(&query)
+            .bind(&q)
+            .bind(limit)
+            .fetch_all_with::<GeneModelRow>(&self.executor)
+            .await?;
 
         Ok(results.into_iter().map(|row| row.to_api_model()).collect())
     }
 
+    /// Transcripts (with exons) of a gene, without the rest of the gene model
+    pub async fn get_transcripts(
+        &self,
+        gene_id: &str,
+    ) -> Result<Option<Vec<Transcript>>, AppError> {
+        let result = self
+            .client
+            .query("SELECT gene_id, transcripts_json FROM gene_models WHERE gene_id = ?")
+            .bind(gene_id)
+            .fetch_optional_with::<GeneTranscriptsRow>(&self.executor)
+            .await?;
+
+        Ok(result.map(|row| row.transcripts()))
+    }
+
+    /// Look up a single transcript by Ensembl ID, with or without version
+    ///
+    /// `gene_models` is keyed by gene, so candidate genes are found through
+    /// the indexed `transcript_ids` column and their parsed transcripts are
+    /// then matched exactly.
+    pub async fn get_transcript(
+        &self,
+        transcript_id: &str,
+    ) -> Result<Option<Transcript>, AppError> {
+        let (id, version) = split_transcript_version(transcript_id);
+        let rows = self
+            .client
+            .query(
+                "SELECT gene_id, transcripts_json FROM gene_models \
+                 WHERE has(transcript_ids, ?) LIMIT 10",
+            )
+            .bind(id)
+            .fetch_all_with::<GeneTranscriptsRow>(&self.executor)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .flat_map(|row| row.transcripts())
+            .find(|t| t.transcript_id == id && version.map_or(true, |v| t.transcript_version == v)))
+    }
+
     /// Build the SELECT query with all gene_models columns
     fn build_select_query(where_clause: &str) -> String {
         format!(
@@ -533,12 +895,115 @@ impl GeneModelsClickHouse {
     }
 }
 
+/// Split "ENST00000380152.8" into ("ENST00000380152", Some("8"))
+fn split_transcript_version(transcript_id: &str) -> (&str, Option<&str>) {
+    let transcript_id = transcript_id.trim();
+    match transcript_id.split_once('.') {
+        Some((id, version)) if !version.is_empty() => (id, Some(version)),
+        Some((id, _)) => (id, None),
+        None => (transcript_id, None),
+    }
+}
+
+impl GeneModelBackend for GeneModelsClickHouse {
+    fn name(&self) -> &'static str {
+        "clickhouse"
+    }
+
+    fn get_by_gene_id<'a>(
+        &'a self,
+        gene_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<GeneModel>, AppError>> {
+        GeneModelsClickHouse::get_by_gene_id(self, gene_id).boxed()
+    }
+
+    fn get_by_symbol<'a>(
+        &'a self,
+        symbol: &'a str,
+    ) -> BoxFuture<'a, Result<Option<GeneModel>, AppError>> {
+        GeneModelsClickHouse::get_by_symbol(self, symbol).boxed()
+    }
+
+    fn get_in_interval<'a>(
+        &'a self,
+        interval: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<GeneModel>, AppError>> {
+        GeneModelsClickHouse::get_in_interval(self, interval).boxed()
+    }
+
+    fn search<'a>(
+        &'a self,
+        q: &'a str,
+        limit: u32,
+    ) -> BoxFuture<'a, Result<Vec<GeneModel>, AppError>> {
+        GeneModelsClickHouse::search(self, q, limit).boxed()
+    }
+
+    fn get_transcripts<'a>(
+        &'a self,
+        gene_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Vec<Transcript>>, AppError>> {
+        GeneModelsClickHouse::get_transcripts(self, gene_id).boxed()
+    }
+
+    fn get_transcript<'a>(
+        &'a self,
+        transcript_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Transcript>, AppError>> {
+        GeneModelsClickHouse::get_transcript(self, transcript_id).boxed()
+    }
+}
+
 /// Check if the gene_models table exists in ClickHouse
-pub async fn gene_models_table_exists(client: &Client) -> bool {
+pub async fn gene_models_table_exists(client: &Client, executor: &QueryExecutor) -> bool {
     let result = client
         .query("SELECT 1 FROM gene_models LIMIT 1")
-        .fetch_optional::<u8>()
+        .fetch_optional_with::<u8>(executor)
         .await;
 
     result.is_ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_transcript_version() {
+        assert_eq!(
+            split_transcript_version("ENST00000380152.8"),
+            ("ENST00000380152", Some("8"))
+        );
+        assert_eq!(
+            split_transcript_version(" ENST00000380152 "),
+            ("ENST00000380152", None)
+        );
+        assert_eq!(
+            split_transcript_version("ENST00000380152."),
+            ("ENST00000380152", None)
+        );
+    }
+
+    #[test]
+    fn test_backend_kind_names() {
+        use clap::ValueEnum;
+        for (name, kind) in [
+            ("clickhouse", GeneModelsBackendKind::ClickHouse),
+            ("hail", GeneModelsBackendKind::Hail),
+            ("auto", GeneModelsBackendKind::Auto),
+        ] {
+            assert_eq!(GeneModelsBackendKind::from_str(name, true), Ok(kind));
+        }
+        assert_eq!(GeneModelsBackendKind::default(), GeneModelsBackendKind::Auto);
+    }
+
+    #[tokio::test]
+    async fn test_hail_backend_rejects_search_without_opening_table() {
+        let hail = LazyGeneModelsQuery::new(&Config::default());
+        assert!(matches!(
+            hail.search("BRCA", 10).await,
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(hail.query.get().is_none());
+    }
+}
@@ -5,66 +5,179 @@
 //!
 //! Key: (gene_id, gene_symbol, annotation, max_MAF)
 //! Values: Pvalue, Pvalue_Burden, Pvalue_SKAT, BETA_Burden, SE_Burden, MAC, etc.
+//!
+//! Cauchy combination rows (max_MAF = -1) are selected with `GeneTest`, as on
+//! the ClickHouse path.
+//!
+//! Multi-ancestry queries read the per-ancestry tables concurrently, bounded
+//! by an engine-wide cap on Hail Table reads and a per-request timeout.
+//! Opened tables are kept in an [`EngineCache`], so repeated queries to the
+//! same phenotype skip re-reading table metadata and indexes from GCS.
 
 use crate::error::AppError;
+use crate::genes::gene_test::GeneTest;
 use crate::models::{
     AnalysisAssetType, AnalysisAssets, AncestryGroup, GeneAssociationResponse,
     GeneAssociationResult, GeneQueryParams,
 };
 use genohype_core::codec::EncodedValue;
+use futures::future::join_all;
 use genohype_core::query::{KeyRange, KeyValue, QueryEngine};
 use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::sync::{RwLock, Semaphore};
 use tracing::{debug, info, warn};
 
 /// Default max_MAF filter value (from shared.py config)
 pub const DEFAULT_MAX_MAF: f64 = 0.001;
 
+/// Hail Table reads in flight across all gene queries
+const MAX_CONCURRENT_HT_READS: usize = 8;
+
+/// Upper bound on a single `query_gene` request
+const GENE_QUERY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// gene_results.ht tables kept open; least recently used are evicted beyond this
+const MAX_CACHED_TABLES: usize = 256;
+
+/// Idle engines kept per table; more are opened on demand under load
+const MAX_IDLE_ENGINES_PER_TABLE: usize = 2;
+
+/// Engines older than this are reopened, so rewritten tables are picked up
+const ENGINE_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// Opened Hail Table engines keyed by URI
+///
+/// Like the gene models pool, an engine is checked out for one blocking read
+/// and checked back in afterwards, so concurrent reads of one table get their
+/// own engines. Generic over the engine type for tests.
+struct EngineCache<E> {
+    tables: Mutex<HashMap<String, CachedTable<E>>>,
+}
+
+struct CachedTable<E> {
+    /// Idle engines with the time each was opened
+    idle: Vec<(E, Instant)>,
+    last_used: Instant,
+}
+
+impl<E> EngineCache<E> {
+    fn new() -> Self {
+        Self {
+            tables: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Run `f` with a cached engine for `uri`, opening one with `open` when
+    /// none is idle. Engines are only returned to the cache after a success.
+    fn with_engine<T>(
+        &self,
+        uri: &str,
+        open: impl FnOnce(&str) -> Result<E, AppError>,
+        f: impl FnOnce(&E) -> Result<T, AppError>,
+    ) -> Result<T, AppError> {
+        let (engine, opened_at) = match self.checkout(uri) {
+            Some(cached) => cached,
+            None => {
+                debug!("Opening HT: {}", uri);
+                (open(uri)?, Instant::now())
+            }
+        };
+        let result = f(&engine)?;
+        self.checkin(uri, engine, opened_at);
+        Ok(result)
+    }
+
+    fn checkout(&self, uri: &str) -> Option<(E, Instant)> {
+        let mut tables = self.tables.lock().unwrap_or_else(PoisonError::into_inner);
+        let table = tables.get_mut(uri)?;
+        table.last_used = Instant::now();
+        table.idle.retain(|(_, opened_at)| opened_at.elapsed() < ENGINE_TTL);
+        table.idle.pop()
+    }
+
+    fn checkin(&self, uri: &str, engine: E, opened_at: Instant) {
+        if opened_at.elapsed() >= ENGINE_TTL {
+            return;
+        }
+        let mut tables = self.tables.lock().unwrap_or_else(PoisonError::into_inner);
+        if !tables.contains_key(uri) && tables.len() >= MAX_CACHED_TABLES {
+            let oldest = tables
+                .iter()
+                .min_by_key(|(_, table)| table.last_used)
+                .map(|(uri, _)| uri.clone());
+            if let Some(oldest) = oldest {
+                tables.remove(&oldest);
+            }
+        }
+        let table = tables.entry(uri.to_string()).or_insert_with(|| CachedTable {
+            idle: Vec::new(),
+            last_used: Instant::now(),
+        });
+        table.last_used = Instant::now();
+        if table.idle.len() < MAX_IDLE_ENGINES_PER_TABLE {
+            table.idle.push((engine, opened_at));
+        }
+    }
+}
+
+fn open_engine(uri: &str) -> Result<QueryEngine, AppError> {
+    Ok(QueryEngine::open_path(uri)?)
+}
+
 /// On-demand gene association query engine
 pub struct GeneQueryEngine {
     /// Shared reference to discovered assets
     assets: Arc<RwLock<Option<AnalysisAssets>>>,
+    /// Permits for blocking Hail Table reads
+    reads: Arc<Semaphore>,
+    /// Opened gene_results.ht tables
+    engines: Arc<EngineCache<QueryEngine>>,
 }
 
 impl GeneQueryEngine {
     /// Create a new query engine with access to the assets cache
     pub fn new(assets: Arc<RwLock<Option<AnalysisAssets>>>) -> Self {
-        Self { assets }
+        Self {
+            assets,
+            reads: Arc::new(Semaphore::new(MAX_CONCURRENT_HT_READS)),
+            engines: Arc::new(EngineCache::new()),
+        }
     }
 
     /// Query gene associations for a specific phenotype and gene
     ///
     /// This queries the gene_results.ht for the given analysis_id and returns
     /// all associations for the specified gene, filtered by ancestry and max_maf.
+    /// With `all_ancestries`, each ancestry's table is read concurrently.
     pub async fn query_gene(
         &self,
         analysis_id: &str,
         gene_id: &str,
         params: GeneQueryParams,
     ) -> Result<GeneAssociationResponse, AppError> {
-        let assets = self.assets.read().await;
-        let assets = assets.as_ref().ok_or_else(|| {
-            AppError::DataTransformError("Assets not loaded".to_string())
-        })?;
-
-        // Determine which ancestries to query
-        let ancestries: Vec<AncestryGroup> = match params.ancestry {
-            Some(anc) => vec![anc],
-            None => vec![AncestryGroup::Meta], // Default to META only
+        let ancestries = selected_ancestries(&params);
+
+        // Find gene_results.ht URIs for this analysis, without holding the
+        // assets lock across the reads
+        let gene_assets: Vec<(String, AncestryGroup)> = {
+            let assets = self.assets.read().await;
+            let assets = assets.as_ref().ok_or_else(|| {
+                AppError::Internal("Assets not loaded".to_string())
+            })?;
+            assets
+                .assets
+                .iter()
+                .filter(|a| {
+                    a.analysis_id.eq_ignore_ascii_case(analysis_id)
+                        && a.asset_type == AnalysisAssetType::Gene
+                        && ancestries.contains(&a.ancestry_group)
+                })
+                .map(|a| (a.uri.clone(), a.ancestry_group))
+                .collect()
         };
 
-        // Find gene_results.ht URIs for this analysis
-        let gene_assets: Vec<_> = assets
-            .assets
-            .iter()
-            .filter(|a| {
-                a.analysis_id.eq_ignore_ascii_case(analysis_id)
-                    && a.asset_type == AnalysisAssetType::Gene
-                    && ancestries.contains(&a.ancestry_group)
-            })
-            .collect();
-
         if gene_assets.is_empty() {
             return Err(AppError::NotFound(format!(
                 "No gene results found for analysis_id: {}",
@@ -79,26 +192,48 @@ impl GeneQueryEngine {
             analysis_id
         );
 
-        // Query each HT and collect results
-        let mut all_results = Vec::new();
         let max_maf = params.max_maf.unwrap_or(DEFAULT_MAX_MAF);
-        let annotation_filter = params.annotation.clone();
+        let test = params.test;
 
-        for asset in gene_assets {
-            let uri = asset.uri.clone();
-            let ancestry = asset.ancestry_group;
+        // One blocking read per ancestry (hail-decoder is sync), run
+        // concurrently under the engine-wide read cap
+        let reads = gene_assets.into_iter().map(|(uri, ancestry)| {
+            let permits = Arc::clone(&self.reads);
+            let engines = Arc::clone(&self.engines);
             let aid = analysis_id.to_string();
             let gid = gene_id.to_string();
-            let ann_filter = annotation_filter.clone();
+            let ann_filter = params.annotation.clone();
+            async move {
+                let permit = permits
+                    .acquire_owned()
+                    .await
+                    .map_err(|e| AppError::Internal(format!("Read limiter closed: {}", e)))?;
+                tokio::task::spawn_blocking(move || {
+                    // Held until the read itself ends, even if the request
+                    // timed out and dropped this future
+                    let _permit = permit;
+                    engines.with_engine(&uri, open_engine, |engine| {
+                        query_gene_ht(engine, &uri, &gid, &aid, ancestry, max_maf, test, ann_filter.as_deref())
+                    })
+                })
+                .await
+                .map_err(|e| AppError::Internal(format!("Task join error: {}", e)))?
+            }
+        });
 
-            // Query in a blocking task since hail-decoder is sync
-            let results = tokio::task::spawn_blocking(move || {
-                query_gene_ht(&uri, &gid, &aid, ancestry, max_maf, ann_filter.as_deref())
-            })
+        // Reads already running finish in the background after a timeout
+        let per_ancestry = tokio::time::timeout(GENE_QUERY_TIMEOUT, join_all(reads))
             .await
-            .map_err(|e| AppError::DataTransformError(format!("Task join error: {}", e)))??;
+            .map_err(|_| {
+                AppError::Timeout(format!(
+                    "Gene query for {} in {} exceeded {:?}",
+                    gene_id, analysis_id, GENE_QUERY_TIMEOUT
+                ))
+            })?;
 
-            all_results.extend(results);
+        let mut all_results = Vec::new();
+        for results in per_ancestry {
+            all_results.extend(results?);
         }
 
         // Extract gene_symbol from results (should be consistent)
@@ -116,7 +251,10 @@ impl GeneQueryEngine {
 
     /// Query all genes for a phenotype (paginated)
     ///
-    /// This is useful for building gene-level Manhattan plots or tables.
+    /// Deprecated: scans the whole table on every request. The gene list
+    /// endpoint reads `gene_associations` in ClickHouse and only falls back to
+    /// this for phenotypes that have not been ingested. Results are ordered as
+    /// in ClickHouse (pvalue ascending, then gene_id, annotation, max_maf).
     pub async fn query_all_genes(
         &self,
         analysis_id: &str,
@@ -126,7 +264,7 @@ impl GeneQueryEngine {
     ) -> Result<Vec<GeneAssociationResult>, AppError> {
         let assets = self.assets.read().await;
         let assets = assets.as_ref().ok_or_else(|| {
-            AppError::DataTransformError("Assets not loaded".to_string())
+            AppError::Internal("Assets not loaded".to_string())
         })?;
 
         // Default to META ancestry
@@ -152,6 +290,7 @@ impl GeneQueryEngine {
         let aid = analysis_id.to_string();
         let max_maf = params.max_maf.unwrap_or(DEFAULT_MAX_MAF);
         let annotation_filter = params.annotation.clone();
+        let test = params.test;
         let limit = limit.unwrap_or(1000);
         let offset = offset.unwrap_or(0);
 
@@ -161,28 +300,40 @@ impl GeneQueryEngine {
         );
 
         // Query in a blocking task
+        let engines = Arc::clone(&self.engines);
         let results = tokio::task::spawn_blocking(move || {
-            query_all_genes_ht(&uri, &aid, ancestry, max_maf, annotation_filter.as_deref(), limit, offset)
+            engines.with_engine(&uri, open_engine, |engine| {
+                query_all_genes_ht(engine, &uri, &aid, ancestry, max_maf, test, annotation_filter.as_deref(), limit, offset)
+            })
         })
         .await
-        .map_err(|e| AppError::DataTransformError(format!("Task join error: {}", e)))??;
+        .map_err(|e| AppError::Internal(format!("Task join error: {}", e)))??;
 
         Ok(results)
     }
 }
 
+/// Ancestry groups a gene query reads
+fn selected_ancestries(params: &GeneQueryParams) -> Vec<AncestryGroup> {
+    if params.all_ancestries {
+        AncestryGroup::all().to_vec()
+    } else {
+        // Default to META only
+        vec![params.ancestry.unwrap_or(AncestryGroup::Meta)]
+    }
+}
+
 /// Query a gene_results.ht file for a specific gene
 fn query_gene_ht(
+    engine: &QueryEngine,
     uri: &str,
     gene_id: &str,
     analysis_id: &str,
     ancestry: AncestryGroup,
     max_maf: f64,
+    test: GeneTest,
     annotation_filter: Option<&str>,
 ) -> Result<Vec<GeneAssociationResult>, AppError> {
-    debug!("Opening HT: {}", uri);
-    let engine = QueryEngine::open_path(uri)?;
-
     // Query by gene_id (first key field)
     let key_ranges = vec![KeyRange::point(
         "gene_id".to_string(),
@@ -194,9 +345,8 @@ fn query_gene_ht(
     for row_result in engine.query_iter(&key_ranges)? {
         let encoded_row = row_result?;
         if let Ok(result) = transform_gene_result(encoded_row, analysis_id, &ancestry.to_string()) {
-            // Apply max_maf filter
-            if (result.max_maf - max_maf).abs() < 0.0001 || result.max_maf < 0.0 {
-                // Include if max_maf matches or is -1 (Cauchy)
+            // Apply max_maf / Cauchy (max_maf = -1) selection
+            if test.matches(result.max_maf, Some(max_maf)) {
                 // Apply annotation filter if specified
                 if let Some(ann) = annotation_filter {
                     if result.annotation.eq_ignore_ascii_case(ann) {
@@ -220,26 +370,27 @@ fn query_gene_ht(
 
 /// Query all genes from a gene_results.ht file
 fn query_all_genes_ht(
+    engine: &QueryEngine,
     uri: &str,
     analysis_id: &str,
     ancestry: AncestryGroup,
     max_maf: f64,
+    test: GeneTest,
     annotation_filter: Option<&str>,
     limit: usize,
     offset: usize,
 ) -> Result<Vec<GeneAssociationResult>, AppError> {
-    debug!("Opening HT for full scan: {}", uri);
-    let engine = QueryEngine::open_path(uri)?;
+    debug!("Full scan of HT: {}", uri);
 
-    // Full scan (no key filter)
+    // Full scan (no key filter); sorted before paginating so pages match
+    // the ClickHouse path
     let mut results = Vec::new();
-    let mut skipped = 0;
 
     for row_result in engine.query_iter(&[])? {
         let encoded_row = row_result?;
         if let Ok(result) = transform_gene_result(encoded_row, analysis_id, &ancestry.to_string()) {
-            // Apply max_maf filter
-            if (result.max_maf - max_maf).abs() < 0.0001 {
+            // Apply max_maf / Cauchy (max_maf = -1) selection
+            if test.matches(result.max_maf, Some(max_maf)) {
                 // Apply annotation filter if specified
                 let include = if let Some(ann) = annotation_filter {
                     result.annotation.eq_ignore_ascii_case(ann)
@@ -248,19 +399,15 @@ fn query_all_genes_ht(
                 };
 
                 if include {
-                    if skipped < offset {
-                        skipped += 1;
-                    } else {
-                        results.push(result);
-                        if results.len() >= limit {
-                            break;
-                        }
-                    }
+                    results.push(result);
                 }
             }
         }
     }
 
+    results.sort_by(compare_gene_results);
+    let results: Vec<_> = results.into_iter().skip(offset).take(limit).collect();
+
     debug!(
         "Found {} results (offset: {}, limit: {}) from {}",
         results.len(),
@@ -271,6 +418,18 @@ fn query_all_genes_ht(
     Ok(results)
 }
 
+/// ClickHouse's `ORDER BY pvalue ASC, gene_id, annotation, max_maf`
+/// (missing p-values last)
+fn compare_gene_results(a: &GeneAssociationResult, b: &GeneAssociationResult) -> std::cmp::Ordering {
+    let pvalue = |r: &GeneAssociationResult| r.pvalue.unwrap_or(f64::INFINITY);
+    pvalue(a)
+        .total_cmp(&pvalue(b))
+        .then_with(|| a.pvalue.is_none().cmp(&b.pvalue.is_none()))
+        .then_with(|| a.gene_id.cmp(&b.gene_id))
+        .then_with(|| a.annotation.cmp(&b.annotation))
+        .then_with(|| a.max_maf.total_cmp(&b.max_maf))
+}
+
 /// Transform an EncodedValue row into a GeneAssociationResult
 fn transform_gene_result(
     value: EncodedValue,
@@ -278,7 +437,7 @@ fn transform_gene_result(
     ancestry_group: &str,
 ) -> Result<GeneAssociationResult, AppError> {
     let EncodedValue::Struct(fields) = value else {
-        return Err(AppError::DataTransformError(
+        return Err(AppError::Internal(
             "Expected Struct at top level".to_string(),
         ));
     };
@@ -340,7 +499,7 @@ fn transform_gene_result(
 fn get_string(map: &HashMap<String, EncodedValue>, key: &str) -> Result<String, AppError> {
     map.get(key)
         .and_then(|v| v.as_string())
-        .ok_or_else(|| AppError::DataTransformError(format!("Missing required field: {}", key)))
+        .ok_or_else(|| AppError::Internal(format!("Missing required field: {}", key)))
 }
 
 fn get_string_opt(map: &HashMap<String, EncodedValue>, key: &str) -> Option<String> {
@@ -389,3 +548,104 @@ fn get_i32_opt(map: &HashMap<String, EncodedValue>, key: &str) -> Option<i32> {
         _ => None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selected_ancestries() {
+        let params = GeneQueryParams::default();
+        assert_eq!(selected_ancestries(&params), vec![AncestryGroup::Meta]);
+
+        let eur = GeneQueryParams {
+            ancestry: Some(AncestryGroup::Eur),
+            ..Default::default()
+        };
+        assert_eq!(selected_ancestries(&eur), vec![AncestryGroup::Eur]);
+
+        let all = GeneQueryParams {
+            all_ancestries: true,
+            ..eur
+        };
+        assert_eq!(selected_ancestries(&all).len(), 7);
+    }
+
+    #[test]
+    fn test_gene_results_follow_clickhouse_order() {
+        let result = |gene_id: &str, pvalue: Option<f64>| GeneAssociationResult {
+            gene_id: gene_id.to_string(),
+            gene_symbol: gene_id.to_string(),
+            annotation: "pLoF".to_string(),
+            max_maf: 0.001,
+            analysis_id: "height".to_string(),
+            ancestry_group: "meta".to_string(),
+            pvalue,
+            pvalue_burden: None,
+            pvalue_skat: None,
+            beta_burden: None,
+            se_burden: None,
+            mac: None,
+            number_rare: None,
+            number_ultra_rare: None,
+            total_variants: None,
+            pvalue_log10: None,
+            chrom: None,
+            pos: None,
+        };
+        let mut results = vec![
+            result("ENSG3", None),
+            result("ENSG2", Some(0.5)),
+            result("ENSG1", Some(0.5)),
+            result("ENSG4", Some(1e-10)),
+        ];
+        results.sort_by(compare_gene_results);
+        let order: Vec<_> = results.iter().map(|r| r.gene_id.as_str()).collect();
+        assert_eq!(order, vec!["ENSG4", "ENSG1", "ENSG2", "ENSG3"]);
+    }
+
+    #[test]
+    fn test_engine_cache_reuses_engines() {
+        let cache = EngineCache::new();
+        let opens = std::cell::Cell::new(0);
+        let open = |_: &str| {
+            opens.set(opens.get() + 1);
+            Ok(opens.get())
+        };
+
+        assert_eq!(cache.with_engine("gs://a", open, |e| Ok(*e)).unwrap(), 1);
+        assert_eq!(cache.with_engine("gs://a", open, |e| Ok(*e)).unwrap(), 1);
+        assert_eq!(cache.with_engine("gs://b", open, |e| Ok(*e)).unwrap(), 2);
+        assert_eq!(opens.get(), 2);
+
+        // A failed read drops its engine
+        let failed: Result<(), _> =
+            cache.with_engine("gs://a", open, |_| Err(AppError::Internal("bad".to_string())));
+        assert!(failed.is_err());
+        assert_eq!(cache.with_engine("gs://a", open, |e| Ok(*e)).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_engine_cache_evicts_least_recently_used() {
+        let cache = EngineCache::new();
+        for i in 0..MAX_CACHED_TABLES {
+            cache.checkin(&format!("gs://{}", i), i, Instant::now());
+        }
+        // Touch the first table so it is the most recently used
+        std::thread::sleep(Duration::from_millis(2));
+        let first = cache.checkout("gs://0").unwrap();
+        cache.checkin("gs://0", first.0, first.1);
+        cache.checkin("gs://new", 0, Instant::now());
+
+        assert_eq!(cache.tables.lock().unwrap().len(), MAX_CACHED_TABLES);
+        assert!(cache.checkout("gs://0").is_some());
+        assert!(cache.checkout("gs://new").is_some());
+
+        // Expired engines are reopened. An Instant that old only exists once
+        // the host has been up for ENGINE_TTL, which fresh CI runners may not.
+        if let Some(stale) = Instant::now().checked_sub(ENGINE_TTL) {
+            cache.checkin("gs://stale", 1, stale);
+            assert!(cache.checkout("gs://stale").is_none());
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Selection of gene test results
+//!
+//! SAIGE-GENE writes one row per (annotation, max_MAF) burden mask plus a
+//! Cauchy combination row per gene, keyed with max_MAF = -1, that combines
+//! the SKAT-O p-values of all masks. `GeneTest` (`test=`) selects between
+//! them with the same semantics on the ClickHouse and Hail Table paths.
+
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// Gene test results to return (`test=`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GeneTest {
+    /// Per-mask burden/SKAT/SKAT-O results, at `max_maf` when given (default)
+    #[default]
+    Mask,
+    /// Cauchy combination across masks only; `max_maf` does not apply
+    Cauchy,
+    /// Both the mask results and the Cauchy combination
+    All,
+}
+
+impl GeneTest {
+    /// Whether a row with `row_max_maf` belongs to this selection, given the
+    /// requested mask MAF
+    pub fn matches(self, row_max_maf: f64, max_maf: Option<f64>) -> bool {
+        let cauchy = row_max_maf < 0.0;
+        let mask_matches = max_maf.is_none_or(|m| (row_max_maf - m).abs() < 1e-9);
+        match self {
+            GeneTest::Mask => !cauchy && mask_matches,
+            GeneTest::Cauchy => cauchy,
+            GeneTest::All => cauchy || mask_matches,
+        }
+    }
+
+    /// SQL condition on the `max_maf` column for this selection, and the
+    /// mask MAF it binds, if any
+    pub fn sql(self, max_maf: Option<f64>) -> (&'static str, Option<f64>) {
+        match (self, max_maf) {
+            (GeneTest::Mask, Some(m)) => ("abs(max_maf - ?) < 1e-9", Some(m)),
+            (GeneTest::Mask, None) => ("max_maf >= 0", None),
+            (GeneTest::Cauchy, _) => ("max_maf < 0", None),
+            (GeneTest::All, Some(m)) => ("(max_maf < 0 OR abs(max_maf - ?) < 1e-9)", Some(m)),
+            (GeneTest::All, None) => ("1", None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gene_test_matches() {
+        assert!(GeneTest::Mask.matches(0.001, Some(0.001)));
+        assert!(!GeneTest::Mask.matches(0.01, Some(0.001)));
+        assert!(!GeneTest::Mask.matches(-1.0, Some(0.001)));
+        assert!(GeneTest::Mask.matches(0.01, None));
+        assert!(!GeneTest::Mask.matches(-1.0, None));
+
+        assert!(GeneTest::Cauchy.matches(-1.0, Some(0.001)));
+        assert!(!GeneTest::Cauchy.matches(0.001, None));
+
+        assert!(GeneTest::All.matches(-1.0, Some(0.001)));
+        assert!(GeneTest::All.matches(0.001, Some(0.001)));
+        assert!(!GeneTest::All.matches(0.01, Some(0.001)));
+    }
+
+    #[test]
+    fn test_gene_test_sql() {
+        assert_eq!(GeneTest::Mask.sql(None), ("max_maf >= 0", None));
+        assert_eq!(GeneTest::Cauchy.sql(Some(0.001)), ("max_maf < 0", None));
+        assert_eq!(GeneTest::All.sql(Some(0.01)).1, Some(0.01));
+        assert_eq!(GeneTest::default(), GeneTest::Mask);
+    }
+
+    #[test]
+    fn test_gene_test_deserialize() {
+        let test: GeneTest = serde_json::from_str("\"cauchy\"").unwrap();
+        assert_eq!(test, GeneTest::Cauchy);
+        assert!(serde_json::from_str::<GeneTest>("\"skat\"").is_err());
+    }
+}
@@ -0,0 +1,260 @@
+//! GTF rendering of gene models
+//!
+//! Writes gene, transcript, exon, CDS and UTR records in GTF 2.2 from the
+//! same gene models the browser draws, so local annotation tracks line up
+//! with it. Coordinates are already 1-based and inclusive. CDS frames are
+//! derived from the CDS lengths in transcription order.
+
+use crate::genomics::Contig;
+use crate::models::{Exon, GeneModel, Transcript};
+use std::fmt::Write;
+
+/// Value of the GTF source column
+const SOURCE: &str = "axaou";
+
+fn seqname(chrom: &str) -> String {
+    Contig::parse(chrom)
+        .map(|c| c.chr().to_string())
+        .unwrap_or_else(|| chrom.to_string())
+}
+
+fn strand(strand: &str) -> &'static str {
+    match strand {
+        "+" => "+",
+        "-" => "-",
+        _ => ".",
+    }
+}
+
+/// Quote an attribute value, dropping characters GTF cannot carry
+fn quoted(value: &str) -> String {
+    let clean: String = value
+        .chars()
+        .filter(|c| *c != '"' && *c != '\t' && *c != '\n')
+        .collect();
+    format!("\"{}\"", clean)
+}
+
+fn gene_attributes(gene: &GeneModel) -> String {
+    let mut attrs = format!("gene_id {};", quoted(&gene.gene_id));
+    if !gene.gene_version.is_empty() {
+        attrs.push_str(&format!(" gene_version {};", quoted(&gene.gene_version)));
+    }
+    attrs.push_str(&format!(" gene_name {};", quoted(&gene.symbol)));
+    attrs
+}
+
+fn transcript_attributes(gene: &GeneModel, transcript: &Transcript) -> String {
+    let mut attrs = gene_attributes(gene);
+    attrs.push_str(&format!(
+        " transcript_id {};",
+        quoted(&transcript.transcript_id)
+    ));
+    if !transcript.transcript_version.is_empty() {
+        attrs.push_str(&format!(
+            " transcript_version {};",
+            quoted(&transcript.transcript_version)
+        ));
+    }
+    if transcript.transcript_id == gene.canonical_transcript_id {
+        attrs.push_str(" tag \"Ensembl_canonical\";");
+    }
+    if gene
+        .mane_select_transcript
+        .as_ref()
+        .is_some_and(|mane| mane.ensembl_id == transcript.transcript_id)
+    {
+        attrs.push_str(" tag \"MANE_Select\";");
+    }
+    attrs
+}
+
+/// GTF frame of each CDS exon, in input order
+fn cds_frames(cds: &[&Exon], strand: &str) -> Vec<u8> {
+    let mut order: Vec<usize> = (0..cds.len()).collect();
+    order.sort_by_key(|&i| cds[i].start);
+    if strand == "-" {
+        order.reverse();
+    }
+    let mut frames = vec![0; cds.len()];
+    let mut length = 0i64;
+    for i in order {
+        frames[i] = ((3 - length % 3) % 3) as u8;
+        length += cds[i].stop - cds[i].start + 1;
+    }
+    frames
+}
+
+fn feature_name(feature_type: &str) -> Option<&'static str> {
+    match feature_type {
+        "exon" => Some("exon"),
+        "CDS" => Some("CDS"),
+        "UTR" => Some("UTR"),
+        _ => None,
+    }
+}
+
+/// Render genes with their transcripts as GTF, genes ordered by position
+pub fn render_gtf(genes: &[GeneModel]) -> String {
+    let mut sorted: Vec<&GeneModel> = genes.iter().collect();
+    sorted.sort_by_key(|g| (g.xstart, g.xstop));
+
+    let mut out = String::from("#!genome-build GRCh38\n");
+    for gene in sorted {
+        let chrom = seqname(&gene.chrom);
+        let gene_strand = strand(&gene.strand);
+        let _ = writeln!(
+            out,
+            "{}\t{}\tgene\t{}\t{}\t.\t{}\t.\t{}",
+            chrom,
+            SOURCE,
+            gene.start,
+            gene.stop,
+            gene_strand,
+            gene_attributes(gene)
+        );
+
+        let mut transcripts: Vec<&Transcript> = gene.transcripts.iter().collect();
+        transcripts.sort_by(|a, b| (a.start, &a.transcript_id).cmp(&(b.start, &b.transcript_id)));
+        for transcript in transcripts {
+            let attrs = transcript_attributes(gene, transcript);
+            let _ = writeln!(
+                out,
+                "{}\t{}\ttranscript\t{}\t{}\t.\t{}\t.\t{}",
+                chrom, SOURCE, transcript.start, transcript.stop, gene_strand, attrs
+            );
+
+            let cds: Vec<&Exon> = transcript
+                .exons
+                .iter()
+                .filter(|e| e.feature_type == "CDS")
+                .collect();
+            let frames = cds_frames(&cds, &gene.strand);
+
+            let mut features: Vec<(&Exon, &'static str, String)> = Vec::new();
+            for exon in &transcript.exons {
+                let Some(feature) = feature_name(&exon.feature_type) else {
+                    continue;
+                };
+                let frame = if feature == "CDS" {
+                    cds.iter()
+                        .position(|c| std::ptr::eq(*c, exon))
+                        .map(|i| frames[i].to_string())
+                        .unwrap_or_else(|| ".".to_string())
+                } else {
+                    ".".to_string()
+                };
+                features.push((exon, feature, frame));
+            }
+            // exon records before the CDS/UTR records they contain
+            features.sort_by_key(|(exon, feature, _)| (exon.start, *feature != "exon"));
+            for (exon, feature, frame) in features {
+                let _ = writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}\t{}\t.\t{}\t{}\t{}",
+                    chrom, SOURCE, feature, exon.start, exon.stop, gene_strand, frame, attrs
+                );
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exon(feature_type: &str, start: i64, stop: i64) -> Exon {
+        Exon {
+            feature_type: feature_type.to_string(),
+            start,
+            stop,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cds_frames() {
+        let a = exon("CDS", 100, 104);
+        let b = exon("CDS", 200, 210);
+        let c = exon("CDS", 300, 302);
+        // Plus strand: 5 bases, then 11 bases
+        assert_eq!(cds_frames(&[&a, &b, &c], "+"), vec![0, 1, 2]);
+        // Minus strand reads c, b, a
+        assert_eq!(cds_frames(&[&a, &b, &c], "-"), vec![1, 0, 0]);
+    }
+
+    #[test]
+    fn test_render_gtf() {
+        let transcript = Transcript {
+            transcript_id: "ENST1".to_string(),
+            transcript_version: "2".to_string(),
+            start: 100,
+            stop: 400,
+            exons: vec![
+                exon("UTR", 100, 151),
+                exon("exon", 100, 200),
+                exon("CDS", 152, 200),
+                exon("exon", 300, 400),
+                exon("CDS", 300, 350),
+            ],
+            ..Default::default()
+        };
+        let gene = GeneModel {
+            gene_id: "ENSG1".to_string(),
+            symbol: "ABC".to_string(),
+            symbol_upper_case: "ABC".to_string(),
+            chrom: "1".to_string(),
+            start: 100,
+            stop: 400,
+            strand: "+".to_string(),
+            xstart: 1_000_000_100,
+            xstop: 1_000_000_400,
+            canonical_transcript_id: "ENST1".to_string(),
+            preferred_transcript_id: "ENST1".to_string(),
+            preferred_transcript_source: String::new(),
+            gencode_symbol: String::new(),
+            gene_version: String::new(),
+            name: String::new(),
+            hgnc_id: String::new(),
+            ncbi_id: String::new(),
+            omim_id: String::new(),
+            reference_genome: "GRCh38".to_string(),
+            alias_symbols: vec![],
+            previous_symbols: vec![],
+            search_terms: vec![],
+            flags: vec![],
+            exons: vec![],
+            transcripts: vec![transcript],
+            mane_select_transcript: None,
+            gnomad_constraint: None,
+        };
+
+        let gtf = render_gtf(&[gene]);
+        let lines: Vec<&str> = gtf.lines().collect();
+        assert_eq!(lines[0], "#!genome-build GRCh38");
+        assert_eq!(
+            lines[1],
+            "chr1\taxaou\tgene\t100\t400\t.\t+\t.\tgene_id \"ENSG1\"; gene_name \"ABC\";"
+        );
+        assert!(lines[2].contains("\ttranscript\t"));
+        assert!(lines[2].ends_with("transcript_version \"2\"; tag \"Ensembl_canonical\";"));
+        let features: Vec<(&str, &str, &str)> = lines[3..]
+            .iter()
+            .map(|l| {
+                let cols: Vec<&str> = l.split('\t').collect();
+                (cols[2], cols[3], cols[7])
+            })
+            .collect();
+        assert_eq!(
+            features,
+            vec![
+                ("exon", "100", "."),
+                ("UTR", "100", "."),
+                ("CDS", "152", "0"),
+                ("exon", "300", "."),
+                ("CDS", "300", "2"),
+            ]
+        );
+    }
+}
@@ -1,6 +1,11 @@
 //! Gene-centric route handlers
 //!
 //! Provides endpoints for cross-phenotype gene queries including
-//! PheWAS, top associations, and gene symbol search.
+//! PheWAS, top associations, and gene symbol search, plus GTF rendering of
+//! gene models, power annotation of burden results and the `test=` selection
+//! of mask versus Cauchy combination results.
 
+pub mod gene_test;
+pub mod gtf;
+pub mod power;
 pub mod routes;
@@ -0,0 +1,226 @@
+//! Power annotation of gene burden results
+//!
+//! Burden tests on a handful of carriers cannot detect anything, yet their
+//! p-values sit next to well-powered results. This module derives the
+//! carrier frequency of a gene result from its MAC and the sample size in
+//! analysis metadata, and classifies its power by the number of carriers
+//! expected among cases (binary traits) or in the whole sample.
+
+use crate::models::{AnalysisMetadata, GeneAssociationApi};
+use crate::response::{EffectMetadata, EffectScale, EffectUnit};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// Expected carriers below which a result is `low` power
+const LOW_POWER_CARRIERS: f64 = 10.0;
+/// Expected carriers below which a result is `moderate` power
+const MODERATE_POWER_CARRIERS: f64 = 50.0;
+
+/// Coarse power class of a gene burden result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerClass {
+    /// Fewer than 10 expected carriers
+    Low,
+    /// 10 to 50 expected carriers
+    Moderate,
+    /// 50 or more expected carriers
+    High,
+}
+
+impl PowerClass {
+    fn from_carriers(carriers: f64) -> Self {
+        if carriers < LOW_POWER_CARRIERS {
+            PowerClass::Low
+        } else if carriers < MODERATE_POWER_CARRIERS {
+            PowerClass::Moderate
+        } else {
+            PowerClass::High
+        }
+    }
+}
+
+/// Metadata record per analysis, preferring the record for `ancestry`
+///
+/// Sample sizes differ by ancestry; other ancestries are only a fallback.
+pub fn metadata_by_analysis<'a>(
+    metadata: &'a [AnalysisMetadata],
+    ancestry: &str,
+) -> HashMap<&'a str, &'a AnalysisMetadata> {
+    let mut by_analysis: HashMap<&str, &AnalysisMetadata> = HashMap::new();
+    for meta in metadata {
+        let exact = meta.ancestry_group.eq_ignore_ascii_case(ancestry);
+        if exact || !by_analysis.contains_key(meta.analysis_id.as_str()) {
+            by_analysis.insert(meta.analysis_id.as_str(), meta);
+        }
+    }
+    by_analysis
+}
+
+fn is_binary(meta: &AnalysisMetadata) -> bool {
+    EffectMetadata::new(&meta.trait_type, EffectScale::Beta).unit == EffectUnit::LogOdds
+}
+
+/// Whether the analysis is binary, and its sample size (cases and controls
+/// for binary traits)
+fn sample_size(meta: &AnalysisMetadata) -> (bool, i64) {
+    let binary = is_binary(meta);
+    let n_samples = if binary {
+        meta.n_cases + meta.n_controls.unwrap_or(0)
+    } else {
+        meta.n_cases
+    };
+    (binary, n_samples)
+}
+
+/// Set the carrier frequency, expected carriers and power class of `row`
+///
+/// Carriers are approximated by the MAC, as nearly all carriers of the rare
+/// variants in a burden mask are heterozygous. Rows without a MAC or a
+/// metadata record are left unannotated.
+pub fn annotate_power(row: &mut GeneAssociationApi, meta: Option<&AnalysisMetadata>) {
+    let (Some(mac), Some(meta)) = (row.mac, meta) else {
+        return;
+    };
+    let (binary, n_samples) = sample_size(meta);
+    if n_samples <= 0 {
+        return;
+    }
+    let carrier_frequency = (mac as f64 / n_samples as f64).min(1.0);
+    let expected_carriers = if binary {
+        carrier_frequency * meta.n_cases as f64
+    } else {
+        mac as f64
+    };
+    row.carrier_frequency = Some(carrier_frequency);
+    row.expected_carriers = Some(expected_carriers);
+    row.power_class = Some(PowerClass::from_carriers(expected_carriers));
+}
+
+/// Whether `row` has at least `min_carriers` expected carriers; rows that
+/// could not be annotated are kept
+pub fn has_min_carriers(row: &GeneAssociationApi, min_carriers: Option<f64>) -> bool {
+    match (min_carriers, row.expected_carriers) {
+        (Some(min), Some(expected)) => expected >= min,
+        _ => true,
+    }
+}
+
+/// SQL condition on `phenotype` and `mac` equivalent to [`has_min_carriers`]
+/// after [`annotate_power`], so the filter can run before `LIMIT`
+///
+/// Binds: the analysis IDs and minimum MACs of [`min_mac_by_analysis`].
+pub const MIN_CARRIERS_SQL: &str =
+    "(mac IS NULL OR mac >= transform(phenotype, ?, ?, toFloat64(0)))";
+
+/// Lowest MAC with `min_carriers` expected carriers, per analysis
+///
+/// Analyses without a usable sample size are left out, so their results are
+/// kept as in [`has_min_carriers`].
+pub fn min_mac_by_analysis(
+    by_analysis: &HashMap<&str, &AnalysisMetadata>,
+    min_carriers: f64,
+) -> (Vec<String>, Vec<f64>) {
+    let mut analysis_ids = Vec::new();
+    let mut min_macs = Vec::new();
+    for (analysis_id, meta) in by_analysis {
+        let (binary, n_samples) = sample_size(meta);
+        if n_samples <= 0 {
+            continue;
+        }
+        // Expected case carriers are capped at the number of cases
+        let min_mac = if !binary {
+            min_carriers
+        } else if (meta.n_cases as f64) < min_carriers {
+            f64::MAX
+        } else {
+            min_carriers * n_samples as f64 / meta.n_cases as f64
+        };
+        analysis_ids.push(analysis_id.to_string());
+        min_macs.push(min_mac);
+    }
+    (analysis_ids, min_macs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::factories::analysis_metadata;
+
+    fn meta(trait_type: &str, n_cases: i64, n_controls: Option<i64>) -> AnalysisMetadata {
+        AnalysisMetadata {
+            n_controls,
+            ..analysis_metadata("pheno", "meta", trait_type, n_cases)
+        }
+    }
+
+    fn row(mac: Option<i64>) -> GeneAssociationApi {
+        GeneAssociationApi {
+            gene_id: "ENSG1".to_string(),
+            gene_symbol: "ABC".to_string(),
+            annotation: "pLoF".to_string(),
+            max_maf: 0.001,
+            analysis_id: "pheno".to_string(),
+            ancestry_group: "meta".to_string(),
+            pvalue: Some(1e-3),
+            neg_log10_p: Some(3.0),
+            pvalue_burden: None,
+            neg_log10_p_burden: None,
+            pvalue_skat: None,
+            neg_log10_p_skat: None,
+            beta_burden: None,
+            mac,
+            contig: "chr1".to_string(),
+            gene_start_position: 100,
+            carrier_frequency: None,
+            expected_carriers: None,
+            power_class: None,
+        }
+    }
+
+    #[test]
+    fn test_annotate_power() {
+        // 200 carriers in 100k samples, 2k of them cases: 4 expected case carriers
+        let mut binary = row(Some(200));
+        annotate_power(&mut binary, Some(&meta("binary", 2_000, Some(98_000))));
+        assert_eq!(binary.carrier_frequency, Some(0.002));
+        assert!((binary.expected_carriers.unwrap() - 4.0).abs() < 1e-9);
+        assert_eq!(binary.power_class, Some(PowerClass::Low));
+        assert!(!has_min_carriers(&binary, Some(5.0)));
+
+        let mut continuous = row(Some(200));
+        annotate_power(&mut continuous, Some(&meta("continuous", 100_000, None)));
+        assert_eq!(continuous.expected_carriers, Some(200.0));
+        assert_eq!(continuous.power_class, Some(PowerClass::High));
+
+        let mut unknown = row(None);
+        annotate_power(&mut unknown, Some(&meta("binary", 10, Some(10))));
+        assert_eq!(unknown.power_class, None);
+        assert!(has_min_carriers(&unknown, Some(5.0)));
+    }
+
+    #[test]
+    fn test_min_mac_by_analysis() {
+        let binary = meta("binary", 2_000, Some(98_000));
+        let by_analysis: HashMap<&str, &AnalysisMetadata> = [("pheno", &binary)].into();
+        let (ids, min_macs) = min_mac_by_analysis(&by_analysis, 5.0);
+        assert_eq!(ids, vec!["pheno"]);
+        assert!((min_macs[0] - 250.0).abs() < 1e-9);
+
+        // Same cut as the in-memory filter
+        for mac in [240, 260] {
+            let mut row = row(Some(mac));
+            annotate_power(&mut row, Some(&binary));
+            assert_eq!(has_min_carriers(&row, Some(5.0)), mac as f64 >= min_macs[0]);
+        }
+
+        let few_cases = meta("binary", 3, Some(1_000));
+        let by_analysis: HashMap<&str, &AnalysisMetadata> = [("pheno", &few_cases)].into();
+        assert_eq!(min_mac_by_analysis(&by_analysis, 5.0).1, vec![f64::MAX]);
+
+        let empty = meta("continuous", 0, None);
+        let by_analysis: HashMap<&str, &AnalysisMetadata> = [("pheno", &empty)].into();
+        assert!(min_mac_by_analysis(&by_analysis, 5.0).0.is_empty());
+    }
+}
@@ -3,25 +3,43 @@
 //! Provides endpoints for cross-phenotype gene queries backed by ClickHouse.
 
 use crate::api::AppState;
+use crate::clickhouse::QueryExt;
 use crate::clickhouse::models::{GeneAssociationRow, GeneSummaryRow};
-use crate::error::AppError;
-use crate::models::GeneAssociationApi;
-use crate::response::{LookupResult, QueryTimer};
+use crate::error::{AppError, ErrorResponse};
+use crate::gene_models::{GENE_SEARCH_NO_MATCH, GENE_SEARCH_RANK_SQL};
+use crate::genes::gene_test::GeneTest;
+use crate::genes::power::{
+    annotate_power, has_min_carriers, metadata_by_analysis, min_mac_by_analysis, MIN_CARRIERS_SQL,
+};
+use crate::genomics::consequence::{ConsequenceClass, ConsequenceFilter};
+use crate::models::{AncestryGroup, GeneAssociationApi, GnomadConstraint};
+use crate::response::{GeneAssociationLookup, LookupResult, QueryTimer};
 use axum::{
     extract::{Path, Query, State},
     Json,
 };
 use clickhouse::Row;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
 
 /// Query parameters for gene PheWAS endpoint
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct GeneQuery {
     /// Ancestry group filter (default: "meta")
     pub ancestry: Option<String>,
     /// Annotation type filter (e.g., "pLoF", "missenseLC")
     pub annotation: Option<String>,
+    /// Minimum minor allele count of the gene result
+    pub min_mac: Option<i64>,
+    /// Minimum expected carriers (among cases for binary traits)
+    pub min_carriers: Option<f64>,
+    /// "mask" (default) for per-mask results, "cauchy" for the Cauchy
+    /// combination, or "all"
+    #[serde(default)]
+    pub test: GeneTest,
     /// Query mode (fast/slow) - accepted but currently ignored
     #[serde(default)]
     pub query_mode: Option<String>,
@@ -30,9 +48,22 @@ pub struct GeneQuery {
 /// GET /api/genes/phewas/:gene_id
 ///
 /// Returns gene association results across all phenotypes for a specific gene.
-/// This is the gene-level PheWAS endpoint.
+/// This is the gene-level PheWAS endpoint. Results carry their carrier
+/// frequency and power class from analysis metadata; `min_mac` and
+/// `min_carriers` hide underpowered results. `test=` selects mask results,
+/// Cauchy combination results or both.
 ///
 /// The gene_id can be either an Ensembl ID (ENSG...) or a gene symbol.
+#[utoipa::path(
+    get,
+    path = "/api/genes/phewas/{gene_id}",
+    tag = "genes",
+    params(("gene_id" = String, Path, description = "Ensembl gene ID or gene symbol"), GeneQuery),
+    responses(
+        (status = 200, description = "Gene burden results across phenotypes", body = GeneAssociationLookup),
+        (status = 500, description = "ClickHouse query failed", body = ErrorResponse)
+    )
+)]
 pub async fn get_gene_phewas(
     State(state): State<Arc<AppState>>,
     Path(gene_id): Path<String>,
@@ -41,9 +72,45 @@ pub async fn get_gene_phewas(
     let timer = QueryTimer::start();
     let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
 
+    let rows = fetch_gene_phewas_rows(
+        &state,
+        &gene_id,
+        &ancestry,
+        params.annotation.as_deref(),
+        params.min_mac,
+        params.test,
+    )
+    .await?;
+
+    let metadata = state.metadata.read().await;
+    let by_analysis = metadata_by_analysis(&metadata, &ancestry);
+    let api_rows: Vec<GeneAssociationApi> = rows
+        .into_iter()
+        .map(|r| {
+            let mut row = r.to_api();
+            annotate_power(&mut row, by_analysis.get(row.analysis_id.as_str()).copied());
+            row
+        })
+        .filter(|row| has_min_carriers(row, params.min_carriers))
+        .collect();
+    Ok(Json(LookupResult::new(api_rows, &timer)))
+}
+
+/// Fetch gene burden results across all phenotypes for one gene.
+///
+/// The gene_id can be either an Ensembl ID (ENSG...) or a gene symbol; symbols
+/// are resolved to ENSG IDs via gene_models for fast index lookup.
+async fn fetch_gene_phewas_rows(
+    state: &AppState,
+    gene_id: &str,
+    ancestry: &str,
+    annotation: Option<&str>,
+    min_mac: Option<i64>,
+    test: GeneTest,
+) -> Result<Vec<GeneAssociationRow>, AppError> {
     // Resolve gene symbol to ENSG ID via gene_models for fast index lookup
     let resolved_gene_id = if gene_id.starts_with("ENSG") {
-        gene_id.clone()
+        gene_id.to_string()
     } else {
         // Look up ENSG ID from symbol
         #[derive(clickhouse::Row, Deserialize)]
@@ -51,20 +118,23 @@ pub async fn get_gene_phewas(
         let row: Option<GeneIdRow> = state
             .clickhouse
             .query("SELECT gene_id FROM gene_models WHERE symbol = ? LIMIT 1")
-            .bind(&gene_id)
-            .fetch_optional()
+            .bind(gene_id)
+            .fetch_optional_with(&state.executor)
             .await
             .ok()
             .flatten();
-        row.map(|r| r.gene_id).unwrap_or_else(|| gene_id.clone())
+        row.map(|r| r.gene_id).unwrap_or_else(|| gene_id.to_string())
     };
 
     let (where_clause, search_value) = if resolved_gene_id.starts_with("ENSG") {
         ("gene_id = ?", resolved_gene_id)
     } else {
-        ("gene_symbol = ?", gene_id.clone())
+        ("gene_symbol = ?", gene_id.to_string())
     };
 
+    // All masks are returned, so the test condition binds no max_maf
+    let (test_filter, _) = test.sql(None);
+
     // Use gene_associations_by_gene (sorted by gene_id, no per-phenotype partitioning)
     // for fast gene lookups instead of gene_associations (partitioned by phenotype).
     let base_query = format!(
@@ -73,32 +143,145 @@ pub async fn get_gene_phewas(
                pvalue, pvalue_burden, pvalue_skat, beta_burden, mac,
                contig, gene_start_position, xpos
         FROM gene_associations_by_gene
-        WHERE {} AND ancestry = ?
+        WHERE {} AND ancestry = ? AND {}
+        {}
         {}
         ORDER BY pvalue ASC
         "#,
         where_clause,
-        if params.annotation.is_some() {
+        test_filter,
+        if annotation.is_some() {
             "AND annotation = ?"
         } else {
             ""
+        },
+        if min_mac.is_some() {
+            "AND mac >= ?"
+        } else {
+            ""
         }
     );
 
     let mut query = state.clickhouse.query(&base_query);
-    query = query.bind(&search_value).bind(&ancestry);
+    query = query.bind(&search_value).bind(ancestry);
 
-    if let Some(ref annotation) = params.annotation {
+    if let Some(annotation) = annotation {
         query = query.bind(annotation);
     }
+    if let Some(min_mac) = min_mac {
+        query = query.bind(min_mac);
+    }
 
-    let rows = query
-        .fetch_all::<GeneAssociationRow>()
+    query
+        .fetch_all_with::<GeneAssociationRow>(&state.executor)
         .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+}
 
-    let api_rows: Vec<GeneAssociationApi> = rows.into_iter().map(|r| r.to_api()).collect();
-    Ok(Json(LookupResult::new(api_rows, timer.elapsed())))
+/// A gene burden result annotated with phenotype metadata
+#[derive(Debug, Clone, Serialize)]
+pub struct GenePhewasGroupedResult {
+    #[serde(flatten)]
+    pub association: GeneAssociationApi,
+    /// Phenotype description (display name applied)
+    pub description: String,
+    /// "binary" or "continuous"
+    pub trait_type: String,
+    pub n_cases: Option<i64>,
+}
+
+/// Gene burden results for one phenotype category
+#[derive(Debug, Clone, Serialize)]
+pub struct GenePhewasCategoryGroup {
+    pub category: String,
+    /// Category color, matching /api/categories
+    pub color: String,
+    /// Lowest p-value across results in this category
+    pub min_pvalue: Option<f64>,
+    pub results: Vec<GenePhewasGroupedResult>,
+}
+
+/// Category used for results whose phenotype has no metadata record
+const UNCATEGORIZED: &str = "Uncategorized";
+
+/// P-value cutoff of `top_gene_associations` (see `top_gene_associations.sql`);
+/// queries with `max_p` at or below it read that table instead of `gene_associations`
+const TOP_GENE_ASSOCIATIONS_MAX_P: f64 = 1e-4;
+
+/// GET /api/genes/phewas/:gene_id/grouped
+///
+/// Returns the gene PheWAS results grouped by phenotype category, with each
+/// result annotated with description, trait_type and n_cases from analysis
+/// metadata so the frontend can render a categorized PheWAS plot directly.
+/// Groups are ordered by category name (matching the color assignment).
+pub async fn get_gene_phewas_grouped(
+    State(state): State<Arc<AppState>>,
+    Path(gene_id): Path<String>,
+    Query(params): Query<GeneQuery>,
+) -> Result<Json<LookupResult<GenePhewasCategoryGroup>>, AppError> {
+    let timer = QueryTimer::start();
+    let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
+
+    let rows = fetch_gene_phewas_rows(
+        &state,
+        &gene_id,
+        &ancestry,
+        params.annotation.as_deref(),
+        params.min_mac,
+        params.test,
+    )
+    .await?;
+
+    let metadata = state.metadata.read().await;
+    let colors = crate::api::category_color_map(&metadata);
+
+    // Prefer the metadata record for the requested ancestry; fall back to any
+    // ancestry since category/description are shared across ancestries.
+    let by_analysis = metadata_by_analysis(&metadata, &ancestry);
+
+    let mut groups: BTreeMap<String, Vec<GenePhewasGroupedResult>> = BTreeMap::new();
+    for row in rows {
+        let mut association = row.to_api();
+        let meta = by_analysis.get(association.analysis_id.as_str());
+        annotate_power(&mut association, meta.copied());
+        if !has_min_carriers(&association, params.min_carriers) {
+            continue;
+        }
+        let category = meta
+            .map(|m| m.category.clone())
+            .filter(|c| !c.is_empty())
+            .unwrap_or_else(|| UNCATEGORIZED.to_string());
+        let description = crate::phenotype_display_names::apply_display_name(
+            &association.analysis_id,
+            meta.map(|m| m.description.as_str()).unwrap_or_default(),
+        );
+        groups.entry(category).or_default().push(GenePhewasGroupedResult {
+            trait_type: meta.map(|m| m.trait_type.clone()).unwrap_or_default(),
+            n_cases: meta.map(|m| m.n_cases),
+            description,
+            association,
+        });
+    }
+
+    let grouped: Vec<GenePhewasCategoryGroup> = groups
+        .into_iter()
+        .map(|(category, results)| {
+            let min_pvalue = results
+                .iter()
+                .filter_map(|r| r.association.pvalue)
+                .min_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            GenePhewasCategoryGroup {
+                color: colors
+                    .get(&category)
+                    .cloned()
+                    .unwrap_or_else(|| "#bab0ac".to_string()),
+                category,
+                min_pvalue,
+                results,
+            }
+        })
+        .collect();
+
+    Ok(Json(LookupResult::new(grouped, &timer)))
 }
 
 /// Query parameters for top gene associations endpoint
@@ -114,15 +297,76 @@ pub struct TopGenesQuery {
     pub min_p: Option<f64>,
     /// Maximum p-value threshold (default: 1e-6)
     pub max_p: Option<f64>,
+    /// Minimum gnomAD pLI (e.g., 0.9 for LoF-intolerant genes)
+    pub min_pli: Option<f64>,
+    /// Maximum gnomAD LoF observed/expected ratio
+    pub max_oe_lof: Option<f64>,
+    /// Keep genes in this gnomAD LOEUF decile or lower (1 = most constrained, 1-10)
+    pub loeuf_decile: Option<u8>,
+    /// Minimum minor allele count of the gene result
+    pub min_mac: Option<i64>,
+    /// Minimum expected carriers (among cases for binary traits); applied
+    /// after `limit`
+    pub min_carriers: Option<f64>,
+    /// "mask" (default), "cauchy" or "all"
+    #[serde(default)]
+    pub test: GeneTest,
     /// Query mode (fast/slow) - accepted but currently ignored
     #[serde(default)]
     pub query_mode: Option<String>,
 }
 
+impl TopGenesQuery {
+    /// SQL restricting `gene_id` to constrained genes, with its bind values in order
+    fn constraint_filter(&self) -> (String, Vec<f64>) {
+        let mut conditions = Vec::new();
+        let mut binds = Vec::new();
+
+        if let Some(min_pli) = self.min_pli {
+            conditions.push("gnomad_pli >= ?".to_string());
+            binds.push(min_pli);
+        }
+        if let Some(max_oe_lof) = self.max_oe_lof {
+            conditions.push("gnomad_oe_lof <= ?".to_string());
+            binds.push(max_oe_lof);
+        }
+        if let Some(decile) = self.loeuf_decile {
+            // Decile = 1 + number of LOEUF decile cutoffs below the gene's LOEUF
+            conditions.push(
+                r#"gnomad_oe_lof_upper IS NOT NULL
+                  AND arrayCount(c -> c < gnomad_oe_lof_upper, (
+                      SELECT quantilesExact(0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9)(gnomad_oe_lof_upper)
+                      FROM gene_models
+                      WHERE gnomad_oe_lof_upper IS NOT NULL
+                  )) + 1 <= ?"#
+                    .to_string(),
+            );
+            binds.push(decile as f64);
+        }
+
+        if conditions.is_empty() {
+            return (String::new(), binds);
+        }
+        (
+            format!(
+                "AND gene_id IN (SELECT gene_id FROM gene_models WHERE {})",
+                conditions.join(" AND ")
+            ),
+            binds,
+        )
+    }
+}
+
 /// GET /api/genes/top-associations
 ///
 /// Returns the most significant gene-phenotype associations globally.
-/// Results are ordered by p-value ascending.
+/// Results are ordered by p-value ascending. Optional `min_pli`, `max_oe_lof`
+/// and `loeuf_decile` restrict results to constrained genes via gnomAD
+/// constraint metrics in `gene_models`. Requests within the default `max_p`
+/// read the pre-filtered `top_gene_associations` table, or
+/// `gene_associations` while that table does not exist yet. Results
+/// are power-annotated as in the gene PheWAS, with the same `min_mac`,
+/// `min_carriers` and `test` filters.
 pub async fn get_top_associations(
     State(state): State<Arc<AppState>>,
     Query(params): Query<TopGenesQuery>,
@@ -132,13 +376,29 @@ pub async fn get_top_associations(
     let min_p = params.min_p.unwrap_or(0.0);
     let max_p = params.max_p.unwrap_or(1e-4);
 
+    if let Some(decile) = params.loeuf_decile {
+        if !(1..=10).contains(&decile) {
+            return Err(AppError::BadRequest(format!(
+                "loeuf_decile must be between 1 and 10, got {}",
+                decile
+            )));
+        }
+    }
+    let (constraint_filter, constraint_binds) = params.constraint_filter();
+
     let dv = state.data_version.as_deref().unwrap_or("none");
     let cache_key = format!(
-        "top_genes:{}:{}:{}:{}:{}",
+        "top_genes:{}:{}:{}:{}:{:?}:{:?}:{:?}:{:?}:{:?}:{:?}:{}",
         params.ancestry,
         params.annotation.as_deref().unwrap_or("none"),
         min_p,
         max_p,
+        params.min_pli,
+        params.max_oe_lof,
+        params.loeuf_decile,
+        params.min_mac,
+        params.min_carriers,
+        params.test,
         dv
     );
 
@@ -150,45 +410,102 @@ pub async fn get_top_associations(
             .unwrap());
     }
 
-    let base_query = format!(
-        r#"
-        SELECT gene_id, gene_symbol, annotation, max_maf, phenotype, ancestry,
-               pvalue, pvalue_burden, pvalue_skat, beta_burden, mac,
-               contig, gene_start_position, xpos
-        FROM gene_associations
-        WHERE ancestry = ?
-          AND pvalue IS NOT NULL
-          AND pvalue >= ?
-          AND pvalue <= ?
-          {}
-        ORDER BY pvalue ASC
-        LIMIT ?
-        "#,
-        if params.annotation.is_some() {
-            "AND annotation = ?"
-        } else {
-            ""
+    let (test_filter, _) = params.test.sql(None);
+    // Filter on expected carriers before LIMIT, so pages are not cut short
+    let min_macs = match params.min_carriers {
+        Some(min_carriers) => {
+            let metadata = state.metadata.read().await;
+            let by_analysis = metadata_by_analysis(&metadata, &params.ancestry);
+            Some(min_mac_by_analysis(&by_analysis, min_carriers)).filter(|(ids, _)| !ids.is_empty())
         }
-    );
+        None => None,
+    };
+    let fetch = |table: &str| {
+        let base_query = format!(
+            r#"
+            SELECT gene_id, gene_symbol, annotation, max_maf, phenotype, ancestry,
+                   pvalue, pvalue_burden, pvalue_skat, beta_burden, mac,
+                   contig, gene_start_position, xpos
+            FROM {}
+            WHERE ancestry = ?
+              AND pvalue IS NOT NULL
+              AND pvalue >= ?
+              AND pvalue <= ?
+              AND {}
+              {}
+              {}
+              {}
+              {}
+            ORDER BY pvalue ASC
+            LIMIT ?
+            "#,
+            table,
+            test_filter,
+            if params.annotation.is_some() {
+                "AND annotation = ?"
+            } else {
+                ""
+            },
+            if params.min_mac.is_some() {
+                "AND mac >= ?"
+            } else {
+                ""
+            },
+            constraint_filter,
+            if min_macs.is_some() {
+                format!("AND {}", MIN_CARRIERS_SQL)
+            } else {
+                String::new()
+            }
+        );
 
-    let mut query = state.clickhouse.query(&base_query);
-    query = query.bind(&params.ancestry).bind(min_p).bind(max_p);
+        let mut query = state.clickhouse.query(&base_query);
+        query = query.bind(&params.ancestry).bind(min_p).bind(max_p);
 
-    if let Some(ref annotation) = params.annotation {
-        query = query.bind(annotation);
-    }
+        if let Some(ref annotation) = params.annotation {
+            query = query.bind(annotation);
+        }
+        if let Some(min_mac) = params.min_mac {
+            query = query.bind(min_mac);
+        }
+        for value in &constraint_binds {
+            query = query.bind(value);
+        }
+        if let Some((analysis_ids, min_macs)) = &min_macs {
+            query = query.bind(analysis_ids).bind(min_macs);
+        }
 
-    query = query.bind(limit);
+        query
+            .bind(limit)
+            .fetch_all_with::<GeneAssociationRow>(&state.executor)
+    };
 
-    let rows = query
-        .fetch_all::<GeneAssociationRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+    let rows = if max_p <= TOP_GENE_ASSOCIATIONS_MAX_P {
+        match fetch("top_gene_associations").await {
+            // UNKNOWN_TABLE: not migrated yet, scan gene_associations instead
+            Err(AppError::UpstreamClickHouse(msg)) if msg.contains("Code: 60.") => {
+                fetch("gene_associations").await?
+            }
+            rows => rows?,
+        }
+    } else {
+        fetch("gene_associations").await?
+    };
 
-    let api_rows: Vec<GeneAssociationApi> = rows.into_iter().map(|r| r.to_api()).collect();
-    let result = LookupResult::new(api_rows, timer.elapsed());
+    let metadata = state.metadata.read().await;
+    let by_analysis = metadata_by_analysis(&metadata, &params.ancestry);
+    let api_rows: Vec<GeneAssociationApi> = rows
+        .into_iter()
+        .map(|r| {
+            let mut row = r.to_api();
+            annotate_power(&mut row, by_analysis.get(row.analysis_id.as_str()).copied());
+            row
+        })
+        .collect();
+    drop(metadata);
+    let result = LookupResult::new(api_rows, &timer);
     let json_bytes =
-        serde_json::to_vec(&result).map_err(|e| AppError::DataTransformError(e.to_string()))?;
+        serde_json::to_vec(&result).map_err(|e| AppError::Internal(e.to_string()))?;
 
     state
         .api_cache
@@ -213,6 +530,8 @@ pub struct GeneSymbolRow {
 ///
 /// Returns distinct gene symbols with their IDs for autocomplete functionality.
 /// Results are ordered alphabetically by gene symbol.
+///
+/// Deprecated: ships the entire symbol list; use `/api/genes/search?q=` instead.
 pub async fn get_all_symbols(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<GeneSymbolRow>>, AppError> {
@@ -227,19 +546,151 @@ pub async fn get_all_symbols(
     let rows = state
         .clickhouse
         .query(query)
-        .fetch_all::<GeneSymbolRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with::<GeneSymbolRow>(&state.executor)
+        .await?;
 
     Ok(Json(rows))
 }
 
+/// Query parameters for gene search
+#[derive(Debug, Deserialize)]
+pub struct GeneSearchQuery {
+    /// Search text: symbol, alias, previous symbol or Ensembl ID (prefix or substring)
+    pub q: String,
+    /// Maximum number of suggestions (default: 10, max: 50)
+    pub limit: Option<u32>,
+}
+
+/// Gene search match row from ClickHouse
+#[derive(Debug, Clone, Deserialize, Row)]
+struct GeneSearchRow {
+    pub gene_id: String,
+    pub symbol: String,
+    pub chrom: String,
+    pub start: i32,
+    pub stop: i32,
+    pub rank: u8,
+    pub matched_alias: String,
+}
+
+/// Ranked gene suggestion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneSearchResult {
+    pub gene_id: String,
+    pub symbol: String,
+    pub chrom: String,
+    pub start: i32,
+    pub stop: i32,
+    /// How the query matched: exact, symbol_prefix, alias, alias_prefix,
+    /// previous_symbol, search_term, ensembl_id or substring
+    pub match_type: String,
+    /// The alias or previous symbol that matched, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matched_alias: Option<String>,
+}
+
+/// Match type for a rank from [`GENE_SEARCH_RANK_SQL`] (lower is better)
+fn gene_match_type(rank: u8) -> &'static str {
+    match rank {
+        0 => "exact",
+        1 => "symbol_prefix",
+        2 => "alias",
+        3 => "alias_prefix",
+        4 => "previous_symbol",
+        5 => "search_term",
+        6 => "ensembl_id",
+        _ => "substring",
+    }
+}
+
+/// GET /api/genes/search?q=
+///
+/// Autocomplete over gene symbols, alias symbols, previous symbols and Ensembl
+/// IDs in `gene_models`. Exact matches rank first, then symbol prefixes, alias
+/// matches, previous symbols, search terms, Ensembl ID prefixes and finally
+/// symbol substrings; ties are broken by symbol length.
+pub async fn search_genes(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<GeneSearchQuery>,
+) -> Result<axum::response::Response, AppError> {
+    let q = params.q.trim().to_uppercase();
+    if q.is_empty() {
+        return Err(AppError::BadRequest("Query parameter 'q' must not be empty".to_string()));
+    }
+    let limit = params.limit.unwrap_or(10).clamp(1, 50);
+
+    let dv = state.data_version.as_deref().unwrap_or("none");
+    let cache_key = format!("gene_search:{}:{}:{}", q, limit, dv);
+
+    if let Some(cached_bytes) = state.api_cache.get(&cache_key).await {
+        return Ok(axum::response::Response::builder()
+            .status(axum::http::StatusCode::OK)
+            .header(axum::http::header::CONTENT_TYPE, "application/json")
+            .body(axum::body::Body::from(cached_bytes))
+            .unwrap());
+    }
+
+    let query = format!(
+        r#"
+        WITH ? AS q
+        SELECT gene_id, symbol, chrom, start, stop,
+               {rank} AS rank,
+               arrayFirst(
+                   a -> startsWith(upper(a), q),
+                   arrayConcat(alias_symbols, arrayMap(a -> ifNull(a, ''), previous_symbols))
+               ) AS matched_alias
+        FROM gene_models
+        WHERE symbol != ''
+          AND (rank < {no_match} OR position(symbol_upper_case, q) > 0)
+        ORDER BY rank ASC, length(symbol) ASC, symbol ASC
+        LIMIT ?
+        "#,
+        rank = GENE_SEARCH_RANK_SQL,
+        no_match = GENE_SEARCH_NO_MATCH
+    );
+
+    let rows = state
+        .clickhouse
+        .query(&query)
+        .bind(&q)
+        .bind(limit)
+        .fetch_all_with::<GeneSearchRow>(&state.executor)
+        .await?;
+
+    let results: Vec<GeneSearchResult> = rows
+        .into_iter()
+        .map(|r| GeneSearchResult {
+            match_type: gene_match_type(r.rank).to_string(),
+            matched_alias: (matches!(r.rank, 2..=4) && !r.matched_alias.is_empty())
+                .then_some(r.matched_alias),
+            gene_id: r.gene_id,
+            symbol: r.symbol,
+            chrom: r.chrom,
+            start: r.start,
+            stop: r.stop,
+        })
+        .collect();
+
+    let json_bytes =
+        serde_json::to_vec(&results).map_err(|e| AppError::Internal(e.to_string()))?;
+    state.api_cache.insert(cache_key, json_bytes.clone()).await;
+
+    Ok(axum::response::Response::builder()
+        .status(axum::http::StatusCode::OK)
+        .header(axum::http::header::CONTENT_TYPE, "application/json")
+        .body(axum::body::Body::from(json_bytes))
+        .unwrap())
+}
+
 /// Query parameters for specific gene associations via query string
 #[derive(Debug, Deserialize)]
 pub struct GeneAssociationsQueryParams {
     pub gene_id: String,
     pub analysis_id: String,
     pub ancestry_group: String,
+    /// "mask" (default), "cauchy" or "all"
+    #[serde(default)]
+    pub test: GeneTest,
     #[serde(default)]
     pub use_index: Option<String>,
 }
@@ -252,24 +703,27 @@ pub async fn get_genes_associations(
     State(state): State<Arc<AppState>>,
     Query(params): Query<GeneAssociationsQueryParams>,
 ) -> Result<Json<Vec<crate::models::GeneAssociationApi>>, AppError> {
-    let base_query = r#"
+    let (test_filter, _) = params.test.sql(None);
+    let base_query = format!(
+        r#"
         SELECT gene_id, gene_symbol, annotation, max_maf, phenotype, ancestry,
                pvalue, pvalue_burden, pvalue_skat, beta_burden, mac,
                contig, gene_start_position, xpos
         FROM gene_associations
-        WHERE gene_id = ? AND phenotype = ? AND ancestry = ?
+        WHERE gene_id = ? AND phenotype = ? AND ancestry = ? AND {}
         ORDER BY pvalue ASC
-    "#;
+        "#,
+        test_filter
+    );
 
     let rows = state
         .clickhouse
-        .query(base_query)
+        .query(&base_query)
         .bind(&params.gene_id)
         .bind(&params.analysis_id)
         .bind(&params.ancestry_group)
-        .fetch_all::<GeneAssociationRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with::<GeneAssociationRow>(&state.executor)
+        .await?;
 
     let api_rows: Vec<crate::models::GeneAssociationApi> =
         rows.into_iter().map(|r| r.to_api()).collect();
@@ -287,6 +741,9 @@ pub struct GeneIntervalQuery {
     pub analysis_id: Option<String>,
     /// Maximum number of results (default: 1000)
     pub limit: Option<u64>,
+    /// "mask" (default), "cauchy" or "all"
+    #[serde(default)]
+    pub test: GeneTest,
     /// Query mode (fast/slow) - accepted but currently ignored
     #[serde(default)]
     pub query_mode: Option<String>,
@@ -294,21 +751,23 @@ pub struct GeneIntervalQuery {
 
 /// GET /api/genes/associations/interval/:interval
 ///
-/// Returns gene associations within a genomic interval.
-/// Interval format: "chr1:12345-67890"
+/// Returns gene associations within a genomic interval, filtered by `test=`.
+/// Interval format: "chr1:12345-67890", or any form accepted by `genomics::parse_region`
 pub async fn get_genes_in_interval(
     State(state): State<Arc<AppState>>,
     Path(interval): Path<String>,
     Query(params): Query<GeneIntervalQuery>,
 ) -> Result<Json<LookupResult<GeneAssociationApi>>, AppError> {
-    use crate::clickhouse::xpos::parse_interval_to_xpos;
-
     let timer = QueryTimer::start();
-    let (xpos_start, xpos_end) = parse_interval_to_xpos(&interval)?;
+    let (xpos_start, xpos_end) =
+        crate::genomics::resolve_region(state.gene_models.as_ref(), &interval)
+            .await?
+            .xpos_range();
     let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
     let limit = params.limit.unwrap_or(1000);
 
-    let mut filters = String::new();
+    let (test_filter, _) = params.test.sql(None);
+    let mut filters = format!("AND {} ", test_filter);
     if params.analysis_id.is_some() {
         filters.push_str("AND phenotype = ? ");
     }
@@ -344,12 +803,11 @@ pub async fn get_genes_in_interval(
     query = query.bind(limit);
 
     let rows = query
-        .fetch_all::<GeneAssociationRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with::<GeneAssociationRow>(&state.executor)
+        .await?;
 
     let api_rows: Vec<GeneAssociationApi> = rows.into_iter().map(|r| r.to_api()).collect();
-    Ok(Json(LookupResult::new(api_rows, timer.elapsed())))
+    Ok(Json(LookupResult::new(api_rows, &timer)))
 }
 
 /// GET /api/genes/summary
@@ -378,13 +836,12 @@ pub async fn get_genes_summary(
     let rows = state
         .clickhouse
         .query(query)
-        .fetch_all::<GeneSummaryRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with::<GeneSummaryRow>(&state.executor)
+        .await?;
 
-    let result = LookupResult::new(rows, timer.elapsed());
+    let result = LookupResult::new(rows, &timer);
     let json_bytes =
-        serde_json::to_vec(&result).map_err(|e| AppError::DataTransformError(e.to_string()))?;
+        serde_json::to_vec(&result).map_err(|e| AppError::Internal(e.to_string()))?;
 
     state
         .api_cache
@@ -397,3 +854,256 @@ pub async fn get_genes_summary(
         .body(axum::body::Body::from(json_bytes))
         .unwrap())
 }
+
+/// Query parameters for the gene LoF summary endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LofSummaryQuery {
+    /// Ancestry group of the burden results (default: "meta")
+    pub ancestry: Option<String>,
+    /// Annotation table: "exomes" (default) or "genomes"
+    pub sequencing_type: Option<String>,
+    /// Only count pLoF variants at or below this allele frequency
+    pub max_af: Option<f64>,
+}
+
+/// Aggregated pLoF allele counts from `exome_annotations` / `genome_annotations`
+#[derive(Debug, Clone, Deserialize, Row)]
+struct LofAggregateRow {
+    variant_count: u64,
+    high_confidence_count: u64,
+    cumulative_af: f64,
+    ac: u64,
+    an: u32,
+    hom: u64,
+    /// Per-ancestry sums, in [`lof_ancestries`] order
+    ancestry_ac: Vec<u64>,
+    ancestry_an: Vec<u32>,
+    ancestry_caf: Vec<f64>,
+}
+
+/// Ancestry groups with their own AC/AN/AF columns in the annotation tables
+fn lof_ancestries() -> impl Iterator<Item = AncestryGroup> {
+    AncestryGroup::all()
+        .iter()
+        .copied()
+        .filter(|a| *a != AncestryGroup::Meta)
+}
+
+/// Array columns of per-ancestry pLoF AC, AN and cumulative AF sums
+fn lof_ancestry_sql() -> String {
+    let column = |aggregate: &dyn Fn(AncestryGroup) -> String| {
+        lof_ancestries().map(aggregate).collect::<Vec<_>>().join(", ")
+    };
+    format!(
+        "[{}] AS ancestry_ac, [{}] AS ancestry_an, [{}] AS ancestry_caf",
+        column(&|a| format!("sum(toUInt64(ifNull(ac_{}, 0)))", a)),
+        column(&|a| format!("max(ifNull(an_{}, 0))", a)),
+        column(&|a| format!("least(sum(ifNull(af_{}, 0)), 1.0)", a)),
+    )
+}
+
+/// pLoF carrier estimate for one ancestry group
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LofCarrierEstimate {
+    /// Ancestry group: "all", or afr, amr, eas, eur, mid or sas
+    pub ancestry: String,
+    /// Summed pLoF allele count
+    pub allele_count: u64,
+    /// Largest allele number across the pLoF variants
+    pub allele_number: u32,
+    /// Summed pLoF homozygote count; not available per ancestry
+    pub homozygote_count: Option<u64>,
+    /// Estimated carriers (ac - hom, or ac without homozygote counts),
+    /// counting compound heterozygotes twice
+    pub carriers: u64,
+    /// carriers / (an / 2)
+    pub carrier_frequency: Option<f64>,
+    /// 1 - (1 - CAF)^2, the carrier frequency expected under Hardy-Weinberg
+    pub expected_carrier_frequency: f64,
+}
+
+impl LofCarrierEstimate {
+    fn from_counts(ancestry: &str, ac: u64, an: u32, hom: Option<u64>, cumulative_af: f64) -> Self {
+        let carriers = ac.saturating_sub(hom.unwrap_or(0));
+        let caf = cumulative_af.clamp(0.0, 1.0);
+        LofCarrierEstimate {
+            ancestry: ancestry.to_string(),
+            allele_count: ac,
+            allele_number: an,
+            homozygote_count: hom,
+            carriers,
+            carrier_frequency: (an >= 2).then(|| carriers as f64 / (an / 2) as f64),
+            expected_carrier_frequency: 1.0 - (1.0 - caf).powi(2),
+        }
+    }
+}
+
+/// Response for GET /api/genes/:gene_id/lof-summary
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct GeneLofSummary {
+    pub gene_id: String,
+    pub gene_symbol: String,
+    pub sequencing_type: String,
+    /// Number of pLoF variants in the gene
+    pub variant_count: u64,
+    /// pLoF variants flagged high-confidence by LOFTEE
+    pub high_confidence_count: u64,
+    /// Cumulative allele frequency (sum of pLoF AFs, capped at 1)
+    pub cumulative_af: f64,
+    pub carriers: Vec<LofCarrierEstimate>,
+    /// gnomAD LoF constraint for the gene
+    pub constraint: Option<GnomadConstraint>,
+    /// pLoF burden results across phenotypes, most significant first
+    pub burden: Vec<GeneAssociationApi>,
+}
+
+/// GET /api/genes/:gene_id/lof-summary
+///
+/// Summarizes the pLoF variants annotated to a gene (count, cumulative AF and
+/// carrier estimates from ac/an/hom, overall and per ancestry) next to its
+/// gnomAD constraint and pLoF burden results, for the "LoF constraint vs.
+/// association" view.
+#[utoipa::path(
+    get,
+    path = "/api/genes/{gene_id}/lof-summary",
+    tag = "genes",
+    params(("gene_id" = String, Path, description = "Ensembl gene ID or gene symbol"), LofSummaryQuery),
+    responses(
+        (status = 200, description = "pLoF summary and burden results", body = GeneLofSummary),
+        (status = 404, description = "Gene not found", body = ErrorResponse),
+        (status = 500, description = "ClickHouse query failed", body = ErrorResponse)
+    )
+)]
+pub async fn get_gene_lof_summary(
+    State(state): State<Arc<AppState>>,
+    Path(gene_id): Path<String>,
+    Query(params): Query<LofSummaryQuery>,
+) -> Result<Json<GeneLofSummary>, AppError> {
+    let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
+    let (table, sequencing_type) = match params.sequencing_type.as_deref() {
+        None | Some("exomes") | Some("exome") => ("exome_annotations", "exomes"),
+        Some("genomes") | Some("genome") => ("genome_annotations", "genomes"),
+        Some(other) => {
+            return Err(AppError::BadRequest(format!(
+                "Unknown sequencing_type '{}'. Expected exomes or genomes",
+                other
+            )))
+        }
+    };
+
+    let gene = if gene_id.to_uppercase().starts_with("ENSG") {
+        let gene_id = gene_id.split('.').next().unwrap_or(&gene_id).to_uppercase();
+        state.gene_models.get_by_gene_id(&gene_id).await?
+    } else {
+        state.gene_models.get_by_symbol(&gene_id).await?
+    };
+    let gene = gene.ok_or_else(|| AppError::NotFound(format!("Gene '{}' not found", gene_id)))?;
+
+    let lof = ConsequenceFilter::class(ConsequenceClass::Lof);
+    let query = format!(
+        r#"
+        SELECT count() AS variant_count,
+               countIf(lof = 'HC') AS high_confidence_count,
+               least(sum(ifNull(af, 0)), 1.0) AS cumulative_af,
+               sum(toUInt64(ifNull(ac, 0))) AS ac,
+               max(ifNull(an, 0)) AS an,
+               sum(toUInt64(ifNull(hom, 0))) AS hom,
+               {}
+        FROM {}
+        WHERE xpos >= ? AND xpos <= ?
+          AND gene_id = ?
+          AND {}
+          {}
+        "#,
+        lof_ancestry_sql(),
+        table,
+        lof.sql("consequence"),
+        if params.max_af.is_some() {
+            "AND af <= ?"
+        } else {
+            ""
+        }
+    );
+    let mut query = state
+        .clickhouse
+        .query(&query)
+        .bind(gene.xstart)
+        .bind(gene.xstop)
+        .bind(&gene.gene_id)
+        .bind(lof.terms());
+    if let Some(max_af) = params.max_af {
+        query = query.bind(max_af);
+    }
+    let lof_row = query
+        .fetch_one_with::<LofAggregateRow>(&state.executor)
+        .await?;
+
+    let burden = fetch_gene_phewas_rows(
+        &state,
+        &gene.gene_id,
+        &ancestry,
+        Some("pLoF"),
+        None,
+        GeneTest::Mask,
+    )
+    .await?
+        .into_iter()
+        .map(|r| r.to_api())
+        .collect();
+
+    let mut carriers = vec![LofCarrierEstimate::from_counts(
+        "all",
+        lof_row.ac,
+        lof_row.an,
+        Some(lof_row.hom),
+        lof_row.cumulative_af,
+    )];
+    for (i, ancestry) in lof_ancestries().enumerate() {
+        carriers.push(LofCarrierEstimate::from_counts(
+            &ancestry.to_string(),
+            lof_row.ancestry_ac.get(i).copied().unwrap_or(0),
+            lof_row.ancestry_an.get(i).copied().unwrap_or(0),
+            None,
+            lof_row.ancestry_caf.get(i).copied().unwrap_or(0.0),
+        ));
+    }
+
+    Ok(Json(GeneLofSummary {
+        carriers,
+        variant_count: lof_row.variant_count,
+        high_confidence_count: lof_row.high_confidence_count,
+        cumulative_af: lof_row.cumulative_af,
+        sequencing_type: sequencing_type.to_string(),
+        constraint: gene.gnomad_constraint,
+        gene_id: gene.gene_id,
+        gene_symbol: gene.symbol,
+        burden,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lof_carrier_estimate() {
+        let estimate = LofCarrierEstimate::from_counts("all", 120, 20_000, Some(10), 0.006);
+        assert_eq!(estimate.carriers, 110);
+        assert_eq!(estimate.carrier_frequency, Some(0.011));
+        assert!((estimate.expected_carrier_frequency - 0.011964).abs() < 1e-9);
+
+        let empty = LofCarrierEstimate::from_counts("all", 0, 0, 0, 0.0);
+        assert_eq!(empty.carrier_frequency, None);
+        assert_eq!(empty.expected_carrier_frequency, 0.0);
+
+        let afr = LofCarrierEstimate::from_counts("afr", 30, 2_000, None, 0.015);
+        assert_eq!(afr.carriers, 30);
+        assert_eq!(afr.homozygote_count, None);
+
+        let sql = lof_ancestry_sql();
+        assert!(sql.starts_with("[sum(toUInt64(ifNull(ac_afr, 0))), "));
+        assert!(sql.contains("max(ifNull(an_sas, 0))] AS ancestry_an"));
+        assert!(!sql.contains("_meta"));
+    }
+}
@@ -0,0 +1,251 @@
+//! VEP consequence terms and the classes the browser filters by
+//!
+//! Annotation tables store VEP's most severe consequence as a Sequence
+//! Ontology term ("stop_gained"), occasionally `&`-joined. Users filter with
+//! `consequence=`, which takes classes (`lof`, `missense`, `synonymous`,
+//! `splice`, `other`) and/or individual terms; [`ConsequenceFilter`] expands
+//! them to terms and renders the ClickHouse condition.
+
+use crate::error::AppError;
+use std::fmt;
+
+/// VEP consequence terms, most severe first
+const VEP_TERMS: [&str; 41] = [
+    "transcript_ablation",
+    "splice_acceptor_variant",
+    "splice_donor_variant",
+    "stop_gained",
+    "frameshift_variant",
+    "stop_lost",
+    "start_lost",
+    "transcript_amplification",
+    "feature_elongation",
+    "feature_truncation",
+    "inframe_insertion",
+    "inframe_deletion",
+    "missense_variant",
+    "protein_altering_variant",
+    "splice_donor_5th_base_variant",
+    "splice_region_variant",
+    "splice_donor_region_variant",
+    "splice_polypyrimidine_tract_variant",
+    "incomplete_terminal_codon_variant",
+    "start_retained_variant",
+    "stop_retained_variant",
+    "synonymous_variant",
+    "coding_sequence_variant",
+    "mature_miRNA_variant",
+    "5_prime_UTR_variant",
+    "3_prime_UTR_variant",
+    "non_coding_transcript_exon_variant",
+    "intron_variant",
+    "NMD_transcript_variant",
+    "non_coding_transcript_variant",
+    "coding_transcript_variant",
+    "upstream_gene_variant",
+    "downstream_gene_variant",
+    "TFBS_ablation",
+    "TFBS_amplification",
+    "TF_binding_site_variant",
+    "regulatory_region_ablation",
+    "regulatory_region_amplification",
+    "regulatory_region_variant",
+    "intergenic_variant",
+    "sequence_variant",
+];
+
+/// Consequence class used by `consequence=` filters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsequenceClass {
+    /// Predicted loss of function (pLoF)
+    Lof,
+    /// Missense and other in-frame protein-altering changes
+    Missense,
+    Synonymous,
+    /// Splice region outside the canonical splice sites
+    Splice,
+    /// Everything else (UTR, intronic, non-coding, regulatory, ...)
+    Other,
+}
+
+impl ConsequenceClass {
+    pub const ALL: [ConsequenceClass; 5] = [
+        ConsequenceClass::Lof,
+        ConsequenceClass::Missense,
+        ConsequenceClass::Synonymous,
+        ConsequenceClass::Splice,
+        ConsequenceClass::Other,
+    ];
+
+    /// Parse a class name ("lof"/"plof", "missense", "synonymous", "splice", "other")
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "lof" | "plof" => Some(Self::Lof),
+            "missense" => Some(Self::Missense),
+            "synonymous" | "syn" => Some(Self::Synonymous),
+            "splice" => Some(Self::Splice),
+            "other" => Some(Self::Other),
+            _ => None,
+        }
+    }
+
+    /// Class of a single VEP term (unknown terms are `Other`)
+    pub fn of(term: &str) -> Self {
+        match term {
+            "transcript_ablation"
+            | "splice_acceptor_variant"
+            | "splice_donor_variant"
+            | "stop_gained"
+            | "frameshift_variant" => Self::Lof,
+            "stop_lost"
+            | "start_lost"
+            | "inframe_insertion"
+            | "inframe_deletion"
+            | "missense_variant"
+            | "protein_altering_variant" => Self::Missense,
+            "synonymous_variant" => Self::Synonymous,
+            "splice_donor_5th_base_variant"
+            | "splice_region_variant"
+            | "splice_donor_region_variant"
+            | "splice_polypyrimidine_tract_variant" => Self::Splice,
+            _ => Self::Other,
+        }
+    }
+
+    /// VEP terms in this class
+    pub fn terms(self) -> impl Iterator<Item = &'static str> {
+        VEP_TERMS
+            .into_iter()
+            .filter(move |term| Self::of(term) == self)
+    }
+}
+
+impl fmt::Display for ConsequenceClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Lof => "lof",
+            Self::Missense => "missense",
+            Self::Synonymous => "synonymous",
+            Self::Splice => "splice",
+            Self::Other => "other",
+        })
+    }
+}
+
+/// Canonical spelling of a VEP term, matched case-insensitively
+fn vep_term(name: &str) -> Option<&'static str> {
+    let name = name.trim();
+    VEP_TERMS
+        .into_iter()
+        .find(|term| term.eq_ignore_ascii_case(name))
+}
+
+/// Set of VEP terms selected by a `consequence=` parameter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsequenceFilter {
+    terms: Vec<&'static str>,
+}
+
+impl ConsequenceFilter {
+    /// Filter matching every term in `class`
+    pub fn class(class: ConsequenceClass) -> Self {
+        let mut terms: Vec<&'static str> = class.terms().collect();
+        terms.sort_unstable();
+        Self { terms }
+    }
+
+    /// Parse a comma-separated list of classes and/or VEP terms
+    ///
+    /// Returns `None` when the parameter is absent or empty.
+    pub fn parse(consequence: Option<&str>) -> Result<Option<Self>, AppError> {
+        let mut terms = Vec::new();
+        for name in consequence.unwrap_or("").split(',').map(str::trim) {
+            if name.is_empty() {
+                continue;
+            }
+            if let Some(class) = ConsequenceClass::parse(name) {
+                terms.extend(class.terms());
+            } else if let Some(term) = vep_term(name) {
+                terms.push(term);
+            } else {
+                return Err(AppError::BadRequest(format!(
+                    "Unknown consequence '{}'. Expected lof, missense, synonymous, splice, other \
+                     or a VEP consequence term",
+                    name
+                )));
+            }
+        }
+        terms.sort_unstable();
+        terms.dedup();
+        Ok((!terms.is_empty()).then_some(Self { terms }))
+    }
+
+    /// Selected VEP terms; bind as the single parameter of [`Self::sql`]
+    pub fn terms(&self) -> &[&'static str] {
+        &self.terms
+    }
+
+    /// ClickHouse condition on a (Nullable) consequence column, e.g. `ann.consequence`
+    pub fn sql(&self, column: &str) -> String {
+        format!("hasAny(?, splitByChar('&', ifNull({}, '')))", column)
+    }
+
+    /// Whether a stored consequence matches, for rows filtered outside ClickHouse
+    pub fn matches(&self, consequence: Option<&str>) -> bool {
+        consequence
+            .unwrap_or("")
+            .split('&')
+            .any(|term| self.terms.contains(&term))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_term_has_one_class() {
+        let total: usize = ConsequenceClass::ALL
+            .iter()
+            .map(|c| c.terms().count())
+            .sum();
+        assert_eq!(total, VEP_TERMS.len());
+        assert_eq!(ConsequenceClass::of("stop_gained"), ConsequenceClass::Lof);
+        assert_eq!(
+            ConsequenceClass::of("intron_variant"),
+            ConsequenceClass::Other
+        );
+    }
+
+    #[test]
+    fn test_filter_expands_classes_and_terms() {
+        let filter = ConsequenceFilter::parse(Some("lof, Synonymous_Variant"))
+            .unwrap()
+            .unwrap();
+        assert!(filter.terms().contains(&"stop_gained"));
+        assert!(filter.terms().contains(&"synonymous_variant"));
+        assert!(!filter.terms().contains(&"missense_variant"));
+        assert!(filter.matches(Some("frameshift_variant&splice_region_variant")));
+        assert!(!filter.matches(Some("missense_variant")));
+        assert!(!filter.matches(None));
+        assert_eq!(
+            ConsequenceFilter::parse(Some("plof")).unwrap(),
+            Some(ConsequenceFilter::class(ConsequenceClass::Lof))
+        );
+
+        assert_eq!(ConsequenceFilter::parse(Some(" , ")).unwrap(), None);
+        assert!(matches!(
+            ConsequenceFilter::parse(Some("lof,nonsense")),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_filter_sql() {
+        let filter = ConsequenceFilter::parse(Some("missense")).unwrap().unwrap();
+        assert_eq!(
+            filter.sql("ann.consequence"),
+            "hasAny(?, splitByChar('&', ifNull(ann.consequence, '')))"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! Canonical contig naming
+//!
+//! Storage convention:
+//! - `contig` columns (`loci`, `loci_variants`, `significant_variants`,
+//!   annotation and association tables) and Hail tables use GRCh38 names:
+//!   `chr1`..`chr22`, `chrX`, `chrY`, `chrM`
+//! - `gene_models.chrom` is bare: `1`..`22`, `X`, `Y`, `M`
+//! - xpos encodes the contig as 1-22, 23 (X), 24 (Y), 25 (M)
+//!
+//! Handlers parse user input once with [`Contig::parse`] (which accepts either
+//! form, any case, and `MT`) and then use [`Contig::chr`] or [`Contig::bare`]
+//! for the table being queried, never string surgery on the raw input.
+
+use crate::error::AppError;
+use std::fmt;
+use std::str::FromStr;
+
+const CHR_NAMES: [&str; 25] = [
+    "chr1", "chr2", "chr3", "chr4", "chr5", "chr6", "chr7", "chr8", "chr9", "chr10", "chr11",
+    "chr12", "chr13", "chr14", "chr15", "chr16", "chr17", "chr18", "chr19", "chr20", "chr21",
+    "chr22", "chrX", "chrY", "chrM",
+];
+
+/// GRCh38 chromosome lengths, in xpos contig order
+const GRCH38_LENGTHS: [u32; 25] = [
+    248_956_422,
+    242_193_529,
+    198_295_559,
+    190_214_555,
+    181_538_259,
+    170_805_979,
+    159_345_973,
+    145_138_636,
+    138_394_717,
+    133_797_422,
+    135_086_622,
+    133_275_309,
+    114_364_328,
+    107_043_718,
+    101_991_189,
+    90_338_345,
+    83_257_441,
+    80_373_285,
+    58_617_616,
+    64_444_167,
+    46_709_983,
+    50_818_468,
+    156_040_895,
+    57_227_415,
+    16_569,
+];
+
+/// A GRCh38 primary contig, stored as its xpos number (1-25)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Contig(u8);
+
+impl Contig {
+    /// All contigs in xpos order
+    pub fn all() -> impl Iterator<Item = Contig> {
+        (1..=25).map(Contig)
+    }
+
+    /// Parse "chr1", "1", "chrx", "X", "MT", "chrM", ...
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.trim();
+        let bare = name
+            .strip_prefix("chr")
+            .or_else(|| name.strip_prefix("CHR"))
+            .or_else(|| name.strip_prefix("Chr"))
+            .unwrap_or(name);
+        match bare.to_ascii_uppercase().as_str() {
+            "X" => Some(Self(23)),
+            "Y" => Some(Self(24)),
+            "M" | "MT" => Some(Self(25)),
+            n if !n.starts_with('0') => n
+                .parse::<u8>()
+                .ok()
+                .filter(|n| (1..=22).contains(n))
+                .map(Self),
+            _ => None,
+        }
+    }
+
+    /// Contig from its xpos number (1-25)
+    pub fn from_index(index: i64) -> Option<Self> {
+        (1..=25).contains(&index).then_some(Self(index as u8))
+    }
+
+    /// Contig encoded in an xpos value
+    pub fn from_xpos(xpos: i64) -> Option<Self> {
+        Self::from_index(xpos / 1_000_000_000)
+    }
+
+    /// xpos contig number (1-25)
+    pub fn index(self) -> u8 {
+        self.0
+    }
+
+    /// GRCh38 name as stored in `contig` columns, e.g. "chrX"
+    pub fn chr(self) -> &'static str {
+        CHR_NAMES[self.0 as usize - 1]
+    }
+
+    /// Bare name as stored in `gene_models.chrom`, e.g. "X"
+    pub fn bare(self) -> &'static str {
+        &self.chr()[3..]
+    }
+
+    /// GRCh38 length in bp
+    pub fn length(self) -> u32 {
+        GRCH38_LENGTHS[self.0 as usize - 1]
+    }
+
+    /// xpos of `position` on this contig
+    pub fn xpos(self, position: u32) -> i64 {
+        self.0 as i64 * 1_000_000_000 + position as i64
+    }
+}
+
+/// ClickHouse expression for the GRCh38 contig name ("chrX") of an xpos
+/// expression, or '' when the xpos is out of range
+///
+/// Derive contigs from xpos rather than string-slicing a stored `contig`
+/// column, which may not follow the storage convention in older tables.
+pub fn xpos_contig_sql(xpos: &str) -> String {
+    let names: Vec<String> = CHR_NAMES.iter().map(|n| format!("'{}'", n)).collect();
+    format!(
+        "arrayElement([{}], toUInt32(intDiv({}, 1000000000)))",
+        names.join(", "),
+        xpos
+    )
+}
+
+impl fmt::Display for Contig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.chr())
+    }
+}
+
+impl FromStr for Contig {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+            .ok_or_else(|| AppError::InvalidInterval(format!("Unknown chromosome '{}'", s)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_accepts_both_conventions() {
+        for (input, chr, bare, index) in [
+            ("chr1", "chr1", "1", 1),
+            ("22", "chr22", "22", 22),
+            ("chrX", "chrX", "X", 23),
+            ("x", "chrX", "X", 23),
+            ("CHRY", "chrY", "Y", 24),
+            ("Y", "chrY", "Y", 24),
+            ("chrM", "chrM", "M", 25),
+            ("MT", "chrM", "M", 25),
+            ("chrMT", "chrM", "M", 25),
+        ] {
+            let contig = Contig::parse(input).unwrap();
+            assert_eq!(contig.chr(), chr, "{}", input);
+            assert_eq!(contig.bare(), bare, "{}", input);
+            assert_eq!(contig.index(), index, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown() {
+        for input in [
+            "",
+            "chr",
+            "chr0",
+            "chr23",
+            "01",
+            "chrZ",
+            "chrUn",
+            "chr1_KI270706v1_random",
+        ] {
+            assert!(Contig::parse(input).is_none(), "{}", input);
+        }
+        assert!(matches!(
+            "chrZ".parse::<Contig>(),
+            Err(AppError::InvalidInterval(_))
+        ));
+    }
+
+    #[test]
+    fn test_xpos_round_trip() {
+        for contig in Contig::all() {
+            let xpos = contig.xpos(12_345);
+            assert_eq!(Contig::from_xpos(xpos), Some(contig));
+            assert_eq!(Contig::parse(contig.chr()), Some(contig));
+            assert_eq!(Contig::parse(contig.bare()), Some(contig));
+        }
+        assert_eq!(Contig::all().count(), 25);
+        assert_eq!(Contig::from_xpos(26_000_000_001), None);
+        assert_eq!(Contig::parse("chrM").unwrap().length(), 16_569);
+    }
+
+    #[test]
+    fn test_xpos_contig_sql() {
+        let sql = xpos_contig_sql("lv.xpos");
+        assert!(sql.starts_with("arrayElement(['chr1', 'chr2', "));
+        assert!(sql.contains("'chrX', 'chrY', 'chrM']"));
+        assert!(sql.ends_with("toUInt32(intDiv(lv.xpos, 1000000000)))"));
+    }
+}
@@ -0,0 +1,264 @@
+//! Genomic region parsing shared by every interval endpoint
+//!
+//! [`parse_region`] accepts the forms users paste into the browser:
+//!
+//! - `chr17:41196312-41277500`, `17:41,196,312-41,277,500` (commas ignored)
+//! - `chr17:41196312` (single position) and `chr17:41196312+/-5000` (flank)
+//! - `chr17`, `X` (whole chromosome, GRCh38 lengths)
+//! - `BRCA2`, `ENSG00000139618`, `ENSG00000139618.17`, `BRCA2+/-50000`
+//!
+//! Gene symbols and Ensembl IDs are resolved against `gene_models` by
+//! [`resolve_region`]; coordinate forms never touch ClickHouse.
+
+pub mod consequence;
+pub mod contig;
+
+pub use contig::Contig;
+
+use crate::error::AppError;
+use crate::gene_models::GeneModelBackend;
+use std::fmt;
+
+/// A resolved, 1-based inclusive genomic interval
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    /// Chromosome without "chr" prefix ("1".."22", "X", "Y", "M")
+    pub chrom: String,
+    pub start: u32,
+    pub stop: u32,
+}
+
+impl Region {
+    /// Contig in GRCh38 Hail/ClickHouse form, e.g. "chr17"
+    pub fn contig(&self) -> String {
+        format!("chr{}", self.chrom)
+    }
+
+    /// (xpos_start, xpos_end) for range queries
+    pub fn xpos_range(&self) -> (i64, i64) {
+        match Contig::parse(&self.chrom) {
+            Some(contig) => (contig.xpos(self.start), contig.xpos(self.stop)),
+            None => (self.start as i64, self.stop as i64),
+        }
+    }
+
+    fn with_flank(contig: Contig, start: u32, stop: u32, flank: u32) -> Self {
+        Self {
+            chrom: contig.bare().to_string(),
+            start: start.saturating_sub(flank).max(1),
+            stop: stop.saturating_add(flank).min(contig.length()),
+        }
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "chr{}:{}-{}", self.chrom, self.start, self.stop)
+    }
+}
+
+/// A parsed region, possibly still naming a gene
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegionQuery {
+    Interval(Region),
+    /// Gene symbol or Ensembl gene ID (version stripped), with flank in bp
+    Gene {
+        name: String,
+        flank: u32,
+    },
+}
+
+fn parse_position(value: &str, input: &str) -> Result<u32, AppError> {
+    value.parse::<u32>().map_err(|_| {
+        AppError::InvalidInterval(format!(
+            "Invalid position '{}' in region '{}'",
+            value, input
+        ))
+    })
+}
+
+/// Split a trailing `+/-N` (also `+-N` or `±N`) flank off `input`
+fn split_flank<'a>(input: &'a str, original: &str) -> Result<(&'a str, u32), AppError> {
+    for marker in ["+/-", "+-", "±"] {
+        if let Some((base, flank)) = input.rsplit_once(marker) {
+            return Ok((base, parse_position(flank, original)?));
+        }
+    }
+    Ok((input, 0))
+}
+
+/// Parse a region string without resolving gene names
+pub fn parse_region(input: &str) -> Result<RegionQuery, AppError> {
+    let cleaned: String = input.trim().chars().filter(|c| *c != ',').collect();
+    if cleaned.is_empty() {
+        return Err(AppError::InvalidInterval("Empty region".to_string()));
+    }
+    let (body, flank) = split_flank(&cleaned, input)?;
+
+    if let Some((chrom, range)) = body.split_once(':') {
+        let contig = Contig::parse(chrom).ok_or_else(|| {
+            AppError::InvalidInterval(format!("Invalid chromosome in region: {}", chrom))
+        })?;
+        let (start, stop) = match range.split_once('-') {
+            Some((start, stop)) => (parse_position(start, input)?, parse_position(stop, input)?),
+            None => {
+                let position = parse_position(range, input)?;
+                (position, position)
+            }
+        };
+        if start > stop {
+            return Err(AppError::InvalidInterval(format!(
+                "Region start {} is after stop {}",
+                start, stop
+            )));
+        }
+        return Ok(RegionQuery::Interval(Region::with_flank(
+            contig, start, stop, flank,
+        )));
+    }
+
+    if let Some(contig) = Contig::parse(body) {
+        return Ok(RegionQuery::Interval(Region {
+            chrom: contig.bare().to_string(),
+            start: 1,
+            stop: contig.length(),
+        }));
+    }
+
+    let is_gene_name = body
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_'));
+    if !is_gene_name {
+        return Err(AppError::InvalidInterval(format!(
+            "Invalid region '{}'. Expected chr:start-end, chr:pos, a chromosome, or a gene",
+            input
+        )));
+    }
+
+    let name = if body.to_uppercase().starts_with("ENSG") {
+        // Drop the Ensembl version suffix (ENSG00000139618.17)
+        body.split('.').next().unwrap_or(body).to_uppercase()
+    } else {
+        body.to_string()
+    };
+    Ok(RegionQuery::Gene { name, flank })
+}
+
+/// Parse a region and resolve gene symbols / Ensembl IDs via the gene model backend
+pub async fn resolve_region(
+    gene_models: &dyn GeneModelBackend,
+    input: &str,
+) -> Result<Region, AppError> {
+    let (name, flank) = match parse_region(input)? {
+        RegionQuery::Interval(region) => return Ok(region),
+        RegionQuery::Gene { name, flank } => (name, flank),
+    };
+
+    let gene = if name.starts_with("ENSG") {
+        gene_models.get_by_gene_id(&name).await?
+    } else {
+        gene_models.get_by_symbol(&name).await?
+    };
+    let gene = gene.ok_or_else(|| AppError::NotFound(format!("Gene '{}' not found", name)))?;
+
+    let contig = Contig::parse(&gene.chrom).ok_or_else(|| {
+        AppError::Internal(format!(
+            "Gene {} has unknown chromosome {}",
+            name, gene.chrom
+        ))
+    })?;
+    Ok(Region::with_flank(
+        contig,
+        gene.start.max(1) as u32,
+        gene.stop.max(1) as u32,
+        flank,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interval(input: &str) -> Region {
+        match parse_region(input).unwrap() {
+            RegionQuery::Interval(region) => region,
+            other => panic!("expected interval for {}, got {:?}", input, other),
+        }
+    }
+
+    #[test]
+    fn test_parse_coordinates() {
+        let region = interval("chr17:41,196,312-41,277,500");
+        assert_eq!(region.chrom, "17");
+        assert_eq!((region.start, region.stop), (41_196_312, 41_277_500));
+        assert_eq!(region.xpos_range(), (17_041_196_312, 17_041_277_500));
+        assert_eq!(interval("1:100-200").to_string(), "chr1:100-200");
+        assert_eq!(interval("chrMT:10-20").chrom, "M");
+    }
+
+    #[test]
+    fn test_parse_position_with_flank() {
+        let region = interval("chr1:12345");
+        assert_eq!((region.start, region.stop), (12345, 12345));
+
+        let region = interval("chr1:12345+/-1000");
+        assert_eq!((region.start, region.stop), (11345, 13345));
+
+        // Flank is clamped to the chromosome
+        let region = interval("chrM:100±1000");
+        assert_eq!((region.start, region.stop), (1, 1100));
+    }
+
+    #[test]
+    fn test_parse_whole_chromosome() {
+        let region = interval("chrX");
+        assert_eq!((region.start, region.stop), (1, 156_040_895));
+        assert_eq!(interval("22").stop, 50_818_468);
+    }
+
+    #[test]
+    fn test_parse_sex_and_mito_chromosomes() {
+        for (input, chrom, contig, xpos_start) in [
+            ("chrX:100-200", "X", "chrX", 23_000_000_100),
+            ("x:100-200", "X", "chrX", 23_000_000_100),
+            ("chrY:100-200", "Y", "chrY", 24_000_000_100),
+            ("Y:100-200", "Y", "chrY", 24_000_000_100),
+            ("chrM:100-200", "M", "chrM", 25_000_000_100),
+            ("MT:100-200", "M", "chrM", 25_000_000_100),
+        ] {
+            let region = interval(input);
+            assert_eq!(region.chrom, chrom, "{}", input);
+            assert_eq!(region.contig(), contig, "{}", input);
+            assert_eq!(region.xpos_range().0, xpos_start, "{}", input);
+        }
+        assert_eq!(interval("chrY").stop, 57_227_415);
+        assert_eq!(interval("chrM").stop, 16_569);
+    }
+
+    #[test]
+    fn test_parse_genes() {
+        assert_eq!(
+            parse_region("BRCA2").unwrap(),
+            RegionQuery::Gene {
+                name: "BRCA2".to_string(),
+                flank: 0
+            }
+        );
+        assert_eq!(
+            parse_region("ENSG00000139618.17+/-5000").unwrap(),
+            RegionQuery::Gene {
+                name: "ENSG00000139618".to_string(),
+                flank: 5000
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse_region("").is_err());
+        assert!(parse_region("chrZ:1-2").is_err());
+        assert!(parse_region("chr1:200-100").is_err());
+        assert!(parse_region("chr1:abc-100").is_err());
+        assert!(parse_region("BRCA2; DROP TABLE").is_err());
+    }
+}
@@ -0,0 +1,387 @@
+//! Liveness and readiness probes
+//!
+//! - `/healthz`: the process is up and serving HTTP (always 200)
+//! - `/readyz`: every mounted dataset has loaded metadata and can reach
+//!   ClickHouse (and, optionally, has discovered assets); 503 otherwise
+//!
+//! - `/api/health/detail`: status and latency of each upstream of a dataset
+//!   (ClickHouse, GCS, the `gene_models` table, the asset cache, background
+//!   tasks); 503 when a critical one is down. The endpoint is public, so
+//!   check details never include bucket paths, task IDs or upstream error
+//!   messages; failures are logged instead.
+//!
+//! Kubernetes should route traffic only once `/readyz` returns 200. With
+//! webhooks configured, `serve` also checks readiness periodically and posts
+//! `health.degraded` / `health.recovered` events when a dataset changes state.
+
+use crate::api::AppState;
+use crate::clickhouse::QueryExt;
+use crate::webhooks::{self, Event};
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, Utc};
+use object_store::gcp::GoogleCloudStorageBuilder;
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Time allowed for each upstream check in `/api/health/detail`
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Admin tasks running longer than this are reported as stuck
+const MAX_TASK_RUNTIME: Duration = Duration::from_secs(6 * 3600);
+
+/// Table stats older than this mean the sampler has stopped
+const MAX_TABLE_STATS_AGE: Duration = Duration::from_secs(3600);
+
+/// State shared by the probe handlers
+pub struct ProbeState {
+    /// (dataset name, state) for every mounted dataset
+    pub datasets: Vec<(String, Arc<AppState>)>,
+    /// Whether readiness also requires asset discovery to have completed
+    pub require_assets: bool,
+}
+
+/// Readiness of a single dataset
+#[derive(Debug, Clone, Serialize)]
+pub struct DatasetReadiness {
+    pub dataset: String,
+    pub metadata_loaded: bool,
+    pub metadata_count: usize,
+    pub clickhouse_reachable: bool,
+    pub assets_discovered: bool,
+    pub ready: bool,
+}
+
+/// Response body for `/readyz`
+#[derive(Debug, Serialize)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    pub datasets: Vec<DatasetReadiness>,
+}
+
+/// GET /healthz
+///
+/// Liveness probe: returns 200 as long as the server is accepting requests.
+pub async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// GET /readyz
+///
+/// Readiness probe: 200 when all datasets are ready to serve, 503 otherwise.
+pub async fn readyz(
+    State(probe): State<Arc<ProbeState>>,
+) -> (StatusCode, Json<ReadinessResponse>) {
+    let mut datasets = Vec::with_capacity(probe.datasets.len());
+    for (name, state) in &probe.datasets {
+        datasets.push(dataset_readiness(name, state, probe.require_assets).await);
+    }
+
+    let ready = datasets.iter().all(|d| d.ready);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(ReadinessResponse { ready, datasets }))
+}
+
+/// Check one dataset's metadata, ClickHouse connection and assets
+pub async fn dataset_readiness(name: &str, state: &AppState, require_assets: bool) -> DatasetReadiness {
+    let metadata_loaded = state.metadata_loaded.load(Ordering::Acquire);
+    let metadata_count = state.metadata.read().await.len();
+    let clickhouse_reachable = crate::clickhouse::client::health_check(&state.clickhouse)
+        .await
+        .is_ok();
+    let assets_discovered = state.assets.read().await.is_some();
+
+    let ready = metadata_loaded && clickhouse_reachable && (assets_discovered || !require_assets);
+
+    DatasetReadiness {
+        dataset: name.to_string(),
+        metadata_loaded,
+        metadata_count,
+        clickhouse_reachable,
+        assets_discovered,
+        ready,
+    }
+}
+
+/// Periodically check readiness and post webhooks when a dataset that has
+/// been ready stops being ready, or recovers
+pub fn spawn_health_monitor(probe: Arc<ProbeState>) {
+    tokio::spawn(async move {
+        let mut was_ready: HashMap<String, bool> = HashMap::new();
+        let interval = probe
+            .datasets
+            .iter()
+            .map(|(_, state)| state.config.webhooks.health_check_secs)
+            .min()
+            .unwrap_or(60)
+            .max(1);
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval));
+        loop {
+            ticker.tick().await;
+            for (name, state) in &probe.datasets {
+                let config = &state.config.webhooks;
+                if !config.enabled() {
+                    continue;
+                }
+                let readiness = dataset_readiness(name, state, probe.require_assets).await;
+                // Startup (not yet ready) is not a degradation
+                let event = match (was_ready.get(name), readiness.ready) {
+                    (Some(true), false) => Some(Event::HealthDegraded(readiness.clone())),
+                    (Some(false), true) => Some(Event::HealthRecovered(readiness.clone())),
+                    _ => None,
+                };
+                if readiness.ready || was_ready.contains_key(name) {
+                    was_ready.insert(name.clone(), readiness.ready);
+                }
+                if let Some(event) = event {
+                    info!("Dataset '{}': {}", name, event.name());
+                    webhooks::spawn(config, event);
+                }
+            }
+        }
+    });
+}
+
+/// Outcome of one check, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Degraded,
+    Down,
+}
+
+/// One upstream dependency
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    /// Whether a failure takes the dataset down (503) rather than degrading it
+    pub critical: bool,
+    pub latency_ms: f64,
+    pub detail: String,
+}
+
+/// Response body for `/api/health/detail`
+#[derive(Debug, Serialize)]
+pub struct HealthDetailResponse {
+    pub status: CheckStatus,
+    pub dataset: String,
+    pub checked_at: DateTime<Utc>,
+    pub checks: Vec<DependencyCheck>,
+}
+
+/// GET /api/health/detail
+///
+/// Checks every upstream concurrently. The overall status is the worst check,
+/// except that a non-critical failure only degrades it; the response is 503
+/// when it is `down`.
+pub async fn health_detail(
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<HealthDetailResponse>) {
+    let (clickhouse, gcs, gene_models, assets, tasks) = tokio::join!(
+        check("clickhouse", true, clickhouse_check(&state)),
+        check("gcs", false, gcs_check(&state)),
+        check("gene_models", false, gene_models_check(&state)),
+        check("asset_cache", false, asset_cache_check(&state)),
+        check("background_tasks", false, background_tasks_check(&state)),
+    );
+    let checks = vec![clickhouse, gcs, gene_models, assets, tasks];
+    let status = overall_status(&checks);
+    let code = if status == CheckStatus::Down {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    (
+        code,
+        Json(HealthDetailResponse {
+            status,
+            dataset: state.config.dataset_version.clone(),
+            checked_at: Utc::now(),
+            checks,
+        }),
+    )
+}
+
+/// Worst status, counting failed non-critical checks as degraded
+fn overall_status(checks: &[DependencyCheck]) -> CheckStatus {
+    checks
+        .iter()
+        .map(|c| match c.status {
+            CheckStatus::Down if !c.critical => CheckStatus::Degraded,
+            status => status,
+        })
+        .max()
+        .unwrap_or(CheckStatus::Ok)
+}
+
+/// Time a check, failing it as `down` when it exceeds [`CHECK_TIMEOUT`]
+async fn check(
+    name: &'static str,
+    critical: bool,
+    probe: impl Future<Output = (CheckStatus, String)>,
+) -> DependencyCheck {
+    let started = Instant::now();
+    let (status, detail) = tokio::time::timeout(CHECK_TIMEOUT, probe)
+        .await
+        .unwrap_or_else(|_| (CheckStatus::Down, format!("timed out after {:?}", CHECK_TIMEOUT)));
+    DependencyCheck {
+        name,
+        status,
+        critical,
+        latency_ms: started.elapsed().as_secs_f64() * 1000.0,
+        detail,
+    }
+}
+
+async fn clickhouse_check(state: &AppState) -> (CheckStatus, String) {
+    match crate::clickhouse::client::health_check(&state.clickhouse).await {
+        Ok(()) => (CheckStatus::Ok, "SELECT 1 succeeded".to_string()),
+        Err(e) => {
+            warn!("Health check: ClickHouse ping failed: {}", e);
+            (CheckStatus::Down, "SELECT 1 failed".to_string())
+        }
+    }
+}
+
+/// One shallow listing of the results prefix
+async fn gcs_check(state: &AppState) -> (CheckStatus, String) {
+    let bucket = &state.config.results_bucket;
+    let store = match GoogleCloudStorageBuilder::new().with_bucket_name(bucket).build() {
+        Ok(store) => store,
+        Err(e) => {
+            warn!("Health check: failed to create GCS client: {}", e);
+            return (CheckStatus::Down, "failed to create GCS client".to_string());
+        }
+    };
+    let prefix = ObjectPath::from(state.config.results_prefix());
+    match store.list_with_delimiter(Some(&prefix)).await {
+        Ok(listing) => (
+            CheckStatus::Ok,
+            format!(
+                "{} entries under the results prefix",
+                listing.common_prefixes.len() + listing.objects.len()
+            ),
+        ),
+        Err(e) => {
+            warn!("Health check: failed to list gs://{}/{}: {}", bucket, prefix, e);
+            (CheckStatus::Down, "failed to list the results prefix".to_string())
+        }
+    }
+}
+
+/// A missing table falls back to the (slow) Hail Table backend
+async fn gene_models_check(state: &AppState) -> (CheckStatus, String) {
+    let present = state
+        .clickhouse
+        .query(
+            "SELECT count() FROM system.tables \
+             WHERE database = currentDatabase() AND name = 'gene_models'",
+        )
+        .fetch_one_with::<u64>(&state.executor)
+        .await;
+    match present {
+        Ok(0) => (CheckStatus::Degraded, "gene_models table missing".to_string()),
+        Ok(_) => (CheckStatus::Ok, "gene_models table present".to_string()),
+        Err(e) => {
+            warn!("Health check: failed to look up gene_models: {}", e);
+            (CheckStatus::Down, "failed to look up gene_models".to_string())
+        }
+    }
+}
+
+/// Age of the discovered assets; they are loaded on first use
+async fn asset_cache_check(state: &AppState) -> (CheckStatus, String) {
+    let assets = state.assets.read().await;
+    let Some(assets) = assets.as_ref() else {
+        return (CheckStatus::Ok, "not loaded yet (loaded on first use)".to_string());
+    };
+    let age = assets
+        .generated_at
+        .as_deref()
+        .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+        .map(|at| Utc::now().signed_duration_since(at));
+    let detail = match age {
+        Some(age) => format!(
+            "{} assets, discovered {}h{:02}m ago",
+            assets.assets.len(),
+            age.num_hours(),
+            age.num_minutes() % 60
+        ),
+        None => format!("{} assets, discovery time unknown", assets.assets.len()),
+    };
+    (CheckStatus::Ok, detail)
+}
+
+/// Stuck admin tasks and a stalled table stats sampler
+async fn background_tasks_check(state: &AppState) -> (CheckStatus, String) {
+    let tasks = state.tasks.list().await;
+    let running: Vec<_> = tasks
+        .iter()
+        .filter(|t| t.status == crate::admin::tasks::TaskStatus::Running)
+        .collect();
+    let queued = tasks
+        .iter()
+        .filter(|t| t.status == crate::admin::tasks::TaskStatus::Queued)
+        .count();
+    let max_runtime = chrono::Duration::from_std(MAX_TASK_RUNTIME).unwrap_or_default();
+    let stuck = running
+        .iter()
+        .filter(|t| t.started_at.is_some_and(|at| Utc::now() - at > max_runtime))
+        .count();
+
+    let mut status = CheckStatus::Ok;
+    let mut detail = format!("{} running, {} queued admin tasks", running.len(), queued);
+    if stuck > 0 {
+        status = CheckStatus::Degraded;
+        detail.push_str(&format!("; {} running over {:?}", stuck, MAX_TASK_RUNTIME));
+    }
+    if let Some(at) = state.table_stats.snapshot().last_success {
+        let age = at.elapsed().unwrap_or_default();
+        detail.push_str(&format!("; table stats sampled {}s ago", age.as_secs()));
+        if age > MAX_TABLE_STATS_AGE {
+            status = CheckStatus::Degraded;
+        }
+    }
+    (status, detail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependency(status: CheckStatus, critical: bool) -> DependencyCheck {
+        DependencyCheck {
+            name: "test",
+            status,
+            critical,
+            latency_ms: 0.0,
+            detail: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_overall_status() {
+        assert_eq!(overall_status(&[]), CheckStatus::Ok);
+        assert_eq!(
+            overall_status(&[dependency(CheckStatus::Ok, true), dependency(CheckStatus::Down, false)]),
+            CheckStatus::Degraded
+        );
+        assert_eq!(
+            overall_status(&[dependency(CheckStatus::Down, true), dependency(CheckStatus::Degraded, false)]),
+            CheckStatus::Down
+        );
+    }
+}
@@ -0,0 +1,391 @@
+//! Minimal htsget-style access to summary statistics
+//!
+//! Genome browsers and workflow engines that speak htsget can fetch windows
+//! of a phenotype's complete GWAS results without custom client code:
+//!
+//! 1. `GET /api/htsget/variants/:analysis_id?referenceName=chr1&start=..&end=..`
+//!    returns an htsget ticket listing the URLs to fetch, a header block
+//!    followed by one body block per [`BLOCK_SIZE`] window of the region.
+//! 2. Each block URL (`.../:analysis_id/data?...`) streams tab-separated rows
+//!    (GWAS-SSF column names) read from the phenotype's variant results Hail
+//!    Table; concatenating the blocks in order yields one TSV file.
+//!
+//! Coordinates follow htsget: `start` is 0-based inclusive and `end` is
+//! exclusive; without them the whole chromosome is returned. Only the `TSV`
+//! format is offered, and errors use the htsget error body.
+//!
+//! Block URLs are absolute, as htsget requires. They start with the
+//! configured `public_url`; without one they are built from the request's
+//! `Host` and the ticket is sent `no-store`, so a shared cache never hands
+//! one client's host to another.
+
+use crate::api::AppState;
+use crate::error::AppError;
+use crate::genomics::Contig;
+use axum::{
+    body::{Body, Bytes},
+    extract::{OriginalUri, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::stream;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::sync::Arc;
+
+/// Genomic span of one body block
+pub const BLOCK_SIZE: u32 = 5_000_000;
+
+/// Rows per chunk when streaming a block
+const ROWS_PER_CHUNK: usize = 10_000;
+
+/// Media type of htsget tickets
+const TICKET_MIME: &str = "application/vnd.ga4gh.htsget.v1.3.0+json; charset=utf-8";
+
+/// Column header of the TSV blocks
+const TSV_HEADER: &str = "chromosome\tbase_pair_location\tother_allele\teffect_allele\t\
+                          p_value\tbeta\tstandard_error\teffect_allele_frequency\tvariant_id\n";
+
+/// Query parameters shared by the ticket and data endpoints
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HtsgetQuery {
+    /// Chromosome, e.g. "chr1" or "1"
+    pub reference_name: Option<String>,
+    /// 0-based inclusive start
+    pub start: Option<u32>,
+    /// 0-based exclusive end
+    pub end: Option<u32>,
+    /// Only "TSV" is supported
+    pub format: Option<String>,
+    /// Ancestry group (default "meta")
+    pub ancestry: Option<String>,
+    /// "genome" (default) or "exome"
+    pub sequencing_type: Option<String>,
+    /// "header" for the header block (data endpoint only)
+    pub class: Option<String>,
+}
+
+/// Error answered in the htsget error format
+pub struct HtsgetError(AppError);
+
+impl From<AppError> for HtsgetError {
+    fn from(e: AppError) -> Self {
+        HtsgetError(e)
+    }
+}
+
+impl IntoResponse for HtsgetError {
+    fn into_response(self) -> Response {
+        let status = self.0.status();
+        let error = match status {
+            StatusCode::BAD_REQUEST => "InvalidInput",
+            StatusCode::NOT_FOUND => "NotFound",
+            StatusCode::UNAUTHORIZED => "InvalidAuthentication",
+            StatusCode::FORBIDDEN => "PermissionDenied",
+            _ => "InternalError",
+        };
+        let body = serde_json::json!({
+            "htsget": { "error": error, "message": self.0.to_string() }
+        });
+        (status, Json(body)).into_response()
+    }
+}
+
+/// Validated request region
+#[derive(Debug, Clone, PartialEq)]
+struct Region {
+    contig: Contig,
+    start: u32,
+    end: u32,
+    ancestry: String,
+    sequencing_type: String,
+}
+
+impl Region {
+    fn from_query(query: &HtsgetQuery) -> Result<Self, AppError> {
+        if let Some(format) = &query.format {
+            if !format.eq_ignore_ascii_case("TSV") {
+                return Err(AppError::BadRequest(format!(
+                    "Unsupported format '{}'; only TSV is available",
+                    format
+                )));
+            }
+        }
+        let name = query
+            .reference_name
+            .as_deref()
+            .ok_or_else(|| AppError::BadRequest("referenceName is required".to_string()))?;
+        let contig: Contig = name.parse()?;
+        let start = query.start.unwrap_or(0);
+        let end = query.end.unwrap_or(contig.length()).min(contig.length());
+        if start >= end {
+            return Err(AppError::BadRequest(format!(
+                "start ({}) must be less than end ({})",
+                start, end
+            )));
+        }
+        let sequencing_type = query.sequencing_type.as_deref().unwrap_or("genome");
+        if !matches!(sequencing_type, "genome" | "exome") {
+            return Err(AppError::BadRequest(format!(
+                "Invalid sequencing_type '{}'. Expected genome or exome",
+                sequencing_type
+            )));
+        }
+        let ancestry = crate::phenotype::manhattan::ancestry_param(query.ancestry.as_deref())?;
+        Ok(Region {
+            contig,
+            start,
+            end,
+            ancestry: ancestry.to_string(),
+            sequencing_type: sequencing_type.to_string(),
+        })
+    }
+
+    /// Body blocks as (start, end) windows of at most [`BLOCK_SIZE`]
+    fn blocks(&self) -> Vec<(u32, u32)> {
+        (self.start..self.end)
+            .step_by(BLOCK_SIZE as usize)
+            .map(|start| (start, start.saturating_add(BLOCK_SIZE).min(self.end)))
+            .collect()
+    }
+}
+
+/// htsget ticket
+#[derive(Debug, Serialize)]
+pub struct Ticket {
+    pub htsget: TicketBody,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TicketBody {
+    pub format: String,
+    pub urls: Vec<TicketUrl>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TicketUrl {
+    pub url: String,
+    pub class: String,
+}
+
+/// GET /api/htsget/variants/:analysis_id
+///
+/// htsget ticket for a region of a phenotype's summary statistics.
+pub async fn get_ticket(
+    State(state): State<Arc<AppState>>,
+    Path(analysis_id): Path<String>,
+    Query(query): Query<HtsgetQuery>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> Result<Response, HtsgetError> {
+    let region = Region::from_query(&query)?;
+    let configured = state.config.public_url.as_deref().map(|url| url.trim_end_matches('/'));
+    let base = configured.map_or_else(|| request_origin(&headers), str::to_string);
+    let data_url = format!("{}{}/data", base, uri.path().trim_end_matches('/'));
+    let ticket = ticket(&region, &data_url);
+    tracing::debug!("htsget ticket for {}: {} blocks", analysis_id, ticket.htsget.urls.len() - 1);
+
+    let mut response = ([(header::CONTENT_TYPE, TICKET_MIME)], Json(ticket)).into_response();
+    if configured.is_none() {
+        response
+            .headers_mut()
+            .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    }
+    Ok(response)
+}
+
+/// Ticket listing the header block and the body blocks of `region`
+fn ticket(region: &Region, data_url: &str) -> Ticket {
+    let block_url = |start: u32, end: u32, class: &str| {
+        let mut params = url::form_urlencoded::Serializer::new(String::new());
+        params
+            .append_pair("referenceName", region.contig.chr())
+            .append_pair("start", &start.to_string())
+            .append_pair("end", &end.to_string())
+            .append_pair("ancestry", &region.ancestry)
+            .append_pair("sequencing_type", &region.sequencing_type);
+        if class == "header" {
+            params.append_pair("class", "header");
+        }
+        TicketUrl {
+            url: format!("{}?{}", data_url, params.finish()),
+            class: class.to_string(),
+        }
+    };
+
+    let mut urls = vec![block_url(region.start, region.end, "header")];
+    urls.extend(region.blocks().into_iter().map(|(start, end)| block_url(start, end, "body")));
+    Ticket {
+        htsget: TicketBody {
+            format: "TSV".to_string(),
+            urls,
+        },
+    }
+}
+
+/// GET /api/htsget/variants/:analysis_id/data
+///
+/// One block of a ticket: the TSV header (`class=header`) or the rows of a
+/// window, streamed in chunks.
+pub async fn get_block(
+    State(state): State<Arc<AppState>>,
+    Path(analysis_id): Path<String>,
+    Query(query): Query<HtsgetQuery>,
+) -> Result<Response, HtsgetError> {
+    let region = Region::from_query(&query)?;
+    let tsv = |body: Body| {
+        ([(header::CONTENT_TYPE, "text/tab-separated-values; charset=utf-8")], body).into_response()
+    };
+    if query.class.as_deref() == Some("header") {
+        return Ok(tsv(Body::from(TSV_HEADER)));
+    }
+    if region.end - region.start > BLOCK_SIZE {
+        return Err(AppError::BadRequest(format!(
+            "Blocks span at most {} bp; request a ticket for larger regions",
+            BLOCK_SIZE
+        ))
+        .into());
+    }
+
+    let ht_path = state
+        .config
+        .variant_results_uri(&region.ancestry, &analysis_id, &region.sequencing_type);
+    // htsget is 0-based half-open; Hail loci are 1-based inclusive
+    let associations = state
+        .hail_client
+        .query_interval_typed(
+            &ht_path,
+            region.contig.chr(),
+            region.start as i32 + 1,
+            region.end as i32,
+        )
+        .await
+        .map_err(|e| AppError::Internal(format!("Hail query error: {}", e)))?;
+
+    let lines: Vec<String> = associations
+        .iter()
+        .map(|a| {
+            format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                a.contig,
+                a.position,
+                a.ref_allele,
+                a.alt_allele,
+                a.pvalue,
+                a.beta,
+                a.se,
+                a.af.map_or("NA".to_string(), |af| af.to_string()),
+                a.variant_id()
+            )
+        })
+        .collect();
+    let chunks: Vec<Result<Bytes, Infallible>> = lines
+        .chunks(ROWS_PER_CHUNK)
+        .map(|chunk| Ok(Bytes::from(chunk.concat())))
+        .collect();
+    Ok(tsv(Body::from_stream(stream::iter(chunks))))
+}
+
+/// Origin from the request's `Host` header, when no `public_url` is configured
+fn request_origin(headers: &HeaderMap) -> String {
+    let host = headers
+        .get(header::HOST)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("localhost");
+    format!("http://{}", host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(reference_name: &str, start: Option<u32>, end: Option<u32>) -> HtsgetQuery {
+        HtsgetQuery {
+            reference_name: Some(reference_name.to_string()),
+            start,
+            end,
+            format: None,
+            ancestry: None,
+            sequencing_type: None,
+            class: None,
+        }
+    }
+
+    #[test]
+    fn test_region_blocks() {
+        let region = Region::from_query(&query("1", Some(1_000_000), Some(12_000_000))).unwrap();
+        assert_eq!(region.contig.chr(), "chr1");
+        assert_eq!(region.ancestry, "meta");
+        assert_eq!(
+            region.blocks(),
+            vec![
+                (1_000_000, 6_000_000),
+                (6_000_000, 11_000_000),
+                (11_000_000, 12_000_000)
+            ]
+        );
+
+        // Whole chromosome when start/end are omitted
+        let whole = Region::from_query(&query("chrY", None, None)).unwrap();
+        assert_eq!(whole.end, whole.contig.length());
+    }
+
+    #[test]
+    fn test_region_validation() {
+        assert!(Region::from_query(&query("chr1", Some(10), Some(10))).is_err());
+        assert!(Region::from_query(&query("chrZ", None, None)).is_err());
+        let mut bam = query("chr1", None, None);
+        bam.format = Some("BAM".to_string());
+        assert!(Region::from_query(&bam).is_err());
+        assert!(Region::from_query(&HtsgetQuery {
+            reference_name: None,
+            ..query("chr1", None, None)
+        })
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ticket() {
+        let mut config = crate::config::Config::default();
+        config.public_url = Some("https://browser.example.org/".to_string());
+        let state = crate::build_state(config, None, Default::default());
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, "attacker.example".parse().unwrap());
+        let ticket_response = |state, headers| {
+            get_ticket(
+                State(state),
+                Path("height".to_string()),
+                Query(query("chr2", Some(0), Some(7_000_000))),
+                OriginalUri("/api/htsget/variants/height?referenceName=chr2".parse().unwrap()),
+                headers,
+            )
+        };
+
+        let response = ticket_response(Arc::clone(&state), headers.clone()).await.ok().unwrap();
+        assert!(response.headers().get(header::CACHE_CONTROL).is_none());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let ticket: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let urls = ticket["htsget"]["urls"].as_array().unwrap();
+        assert_eq!(urls.len(), 3);
+        assert_eq!(urls[0]["class"], "header");
+        assert_eq!(urls[2]["class"], "body");
+        assert_eq!(
+            urls[1]["url"],
+            "https://browser.example.org/api/htsget/variants/height/data?referenceName=chr2\
+             &start=0&end=5000000&ancestry=meta&sequencing_type=genome"
+        );
+
+        // Without a public URL the ticket names the request host and is not cached
+        let state = crate::build_state(crate::config::Config::default(), None, Default::default());
+        let response = ticket_response(state, headers).await.ok().unwrap();
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let ticket: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(ticket["htsget"]["urls"][0]["url"]
+            .as_str()
+            .unwrap()
+            .starts_with("http://attacker.example/api/htsget/variants/height/data?"));
+    }
+}
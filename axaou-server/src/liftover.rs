@@ -0,0 +1,457 @@
+//! Variant liftover between GRCh37 and GRCh38
+//!
+//! Coordinates are mapped through UCSC chain files (`hg38ToHg19.over.chain.gz`,
+//! `hg19ToHg38.over.chain.gz`), configured in the `[liftover]` section:
+//!
+//! ```toml
+//! [liftover]
+//! grch38_to_grch37 = "/data/chains/hg38ToHg19.over.chain.gz"
+//! grch37_to_grch38 = "/data/chains/hg19ToHg38.over.chain.gz"
+//! ```
+//!
+//! A variant lifts only if its whole reference allele falls inside one
+//! ungapped chain block. On a reverse-strand block the alleles are
+//! reverse-complemented; indels are not re-normalized.
+
+use crate::error::AppError;
+use crate::genomics::Contig;
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use utoipa::{IntoParams, ToSchema};
+
+/// `[liftover]` config section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct LiftoverConfig {
+    /// Chain file mapping GRCh38 to GRCh37 (plain or gzipped)
+    pub grch38_to_grch37: Option<PathBuf>,
+    /// Chain file mapping GRCh37 to GRCh38 (plain or gzipped)
+    pub grch37_to_grch38: Option<PathBuf>,
+}
+
+/// `build=` parameter accepted by variant lookups
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BuildQuery {
+    /// Build of the variant ID: "GRCh38" (default) or "GRCh37"/"hg19" to lift first
+    pub build: Option<String>,
+}
+
+/// Reference genome build
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub enum Build {
+    GRCh37,
+    GRCh38,
+}
+
+impl Build {
+    /// Parse "GRCh37", "hg19", "37", "GRCh38", "hg38", "38" (case-insensitive)
+    pub fn parse(name: &str) -> Result<Self, AppError> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "grch37" | "hg19" | "37" => Ok(Self::GRCh37),
+            "grch38" | "hg38" | "38" => Ok(Self::GRCh38),
+            _ => Err(AppError::BadRequest(format!(
+                "Unknown genome build '{}'. Expected GRCh37 or GRCh38",
+                name
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Build {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GRCh37 => f.write_str("GRCh37"),
+            Self::GRCh38 => f.write_str("GRCh38"),
+        }
+    }
+}
+
+/// One ungapped alignment block, in 0-based half-open source coordinates
+#[derive(Debug, Clone)]
+struct Block {
+    start: u64,
+    end: u64,
+    /// Index into `ChainMap::target_contigs`
+    target: usize,
+    /// Start of the block on the target strand given by `reverse`
+    target_start: u64,
+    target_size: u64,
+    reverse: bool,
+}
+
+/// Parsed chain file: source contig -> blocks sorted by start
+#[derive(Debug, Default)]
+pub struct ChainMap {
+    blocks: HashMap<String, Vec<Block>>,
+    target_contigs: Vec<String>,
+}
+
+/// A mapped 0-based half-open interval on the target build
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappedInterval {
+    pub contig: String,
+    pub start: u64,
+    pub end: u64,
+    pub reverse: bool,
+}
+
+impl ChainMap {
+    /// Load a chain file, decompressing `.gz` files
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+        let is_gzip = path.extension().is_some_and(|ext| ext == "gz");
+        if is_gzip {
+            Self::parse(BufReader::new(flate2::read::MultiGzDecoder::new(file)))
+        } else {
+            Self::parse(BufReader::new(file))
+        }
+        .with_context(|| format!("Failed to parse chain file {:?}", path))
+    }
+
+    /// Parse UCSC chain format
+    pub fn parse(reader: impl BufRead) -> anyhow::Result<Self> {
+        let mut map = Self::default();
+        let mut target_index: HashMap<String, usize> = HashMap::new();
+        // (source contig, source position, target index, target position, target size, reverse)
+        let mut current: Option<(String, u64, usize, u64, u64, bool)> = None;
+
+        for (line_no, line) in reader.lines().enumerate() {
+            let line = line?;
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.is_empty() {
+                continue;
+            }
+
+            if fields[0] == "chain" {
+                // chain score tName tSize tStrand tStart tEnd qName qSize qStrand qStart qEnd id
+                if fields.len() < 12 {
+                    bail!("Line {}: malformed chain header", line_no + 1);
+                }
+                let target = fields[7].to_string();
+                let next_index = target_index.len();
+                let index = *target_index.entry(target.clone()).or_insert(next_index);
+                if index == map.target_contigs.len() {
+                    map.target_contigs.push(target);
+                }
+                current = Some((
+                    fields[2].to_string(),
+                    fields[5].parse()?,
+                    index,
+                    fields[10].parse()?,
+                    fields[8].parse()?,
+                    fields[9] == "-",
+                ));
+                continue;
+            }
+
+            let Some((source, source_pos, target, target_pos, target_size, reverse)) =
+                current.as_mut()
+            else {
+                bail!("Line {}: alignment data before chain header", line_no + 1);
+            };
+            let size: u64 = fields[0].parse()?;
+            map.blocks.entry(source.clone()).or_default().push(Block {
+                start: *source_pos,
+                end: *source_pos + size,
+                target: *target,
+                target_start: *target_pos,
+                target_size: *target_size,
+                reverse: *reverse,
+            });
+            if fields.len() >= 3 {
+                let source_gap: u64 = fields[1].parse()?;
+                let target_gap: u64 = fields[2].parse()?;
+                *source_pos += size + source_gap;
+                *target_pos += size + target_gap;
+            } else {
+                // Last block of the chain
+                current = None;
+            }
+        }
+
+        for blocks in map.blocks.values_mut() {
+            blocks.sort_by_key(|b| b.start);
+        }
+        Ok(map)
+    }
+
+    /// Map a 0-based half-open source interval that lies within one block
+    pub fn map_interval(&self, contig: &str, start: u64, end: u64) -> Option<MappedInterval> {
+        let blocks = self.blocks.get(contig)?;
+        let i = blocks.partition_point(|b| b.start <= start);
+        let block = &blocks[i.checked_sub(1)?];
+        if end > block.end {
+            return None;
+        }
+
+        let offset = start - block.start;
+        let strand_start = block.target_start + offset;
+        let strand_end = strand_start + (end - start);
+        let (start, end) = if block.reverse {
+            (
+                block.target_size - strand_end,
+                block.target_size - strand_start,
+            )
+        } else {
+            (strand_start, strand_end)
+        };
+        Some(MappedInterval {
+            contig: self.target_contigs[block.target].clone(),
+            start,
+            end,
+            reverse: block.reverse,
+        })
+    }
+}
+
+/// A variant lifted to another build
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct LiftedVariant {
+    pub contig: String,
+    pub position: u32,
+    pub ref_allele: String,
+    pub alt_allele: String,
+    /// True when the variant mapped to the reverse strand
+    pub reverse_strand: bool,
+}
+
+impl LiftedVariant {
+    /// Variant ID in the browser's "chr1-12345-A-T" form
+    pub fn variant_id(&self) -> String {
+        crate::clickhouse::xpos::make_variant_id(
+            &self.contig,
+            self.position,
+            &self.ref_allele,
+            &self.alt_allele,
+        )
+    }
+}
+
+fn reverse_complement(allele: &str) -> String {
+    allele
+        .chars()
+        .rev()
+        .map(|c| match c {
+            'A' => 'T',
+            'T' => 'A',
+            'C' => 'G',
+            'G' => 'C',
+            'a' => 't',
+            't' => 'a',
+            'c' => 'g',
+            'g' => 'c',
+            other => other,
+        })
+        .collect()
+}
+
+/// Chain maps for each configured direction
+#[derive(Debug, Default)]
+pub struct Liftover {
+    grch38_to_grch37: Option<ChainMap>,
+    grch37_to_grch38: Option<ChainMap>,
+}
+
+impl Liftover {
+    /// Load the configured chain files; a missing or unreadable file disables
+    /// that direction with a warning rather than failing startup
+    pub fn load(config: &LiftoverConfig) -> Self {
+        let load = |path: &Option<PathBuf>| {
+            let path = path.as_ref()?;
+            match ChainMap::load(path) {
+                Ok(map) => {
+                    info!("Loaded liftover chains from {:?}", path);
+                    Some(map)
+                }
+                Err(e) => {
+                    warn!("Liftover disabled for {:?}: {:#}", path, e);
+                    None
+                }
+            }
+        };
+        Self {
+            grch38_to_grch37: load(&config.grch38_to_grch37),
+            grch37_to_grch38: load(&config.grch37_to_grch38),
+        }
+    }
+
+    fn chains(&self, from: Build, to: Build) -> Result<&ChainMap, AppError> {
+        let chains = match (from, to) {
+            (Build::GRCh38, Build::GRCh37) => self.grch38_to_grch37.as_ref(),
+            (Build::GRCh37, Build::GRCh38) => self.grch37_to_grch38.as_ref(),
+            _ => None,
+        };
+        chains.ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "Liftover from {} to {} is not configured",
+                from, to
+            ))
+        })
+    }
+
+    /// Lift a variant ID ("chr1-12345-A-T" or "1-12345-A-T") between builds
+    pub fn lift_variant(
+        &self,
+        variant_id: &str,
+        from: Build,
+        to: Build,
+    ) -> Result<LiftedVariant, AppError> {
+        let invalid = || {
+            AppError::InvalidInterval(format!(
+                "Invalid variant ID format '{}'. Expected chr-pos-ref-alt",
+                variant_id
+            ))
+        };
+        let parts: Vec<&str> = variant_id.split('-').collect();
+        let &[contig, position, ref_allele, alt_allele] = parts.as_slice() else {
+            return Err(invalid());
+        };
+        let contig: Contig = contig.parse()?;
+        let position: u64 = position.parse().map_err(|_| invalid())?;
+        if position == 0 || ref_allele.is_empty() {
+            return Err(invalid());
+        }
+
+        if from == to {
+            return Ok(LiftedVariant {
+                contig: contig.chr().to_string(),
+                position: position as u32,
+                ref_allele: ref_allele.to_string(),
+                alt_allele: alt_allele.to_string(),
+                reverse_strand: false,
+            });
+        }
+
+        let start = position - 1;
+        let end = start + ref_allele.len() as u64;
+        let mapped = self
+            .chains(from, to)?
+            .map_interval(contig.chr(), start, end)
+            .ok_or_else(|| {
+                AppError::NotFound(format!(
+                    "Variant {} does not lift over from {} to {}",
+                    variant_id, from, to
+                ))
+            })?;
+
+        let (ref_allele, alt_allele) = if mapped.reverse {
+            (
+                reverse_complement(ref_allele),
+                reverse_complement(alt_allele),
+            )
+        } else {
+            (ref_allele.to_string(), alt_allele.to_string())
+        };
+        Ok(LiftedVariant {
+            contig: Contig::parse(&mapped.contig).map_or(mapped.contig, |c| c.chr().to_string()),
+            position: (mapped.start + 1) as u32,
+            ref_allele,
+            alt_allele,
+            reverse_strand: mapped.reverse,
+        })
+    }
+
+    /// Resolve a variant ID given in `build` (default GRCh38) to the GRCh38 ID
+    /// the tables are keyed by
+    pub fn to_grch38_variant_id(
+        &self,
+        variant_id: &str,
+        build: Option<&str>,
+    ) -> Result<String, AppError> {
+        match build.map(Build::parse).transpose()? {
+            None | Some(Build::GRCh38) => Ok(variant_id.to_string()),
+            Some(Build::GRCh37) => Ok(self
+                .lift_variant(variant_id, Build::GRCh37, Build::GRCh38)?
+                .variant_id()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two chains: chr1 forward with a gap, chr2 onto the reverse strand of chr2
+    const CHAINS: &str = "\
+chain 1000 chr1 1000 + 100 300 chr1 1000 + 200 410 1
+50 20 30
+130
+
+chain 500 chr2 1000 + 0 100 chr2 1000 - 0 100 2
+100
+";
+
+    fn liftover() -> Liftover {
+        Liftover {
+            grch38_to_grch37: Some(ChainMap::parse(CHAINS.as_bytes()).unwrap()),
+            grch37_to_grch38: None,
+        }
+    }
+
+    #[test]
+    fn test_map_interval() {
+        let map = ChainMap::parse(CHAINS.as_bytes()).unwrap();
+        // First block: [100, 150) -> [200, 250)
+        assert_eq!(map.map_interval("chr1", 100, 101).unwrap().start, 200);
+        // Second block: [170, 300) -> [280, 410)
+        assert_eq!(map.map_interval("chr1", 170, 171).unwrap().start, 280);
+        // Gap between blocks and spans across a gap do not map
+        assert!(map.map_interval("chr1", 160, 161).is_none());
+        assert!(map.map_interval("chr1", 149, 151).is_none());
+        assert!(map.map_interval("chr1", 50, 51).is_none());
+        assert!(map.map_interval("chr3", 100, 101).is_none());
+
+        let mapped = map.map_interval("chr2", 10, 12).unwrap();
+        assert_eq!((mapped.start, mapped.end, mapped.reverse), (988, 990, true));
+    }
+
+    #[test]
+    fn test_lift_variant() {
+        let liftover = liftover();
+        let lifted = liftover
+            .lift_variant("1-101-A-G", Build::GRCh38, Build::GRCh37)
+            .unwrap();
+        assert_eq!(lifted.variant_id(), "chr1-201-A-G");
+
+        let lifted = liftover
+            .lift_variant("chr2-11-AC-T", Build::GRCh38, Build::GRCh37)
+            .unwrap();
+        assert_eq!(lifted.variant_id(), "chr2-989-GT-A");
+        assert!(lifted.reverse_strand);
+
+        assert!(matches!(
+            liftover.lift_variant("chr1-161-A-G", Build::GRCh38, Build::GRCh37),
+            Err(AppError::NotFound(_))
+        ));
+        assert!(matches!(
+            liftover.lift_variant("chr1-101-A-G", Build::GRCh37, Build::GRCh38),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_to_grch38_variant_id() {
+        let liftover = liftover();
+        assert_eq!(
+            liftover.to_grch38_variant_id("chr1-101-A-G", None).unwrap(),
+            "chr1-101-A-G"
+        );
+        assert_eq!(
+            liftover
+                .to_grch38_variant_id("chr1-101-A-G", Some("hg38"))
+                .unwrap(),
+            "chr1-101-A-G"
+        );
+        assert!(liftover
+            .to_grch38_variant_id("chr1-101-A-G", Some("hg19"))
+            .is_err());
+        assert!(Build::parse("hg18").is_err());
+    }
+}
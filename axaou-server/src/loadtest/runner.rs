@@ -574,7 +574,7 @@ async fn fetch_gcp_metrics(
 // Rolling Metrics (for abort decisions + live summary)
 // ---------------------------------------------------------------------------
 
-fn percentile(sorted: &[u64], p: f64) -> u64 {
+pub(crate) fn percentile(sorted: &[u64], p: f64) -> u64 {
     if sorted.is_empty() {
         return 0;
     }
@@ -8,26 +8,54 @@
 //! - `serve` - Run the HTTP server
 //! - `discover` - Discover analysis assets from GCS and save to JSON
 //! - `analyze` - Analyze/summarize discovered assets
+//! - `assets diff` - Compare two assets manifests
 
 mod admin;
 mod analysis_assets;
+mod analysis_search;
 mod api;
+mod cache_control;
 mod cli;
 mod clickhouse;
+mod concurrency;
+mod config;
+mod correlations;
 mod data;
+mod datasets;
+mod downloads;
 mod error;
+mod etag;
+mod export;
+mod fixtures;
 mod gene_models;
 mod gene_queries;
+mod genomics;
 mod genes;
+mod health;
+mod htsget;
+mod liftover;
 mod loadtest;
+mod metadata;
 mod models;
+mod openapi;
 mod phenotype;
 mod phenotype_display_names;
+mod rate_limit;
 mod response;
+mod single_flight;
+mod table_metrics;
+#[cfg(test)]
+mod test_support;
 mod variants;
+mod version;
+mod webhooks;
 
 use api::AppState;
-use axum::{routing::get, Router};
+use crate::clickhouse::QueryExt;
+use axum::{
+    routing::{get, MethodRouter},
+    Router,
+};
 use clap::{Parser, Subcommand};
 use models::AnalysisAssets;
 use std::{net::SocketAddr, path::PathBuf, sync::Arc};
@@ -35,6 +63,8 @@ use tokio::sync::RwLock;
 use tower_http::compression::CompressionLayer;
 use tower_http::cors::{Any, CorsLayer};
 use tracing::info;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 #[derive(Parser)]
 #[command(name = "axaou-server")]
@@ -55,6 +85,93 @@ enum Commands {
         /// Path to pre-computed assets JSON (optional, will discover on-demand if not provided)
         #[arg(long)]
         assets_file: Option<PathBuf>,
+
+        /// Path to dataset config TOML (buckets, prefixes); defaults to $AXAOU_CONFIG or built-in 414k paths
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// Path to a dataset registry TOML to serve several data freezes under /api/v/:dataset
+        /// (takes precedence over --config)
+        #[arg(long)]
+        datasets: Option<PathBuf>,
+
+        /// Sustained requests/second allowed per client IP (0 disables rate limiting)
+        #[arg(long, default_value = "0")]
+        rate_limit_rps: f64,
+
+        /// Burst size (bucket capacity) per client IP
+        #[arg(long, default_value = "60")]
+        rate_limit_burst: u32,
+
+        /// Sustained requests/second for clients presenting a known API key (x-api-key header)
+        #[arg(long, default_value = "50")]
+        rate_limit_key_rps: f64,
+
+        /// Burst size for clients presenting a known API key
+        #[arg(long, default_value = "500")]
+        rate_limit_key_burst: u32,
+
+        /// Comma-separated API keys granted the per-key quota
+        #[arg(long, value_delimiter = ',')]
+        api_keys: Vec<String>,
+
+        /// Proxies in front of the server that append to X-Forwarded-For (1 on
+        /// Cloud Run); rate limiting keys on the peer address when 0
+        #[arg(long, default_value = "0")]
+        trusted_proxy_hops: usize,
+
+        /// Maximum ClickHouse-backed requests in flight; more are shed with 503 (0 = unlimited)
+        #[arg(long, default_value = "64")]
+        max_concurrent_queries: usize,
+
+        /// Maximum plot image and download proxy requests in flight (0 = unlimited)
+        #[arg(long, default_value = "16")]
+        max_concurrent_images: usize,
+
+        /// Maximum Hail-backed requests (query_mode=slow, region renders) in flight (0 = unlimited)
+        #[arg(long, default_value = "4")]
+        max_concurrent_hail: usize,
+
+        /// Whole-table tar downloads allowed per client and hour (0 = unlimited)
+        #[arg(long, default_value = "20")]
+        download_rate_per_hour: u32,
+
+        /// Tar downloads a client may start back to back
+        #[arg(long, default_value = "3")]
+        download_burst: u32,
+
+        /// Load metadata and warm the API cache before binding the port
+        /// (otherwise this runs in the background after startup)
+        #[arg(long)]
+        warm_cache: bool,
+
+        /// Require asset discovery to have completed before /readyz reports ready
+        #[arg(long)]
+        readiness_requires_assets: bool,
+
+        /// Start serving before analysis metadata has loaded: it is loaded in
+        /// the background with retries, and routes that need it return 503
+        /// until then (implies background cache warming)
+        #[arg(long)]
+        lazy_metadata: bool,
+
+        /// Re-run asset discovery every N seconds and swap in new results (0 disables)
+        #[arg(long, default_value = "0")]
+        rediscover_interval_secs: u64,
+
+        /// Sample ClickHouse table row counts and modification times for
+        /// /metrics every N seconds (0 disables)
+        #[arg(long, default_value = "300")]
+        table_stats_interval_secs: u64,
+
+        /// Gene model backend: ClickHouse, the Hail Table, or ClickHouse with Hail fallback
+        #[arg(long, value_enum, default_value_t = gene_models::GeneModelsBackendKind::Auto)]
+        gene_models_backend: gene_models::GeneModelsBackendKind,
+
+        /// Serve canned responses from this fixture directory instead of GCS and
+        /// ClickHouse (local frontend development and e2e tests)
+        #[arg(long)]
+        fixtures: Option<PathBuf>,
     },
 
     /// Discover analysis assets from GCS and save to JSON
@@ -66,6 +183,32 @@ enum Commands {
         /// Filter by metadata (only discover assets for known phenotypes)
         #[arg(long, default_value = "true")]
         filter_by_metadata: bool,
+
+        /// Path to dataset config TOML (buckets, prefixes); defaults to $AXAOU_CONFIG or built-in 414k paths
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// Previous assets JSON to compare against; prefixes that changed since then are reported
+        #[arg(long)]
+        previous: Option<PathBuf>,
+
+        /// Write the added/removed change report (incremental mode) to this JSON file
+        #[arg(long, requires = "previous")]
+        report: Option<PathBuf>,
+
+        /// Also replace the `analysis_assets` ClickHouse table, which the server
+        /// loads at startup instead of scanning GCS
+        #[arg(long)]
+        to_clickhouse: bool,
+
+        /// ClickHouse URL (with --to-clickhouse)
+        #[arg(long, default_value = "http://localhost:8123")]
+        clickhouse_url: String,
+
+        /// ClickHouse database (with --to-clickhouse; default: the config's
+        /// clickhouse_database, then "default")
+        #[arg(long)]
+        database: Option<String>,
     },
 
     /// Analyze/summarize discovered assets
@@ -114,6 +257,12 @@ enum Commands {
         sample: Option<f64>,
     },
 
+    /// Inspect and compare assets manifests
+    Assets {
+        #[command(subcommand)]
+        command: cli::AssetsCommand,
+    },
+
     /// Load data into ClickHouse from Hail Tables
     Ingest {
         #[command(subcommand)]
@@ -126,12 +275,30 @@ enum Commands {
         command: cli::DeriveCommand,
     },
 
+    /// Export results for external tools (UCSC/IGV track hubs)
+    Export {
+        #[command(subcommand)]
+        command: cli::ExportCommand,
+    },
+
+    /// Apply versioned ClickHouse schema migrations
+    Migrate {
+        #[command(subcommand)]
+        command: cli::MigrateCommand,
+    },
+
     /// Run load tests against a running server instance
     LoadTest {
         /// Path to the loadtest TOML configuration file
         #[arg(long)]
         config: PathBuf,
     },
+
+    /// Replay a recorded query mix against a running server and report latencies
+    Bench(cli::bench::BenchArgs),
+
+    /// Write per-phenotype and per-gene JSON snapshots and a sitemap.xml
+    ExportStatic(cli::export_static::ExportStaticArgs),
 }
 
 #[tokio::main]
@@ -152,14 +319,98 @@ async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Serve { port, assets_file } => {
-            run_server(port, assets_file).await?;
+        Commands::Serve {
+            port,
+            assets_file,
+            config,
+            datasets,
+            rate_limit_rps,
+            rate_limit_burst,
+            rate_limit_key_rps,
+            rate_limit_key_burst,
+            api_keys,
+            trusted_proxy_hops,
+            max_concurrent_queries,
+            max_concurrent_images,
+            max_concurrent_hail,
+            download_rate_per_hour,
+            download_burst,
+            warm_cache,
+            readiness_requires_assets,
+            lazy_metadata,
+            rediscover_interval_secs,
+            table_stats_interval_secs,
+            gene_models_backend,
+            fixtures,
+        } => {
+            if let Some(dir) = fixtures {
+                return fixtures::run_fixture_server(port, dir).await;
+            }
+            let registry = match datasets {
+                Some(path) => datasets::DatasetRegistry::load(&path)?,
+                None => datasets::DatasetRegistry::single(config::Config::load(config.as_deref())?),
+            };
+            let rate_limit = (rate_limit_rps > 0.0).then(|| rate_limit::RateLimitConfig {
+                ip_quota: rate_limit::Quota {
+                    per_second: rate_limit_rps,
+                    burst: rate_limit_burst.max(1) as f64,
+                },
+                key_quota: rate_limit::Quota {
+                    per_second: rate_limit_key_rps.max(rate_limit_rps),
+                    burst: rate_limit_key_burst.max(1) as f64,
+                },
+                api_keys: api_keys.iter().cloned().collect(),
+                trusted_proxy_hops,
+            });
+            // Tar downloads stream whole tables from GCS, so they get their
+            // own quota even when the general rate limit is off
+            let download_quota = rate_limit::Quota {
+                per_second: download_rate_per_hour as f64 / 3600.0,
+                burst: download_burst.max(1) as f64,
+            };
+            let download_limit = (download_rate_per_hour > 0).then(|| rate_limit::RateLimitConfig {
+                ip_quota: download_quota,
+                key_quota: download_quota,
+                api_keys: api_keys.into_iter().collect(),
+                trusted_proxy_hops,
+            });
+            let options = ServeOptions {
+                rate_limit,
+                download_limit,
+                concurrency: concurrency::ConcurrencyConfig {
+                    images: max_concurrent_images,
+                    queries: max_concurrent_queries,
+                    hail: max_concurrent_hail,
+                },
+                warm_cache,
+                readiness_requires_assets,
+                lazy_metadata,
+                rediscover_interval: (rediscover_interval_secs > 0)
+                    .then(|| std::time::Duration::from_secs(rediscover_interval_secs)),
+                table_stats_interval: (table_stats_interval_secs > 0)
+                    .then(|| std::time::Duration::from_secs(table_stats_interval_secs)),
+                gene_models_backend,
+            };
+            run_server(port, assets_file, registry, options).await?;
         }
         Commands::Discover {
             output,
             filter_by_metadata,
+            config,
+            previous,
+            report,
+            to_clickhouse,
+            clickhouse_url,
+            database,
         } => {
-            run_discover(output, filter_by_metadata).await?;
+            let config = config::Config::load(config.as_deref())?;
+            let clickhouse = to_clickhouse.then(|| {
+                let database = database
+                    .or_else(|| config.clickhouse_database.clone())
+                    .unwrap_or_else(|| "default".to_string());
+                cli::sql::SqlClient::new(&clickhouse_url, &database)
+            });
+            run_discover(output, filter_by_metadata, &config, previous, report, clickhouse).await?;
         }
         Commands::Analyze { input } => {
             run_analyze(input).await?;
@@ -188,15 +439,30 @@ async fn main() -> anyhow::Result<()> {
             )
             .await?;
         }
+        Commands::Assets { command } => {
+            cli::run_assets(command).await?;
+        }
         Commands::Ingest { command } => {
             cli::run_ingest(command).await?;
         }
         Commands::Derive { command } => {
             cli::run_derive(command).await?;
         }
+        Commands::Export { command } => {
+            cli::run_export(command).await?;
+        }
+        Commands::Migrate { command } => {
+            cli::run_migrate(command).await?;
+        }
         Commands::LoadTest { config } => {
             cli::run_loadtest(config).await?;
         }
+        Commands::Bench(args) => {
+            cli::bench::run_bench(&args).await?;
+        }
+        Commands::ExportStatic(args) => {
+            cli::export_static::run_export_static(&args).await?;
+        }
     }
 
     Ok(())
@@ -207,13 +473,27 @@ async fn health_check() -> &'static str {
     "ok"
 }
 
-/// Run the HTTP server
-async fn run_server(port: u16, assets_file: Option<PathBuf>) -> anyhow::Result<()> {
-    info!("Starting AxAoU Server...");
-
+/// Build the application state for one dataset
+///
+/// Metadata and assets are loaded in the background after the server binds.
+fn build_state(
+    config: config::Config,
+    assets_file: Option<PathBuf>,
+    gene_models_backend: gene_models::GeneModelsBackendKind,
+) -> Arc<AppState> {
     // Initialize ClickHouse client (connection is lazy — no network call here)
-    let clickhouse_client = clickhouse::client::connect();
+    let clickhouse_client =
+        clickhouse::client::connect_to_database(config.clickhouse_database.as_deref());
+    build_state_with_client(config, assets_file, gene_models_backend, clickhouse_client)
+}
 
+/// [`build_state`] against an existing ClickHouse client
+fn build_state_with_client(
+    config: config::Config,
+    assets_file: Option<PathBuf>,
+    gene_models_backend: gene_models::GeneModelsBackendKind,
+    clickhouse_client: ::clickhouse::Client,
+) -> Arc<AppState> {
     // Metadata and assets start empty — loaded in background after server binds port.
     // This avoids blocking startup on ClickHouse/GCS network round-trips.
     let metadata: Arc<RwLock<Vec<models::AnalysisMetadata>>> =
@@ -252,199 +532,526 @@ async fn run_server(port: u16, assets_file: Option<PathBuf>) -> anyhow::Result<(
     let api_cache = moka::future::Cache::builder()
         .max_capacity(500_000)
         .time_to_live(std::time::Duration::from_secs(24 * 60 * 60))
+        .expire_after(phenotype::manhattan_render::ApiCacheExpiry)
         .weigher(|_key, value: &Vec<u8>| -> u32 {
             // Estimate weight as byte size in KB (1KB = 1 unit)
             (value.len() / 1024).max(1) as u32
         })
         .build();
 
+    // A cache directory that can't be created disables the disk cache, not the server
+    let plot_cache = config.plot_delivery.cache_dir.as_ref().and_then(|dir| {
+        phenotype::plot_cache::PlotDiskCache::open(
+            dir,
+            config.plot_delivery.cache_max_mb * 1024 * 1024,
+            std::time::Duration::from_secs(config.plot_delivery.revalidate_secs),
+        )
+        .map_err(|e| tracing::warn!("Plot disk cache at {:?} disabled: {}", dir, e))
+        .ok()
+    });
+    // Renders are versioned by data version, so they never need revalidating
+    let render_cache = config.render_cache_dir.as_ref().and_then(|dir| {
+        phenotype::plot_cache::PlotDiskCache::open(
+            dir,
+            config.render_cache_max_mb * 1024 * 1024,
+            std::time::Duration::MAX,
+        )
+        .map_err(|e| tracing::warn!("Render disk cache at {:?} disabled: {}", dir, e))
+        .ok()
+    });
+
+    // Chain files are small enough to load synchronously at startup
+    let liftover = liftover::Liftover::load(&config.liftover);
+
+    let executor = Arc::new(clickhouse::executor::QueryExecutor::new(
+        config.query_policy.clone(),
+    ));
+    let gene_models = gene_models::backend(
+        gene_models_backend,
+        clickhouse_client.clone(),
+        Arc::clone(&executor),
+        &config,
+    );
+
     // Create shared application state
-    let state = Arc::new(AppState {
+    Arc::new(AppState {
         metadata: Arc::clone(&metadata),
         assets,
         gene_queries,
         clickhouse: clickhouse_client,
+        executor,
         hail_client,
         api_cache,
+        inflight: single_flight::SingleFlight::default(),
+        plot_cache,
+        render_cache,
         data_version,
-    });
+        config: Arc::new(config),
+        metadata_loaded: std::sync::atomic::AtomicBool::new(false),
+        tasks: admin::tasks::TaskRegistry::default(),
+        liftover,
+        gene_models,
+        table_stats: table_metrics::TableStatsCache::default(),
+    })
+}
+
+/// API routes bound to one dataset's state, with its Cache-Control policy
+/// and admin token
+fn dataset_router(state: &Arc<AppState>) -> Router {
+    let policy = Arc::new(state.config.cache_control.clone());
+    let admin_token = admin::auth::AdminToken::new(state.config.admin_token.as_deref());
+    api_router()
+        .merge(admin_router().route_layer(axum::middleware::from_fn_with_state(
+            admin_token,
+            admin::auth::require_admin,
+        )))
+        .route_layer(axum::middleware::from_fn_with_state(
+            policy,
+            cache_control::apply,
+        ))
+        .route_layer(axum::middleware::from_fn(
+            clickhouse::executor::track_db_usage,
+        ))
+        .with_state(state.clone())
+}
 
-    // Build the router with /api prefix to match proxy behavior
-    let app = Router::new()
-        .nest(
-            "/api",
-            Router::new()
-                .route("/health", get(health_check))
-                .route("/config", get(api::get_config))
-                .route("/analyses", get(api::get_analyses))
-                .route("/analyses/:analysis_id", get(api::get_analysis_by_id))
-                .route("/categories", get(api::get_categories))
-                .route("/genes/model/:gene_id", get(api::get_gene_model))
-                .route(
-                    "/genes/model/interval/:interval",
-                    get(api::get_gene_models_in_interval),
-                )
-                // Analysis assets discovery endpoints
-                .route("/analyses-loaded", get(api::get_analyses_loaded))
-                .route("/assets", get(api::get_assets))
-                .route("/assets/summary", get(api::get_assets_summary))
-                // Gene association query endpoints
-                .route(
-                    "/phenotype/:analysis_id/genes",
-                    get(api::list_gene_associations),
-                )
-                .route(
-                    "/phenotype/:analysis_id/genes/:gene_id",
-                    get(api::get_gene_associations),
-                )
-                // --- Phenotype / Gene Summary Routes (derived tables) ---
-                .route(
-                    "/phenotypes/summary",
-                    get(phenotype::summary::get_phenotypes_summary),
-                )
-                .route(
-                    "/genes/summary",
-                    get(genes::routes::get_genes_summary),
-                )
-                // --- Phenotype / Manhattan Routes (ClickHouse-backed) ---
-                .route(
-                    "/phenotype/:analysis_id/loci",
-                    get(phenotype::loci::get_phenotype_loci),
-                )
-                .route(
-                    "/phenotype/:analysis_id/loci/:locus_id/variants",
-                    get(phenotype::loci::get_locus_variants),
-                )
-                .route(
-                    "/phenotype/:analysis_id/loci/:locus_id/plot",
-                    get(phenotype::loci::get_locus_plot),
-                )
-                .route(
-                    "/phenotype/:analysis_id/loci/:locus_id/plot/image",
-                    get(phenotype::loci::get_locus_plot_image),
-                )
-                .route(
-                    "/phenotype/:analysis_id/significant",
-                    get(phenotype::significant::get_significant_variants),
-                )
-                .route(
-                    "/phenotype/:analysis_id/plots",
-                    get(phenotype::plots::get_phenotype_plots),
-                )
-                // --- Unified Overview Route ---
-                .route(
-                    "/phenotype/:analysis_id/overview",
-                    get(phenotype::overview::get_phenotype_overview),
-                )
-                // --- Manhattan Plot Proxy Routes ---
-                // --- Region Render Routes (server-side locus PNG) ---
-                .route(
-                    "/phenotype/:analysis_id/region/render",
-                    get(phenotype::region_render::render_region_plot),
-                )
-                .route(
-                    "/phenotype/:analysis_id/region/render/overlay",
-                    get(phenotype::region_render::render_region_overlay),
-                )
-                // --- Manhattan Plot Proxy Routes ---
-                .route(
-                    "/phenotype/:analysis_id/manhattan",
-                    get(phenotype::manhattan::get_manhattan_data),
-                )
-                .route(
-                    "/phenotype/:analysis_id/manhattan/image",
-                    get(phenotype::manhattan::get_manhattan_image),
-                )
-                .route(
-                    "/phenotype/:analysis_id/manhattan/overlay",
-                    get(phenotype::manhattan::get_manhattan_overlay),
-                )
-                // --- Variant Annotation Routes (ClickHouse-backed) ---
-                .route(
-                    "/variants/search",
-                    get(variants::annotations::search_variants),
-                )
-                .route(
-                    "/variants/annotations/:variant_id",
-                    get(variants::annotations::get_annotation_by_id),
-                )
-                .route(
-                    "/variants/annotations/interval/:interval",
-                    get(variants::annotations::get_annotations_by_interval),
-                )
-                .route(
-                    "/variants/annotations/gene/:gene_id",
-                    get(variants::annotations::get_annotations_by_gene),
-                )
-                // --- Association / PheWAS Routes (ClickHouse-backed) ---
-                .route(
-                    "/variants/associations/variant/:variant_id",
-                    get(variants::annotations::get_association_by_variant),
-                )
-                .route(
-                    "/variants/associations/interval/:interval",
-                    get(variants::annotations::get_associations_by_interval),
-                )
-                .route(
-                    "/variants/associations/phewas/:variant_id",
-                    get(variants::phewas::get_phewas_by_variant),
-                )
-                .route(
-                    "/variants/associations/phewas/interval/:interval",
-                    get(variants::phewas::get_phewas_by_interval),
-                )
-                .route(
-                    "/variants/associations/top",
-                    get(variants::phewas::get_top_variants),
-                )
-                .route(
-                    "/variants/associations/top-aggregated",
-                    get(variants::phewas::get_top_variants_aggregated),
-                )
-                .route(
-                    "/variants/associations/gene/:gene_id",
-                    get(variants::associations::get_variants_by_gene),
-                )
-                .route(
-                    "/variants/associations/manhattan/:analysis_id/top",
-                    get(variants::associations::get_manhattan_top),
-                )
-                // --- Gene Routes (ClickHouse-backed) ---
-                .route(
-                    "/genes/phewas/:gene_id",
-                    get(genes::routes::get_gene_phewas),
-                )
-                .route(
-                    "/genes/top-associations",
-                    get(genes::routes::get_top_associations),
-                )
-                .route(
-                    "/genes/all-symbols",
-                    get(genes::routes::get_all_symbols),
-                )
-                .route(
-                    "/genes/associations",
-                    get(genes::routes::get_genes_associations),
-                )
-                .route(
-                    "/genes/associations/interval/:interval",
-                    get(genes::routes::get_genes_in_interval),
-                )
-                // --- QQ Plot Route (ClickHouse-backed) ---
-                .route(
-                    "/phenotype/:analysis_id/qq",
-                    get(phenotype::qq::get_qq_plot),
-                )
-                // --- Admin Routes ---
-                .route(
-                    "/admin/pipeline/stats",
-                    get(admin::pipeline::get_pipeline_stats),
-                )
-                .route(
-                    "/admin/cache/clear",
-                    axum::routing::post(admin::pipeline::clear_cache),
-                )
-                ,
+/// Wrap a cacheable route with ETag / `If-None-Match` handling
+fn cached(route: MethodRouter<Arc<AppState>>) -> MethodRouter<Arc<AppState>> {
+    route.layer(axum::middleware::from_fn(etag::conditional))
+}
+
+/// Wrap a route whose format is negotiated from `Accept` with `Vary: Accept`
+fn negotiated(route: MethodRouter<Arc<AppState>>) -> MethodRouter<Arc<AppState>> {
+    route.layer(axum::middleware::from_fn(response::vary_accept))
+}
+
+/// Routes served for every dataset (mounted under /api and /api/v/:dataset)
+fn api_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/health", get(health_check))
+        .route("/health/detail", get(health::health_detail))
+        .route("/config", cached(get(api::get_config)))
+        .route("/version", get(version::get_version))
+        .route("/analyses", cached(get(api::get_analyses)))
+        .route("/analyses/search", get(analysis_search::search_analyses))
+        .route("/analyses/heritability", get(api::get_heritability_ranking))
+        .route("/analyses/:analysis_id", get(api::get_analysis_by_id))
+        .route(
+            "/analyses/:analysis_id/correlations",
+            get(correlations::get_analysis_correlations),
+        )
+        .route("/correlations/matrix", get(correlations::get_correlation_matrix))
+        .route("/compare/manhattan", get(phenotype::compare::get_phenotype_comparison))
+        .route("/categories", cached(get(api::get_categories)))
+        .route("/genes/model/search", get(api::search_gene_models))
+        .route("/genes/model/:gene_id", cached(get(api::get_gene_model)))
+        .route("/genes/model/:gene_id/transcripts", cached(get(api::get_gene_transcripts)))
+        .route("/transcripts/:transcript_id", cached(get(api::get_transcript)))
+        .route(
+            "/genes/model/interval/:interval",
+            cached(get(api::get_gene_models_in_interval)),
+        )
+        // Analysis assets discovery endpoints
+        .route("/analyses-loaded", get(api::get_analyses_loaded))
+        .route("/assets", get(api::get_assets))
+        .route("/assets/summary", get(api::get_assets_summary))
+        .route("/downloads/:analysis_id", get(downloads::get_downloads))
+        .route(
+            "/downloads/:analysis_id/:asset_id/tar",
+            get(downloads::get_download_tar),
+        )
+        // htsget-style region access to summary statistics
+        .route("/htsget/variants/:analysis_id", get(htsget::get_ticket))
+        .route("/htsget/variants/:analysis_id/data", get(htsget::get_block))
+        // Gene association query endpoints
+        .route(
+            "/phenotype/:analysis_id/genes",
+            negotiated(get(api::list_gene_associations)),
+        )
+        .route(
+            "/phenotype/:analysis_id/known-hits",
+            get(phenotype::known_hits::get_known_hits),
+        )
+        .route(
+            "/phenotype/:analysis_id/shared-loci",
+            get(phenotype::shared_loci::get_shared_loci),
+        )
+        .route(
+            "/phenotype/:analysis_id/genes/count",
+            get(api::count_gene_associations),
+        )
+        .route(
+            "/phenotype/:analysis_id/genes/:gene_id",
+            get(api::get_gene_associations),
+        )
+        // --- Phenotype / Gene Summary Routes (derived tables) ---
+        .route(
+            "/phenotypes/summary",
+            get(phenotype::summary::get_phenotypes_summary),
+        )
+        .route(
+            "/genes/summary",
+            get(genes::routes::get_genes_summary),
+        )
+        // --- Phenotype / Manhattan Routes (ClickHouse-backed) ---
+        .route(
+            "/phenotype/:analysis_id/loci",
+            get(phenotype::loci::get_phenotype_loci),
+        )
+        .route(
+            "/phenotype/:analysis_id/loci.bed",
+            get(phenotype::loci::get_loci_bed),
+        )
+        .route(
+            "/phenotype/:analysis_id/loci/top",
+            get(phenotype::loci::get_top_loci),
+        )
+        .route(
+            "/phenotype/:analysis_id/loci/:locus_id/variants",
+            get(phenotype::loci::get_locus_variants),
+        )
+        .route(
+            "/phenotype/:analysis_id/loci/:locus_id/locuszoom",
+            get(phenotype::locuszoom::get_locus_zoom),
+        )
+        .route(
+            "/phenotype/:analysis_id/thresholds",
+            get(phenotype::thresholds::get_thresholds),
+        )
+        .route(
+            "/phenotype/:analysis_id/loci/:locus_id/conditional",
+            get(phenotype::conditional::get_conditional_results),
+        )
+        .route(
+            "/phenotype/:analysis_id/loci/:locus_id/credible-sets",
+            get(phenotype::credible_sets::get_credible_sets),
+        )
+        .route(
+            "/phenotype/:analysis_id/loci/:locus_id/plot",
+            get(phenotype::loci::get_locus_plot),
+        )
+        .route(
+            "/phenotype/:analysis_id/loci/:locus_id/plot/image",
+            cached(get(phenotype::loci::get_locus_plot_image)),
+        )
+        .route(
+            "/phenotype/:analysis_id/significant",
+            get(phenotype::significant::get_significant_variants),
+        )
+        .route(
+            "/phenotype/:analysis_id/top-variants",
+            get(phenotype::significant::get_top_variants),
+        )
+        .route(
+            "/phenotype/:analysis_id/plots",
+            get(phenotype::plots::get_phenotype_plots),
+        )
+        // --- Unified Overview Route ---
+        .route(
+            "/phenotype/:analysis_id/overview",
+            get(phenotype::overview::get_phenotype_overview),
+        )
+        // --- Manhattan Plot Proxy Routes ---
+        // --- Region Render Routes (server-side locus PNG) ---
+        .route(
+            "/phenotype/:analysis_id/region/render",
+            get(phenotype::region_render::render_region_plot),
+        )
+        .route(
+            "/phenotype/:analysis_id/region/render/overlay",
+            get(phenotype::region_render::render_region_overlay),
+        )
+        // --- Manhattan Plot Proxy Routes ---
+        .route(
+            "/phenotype/:analysis_id/manhattan",
+            get(phenotype::manhattan::get_manhattan_data),
+        )
+        .route(
+            "/phenotype/:analysis_id/manhattan/image",
+            cached(get(phenotype::manhattan::get_manhattan_image)),
+        )
+        .route(
+            "/phenotype/:analysis_id/manhattan/thumbnail",
+            cached(get(phenotype::thumbnail::get_manhattan_thumbnail)),
+        )
+        .route(
+            "/phenotype/:analysis_id/manhattan/overlay",
+            get(phenotype::manhattan::get_manhattan_overlay),
+        )
+        .route(
+            "/phenotype/:analysis_id/gene-manhattan",
+            get(phenotype::gene_manhattan::get_gene_manhattan),
+        )
+        // --- Variant Annotation Routes (ClickHouse-backed) ---
+        .route(
+            "/variants/search",
+            get(variants::annotations::search_variants),
+        )
+        .route(
+            "/variants/annotations/:variant_id",
+            get(variants::annotations::get_annotation_by_id),
+        )
+        .route(
+            "/variants/annotations/batch",
+            axum::routing::post(variants::annotations::get_annotations_batch),
+        )
+        .route(
+            "/variants/annotations/interval/:interval",
+            negotiated(get(variants::annotations::get_annotations_by_interval)),
+        )
+        .route(
+            "/variants/annotations/interval/:interval/count",
+            get(variants::annotations::count_annotations_by_interval),
+        )
+        .route(
+            "/variants/annotations/gene/:gene_id",
+            get(variants::annotations::get_annotations_by_gene),
+        )
+        // --- Association / PheWAS Routes (ClickHouse-backed) ---
+        .route(
+            "/variants/associations/variant/:variant_id",
+            get(variants::annotations::get_association_by_variant),
+        )
+        .route(
+            "/variants/associations/interval/:interval",
+            negotiated(get(variants::annotations::get_associations_by_interval)),
+        )
+        .route(
+            "/variants/associations/phewas/:variant_id",
+            negotiated(get(variants::phewas::get_phewas_by_variant)),
+        )
+        .route(
+            "/variants/associations/phewas/interval/:interval",
+            get(variants::phewas::get_phewas_by_interval),
+        )
+        .route(
+            "/variants/associations/top",
+            get(variants::phewas::get_top_variants),
+        )
+        .route(
+            "/variants/associations/top-aggregated",
+            get(variants::phewas::get_top_variants_aggregated),
+        )
+        .route(
+            "/variants/associations/gene/:gene_id",
+            get(variants::associations::get_variants_by_gene),
+        )
+        .route(
+            "/variants/associations/manhattan/:analysis_id/top",
+            get(variants::associations::get_manhattan_top),
+        )
+        .route(
+            "/variants/:variant_id/forest",
+            get(variants::forest::get_forest_plot),
+        )
+        .route(
+            "/variants/:variant_id/liftover",
+            get(variants::liftover::get_variant_liftover),
+        )
+        .route("/variants/:variant_id/ld", get(variants::ld::get_ld_proxies))
+        // --- Gene Routes (ClickHouse-backed) ---
+        .route(
+            "/genes/phewas/:gene_id",
+            get(genes::routes::get_gene_phewas),
+        )
+        .route(
+            "/genes/phewas/:gene_id/grouped",
+            get(genes::routes::get_gene_phewas_grouped),
+        )
+        .route(
+            "/genes/:gene_id/lof-summary",
+            get(genes::routes::get_gene_lof_summary),
+        )
+        .route(
+            "/genes/top-associations",
+            get(genes::routes::get_top_associations),
+        )
+        .route(
+            "/genes/all-symbols",
+            get(genes::routes::get_all_symbols),
+        )
+        .route(
+            "/genes/search",
+            get(genes::routes::search_genes),
+        )
+        .route(
+            "/genes/associations",
+            get(genes::routes::get_genes_associations),
+        )
+        .route(
+            "/genes/associations/interval/:interval",
+            get(genes::routes::get_genes_in_interval),
+        )
+        // --- QQ Plot Route (ClickHouse-backed) ---
+        .route(
+            "/phenotype/:analysis_id/qq",
+            get(phenotype::qq::get_qq_plot),
+        )
+        .route(
+            "/phenotype/:analysis_id/qq/image",
+            cached(get(phenotype::qq::get_qq_image)),
+        )
+}
+
+/// Admin routes, served only to requests with the dataset's admin token
+fn admin_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/admin/pipeline/stats",
+            get(admin::pipeline::get_pipeline_stats),
+        )
+        .route(
+            "/admin/cache/clear",
+            axum::routing::post(admin::pipeline::clear_cache),
+        )
+        .route(
+            "/admin/ingest-runs",
+            get(admin::ingest_runs::list_ingest_runs),
+        )
+        .route(
+            "/admin/slow-queries",
+            get(admin::slow_queries::get_slow_queries)
+                .delete(admin::slow_queries::reset_slow_queries),
+        )
+        .route(
+            "/admin/assets/refresh",
+            axum::routing::post(admin::tasks::start_rediscovery),
+        )
+        .route(
+            "/admin/assets/rediscover",
+            axum::routing::post(admin::tasks::start_rediscovery),
+        )
+        .route(
+            "/admin/tasks",
+            get(admin::tasks::list_tasks).post(admin::tasks::create_task),
+        )
+        .route("/admin/tasks/:task_id", get(admin::tasks::get_task))
+        .route(
+            "/admin/tasks/:task_id/events",
+            get(admin::tasks::stream_task_events),
+        )
+}
+
+/// Server startup options beyond port and datasets
+struct ServeOptions {
+    /// Per-client rate limiting (None disables it)
+    rate_limit: Option<rate_limit::RateLimitConfig>,
+    /// Per-client limit on tar downloads (None disables it)
+    download_limit: Option<rate_limit::RateLimitConfig>,
+    /// Requests in flight per endpoint class
+    concurrency: concurrency::ConcurrencyConfig,
+    /// Load metadata and warm caches before binding the port
+    warm_cache: bool,
+    /// Whether /readyz also waits for asset discovery
+    readiness_requires_assets: bool,
+    /// Bind before metadata has loaded, retrying it in the background
+    lazy_metadata: bool,
+    /// Interval for background asset re-discovery (None disables it)
+    rediscover_interval: Option<std::time::Duration>,
+    /// Interval for sampling table stats for /metrics (None disables it)
+    table_stats_interval: Option<std::time::Duration>,
+    /// Gene model backend for every dataset
+    gene_models_backend: gene_models::GeneModelsBackendKind,
+}
+
+/// Run the HTTP server
+///
+/// Every dataset in the registry gets its own `AppState` (ClickHouse database,
+/// asset roots, caches) mounted under `/api/v/:dataset`. The default dataset is
+/// also mounted at `/api` so existing clients keep working.
+async fn run_server(
+    port: u16,
+    assets_file: Option<PathBuf>,
+    registry: datasets::DatasetRegistry,
+    options: ServeOptions,
+) -> anyhow::Result<()> {
+    info!("Starting AxAoU Server...");
+
+    let mut app = Router::new();
+    let mut states = Vec::new();
+    for (name, config) in &registry.datasets {
+        let is_default = *name == registry.default;
+        // Pre-computed assets file applies to the default dataset only
+        let dataset_assets = if is_default { assets_file.clone() } else { None };
+        let state = build_state(config.clone(), dataset_assets, options.gene_models_backend);
+
+        let router = || {
+            let router = dataset_router(&state);
+            if options.lazy_metadata {
+                router.route_layer(axum::middleware::from_fn_with_state(
+                    Arc::clone(&state),
+                    metadata::require_metadata,
+                ))
+            } else {
+                router
+            }
+        };
+        info!("Mounting dataset '{}' at /api/v/{}", name, name);
+        app = app.nest(&format!("/api/v/{}", name), router());
+        if is_default {
+            app = app.nest("/api", router());
+        }
+        if let Some(interval) = options.rediscover_interval {
+            analysis_assets::spawn_periodic_rediscovery(
+                Arc::clone(&state.config),
+                Arc::clone(&state.metadata),
+                Arc::clone(&state.assets),
+                interval,
+            );
+        }
+        if let Some(interval) = options.table_stats_interval {
+            table_metrics::spawn_sampler(Arc::clone(&state), interval);
+        }
+        states.push((name.clone(), state));
+    }
+
+    // Per-class concurrency limits; applied inside the rate limiter so
+    // rate-limited requests never take a slot
+    let limits = options.concurrency;
+    info!(
+        "Concurrency limits: {} queries, {} images, {} Hail (0 = unlimited)",
+        limits.queries, limits.images, limits.hail
+    );
+    app = app.layer(axum::middleware::from_fn_with_state(
+        concurrency::ConcurrencyLimits::new(limits),
+        concurrency::limit_concurrency,
+    ));
+
+    // Per-client rate limiting for the data API (disabled unless --rate-limit-rps > 0)
+    if let Some(rate_limit_config) = options.rate_limit {
+        info!(
+            "Rate limiting enabled: {} req/s per IP (burst {})",
+            rate_limit_config.ip_quota.per_second, rate_limit_config.ip_quota.burst
+        );
+        let limiter = rate_limit::RateLimiter::new(rate_limit_config);
+        limiter.spawn_cleanup();
+        app = app.layer(axum::middleware::from_fn_with_state(
+            limiter,
+            rate_limit::rate_limit,
+        ));
+    }
+    if let Some(download_limit_config) = options.download_limit {
+        info!(
+            "Tar downloads limited to {:.0} per client and hour (burst {})",
+            download_limit_config.ip_quota.per_second * 3600.0,
+            download_limit_config.ip_quota.burst
+        );
+        let limiter = rate_limit::RateLimiter::new(download_limit_config);
+        limiter.spawn_cleanup();
+        app = app.layer(axum::middleware::from_fn_with_state(
+            limiter,
+            rate_limit::rate_limit_downloads,
+        ));
+    }
+
+    let dataset_list = datasets::DatasetList::from_registry(&registry);
+    let app = app
+        .route(
+            "/api/datasets",
+            get(move || {
+                let dataset_list = dataset_list.clone();
+                async move { axum::Json(dataset_list) }
+            }),
         )
         .layer(CompressionLayer::new())
         .layer(
@@ -452,8 +1059,28 @@ async fn run_server(port: u16, assets_file: Option<PathBuf>) -> anyhow::Result<(
                 .allow_origin(Any)
                 .allow_methods(Any)
                 .allow_headers(Any),
-        )
-        .with_state(state.clone());
+        );
+
+    // Liveness/readiness probes and metrics (outside /api, not rate limited)
+    let probe_state = Arc::new(health::ProbeState {
+        datasets: states.clone(),
+        require_assets: options.readiness_requires_assets,
+    });
+    if states.iter().any(|(_, state)| state.config.webhooks.enabled()) {
+        health::spawn_health_monitor(Arc::clone(&probe_state));
+    }
+    let app = app.merge(
+        Router::new()
+            .route("/healthz", get(health::healthz))
+            .route("/readyz", get(health::readyz))
+            .route("/metrics", get(table_metrics::metrics))
+            .with_state(probe_state),
+    );
+
+    // Serve the OpenAPI spec and Swagger UI
+    let app = app.merge(
+        SwaggerUi::new("/api/docs").url("/api/openapi.json", openapi::ApiDoc::openapi()),
+    );
 
     // Mount load test dashboard routes (separate state)
     let lt_db = loadtest::db::LoadTestDb::open("loadtest.db")
@@ -461,25 +1088,35 @@ async fn run_server(port: u16, assets_file: Option<PathBuf>) -> anyhow::Result<(
     let lt_state = Arc::new(loadtest::LoadTestState::new(lt_db));
     let app = app.nest("/api/loadtest", loadtest::api::router(lt_state));
 
-    // Warm the cache in the background for the heaviest queries
-    tokio::spawn(warm_cache(state));
+    // Load metadata and warm the cache for the heaviest queries, either
+    // before binding (--warm-cache) or in the background
+    for (name, state) in states {
+        if options.warm_cache && !options.lazy_metadata {
+            info!("Warming dataset '{}' before accepting traffic...", name);
+            warm_cache(state, false).await;
+        } else {
+            tokio::spawn(warm_cache(state, options.lazy_metadata));
+        }
+    }
 
     // Bind to configurable port
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     info!("Server listening on http://{}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
 
 /// Pre-warm the API cache for the heaviest global queries.
 /// Runs in the background so the server can start serving immediately.
-async fn warm_cache(state: Arc<AppState>) {
-    use crate::clickhouse::models::{
-        AnalysisMetadataRow, GeneAssociationRow, GeneSummaryRow, PhenotypeSummaryRow,
-    };
+async fn warm_cache(state: Arc<AppState>, retry_metadata: bool) {
+    use crate::clickhouse::models::{GeneAssociationRow, GeneSummaryRow, PhenotypeSummaryRow};
     use crate::response::{LookupResult, QueryTimer};
 
     // First: connect to ClickHouse and load metadata (needed before API can serve)
@@ -488,20 +1125,14 @@ async fn warm_cache(state: Arc<AppState>) {
         Err(e) => tracing::warn!("ClickHouse connection warning: {}", e),
     }
 
-    info!("Loading analysis metadata from ClickHouse...");
-    match state
-        .clickhouse
-        .query("SELECT analysis_id, ancestry_group, category, description, description_more, trait_type, pheno_sex, n_cases, n_controls, lambda_gc_exome, lambda_gc_acaf, lambda_gc_gene_burden_001, keep_pheno_burden, keep_pheno_skat, keep_pheno_skato FROM analysis_metadata")
-        .fetch_all::<AnalysisMetadataRow>()
-        .await
-    {
-        Ok(rows) => {
-            let api_rows: Vec<crate::models::AnalysisMetadata> =
-                rows.iter().map(|r| r.to_api()).collect();
-            info!("Loaded {} metadata records.", api_rows.len());
-            *state.metadata.write().await = api_rows;
+    info!("Loading analysis metadata...");
+    if retry_metadata {
+        metadata::load_with_retries(&state).await;
+    } else {
+        match metadata::load_metadata(&state.clickhouse, &state.executor, &state.config).await {
+            Ok(api_rows) => metadata::install(&state, api_rows).await,
+            Err(e) => tracing::error!("Failed to load metadata: {}", e),
         }
-        Err(e) => tracing::error!("Failed to load metadata: {}", e),
     }
 
     let dv = state.data_version.as_deref().unwrap_or("none");
@@ -511,7 +1142,7 @@ async fn warm_cache(state: Arc<AppState>) {
     match state
         .clickhouse
         .query("SELECT * FROM phenotype_summary ORDER BY sig_loci_count DESC, analysis_id ASC")
-        .fetch_all::<PhenotypeSummaryRow>()
+        .fetch_all_with::<PhenotypeSummaryRow>(&state.executor)
         .await
     {
         Ok(mut rows) => {
@@ -522,7 +1153,7 @@ async fn warm_cache(state: Arc<AppState>) {
                 );
             }
             let key = format!("phenotypes_summary_all_{}", dv);
-            if let Ok(bytes) = serde_json::to_vec(&LookupResult::new(rows, timer.elapsed())) {
+            if let Ok(bytes) = serde_json::to_vec(&LookupResult::new(rows, &timer)) {
                 info!("Cache warm: phenotypes_summary ({} bytes)", bytes.len());
                 state.api_cache.insert(key, bytes).await;
             }
@@ -535,12 +1166,12 @@ async fn warm_cache(state: Arc<AppState>) {
     match state
         .clickhouse
         .query("SELECT * FROM gene_summary ORDER BY sig_phenos_variant_count DESC, gene_symbol ASC")
-        .fetch_all::<GeneSummaryRow>()
+        .fetch_all_with::<GeneSummaryRow>(&state.executor)
         .await
     {
         Ok(rows) => {
             let key = format!("genes_summary_all_{}", dv);
-            if let Ok(bytes) = serde_json::to_vec(&LookupResult::new(rows, timer.elapsed())) {
+            if let Ok(bytes) = serde_json::to_vec(&LookupResult::new(rows, &timer)) {
                 info!("Cache warm: genes_summary ({} bytes)", bytes.len());
                 state.api_cache.insert(key, bytes).await;
             }
@@ -555,11 +1186,12 @@ async fn warm_cache(state: Arc<AppState>) {
             SELECT gene_id, gene_symbol, annotation, max_maf, phenotype, ancestry,
                    pvalue, pvalue_burden, pvalue_skat, beta_burden, mac,
                    contig, gene_start_position, xpos
-            FROM gene_associations
+            FROM top_gene_associations
             WHERE ancestry = 'meta'
               AND pvalue IS NOT NULL
               AND pvalue >= 0
               AND pvalue <= 0.0001
+              AND max_maf >= 0
               AND annotation = ?
             ORDER BY pvalue ASC
             LIMIT 100000
@@ -568,7 +1200,7 @@ async fn warm_cache(state: Arc<AppState>) {
             .clickhouse
             .query(query)
             .bind(*annotation)
-            .fetch_all::<GeneAssociationRow>()
+            .fetch_all_with::<GeneAssociationRow>(&state.executor)
             .await
         {
             Ok(rows) => {
@@ -576,7 +1208,7 @@ async fn warm_cache(state: Arc<AppState>) {
                     rows.into_iter().map(|r| r.to_api()).collect();
                 let key = format!("top_genes:meta:{}:0:0.0001:{}", annotation, dv);
                 if let Ok(bytes) =
-                    serde_json::to_vec(&LookupResult::new(api_rows, timer.elapsed()))
+                    serde_json::to_vec(&LookupResult::new(api_rows, &timer))
                 {
                     info!(
                         "Cache warm: top_genes/{} ({} bytes)",
@@ -612,7 +1244,7 @@ async fn warm_cache(state: Arc<AppState>) {
     match state
         .clickhouse
         .query(query)
-        .fetch_all::<crate::clickhouse::models::AggregatedVariantRow>()
+        .fetch_all_with::<crate::clickhouse::models::AggregatedVariantRow>(&state.executor)
         .await
     {
         Ok(rows) => {
@@ -622,7 +1254,7 @@ async fn warm_cache(state: Arc<AppState>) {
                 "top_variants_agg:meta:0:0.000001:1000:none:none:{}",
                 dv
             );
-            if let Ok(bytes) = serde_json::to_vec(&LookupResult::new(api_rows, timer.elapsed())) {
+            if let Ok(bytes) = serde_json::to_vec(&LookupResult::new(api_rows, &timer)) {
                 info!("Cache warm: top_variants_agg ({} bytes)", bytes.len());
                 state.api_cache.insert(key, bytes).await;
             }
@@ -634,13 +1266,20 @@ async fn warm_cache(state: Arc<AppState>) {
 }
 
 /// Discover analysis assets from GCS and save to JSON
-async fn run_discover(output: PathBuf, filter_by_metadata: bool) -> anyhow::Result<()> {
+async fn run_discover(
+    output: PathBuf,
+    filter_by_metadata: bool,
+    config: &config::Config,
+    previous: Option<PathBuf>,
+    report: Option<PathBuf>,
+    clickhouse: Option<cli::sql::SqlClient>,
+) -> anyhow::Result<()> {
     info!("Starting asset discovery...");
 
     // Load metadata for filtering if requested
     let valid_phenotypes = if filter_by_metadata {
         info!("Loading metadata to filter phenotypes...");
-        let metadata = data::load_all_metadata().await?;
+        let metadata = data::load_all_metadata(config).await?;
         info!("Loaded {} metadata records.", metadata.len());
         Some(analysis_assets::get_valid_phenotypes(&metadata))
     } else {
@@ -648,8 +1287,41 @@ async fn run_discover(output: PathBuf, filter_by_metadata: bool) -> anyhow::Resu
     };
 
     // Discover assets
-    let discovery = analysis_assets::AssetDiscovery::new()?;
-    let assets = discovery.discover_all(valid_phenotypes.as_ref()).await?;
+    let discovery = analysis_assets::AssetDiscovery::new(config)?;
+    let assets = match previous {
+        Some(previous_path) => {
+            info!("Loading previous assets from {:?}...", previous_path);
+            let contents = tokio::fs::read_to_string(&previous_path).await?;
+            let previous: AnalysisAssets = serde_json::from_str(&contents)?;
+
+            let (assets, change_report) = discovery
+                .discover_incremental(&previous, valid_phenotypes.as_ref())
+                .await?;
+
+            info!(
+                "Incremental discovery: {} prefixes unchanged, {} changed; {} assets added, {} removed",
+                change_report.unchanged_prefixes,
+                change_report.changed_prefixes,
+                change_report.diff.added.len(),
+                change_report.diff.removed.len()
+            );
+            for asset in &change_report.diff.added {
+                info!("  + {}", asset.uri);
+            }
+            for asset in &change_report.diff.removed {
+                info!("  - {}", asset.uri);
+            }
+
+            if let Some(report_path) = report {
+                let json = serde_json::to_string_pretty(&change_report)?;
+                tokio::fs::write(&report_path, &json).await?;
+                info!("Saved change report to {:?}", report_path);
+            }
+            assets
+        }
+        None => discovery.discover_all(valid_phenotypes.as_ref()).await?,
+    };
+    let assets = assets.with_manifest_header(&config.dataset_version);
 
     info!(
         "Discovered {} assets across {} unique phenotypes",
@@ -662,6 +1334,11 @@ async fn run_discover(output: PathBuf, filter_by_metadata: bool) -> anyhow::Resu
     tokio::fs::write(&output, &json).await?;
     info!("Saved assets to {:?}", output);
 
+    if let Some(sql) = clickhouse {
+        analysis_assets::save_to_clickhouse(&sql, &assets).await?;
+        info!("Wrote {} assets to analysis_assets", assets.assets.len());
+    }
+
     // Print summary
     print_summary(&assets);
 
@@ -723,6 +1400,12 @@ async fn run_query_assets(
                     "gene_exp_p" | "geneexpp" => {
                         matches!(a.asset_type, crate::models::AnalysisAssetType::GeneExpP)
                     }
+                    "conditional" | "cond" => {
+                        matches!(a.asset_type, crate::models::AnalysisAssetType::Conditional)
+                    }
+                    "credible_set" | "credible_sets" | "finemap" => {
+                        matches!(a.asset_type, crate::models::AnalysisAssetType::CredibleSet)
+                    }
                     _ => {
                         // Fallback to contains for flexibility
                         let type_str = format!("{:?}", a.asset_type).to_lowercase();
@@ -868,3 +1551,126 @@ fn increase_fd_limit() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    /// Every path is registered for GET only, so a POST reaching a mounted
+    /// route is rejected with 405 before any handler (or ClickHouse) runs,
+    /// while an unmounted path falls through to 404.
+    #[tokio::test]
+    async fn test_phenotype_and_gene_handlers_are_mounted() {
+        let app = api_router().with_state(build_state(
+            config::Config::default(),
+            None,
+            gene_models::GeneModelsBackendKind::default(),
+        ));
+
+        for path in [
+            "/phenotype/height/manhattan",
+            "/phenotype/height/manhattan/image",
+            "/phenotype/height/manhattan/overlay",
+            "/phenotype/height/overview",
+            "/phenotype/height/loci/locus-1/plot",
+            "/phenotype/height/loci/locus-1/plot/image",
+            "/phenotype/height/qq",
+            "/phenotype/height/qq/image",
+            "/genes/associations",
+            "/genes/top-associations",
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::post(path).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(
+                response.status(),
+                StatusCode::METHOD_NOT_ALLOWED,
+                "{} is not routed",
+                path
+            );
+        }
+
+        let response = app
+            .oneshot(Request::get("/phenotype/height/not-a-route").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    /// Task creation (ingest with `init_strategy=replace` included) is only
+    /// reachable with the dataset's admin token. The body names an unknown
+    /// table, so a request that gets past authentication is rejected by the
+    /// JSON extractor and never queues a task.
+    #[tokio::test]
+    async fn test_admin_tasks_require_admin_token() {
+        let post_task = |app: Router, authorization: Option<&'static str>| {
+            let mut request = Request::post("/admin/tasks")
+                .header(axum::http::header::CONTENT_TYPE, "application/json");
+            if let Some(value) = authorization {
+                request = request.header(axum::http::header::AUTHORIZATION, value);
+            }
+            let body = r#"{"kind":"ingest","table":"not_a_table","init_strategy":"replace"}"#;
+            app.oneshot(request.body(Body::from(body)).unwrap())
+        };
+
+        let mut config = config::Config::default();
+        config.admin_token = Some("s3cret".to_string());
+        let state = build_state(config, None, gene_models::GeneModelsBackendKind::default());
+        let app = dataset_router(&state);
+        for authorization in [None, Some("Bearer guess")] {
+            let response = post_task(app.clone(), authorization).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        let response = post_task(app, Some("Bearer s3cret")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(state.tasks.list().await.is_empty());
+
+        // No configured token: the admin API is disabled
+        let state = build_state(
+            config::Config::default(),
+            None,
+            gene_models::GeneModelsBackendKind::default(),
+        );
+        let response = post_task(dataset_router(&state), Some("Bearer ")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    /// With `--lazy-metadata`, gated routes and handlers that read metadata
+    /// mid-request both answer 503 with `Retry-After` until it has loaded
+    #[tokio::test]
+    async fn test_require_metadata() {
+        let state = build_state(
+            config::Config::default(),
+            None,
+            gene_models::GeneModelsBackendKind::default(),
+        );
+        let app = dataset_router(&state).route_layer(axum::middleware::from_fn_with_state(
+            Arc::clone(&state),
+            metadata::require_metadata,
+        ));
+        for uri in [
+            "/analyses",
+            "/variants/associations/interval/chr1-100-200?analysis_id=height",
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE, "{}", uri);
+            assert_eq!(response.headers()[axum::http::header::RETRY_AFTER], "5", "{}", uri);
+        }
+
+        metadata::install(&state, Vec::new()).await;
+        let response = app
+            .oneshot(Request::get("/analyses").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,498 @@
+//! Analysis metadata backends
+//!
+//! The ClickHouse `analysis_metadata` table is the primary source: filtering,
+//! sorting and pagination for `/api/analyses` are pushed down into SQL. The
+//! metadata Hail Table on GCS (see [`crate::data`]) is the fallback when
+//! ClickHouse is unavailable, and the in-memory copy in `AppState` is filtered
+//! with the same [`MetadataFilter`] semantics when a SQL query fails.
+//!
+//! With `serve --lazy-metadata` the server binds before metadata has loaded:
+//! [`load_with_retries`] keeps trying in the background, and
+//! [`require_metadata`] answers routes that need the in-memory copy with 503
+//! until it has. Handlers that read metadata mid-request, such as
+//! [`crate::api::effect_metadata`], return [`AppError::NotReady`] themselves.
+
+use crate::api::AppState;
+use crate::clickhouse::executor::{QueryExecutor, QueryExt};
+use crate::clickhouse::models::AnalysisMetadataRow;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::models::AnalysisMetadata;
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+/// Longest wait between metadata load attempts
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Columns selected into [`AnalysisMetadataRow`]
+const METADATA_COLUMNS: &str = "analysis_id, ancestry_group, category, description, \
+    description_more, trait_type, pheno_sex, n_cases, n_controls, lambda_gc_exome, \
+    lambda_gc_acaf, lambda_gc_gene_burden_001, keep_pheno_burden, keep_pheno_skat, keep_pheno_skato, \
+    heritability, heritability_se, heritability_method";
+
+/// Analyses with rows in `gene_associations` for the same ancestry, the table
+/// the gene-results handlers read
+const GENE_RESULTS_IN: &str = "(analysis_id, lower(ancestry_group)) IN \
+    (SELECT DISTINCT phenotype, lower(ancestry) FROM gene_associations)";
+const GENE_RESULTS_NOT_IN: &str = "(analysis_id, lower(ancestry_group)) NOT IN \
+    (SELECT DISTINCT phenotype, lower(ancestry) FROM gene_associations)";
+
+/// Sortable metadata fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataSortField {
+    AnalysisId,
+    Description,
+    Category,
+    NCases,
+    NControls,
+    LambdaGcAcaf,
+    LambdaGcExome,
+    Heritability,
+}
+
+impl MetadataSortField {
+    fn column(&self) -> &'static str {
+        match self {
+            Self::AnalysisId => "analysis_id",
+            Self::Description => "description",
+            Self::Category => "category",
+            Self::NCases => "n_cases",
+            Self::NControls => "n_controls",
+            Self::LambdaGcAcaf => "lambda_gc_acaf",
+            Self::LambdaGcExome => "lambda_gc_exome",
+            Self::Heritability => "heritability",
+        }
+    }
+
+    /// Ascending comparison; missing values compare as `None` and are placed
+    /// last by [`MetadataFilter::apply`]
+    fn compare(&self, a: &AnalysisMetadata, b: &AnalysisMetadata) -> Option<Ordering> {
+        fn floats(a: Option<f64>, b: Option<f64>) -> Option<Ordering> {
+            a?.partial_cmp(&b?)
+        }
+        match self {
+            Self::AnalysisId => Some(a.analysis_id.cmp(&b.analysis_id)),
+            Self::Description => Some(a.description.cmp(&b.description)),
+            Self::Category => Some(a.category.cmp(&b.category)),
+            Self::NCases => Some(a.n_cases.cmp(&b.n_cases)),
+            Self::NControls => Some(a.n_controls?.cmp(&b.n_controls?)),
+            Self::LambdaGcAcaf => floats(a.lambda_gc_acaf, b.lambda_gc_acaf),
+            Self::LambdaGcExome => floats(a.lambda_gc_exome, b.lambda_gc_exome),
+            Self::Heritability => floats(a.heritability, b.heritability),
+        }
+    }
+
+    fn is_missing(&self, m: &AnalysisMetadata) -> bool {
+        match self {
+            Self::NControls => m.n_controls.is_none(),
+            Self::LambdaGcAcaf => m.lambda_gc_acaf.is_none(),
+            Self::LambdaGcExome => m.lambda_gc_exome.is_none(),
+            Self::Heritability => m.heritability.is_none(),
+            _ => false,
+        }
+    }
+}
+
+/// Sort direction
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Filtering, sorting and pagination for metadata queries
+#[derive(Debug, Clone, Default)]
+pub struct MetadataFilter {
+    /// Ancestry group (case-insensitive)
+    pub ancestry_group: Option<String>,
+    /// Exact category
+    pub category: Option<String>,
+    /// Exact trait type ("continuous", "binary", ...)
+    pub trait_type: Option<String>,
+    /// Minimum number of cases
+    pub min_n_cases: Option<i64>,
+    /// Only analyses with (or without) gene burden results for the ancestry
+    pub has_gene_results: Option<bool>,
+    /// Only analyses with (or without) a heritability estimate
+    pub has_heritability: Option<bool>,
+    /// Sort field (default: analysis_id, ancestry_group)
+    pub sort_by: Option<MetadataSortField>,
+    /// Sort descending
+    pub descending: bool,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+impl MetadataFilter {
+    /// Stable key for response caching
+    pub fn cache_key(&self) -> String {
+        format!(
+            "{:?}:{:?}:{:?}:{:?}:{:?}:{:?}:{:?}:{}:{:?}:{:?}",
+            self.ancestry_group.as_deref().map(str::to_lowercase),
+            self.category,
+            self.trait_type,
+            self.min_n_cases,
+            self.has_gene_results,
+            self.has_heritability,
+            self.sort_by,
+            self.descending,
+            self.limit,
+            self.offset
+        )
+    }
+
+    /// SQL for this filter; binds follow the order of the `?` placeholders
+    /// (ancestry_group, category, trait_type, min_n_cases, limit, offset)
+    fn to_sql(&self) -> String {
+        let mut conditions = Vec::new();
+        if self.ancestry_group.is_some() {
+            conditions.push("lower(ancestry_group) = lower(?)");
+        }
+        if self.category.is_some() {
+            conditions.push("category = ?");
+        }
+        if self.trait_type.is_some() {
+            conditions.push("trait_type = ?");
+        }
+        if self.min_n_cases.is_some() {
+            conditions.push("n_cases >= ?");
+        }
+        match self.has_gene_results {
+            Some(true) => conditions.push(GENE_RESULTS_IN),
+            Some(false) => conditions.push(GENE_RESULTS_NOT_IN),
+            None => {}
+        }
+        match self.has_heritability {
+            Some(true) => conditions.push("heritability IS NOT NULL"),
+            Some(false) => conditions.push("heritability IS NULL"),
+            None => {}
+        }
+
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let order_clause = match self.sort_by {
+            Some(field) => format!(
+                "ORDER BY {} {} NULLS LAST, analysis_id, ancestry_group",
+                field.column(),
+                if self.descending { "DESC" } else { "ASC" }
+            ),
+            None => "ORDER BY analysis_id, ancestry_group".to_string(),
+        };
+
+        let mut sql = format!(
+            "SELECT {} FROM analysis_metadata {} {}",
+            METADATA_COLUMNS, where_clause, order_clause
+        );
+        if self.limit.is_some() {
+            sql.push_str(" LIMIT ?");
+        }
+        if self.offset.is_some() {
+            sql.push_str(" OFFSET ?");
+        }
+        sql
+    }
+
+    /// Whether the filter can only be answered by ClickHouse: `has_gene_results`
+    /// depends on `gene_associations`, which has no in-memory copy
+    pub fn requires_clickhouse(&self) -> bool {
+        self.has_gene_results.is_some()
+    }
+
+    /// Whether a record passes the filters (ignores sorting and pagination,
+    /// and `has_gene_results`; see [`Self::requires_clickhouse`])
+    pub fn matches(&self, m: &AnalysisMetadata) -> bool {
+        self.ancestry_group
+            .as_deref()
+            .map_or(true, |a| m.ancestry_group.eq_ignore_ascii_case(a))
+            && self.category.as_deref().map_or(true, |c| m.category == c)
+            && self.trait_type.as_deref().map_or(true, |t| m.trait_type == t)
+            && self.min_n_cases.map_or(true, |n| m.n_cases >= n)
+            && self.has_heritability.map_or(true, |want| m.heritability.is_some() == want)
+    }
+
+    /// Filter, sort and paginate records in memory
+    pub fn apply(&self, metadata: &[AnalysisMetadata]) -> Vec<AnalysisMetadata> {
+        let mut rows: Vec<AnalysisMetadata> =
+            metadata.iter().filter(|m| self.matches(m)).cloned().collect();
+
+        if let Some(field) = self.sort_by {
+            rows.sort_by(|a, b| {
+                match (field.is_missing(a), field.is_missing(b)) {
+                    (true, false) => return Ordering::Greater,
+                    (false, true) => return Ordering::Less,
+                    _ => {}
+                }
+                let ord = field.compare(a, b).unwrap_or(Ordering::Equal);
+                let ord = if self.descending { ord.reverse() } else { ord };
+                ord.then_with(|| a.analysis_id.cmp(&b.analysis_id))
+                    .then_with(|| a.ancestry_group.cmp(&b.ancestry_group))
+            });
+        }
+
+        let offset = self.offset.unwrap_or(0) as usize;
+        let limit = self.limit.map_or(usize::MAX, |l| l as usize);
+        rows.into_iter().skip(offset).take(limit).collect()
+    }
+}
+
+/// Metadata backend over the ClickHouse `analysis_metadata` table
+pub struct MetadataClickHouse<'a> {
+    client: &'a clickhouse::Client,
+    executor: &'a QueryExecutor,
+}
+
+impl<'a> MetadataClickHouse<'a> {
+    pub fn new(client: &'a clickhouse::Client, executor: &'a QueryExecutor) -> Self {
+        Self { client, executor }
+    }
+
+    /// Run a filtered, sorted and paginated metadata query
+    pub async fn query(&self, filter: &MetadataFilter) -> Result<Vec<AnalysisMetadata>, AppError> {
+        let sql = filter.to_sql();
+        let mut query = self.client.query(&sql);
+
+        if let Some(ancestry) = &filter.ancestry_group {
+            query = query.bind(ancestry);
+        }
+        if let Some(category) = &filter.category {
+            query = query.bind(category);
+        }
+        if let Some(trait_type) = &filter.trait_type {
+            query = query.bind(trait_type);
+        }
+        if let Some(min_n_cases) = filter.min_n_cases {
+            query = query.bind(min_n_cases);
+        }
+        if let Some(limit) = filter.limit {
+            query = query.bind(limit);
+        }
+        if let Some(offset) = filter.offset {
+            query = query.bind(offset);
+        }
+
+        let rows = query
+            .fetch_all_with::<AnalysisMetadataRow>(self.executor)
+            .await?;
+
+        Ok(rows.iter().map(|r| r.to_api()).collect())
+    }
+
+    /// Load every metadata record
+    pub async fn load_all(&self) -> Result<Vec<AnalysisMetadata>, AppError> {
+        self.query(&MetadataFilter::default()).await
+    }
+}
+
+/// Load all metadata from ClickHouse, falling back to the Hail Table on GCS
+/// when ClickHouse is unreachable or the table is empty.
+pub async fn load_metadata(
+    client: &clickhouse::Client,
+    executor: &QueryExecutor,
+    config: &Config,
+) -> Result<Vec<AnalysisMetadata>, AppError> {
+    match MetadataClickHouse::new(client, executor).load_all().await {
+        Ok(rows) if !rows.is_empty() => {
+            info!("Loaded {} metadata records from ClickHouse.", rows.len());
+            return Ok(rows);
+        }
+        Ok(_) => warn!("ClickHouse analysis_metadata is empty; falling back to Hail Table"),
+        Err(e) => warn!("Failed to load metadata from ClickHouse ({}); falling back to Hail Table", e),
+    }
+
+    let rows = crate::data::load_all_metadata(config).await?;
+    info!("Loaded {} metadata records from Hail Table.", rows.len());
+    Ok(rows)
+}
+
+/// Install loaded metadata and mark the dataset ready
+pub async fn install(state: &AppState, rows: Vec<AnalysisMetadata>) {
+    *state.metadata.write().await = rows;
+    state
+        .metadata_loaded
+        .store(true, std::sync::atomic::Ordering::Release);
+}
+
+/// Load metadata, retrying with exponential backoff until it succeeds
+pub async fn load_with_retries(state: &AppState) {
+    let mut delay = Duration::from_secs(1);
+    loop {
+        match load_metadata(&state.clickhouse, &state.executor, &state.config).await {
+            Ok(rows) => {
+                install(state, rows).await;
+                return;
+            }
+            Err(e) => {
+                warn!("Failed to load metadata ({}); retrying in {:?}", e, delay);
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+            }
+        }
+    }
+}
+
+/// Whether a route (path relative to the dataset mount) reads the in-memory
+/// metadata
+fn depends_on_metadata(path: &str) -> bool {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        ["analyses", ..] | ["categories"] | ["correlations", ..] | ["compare", ..] => true,
+        ["phenotype", _, "shared-loci"] | ["phenotype", _, "qq", "image"] => true,
+        ["genes", "phewas", ..] | ["genes", "top-associations"] => true,
+        _ => false,
+    }
+}
+
+/// Middleware answering metadata-dependent routes with 503 until metadata
+/// has loaded
+pub async fn require_metadata(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if state.metadata_loaded.load(std::sync::atomic::Ordering::Acquire)
+        || !depends_on_metadata(request.uri().path())
+    {
+        return next.run(request).await;
+    }
+    AppError::NotReady("Analysis metadata is still loading".to_string()).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::factories::analysis_metadata;
+
+    #[test]
+    fn test_depends_on_metadata() {
+        assert!(depends_on_metadata("/analyses"));
+        assert!(depends_on_metadata("/analyses/height/correlations"));
+        assert!(depends_on_metadata("/phenotype/height/qq/image"));
+        assert!(depends_on_metadata("/genes/phewas/ENSG00000169174/grouped"));
+        assert!(!depends_on_metadata("/analyses-loaded"));
+        assert!(!depends_on_metadata("/phenotype/height/qq"));
+        assert!(!depends_on_metadata("/genes/model/PCSK9"));
+        assert!(!depends_on_metadata("/health"));
+    }
+
+    #[test]
+    fn test_to_sql() {
+        let filter = MetadataFilter {
+            ancestry_group: Some("META".to_string()),
+            min_n_cases: Some(1000),
+            sort_by: Some(MetadataSortField::NCases),
+            descending: true,
+            limit: Some(50),
+            ..Default::default()
+        };
+        let sql = filter.to_sql();
+        assert!(sql.contains("WHERE lower(ancestry_group) = lower(?) AND n_cases >= ?"));
+        assert!(sql.contains("ORDER BY n_cases DESC NULLS LAST"));
+        assert!(sql.ends_with("LIMIT ?"));
+        assert!(MetadataFilter::default().to_sql().ends_with("ORDER BY analysis_id, ancestry_group"));
+
+        let filter = MetadataFilter {
+            category: Some("lab_measurement".to_string()),
+            has_gene_results: Some(false),
+            ..Default::default()
+        };
+        let sql = filter.to_sql();
+        assert!(sql.contains("WHERE category = ? AND (analysis_id, lower(ancestry_group)) NOT IN"));
+    }
+
+    #[test]
+    fn test_apply_filters_sorts_and_paginates() {
+        let records = vec![
+            AnalysisMetadata {
+                lambda_gc_acaf: Some(1.1),
+                ..analysis_metadata("a", "meta", "binary", 500)
+            },
+            analysis_metadata("b", "meta", "continuous", 5000),
+            AnalysisMetadata {
+                lambda_gc_acaf: Some(1.0),
+                ..analysis_metadata("c", "meta", "continuous", 3000)
+            },
+            AnalysisMetadata {
+                lambda_gc_acaf: Some(1.0),
+                ..analysis_metadata("c", "eur", "continuous", 2000)
+            },
+        ];
+
+        let filter = MetadataFilter {
+            ancestry_group: Some("META".to_string()),
+            trait_type: Some("continuous".to_string()),
+            sort_by: Some(MetadataSortField::NCases),
+            descending: true,
+            ..Default::default()
+        };
+        let ids: Vec<_> = filter.apply(&records).into_iter().map(|m| m.analysis_id).collect();
+        assert_eq!(ids, vec!["b", "c"]);
+
+        // Missing values sort last in either direction
+        let filter = MetadataFilter {
+            ancestry_group: Some("meta".to_string()),
+            sort_by: Some(MetadataSortField::LambdaGcAcaf),
+            ..Default::default()
+        };
+        let ids: Vec<_> = filter.apply(&records).into_iter().map(|m| m.analysis_id).collect();
+        assert_eq!(ids, vec!["c", "a", "b"]);
+
+        let filter = MetadataFilter {
+            min_n_cases: Some(1000),
+            offset: Some(1),
+            limit: Some(1),
+            ..Default::default()
+        };
+        let page = filter.apply(&records);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].analysis_id, "c");
+
+        // Gene results live in ClickHouse only, so memory can't answer for them
+        assert!(!filter.requires_clickhouse());
+        let filter = MetadataFilter {
+            has_gene_results: Some(false),
+            ..Default::default()
+        };
+        assert!(filter.requires_clickhouse());
+    }
+
+    #[test]
+    fn test_heritability_ranking() {
+        let mut records = vec![
+            analysis_metadata("a", "meta", "continuous", 500),
+            analysis_metadata("b", "meta", "continuous", 500),
+            analysis_metadata("c", "meta", "binary", 500),
+            analysis_metadata("d", "meta", "continuous", 500),
+        ];
+        records[0].heritability = Some(0.12);
+        records[1].heritability = Some(0.45);
+        records[2].heritability = Some(0.08);
+
+        let filter = MetadataFilter {
+            has_heritability: Some(true),
+            sort_by: Some(MetadataSortField::Heritability),
+            descending: true,
+            ..Default::default()
+        };
+        let ids: Vec<_> = filter.apply(&records).into_iter().map(|m| m.analysis_id).collect();
+        assert_eq!(ids, vec!["b", "a", "c"]);
+        assert!(filter
+            .to_sql()
+            .contains("WHERE heritability IS NOT NULL ORDER BY heritability DESC NULLS LAST"));
+    }
+}
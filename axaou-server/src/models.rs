@@ -5,8 +5,10 @@
 //! - `GeneModelsHds` for gene model data
 //! - `AnalysisAsset` for discovered analysis result assets
 
+use crate::genes::gene_test::GeneTest;
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use utoipa::ToSchema;
 
 // ============================================================================
 // API Response Models - Frontend-compatible types with nested structures
@@ -15,7 +17,7 @@ use std::fmt;
 /// Genomic locus (chromosome + position) for frontend compatibility.
 ///
 /// Matches the frontend's expected nested `locus` object structure.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Locus {
     pub contig: String,
     pub position: u32,
@@ -39,7 +41,7 @@ impl Locus {
 /// This struct matches the frontend's expected shape with:
 /// - `variant_id`: Generated string ID (chr1-12345-A-T)
 /// - `locus`: Nested object with contig + position
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct VariantAssociationApi {
     pub variant_id: String,
     pub locus: Locus,
@@ -58,7 +60,7 @@ pub struct VariantAssociationApi {
 /// Variant annotation data for API responses.
 ///
 /// Includes nested locus and computed variant_id.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct VariantAnnotationApi {
     pub variant_id: String,
     pub locus: Locus,
@@ -77,12 +79,36 @@ pub struct VariantAnnotationApi {
     pub polyphen2: Option<String>,
     pub amino_acids: Option<String>,
     pub lof: Option<String>,
+    /// In-silico predictors, present only when requested with `fields=`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cadd_phred: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revel_max: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spliceai_ds_max: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alphamissense_score: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alphamissense_class: Option<String>,
+    /// Per-ancestry frequencies, present on the single-variant endpoint
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ancestry_frequencies: Option<Vec<AncestryFrequency>>,
+}
+
+/// Allele count, number and frequency in one ancestry group
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AncestryFrequency {
+    /// Ancestry group: afr, amr, eas, eur, mid or sas
+    pub ancestry: String,
+    pub allele_count: Option<u32>,
+    pub allele_number: Option<u32>,
+    pub allele_frequency: Option<f64>,
 }
 
 /// Aggregated variant association data for API responses.
 ///
 /// Matches the frontend's expected shape for the top variants PheWAS table.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct AggregatedVariantApi {
     pub variant_id: String,
     pub locus: Locus,
@@ -105,7 +131,7 @@ pub struct AggregatedVariantApi {
 /// Gene association data for API responses.
 ///
 /// Field names match frontend GeneAssociationsHds type.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct GeneAssociationApi {
     pub gene_id: String,
     pub gene_symbol: String,
@@ -130,6 +156,14 @@ pub struct GeneAssociationApi {
     pub contig: String,
     /// Gene start position
     pub gene_start_position: i32,
+    /// MAC over the analysis sample size (see `genes::power`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub carrier_frequency: Option<f64>,
+    /// Carriers expected among cases (binary traits) or in the sample
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_carriers: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub power_class: Option<crate::genes::power::PowerClass>,
 }
 
 // ============================================================================
@@ -250,6 +284,10 @@ pub enum AnalysisAssetType {
     Gene,
     /// Expected P-values for gene results
     GeneExpP,
+    /// Stepwise conditional analysis results (one round per independent signal)
+    Conditional,
+    /// Fine-mapping (SuSiE/FINEMAP) PIPs and credible sets
+    CredibleSet,
 }
 
 impl AnalysisAssetType {
@@ -270,6 +308,18 @@ impl AnalysisAssetType {
             }
             (AnalysisAssetType::Gene, _) => "gene_results.ht",
             (AnalysisAssetType::GeneExpP, _) => "gene_expected_p.ht",
+            (AnalysisAssetType::Conditional, Some(SequencingType::Exomes)) => {
+                "exome_conditional_results.ht"
+            }
+            (AnalysisAssetType::Conditional, Some(SequencingType::Genomes)) => {
+                "genome_conditional_results.ht"
+            }
+            (AnalysisAssetType::CredibleSet, Some(SequencingType::Exomes)) => {
+                "exome_credible_sets.ht"
+            }
+            (AnalysisAssetType::CredibleSet, Some(SequencingType::Genomes)) => {
+                "genome_credible_sets.ht"
+            }
             // Fallback - shouldn't happen
             _ => "results.ht",
         }
@@ -294,6 +344,18 @@ impl AnalysisAssetType {
             }
             "gene_results.ht" => Some((AnalysisAssetType::Gene, None)),
             "gene_expected_p.ht" => Some((AnalysisAssetType::GeneExpP, None)),
+            "exome_conditional_results.ht" => {
+                Some((AnalysisAssetType::Conditional, Some(SequencingType::Exomes)))
+            }
+            "genome_conditional_results.ht" => {
+                Some((AnalysisAssetType::Conditional, Some(SequencingType::Genomes)))
+            }
+            "exome_credible_sets.ht" => {
+                Some((AnalysisAssetType::CredibleSet, Some(SequencingType::Exomes)))
+            }
+            "genome_credible_sets.ht" => {
+                Some((AnalysisAssetType::CredibleSet, Some(SequencingType::Genomes)))
+            }
             _ => None,
         }
     }
@@ -330,13 +392,33 @@ impl AnalysisAsset {
     }
 }
 
+/// Current schema version of the assets manifest (`assets.json`)
+pub const ASSETS_MANIFEST_VERSION: u32 = 1;
+
 /// Collection of discovered analysis assets
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AnalysisAssets {
+    /// Manifest schema version; 0 for manifests written before versioning
+    #[serde(default)]
+    pub version: u32,
+    /// When discovery produced this snapshot (RFC 3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated_at: Option<String>,
+    /// Dataset version the snapshot was discovered for (e.g., "414k")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dataset: Option<String>,
     pub assets: Vec<AnalysisAsset>,
 }
 
 impl AnalysisAssets {
+    /// Stamp the snapshot with the current manifest version and time
+    pub fn with_manifest_header(mut self, dataset: &str) -> Self {
+        self.version = ASSETS_MANIFEST_VERSION;
+        self.generated_at = Some(chrono::Utc::now().to_rfc3339());
+        self.dataset = Some(dataset.to_string());
+        self
+    }
+
     /// Filter assets by criteria
     pub fn filter(
         &self,
@@ -359,11 +441,48 @@ impl AnalysisAssets {
         ids.dedup();
         ids
     }
+
+    /// Compare against a newer snapshot, keyed by asset URI
+    pub fn diff(&self, newer: &AnalysisAssets) -> AssetDiff {
+        use std::collections::HashSet;
+
+        let old_uris: HashSet<&str> = self.assets.iter().map(|a| a.uri.as_str()).collect();
+        let new_uris: HashSet<&str> = newer.assets.iter().map(|a| a.uri.as_str()).collect();
+
+        AssetDiff {
+            added: newer
+                .assets
+                .iter()
+                .filter(|a| !old_uris.contains(a.uri.as_str()))
+                .cloned()
+                .collect(),
+            removed: self
+                .assets
+                .iter()
+                .filter(|a| !new_uris.contains(a.uri.as_str()))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// Assets added/removed between two discovery snapshots
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AssetDiff {
+    pub added: Vec<AnalysisAsset>,
+    pub removed: Vec<AnalysisAsset>,
+}
+
+impl AssetDiff {
+    /// True when both snapshots contain the same assets
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
 }
 
 /// Represents the analysis metadata served to the frontend.
 /// Corresponds to the TypeScript type `AnalysisMetadataHds`.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AnalysisMetadata {
     pub analysis_id: String,
     pub ancestry_group: String,
@@ -373,6 +492,11 @@ pub struct AnalysisMetadata {
     pub keep_pheno_burden: bool,
     pub keep_pheno_skat: bool,
     pub keep_pheno_skato: bool,
+    /// SNP heritability estimate, when available
+    pub heritability: Option<f64>,
+    pub heritability_se: Option<f64>,
+    /// Method of the heritability estimate ("ldsc" or "saige")
+    pub heritability_method: Option<String>,
     pub lambda_gc_acaf: Option<f64>,
     pub lambda_gc_exome: Option<f64>,
     pub lambda_gc_gene_burden_001: Option<f64>,
@@ -388,7 +512,7 @@ pub struct AnalysisMetadata {
 
 /// Represents a gene model served to the frontend.
 /// Corresponds to the TypeScript type `GeneModelsHds`.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct GeneModel {
     pub gene_id: String,
     pub symbol: String,
@@ -420,7 +544,7 @@ pub struct GeneModel {
 }
 
 /// Exon coordinates
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
 pub struct Exon {
     #[serde(default)]
     pub feature_type: String,
@@ -435,7 +559,7 @@ pub struct Exon {
 }
 
 /// Transcript information
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
 pub struct Transcript {
     #[serde(default)]
     pub transcript_id: String,
@@ -466,7 +590,7 @@ pub struct Transcript {
 }
 
 /// MANE Select transcript information
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
 pub struct ManeSelectTranscript {
     #[serde(default)]
     pub ensembl_id: String,
@@ -486,7 +610,7 @@ pub struct ManeSelectTranscript {
 
 /// Gene association result from burden/SKAT tests
 /// Matches the schema of gene_results.ht in per-phenotype directories
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct GeneAssociationResult {
     // Key fields
     pub gene_id: String,
@@ -519,8 +643,36 @@ pub struct GeneAssociationResult {
     pub pos: Option<i32>,
 }
 
+impl GeneAssociationResult {
+    /// Convert to the gene list row served from ClickHouse
+    pub fn to_api(self) -> GeneAssociationApi {
+        use crate::clickhouse::models::GeneAssociationRow;
+        GeneAssociationApi {
+            neg_log10_p: GeneAssociationRow::compute_neg_log10_p(self.pvalue),
+            neg_log10_p_burden: GeneAssociationRow::compute_neg_log10_p(self.pvalue_burden),
+            neg_log10_p_skat: GeneAssociationRow::compute_neg_log10_p(self.pvalue_skat),
+            gene_id: self.gene_id,
+            gene_symbol: self.gene_symbol,
+            annotation: self.annotation,
+            max_maf: self.max_maf,
+            analysis_id: self.analysis_id,
+            ancestry_group: self.ancestry_group,
+            pvalue: self.pvalue,
+            pvalue_burden: self.pvalue_burden,
+            pvalue_skat: self.pvalue_skat,
+            beta_burden: self.beta_burden,
+            mac: self.mac,
+            contig: self.chrom.unwrap_or_default(),
+            gene_start_position: self.pos.unwrap_or_default(),
+            carrier_frequency: None,
+            expected_carriers: None,
+            power_class: None,
+        }
+    }
+}
+
 /// Response wrapper for gene association queries
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct GeneAssociationResponse {
     pub gene_id: String,
     pub gene_symbol: String,
@@ -532,14 +684,18 @@ pub struct GeneAssociationResponse {
 pub struct GeneQueryParams {
     /// Filter by ancestry group (default: META only)
     pub ancestry: Option<AncestryGroup>,
+    /// Query every ancestry group (`ancestry=all`); overrides `ancestry`
+    pub all_ancestries: bool,
     /// Filter by annotation type (e.g., "pLoF", "missenseLC")
     pub annotation: Option<String>,
     /// Filter by max MAF (default: 0.001)
     pub max_maf: Option<f64>,
+    /// Mask results, Cauchy combination results, or both
+    pub test: GeneTest,
 }
 
 /// gnomAD constraint metrics for a gene
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct GnomadConstraint {
     pub gene: String,
     pub gene_id: String,
@@ -0,0 +1,225 @@
+//! OpenAPI specification for the AxAoU HTTP API
+//!
+//! Collects the `#[utoipa::path]` annotations from the handler modules into a
+//! single OpenAPI 3 document. The spec is served at `/api/openapi.json` and
+//! browsable through Swagger UI at `/api/docs`, so external consumers can
+//! generate clients without reading the Rust source.
+//!
+//! Only the JSON endpoints meant for external consumers are annotated; the
+//! spec's description says so, and names the route groups left out.
+
+use crate::api::{AnalysisCategory, AxaouConfig, FrontendConfig};
+use crate::clickhouse::models::{
+    KnownAssociationRow, LocusRow, LocusVariantExtendedRow, LocusVariantLdRow, LocusVariantRow,
+    QQRow,
+};
+use crate::correlations::{CorrelationMatrix, GeneticCorrelation};
+use crate::downloads::{DownloadAsset, DownloadFile, DownloadListing};
+use crate::error::ErrorResponse;
+use crate::genes::gene_test::GeneTest;
+use crate::genes::power::PowerClass;
+use crate::genes::routes::{GeneLofSummary, LofCarrierEstimate};
+use crate::liftover::{Build, LiftedVariant};
+use crate::phenotype::compare::{
+    CompareHit, CompareLocus, ComparedPhenotype, OverlapStats, PhenotypeComparison,
+};
+use crate::phenotype::gene_manhattan::GeneManhattanColumns;
+use crate::phenotype::conditional::{ConditionalResults, ConditionalSignal};
+use crate::phenotype::credible_sets::{CredibleSet, CredibleSetVariant, CredibleSets};
+use crate::phenotype::loci::{NearestGene, TopLocus};
+use crate::phenotype::locuszoom::{
+    LocusZoomGene, LocusZoomResponse, LocusZoomVariant, RecombinationPoint,
+};
+use crate::phenotype::shared_loci::{SharedLocus, SharedPhenotype};
+use crate::phenotype::significant::TopVariant;
+use crate::phenotype::thresholds::{SignificanceThreshold, SignificanceThresholds};
+use crate::metadata::{MetadataSortField, SortOrder};
+use crate::models::{
+    AggregatedVariantApi, AnalysisMetadata, AncestryFrequency, Exon, GeneAssociationApi, GeneModel,
+    GnomadConstraint, Locus, ManeSelectTranscript, Transcript, VariantAnnotationApi,
+    VariantAssociationApi,
+};
+use crate::response::{
+    EffectMetadata, EffectScale, EffectUnit, GeneAssociationLookup, ResponseFormat,
+    VariantAnnotationLookup, VariantAssociationLookup, VariantColumns,
+};
+use crate::variants::ld::LdProxy;
+use crate::variants::liftover::VariantLiftoverResponse;
+use crate::version::{BuildInfo, TableVersion, VersionInfo};
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "AxAoU Browser API",
+        description = "All by All (All of Us) browser API for phenotype, gene, and variant association results.\n\n\
+This document covers the stable JSON endpoints for external consumers: configuration and version, \
+analysis metadata and correlations, downloads, gene models and transcripts, per-phenotype loci, \
+LocusZoom, conditional results, credible sets, thresholds, significant and top variants, known hits, \
+comparisons, shared loci, the gene Manhattan and Q-Q data, gene PheWAS and LoF summaries, and \
+variant PheWAS, liftover and LD proxies.\n\n\
+Other routes are served but not documented here and may change without notice: health and readiness \
+checks, admin routes, htsget, plot data, images and server-side renders (Manhattan, forest, thumbnails, \
+overlays, region and locus plots), phenotype overviews, asset listings, search and symbol lookups, and \
+the browser's table endpoints for gene associations, variant annotations and associations, grouped and \
+interval PheWAS, top associations, and gene and phenotype summaries."
+    ),
+    paths(
+        crate::api::get_config,
+        crate::version::get_version,
+        crate::api::get_analyses,
+        crate::api::get_heritability_ranking,
+        crate::api::get_analysis_by_id,
+        crate::api::get_categories,
+        crate::correlations::get_analysis_correlations,
+        crate::correlations::get_correlation_matrix,
+        crate::downloads::get_downloads,
+        crate::downloads::get_download_tar,
+        crate::api::get_gene_model,
+        crate::api::get_gene_models_in_interval,
+        crate::api::search_gene_models,
+        crate::api::get_gene_transcripts,
+        crate::api::get_transcript,
+        crate::phenotype::loci::get_phenotype_loci,
+        crate::phenotype::loci::get_loci_bed,
+        crate::phenotype::loci::get_locus_variants,
+        crate::phenotype::loci::get_top_loci,
+        crate::phenotype::locuszoom::get_locus_zoom,
+        crate::phenotype::conditional::get_conditional_results,
+        crate::phenotype::credible_sets::get_credible_sets,
+        crate::phenotype::thresholds::get_thresholds,
+        crate::phenotype::significant::get_significant_variants,
+        crate::phenotype::significant::get_top_variants,
+        crate::phenotype::known_hits::get_known_hits,
+        crate::phenotype::compare::get_phenotype_comparison,
+        crate::phenotype::shared_loci::get_shared_loci,
+        crate::phenotype::gene_manhattan::get_gene_manhattan,
+        crate::phenotype::qq::get_qq_plot,
+        crate::genes::routes::get_gene_phewas,
+        crate::genes::routes::get_gene_lof_summary,
+        crate::variants::phewas::get_phewas_by_variant,
+        crate::variants::liftover::get_variant_liftover,
+        crate::variants::ld::get_ld_proxies,
+    ),
+    components(schemas(
+        ErrorResponse,
+        AxaouConfig,
+        FrontendConfig,
+        VersionInfo,
+        BuildInfo,
+        TableVersion,
+        AnalysisCategory,
+        AnalysisMetadata,
+        MetadataSortField,
+        SortOrder,
+        GeneticCorrelation,
+        CorrelationMatrix,
+        GeneModel,
+        Exon,
+        Transcript,
+        ManeSelectTranscript,
+        GnomadConstraint,
+        Locus,
+        GeneAssociationApi,
+        PowerClass,
+        GeneTest,
+        VariantAssociationApi,
+        VariantAnnotationApi,
+        AncestryFrequency,
+        AggregatedVariantApi,
+        LocusRow,
+        LocusVariantRow,
+        LocusVariantLdRow,
+        LocusVariantExtendedRow,
+        TopLocus,
+        NearestGene,
+        LocusZoomResponse,
+        LocusZoomVariant,
+        LocusZoomGene,
+        RecombinationPoint,
+        ConditionalResults,
+        ConditionalSignal,
+        CredibleSets,
+        CredibleSet,
+        CredibleSetVariant,
+        SignificanceThresholds,
+        SignificanceThreshold,
+        QQRow,
+        GeneAssociationLookup,
+        VariantAssociationLookup,
+        VariantAnnotationLookup,
+        EffectMetadata,
+        EffectUnit,
+        EffectScale,
+        Build,
+        LiftedVariant,
+        VariantLiftoverResponse,
+        LdProxy,
+        TopVariant,
+        KnownAssociationRow,
+        PhenotypeComparison,
+        ComparedPhenotype,
+        CompareHit,
+        CompareLocus,
+        OverlapStats,
+        SharedLocus,
+        SharedPhenotype,
+        GeneManhattanColumns,
+        GeneLofSummary,
+        LofCarrierEstimate,
+        ResponseFormat,
+        VariantColumns,
+        DownloadListing,
+        DownloadAsset,
+        DownloadFile,
+    )),
+    tags(
+        (name = "config", description = "Frontend configuration"),
+        (name = "analyses", description = "Analysis (phenotype) metadata"),
+        (name = "phenotype", description = "Per-phenotype loci, Manhattan and Q-Q data"),
+        (name = "genes", description = "Gene models and gene burden results"),
+        (name = "variants", description = "Variant annotations and associations"),
+    )
+)]
+pub struct ApiDoc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_includes_core_paths() {
+        let spec = ApiDoc::openapi();
+        let paths = &spec.paths.paths;
+        assert!(paths.contains_key("/api/analyses"));
+        assert!(paths.contains_key("/api/phenotype/{analysis_id}/loci"));
+        assert!(paths.contains_key("/api/genes/phewas/{gene_id}"));
+        assert!(paths.contains_key("/api/variants/{variant_id}/liftover"));
+        assert!(paths.contains_key("/api/transcripts/{transcript_id}"));
+        assert!(paths.contains_key("/api/genes/model/search"));
+        assert!(paths.contains_key("/api/phenotype/{analysis_id}/top-variants"));
+        assert!(paths.contains_key("/api/genes/{gene_id}/lof-summary"));
+        assert!(paths.contains_key("/api/phenotype/{analysis_id}/known-hits"));
+        assert!(paths.contains_key("/api/correlations/matrix"));
+        assert!(paths.contains_key("/api/analyses/heritability"));
+        assert!(paths.contains_key("/api/compare/manhattan"));
+        assert!(paths.contains_key("/api/phenotype/{analysis_id}/shared-loci"));
+        assert!(paths.contains_key("/api/variants/{variant_id}/ld"));
+        assert!(paths.contains_key("/api/phenotype/{analysis_id}/gene-manhattan"));
+        assert!(paths.contains_key("/api/downloads/{analysis_id}"));
+        assert!(paths.contains_key("/api/phenotype/{analysis_id}/loci.bed"));
+        assert!(paths.contains_key("/api/phenotype/{analysis_id}/loci/{locus_id}/locuszoom"));
+        assert!(paths.contains_key("/api/phenotype/{analysis_id}/loci/{locus_id}/conditional"));
+        assert!(paths.contains_key("/api/phenotype/{analysis_id}/loci/{locus_id}/credible-sets"));
+        assert!(paths.contains_key("/api/phenotype/{analysis_id}/thresholds"));
+        assert!(paths.contains_key("/api/version"));
+    }
+
+    #[test]
+    fn test_spec_serializes_to_json() {
+        let json = ApiDoc::openapi().to_json().unwrap();
+        assert!(json.contains("\"openapi\""));
+        assert!(json.contains("not documented here"));
+        assert!(json.contains("GeneAssociationLookup"));
+    }
+}
@@ -0,0 +1,399 @@
+//! Two-phenotype comparison handler
+//!
+//! Serves the data behind the mirrored Manhattan ("Miami") view: the
+//! significant hits of two phenotypes, the loci of both merged into shared
+//! regions so each hit can be keyed to one, and overlap statistics.
+
+use crate::api::AppState;
+use crate::clickhouse::models::{LocusRow, LOCUS_COLUMNS};
+use crate::clickhouse::xpos::make_variant_id;
+use crate::clickhouse::QueryExt;
+use crate::error::{AppError, ErrorResponse};
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+/// Query parameters for the comparison endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CompareQuery {
+    /// First analysis ID (plotted on top)
+    pub a: String,
+    /// Second analysis ID (plotted mirrored below)
+    pub b: String,
+    /// Ancestry group (default: "meta")
+    pub ancestry: Option<String>,
+    /// Sequencing type: "exome" or "genome" (default: both)
+    pub sequencing_type: Option<String>,
+    /// Maximum number of hits per phenotype (default: 20000, max: 50000)
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Row)]
+struct HitRow {
+    sequencing_type: String,
+    contig: String,
+    xpos: i64,
+    position: i32,
+    #[serde(rename = "ref")]
+    ref_allele: String,
+    alt: String,
+    pvalue: f64,
+    neg_log10_p: f32,
+    beta: Option<f64>,
+}
+
+/// Significant variant of one phenotype
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CompareHit {
+    pub variant_id: String,
+    pub sequencing_type: String,
+    pub xpos: i64,
+    pub pvalue: f64,
+    pub neg_log10_p: f32,
+    pub beta: Option<f64>,
+    /// Index into `PhenotypeComparison::loci`, when the hit falls in a locus
+    pub locus: Option<usize>,
+}
+
+/// Hits of one side of the comparison
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ComparedPhenotype {
+    pub analysis_id: String,
+    /// Phenotype description (display name applied)
+    pub description: String,
+    pub hits: Vec<CompareHit>,
+}
+
+/// Region covered by overlapping loci of either phenotype
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct CompareLocus {
+    pub contig: String,
+    pub xstart: i64,
+    pub xstop: i64,
+    /// Lead p-value of the first phenotype's loci in the region
+    pub a_lead_pvalue: Option<f64>,
+    /// Lead p-value of the second phenotype's loci in the region
+    pub b_lead_pvalue: Option<f64>,
+}
+
+impl CompareLocus {
+    /// Whether both phenotypes have a locus in the region
+    pub fn is_shared(&self) -> bool {
+        self.a_lead_pvalue.is_some() && self.b_lead_pvalue.is_some()
+    }
+}
+
+/// Overlap between the two phenotypes' loci and hits
+#[derive(Debug, Clone, Default, PartialEq, Serialize, ToSchema)]
+pub struct OverlapStats {
+    pub a_loci: usize,
+    pub b_loci: usize,
+    /// Regions with loci of both phenotypes
+    pub shared_loci: usize,
+    /// Shared regions over all regions
+    pub jaccard: f64,
+    /// Variants significant for both phenotypes
+    pub shared_variants: usize,
+    /// Shared variants with effects in the same direction
+    pub concordant_variants: usize,
+}
+
+/// Response of the comparison endpoint
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PhenotypeComparison {
+    pub ancestry: String,
+    pub a: ComparedPhenotype,
+    pub b: ComparedPhenotype,
+    /// Merged loci, ordered by position
+    pub loci: Vec<CompareLocus>,
+    pub overlap: OverlapStats,
+}
+
+/// Merge the loci of both phenotypes into non-overlapping regions
+fn merge_loci(a: &[LocusRow], b: &[LocusRow]) -> Vec<CompareLocus> {
+    let mut spans: Vec<(&LocusRow, bool)> = a
+        .iter()
+        .map(|l| (l, true))
+        .chain(b.iter().map(|l| (l, false)))
+        .collect();
+    spans.sort_by_key(|(l, _)| (l.xstart, l.xstop));
+
+    fn min_p(current: Option<f64>, p: f64) -> Option<f64> {
+        Some(current.map_or(p, |c| c.min(p)))
+    }
+
+    let mut merged: Vec<CompareLocus> = Vec::new();
+    for (locus, is_a) in spans {
+        let overlaps = merged.last().is_some_and(|last| locus.xstart <= last.xstop);
+        if !overlaps {
+            merged.push(CompareLocus {
+                contig: locus.contig.clone(),
+                xstart: locus.xstart,
+                xstop: locus.xstop,
+                a_lead_pvalue: None,
+                b_lead_pvalue: None,
+            });
+        }
+        let region = merged.last_mut().unwrap();
+        region.xstop = region.xstop.max(locus.xstop);
+        if is_a {
+            region.a_lead_pvalue = min_p(region.a_lead_pvalue, locus.lead_pvalue);
+        } else {
+            region.b_lead_pvalue = min_p(region.b_lead_pvalue, locus.lead_pvalue);
+        }
+    }
+    merged
+}
+
+/// Index of the merged locus containing `xpos`
+fn locus_index(loci: &[CompareLocus], xpos: i64) -> Option<usize> {
+    let i = loci.partition_point(|l| l.xstart <= xpos).checked_sub(1)?;
+    (xpos <= loci[i].xstop).then_some(i)
+}
+
+fn to_hits(rows: Vec<HitRow>, loci: &[CompareLocus]) -> Vec<CompareHit> {
+    rows.into_iter()
+        .map(|row| CompareHit {
+            variant_id: make_variant_id(
+                &row.contig,
+                row.position as u32,
+                &row.ref_allele,
+                &row.alt,
+            ),
+            sequencing_type: row.sequencing_type,
+            locus: locus_index(loci, row.xpos),
+            xpos: row.xpos,
+            pvalue: row.pvalue,
+            neg_log10_p: row.neg_log10_p,
+            beta: row.beta,
+        })
+        .collect()
+}
+
+fn overlap_stats(
+    a_loci: usize,
+    b_loci: usize,
+    loci: &[CompareLocus],
+    a: &[CompareHit],
+    b: &[CompareHit],
+) -> OverlapStats {
+    let shared_loci = loci.iter().filter(|l| l.is_shared()).count();
+    let b_betas: HashMap<&str, Option<f64>> =
+        b.iter().map(|h| (h.variant_id.as_str(), h.beta)).collect();
+
+    let mut shared_variants = 0;
+    let mut concordant_variants = 0;
+    for hit in a {
+        if let Some(b_beta) = b_betas.get(hit.variant_id.as_str()) {
+            shared_variants += 1;
+            if let (Some(x), Some(y)) = (hit.beta, b_beta) {
+                if x.signum() == y.signum() {
+                    concordant_variants += 1;
+                }
+            }
+        }
+    }
+
+    OverlapStats {
+        a_loci,
+        b_loci,
+        shared_loci,
+        jaccard: if loci.is_empty() {
+            0.0
+        } else {
+            shared_loci as f64 / loci.len() as f64
+        },
+        shared_variants,
+        concordant_variants,
+    }
+}
+
+async fn fetch_loci(
+    state: &AppState,
+    analysis_id: &str,
+    ancestry: &str,
+) -> Result<Vec<LocusRow>, AppError> {
+    let query = format!(
+        r#"
+        SELECT {}
+        FROM loci
+        WHERE phenotype = ? AND ancestry = ?
+        "#,
+        LOCUS_COLUMNS
+    );
+    Ok(state
+        .clickhouse
+        .query(&query)
+        .bind(analysis_id)
+        .bind(ancestry)
+        .fetch_all_with::<LocusRow>(&state.executor)
+        .await?)
+}
+
+async fn fetch_hits(
+    state: &AppState,
+    analysis_id: &str,
+    ancestry: &str,
+    sequencing_type: Option<&str>,
+    limit: u64,
+) -> Result<Vec<HitRow>, AppError> {
+    let query = format!(
+        r#"
+        SELECT sequencing_type, contig, xpos, position, ref, alt, pvalue, neg_log10_p, beta
+        FROM loci_variants
+        WHERE phenotype = ? AND ancestry = ?{} AND is_significant = true
+          AND (association_ac IS NULL OR association_ac >= 5)
+        ORDER BY pvalue ASC
+        LIMIT ?
+        "#,
+        if sequencing_type.is_some() {
+            " AND sequencing_type = ?"
+        } else {
+            ""
+        }
+    );
+    let mut q = state
+        .clickhouse
+        .query(&query)
+        .bind(analysis_id)
+        .bind(ancestry);
+    if let Some(sequencing_type) = sequencing_type {
+        q = q.bind(sequencing_type);
+    }
+    Ok(q.bind(limit)
+        .fetch_all_with::<HitRow>(&state.executor)
+        .await?)
+}
+
+/// GET /api/compare/manhattan?a=&b=
+///
+/// Significant hits of two phenotypes for a Miami plot, each keyed to the
+/// merged locus it falls in, with locus and variant overlap statistics.
+#[utoipa::path(
+    get,
+    path = "/api/compare/manhattan",
+    tag = "phenotype",
+    params(CompareQuery),
+    responses(
+        (status = 200, description = "Hits of both phenotypes keyed by merged loci", body = PhenotypeComparison),
+        (status = 400, description = "Invalid sequencing type or identical phenotypes", body = ErrorResponse)
+    )
+)]
+pub async fn get_phenotype_comparison(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CompareQuery>,
+) -> Result<Json<PhenotypeComparison>, AppError> {
+    if params.a == params.b {
+        return Err(AppError::BadRequest(
+            "a and b must be different phenotypes".to_string(),
+        ));
+    }
+    let sequencing_type = params.sequencing_type.as_deref();
+    if let Some(seq) = sequencing_type {
+        if seq != "exome" && seq != "genome" {
+            return Err(AppError::BadRequest(format!(
+                "Invalid sequencing_type '{}'. Expected exome or genome",
+                seq
+            )));
+        }
+    }
+    let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
+    let limit = params.limit.unwrap_or(20000).clamp(1, 50000);
+
+    let (a_loci, b_loci, a_rows, b_rows) = tokio::try_join!(
+        fetch_loci(&state, &params.a, &ancestry),
+        fetch_loci(&state, &params.b, &ancestry),
+        fetch_hits(&state, &params.a, &ancestry, sequencing_type, limit),
+        fetch_hits(&state, &params.b, &ancestry, sequencing_type, limit),
+    )?;
+
+    let loci = merge_loci(&a_loci, &b_loci);
+    let a_hits = to_hits(a_rows, &loci);
+    let b_hits = to_hits(b_rows, &loci);
+    let overlap = overlap_stats(a_loci.len(), b_loci.len(), &loci, &a_hits, &b_hits);
+
+    let metadata = state.metadata.read().await;
+    let description = |analysis_id: &str| {
+        let desc = metadata
+            .iter()
+            .find(|m| m.analysis_id == analysis_id)
+            .map(|m| m.description.as_str())
+            .unwrap_or(analysis_id);
+        crate::phenotype_display_names::apply_display_name(analysis_id, desc)
+    };
+
+    Ok(Json(PhenotypeComparison {
+        a: ComparedPhenotype {
+            description: description(&params.a),
+            analysis_id: params.a,
+            hits: a_hits,
+        },
+        b: ComparedPhenotype {
+            description: description(&params.b),
+            analysis_id: params.b,
+            hits: b_hits,
+        },
+        ancestry,
+        loci,
+        overlap,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::factories::locus;
+
+    fn hit(variant_id: &str, xpos: i64, beta: f64, loci: &[CompareLocus]) -> CompareHit {
+        CompareHit {
+            variant_id: variant_id.to_string(),
+            sequencing_type: "genome".to_string(),
+            xpos,
+            pvalue: 1e-10,
+            neg_log10_p: 10.0,
+            beta: Some(beta),
+            locus: locus_index(loci, xpos),
+        }
+    }
+
+    #[test]
+    fn test_merge_loci_and_overlap() {
+        let a = vec![
+            locus("a1", "chr1", 100, 200, 1e-20),
+            locus("a2", "chr1", 5_000, 5_100, 1e-9),
+        ];
+        let b = vec![
+            locus("b1", "chr1", 150, 300, 1e-12),
+            locus("b2", "chr2", 0, 100, 1e-8),
+        ];
+        let loci = merge_loci(&a, &b);
+        assert_eq!(loci.len(), 3);
+        assert_eq!((loci[0].xstart, loci[0].xstop), (1_000_000_100, 1_000_000_300));
+        assert_eq!(loci[0].a_lead_pvalue, Some(1e-20));
+        assert_eq!(loci[0].b_lead_pvalue, Some(1e-12));
+        assert!(loci[0].is_shared());
+        assert!(!loci[1].is_shared());
+
+        assert_eq!(locus_index(&loci, 1_000_000_250), Some(0));
+        assert_eq!(locus_index(&loci, 1_000_000_400), None);
+        assert_eq!(locus_index(&loci, 2_000_000_100), Some(2));
+
+        let a_hits = vec![
+            hit("chr1-120-A-G", 1_000_000_120, 0.2, &loci),
+            hit("chr1-5050-C-T", 1_000_005_050, 0.1, &loci),
+        ];
+        let b_hits = vec![hit("chr1-120-A-G", 1_000_000_120, -0.3, &loci)];
+        let stats = overlap_stats(a.len(), b.len(), &loci, &a_hits, &b_hits);
+        assert_eq!(stats.shared_loci, 1);
+        assert!((stats.jaccard - 1.0 / 3.0).abs() < 1e-12);
+        assert_eq!(stats.shared_variants, 1);
+        assert_eq!(stats.concordant_variants, 0);
+    }
+}
@@ -0,0 +1,187 @@
+//! Conditional analysis handler
+//!
+//! Serves the independent signals of a locus from `conditional_variants`,
+//! the stepwise conditional results loaded by `ingest variant-results`. Each
+//! round contributes one signal: its best variant, conditioned on the index
+//! variants of the previous rounds.
+
+use crate::api::AppState;
+use crate::clickhouse::xpos::make_variant_id_from_xpos;
+use crate::clickhouse::QueryExt;
+use crate::error::{AppError, ErrorResponse};
+use crate::phenotype::manhattan::locus_params;
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+/// Query parameters for the conditional results endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ConditionalQuery {
+    /// Ancestry group (default: "meta")
+    pub ancestry: Option<String>,
+    /// Sequencing type, "exome" or "genome" (default: "genome")
+    pub sequencing_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Row)]
+struct ConditionalRoundRow {
+    round: u8,
+    /// (xpos, ref, alt, beta, se) of the round's best variant
+    best: (i64, String, String, Option<f64>, Option<f64>),
+    pvalue: f64,
+    neg_log10_p: f32,
+    conditioned_on: Vec<String>,
+    variant_count: u64,
+}
+
+/// Independent signal found in one round of the stepwise analysis
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ConditionalSignal {
+    /// Round of the analysis, starting at 1 (unconditioned)
+    pub round: u8,
+    /// Index variant of the signal
+    pub variant_id: String,
+    pub pvalue: f64,
+    pub neg_log10_p: f32,
+    pub beta: Option<f64>,
+    pub se: Option<f64>,
+    /// Index variants of the earlier rounds this round was conditioned on
+    pub conditioned_on: Vec<String>,
+    /// Variants tested in the round
+    pub variant_count: u64,
+}
+
+/// Independent signals of one locus
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConditionalResults {
+    pub analysis_id: String,
+    pub locus_id: String,
+    pub ancestry: String,
+    pub sequencing_type: String,
+    /// Signals in round order; empty when no conditional analysis was run
+    pub signals: Vec<ConditionalSignal>,
+}
+
+fn conditional_signal(row: ConditionalRoundRow) -> ConditionalSignal {
+    let (xpos, ref_allele, alt, beta, se) = row.best;
+    ConditionalSignal {
+        round: row.round,
+        variant_id: make_variant_id_from_xpos(xpos, &ref_allele, &alt),
+        pvalue: row.pvalue,
+        neg_log10_p: row.neg_log10_p,
+        beta,
+        se,
+        conditioned_on: row.conditioned_on,
+        variant_count: row.variant_count,
+    }
+}
+
+/// GET /api/phenotype/:analysis_id/loci/:locus_id/conditional
+///
+/// Independent signals of a locus from the stepwise conditional analysis,
+/// one per round. Phenotypes without conditional results return an empty
+/// list.
+#[utoipa::path(
+    get,
+    path = "/api/phenotype/{analysis_id}/loci/{locus_id}/conditional",
+    tag = "phenotype",
+    params(
+        ("analysis_id" = String, Path, description = "Analysis (phenotype) ID"),
+        ("locus_id" = String, Path, description = "Locus ID"),
+        ConditionalQuery
+    ),
+    responses(
+        (status = 200, description = "Independent signals of the locus", body = ConditionalResults),
+        (status = 400, description = "Invalid ancestry or sequencing type", body = ErrorResponse)
+    )
+)]
+pub async fn get_conditional_results(
+    State(state): State<Arc<AppState>>,
+    Path((analysis_id, locus_id)): Path<(String, String)>,
+    Query(params): Query<ConditionalQuery>,
+) -> Result<Json<ConditionalResults>, AppError> {
+    let (ancestry, sequencing_type) =
+        locus_params(params.ancestry.as_deref(), params.sequencing_type.as_deref())?;
+
+    let dv = state.data_version.as_deref().unwrap_or("none");
+    let cache_key = format!(
+        "conditional:{}:{}:{}:{}:{}",
+        analysis_id, locus_id, ancestry, sequencing_type, dv
+    );
+    if let Some(cached_bytes) = state.api_cache.get(&cache_key).await {
+        let response: ConditionalResults =
+            serde_json::from_slice(&cached_bytes).map_err(|e| AppError::Internal(e.to_string()))?;
+        return Ok(Json(response));
+    }
+
+    let query = r#"
+        SELECT
+            round,
+            argMin(tuple(xpos, ref, alt, beta, se), pvalue) AS best,
+            min(pvalue) AS pvalue,
+            max(neg_log10_p) AS neg_log10_p,
+            any(conditioned_on) AS conditioned_on,
+            count() AS variant_count
+        FROM conditional_variants
+        WHERE phenotype = ? AND ancestry = ? AND sequencing_type = ? AND locus_id = ?
+        GROUP BY round
+        ORDER BY round
+    "#;
+    let rows = state
+        .clickhouse
+        .query(query)
+        .bind(&analysis_id)
+        .bind(&ancestry)
+        .bind(&sequencing_type)
+        .bind(&locus_id)
+        .fetch_all_with::<ConditionalRoundRow>(&state.executor)
+        .await?;
+
+    let response = ConditionalResults {
+        analysis_id,
+        locus_id,
+        ancestry,
+        sequencing_type,
+        signals: rows.into_iter().map(conditional_signal).collect(),
+    };
+
+    if let Ok(bytes) = serde_json::to_vec(&response) {
+        state.api_cache.insert(cache_key, bytes).await;
+    }
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conditional_signal() {
+        let row = ConditionalRoundRow {
+            round: 2,
+            best: (
+                2_000_012_345,
+                "C".to_string(),
+                "T".to_string(),
+                Some(-0.2),
+                None,
+            ),
+            pvalue: 3e-10,
+            neg_log10_p: 9.5,
+            conditioned_on: vec!["chr2-10000-A-G".to_string()],
+            variant_count: 812,
+        };
+        let signal = conditional_signal(row);
+        assert_eq!(signal.round, 2);
+        assert_eq!(signal.variant_id, "chr2-12345-C-T");
+        assert_eq!(signal.beta, Some(-0.2));
+        assert_eq!(signal.se, None);
+        assert_eq!(signal.conditioned_on, vec!["chr2-10000-A-G"]);
+    }
+}
@@ -0,0 +1,262 @@
+//! Fine-mapping credible set handler
+//!
+//! Serves the SuSiE/FINEMAP results of a locus from `credible_sets`, loaded
+//! by `ingest variant-results`. Also provides the PIP lookup the locus
+//! variant and LocusZoom handlers merge into their variants.
+
+use crate::api::AppState;
+use crate::clickhouse::xpos::make_variant_id_from_xpos;
+use crate::clickhouse::QueryExt;
+use crate::error::{AppError, ErrorResponse};
+use crate::phenotype::manhattan::locus_params;
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+/// Query parameters for the credible sets endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CredibleSetsQuery {
+    /// Ancestry group (default: "meta")
+    pub ancestry: Option<String>,
+    /// Sequencing type, "exome" or "genome" (default: "genome")
+    pub sequencing_type: Option<String>,
+}
+
+/// Fine-mapped variant of a locus
+#[derive(Debug, Clone, Deserialize, Row)]
+pub(crate) struct PipRow {
+    pub xpos: i64,
+    #[serde(rename = "ref")]
+    pub ref_allele: String,
+    pub alt: String,
+    pub pip: f64,
+    pub cs_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Deserialize, Row)]
+struct CredibleSetRow {
+    method: String,
+    cs_id: i32,
+    cs_coverage: Option<f32>,
+    xpos: i64,
+    #[serde(rename = "ref")]
+    ref_allele: String,
+    alt: String,
+    pip: f64,
+}
+
+/// Variant of a credible set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CredibleSetVariant {
+    pub variant_id: String,
+    pub position: u32,
+    /// Posterior inclusion probability
+    pub pip: f64,
+}
+
+/// One credible set of a locus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CredibleSet {
+    /// Fine-mapping method, "susie" or "finemap"
+    pub method: String,
+    /// Index of the set within the locus
+    pub cs_id: i32,
+    /// Coverage target of the set (e.g. 0.95)
+    pub coverage: Option<f32>,
+    pub size: usize,
+    /// Sum of the PIPs of the set's variants
+    pub total_pip: f64,
+    /// Variant with the highest PIP
+    pub lead_variant: String,
+    pub lead_pip: f64,
+    /// Variants by decreasing PIP
+    pub variants: Vec<CredibleSetVariant>,
+}
+
+/// Credible sets of one locus
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CredibleSets {
+    pub analysis_id: String,
+    pub locus_id: String,
+    pub ancestry: String,
+    pub sequencing_type: String,
+    /// Empty when the locus was not fine-mapped
+    pub credible_sets: Vec<CredibleSet>,
+}
+
+/// Group rows ordered by (method, cs_id, pip desc) into credible sets
+fn group_credible_sets(rows: Vec<CredibleSetRow>) -> Vec<CredibleSet> {
+    let mut sets: Vec<CredibleSet> = Vec::new();
+    for row in rows {
+        let variant = CredibleSetVariant {
+            variant_id: make_variant_id_from_xpos(row.xpos, &row.ref_allele, &row.alt),
+            position: (row.xpos % 1_000_000_000) as u32,
+            pip: row.pip,
+        };
+        match sets.last_mut() {
+            Some(set) if set.method == row.method && set.cs_id == row.cs_id => {
+                set.size += 1;
+                set.total_pip += row.pip;
+                if row.pip > set.lead_pip {
+                    set.lead_variant = variant.variant_id.clone();
+                    set.lead_pip = row.pip;
+                }
+                set.variants.push(variant);
+            }
+            _ => sets.push(CredibleSet {
+                method: row.method,
+                cs_id: row.cs_id,
+                coverage: row.cs_coverage,
+                size: 1,
+                total_pip: row.pip,
+                lead_variant: variant.variant_id.clone(),
+                lead_pip: row.pip,
+                variants: vec![variant],
+            }),
+        }
+    }
+    sets
+}
+
+/// PIPs of the fine-mapped variants of a locus
+///
+/// Empty when the locus was not fine-mapped or `credible_sets` is missing,
+/// so callers can merge the result unconditionally.
+pub(crate) async fn fetch_locus_pips(
+    state: &AppState,
+    analysis_id: &str,
+    locus_id: &str,
+    ancestry: &str,
+    sequencing_type: &str,
+) -> Vec<PipRow> {
+    let query = r#"
+        SELECT xpos, ref, alt, pip, cs_id
+        FROM credible_sets
+        WHERE phenotype = ? AND ancestry = ? AND sequencing_type = ? AND locus_id = ?
+    "#;
+    match state
+        .clickhouse
+        .query(query)
+        .bind(analysis_id)
+        .bind(ancestry)
+        .bind(sequencing_type)
+        .bind(locus_id)
+        .fetch_all_with::<PipRow>(&state.executor)
+        .await
+    {
+        Ok(rows) => rows,
+        // Typically credible_sets has not been ingested
+        Err(e) => {
+            tracing::warn!("PIP lookup of locus {} failed: {}", locus_id, e);
+            Vec::new()
+        }
+    }
+}
+
+/// GET /api/phenotype/:analysis_id/loci/:locus_id/credible-sets
+///
+/// Fine-mapping credible sets of a locus, each with its variants by
+/// decreasing PIP. Loci that were not fine-mapped return an empty list.
+#[utoipa::path(
+    get,
+    path = "/api/phenotype/{analysis_id}/loci/{locus_id}/credible-sets",
+    tag = "phenotype",
+    params(
+        ("analysis_id" = String, Path, description = "Analysis (phenotype) ID"),
+        ("locus_id" = String, Path, description = "Locus ID"),
+        CredibleSetsQuery
+    ),
+    responses(
+        (status = 200, description = "Credible sets of the locus", body = CredibleSets),
+        (status = 400, description = "Invalid ancestry or sequencing type", body = ErrorResponse)
+    )
+)]
+pub async fn get_credible_sets(
+    State(state): State<Arc<AppState>>,
+    Path((analysis_id, locus_id)): Path<(String, String)>,
+    Query(params): Query<CredibleSetsQuery>,
+) -> Result<Json<CredibleSets>, AppError> {
+    let (ancestry, sequencing_type) =
+        locus_params(params.ancestry.as_deref(), params.sequencing_type.as_deref())?;
+
+    let dv = state.data_version.as_deref().unwrap_or("none");
+    let cache_key = format!(
+        "credible_sets:{}:{}:{}:{}:{}",
+        analysis_id, locus_id, ancestry, sequencing_type, dv
+    );
+    if let Some(cached_bytes) = state.api_cache.get(&cache_key).await {
+        let response: CredibleSets =
+            serde_json::from_slice(&cached_bytes).map_err(|e| AppError::Internal(e.to_string()))?;
+        return Ok(Json(response));
+    }
+
+    let query = r#"
+        SELECT method, assumeNotNull(cs_id) AS cs_id, cs_coverage, xpos, ref, alt, pip
+        FROM credible_sets
+        WHERE phenotype = ? AND ancestry = ? AND sequencing_type = ? AND locus_id = ?
+          AND cs_id IS NOT NULL
+        ORDER BY method, cs_id, pip DESC, xpos
+    "#;
+    let rows = state
+        .clickhouse
+        .query(query)
+        .bind(&analysis_id)
+        .bind(&ancestry)
+        .bind(&sequencing_type)
+        .bind(&locus_id)
+        .fetch_all_with::<CredibleSetRow>(&state.executor)
+        .await?;
+
+    let response = CredibleSets {
+        analysis_id,
+        locus_id,
+        ancestry,
+        sequencing_type,
+        credible_sets: group_credible_sets(rows),
+    };
+
+    if let Ok(bytes) = serde_json::to_vec(&response) {
+        state.api_cache.insert(cache_key, bytes).await;
+    }
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(method: &str, cs_id: i32, position: i64, pip: f64) -> CredibleSetRow {
+        CredibleSetRow {
+            method: method.to_string(),
+            cs_id,
+            cs_coverage: Some(0.95),
+            xpos: 3_000_000_000 + position,
+            ref_allele: "A".to_string(),
+            alt: "C".to_string(),
+            pip,
+        }
+    }
+
+    #[test]
+    fn test_group_credible_sets() {
+        let sets = group_credible_sets(vec![
+            row("susie", 1, 500, 0.7),
+            row("susie", 1, 400, 0.25),
+            row("susie", 2, 900, 0.99),
+        ]);
+        assert_eq!(sets.len(), 2);
+        assert_eq!(sets[0].size, 2);
+        assert!((sets[0].total_pip - 0.95).abs() < 1e-9);
+        assert_eq!(sets[0].lead_variant, "chr3-500-A-C");
+        assert_eq!(sets[0].variants[1].position, 400);
+        assert_eq!(sets[1].cs_id, 2);
+        assert_eq!(sets[1].lead_pip, 0.99);
+        assert!(group_credible_sets(Vec::new()).is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+//! Gene-level Manhattan handler
+//!
+//! Serves every gene burden result of a phenotype for an interactive gene
+//! Manhattan, as parallel arrays rather than objects. Unlike the gene overlay
+//! in [`crate::phenotype::manhattan`], results are not cut at p < 0.05 or
+//! truncated to the top 500 genes.
+
+use crate::api::AppState;
+use crate::clickhouse::QueryExt;
+use crate::error::{AppError, ErrorResponse};
+use crate::genes::gene_test::GeneTest;
+use crate::genomics::Contig;
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+/// -log10(p) reported for p-values that underflowed to 0
+const MAX_NEG_LOG10_P: f64 = 350.0;
+
+/// Query parameters for the gene Manhattan endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GeneManhattanQuery {
+    /// Ancestry group (default: "meta")
+    pub ancestry: Option<String>,
+    /// Burden set ("pLoF", "missenseLC", "synonymous"); default: the most
+    /// significant set per gene
+    pub annotation: Option<String>,
+    /// Maximum MAF of the burden test (default: 0.001)
+    pub max_maf: Option<f64>,
+    /// "mask" (default), "cauchy" for the Cauchy combination (ignores
+    /// `max_maf`), or "all"
+    #[serde(default)]
+    pub test: GeneTest,
+    /// P-value field: "pvalue" (SKAT-O, default), "pvalue_burden" or "pvalue_skat"
+    pub pvalue_field: Option<String>,
+    /// Restrict to one chromosome, e.g. "chr1"
+    pub contig: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Row)]
+struct GenePointRow {
+    gene_id: String,
+    symbol: String,
+    best_annotation: String,
+    gene_xpos: i64,
+    min_pvalue: f64,
+}
+
+/// Gene Manhattan points as parallel arrays, ordered by genomic position
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct GeneManhattanColumns {
+    pub analysis_id: String,
+    pub ancestry: String,
+    pub max_maf: f64,
+    pub pvalue_field: String,
+    /// Number of genes (length of every array)
+    pub count: usize,
+    pub gene_id: Vec<String>,
+    pub gene_symbol: Vec<String>,
+    /// Burden set each point was taken from
+    pub annotation: Vec<String>,
+    /// Gene start position as xpos
+    pub xpos: Vec<i64>,
+    pub neg_log10_p: Vec<f64>,
+}
+
+impl GeneManhattanColumns {
+    fn push(&mut self, row: GenePointRow) {
+        self.count += 1;
+        self.gene_id.push(row.gene_id);
+        self.gene_symbol.push(row.symbol);
+        self.annotation.push(row.best_annotation);
+        self.xpos.push(row.gene_xpos);
+        self.neg_log10_p.push(if row.min_pvalue > 0.0 {
+            -row.min_pvalue.log10()
+        } else {
+            MAX_NEG_LOG10_P
+        });
+    }
+}
+
+/// Validate a `pvalue_field` parameter against the gene_associations columns
+fn pvalue_column(field: Option<&str>) -> Result<&'static str, AppError> {
+    match field.unwrap_or("pvalue") {
+        "pvalue" => Ok("pvalue"),
+        "pvalue_burden" => Ok("pvalue_burden"),
+        "pvalue_skat" => Ok("pvalue_skat"),
+        other => Err(AppError::BadRequest(format!(
+            "Invalid pvalue_field '{}'. Expected pvalue, pvalue_burden or pvalue_skat",
+            other
+        ))),
+    }
+}
+
+/// GET /api/phenotype/:analysis_id/gene-manhattan
+///
+/// One point per gene (its most significant burden set unless `annotation`
+/// is given), as columnar JSON. Cached per data version.
+#[utoipa::path(
+    get,
+    path = "/api/phenotype/{analysis_id}/gene-manhattan",
+    tag = "phenotype",
+    params(("analysis_id" = String, Path, description = "Analysis (phenotype) ID"), GeneManhattanQuery),
+    responses(
+        (status = 200, description = "Gene points as parallel arrays", body = GeneManhattanColumns),
+        (status = 400, description = "Invalid pvalue_field or contig", body = ErrorResponse)
+    )
+)]
+pub async fn get_gene_manhattan(
+    State(state): State<Arc<AppState>>,
+    Path(analysis_id): Path<String>,
+    Query(params): Query<GeneManhattanQuery>,
+) -> Result<Json<GeneManhattanColumns>, AppError> {
+    let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
+    let max_maf = params.max_maf.unwrap_or(0.001);
+    let column = pvalue_column(params.pvalue_field.as_deref())?;
+    let contig = params
+        .contig
+        .as_deref()
+        .map(str::parse::<Contig>)
+        .transpose()?;
+
+    let dv = state.data_version.as_deref().unwrap_or("none");
+    let cache_key = format!(
+        "gene_manhattan:{}:{}:{:?}:{}:{:?}:{}:{:?}:{}",
+        analysis_id, ancestry, params.annotation, max_maf, params.test, column, contig, dv
+    );
+    if let Some(cached_bytes) = state.api_cache.get(&cache_key).await {
+        let columns: GeneManhattanColumns =
+            serde_json::from_slice(&cached_bytes).map_err(|e| AppError::Internal(e.to_string()))?;
+        return Ok(Json(columns));
+    }
+
+    let (test_filter, test_max_maf) = params.test.sql(Some(max_maf));
+    let mut filters = String::new();
+    if params.annotation.is_some() {
+        filters.push_str(" AND annotation = ?");
+    }
+    if contig.is_some() {
+        filters.push_str(" AND xpos >= ? AND xpos < ?");
+    }
+    let query = format!(
+        r#"
+        SELECT
+            gene_id,
+            argMin(gene_symbol, {column}) AS symbol,
+            argMin(annotation, {column}) AS best_annotation,
+            argMin(xpos, {column}) AS gene_xpos,
+            assumeNotNull(min({column})) AS min_pvalue
+        FROM gene_associations
+        WHERE phenotype = ? AND ancestry = ? AND {test_filter}
+          AND {column} IS NOT NULL{filters}
+        GROUP BY gene_id
+        ORDER BY gene_xpos, gene_id
+        "#,
+        column = column,
+        test_filter = test_filter,
+        filters = filters
+    );
+
+    let mut q = state
+        .clickhouse
+        .query(&query)
+        .bind(&analysis_id)
+        .bind(&ancestry);
+    if let Some(max_maf) = test_max_maf {
+        q = q.bind(max_maf);
+    }
+    if let Some(annotation) = &params.annotation {
+        q = q.bind(annotation);
+    }
+    if let Some(contig) = contig {
+        q = q.bind(contig.xpos(0)).bind(contig.xpos(0) + 1_000_000_000);
+    }
+    let rows = q.fetch_all_with::<GenePointRow>(&state.executor).await?;
+
+    let mut columns = GeneManhattanColumns {
+        analysis_id,
+        ancestry,
+        max_maf,
+        pvalue_field: column.to_string(),
+        ..Default::default()
+    };
+    for row in rows {
+        columns.push(row);
+    }
+
+    if let Ok(bytes) = serde_json::to_vec(&columns) {
+        state.api_cache.insert(cache_key, bytes).await;
+    }
+    Ok(Json(columns))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_columns() {
+        let mut columns = GeneManhattanColumns::default();
+        for (gene_id, pvalue) in [("ENSG1", 1e-8), ("ENSG2", 0.0)] {
+            columns.push(GenePointRow {
+                gene_id: gene_id.to_string(),
+                symbol: gene_id.to_lowercase(),
+                best_annotation: "pLoF".to_string(),
+                gene_xpos: 1_000_000_100,
+                min_pvalue: pvalue,
+            });
+        }
+        assert_eq!(columns.count, 2);
+        assert_eq!(columns.gene_id, vec!["ENSG1", "ENSG2"]);
+        assert!((columns.neg_log10_p[0] - 8.0).abs() < 1e-9);
+        assert_eq!(columns.neg_log10_p[1], MAX_NEG_LOG10_P);
+
+        assert_eq!(pvalue_column(None).unwrap(), "pvalue");
+        assert_eq!(pvalue_column(Some("pvalue_skat")).unwrap(), "pvalue_skat");
+        assert!(pvalue_column(Some("beta_burden")).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! Known-hits overlay handler
+//!
+//! Serves previously reported associations (GWAS Catalog, loaded by
+//! `ingest known-hits`) so the Manhattan view can mark known loci. A
+//! phenotype's catalog traits come from `known_association_traits`.
+
+use crate::api::AppState;
+use crate::clickhouse::models::KnownAssociationRow;
+use crate::clickhouse::QueryExt;
+use crate::error::{AppError, ErrorResponse};
+use crate::genomics::resolve_region;
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::IntoParams;
+
+/// Columns of `known_associations`, in `KnownAssociationRow` order
+const KNOWN_ASSOCIATION_COLUMNS: &str = "source, study_accession, pubmed_id, reported_trait, \
+    mapped_traits, efo_ids, rsid, contig, position, xpos, risk_allele, pvalue, reported_genes";
+
+/// Query parameters for the known-hits endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct KnownHitsQuery {
+    /// Region to search (chr:start-end, chromosome or gene); whole genome if absent
+    pub interval: Option<String>,
+    /// Return every known association in the region, not just the phenotype's traits
+    pub all: Option<bool>,
+    /// Maximum reported p-value
+    pub max_p: Option<f64>,
+    /// Maximum number of results (default: 1000, max: 10000)
+    pub limit: Option<u64>,
+}
+
+/// GET /api/phenotype/:analysis_id/known-hits
+///
+/// Previously reported associations for the phenotype's mapped EFO traits,
+/// most significant first. `all=true` ignores the trait mapping, for
+/// phenotypes without one.
+#[utoipa::path(
+    get,
+    path = "/api/phenotype/{analysis_id}/known-hits",
+    tag = "phenotype",
+    params(("analysis_id" = String, Path, description = "Analysis (phenotype) ID"), KnownHitsQuery),
+    responses(
+        (status = 200, description = "Known associations, most significant first", body = Vec<KnownAssociationRow>),
+        (status = 400, description = "Invalid interval", body = ErrorResponse)
+    )
+)]
+pub async fn get_known_hits(
+    State(state): State<Arc<AppState>>,
+    Path(analysis_id): Path<String>,
+    Query(params): Query<KnownHitsQuery>,
+) -> Result<Json<Vec<KnownAssociationRow>>, AppError> {
+    let region = match params.interval.as_deref() {
+        Some(interval) => Some(resolve_region(state.gene_models.as_ref(), interval).await?),
+        None => None,
+    };
+    let by_trait = !params.all.unwrap_or(false);
+    let limit = params.limit.unwrap_or(1000).clamp(1, 10000);
+
+    let mut conditions = Vec::new();
+    if region.is_some() {
+        conditions.push("xpos >= ? AND xpos <= ?");
+    }
+    if by_trait {
+        conditions.push(
+            "hasAny(efo_ids, (SELECT groupArray(efo_id) FROM known_association_traits WHERE phenotype = ?))",
+        );
+    }
+    if params.max_p.is_some() {
+        conditions.push("pvalue <= ?");
+    }
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+
+    let query = format!(
+        r#"
+        SELECT {}
+        FROM known_associations
+        {}
+        ORDER BY pvalue ASC
+        LIMIT ?
+        "#,
+        KNOWN_ASSOCIATION_COLUMNS, where_clause
+    );
+
+    let mut q = state.clickhouse.query(&query);
+    if let Some(region) = &region {
+        let (xpos_start, xpos_end) = region.xpos_range();
+        q = q.bind(xpos_start).bind(xpos_end);
+    }
+    if by_trait {
+        q = q.bind(&analysis_id);
+    }
+    if let Some(max_p) = params.max_p {
+        q = q.bind(max_p);
+    }
+
+    let rows = q
+        .bind(limit)
+        .fetch_all_with::<KnownAssociationRow>(&state.executor)
+        .await?;
+    Ok(Json(rows))
+}
@@ -4,17 +4,28 @@
 //! for Manhattan plot rendering.
 
 use crate::api::AppState;
-use crate::clickhouse::models::{LocusRow, LocusVariantRow};
-use crate::error::AppError;
+use crate::clickhouse::QueryExt;
+use crate::clickhouse::models::{LocusRow, LocusVariantLdRow, LOCUS_COLUMNS};
+use crate::clickhouse::xpos::parse_variant_id;
+use crate::error::{AppError, ErrorResponse};
+use crate::phenotype::credible_sets::{fetch_locus_pips, PipRow};
+use crate::phenotype::manhattan::parse_gcs_uri;
+use crate::phenotype::render::YScale;
+use crate::response::ResponseFormat;
 use axum::{
     extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
 
 /// Query parameters for loci list endpoint
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct LociQuery {
     /// Ancestry group filter (default: "meta")
     pub ancestry: Option<String>,
@@ -24,6 +35,16 @@ pub struct LociQuery {
 ///
 /// Returns all loci for a phenotype with their metadata including
 /// lead variant, variant counts, and plot URIs.
+#[utoipa::path(
+    get,
+    path = "/api/phenotype/{analysis_id}/loci",
+    tag = "phenotype",
+    params(("analysis_id" = String, Path, description = "Analysis (phenotype) ID"), LociQuery),
+    responses(
+        (status = 200, description = "Loci for the phenotype", body = Vec<LocusRow>),
+        (status = 500, description = "ClickHouse query failed", body = ErrorResponse)
+    )
+)]
 pub async fn get_phenotype_loci(
     State(state): State<Arc<AppState>>,
     Path(analysis_id): Path<String>,
@@ -31,65 +52,493 @@ pub async fn get_phenotype_loci(
 ) -> Result<Json<Vec<LocusRow>>, AppError> {
     let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
 
-    let query = r#"
-        SELECT
-            locus_id, phenotype, ancestry, contig, start, stop,
-            xstart, xstop, source, lead_variant, lead_pvalue,
-            exome_count, genome_count, plot_gcs_uri
+    let query = format!(
+        r#"
+        SELECT {}
         FROM loci
         WHERE phenotype = ? AND ancestry = ?
-    "#;
+        "#,
+        LOCUS_COLUMNS
+    );
 
     let rows = state
         .clickhouse
-        .query(query)
+        .query(&query)
         .bind(&analysis_id)
         .bind(&ancestry)
-        .fetch_all::<LocusRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with::<LocusRow>(&state.executor)
+        .await?;
 
     Ok(Json(rows))
 }
 
+/// Query parameters for the loci BED export
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LociBedQuery {
+    /// Ancestry group filter (default: "meta")
+    pub ancestry: Option<String>,
+    /// Start with a UCSC `track` line (default: true)
+    pub track: Option<bool>,
+}
+
+/// BED score per unit of -log10(p)
+const BED_SCORE_PER_NEG_LOG10_P: f64 = 100.0;
+
+/// Render loci as BED6, sorted by position
+///
+/// Name is the lead variant and score the lead -log10(p) × 100, capped at the
+/// BED maximum of 1000, so `useScore` shading separates loci between
+/// suggestive and p = 1e-10. Loci with a lead p-value of 0 score 1000.
+pub(crate) fn loci_bed(analysis_id: &str, ancestry: &str, mut loci: Vec<LocusRow>, track: bool) -> String {
+    loci.sort_by_key(|l| (l.xstart, l.xstop));
+    let mut bed = String::new();
+    if track {
+        bed.push_str(&format!(
+            "track name=\"{id} loci\" description=\"{id} ({ancestry}) significant loci\" useScore=1\n",
+            id = analysis_id.replace('"', "'"),
+            ancestry = ancestry.replace('"', "'")
+        ));
+    }
+    for locus in loci {
+        let score = if locus.lead_pvalue > 0.0 {
+            (-locus.lead_pvalue.log10() * BED_SCORE_PER_NEG_LOG10_P).round().clamp(0.0, 1000.0) as u32
+        } else {
+            1000
+        };
+        let name = if locus.lead_variant.is_empty() {
+            locus.locus_id.as_str()
+        } else {
+            locus.lead_variant.as_str()
+        };
+        let contig = crate::genomics::Contig::parse(&locus.contig)
+            .map(|c| c.chr().to_string())
+            .unwrap_or_else(|| locus.contig.clone());
+        bed.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t.\n",
+            contig,
+            (locus.start - 1).max(0),
+            locus.stop,
+            name,
+            score
+        ));
+    }
+    bed
+}
+
+/// GET /api/phenotype/:analysis_id/loci.bed
+///
+/// Loci of a phenotype as a BED track for UCSC or IGV (0-based starts). Convert
+/// with `bedToBigBed` for a bigBed.
+#[utoipa::path(
+    get,
+    path = "/api/phenotype/{analysis_id}/loci.bed",
+    tag = "phenotype",
+    params(("analysis_id" = String, Path, description = "Analysis (phenotype) ID"), LociBedQuery),
+    responses(
+        (status = 200, description = "BED6 track of the loci", content_type = "text/plain"),
+        (status = 500, description = "ClickHouse query failed", body = ErrorResponse)
+    )
+)]
+pub async fn get_loci_bed(
+    State(state): State<Arc<AppState>>,
+    Path(analysis_id): Path<String>,
+    Query(params): Query<LociBedQuery>,
+) -> Result<Response, AppError> {
+    let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
+
+    let query = format!(
+        r#"
+        SELECT {}
+        FROM loci
+        WHERE phenotype = ? AND ancestry = ?
+        "#,
+        LOCUS_COLUMNS
+    );
+    let loci = state
+        .clickhouse
+        .query(&query)
+        .bind(&analysis_id)
+        .bind(&ancestry)
+        .fetch_all_with::<LocusRow>(&state.executor)
+        .await?;
+
+    let bed = loci_bed(&analysis_id, &ancestry, loci, params.track.unwrap_or(true));
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                "text/plain; charset=utf-8".to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"{}_{}_loci.bed\"", analysis_id, ancestry),
+            ),
+        ],
+        bed,
+    )
+        .into_response())
+}
+
 /// Query parameters for locus variants endpoint
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct LocusVariantsQuery {
     /// Ancestry group filter (default: "meta")
     pub ancestry: Option<String>,
     /// Sequencing type (required: "exome" or "genome")
     pub sequencing_type: String,
+    /// Minimum r² with the lead variant for `lead_r2` to be set (default: 0.6)
+    pub ld_r2: Option<f32>,
+    /// Response layout: "rows" (default), "columnar", "arrow" or "parquet"
+    pub format: Option<ResponseFormat>,
 }
 
 /// GET /api/phenotype/:analysis_id/loci/:locus_id/variants
 ///
 /// Returns all variants within a specific locus for Manhattan plot rendering.
-/// Variants are sorted by position for efficient rendering.
+/// Variants are sorted by position for efficient rendering. Significant
+/// variants in LD with the locus lead variant carry their r² in `lead_r2`;
+/// when the lead or the `ld_pairs` table is unavailable, `lead_r2` is null.
+/// Variants of fine-mapped loci also carry their `pip` and `credible_set`.
+/// With `format=columnar` the variants come back as
+/// [`crate::response::VariantColumns`].
+#[utoipa::path(
+    get,
+    path = "/api/phenotype/{analysis_id}/loci/{locus_id}/variants",
+    tag = "phenotype",
+    params(
+        ("analysis_id" = String, Path, description = "Analysis (phenotype) ID"),
+        ("locus_id" = String, Path, description = "Locus ID"),
+        LocusVariantsQuery
+    ),
+    responses(
+        (status = 200, description = "Variants in the locus (VariantColumns with format=columnar)", body = Vec<LocusVariantLdRow>),
+        (status = 500, description = "ClickHouse query failed", body = ErrorResponse)
+    )
+)]
 pub async fn get_locus_variants(
     State(state): State<Arc<AppState>>,
     Path((analysis_id, locus_id)): Path<(String, String)>,
     Query(params): Query<LocusVariantsQuery>,
-) -> Result<Json<Vec<LocusVariantRow>>, AppError> {
+) -> Result<Response, AppError> {
     let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
+    let min_r2 = params.ld_r2.unwrap_or(0.6);
+    let format = params.format.unwrap_or_default();
+    let pips = fetch_locus_pips(
+        &state,
+        &analysis_id,
+        &locus_id,
+        &ancestry,
+        &params.sequencing_type,
+    )
+    .await;
+
+    let lead_query = r#"
+        SELECT lead_variant
+        FROM loci
+        WHERE phenotype = ? AND ancestry = ? AND locus_id = ?
+        LIMIT 1
+    "#;
+    let lead = state
+        .clickhouse
+        .query(lead_query)
+        .bind(&analysis_id)
+        .bind(&ancestry)
+        .bind(&locus_id)
+        .fetch_optional_with::<String>(&state.executor)
+        .await?
+        .and_then(|lead| parse_variant_id(&lead).ok());
+
+    if let Some((lead_xpos, lead_ref, lead_alt)) = lead {
+        let query = r#"
+            SELECT
+                v.xpos, v.ref, v.alt, v.position, v.pvalue, v.neg_log10_p, v.is_significant,
+                if(v.is_significant, ld.r2, NULL) AS lead_r2
+            FROM loci_variants AS v
+            LEFT JOIN (
+                SELECT xpos2, ref2, alt2, toNullable(r2) AS r2
+                FROM ld_pairs
+                WHERE ancestry = lower(?) AND xpos1 = ? AND ref1 = ? AND alt1 = ? AND r2 >= ?
+            ) AS ld ON v.xpos = ld.xpos2 AND v.ref = ld.ref2 AND v.alt = ld.alt2
+            WHERE v.phenotype = ? AND v.locus_id = ? AND v.ancestry = ? AND v.sequencing_type = ?
+              AND (v.association_ac IS NULL OR v.association_ac >= 5)
+            ORDER BY v.position
+        "#;
+        let result = state
+            .clickhouse
+            .query(query)
+            .bind(&ancestry)
+            .bind(lead_xpos)
+            .bind(&lead_ref)
+            .bind(&lead_alt)
+            .bind(min_r2)
+            .bind(&analysis_id)
+            .bind(&locus_id)
+            .bind(&ancestry)
+            .bind(&params.sequencing_type)
+            .fetch_all_with::<LocusVariantLdRow>(&state.executor)
+            .await;
+        match result {
+            Ok(mut rows) => {
+                merge_pips(&mut rows, &pips);
+                return format.variant_response(rows);
+            }
+            // Typically ld_pairs has not been ingested
+            Err(e) => tracing::warn!("LD annotation of locus {} failed: {}", locus_id, e),
+        }
+    }
 
     let query = r#"
-        SELECT xpos, position, pvalue, neg_log10_p, is_significant
+        SELECT
+            xpos, ref, alt, position, pvalue, neg_log10_p, is_significant,
+            CAST(NULL, 'Nullable(Float32)') AS lead_r2
         FROM loci_variants
         WHERE phenotype = ? AND locus_id = ? AND ancestry = ? AND sequencing_type = ?
           AND (association_ac IS NULL OR association_ac >= 5)
         ORDER BY position
     "#;
 
-    let rows = state
+    let mut rows = state
         .clickhouse
         .query(query)
         .bind(&analysis_id)
         .bind(&locus_id)
         .bind(&ancestry)
         .bind(&params.sequencing_type)
-        .fetch_all::<LocusVariantRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with::<LocusVariantLdRow>(&state.executor)
+        .await?;
+    merge_pips(&mut rows, &pips);
+    format.variant_response(rows)
+}
+
+/// Set `pip` and `credible_set` of the rows from the locus fine-mapping
+///
+/// PIPs are matched by allele, so each allele of a multi-allelic site keeps
+/// its own; an allele in several credible sets takes its highest PIP.
+fn merge_pips(rows: &mut [LocusVariantLdRow], pips: &[PipRow]) {
+    if pips.is_empty() {
+        return;
+    }
+    let mut by_allele: HashMap<(i64, &str, &str), &PipRow> = HashMap::new();
+    for pip in pips {
+        let key = (pip.xpos, pip.ref_allele.as_str(), pip.alt.as_str());
+        let entry = by_allele.entry(key).or_insert(pip);
+        if pip.pip > entry.pip {
+            *entry = pip;
+        }
+    }
+    for row in rows {
+        if let Some(pip) = by_allele.get(&(row.xpos, row.ref_allele.as_str(), row.alt.as_str())) {
+            row.pip = Some(pip.pip);
+            row.credible_set = pip.cs_id;
+        }
+    }
+}
+
+// =============================================================================
+// Top Loci Table
+// =============================================================================
+
+/// Query parameters for the top loci endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TopLociQuery {
+    /// Ancestry group filter (default: "meta")
+    pub ancestry: Option<String>,
+    /// Maximum number of loci (default: 50, max: 500)
+    pub limit: Option<u32>,
+    /// Window around the locus searched for the nearest gene, in kb (default: 500)
+    pub window_kb: Option<u32>,
+}
+
+/// Gene near a lead variant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct NearestGene {
+    pub gene_id: String,
+    pub symbol: String,
+    /// Distance in bp from the lead variant to the gene body (0 if inside)
+    pub distance: i64,
+}
+
+/// One row of the top loci table
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TopLocus {
+    pub locus_id: String,
+    pub contig: String,
+    pub start: i32,
+    pub stop: i32,
+    pub source: String,
+    pub lead_variant: String,
+    pub lead_pvalue: f64,
+    pub exome_count: u32,
+    pub genome_count: u32,
+    pub nearest_gene: Option<NearestGene>,
+    /// Symbols of genes overlapping the locus, in genomic order
+    pub genes_in_locus: Vec<String>,
+}
+
+/// Gene span from gene_models
+#[derive(Debug, Clone, Deserialize, clickhouse::Row)]
+struct GeneSpanRow {
+    gene_id: String,
+    symbol: String,
+    xstart: i64,
+    xstop: i64,
+}
+
+/// Distance from a position to a gene body (0 when inside)
+fn gene_distance(xpos: i64, gene: &GeneSpanRow) -> i64 {
+    if xpos < gene.xstart {
+        gene.xstart - xpos
+    } else if xpos > gene.xstop {
+        xpos - gene.xstop
+    } else {
+        0
+    }
+}
+
+/// Nearest gene to the lead variant within `window` bp, plus genes overlapping
+/// the locus span. `genes` may contain genes from other contigs; xpos
+/// arithmetic keeps them out of range.
+fn annotate_locus(
+    lead_xpos: i64,
+    xstart: i64,
+    xstop: i64,
+    window: i64,
+    genes: &[GeneSpanRow],
+) -> (Option<NearestGene>, Vec<String>) {
+    let nearest = genes
+        .iter()
+        .map(|g| (gene_distance(lead_xpos, g), g))
+        .filter(|(d, _)| *d <= window)
+        .min_by(|(da, a), (db, b)| da.cmp(db).then_with(|| a.symbol.cmp(&b.symbol)))
+        .map(|(distance, g)| NearestGene {
+            gene_id: g.gene_id.clone(),
+            symbol: g.symbol.clone(),
+            distance,
+        });
+
+    let mut overlapping: Vec<&GeneSpanRow> = genes
+        .iter()
+        .filter(|g| g.xstop >= xstart && g.xstart <= xstop)
+        .collect();
+    overlapping.sort_by_key(|g| g.xstart);
+    let mut in_locus: Vec<String> = Vec::new();
+    for g in overlapping {
+        if !in_locus.contains(&g.symbol) {
+            in_locus.push(g.symbol.clone());
+        }
+    }
+
+    (nearest, in_locus)
+}
+
+/// GET /api/phenotype/:analysis_id/loci/top
+///
+/// Returns loci ordered by lead p-value, each annotated with the gene nearest
+/// its lead variant and the genes overlapping the locus.
+#[utoipa::path(
+    get,
+    path = "/api/phenotype/{analysis_id}/loci/top",
+    tag = "phenotype",
+    params(("analysis_id" = String, Path, description = "Analysis (phenotype) ID"), TopLociQuery),
+    responses(
+        (status = 200, description = "Top loci with gene annotation", body = Vec<TopLocus>),
+        (status = 500, description = "ClickHouse query failed", body = ErrorResponse)
+    )
+)]
+pub async fn get_top_loci(
+    State(state): State<Arc<AppState>>,
+    Path(analysis_id): Path<String>,
+    Query(params): Query<TopLociQuery>,
+) -> Result<Json<Vec<TopLocus>>, AppError> {
+    let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
+    let limit = params.limit.unwrap_or(50).clamp(1, 500);
+    let window = params.window_kb.unwrap_or(500) as i64 * 1000;
+
+    let dv = state.data_version.as_deref().unwrap_or("none");
+    let cache_key = format!("top_loci:{}:{}:{}:{}:{}", analysis_id, ancestry, limit, window, dv);
+    if let Some(cached_bytes) = state.api_cache.get(&cache_key).await {
+        let rows: Vec<TopLocus> = serde_json::from_slice(&cached_bytes)
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        return Ok(Json(rows));
+    }
+
+    let loci_query = format!(
+        r#"
+        SELECT {}
+        FROM loci
+        WHERE phenotype = ? AND ancestry = ?
+        ORDER BY lead_pvalue ASC, xstart ASC
+        LIMIT ?
+        "#,
+        LOCUS_COLUMNS
+    );
+
+    let loci = state
+        .clickhouse
+        .query(&loci_query)
+        .bind(&analysis_id)
+        .bind(&ancestry)
+        .bind(limit)
+        .fetch_all_with::<LocusRow>(&state.executor)
+        .await?;
+
+    // Lead variant position, falling back to the locus midpoint
+    let lead_positions: Vec<i64> = loci
+        .iter()
+        .map(|l| {
+            parse_variant_id(&l.lead_variant)
+                .map(|(xpos, _, _)| xpos)
+                .unwrap_or((l.xstart + l.xstop) / 2)
+        })
+        .collect();
+
+    // One gene_models scan covering every locus span plus the search window
+    let genes = if loci.is_empty() {
+        Vec::new()
+    } else {
+        let ranges = vec!["(xstop >= ? AND xstart <= ?)"; loci.len()].join(" OR ");
+        let genes_query = format!(
+            "SELECT gene_id, symbol, xstart, xstop FROM gene_models WHERE symbol != '' AND ({})",
+            ranges
+        );
+        let mut query = state.clickhouse.query(&genes_query);
+        for (locus, lead) in loci.iter().zip(&lead_positions) {
+            query = query
+                .bind(locus.xstart.min(*lead) - window)
+                .bind(locus.xstop.max(*lead) + window);
+        }
+        query
+            .fetch_all_with::<GeneSpanRow>(&state.executor)
+            .await?
+    };
+
+    let rows: Vec<TopLocus> = loci
+        .into_iter()
+        .zip(lead_positions)
+        .map(|(l, lead)| {
+            let (nearest_gene, genes_in_locus) = annotate_locus(lead, l.xstart, l.xstop, window, &genes);
+            TopLocus {
+                locus_id: l.locus_id,
+                contig: l.contig,
+                start: l.start,
+                stop: l.stop,
+                source: l.source,
+                lead_variant: l.lead_variant,
+                lead_pvalue: l.lead_pvalue,
+                exome_count: l.exome_count,
+                genome_count: l.genome_count,
+                nearest_gene,
+                genes_in_locus,
+            }
+        })
+        .collect();
+
+    if let Ok(bytes) = serde_json::to_vec(&rows) {
+        state.api_cache.insert(cache_key, bytes).await;
+    }
 
     Ok(Json(rows))
 }
@@ -155,10 +604,112 @@ pub struct LocusPlotQuery {
     pub ancestry: Option<String>,
 }
 
+/// Size of hail-decoder locus plots, used when the PNG header cannot be read
+const DEFAULT_PLOT_DIMENSIONS: ImageDimensions = ImageDimensions {
+    width: 800,
+    height: 400,
+};
+
+/// Significance line drawn on locus plots
+const LOCUS_PLOT_THRESHOLD: f64 = 5e-8;
+
+/// Width and height from the IHDR chunk at the start of a PNG file
+fn png_dimensions(header: &[u8]) -> Option<ImageDimensions> {
+    if header.len() < 24 || &header[..8] != b"\x89PNG\r\n\x1a\n" || &header[12..16] != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(header[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(header[20..24].try_into().ok()?);
+    (width > 0 && height > 0).then_some(ImageDimensions { width, height })
+}
+
+/// Sidecar of a locus plot of size `image` whose y-axis tops out at the
+/// largest -log10(p) in the locus, with the threshold placed by the same
+/// scale the renderers use
+fn locus_sidecar(image: ImageDimensions, max_neg_log_p: f64) -> LocusPlotSidecar {
+    let y_axis = YAxisConfig {
+        log_threshold: 10.0,
+        linear_fraction: 0.6,
+        max_neg_log_p: max_neg_log_p.max(10.0),
+    };
+    let scale = YScale::with_max(image.height, y_axis.max_neg_log_p);
+    let y_px = scale.get_y(LOCUS_PLOT_THRESHOLD, None).round() as u32;
+    LocusPlotSidecar {
+        image,
+        y_axis,
+        threshold: ThresholdMarker {
+            pvalue: LOCUS_PLOT_THRESHOLD,
+            y_px,
+        },
+    }
+}
+
+/// Image dimensions from the first bytes of a plot PNG in GCS
+async fn read_plot_dimensions(uri: &str) -> Result<Option<ImageDimensions>, AppError> {
+    use object_store::gcp::GoogleCloudStorageBuilder;
+    use object_store::path::Path as ObjectPath;
+    use object_store::ObjectStore;
+
+    let (bucket, path) = parse_gcs_uri(uri)
+        .ok_or_else(|| AppError::Internal(format!("Invalid GCS URI: {}", uri)))?;
+    let store = GoogleCloudStorageBuilder::new()
+        .with_bucket_name(bucket)
+        .build()
+        .map_err(|e| AppError::UpstreamGcs(format!("Failed to create GCS client: {}", e)))?;
+    let header = store
+        .get_range(&ObjectPath::from(path), 0..24)
+        .await
+        .map_err(|e| AppError::UpstreamGcs(format!("Failed to read plot header: {}", e)))?;
+    Ok(png_dimensions(&header))
+}
+
+/// Sidecar derived from the locus variants and the stored PNG
+async fn compute_locus_sidecar(
+    state: &AppState,
+    locus: &LocusRow,
+) -> Result<LocusPlotSidecar, AppError> {
+    let max_query = r#"
+        SELECT max(neg_log10_p)
+        FROM loci_variants
+        WHERE phenotype = ? AND locus_id = ? AND ancestry = ?
+    "#;
+    let max_neg_log_p = state
+        .clickhouse
+        .query(max_query)
+        .bind(&locus.phenotype)
+        .bind(&locus.locus_id)
+        .bind(&locus.ancestry)
+        .fetch_one_with::<f32>(&state.executor)
+        .await? as f64;
+    // No variants loaded: the lead p-value bounds the plot
+    let max_neg_log_p = if max_neg_log_p > 0.0 {
+        max_neg_log_p
+    } else if locus.lead_pvalue > 0.0 {
+        -locus.lead_pvalue.log10()
+    } else {
+        300.0
+    };
+
+    let image = match read_plot_dimensions(&locus.plot_gcs_uri).await {
+        Ok(Some(image)) => image,
+        Ok(None) => {
+            tracing::warn!("Locus plot {} is not a PNG", locus.plot_gcs_uri);
+            DEFAULT_PLOT_DIMENSIONS
+        }
+        Err(e) => {
+            tracing::warn!("Locus plot dimensions unavailable: {}", e);
+            DEFAULT_PLOT_DIMENSIONS
+        }
+    };
+    Ok(locus_sidecar(image, max_neg_log_p))
+}
+
 /// GET /api/phenotype/:analysis_id/loci/:locus_id/plot
 ///
 /// Returns the pre-rendered locus plot PNG URL and sidecar metadata
-/// for coordinate mapping.
+/// for coordinate mapping. The sidecar uses the image's real dimensions
+/// (read from the PNG header) and a y-axis scaled to the locus's largest
+/// -log10(p).
 pub async fn get_locus_plot(
     State(state): State<Arc<AppState>>,
     Path((analysis_id, locus_id)): Path<(String, String)>,
@@ -167,25 +718,24 @@ pub async fn get_locus_plot(
     let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
 
     // Query the loci table for plot URI
-    let query = r#"
-        SELECT
-            locus_id, phenotype, ancestry, contig, start, stop,
-            xstart, xstop, source, lead_variant, lead_pvalue,
-            exome_count, genome_count, plot_gcs_uri
+    let query = format!(
+        r#"
+        SELECT {}
         FROM loci
         WHERE phenotype = ? AND locus_id = ? AND ancestry = ?
         LIMIT 1
-    "#;
+        "#,
+        LOCUS_COLUMNS
+    );
 
     let rows = state
         .clickhouse
-        .query(query)
+        .query(&query)
         .bind(&analysis_id)
         .bind(&locus_id)
         .bind(&ancestry)
-        .fetch_all::<LocusRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with::<LocusRow>(&state.executor)
+        .await?;
 
     let locus = rows.into_iter().next().ok_or_else(|| {
         AppError::NotFound(format!(
@@ -209,22 +759,22 @@ pub async fn get_locus_plot(
         analysis_id, locus_id, ancestry
     );
 
-    // Default sidecar configuration matching hail-decoder locus plots
-    // These are the standard dimensions and scale parameters
-    let sidecar = LocusPlotSidecar {
-        image: ImageDimensions {
-            width: 800,
-            height: 400,
-        },
-        y_axis: YAxisConfig {
-            log_threshold: 10.0,
-            linear_fraction: 0.6,
-            max_neg_log_p: 50.0,
-        },
-        threshold: ThresholdMarker {
-            pvalue: 5e-8,
-            y_px: 160, // Approximate pixel position of threshold line
-        },
+    let dv = state.data_version.as_deref().unwrap_or("none");
+    let cache_key = format!(
+        "locus_sidecar:{}:{}:{}:{}",
+        analysis_id, locus_id, ancestry, dv
+    );
+    let sidecar = match state.api_cache.get(&cache_key).await {
+        Some(cached_bytes) => {
+            serde_json::from_slice(&cached_bytes).map_err(|e| AppError::Internal(e.to_string()))?
+        }
+        None => {
+            let sidecar = compute_locus_sidecar(&state, &locus).await?;
+            if let Ok(bytes) = serde_json::to_vec(&sidecar) {
+                state.api_cache.insert(cache_key, bytes).await;
+            }
+            sidecar
+        }
     };
 
     Ok(Json(LocusPlotResponse {
@@ -238,15 +788,15 @@ pub async fn get_locus_plot(
 
 /// GET /api/phenotype/:analysis_id/loci/:locus_id/plot/image
 ///
-/// Proxies the locus plot PNG image from GCS.
+/// Proxies the locus plot PNG image from GCS, or returns a signed URL when
+/// `plot_delivery` is configured for it. Proxied images honour `Range`.
 pub async fn get_locus_plot_image(
     State(state): State<Arc<AppState>>,
     Path((analysis_id, locus_id)): Path<(String, String)>,
     Query(params): Query<LocusPlotQuery>,
+    method: axum::http::Method,
+    headers: axum::http::HeaderMap,
 ) -> Result<axum::response::Response, AppError> {
-    use axum::body::Body;
-    use axum::http::{header, Response, StatusCode};
-
     let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
 
     // Query the loci table for plot URI
@@ -264,13 +814,12 @@ pub async fn get_locus_plot_image(
 
     let rows = state
         .clickhouse
-        .query(query)
+        .query(&query)
         .bind(&analysis_id)
         .bind(&locus_id)
         .bind(&ancestry)
-        .fetch_all::<PlotUriRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with::<PlotUriRow>(&state.executor)
+        .await?;
 
     let plot_uri = rows
         .into_iter()
@@ -285,48 +834,146 @@ pub async fn get_locus_plot_image(
         )));
     }
 
-    // Fetch image from GCS using object_store
-    use object_store::gcp::GoogleCloudStorageBuilder;
-    use object_store::path::Path as ObjectPath;
-    use object_store::ObjectStore;
+    // Hand out a signed URL instead of streaming, when configured
+    if let Some(response) =
+        crate::phenotype::plot_delivery::signed_plot_response(&state.config.plot_delivery, &plot_uri).await?
+    {
+        return Ok(response);
+    }
 
-    // Parse GCS URI: gs://bucket/path/to/file.png
-    let uri_parts: Vec<&str> = plot_uri
-        .strip_prefix("gs://")
-        .ok_or_else(|| AppError::DataTransformError("Invalid GCS URI".to_string()))?
-        .splitn(2, '/')
-        .collect();
+    // Cache-Control comes from the route policy
+    let (response, _) = crate::phenotype::plot_delivery::gcs_png_response(
+        state.plot_cache.as_ref(),
+        &plot_uri,
+        &method,
+        &headers,
+    )
+    .await
+    .map_err(|e| match e {
+        AppError::UpstreamGcs(msg) => AppError::NotFound(format!("Failed to fetch plot image: {}", msg)),
+        e => e,
+    })?;
 
-    if uri_parts.len() != 2 {
-        return Err(AppError::DataTransformError(
-            "Invalid GCS URI format".to_string(),
-        ));
-    }
+    Ok(response)
+}
 
-    let bucket = uri_parts[0];
-    let path = uri_parts[1];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::factories::locus;
+
+    fn gene(symbol: &str, xstart: i64, xstop: i64) -> GeneSpanRow {
+        GeneSpanRow {
+            gene_id: format!("ENSG_{}", symbol),
+            symbol: symbol.to_string(),
+            xstart,
+            xstop,
+        }
+    }
 
-    let store = GoogleCloudStorageBuilder::new()
-        .with_bucket_name(bucket)
-        .build()
-        .map_err(|e| AppError::DataTransformError(format!("Failed to create GCS client: {}", e)))?;
+    #[test]
+    fn test_annotate_locus() {
+        let base = 1_000_000_000;
+        let genes = vec![
+            gene("A", base + 1_000, base + 2_000),
+            gene("B", base + 9_000, base + 20_000),
+            gene("C", base + 50_000, base + 60_000),
+            gene("OTHER_CONTIG", 2_000_000_000, 2_000_010_000),
+        ];
+
+        // Lead variant inside B; locus spans A and B
+        let (nearest, in_locus) = annotate_locus(base + 10_000, base, base + 15_000, 500_000, &genes);
+        let nearest = nearest.unwrap();
+        assert_eq!(nearest.symbol, "B");
+        assert_eq!(nearest.distance, 0);
+        assert_eq!(in_locus, vec!["A", "B"]);
+
+        // Intergenic lead variant: closest gene body wins
+        let (nearest, in_locus) = annotate_locus(base + 40_000, base + 35_000, base + 45_000, 500_000, &genes);
+        assert_eq!(nearest.unwrap().symbol, "C");
+        assert!(in_locus.is_empty());
+
+        // Nothing within the window
+        let (nearest, _) = annotate_locus(base + 40_000, base + 35_000, base + 45_000, 5_000, &genes);
+        assert!(nearest.is_none());
+    }
 
-    let object_path = ObjectPath::from(path);
-    let data = store
-        .get(&object_path)
-        .await
-        .map_err(|e| AppError::NotFound(format!("Failed to fetch plot image: {}", e)))?
-        .bytes()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("Failed to read image data: {}", e)))?;
+    #[test]
+    fn test_loci_bed() {
+        let loci = vec![
+            locus("l2", "chr2", 5000, 6000, 1e-300),
+            locus("l1", "chr1", 100, 1100, 2e-8),
+            locus("l3", "chr1", 50, 1050, 0.0),
+            locus("l4", "chr2", 9000, 10000, 1e-3),
+        ];
+        let bed = loci_bed("height", "meta", loci, true);
+        let lines: Vec<&str> = bed.lines().collect();
+        assert!(lines[0].starts_with("track name=\"height loci\""));
+        assert_eq!(lines[1], "chr1\t49\t1050\tchr1-60-A-T\t1000\t.");
+        assert_eq!(lines[2], "chr1\t99\t1100\tchr1-110-A-T\t770\t.");
+        assert_eq!(lines[3], "chr2\t4999\t6000\tchr2-5010-A-T\t1000\t.");
+        assert_eq!(lines[4], "chr2\t8999\t10000\tchr2-9010-A-T\t300\t.");
+
+        let bed = loci_bed("height", "meta", Vec::new(), false);
+        assert!(bed.is_empty());
+    }
 
-    // Build response with image/png content type and caching headers
-    let response = Response::builder()
-        .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, "image/png")
-        .header(header::CACHE_CONTROL, "public, max-age=86400") // Cache for 24 hours
-        .body(Body::from(data.to_vec()))
-        .map_err(|e| AppError::DataTransformError(format!("Failed to build response: {}", e)))?;
+    #[test]
+    fn test_locus_sidecar() {
+        let mut png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+        png.extend_from_slice(&1200u32.to_be_bytes());
+        png.extend_from_slice(&600u32.to_be_bytes());
+        let image = png_dimensions(&png).unwrap();
+        assert_eq!((image.width, image.height), (1200, 600));
+        assert!(png_dimensions(&png[..20]).is_none());
+        assert!(png_dimensions(b"GIF89a..................").is_none());
+
+        // Threshold sits in the linear part: 600 - 7.3 / 10 * 360
+        let sidecar = locus_sidecar(image, 42.0);
+        assert_eq!(sidecar.y_axis.max_neg_log_p, 42.0);
+        assert_eq!(sidecar.threshold.y_px, 337);
+
+        // Loci below the log threshold keep a 10 -log10(p) axis
+        assert_eq!(locus_sidecar(DEFAULT_PLOT_DIMENSIONS, 6.0).y_axis.max_neg_log_p, 10.0);
+    }
 
-    Ok(response)
+    #[test]
+    fn test_merge_pips() {
+        let variant = |xpos: i64, alt: &str| LocusVariantLdRow {
+            xpos,
+            ref_allele: "A".to_string(),
+            alt: alt.to_string(),
+            position: (xpos % 1_000_000_000) as i32,
+            pvalue: 1e-9,
+            neg_log10_p: 9.0,
+            is_significant: true,
+            lead_r2: None,
+            pip: None,
+            credible_set: None,
+        };
+        let pip = |xpos: i64, alt: &str, pip: f64, cs_id: Option<i32>| PipRow {
+            xpos,
+            ref_allele: "A".to_string(),
+            alt: alt.to_string(),
+            pip,
+            cs_id,
+        };
+        // Multi-allelic site: each allele keeps its own PIP
+        let mut rows = vec![
+            variant(1_000_000_100, "C"),
+            variant(1_000_000_100, "G"),
+            variant(1_000_000_200, "C"),
+        ];
+        merge_pips(
+            &mut rows,
+            &[
+                pip(1_000_000_100, "C", 0.1, None),
+                pip(1_000_000_100, "G", 0.8, Some(1)),
+                pip(1_000_000_100, "G", 0.6, Some(2)),
+            ],
+        );
+        assert_eq!((rows[0].pip, rows[0].credible_set), (Some(0.1), None));
+        assert_eq!((rows[1].pip, rows[1].credible_set), (Some(0.8), Some(1)));
+        assert_eq!(rows[2].pip, None);
+    }
 }
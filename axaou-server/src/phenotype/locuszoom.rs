@@ -0,0 +1,432 @@
+//! LocusZoom panel handler
+//!
+//! Serves everything an interactive LocusZoom-style panel draws for one
+//! locus in a single response: the association variants (with r² to the
+//! lead variant from `ld_pairs` when available), the gene models in the
+//! window, and the recombination rate map from `recombination_rates`.
+//! Variants of fine-mapped loci carry their PIP from `credible_sets`.
+//! Missing LD, fine-mapping or recombination tables degrade to empty tracks.
+
+use crate::api::AppState;
+use crate::clickhouse::models::{LocusRow, RecombinationRateRow, LOCUS_COLUMNS};
+use crate::clickhouse::xpos::{make_variant_id_from_xpos, parse_variant_id};
+use crate::clickhouse::QueryExt;
+use crate::error::{AppError, ErrorResponse};
+use crate::genomics::{Contig, Region};
+use crate::models::Exon;
+use crate::phenotype::credible_sets::{fetch_locus_pips, PipRow};
+use crate::phenotype::manhattan::locus_params;
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+/// Query parameters for the LocusZoom endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LocusZoomQuery {
+    /// Ancestry group (default: "meta"); also selects the LD reference
+    pub ancestry: Option<String>,
+    /// Sequencing type, "exome" or "genome" (default: "genome")
+    pub sequencing_type: Option<String>,
+    /// Padding added to each side of the locus for genes and recombination
+    /// rates, in kb (default: 50, max: 500)
+    pub flank_kb: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize, Row)]
+struct ZoomVariantRow {
+    xpos: i64,
+    #[serde(rename = "ref")]
+    ref_allele: String,
+    alt: String,
+    pvalue: f64,
+    neg_log10_p: f32,
+    beta: Option<f64>,
+    se: Option<f64>,
+    af: Option<f64>,
+    is_significant: bool,
+    r2: Option<f32>,
+}
+
+/// Association variant of a LocusZoom panel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LocusZoomVariant {
+    pub variant_id: String,
+    pub position: u32,
+    pub pvalue: f64,
+    pub neg_log10_p: f32,
+    pub beta: Option<f64>,
+    pub se: Option<f64>,
+    pub af: Option<f64>,
+    pub is_significant: bool,
+    /// r² with the lead variant; null when no LD is stored for the pair
+    pub r2: Option<f32>,
+    pub is_lead: bool,
+    /// Fine-mapping posterior inclusion probability
+    pub pip: Option<f64>,
+    /// Credible set of the variant within the locus
+    pub credible_set: Option<i32>,
+}
+
+/// Gene drawn in the gene track
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LocusZoomGene {
+    pub gene_id: String,
+    pub symbol: String,
+    pub start: i64,
+    pub stop: i64,
+    pub strand: String,
+    pub canonical_transcript_id: String,
+    /// Exon, CDS and UTR features of the gene
+    pub exons: Vec<Exon>,
+}
+
+/// Point of the recombination rate track
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RecombinationPoint {
+    pub position: u32,
+    /// Recombination rate in cM/Mb
+    pub rate: f64,
+    /// Genetic map position in cM
+    pub genetic_map_cm: f64,
+}
+
+/// Data of one LocusZoom panel
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LocusZoomResponse {
+    pub analysis_id: String,
+    pub locus_id: String,
+    pub ancestry: String,
+    pub sequencing_type: String,
+    /// Panel window: the locus span plus `flank_kb` on each side
+    pub contig: String,
+    pub start: u32,
+    pub stop: u32,
+    pub lead_variant: String,
+    pub lead_pvalue: f64,
+    /// Whether `r2` was looked up in `ld_pairs`
+    pub ld_available: bool,
+    /// Variants of the locus in position order
+    pub variants: Vec<LocusZoomVariant>,
+    pub genes: Vec<LocusZoomGene>,
+    /// Recombination rates covering the window, in position order
+    pub recombination: Vec<RecombinationPoint>,
+}
+
+fn zoom_variant(
+    row: ZoomVariantRow,
+    lead: Option<&(i64, String, String)>,
+    pip: Option<&PipRow>,
+) -> LocusZoomVariant {
+    let is_lead = lead.is_some_and(|(xpos, ref_allele, alt)| {
+        row.xpos == *xpos && row.ref_allele == *ref_allele && row.alt == *alt
+    });
+    LocusZoomVariant {
+        variant_id: make_variant_id_from_xpos(row.xpos, &row.ref_allele, &row.alt),
+        position: (row.xpos % 1_000_000_000) as u32,
+        pvalue: row.pvalue,
+        neg_log10_p: row.neg_log10_p,
+        beta: row.beta,
+        se: row.se,
+        af: row.af,
+        is_significant: row.is_significant,
+        r2: if is_lead { Some(1.0) } else { row.r2 },
+        is_lead,
+        pip: pip.map(|p| p.pip),
+        credible_set: pip.and_then(|p| p.cs_id),
+    }
+}
+
+/// Window of the panel: the locus span plus `flank` bp, clamped to the contig
+fn zoom_window(locus: &LocusRow, flank: u32) -> Result<(Contig, u32, u32), AppError> {
+    let contig = Contig::from_xpos(locus.xstart).ok_or_else(|| {
+        AppError::Internal(format!("Locus {} has an invalid xstart", locus.locus_id))
+    })?;
+    let start = (locus.start.max(1) as u32).saturating_sub(flank).max(1);
+    let stop = (locus.stop.max(1) as u32)
+        .saturating_add(flank)
+        .min(contig.length());
+    Ok((contig, start, stop))
+}
+
+/// GET /api/phenotype/:analysis_id/loci/:locus_id/locuszoom
+///
+/// Variants, genes and recombination rates for a LocusZoom-style panel of
+/// one locus. `r2` is the LD of each variant with the locus lead variant in
+/// the requested ancestry; it is null for pairs below the export threshold
+/// and for every variant when `ld_pairs` is unavailable (`ld_available`).
+#[utoipa::path(
+    get,
+    path = "/api/phenotype/{analysis_id}/loci/{locus_id}/locuszoom",
+    tag = "phenotype",
+    params(
+        ("analysis_id" = String, Path, description = "Analysis (phenotype) ID"),
+        ("locus_id" = String, Path, description = "Locus ID"),
+        LocusZoomQuery
+    ),
+    responses(
+        (status = 200, description = "LocusZoom panel data", body = LocusZoomResponse),
+        (status = 404, description = "Locus not found", body = ErrorResponse)
+    )
+)]
+pub async fn get_locus_zoom(
+    State(state): State<Arc<AppState>>,
+    Path((analysis_id, locus_id)): Path<(String, String)>,
+    Query(params): Query<LocusZoomQuery>,
+) -> Result<Json<LocusZoomResponse>, AppError> {
+    let (ancestry, sequencing_type) =
+        locus_params(params.ancestry.as_deref(), params.sequencing_type.as_deref())?;
+    let flank = params.flank_kb.unwrap_or(50).min(500) * 1000;
+
+    let dv = state.data_version.as_deref().unwrap_or("none");
+    let cache_key = format!(
+        "locuszoom:{}:{}:{}:{}:{}:{}",
+        analysis_id, locus_id, ancestry, sequencing_type, flank, dv
+    );
+    if let Some(cached_bytes) = state.api_cache.get(&cache_key).await {
+        let response: LocusZoomResponse =
+            serde_json::from_slice(&cached_bytes).map_err(|e| AppError::Internal(e.to_string()))?;
+        return Ok(Json(response));
+    }
+
+    let locus_query = format!(
+        r#"
+        SELECT {}
+        FROM loci
+        WHERE phenotype = ? AND ancestry = ? AND locus_id = ?
+        LIMIT 1
+        "#,
+        LOCUS_COLUMNS
+    );
+    let locus = state
+        .clickhouse
+        .query(&locus_query)
+        .bind(&analysis_id)
+        .bind(&ancestry)
+        .bind(&locus_id)
+        .fetch_optional_with::<LocusRow>(&state.executor)
+        .await?
+        .ok_or_else(|| {
+            AppError::NotFound(format!(
+                "Locus {} not found for {} ({})",
+                locus_id, analysis_id, ancestry
+            ))
+        })?;
+    let (contig, start, stop) = zoom_window(&locus, flank)?;
+    let lead = parse_variant_id(&locus.lead_variant).ok();
+
+    let mut ld_available = false;
+    let mut rows = None;
+    if let Some((lead_xpos, lead_ref, lead_alt)) = &lead {
+        let query = r#"
+            SELECT
+                v.xpos, v.ref, v.alt, v.pvalue, v.neg_log10_p, v.beta, v.se, v.af,
+                v.is_significant, ld.r2
+            FROM loci_variants AS v
+            LEFT JOIN (
+                SELECT xpos2, ref2, alt2, toNullable(r2) AS r2
+                FROM ld_pairs
+                WHERE ancestry = ? AND xpos1 = ? AND ref1 = ? AND alt1 = ?
+            ) AS ld ON v.xpos = ld.xpos2 AND v.ref = ld.ref2 AND v.alt = ld.alt2
+            WHERE v.phenotype = ? AND v.locus_id = ? AND v.ancestry = ? AND v.sequencing_type = ?
+              AND (v.association_ac IS NULL OR v.association_ac >= 5)
+            ORDER BY v.xpos
+        "#;
+        let result = state
+            .clickhouse
+            .query(query)
+            .bind(&ancestry)
+            .bind(*lead_xpos)
+            .bind(lead_ref)
+            .bind(lead_alt)
+            .bind(&analysis_id)
+            .bind(&locus_id)
+            .bind(&ancestry)
+            .bind(&sequencing_type)
+            .fetch_all_with::<ZoomVariantRow>(&state.executor)
+            .await;
+        match result {
+            Ok(r) => {
+                ld_available = true;
+                rows = Some(r);
+            }
+            // Typically ld_pairs has not been ingested
+            Err(e) => tracing::warn!("LD annotation of locus {} failed: {}", locus_id, e),
+        }
+    }
+    let rows = match rows {
+        Some(rows) => rows,
+        None => {
+            let query = r#"
+                SELECT
+                    xpos, ref, alt, pvalue, neg_log10_p, beta, se, af,
+                    is_significant, CAST(NULL AS Nullable(Float32)) AS r2
+                FROM loci_variants
+                WHERE phenotype = ? AND locus_id = ? AND ancestry = ? AND sequencing_type = ?
+                  AND (association_ac IS NULL OR association_ac >= 5)
+                ORDER BY xpos
+            "#;
+            state
+                .clickhouse
+                .query(query)
+                .bind(&analysis_id)
+                .bind(&locus_id)
+                .bind(&ancestry)
+                .bind(&sequencing_type)
+                .fetch_all_with::<ZoomVariantRow>(&state.executor)
+                .await?
+        }
+    };
+    let pips = fetch_locus_pips(&state, &analysis_id, &locus_id, &ancestry, &sequencing_type).await;
+    let pips: HashMap<(i64, &str, &str), &PipRow> = pips
+        .iter()
+        .map(|p| ((p.xpos, p.ref_allele.as_str(), p.alt.as_str()), p))
+        .collect();
+    let variants: Vec<LocusZoomVariant> = rows
+        .into_iter()
+        .map(|row| {
+            let pip = pips
+                .get(&(row.xpos, row.ref_allele.as_str(), row.alt.as_str()))
+                .copied();
+            zoom_variant(row, lead.as_ref(), pip)
+        })
+        .collect();
+
+    let region = Region {
+        chrom: contig.bare().to_string(),
+        start,
+        stop,
+    };
+    let genes = state
+        .gene_models
+        .get_in_interval(&region.to_string())
+        .await?
+        .into_iter()
+        .map(|gene| LocusZoomGene {
+            gene_id: gene.gene_id,
+            symbol: gene.symbol,
+            start: gene.start,
+            stop: gene.stop,
+            strand: gene.strand,
+            canonical_transcript_id: gene.canonical_transcript_id,
+            exons: gene.exons,
+        })
+        .collect();
+
+    // The map points bracketing the window are included so the rate line
+    // spans it completely
+    let recombination_query = r#"
+        SELECT contig, position, xpos, rate, genetic_map_cm
+        FROM recombination_rates
+        WHERE xpos >= (
+                SELECT coalesce(maxOrNull(xpos), ?) FROM recombination_rates WHERE xpos >= ? AND xpos <= ?
+            )
+          AND xpos <= (
+                SELECT coalesce(minOrNull(xpos), ?) FROM recombination_rates WHERE xpos >= ? AND xpos <= ?
+            )
+        ORDER BY xpos
+    "#;
+    let (xpos_start, xpos_end) = (contig.xpos(start), contig.xpos(stop));
+    let (contig_start, contig_end) = (contig.xpos(0), contig.xpos(contig.length()));
+    let recombination = match state
+        .clickhouse
+        .query(recombination_query)
+        .bind(xpos_start)
+        .bind(contig_start)
+        .bind(xpos_start)
+        .bind(xpos_end)
+        .bind(xpos_end)
+        .bind(contig_end)
+        .fetch_all_with::<RecombinationRateRow>(&state.executor)
+        .await
+    {
+        Ok(rows) => rows
+            .into_iter()
+            .map(|row| RecombinationPoint {
+                position: row.position,
+                rate: row.rate,
+                genetic_map_cm: row.genetic_map_cm,
+            })
+            .collect(),
+        // Typically recombination_rates has not been ingested
+        Err(e) => {
+            tracing::warn!("Recombination rates for locus {} failed: {}", locus_id, e);
+            Vec::new()
+        }
+    };
+
+    let response = LocusZoomResponse {
+        analysis_id,
+        locus_id,
+        ancestry,
+        sequencing_type,
+        contig: contig.chr().to_string(),
+        start,
+        stop,
+        lead_variant: locus.lead_variant,
+        lead_pvalue: locus.lead_pvalue,
+        ld_available,
+        variants,
+        genes,
+        recombination,
+    };
+
+    if let Ok(bytes) = serde_json::to_vec(&response) {
+        state.api_cache.insert(cache_key, bytes).await;
+    }
+    Ok(Json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(xpos: i64, r2: Option<f32>) -> ZoomVariantRow {
+        ZoomVariantRow {
+            xpos,
+            ref_allele: "A".to_string(),
+            alt: "G".to_string(),
+            pvalue: 1e-9,
+            neg_log10_p: 9.0,
+            beta: Some(0.1),
+            se: Some(0.01),
+            af: Some(0.2),
+            is_significant: true,
+            r2,
+        }
+    }
+
+    #[test]
+    fn test_zoom_variant() {
+        let lead = (1_000_001_000, "A".to_string(), "G".to_string());
+
+        let v = zoom_variant(row(1_000_001_000, None), Some(&lead), None);
+        assert!(v.is_lead);
+        assert_eq!(v.r2, Some(1.0));
+        assert_eq!(v.variant_id, "chr1-1000-A-G");
+        assert_eq!(v.position, 1000);
+
+        let v = zoom_variant(row(1_000_002_000, Some(0.7)), Some(&lead), None);
+        assert!(!v.is_lead);
+        assert_eq!(v.r2, Some(0.7));
+        assert_eq!(v.pip, None);
+        assert!(!zoom_variant(row(1_000_001_000, None), None, None).is_lead);
+
+        let pip = PipRow {
+            xpos: 1_000_001_000,
+            ref_allele: "A".to_string(),
+            alt: "G".to_string(),
+            pip: 0.92,
+            cs_id: Some(1),
+        };
+        let v = zoom_variant(row(1_000_001_000, None), Some(&lead), Some(&pip));
+        assert_eq!(v.pip, Some(0.92));
+        assert_eq!(v.credible_set, Some(1));
+    }
+}
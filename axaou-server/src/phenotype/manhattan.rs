@@ -5,20 +5,23 @@
 //! calculation to match the PNG layout.
 
 use crate::api::AppState;
+use crate::clickhouse::executor::{QueryExecutor, QueryPolicyConfig};
+use crate::clickhouse::QueryExt;
 use crate::clickhouse::models::PlotRow;
 use crate::clickhouse::xpos::compute_xpos;
 use crate::error::AppError;
+use crate::genomics::contig::{xpos_contig_sql, Contig};
+use crate::models::AncestryGroup;
+use crate::phenotype::manhattan_render::{render_manhattan_png, rendered_cache_key};
+use crate::phenotype::plot_cache::fetch_plot_png;
+use crate::phenotype::plot_delivery::{gcs_png_response, png_response, signed_plot_response};
 use axum::{
-    body::Body,
     extract::{Path, Query, State},
-    http::{header, StatusCode},
+    http::{HeaderMap, Method},
     response::Response,
     Json,
 };
 use clickhouse::Row;
-use object_store::gcp::GoogleCloudStorageBuilder;
-use object_store::path::Path as ObjectPath;
-use object_store::ObjectStore;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tracing::debug;
@@ -258,7 +261,7 @@ pub struct ManhattanResponse {
 }
 
 /// Parse a GCS URI into bucket and path components
-fn parse_gcs_uri(uri: &str) -> Option<(String, String)> {
+pub(crate) fn parse_gcs_uri(uri: &str) -> Option<(String, String)> {
     let uri = uri.strip_prefix("gs://")?;
     let mut parts = uri.splitn(2, '/');
     let bucket = parts.next()?.to_string();
@@ -270,14 +273,12 @@ fn parse_gcs_uri(uri: &str) -> Option<(String, String)> {
 async fn get_manhattan_uri(
     state: &AppState,
     analysis_id: &str,
-    ancestry: Option<&str>,
+    ancestry: &str,
     plot_type: Option<&str>,
     contig: &str,
 ) -> Result<String, AppError> {
     // Default plot_type to genome_manhattan if not specified
     let base_plot_type = plot_type.unwrap_or("genome_manhattan");
-    // Default ancestry to meta if not specified
-    let ancestry = ancestry.unwrap_or("meta");
 
     // For per-chromosome view, construct plot_type as "{contig}_{base_type}"
     // e.g., "chr1_genome_manhattan", "chr1_exome_manhattan"
@@ -301,9 +302,8 @@ async fn get_manhattan_uri(
         .bind(analysis_id)
         .bind(&effective_plot_type)
         .bind(ancestry)
-        .fetch_optional::<PlotRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_optional_with::<PlotRow>(&state.executor)
+        .await?;
 
     match row {
         Some(plot) => Ok(plot.gcs_uri),
@@ -316,87 +316,194 @@ async fn get_manhattan_uri(
 
 /// GET /api/phenotype/:analysis_id/manhattan/image
 ///
-/// Streams the Manhattan plot PNG from GCS with server-side caching.
+/// Streams the Manhattan plot PNG from GCS with server-side caching, or returns
+/// a signed URL when `plot_delivery` is configured for it. Falls back to
+/// rendering from ClickHouse when no pre-rendered image exists. Proxied
+/// images honour `Range`, and HEAD is answered without reading the image.
 pub async fn get_manhattan_image(
     State(state): State<Arc<AppState>>,
     Path(analysis_id): Path<String>,
     Query(params): Query<ManhattanQuery>,
+    method: Method,
+    headers: HeaderMap,
 ) -> Result<Response, AppError> {
     debug!("Fetching Manhattan image for phenotype: {}", analysis_id);
 
     // Default contig to "all" if not specified
-    let contig = params.contig.as_deref().unwrap_or("all");
-    let ancestry = params.ancestry.as_deref().unwrap_or("meta");
+    let contig = contig_param(params.contig.as_deref())?;
+    let ancestry = ancestry_param(params.ancestry.as_deref())?.to_string();
+    let ancestry = ancestry.as_str();
     let plot_type = params.plot_type.as_deref().unwrap_or("genome_manhattan");
     let data_version = params.v.as_deref().unwrap_or("");
 
-    // Construct cache key with data version
-    let cache_key = format!("{}-{}-{}-{}-{}-image", analysis_id, ancestry, plot_type, contig, data_version);
+    let cache_key = image_cache_key(&analysis_id, ancestry, plot_type, contig, data_version);
 
     // Check cache first
     if let Some(cached_bytes) = state.api_cache.get(&cache_key).await {
         debug!("Cache hit for Manhattan image: {}", cache_key);
-        return Ok(Response::builder()
-            .status(StatusCode::OK)
-            .header(header::CONTENT_TYPE, "image/png")
-            .header(header::CACHE_CONTROL, "public, max-age=31536000, immutable")
-            .body(Body::from(cached_bytes))
-            .unwrap());
+        return Ok(png_response(cached_bytes, &headers));
     }
 
     debug!("Cache miss for Manhattan image: {}", cache_key);
 
-    // Get the GCS URI from ClickHouse
-    let gcs_uri = get_manhattan_uri(
+    // Get the GCS URI from ClickHouse; render from loci_variants when no
+    // pre-rendered image exists for this ancestry/plot_type
+    let gcs_uri = match get_manhattan_uri(
         &state,
         &analysis_id,
-        params.ancestry.as_deref(),
+        ancestry,
         params.plot_type.as_deref(),
         contig,
     )
-    .await?;
+    .await
+    {
+        Ok(uri) => uri,
+        Err(AppError::NotFound(msg)) => {
+            debug!("{}; rendering from loci_variants", msg);
+            let bytes =
+                rendered_manhattan_png(&state, &cache_key, &analysis_id, ancestry, plot_type, contig)
+                    .await?;
+            return Ok(png_response(bytes, &headers));
+        }
+        Err(e) => return Err(e),
+    };
 
     // Ensure it's a PNG
     if !gcs_uri.ends_with(".png") {
-        return Err(AppError::DataTransformError(format!(
+        return Err(AppError::Internal(format!(
             "Expected PNG file, got: {}",
             gcs_uri
         )));
     }
 
-    // Parse the GCS URI
-    let (bucket, path) = parse_gcs_uri(&gcs_uri).ok_or_else(|| {
-        AppError::DataTransformError(format!("Invalid GCS URI: {}", gcs_uri))
-    })?;
-
-    // Create GCS client for this bucket
-    let store = GoogleCloudStorageBuilder::new()
-        .with_bucket_name(&bucket)
-        .build()
-        .map_err(|e| AppError::DataTransformError(format!("Failed to create GCS client: {}", e)))?;
-
-    // Fetch the object
-    let object_path = ObjectPath::from(path.as_str());
-    let result = store
-        .get(&object_path)
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("Failed to fetch from GCS: {}", e)))?;
-
-    // Read all bytes for caching
-    let bytes = result.bytes().await
-        .map_err(|e| AppError::DataTransformError(format!("Failed to read bytes from GCS: {}", e)))?;
-    let bytes_vec = bytes.to_vec();
-
-    // Cache the bytes
-    state.api_cache.insert(cache_key.clone(), bytes_vec.clone()).await;
-    debug!("Cached Manhattan image: {}", cache_key);
-
-    Ok(Response::builder()
-        .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, "image/png")
-        .header(header::CACHE_CONTROL, "public, max-age=31536000, immutable")
-        .body(Body::from(bytes_vec))
-        .unwrap())
+    // Hand out a signed URL instead of streaming, when configured
+    if let Some(response) = signed_plot_response(&state.config.plot_delivery, &gcs_uri).await? {
+        return Ok(response);
+    }
+
+    let (response, bytes) =
+        gcs_png_response(state.plot_cache.as_ref(), &gcs_uri, &method, &headers).await?;
+
+    // Cache the bytes when the whole image was read
+    if let Some(bytes) = bytes {
+        state.api_cache.insert(cache_key.clone(), bytes).await;
+        debug!("Cached Manhattan image: {}", cache_key);
+    }
+
+    Ok(response)
+}
+
+/// Manhattan PNG rendered from `loci_variants`, cached under the short-lived
+/// render key of `image_key` so it never stands in for the real image
+async fn rendered_manhattan_png(
+    state: &AppState,
+    image_key: &str,
+    analysis_id: &str,
+    ancestry: &str,
+    plot_type: &str,
+    contig: &str,
+) -> Result<Vec<u8>, AppError> {
+    let cache_key = rendered_cache_key(image_key);
+    if let Some(cached_bytes) = state.api_cache.get(&cache_key).await {
+        return Ok(cached_bytes);
+    }
+    let bytes = render_manhattan_png(state, analysis_id, ancestry, plot_type, contig).await?;
+    state.api_cache.insert(cache_key, bytes.clone()).await;
+    Ok(bytes)
+}
+
+/// API cache key of a full-size Manhattan PNG (includes the data version)
+fn image_cache_key(analysis_id: &str, ancestry: &str, plot_type: &str, contig: &str, data_version: &str) -> String {
+    format!("{}-{}-{}-{}-{}-image", analysis_id, ancestry, plot_type, contig, data_version)
+}
+
+/// Full-size Manhattan PNG bytes from the API cache, GCS (through the disk
+/// cache) or rendered from ClickHouse, regardless of `plot_delivery` mode.
+///
+/// The flag is true for a render fallback, which callers must not cache as
+/// long as the real image.
+pub(crate) async fn manhattan_png(
+    state: &AppState,
+    analysis_id: &str,
+    ancestry: &str,
+    plot_type: &str,
+    contig: &str,
+    data_version: &str,
+) -> Result<(Vec<u8>, bool), AppError> {
+    let cache_key = image_cache_key(analysis_id, ancestry, plot_type, contig, data_version);
+    if let Some(cached_bytes) = state.api_cache.get(&cache_key).await {
+        return Ok((cached_bytes, false));
+    }
+
+    match get_manhattan_uri(state, analysis_id, ancestry, Some(plot_type), contig).await {
+        Ok(gcs_uri) if gcs_uri.ends_with(".png") => {
+            let bytes = fetch_plot_png(state.plot_cache.as_ref(), &gcs_uri).await?;
+            state.api_cache.insert(cache_key, bytes.clone()).await;
+            Ok((bytes, false))
+        }
+        Ok(gcs_uri) => Err(AppError::Internal(format!("Expected PNG file, got: {}", gcs_uri))),
+        Err(AppError::NotFound(_)) => {
+            let bytes =
+                rendered_manhattan_png(state, &cache_key, analysis_id, ancestry, plot_type, contig)
+                    .await?;
+            Ok((bytes, true))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Normalize the `contig` query parameter to "all" or a GRCh38 name ("chrX"),
+/// the form plot files and `contig` columns use
+pub(crate) fn contig_param(contig: Option<&str>) -> Result<&'static str, AppError> {
+    match contig {
+        None | Some("all") => Ok("all"),
+        Some(name) => Ok(name.parse::<Contig>()?.chr()),
+    }
+}
+
+/// Normalize the `ancestry` query parameter to the group ClickHouse rows are
+/// keyed by (lowercase, "meta" when absent)
+pub(crate) fn ancestry_param(ancestry: Option<&str>) -> Result<AncestryGroup, AppError> {
+    match ancestry {
+        None => Ok(AncestryGroup::Meta),
+        Some(name) => AncestryGroup::from_dir_name(name.trim()).ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "Invalid ancestry '{}'. Expected meta, afr, amr, eas, eur, mid or sas",
+                name
+            ))
+        }),
+    }
+}
+
+/// Validate the `ancestry` and `sequencing_type` parameters of the locus
+/// detail endpoints; `sequencing_type` is "exome" or "genome" (the default)
+pub(crate) fn locus_params(
+    ancestry: Option<&str>,
+    sequencing_type: Option<&str>,
+) -> Result<(String, String), AppError> {
+    let ancestry = ancestry_param(ancestry)?.to_string();
+    let sequencing_type = sequencing_type.unwrap_or("genome");
+    if sequencing_type != "exome" && sequencing_type != "genome" {
+        return Err(AppError::BadRequest(format!(
+            "Invalid sequencing_type '{}'. Expected exome or genome",
+            sequencing_type
+        )));
+    }
+    Ok((ancestry, sequencing_type.to_string()))
+}
+
+/// Allele count column of the annotation tables matching an ancestry;
+/// meta results use the all-sample count
+fn annotation_ac_column(ancestry: &str) -> &'static str {
+    match AncestryGroup::from_dir_name(ancestry) {
+        Some(AncestryGroup::Afr) => "ac_afr",
+        Some(AncestryGroup::Amr) => "ac_amr",
+        Some(AncestryGroup::Eas) => "ac_eas",
+        Some(AncestryGroup::Eur) => "ac_eur",
+        Some(AncestryGroup::Mid) => "ac_mid",
+        Some(AncestryGroup::Sas) => "ac_sas",
+        Some(AncestryGroup::Meta) | None => "ac",
+    }
 }
 
 /// Build variant ID from components
@@ -404,10 +511,28 @@ fn make_variant_id(contig: &str, position: i32, ref_allele: &str, alt: &str) ->
     format!("{}-{}-{}-{}", contig, position, ref_allele, alt)
 }
 
-/// Fetch peak annotations with nearby genes from ClickHouse
+/// Peak annotations as persisted by `ingest variant-results`
 ///
-/// Returns top N GWAS peaks with genes in locus (±200kb), coding variant counts,
-/// and burden test p-values where available.
+/// One row per locus; `peak_json` is the serialized [`Peak`] computed by
+/// [`compute_peak_annotations`] for the whole genome.
+#[derive(Debug, Clone, Serialize, Deserialize, Row)]
+pub(crate) struct PeakAnnotationRow {
+    pub phenotype: String,
+    pub ancestry: String,
+    pub sequencing_type: String,
+    pub locus_id: String,
+    pub contig: String,
+    pub position: i32,
+    pub pvalue: f64,
+    pub peak_json: String,
+}
+
+/// Fetch peak annotations with nearby genes
+///
+/// Reads the keyed `phenotype_peak_annotations` table written at ingest time
+/// (restricted to `contig` unless it is "all"), falling back to
+/// [`compute_peak_annotations`] when nothing has been persisted for this
+/// phenotype or the table is missing.
 pub(crate) async fn fetch_peak_annotations(
     state: &AppState,
     analysis_id: &str,
@@ -417,29 +542,197 @@ pub(crate) async fn fetch_peak_annotations(
     contig: &str,
     limit: u32,
 ) -> Result<Vec<Peak>, AppError> {
-    // Compute xpos bounds for chromosome filtering
-    let xpos_filter = if contig != "all" {
-        let xpos_start = compute_xpos(contig, 0);
-        let xpos_end = xpos_start + 1_000_000_000;
-        format!("AND lv.xpos >= {} AND lv.xpos < {}", xpos_start, xpos_end)
-    } else {
-        String::new()
-    };
+    match read_persisted_peaks(state, analysis_id, ancestry, sequencing_type, contig, limit).await {
+        Ok(peaks) if !peaks.is_empty() => return Ok(peaks),
+        Ok(_) => debug!(
+            "No persisted peaks for {}/{}/{}, computing live",
+            analysis_id, ancestry, sequencing_type
+        ),
+        Err(e) => debug!("Persisted peak read failed, computing live: {}", e),
+    }
 
-    // Query precomputed loci table directly, then annotate with nearby genes and coding variants
+    compute_peak_annotations(
+        &state.clickhouse,
+        &state.executor,
+        analysis_id,
+        ancestry,
+        sequencing_type,
+        annotation_table,
+        contig,
+        limit,
+    )
+    .await
+}
+
+async fn read_persisted_peaks(
+    state: &AppState,
+    analysis_id: &str,
+    ancestry: &str,
+    sequencing_type: &str,
+    contig: &str,
+    limit: u32,
+) -> Result<Vec<Peak>, AppError> {
     let query = format!(
         r#"
-        WITH sig_counts AS (
+        SELECT peak_json
+        FROM phenotype_peak_annotations FINAL
+        WHERE phenotype = ? AND ancestry = ? AND sequencing_type = ?
+          {}
+        ORDER BY pvalue ASC
+        LIMIT ?
+        "#,
+        if contig != "all" { "AND contig = ?" } else { "" }
+    );
+
+    let mut q = state
+        .clickhouse
+        .query(&query)
+        .bind(analysis_id)
+        .bind(ancestry)
+        .bind(sequencing_type);
+    if contig != "all" {
+        q = q.bind(contig);
+    }
+    let rows: Vec<String> = q.bind(limit).fetch_all_with(&state.executor).await?;
+
+    rows.iter()
+        .map(|json| {
+            serde_json::from_str(json)
+                .map_err(|e| AppError::Internal(format!("Invalid persisted peak: {}", e)))
+        })
+        .collect()
+}
+
+/// Recompute and store the peak annotations for one phenotype/ancestry/sequencing type
+///
+/// Called by `ingest variant-results` after `loci_variants` is loaded, so the
+/// Manhattan overlay becomes a keyed read. Returns the number of peaks stored.
+pub(crate) async fn persist_peak_annotations(
+    client: &clickhouse::Client,
+    analysis_id: &str,
+    ancestry: &str,
+    sequencing_type: &str,
+) -> Result<usize, AppError> {
+    let annotation_table = match sequencing_type {
+        "exome" => "exome_annotations",
+        _ => "genome_annotations",
+    };
+    // Runs once per ingested phenotype over the whole genome, so it gets a
+    // longer timeout than request-time queries
+    let executor = QueryExecutor::new(QueryPolicyConfig {
+        timeout_secs: 600,
+        ..QueryPolicyConfig::default()
+    });
+    let peaks = compute_peak_annotations(
+        client,
+        &executor,
+        analysis_id,
+        ancestry,
+        sequencing_type,
+        annotation_table,
+        "all",
+        10000,
+    )
+    .await?;
+
+    let ch_err = |e: clickhouse::error::Error| {
+        AppError::UpstreamClickHouse(format!("Peak persistence error: {}", e))
+    };
+    client
+        .query("DELETE FROM phenotype_peak_annotations WHERE phenotype = ? AND ancestry = ? AND sequencing_type = ?")
+        .bind(analysis_id)
+        .bind(ancestry)
+        .bind(sequencing_type)
+        .execute()
+        .await
+        .map_err(ch_err)?;
+
+    if peaks.is_empty() {
+        return Ok(0);
+    }
+
+    let mut insert = client
+        .insert::<PeakAnnotationRow>("phenotype_peak_annotations")
+        .map_err(ch_err)?;
+    for peak in &peaks {
+        let peak_json = serde_json::to_string(peak).map_err(|e| AppError::Internal(e.to_string()))?;
+        insert
+            .write(&PeakAnnotationRow {
+                phenotype: analysis_id.to_string(),
+                ancestry: ancestry.to_string(),
+                sequencing_type: sequencing_type.to_string(),
+                locus_id: peak.locus_id.clone(),
+                contig: peak.contig.clone(),
+                position: peak.position,
+                pvalue: peak.pvalue,
+                peak_json,
+            })
+            .await
+            .map_err(ch_err)?;
+    }
+    insert.end().await.map_err(ch_err)?;
+
+    Ok(peaks.len())
+}
+
+/// Significant variants per locus from `phenotype_peaks`
+///
+/// Bind order: phenotype, ancestry, sequencing_type
+const PEAKS_SIG_COUNTS: &str = "
+            SELECT locus_id, toUInt32(sum(sig_variant_count)) as sig_variant_count
+            FROM phenotype_peaks
+            WHERE phenotype = ? AND ancestry = ? AND sequencing_type = ?
+            GROUP BY locus_id";
+
+/// [`PEAKS_SIG_COUNTS`] counted from `loci_variants`, for databases without
+/// `phenotype_peaks`
+const LOCI_VARIANTS_SIG_COUNTS: &str = "
             SELECT locus_id, toUInt32(count()) as sig_variant_count
             FROM loci_variants
-            WHERE phenotype = ? AND ancestry = ? AND sequencing_type = ? AND is_significant = true
+            WHERE phenotype = ? AND ancestry = ? AND sequencing_type = ?
+              AND is_significant = true
               AND (association_ac IS NULL OR association_ac >= 5)
-            GROUP BY locus_id
-        ),
+            GROUP BY locus_id";
+
+/// Compute peak annotations with nearby genes from ClickHouse
+///
+/// Returns top N GWAS peaks with genes in locus (±200kb), coding variant counts,
+/// and burden test p-values where available. Coding allele counts and burden
+/// results are taken from `ancestry`, so per-ancestry plots stay consistent.
+pub(crate) async fn compute_peak_annotations(
+    client: &clickhouse::Client,
+    executor: &QueryExecutor,
+    analysis_id: &str,
+    ancestry: &str,
+    sequencing_type: &str,
+    annotation_table: &str,
+    contig: &str,
+    limit: u32,
+) -> Result<Vec<Peak>, AppError> {
+    // Compute xpos bounds for chromosome filtering (loci and their variants)
+    let (locus_filter, xpos_filter) = if contig != "all" {
+        let xpos_start = compute_xpos(contig, 0);
+        let xpos_end = xpos_start + 1_000_000_000;
+        (
+            format!("AND l.xstart >= {} AND l.xstart < {}", xpos_start, xpos_end),
+            format!("AND lv.xpos >= {} AND lv.xpos < {}", xpos_start, xpos_end),
+        )
+    } else {
+        (String::new(), String::new())
+    };
+
+    // Contigs and gene joins go through xpos so chrX/chrY/chrM loci are
+    // matched the same way as autosomes
+    // Query precomputed loci and per-locus significant counts (phenotype_peaks, built by
+    // `ingest variant-results`), then annotate with nearby genes and coding variants
+    let query = |sig_counts: &str| format!(
+        r#"
+        WITH sig_counts AS ({sig_counts}),
         peaks AS (
             SELECT
-                l.locus_id, l.contig, l.start, l.stop,
+                l.locus_id, {locus_contig} as contig, l.start, l.stop,
                 toInt32OrZero(splitByChar(':', l.lead_variant)[2]) as peak_position,
+                intDiv(l.xstart, 1000000000) * 1000000000 + peak_position as peak_xpos,
                 l.lead_pvalue as peak_pvalue,
                 toUInt32(l.exome_count + l.genome_count) as variant_count,
                 coalesce(sc.sig_variant_count, toUInt32(0)) as sig_variant_count
@@ -447,6 +740,7 @@ pub(crate) async fn fetch_peak_annotations(
             LEFT JOIN sig_counts sc ON sc.locus_id = l.locus_id
             WHERE l.phenotype = ? AND l.ancestry = ?
               AND (l.source = 'both' OR l.source = ?)
+              {locus_filter}
             ORDER BY l.lead_pvalue ASC
             LIMIT ?
         ),
@@ -457,9 +751,9 @@ pub(crate) async fn fetch_peak_annotations(
                 abs(p.peak_position - (gm.start + gm.stop) / 2) as distance_to_peak
             FROM peaks p
             JOIN gene_models gm
-                ON gm.chrom = substring(p.contig, 4)
-                AND gm.start < p.peak_position + 200000
-                AND gm.stop > p.peak_position - 200000
+                ON intDiv(gm.xstart, 1000000000) = intDiv(p.peak_xpos, 1000000000)
+                AND gm.xstart < p.peak_xpos + 200000
+                AND gm.xstop > p.peak_xpos - 200000
             WHERE gm.symbol != '' AND gm.symbol NOT LIKE 'ENSG%'
         ),
         coding_variants AS (
@@ -473,13 +767,9 @@ pub(crate) async fn fetch_peak_annotations(
                 argMinIf(ann.consequence, lv.pvalue, ann.consequence IN ('stop_gained', 'frameshift_variant', 'splice_acceptor_variant', 'splice_donor_variant', 'start_lost', 'stop_lost', 'missense_variant')) as best_coding_csq,
                 argMinIf(ann.hgvsp, lv.pvalue, ann.consequence IN ('stop_gained', 'frameshift_variant', 'splice_acceptor_variant', 'splice_donor_variant', 'start_lost', 'stop_lost', 'missense_variant')) as best_coding_hgvsp,
                 argMinIf(ann.hgvsc, lv.pvalue, ann.consequence IN ('stop_gained', 'frameshift_variant', 'splice_acceptor_variant', 'splice_donor_variant', 'start_lost', 'stop_lost', 'missense_variant')) as best_coding_hgvsc,
-                argMinIf(ann.ac, lv.pvalue, ann.consequence IN ('stop_gained', 'frameshift_variant', 'splice_acceptor_variant', 'splice_donor_variant', 'start_lost', 'stop_lost', 'missense_variant')) as best_coding_ac,
+                argMinIf(ann.{ac_column}, lv.pvalue, ann.consequence IN ('stop_gained', 'frameshift_variant', 'splice_acceptor_variant', 'splice_donor_variant', 'start_lost', 'stop_lost', 'missense_variant')) as best_coding_ac,
                 argMinIf(
-                    concat('chr',
-                        multiIf(intDiv(lv.xpos, 1000000000) <= 22, toString(intDiv(lv.xpos, 1000000000)),
-                                intDiv(lv.xpos, 1000000000) = 23, 'X',
-                                intDiv(lv.xpos, 1000000000) = 24, 'Y', 'M'),
-                        '-', toString(lv.xpos % 1000000000), '-', lv.ref, '-', lv.alt),
+                    concat({variant_contig}, '-', toString(lv.xpos % 1000000000), '-', lv.ref, '-', lv.alt),
                     lv.pvalue,
                     ann.consequence IN ('stop_gained', 'frameshift_variant', 'splice_acceptor_variant', 'splice_donor_variant', 'start_lost', 'stop_lost', 'missense_variant')
                 ) as best_coding_variant_id,
@@ -514,25 +804,34 @@ pub(crate) async fn fetch_peak_annotations(
         ORDER BY lg.peak_pvalue ASC, lg.distance_to_peak ASC
         "#,
         annotation_table = annotation_table,
+        ac_column = annotation_ac_column(ancestry),
+        locus_contig = xpos_contig_sql("l.xstart"),
+        variant_contig = xpos_contig_sql("lv.xpos"),
+        locus_filter = locus_filter,
         xpos_filter = xpos_filter
     );
-
-    let rows: Vec<PeakGeneRow> = state
-        .clickhouse
-        .query(&query)
-        .bind(analysis_id) // sig_counts: phenotype
-        .bind(ancestry)    // sig_counts: ancestry
-        .bind(sequencing_type) // sig_counts: sequencing_type
-        .bind(analysis_id) // peaks: phenotype
-        .bind(ancestry)    // peaks: ancestry
-        .bind(sequencing_type) // peaks: source
-        .bind(limit)       // peaks: limit
-        .bind(analysis_id) // coding_variants: phenotype
-        .bind(ancestry)    // coding_variants: ancestry
-        .bind(sequencing_type) // coding_variants: sequencing_type
-        .fetch_all()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("Peak annotation query error: {}", e)))?;
+    let fetch = |sig_counts: &str| {
+        client
+            .query(&query(sig_counts))
+            .bind(analysis_id) // sig_counts: phenotype
+            .bind(ancestry)    // sig_counts: ancestry
+            .bind(sequencing_type) // sig_counts: sequencing_type
+            .bind(analysis_id) // peaks: phenotype
+            .bind(ancestry)    // peaks: ancestry
+            .bind(sequencing_type) // peaks: source
+            .bind(limit)       // peaks: limit
+            .bind(analysis_id) // coding_variants: phenotype
+            .bind(ancestry)    // coding_variants: ancestry
+            .bind(sequencing_type) // coding_variants: sequencing_type
+            .fetch_all_with::<PeakGeneRow>(executor)
+    };
+    let rows = match fetch(PEAKS_SIG_COUNTS).await {
+        // UNKNOWN_TABLE: phenotype_peaks not migrated yet, count from loci_variants
+        Err(AppError::UpstreamClickHouse(msg)) if msg.contains("Code: 60.") => {
+            fetch(LOCI_VARIANTS_SIG_COUNTS).await?
+        }
+        rows => rows?,
+    };
 
     // Collect unique gene IDs for burden query
     let gene_ids: std::collections::HashSet<String> = rows.iter().map(|r| r.gene_id.clone()).collect();
@@ -565,12 +864,11 @@ pub(crate) async fn fetch_peak_annotations(
             gene_ids_in
         );
 
-        let burden_rows: Vec<BurdenRow> = match state
-            .clickhouse
+        let burden_rows: Vec<BurdenRow> = match client
             .query(&burden_query)
             .bind(analysis_id)
             .bind(ancestry)
-            .fetch_all()
+            .fetch_all_with(executor)
             .await
         {
             Ok(rows) => rows,
@@ -701,9 +999,10 @@ pub async fn get_manhattan_overlay(
 ) -> Result<Json<ManhattanOverlay>, AppError> {
     debug!("Building Manhattan overlay from ClickHouse for phenotype: {}", analysis_id);
 
-    let ancestry = params.ancestry.as_deref().unwrap_or("meta");
+    let ancestry = ancestry_param(params.ancestry.as_deref())?.to_string();
+    let ancestry = ancestry.as_str();
     let plot_type = params.plot_type.as_deref().unwrap_or("genome_manhattan");
-    let contig = params.contig.as_deref().unwrap_or("all");
+    let contig = contig_param(params.contig.as_deref())?;
     let data_version = params.v.as_deref().unwrap_or("");
 
     // Construct cache key with data version
@@ -713,17 +1012,42 @@ pub async fn get_manhattan_overlay(
     if let Some(cached_bytes) = state.api_cache.get(&cache_key).await {
         debug!("Cache hit for Manhattan overlay: {}", cache_key);
         let overlay: ManhattanOverlay = serde_json::from_slice(&cached_bytes)
-            .map_err(|e| AppError::DataTransformError(format!("Failed to deserialize cached overlay: {}", e)))?;
+            .map_err(|e| AppError::Internal(format!("Failed to deserialize cached overlay: {}", e)))?;
         return Ok(Json(overlay));
     }
 
     debug!("Cache miss for Manhattan overlay: {}", cache_key);
 
-    // Handle gene Manhattan separately
-    if plot_type == "gene_manhattan" {
-        return get_gene_manhattan_overlay(&state, &analysis_id, ancestry, contig, data_version).await;
-    }
+    // Concurrent misses for the same overlay share one build
+    let json_bytes = state
+        .inflight
+        .run(&cache_key, || async {
+            let overlay = if plot_type == "gene_manhattan" {
+                build_gene_manhattan_overlay(&state, &analysis_id, ancestry, contig).await?
+            } else {
+                build_manhattan_overlay(&state, &analysis_id, ancestry, plot_type, contig).await?
+            };
+            let json_bytes = serde_json::to_vec(&overlay)
+                .map_err(|e| AppError::Internal(format!("Failed to serialize overlay: {}", e)))?;
+            state.api_cache.insert(cache_key.clone(), json_bytes.clone()).await;
+            debug!("Cached Manhattan overlay: {}", cache_key);
+            Ok::<_, AppError>(json_bytes)
+        })
+        .await?;
 
+    let overlay: ManhattanOverlay = serde_json::from_slice(&json_bytes)
+        .map_err(|e| AppError::Internal(format!("Failed to deserialize overlay: {}", e)))?;
+    Ok(Json(overlay))
+}
+
+/// Build the variant Manhattan overlay for an exome or genome plot
+async fn build_manhattan_overlay(
+    state: &AppState,
+    analysis_id: &str,
+    ancestry: &str,
+    plot_type: &str,
+    contig: &str,
+) -> Result<ManhattanOverlay, AppError> {
     // Determine sequencing type from plot_type for variant Manhattan
     let sequencing_type = match plot_type {
         "exome_manhattan" => "exome",
@@ -746,25 +1070,22 @@ pub async fn get_manhattan_overlay(
         String::new()
     };
 
-    // Use loci_variants with is_significant filter since significant_variants may be empty
+    // Use loci_variants with is_significant filter since significant_variants may be empty.
+    // Allele counts come from the column of the requested ancestry.
     let query = format!(
         r#"
         SELECT
-            CASE intDiv(lv.xpos, 1000000000)
-                WHEN 23 THEN 'chrX'
-                WHEN 24 THEN 'chrY'
-                WHEN 25 THEN 'chrM'
-                ELSE concat('chr', toString(intDiv(lv.xpos, 1000000000)))
-            END as contig,
+            {contig} as contig,
             lv.position, lv.ref, lv.alt, lv.pvalue, lv.neg_log10_p, 0.0 as beta,
             ann.gene_symbol, ann.consequence, ann.hgvsc, ann.hgvsp, ann.ac
         FROM loci_variants lv
         LEFT JOIN (
-            SELECT xpos, ref, alt, gene_symbol, consequence, hgvsc, hgvsp, ac
+            SELECT xpos, ref, alt, gene_symbol, consequence, hgvsc, hgvsp, {ac_column} AS ac
             FROM {annotation_table}
             WHERE xpos IN (
                 SELECT xpos FROM loci_variants
-                WHERE phenotype = ? AND is_significant = true
+                WHERE phenotype = ? AND ancestry = ? AND sequencing_type = ?
+                  AND is_significant = true
             )
         ) ann ON lv.xpos = ann.xpos AND lv.ref = ann.ref AND lv.alt = ann.alt
         WHERE lv.phenotype = ?
@@ -775,7 +1096,9 @@ pub async fn get_manhattan_overlay(
             {xpos_filter}
         ORDER BY lv.pvalue ASC
         "#,
+        contig = xpos_contig_sql("lv.xpos"),
         annotation_table = annotation_table,
+        ac_column = annotation_ac_column(ancestry),
         xpos_filter = xpos_filter
     );
 
@@ -795,25 +1118,25 @@ pub async fn get_manhattan_overlay(
         let count: u64 = state
             .clickhouse
             .query(count_query)
-            .bind(&analysis_id)
+            .bind(analysis_id)
             .bind(ancestry)
             .bind(sequencing_type)
-            .fetch_one()
-            .await
-            .map_err(|e| AppError::DataTransformError(format!("Count query error: {}", e)))?;
+            .fetch_one_with(&state.executor)
+            .await?;
         (vec![], count as usize)
     } else {
         // Per-chromosome view: fetch full variant data
         let rows: Vec<SignificantVariantRow> = state
             .clickhouse
             .query(&query)
-            .bind(&analysis_id)  // for IN subquery
-            .bind(&analysis_id)  // for outer WHERE
+            .bind(analysis_id)  // for IN subquery
             .bind(ancestry)
             .bind(sequencing_type)
-            .fetch_all()
-            .await
-            .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+            .bind(analysis_id)  // for outer WHERE
+            .bind(ancestry)
+            .bind(sequencing_type)
+            .fetch_all_with(&state.executor)
+            .await?;
 
         let hits: Vec<SignificantHit> = rows
             .into_iter()
@@ -844,8 +1167,8 @@ pub async fn get_manhattan_overlay(
 
     // Fetch all peak annotations for the locus navigator table (no limit)
     let peaks = match fetch_peak_annotations(
-        &state,
-        &analysis_id,
+        state,
+        analysis_id,
         ancestry,
         sequencing_type,
         annotation_table,
@@ -861,41 +1184,20 @@ pub async fn get_manhattan_overlay(
         }
     };
 
-    let overlay = ManhattanOverlay {
+    Ok(ManhattanOverlay {
         significant_hits,
         hit_count,
         peaks,
-    };
-
-    // Cache the overlay as JSON bytes
-    if let Ok(json_bytes) = serde_json::to_vec(&overlay) {
-        state.api_cache.insert(cache_key.clone(), json_bytes).await;
-        debug!("Cached Manhattan overlay: {}", cache_key);
-    }
-
-    Ok(Json(overlay))
+    })
 }
 
-/// Get gene Manhattan overlay from gene_associations table
-async fn get_gene_manhattan_overlay(
+/// Build the gene Manhattan overlay from the gene_associations table
+async fn build_gene_manhattan_overlay(
     state: &AppState,
     analysis_id: &str,
     ancestry: &str,
     contig: &str,
-    data_version: &str,
-) -> Result<Json<ManhattanOverlay>, AppError> {
-    // Construct cache key with data version
-    let cache_key = format!("{}-{}-gene_manhattan-{}-{}-overlay-v2", analysis_id, ancestry, contig, data_version);
-
-    // Check cache first
-    if let Some(cached_bytes) = state.api_cache.get(&cache_key).await {
-        debug!("Cache hit for gene Manhattan overlay: {}", cache_key);
-        let overlay: ManhattanOverlay = serde_json::from_slice(&cached_bytes)
-            .map_err(|e| AppError::DataTransformError(format!("Failed to deserialize cached overlay: {}", e)))?;
-        return Ok(Json(overlay));
-    }
-
-    debug!("Cache miss for gene Manhattan overlay: {}", cache_key);
+) -> Result<ManhattanOverlay, AppError> {
     // Compute xpos bounds for chromosome filtering
     let xpos_filter = if contig != "all" {
         let xpos_start = compute_xpos(contig, 0);
@@ -910,7 +1212,7 @@ async fn get_gene_manhattan_overlay(
     let query = format!(
         r#"
         SELECT
-            gene_id, gene_symbol, contig, gene_start_position AS position,
+            gene_id, gene_symbol, {contig} AS contig, gene_start_position AS position,
             pvalue, pvalue_burden, pvalue_skat, beta_burden
         FROM gene_associations
         WHERE phenotype = ?
@@ -921,6 +1223,7 @@ async fn get_gene_manhattan_overlay(
         ORDER BY pvalue ASC
         LIMIT 500
         "#,
+        contig = xpos_contig_sql("xpos"),
         xpos_filter = xpos_filter
     );
 
@@ -929,9 +1232,8 @@ async fn get_gene_manhattan_overlay(
         .query(&query)
         .bind(analysis_id)
         .bind(ancestry)
-        .fetch_all()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with(&state.executor)
+        .await?;
 
     // Convert to SignificantHit for genes
     // For genes, compute neg_log10_p from pvalue (gene table doesn't have pre-computed values)
@@ -1023,19 +1325,11 @@ async fn get_gene_manhattan_overlay(
         significant_hits
     };
 
-    let overlay = ManhattanOverlay {
+    Ok(ManhattanOverlay {
         significant_hits: display_hits,
         hit_count,
         peaks: Some(peaks),
-    };
-
-    // Cache the overlay as JSON bytes
-    if let Ok(json_bytes) = serde_json::to_vec(&overlay) {
-        state.api_cache.insert(cache_key.clone(), json_bytes).await;
-        debug!("Cached gene Manhattan overlay: {}", cache_key);
-    }
-
-    Ok(Json(overlay))
+    })
 }
 
 /// GET /api/phenotype/:analysis_id/manhattan
@@ -1049,13 +1343,14 @@ pub async fn get_manhattan_data(
     debug!("Fetching Manhattan data for phenotype: {}", analysis_id);
 
     // Default contig to "all" if not specified
-    let contig = params.contig.as_deref().unwrap_or("all");
+    let contig = contig_param(params.contig.as_deref())?;
+    let ancestry = ancestry_param(params.ancestry.as_deref())?.to_string();
 
     // First verify the plot exists by checking the URI
     let _gcs_uri = get_manhattan_uri(
         &state,
         &analysis_id,
-        params.ancestry.as_deref(),
+        &ancestry,
         params.plot_type.as_deref(),
         contig,
     )
@@ -1066,7 +1361,7 @@ pub async fn get_manhattan_data(
         State(Arc::clone(&state)),
         Path(analysis_id.clone()),
         Query(ManhattanQuery {
-            ancestry: params.ancestry.clone(),
+            ancestry: Some(ancestry.clone()),
             plot_type: params.plot_type.clone(),
             contig: params.contig.clone(),
             v: params.v.clone(),
@@ -1087,8 +1382,8 @@ pub async fn get_manhattan_data(
 
     // Construct the image URL that points to our proxy endpoint
     let mut query_params = Vec::new();
-    if let Some(ref anc) = params.ancestry {
-        query_params.push(format!("ancestry={}", anc));
+    if params.ancestry.is_some() {
+        query_params.push(format!("ancestry={}", ancestry));
     }
     if let Some(ref pt) = params.plot_type {
         query_params.push(format!("plot_type={}", pt));
@@ -1112,3 +1407,25 @@ pub async fn get_manhattan_data(
         has_overlay,
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ancestry_param() {
+        assert_eq!(ancestry_param(None).unwrap(), AncestryGroup::Meta);
+        assert_eq!(ancestry_param(Some("EUR")).unwrap().to_string(), "eur");
+        assert!(ancestry_param(Some("nfe")).is_err());
+
+        assert_eq!(
+            locus_params(Some("AFR"), None).unwrap(),
+            ("afr".to_string(), "genome".to_string())
+        );
+        assert!(locus_params(None, Some("exomes")).is_err());
+        assert!(locus_params(Some("nfe"), Some("exome")).is_err());
+
+        assert_eq!(annotation_ac_column("afr"), "ac_afr");
+        assert_eq!(annotation_ac_column("meta"), "ac");
+    }
+}
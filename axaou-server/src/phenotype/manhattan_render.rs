@@ -0,0 +1,363 @@
+//! Server-side Manhattan plot rendering
+//!
+//! Fallback for `/manhattan/image` when no pre-rendered PNG is registered in
+//! `phenotype_plots`. Points come from ClickHouse `loci_variants`, which only
+//! stores variants within loci, so the rendered plot shows the peaks rather
+//! than the full genome-wide background.
+//!
+//! Rendered PNGs are kept in a size-bounded disk cache at `render_cache_dir`
+//! (when configured, see `plot_cache`) so they survive restarts. Callers
+//! keep them in the API cache under a [`rendered_cache_key`], which expires
+//! after [`RENDERED_TTL`] rather than the cache's 24 hours, so a pre-rendered
+//! image registered later is served instead of the partial render.
+
+use crate::api::AppState;
+use crate::clickhouse::QueryExt;
+use crate::clickhouse::xpos::compute_xpos;
+use crate::error::AppError;
+use crate::genomics::Contig;
+use crate::phenotype::render::YScale;
+use clickhouse::Row;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use tiny_skia::{Color, Paint, PathBuilder, Pixmap, Rect, Stroke, StrokeDash, Transform};
+use tracing::{debug, info, warn};
+
+/// Rendered image width in pixels
+const PLOT_WIDTH: u32 = 2000;
+/// Rendered image height in pixels
+const PLOT_HEIGHT: u32 = 600;
+/// Point half-size in pixels
+const POINT_RADIUS: f32 = 2.0;
+/// Genome-wide significance threshold
+const SIGNIFICANCE_THRESHOLD: f64 = 5e-8;
+
+/// Lifetime of rendered plots in the API cache
+pub const RENDERED_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Suffix of API cache keys holding rendered plots
+const RENDERED_KEY_SUFFIX: &str = "-rendered";
+
+/// API cache key of the render fallback for an image cached under `image_key`
+pub fn rendered_cache_key(image_key: &str) -> String {
+    format!("{}{}", image_key, RENDERED_KEY_SUFFIX)
+}
+
+/// API cache expiry: rendered plots live for [`RENDERED_TTL`], every other
+/// entry for the cache's time-to-live
+pub struct ApiCacheExpiry;
+
+impl moka::Expiry<String, Vec<u8>> for ApiCacheExpiry {
+    fn expire_after_create(&self, key: &String, _value: &Vec<u8>, _created_at: Instant) -> Option<Duration> {
+        key.ends_with(RENDERED_KEY_SUFFIX).then_some(RENDERED_TTL)
+    }
+}
+
+/// Point as stored in `loci_variants`
+#[derive(Debug, Clone, Deserialize, Row)]
+struct ManhattanPointRow {
+    pub xpos: i64,
+    pub neg_log10_p: f32,
+}
+
+// =============================================================================
+// Layout
+// =============================================================================
+
+/// Horizontal layout of one or more chromosomes laid end to end
+#[derive(Debug, Clone)]
+pub struct GenomeLayout {
+    /// (xpos of position 0, cumulative offset in bp, length in bp)
+    contigs: Vec<(i64, u64, u32)>,
+    total_length: u64,
+}
+
+impl GenomeLayout {
+    /// Layout for "all" (genome-wide) or a single chromosome
+    ///
+    /// The genome-wide layout runs every contig in `genomics::contig` order,
+    /// chr1..chr22, chrX, chrY, chrM; chrM (16.5kb) takes the last pixel.
+    pub fn for_contig(contig: &str) -> Option<Self> {
+        let selected: Vec<Contig> = if contig == "all" {
+            Contig::all().collect()
+        } else {
+            vec![Contig::parse(contig)?]
+        };
+
+        let mut contigs = Vec::with_capacity(selected.len());
+        let mut offset = 0u64;
+        for contig in selected {
+            contigs.push((contig.xpos(0), offset, contig.length()));
+            offset += contig.length() as u64;
+        }
+
+        Some(Self {
+            contigs,
+            total_length: offset,
+        })
+    }
+
+    /// Fraction of the plot width for an xpos, or None if outside the layout
+    pub fn x_fraction(&self, xpos: i64) -> Option<f64> {
+        let base = xpos - xpos % 1_000_000_000;
+        let position = xpos % 1_000_000_000;
+        let (_, offset, length) = self.contigs.iter().find(|(b, _, _)| *b == base)?;
+        let position = position.clamp(0, *length as i64) as u64;
+        Some((offset + position) as f64 / self.total_length as f64)
+    }
+
+    /// Index of the chromosome containing an xpos (for alternating colors)
+    fn contig_index(&self, xpos: i64) -> Option<usize> {
+        let base = xpos - xpos % 1_000_000_000;
+        self.contigs.iter().position(|(b, _, _)| *b == base)
+    }
+}
+
+// =============================================================================
+// Renderer
+// =============================================================================
+
+/// Rasterizes Manhattan points onto a white canvas
+pub struct ManhattanRenderer {
+    pixmap: Pixmap,
+    layout: GenomeLayout,
+    scale: YScale,
+}
+
+impl ManhattanRenderer {
+    pub fn new(layout: GenomeLayout) -> Self {
+        let mut pixmap = Pixmap::new(PLOT_WIDTH, PLOT_HEIGHT)
+            .expect("Failed to allocate pixmap. Dimensions may be too large.");
+        pixmap.fill(Color::WHITE);
+
+        Self {
+            pixmap,
+            layout,
+            scale: YScale::new(PLOT_HEIGHT),
+        }
+    }
+
+    /// Draw a dashed horizontal line at the significance threshold
+    pub fn draw_threshold_line(&mut self, pvalue: f64) {
+        let y = self.scale.get_y(pvalue, None);
+
+        let mut paint = Paint::default();
+        paint.set_color_rgba8(220, 38, 38, 255);
+        paint.anti_alias = true;
+
+        let mut stroke = Stroke::default();
+        stroke.width = 1.5;
+        stroke.dash = StrokeDash::new(vec![6.0, 4.0], 0.0);
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0.0, y);
+        pb.line_to(PLOT_WIDTH as f32, y);
+
+        if let Some(path) = pb.finish() {
+            self.pixmap
+                .stroke_path(&path, &paint, &stroke, Transform::identity(), None);
+        }
+    }
+
+    /// Draw points with chromosome-alternating colors
+    pub fn draw_points(&mut self, points: &[(i64, f32)]) {
+        let mut even = Paint::default();
+        even.set_color_rgba8(38, 84, 166, 220);
+        let mut odd = Paint::default();
+        odd.set_color_rgba8(120, 160, 214, 220);
+
+        for &(xpos, neg_log10_p) in points {
+            let (Some(fraction), Some(index)) =
+                (self.layout.x_fraction(xpos), self.layout.contig_index(xpos))
+            else {
+                continue;
+            };
+            let x = (fraction * PLOT_WIDTH as f64) as f32;
+            let y = self.scale.get_y(0.0, Some(neg_log10_p as f64));
+            let paint = if index % 2 == 0 { &even } else { &odd };
+
+            if let Some(rect) = Rect::from_xywh(
+                x - POINT_RADIUS,
+                y - POINT_RADIUS,
+                POINT_RADIUS * 2.0,
+                POINT_RADIUS * 2.0,
+            ) {
+                self.pixmap
+                    .fill_rect(rect, paint, Transform::identity(), None);
+            }
+        }
+    }
+
+    /// Encode the rendered pixmap to PNG bytes
+    pub fn encode_png(&self) -> Result<Vec<u8>, AppError> {
+        self.pixmap
+            .encode_png()
+            .map_err(|e| AppError::Internal(format!("PNG encoding failed: {}", e)))
+    }
+}
+
+// =============================================================================
+// Rendering from ClickHouse
+// =============================================================================
+
+/// Render disk cache key for a plot; entries are versioned by data version
+fn disk_cache_key(analysis_id: &str, ancestry: &str, plot_type: &str, contig: &str) -> String {
+    format!(
+        "rendered:{}/{}/{}/{}",
+        analysis_id, ancestry, plot_type, contig
+    )
+}
+
+/// Render a variant Manhattan PNG from `loci_variants`
+///
+/// Gene Manhattan plots are not rendered (gene results have no point table).
+pub async fn render_manhattan_png(
+    state: &AppState,
+    analysis_id: &str,
+    ancestry: &str,
+    plot_type: &str,
+    contig: &str,
+) -> Result<Vec<u8>, AppError> {
+    let sequencing_type = match plot_type {
+        "genome_manhattan" => "genome",
+        "exome_manhattan" => "exome",
+        _ => {
+            return Err(AppError::NotFound(format!(
+                "No pre-rendered '{}' plot for phenotype '{}' and it cannot be rendered on demand",
+                plot_type, analysis_id
+            )))
+        }
+    };
+
+    let layout = GenomeLayout::for_contig(contig)
+        .ok_or_else(|| AppError::InvalidInterval(format!("Unknown contig: {}", contig)))?;
+
+    let cache_key = disk_cache_key(analysis_id, ancestry, plot_type, contig);
+    let data_version = state.data_version.as_deref().unwrap_or("none");
+    if let Some(cache) = &state.render_cache {
+        if let Some(bytes) = cache.lookup(&cache_key, data_version).await {
+            debug!("Disk cache hit for rendered Manhattan: {}", cache_key);
+            return Ok(bytes);
+        }
+    }
+
+    let xpos_filter = if contig != "all" {
+        let xpos_start = compute_xpos(contig, 0);
+        format!(
+            "AND xpos >= {} AND xpos < {}",
+            xpos_start,
+            xpos_start + 1_000_000_000
+        )
+    } else {
+        String::new()
+    };
+
+    let query = format!(
+        r#"
+        SELECT xpos, neg_log10_p
+        FROM loci_variants
+        WHERE phenotype = ? AND ancestry = ? AND sequencing_type = ?
+          {xpos_filter}
+        "#,
+        xpos_filter = xpos_filter
+    );
+
+    let rows = state
+        .clickhouse
+        .query(&query)
+        .bind(analysis_id)
+        .bind(ancestry)
+        .bind(sequencing_type)
+        .fetch_all_with::<ManhattanPointRow>(&state.executor)
+        .await?;
+
+    if rows.is_empty() {
+        return Err(AppError::NotFound(format!(
+            "No variants to render for phenotype '{}' ({}, {})",
+            analysis_id, ancestry, sequencing_type
+        )));
+    }
+
+    let points: Vec<(i64, f32)> = rows.iter().map(|r| (r.xpos, r.neg_log10_p)).collect();
+
+    // Rasterization is CPU-bound; keep it off the async workers
+    let bytes = tokio::task::spawn_blocking(move || {
+        let mut renderer = ManhattanRenderer::new(layout);
+        renderer.draw_threshold_line(SIGNIFICANCE_THRESHOLD);
+        renderer.draw_points(&points);
+        renderer.encode_png()
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Render task failed: {}", e)))??;
+
+    info!(
+        "Rendered Manhattan for {} ({}, {}, {}): {} points",
+        analysis_id,
+        ancestry,
+        plot_type,
+        contig,
+        rows.len()
+    );
+
+    if let Some(cache) = &state.render_cache {
+        if let Err(e) = cache.insert(&cache_key, data_version, &bytes).await {
+            warn!("Failed to write {} to the render disk cache: {}", cache_key, e);
+        }
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn genome_layout_orders_chromosomes() {
+        let layout = GenomeLayout::for_contig("all").unwrap();
+        let chr1 = layout.x_fraction(compute_xpos("chr1", 1)).unwrap();
+        let chr2 = layout.x_fraction(compute_xpos("chr2", 1)).unwrap();
+        let chrx = layout.x_fraction(compute_xpos("chrX", 1_000_000)).unwrap();
+        let chry = layout.x_fraction(compute_xpos("chrY", 1_000_000)).unwrap();
+        let chrm = layout.x_fraction(compute_xpos("chrM", 100)).unwrap();
+        assert!(chr1 < chr2 && chr2 < chrx && chrx < chry && chry < chrm);
+        assert!(chrm < 1.0);
+        assert_eq!(layout.contig_index(compute_xpos("chr2", 5)), Some(1));
+        assert_eq!(layout.contig_index(compute_xpos("chrM", 5)), Some(24));
+    }
+
+    #[test]
+    fn single_contig_layout() {
+        let layout = GenomeLayout::for_contig("22").unwrap();
+        let mid = layout.x_fraction(compute_xpos("chr22", 25_409_234)).unwrap();
+        assert!((mid - 0.5).abs() < 1e-6);
+        assert!(layout.x_fraction(compute_xpos("chr1", 100)).is_none());
+        assert!(GenomeLayout::for_contig("chr99").is_none());
+        assert!(GenomeLayout::for_contig("x").is_some());
+        let chrm = GenomeLayout::for_contig("chrM").unwrap();
+        assert!(chrm.x_fraction(compute_xpos("MT", 8_000)).is_some());
+    }
+
+    #[test]
+    fn rendered_keys_expire_early() {
+        use moka::Expiry;
+        let image_key = "height-meta-genome_manhattan-all--image".to_string();
+        let rendered_key = rendered_cache_key(&image_key);
+        assert_ne!(rendered_key, image_key);
+        let now = Instant::now();
+        assert_eq!(ApiCacheExpiry.expire_after_create(&image_key, &Vec::new(), now), None);
+        assert_eq!(
+            ApiCacheExpiry.expire_after_create(&rendered_key, &Vec::new(), now),
+            Some(RENDERED_TTL)
+        );
+    }
+
+    #[test]
+    fn renders_png() {
+        let mut renderer = ManhattanRenderer::new(GenomeLayout::for_contig("all").unwrap());
+        renderer.draw_threshold_line(SIGNIFICANCE_THRESHOLD);
+        renderer.draw_points(&[(compute_xpos("chr1", 1_000_000), 12.0)]);
+        let png = renderer.encode_png().unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+    }
+}
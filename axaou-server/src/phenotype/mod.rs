@@ -1,14 +1,29 @@
 //! Phenotype-specific route handlers
 //!
 //! Provides endpoints for Manhattan plot data including loci, variants,
-//! significant variants, plot metadata, QQ plots, and Manhattan plot proxies.
+//! significant variants, plot metadata, QQ plots, Manhattan plot proxies and
+//! thumbnails, LocusZoom panels, conditional analysis signals, fine-mapping
+//! credible sets, two-phenotype comparisons and multiple-testing thresholds.
 
+pub mod compare;
+pub mod conditional;
+pub mod credible_sets;
+pub mod gene_manhattan;
+pub mod known_hits;
 pub mod loci;
+pub mod locuszoom;
 pub mod manhattan;
+pub mod manhattan_render;
 pub mod overview;
+pub mod plot_cache;
+pub mod plot_delivery;
 pub mod plots;
 pub mod qq;
+pub mod qq_render;
 pub mod region_render;
 pub mod render;
+pub mod shared_loci;
 pub mod significant;
 pub mod summary;
+pub mod thresholds;
+pub mod thumbnail;
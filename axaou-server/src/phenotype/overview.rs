@@ -4,8 +4,11 @@
 //! test results into a unified view for the Overview tab.
 
 use crate::api::AppState;
+use crate::clickhouse::QueryExt;
 use crate::error::AppError;
-use crate::phenotype::manhattan::{compute_neg_log10_p, fetch_peak_annotations, BurdenResult, GeneInLocus, Peak};
+use crate::phenotype::manhattan::{
+    ancestry_param, compute_neg_log10_p, fetch_peak_annotations, BurdenResult, GeneInLocus, Peak,
+};
 use axum::{
     extract::{Path, Query, State},
     Json,
@@ -237,7 +240,8 @@ pub async fn get_phenotype_overview(
 ) -> Result<Json<UnifiedOverviewResponse>, AppError> {
     debug!("Fetching unified overview for phenotype: {}", analysis_id);
 
-    let ancestry = params.ancestry.as_deref().unwrap_or("meta");
+    let ancestry = ancestry_param(params.ancestry.as_deref())?.to_string();
+    let ancestry = ancestry.as_str();
     let data_version = params.v.as_deref().unwrap_or("");
 
     // Construct cache key with data version
@@ -247,12 +251,36 @@ pub async fn get_phenotype_overview(
     if let Some(cached_bytes) = state.api_cache.get(&cache_key).await {
         debug!("Cache hit for overview: {}", cache_key);
         let response: UnifiedOverviewResponse = serde_json::from_slice(&cached_bytes)
-            .map_err(|e| AppError::DataTransformError(format!("Failed to deserialize cached overview: {}", e)))?;
+            .map_err(|e| AppError::Internal(format!("Failed to deserialize cached overview: {}", e)))?;
         return Ok(Json(response));
     }
 
     debug!("Cache miss for overview: {}", cache_key);
 
+    // Concurrent misses for the same overview share one build
+    let json_bytes = state
+        .inflight
+        .run(&cache_key, || async {
+            let response = build_phenotype_overview(&state, &analysis_id, ancestry).await;
+            let json_bytes = serde_json::to_vec(&response)
+                .map_err(|e| AppError::Internal(format!("Failed to serialize overview: {}", e)))?;
+            state.api_cache.insert(cache_key.clone(), json_bytes.clone()).await;
+            debug!("Cached overview: {}", cache_key);
+            Ok::<_, AppError>(json_bytes)
+        })
+        .await?;
+
+    let response: UnifiedOverviewResponse = serde_json::from_slice(&json_bytes)
+        .map_err(|e| AppError::Internal(format!("Failed to deserialize overview: {}", e)))?;
+    Ok(Json(response))
+}
+
+/// Build the unified overview; failed sub-queries contribute no loci
+async fn build_phenotype_overview(
+    state: &AppState,
+    analysis_id: &str,
+    ancestry: &str,
+) -> UnifiedOverviewResponse {
     // Fetch genome peaks, exome peaks, and burden hits in parallel
     let burden_threshold = 2.5e-6;
     let burden_query = r#"
@@ -270,8 +298,8 @@ pub async fn get_phenotype_overview(
     let (genome_peaks, exome_peaks, burden_rows) = tokio::join!(
         async {
             fetch_peak_annotations(
-                &state,
-                &analysis_id,
+                state,
+                analysis_id,
                 ancestry,
                 "genome",
                 "genome_annotations",
@@ -283,8 +311,8 @@ pub async fn get_phenotype_overview(
         },
         async {
             fetch_peak_annotations(
-                &state,
-                &analysis_id,
+                state,
+                analysis_id,
                 ancestry,
                 "exome",
                 "exome_annotations",
@@ -298,12 +326,12 @@ pub async fn get_phenotype_overview(
             let rows: Vec<SignificantBurdenRow> = state
                 .clickhouse
                 .query(burden_query)
-                .bind(&analysis_id)
+                .bind(analysis_id)
                 .bind(ancestry)
                 .bind(burden_threshold)
                 .bind(burden_threshold)
                 .bind(burden_threshold)
-                .fetch_all()
+                .fetch_all_with(&state.executor)
                 .await
                 .unwrap_or_default();
             rows
@@ -439,17 +467,9 @@ pub async fn get_phenotype_overview(
         analysis_id, ancestry
     );
 
-    let response = UnifiedOverviewResponse {
+    UnifiedOverviewResponse {
         genome_image_url,
         exome_image_url,
         unified_loci,
-    };
-
-    // Cache the response as JSON bytes
-    if let Ok(json_bytes) = serde_json::to_vec(&response) {
-        state.api_cache.insert(cache_key.clone(), json_bytes).await;
-        debug!("Cached overview: {}", cache_key);
     }
-
-    Ok(Json(response))
 }
@@ -0,0 +1,414 @@
+//! On-disk cache of plot PNGs proxied from GCS
+//!
+//! In `proxy` delivery mode every Manhattan and locus plot request would
+//! otherwise fetch the PNG from the bucket; the in-memory API cache only
+//! helps until the process restarts or evicts it. With `cache_dir` set in
+//! `[plot_delivery]`, fetched images are kept on local disk:
+//!
+//! ```toml
+//! [plot_delivery]
+//! cache_dir = "/var/cache/axaou/gcs-plots"
+//! cache_max_mb = 2048
+//! revalidate_secs = 300
+//! ```
+//!
+//! Each image is stored with the GCS generation it was fetched at. Entries
+//! older than `revalidate_secs` are revalidated with a metadata request and
+//! refetched only if the object's generation changed; if GCS can't be
+//! reached, the cached copy is served stale. When the directory
+//! grows past `cache_max_mb`, the least recently used images are removed.
+//! The index is rebuilt from the directory at startup.
+//!
+//! The same cache holds images the server renders itself (see
+//! `manhattan_render`), keyed by a name of the caller's choosing and
+//! versioned by the data version instead of a GCS generation.
+
+use crate::error::AppError;
+use crate::phenotype::manhattan::parse_gcs_uri;
+use object_store::gcp::{GoogleCloudStorage, GoogleCloudStorageBuilder};
+use object_store::path::Path as ObjectPath;
+use object_store::{ObjectMeta, ObjectStore};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, info, warn};
+
+/// Suffix of temporary files, unique per write so concurrent writers of
+/// one key never share a file
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Sidecar written next to each cached image
+#[derive(Debug, Serialize, Deserialize)]
+struct EntryMeta {
+    uri: String,
+    generation: String,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    /// File stem shared by the image and its sidecar
+    stem: String,
+    generation: String,
+    size: u64,
+    last_used: SystemTime,
+    /// When the generation was last confirmed against GCS (`None` after a restart)
+    validated_at: Option<Instant>,
+}
+
+/// LRU disk cache of GCS plot images
+pub struct PlotDiskCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    revalidate_after: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl PlotDiskCache {
+    /// Open the cache at `dir`, indexing images left by earlier runs
+    pub fn open(dir: &Path, max_bytes: u64, revalidate_after: Duration) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let mut entries = HashMap::new();
+        for file in std::fs::read_dir(dir)? {
+            let path = file?.path();
+            match path.extension().and_then(|e| e.to_str()) {
+                Some("json") => {}
+                // Left by a write interrupted by shutdown
+                Some("tmp") => {
+                    let _ = std::fs::remove_file(&path);
+                    continue;
+                }
+                _ => continue,
+            }
+            let Some(stem) = path.file_stem().and_then(|s| s.to_str()).map(str::to_string) else {
+                continue;
+            };
+            let image = dir.join(format!("{}.png", stem));
+            let (Ok(contents), Ok(image_meta)) = (std::fs::read(&path), std::fs::metadata(&image))
+            else {
+                continue;
+            };
+            let Ok(meta) = serde_json::from_slice::<EntryMeta>(&contents) else {
+                continue;
+            };
+            entries.insert(
+                meta.uri,
+                Entry {
+                    stem,
+                    generation: meta.generation,
+                    size: image_meta.len(),
+                    last_used: image_meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    validated_at: None,
+                },
+            );
+        }
+        info!("Plot disk cache at {:?}: {} images", dir, entries.len());
+
+        let cache = Self {
+            dir: dir.to_path_buf(),
+            max_bytes,
+            revalidate_after,
+            entries: Mutex::new(entries),
+        };
+        cache.evict();
+        Ok(cache)
+    }
+
+    /// Fetch a `gs://` PNG and its generation, from disk when the cached
+    /// generation is current
+    pub async fn fetch(&self, gcs_uri: &str) -> Result<(Vec<u8>, String), AppError> {
+        let cached = self.entries.lock().unwrap().get(gcs_uri).cloned();
+        if let Some(entry) = &cached {
+            let fresh = entry
+                .validated_at
+                .is_some_and(|at| at.elapsed() < self.revalidate_after);
+            if fresh {
+                if let Some(bytes) = self.read(gcs_uri, entry, false).await {
+                    return Ok((bytes, entry.generation.clone()));
+                }
+            }
+        }
+
+        let (store, object_path) = gcs_object(gcs_uri)?;
+        if let Some(entry) = &cached {
+            // Conditional revalidation: a metadata request instead of the image
+            match store.head(&object_path).await {
+                Ok(meta) if generation(&meta) == entry.generation => {
+                    if let Some(bytes) = self.read(gcs_uri, entry, true).await {
+                        debug!("Plot disk cache revalidated: {}", gcs_uri);
+                        return Ok((bytes, entry.generation.clone()));
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    // Left unvalidated, so the next request retries GCS
+                    warn!("Failed to stat {}, serving cached copy: {}", gcs_uri, e);
+                    if let Some(bytes) = self.read(gcs_uri, entry, false).await {
+                        return Ok((bytes, entry.generation.clone()));
+                    }
+                    return Err(AppError::UpstreamGcs(format!(
+                        "Failed to stat {}: {}",
+                        gcs_uri, e
+                    )));
+                }
+            }
+        }
+
+        let (bytes, generation) = get_object(&store, &object_path).await?;
+        if let Err(e) = self.store(gcs_uri, &generation, &bytes).await {
+            warn!("Failed to write {} to the plot disk cache: {}", gcs_uri, e);
+        }
+        Ok((bytes, generation))
+    }
+
+    /// Cached copy of a locally produced image stored under `key` at `version`
+    pub async fn lookup(&self, key: &str, version: &str) -> Option<Vec<u8>> {
+        let entry = self.entries.lock().unwrap().get(key).cloned()?;
+        if entry.generation != version {
+            return None;
+        }
+        self.read(key, &entry, false).await
+    }
+
+    /// Store a locally produced image under `key` at `version`, replacing
+    /// the copy of any other version
+    pub async fn insert(&self, key: &str, version: &str, bytes: &[u8]) -> std::io::Result<()> {
+        self.store(key, version, bytes).await
+    }
+
+    /// Read a cached image, marking it used (and validated); `None` drops a
+    /// missing or unreadable entry
+    async fn read(&self, gcs_uri: &str, entry: &Entry, validated: bool) -> Option<Vec<u8>> {
+        match tokio::fs::read(self.dir.join(format!("{}.png", entry.stem))).await {
+            Ok(bytes) => {
+                if let Some(current) = self.entries.lock().unwrap().get_mut(gcs_uri) {
+                    current.last_used = SystemTime::now();
+                    if validated {
+                        current.validated_at = Some(Instant::now());
+                    }
+                }
+                Some(bytes)
+            }
+            Err(e) => {
+                debug!("Plot disk cache entry for {} unreadable: {}", gcs_uri, e);
+                self.entries.lock().unwrap().remove(gcs_uri);
+                None
+            }
+        }
+    }
+
+    /// Write an image and its sidecar, then evict down to the size budget
+    async fn store(&self, gcs_uri: &str, generation: &str, bytes: &[u8]) -> std::io::Result<()> {
+        let stem = format!("{:016x}", fnv1a(gcs_uri));
+        let meta = serde_json::to_vec(&EntryMeta {
+            uri: gcs_uri.to_string(),
+            generation: generation.to_string(),
+        })?;
+        // Write to temporary names and rename, so readers never see a partial file
+        for (extension, contents) in [("png", bytes), ("json", meta.as_slice())] {
+            let tmp = self.dir.join(format!(
+                "{}.{}.{}-{}.tmp",
+                stem,
+                extension,
+                std::process::id(),
+                TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            tokio::fs::write(&tmp, contents).await?;
+            tokio::fs::rename(&tmp, self.dir.join(format!("{}.{}", stem, extension))).await?;
+        }
+
+        self.entries.lock().unwrap().insert(
+            gcs_uri.to_string(),
+            Entry {
+                stem,
+                generation: generation.to_string(),
+                size: bytes.len() as u64,
+                last_used: SystemTime::now(),
+                validated_at: Some(Instant::now()),
+            },
+        );
+        self.evict();
+        Ok(())
+    }
+
+    /// Remove least recently used images until the cache fits its budget
+    fn evict(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        let mut total: u64 = entries.values().map(|e| e.size).sum();
+        if total <= self.max_bytes {
+            return;
+        }
+        let mut by_age: Vec<(SystemTime, String)> = entries
+            .iter()
+            .map(|(uri, e)| (e.last_used, uri.clone()))
+            .collect();
+        by_age.sort();
+        for (_, uri) in by_age {
+            if total <= self.max_bytes {
+                break;
+            }
+            if let Some(entry) = entries.remove(&uri) {
+                total -= entry.size;
+                for extension in ["png", "json"] {
+                    let _ = std::fs::remove_file(self.dir.join(format!("{}.{}", entry.stem, extension)));
+                }
+                debug!("Evicted {} from the plot disk cache", uri);
+            }
+        }
+    }
+
+    /// Images cached and their total size in bytes
+    pub fn usage(&self) -> (usize, u64) {
+        let entries = self.entries.lock().unwrap();
+        (entries.len(), entries.values().map(|e| e.size).sum())
+    }
+}
+
+/// Fetch a `gs://` plot PNG, through the disk cache when one is configured
+pub async fn fetch_plot_png(cache: Option<&PlotDiskCache>, gcs_uri: &str) -> Result<Vec<u8>, AppError> {
+    Ok(fetch_plot_object(cache, gcs_uri).await?.0)
+}
+
+/// Fetch a `gs://` plot PNG and its generation, through the disk cache when
+/// one is configured
+pub async fn fetch_plot_object(
+    cache: Option<&PlotDiskCache>,
+    gcs_uri: &str,
+) -> Result<(Vec<u8>, String), AppError> {
+    match cache {
+        Some(cache) => cache.fetch(gcs_uri).await,
+        None => {
+            let (store, object_path) = gcs_object(gcs_uri)?;
+            get_object(&store, &object_path).await
+        }
+    }
+}
+
+/// GCS client and object path of a `gs://` URI
+pub(crate) fn gcs_object(gcs_uri: &str) -> Result<(GoogleCloudStorage, ObjectPath), AppError> {
+    let (bucket, path) = parse_gcs_uri(gcs_uri)
+        .ok_or_else(|| AppError::Internal(format!("Invalid GCS URI: {}", gcs_uri)))?;
+    let store = GoogleCloudStorageBuilder::new()
+        .with_bucket_name(&bucket)
+        .build()
+        .map_err(|e| AppError::UpstreamGcs(format!("Failed to create GCS client: {}", e)))?;
+    Ok((store, ObjectPath::from(path.as_str())))
+}
+
+/// Object bytes and generation
+async fn get_object(
+    store: &GoogleCloudStorage,
+    object_path: &ObjectPath,
+) -> Result<(Vec<u8>, String), AppError> {
+    let result = store
+        .get(object_path)
+        .await
+        .map_err(|e| AppError::UpstreamGcs(format!("Failed to fetch from GCS: {}", e)))?;
+    let generation = generation(&result.meta);
+    let bytes = result
+        .bytes()
+        .await
+        .map_err(|e| AppError::UpstreamGcs(format!("Failed to read bytes from GCS: {}", e)))?;
+    Ok((bytes.to_vec(), generation))
+}
+
+/// GCS object generation, falling back to the ETag
+pub(crate) fn generation(meta: &ObjectMeta) -> String {
+    meta.version
+        .clone()
+        .or_else(|| meta.e_tag.clone())
+        .unwrap_or_default()
+}
+
+fn fnv1a(s: &str) -> u64 {
+    s.bytes()
+        .fold(0xcbf29ce484222325u64, |h, b| (h ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("axaou-plot-cache-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn test_evicts_least_recently_used() {
+        let dir = scratch_dir();
+        let cache = PlotDiskCache::open(&dir, 250, Duration::from_secs(300)).unwrap();
+        cache.store("gs://b/a.png", "1", &[0; 100]).await.unwrap();
+        cache.store("gs://b/b.png", "1", &[0; 100]).await.unwrap();
+
+        // Touch a.png so b.png is the least recently used
+        let a = cache.entries.lock().unwrap()["gs://b/a.png"].clone();
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(cache.read("gs://b/a.png", &a, false).await.is_some());
+
+        cache.store("gs://b/c.png", "1", &[0; 100]).await.unwrap();
+        let entries = cache.entries.lock().unwrap().clone();
+        assert!(entries.contains_key("gs://b/a.png"));
+        assert!(!entries.contains_key("gs://b/b.png"));
+        assert!(entries.contains_key("gs://b/c.png"));
+        assert_eq!(cache.usage(), (2, 200));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_stores_of_one_key() {
+        let dir = scratch_dir();
+        let cache = PlotDiskCache::open(&dir, 1 << 20, Duration::from_secs(300)).unwrap();
+        let images: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; 4096]).collect();
+        let writes = images
+            .iter()
+            .map(|image| cache.store("gs://b/height.png", "1", image));
+        for result in futures::future::join_all(writes).await {
+            result.unwrap();
+        }
+
+        // One writer's image survives whole, and no temporary files remain
+        let entry = cache.entries.lock().unwrap()["gs://b/height.png"].clone();
+        let bytes = cache.read("gs://b/height.png", &entry, false).await.unwrap();
+        assert!(images.contains(&bytes));
+        let files = std::fs::read_dir(&dir).unwrap().count();
+        assert_eq!(files, 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_local_entries_are_versioned() {
+        let dir = scratch_dir();
+        let cache = PlotDiskCache::open(&dir, 150, Duration::MAX).unwrap();
+        cache.insert("rendered:height", "v1", &[1; 100]).await.unwrap();
+        assert_eq!(cache.lookup("rendered:height", "v1").await.unwrap(), vec![1; 100]);
+        assert!(cache.lookup("rendered:height", "v2").await.is_none());
+
+        // A new version replaces the old file rather than adding one
+        cache.insert("rendered:height", "v2", &[2; 100]).await.unwrap();
+        assert_eq!(cache.lookup("rendered:height", "v2").await.unwrap(), vec![2; 100]);
+        assert_eq!(cache.usage(), (1, 100));
+
+        // Renders count against the size budget like any other image
+        cache.insert("rendered:weight", "v2", &[3; 100]).await.unwrap();
+        assert_eq!(cache.usage(), (1, 100));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_index_survives_restart() {
+        let dir = scratch_dir();
+        let cache = PlotDiskCache::open(&dir, 1 << 20, Duration::from_secs(300)).unwrap();
+        cache.store("gs://b/height.png", "1700000000000000", b"png").await.unwrap();
+        drop(cache);
+
+        let reopened = PlotDiskCache::open(&dir, 1 << 20, Duration::from_secs(300)).unwrap();
+        let entry = reopened.entries.lock().unwrap()["gs://b/height.png"].clone();
+        assert_eq!(entry.generation, "1700000000000000");
+        // Entries from an earlier run are revalidated before use
+        assert!(entry.validated_at.is_none());
+        assert_eq!(reopened.read("gs://b/height.png", &entry, true).await.unwrap(), b"png");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
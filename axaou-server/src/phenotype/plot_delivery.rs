@@ -0,0 +1,460 @@
+//! Plot image delivery modes
+//!
+//! Pre-rendered Manhattan and locus PNGs live in GCS. By default the server
+//! proxies the bytes, but that makes the axum process a bandwidth bottleneck.
+//! The `[plot_delivery]` config section can instead hand out short-lived V4
+//! signed URLs, either as JSON or as a redirect:
+//!
+//! ```toml
+//! [plot_delivery]
+//! mode = "redirect"   # "proxy" (default), "signed_url" or "redirect"
+//! ttl_secs = 900
+//! ```
+//!
+//! Plots rendered on the server (no GCS object) are always streamed. In
+//! `proxy` mode, `cache_dir` keeps fetched images on local disk (see
+//! `plot_cache`).
+//!
+//! Proxied images honour single `Range: bytes=...` requests with `206` (or
+//! `416`) so browsers and CDNs can resume partial downloads. Every 200, 206
+//! and HEAD response carries a strong `ETag` (the GCS generation, or a hash of
+//! images held in memory) and `If-Range` is honoured, so a resumed download
+//! never mixes bytes of two object versions. Without a copy in memory or on
+//! disk, ranges are read from GCS pinned to the object's ETag; HEAD is always
+//! answered from object metadata without downloading the image.
+
+use crate::error::AppError;
+use crate::phenotype::manhattan::parse_gcs_uri;
+use crate::phenotype::plot_cache::{fetch_plot_object, gcs_object, generation, PlotDiskCache};
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use object_store::gcp::GoogleCloudStorageBuilder;
+use object_store::path::Path as ObjectPath;
+use object_store::signer::Signer;
+use object_store::{GetOptions, GetRange, ObjectMeta, ObjectStore};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How plot images stored in GCS are delivered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlotDeliveryMode {
+    /// Stream bytes through the server
+    #[default]
+    Proxy,
+    /// Return `{ "url", "expires_in" }` JSON with a signed URL
+    SignedUrl,
+    /// 307 redirect to a signed URL
+    Redirect,
+}
+
+/// `[plot_delivery]` config section
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct PlotDeliveryConfig {
+    pub mode: PlotDeliveryMode,
+    /// Lifetime of signed URLs in seconds
+    pub ttl_secs: u64,
+    /// Directory for caching proxied images (default: no disk cache)
+    pub cache_dir: Option<PathBuf>,
+    /// Size budget of `cache_dir` in MiB
+    pub cache_max_mb: u64,
+    /// Age after which a cached image is revalidated against GCS
+    pub revalidate_secs: u64,
+}
+
+impl Default for PlotDeliveryConfig {
+    fn default() -> Self {
+        Self {
+            mode: PlotDeliveryMode::Proxy,
+            ttl_secs: 900,
+            cache_dir: None,
+            cache_max_mb: 2048,
+            revalidate_secs: 300,
+        }
+    }
+}
+
+/// Body returned in `signed_url` mode
+#[derive(Debug, Serialize)]
+pub struct SignedPlotUrl {
+    pub url: String,
+    /// Seconds until the URL expires
+    pub expires_in: u64,
+}
+
+/// Create a V4 signed GET URL for a `gs://` object
+pub async fn sign_gcs_uri(gcs_uri: &str, ttl: Duration) -> Result<String, AppError> {
+    let (bucket, path) = parse_gcs_uri(gcs_uri)
+        .ok_or_else(|| AppError::Internal(format!("Invalid GCS URI: {}", gcs_uri)))?;
+
+    let store = GoogleCloudStorageBuilder::new()
+        .with_bucket_name(&bucket)
+        .build()
+        .map_err(|e| AppError::UpstreamGcs(format!("Failed to create GCS client: {}", e)))?;
+
+    let url = store
+        .signed_url(Method::GET, &ObjectPath::from(path.as_str()), ttl)
+        .await
+        .map_err(|e| AppError::UpstreamGcs(format!("Failed to sign GCS URL: {}", e)))?;
+
+    Ok(url.to_string())
+}
+
+/// Response for a GCS-hosted plot, or `None` when the image should be proxied
+pub async fn signed_plot_response(
+    config: &PlotDeliveryConfig,
+    gcs_uri: &str,
+) -> Result<Option<Response>, AppError> {
+    if config.mode == PlotDeliveryMode::Proxy {
+        return Ok(None);
+    }
+
+    let url = sign_gcs_uri(gcs_uri, Duration::from_secs(config.ttl_secs)).await?;
+
+    // Signed URLs expire, so neither the redirect nor the JSON may be cached
+    // past their lifetime; set Cache-Control here so the route policy keeps it
+    let response = match config.mode {
+        PlotDeliveryMode::Redirect => (
+            StatusCode::TEMPORARY_REDIRECT,
+            [(header::LOCATION, url), (header::CACHE_CONTROL, "no-store".to_string())],
+        )
+            .into_response(),
+        _ => (
+            [(header::CACHE_CONTROL, "no-store")],
+            Json(SignedPlotUrl {
+                url,
+                expires_in: config.ttl_secs,
+            }),
+        )
+            .into_response(),
+    };
+    Ok(Some(response))
+}
+
+/// Outcome of matching a `Range` header against a body length
+#[derive(Debug, PartialEq, Eq)]
+pub enum ByteRange {
+    /// No usable range: serve the whole body
+    Full,
+    /// Serve these bytes with 206
+    Partial(Range<u64>),
+    /// The range starts past the end: 416
+    Unsatisfiable,
+}
+
+/// Match a `Range` header against a body of `len` bytes
+///
+/// Only a single `bytes=` range is honoured; multiple ranges and malformed
+/// headers are ignored, as RFC 9110 allows.
+pub fn parse_range(range: Option<&str>, len: u64) -> ByteRange {
+    let Some(spec) = range.and_then(|r| r.trim().strip_prefix("bytes=")) else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.split_once('-') else {
+        return ByteRange::Full;
+    };
+    let (start, end) = (start.trim(), end.trim());
+
+    if start.is_empty() {
+        // Suffix range: the last `end` bytes
+        return match end.parse::<u64>() {
+            Ok(0) => ByteRange::Unsatisfiable,
+            Ok(n) if len > 0 => ByteRange::Partial(len.saturating_sub(n)..len),
+            Ok(_) => ByteRange::Unsatisfiable,
+            Err(_) => ByteRange::Full,
+        };
+    }
+    let Ok(start) = start.parse::<u64>() else {
+        return ByteRange::Full;
+    };
+    let end = match end {
+        "" => len,
+        end => match end.parse::<u64>() {
+            Ok(end) if end >= start => (end + 1).min(len),
+            _ => return ByteRange::Full,
+        },
+    };
+    if start >= len {
+        ByteRange::Unsatisfiable
+    } else {
+        ByteRange::Partial(start..end)
+    }
+}
+
+/// Strong validators of one version of an image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validator {
+    etag: String,
+    last_modified: Option<String>,
+}
+
+impl Validator {
+    /// Validator of a GCS object version
+    pub fn from_generation(generation: &str) -> Self {
+        Self {
+            etag: format!("\"{}\"", generation),
+            last_modified: None,
+        }
+    }
+
+    fn from_meta(meta: &ObjectMeta) -> Self {
+        Self {
+            last_modified: Some(meta.last_modified.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+            ..Self::from_generation(&generation(meta))
+        }
+    }
+
+    /// Validator of an image held in memory, from its content
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let tag = crate::etag::compute_etag(bytes);
+        Self {
+            etag: tag.trim_start_matches("W/").to_string(),
+            last_modified: None,
+        }
+    }
+
+    /// Whether an `If-Range` value names this version (strong comparison)
+    fn matches(&self, if_range: &str) -> bool {
+        let if_range = if_range.trim();
+        if if_range.starts_with('"') {
+            if_range == self.etag
+        } else {
+            // A weak tag never matches; otherwise an HTTP date
+            !if_range.starts_with("W/") && self.last_modified.as_deref() == Some(if_range)
+        }
+    }
+
+    fn apply(&self, mut response: Response) -> Response {
+        let headers = response.headers_mut();
+        if let Ok(etag) = HeaderValue::from_str(&self.etag) {
+            headers.insert(header::ETAG, etag);
+        }
+        if let Some(Ok(date)) = self.last_modified.as_deref().map(HeaderValue::from_str) {
+            headers.insert(header::LAST_MODIFIED, date);
+        }
+        response
+    }
+}
+
+/// The `Range` header, unless `If-Range` names another version of the image
+fn range_header<'a>(headers: &'a HeaderMap, validator: &Validator) -> Option<&'a str> {
+    let if_range = headers.get(header::IF_RANGE).map(|v| v.to_str().unwrap_or_default());
+    if if_range.is_some_and(|if_range| !validator.matches(if_range)) {
+        return None;
+    }
+    headers.get(header::RANGE).and_then(|v| v.to_str().ok())
+}
+
+fn unsatisfiable(len: u64) -> Response {
+    Response::builder()
+        .status(StatusCode::RANGE_NOT_SATISFIABLE)
+        .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+        .header(header::ACCEPT_RANGES, "bytes")
+        .body(Body::empty())
+        .unwrap()
+}
+
+fn partial(range: &Range<u64>, len: u64, body: Body, validator: &Validator) -> Response {
+    let response = Response::builder()
+        .status(StatusCode::PARTIAL_CONTENT)
+        .header(header::CONTENT_TYPE, "image/png")
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CONTENT_LENGTH, range.end - range.start)
+        .header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", range.start, range.end - 1, len),
+        )
+        .body(body)
+        .unwrap();
+    validator.apply(response)
+}
+
+fn full(len: u64, body: Body, validator: &Validator) -> Response {
+    let response = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "image/png")
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CONTENT_LENGTH, len)
+        .body(body)
+        .unwrap();
+    validator.apply(response)
+}
+
+/// PNG response for an image held in memory, honouring `Range`
+pub fn png_response(bytes: Vec<u8>, headers: &HeaderMap) -> Response {
+    let validator = Validator::from_bytes(&bytes);
+    versioned_png_response(bytes, headers, &validator)
+}
+
+/// PNG response for one known version of an image, honouring `Range`
+fn versioned_png_response(bytes: Vec<u8>, headers: &HeaderMap, validator: &Validator) -> Response {
+    let len = bytes.len() as u64;
+    match parse_range(range_header(headers, validator), len) {
+        ByteRange::Full => full(len, Body::from(bytes), validator),
+        ByteRange::Partial(range) => {
+            let body = Body::from(bytes[range.start as usize..range.end as usize].to_vec());
+            partial(&range, len, body, validator)
+        }
+        ByteRange::Unsatisfiable => unsatisfiable(len),
+    }
+}
+
+/// PNG response for a proxied `gs://` image, honouring `Range` and HEAD
+///
+/// Also returns the full image when it was read, for the caller to cache.
+/// HEAD touches only object metadata. Other requests go through the disk
+/// cache when there is one; without it, ranges read only the requested bytes.
+pub async fn gcs_png_response(
+    cache: Option<&PlotDiskCache>,
+    gcs_uri: &str,
+    method: &Method,
+    headers: &HeaderMap,
+) -> Result<(Response, Option<Vec<u8>>), AppError> {
+    let ranged = headers.contains_key(header::RANGE);
+    let is_head = *method == Method::HEAD;
+    if !is_head && (cache.is_some() || !ranged) {
+        return whole_object_response(cache, gcs_uri, headers).await;
+    }
+
+    let (store, object_path) = gcs_object(gcs_uri)?;
+    let meta = store
+        .head(&object_path)
+        .await
+        .map_err(|e| AppError::UpstreamGcs(format!("Failed to stat {}: {}", gcs_uri, e)))?;
+    let validator = Validator::from_meta(&meta);
+    let len = meta.size as u64;
+
+    let response = match parse_range(range_header(headers, &validator), len) {
+        ByteRange::Unsatisfiable => unsatisfiable(len),
+        ByteRange::Partial(range) if is_head => partial(&range, len, Body::empty(), &validator),
+        ByteRange::Partial(range) => {
+            // Fails rather than mixing versions if the object was replaced
+            // since the metadata request
+            let options = GetOptions {
+                if_match: meta.e_tag.clone(),
+                range: Some(GetRange::Bounded(range.start as usize..range.end as usize)),
+                ..GetOptions::default()
+            };
+            let bytes = store
+                .get_opts(&object_path, options)
+                .await
+                .map_err(|e| AppError::UpstreamGcs(format!("Failed to fetch from GCS: {}", e)))?
+                .bytes()
+                .await
+                .map_err(|e| AppError::UpstreamGcs(format!("Failed to read bytes from GCS: {}", e)))?;
+            partial(&range, len, Body::from(bytes), &validator)
+        }
+        ByteRange::Full if is_head => full(len, Body::empty(), &validator),
+        ByteRange::Full => return whole_object_response(cache, gcs_uri, headers).await,
+    };
+    Ok((response, None))
+}
+
+async fn whole_object_response(
+    cache: Option<&PlotDiskCache>,
+    gcs_uri: &str,
+    headers: &HeaderMap,
+) -> Result<(Response, Option<Vec<u8>>), AppError> {
+    let (bytes, generation) = fetch_plot_object(cache, gcs_uri).await?;
+    let validator = Validator::from_generation(&generation);
+    Ok((versioned_png_response(bytes.clone(), headers, &validator), Some(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_from_toml() {
+        let config: PlotDeliveryConfig = toml::from_str(r#"mode = "signed_url""#).unwrap();
+        assert_eq!(config.mode, PlotDeliveryMode::SignedUrl);
+        assert_eq!(config.ttl_secs, 900);
+        assert_eq!(PlotDeliveryConfig::default().mode, PlotDeliveryMode::Proxy);
+
+        let config: PlotDeliveryConfig =
+            toml::from_str("cache_dir = \"/var/cache/axaou/gcs-plots\"\ncache_max_mb = 512").unwrap();
+        assert_eq!(config.cache_dir, Some(PathBuf::from("/var/cache/axaou/gcs-plots")));
+        assert_eq!(config.cache_max_mb, 512);
+        assert_eq!(config.revalidate_secs, 300);
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range(None, 100), ByteRange::Full);
+        assert_eq!(parse_range(Some("bytes=0-49"), 100), ByteRange::Partial(0..50));
+        assert_eq!(parse_range(Some("bytes=50-"), 100), ByteRange::Partial(50..100));
+        assert_eq!(parse_range(Some("bytes=90-200"), 100), ByteRange::Partial(90..100));
+        assert_eq!(parse_range(Some("bytes=-10"), 100), ByteRange::Partial(90..100));
+        assert_eq!(parse_range(Some("bytes=-500"), 100), ByteRange::Partial(0..100));
+        assert_eq!(parse_range(Some("bytes=100-"), 100), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range(Some("bytes=-0"), 100), ByteRange::Unsatisfiable);
+        // Ignored: multiple ranges, other units, malformed
+        assert_eq!(parse_range(Some("bytes=0-1,5-9"), 100), ByteRange::Full);
+        assert_eq!(parse_range(Some("items=0-1"), 100), ByteRange::Full);
+        assert_eq!(parse_range(Some("bytes=9-1"), 100), ByteRange::Full);
+    }
+
+    #[tokio::test]
+    async fn test_png_response_ranges() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, "bytes=2-5".parse().unwrap());
+        let response = png_response(b"0123456789".to_vec(), &headers);
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes 2-5/10");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"2345");
+
+        headers.insert(header::RANGE, "bytes=10-".parse().unwrap());
+        let response = png_response(b"0123456789".to_vec(), &headers);
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes */10");
+
+        let response = png_response(b"0123456789".to_vec(), &HeaderMap::new());
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ACCEPT_RANGES], "bytes");
+        assert!(response.headers().contains_key(header::ETAG));
+    }
+
+    #[test]
+    fn test_if_range() {
+        let validator = Validator::from_generation("1700000000123456");
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, "bytes=2-5".parse().unwrap());
+        let ranged = |headers: &HeaderMap| {
+            versioned_png_response(b"0123456789".to_vec(), headers, &validator)
+        };
+
+        let response = ranged(&headers);
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()[header::ETAG], "\"1700000000123456\"");
+
+        // Same version: still partial
+        headers.insert(header::IF_RANGE, "\"1700000000123456\"".parse().unwrap());
+        assert_eq!(ranged(&headers).status(), StatusCode::PARTIAL_CONTENT);
+
+        // Another version, a weak tag or an unknown date: the whole image
+        for if_range in ["\"1700000000999999\"", "W/\"1700000000123456\"", "Tue, 14 Nov 2023 22:13:20 GMT"] {
+            headers.insert(header::IF_RANGE, if_range.parse().unwrap());
+            let response = ranged(&headers);
+            assert_eq!(response.status(), StatusCode::OK, "If-Range: {}", if_range);
+            assert_eq!(response.headers()[header::CONTENT_LENGTH], "10");
+        }
+
+        let dated = Validator {
+            last_modified: Some("Tue, 14 Nov 2023 22:13:20 GMT".to_string()),
+            ..validator.clone()
+        };
+        assert!(dated.matches("Tue, 14 Nov 2023 22:13:20 GMT"));
+        assert!(!dated.matches("Wed, 15 Nov 2023 22:13:20 GMT"));
+    }
+}
@@ -3,6 +3,7 @@
 //! Provides endpoint for retrieving pre-rendered Manhattan plot URIs.
 
 use crate::api::AppState;
+use crate::clickhouse::QueryExt;
 use crate::clickhouse::models::PlotRow;
 use crate::error::AppError;
 use axum::{
@@ -29,9 +30,8 @@ pub async fn get_phenotype_plots(
         .clickhouse
         .query(query)
         .bind(&analysis_id)
-        .fetch_all::<PlotRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with::<PlotRow>(&state.executor)
+        .await?;
 
     Ok(Json(rows))
 }
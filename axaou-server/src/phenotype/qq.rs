@@ -1,19 +1,28 @@
 //! QQ plot query handlers
 //!
-//! Provides endpoints for retrieving Q-Q plot data points.
+//! Provides endpoints for retrieving Q-Q plot data points and a server-rendered
+//! Q-Q plot image annotated with lambda GC.
 
 use crate::api::AppState;
+use crate::clickhouse::QueryExt;
 use crate::clickhouse::models::QQRow;
-use crate::error::AppError;
+use crate::error::{AppError, ErrorResponse};
+use crate::genomics::Contig;
+use crate::phenotype::qq_render::QQRenderer;
 use axum::{
+    body::Body,
     extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::Response,
     Json,
 };
 use serde::Deserialize;
 use std::sync::Arc;
+use utoipa::IntoParams;
 
 /// Query parameters for QQ plot endpoint
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct QQQuery {
     /// Ancestry group filter (default: "meta")
     pub ancestry: Option<String>,
@@ -23,12 +32,32 @@ pub struct QQQuery {
     pub contig: Option<String>,
     /// Maximum number of points to return (default: 10000)
     pub limit: Option<u32>,
+    /// Bin points into at most this many expected -log10(p) buckets, keeping the
+    /// most significant point per bucket (default: 4000, 0 disables binning)
+    pub max_points: Option<u32>,
 }
 
+/// Default number of expected -log10(p) buckets for QQ responses
+const DEFAULT_MAX_POINTS: u32 = 4000;
+
+/// Upper bound on `max_points`
+const MAX_MAX_POINTS: u32 = 50_000;
+
 /// GET /api/phenotype/:analysis_id/qq
 ///
 /// Returns QQ plot points for a phenotype.
-/// Points are pre-downsampled for efficient rendering.
+/// Points are binned by expected -log10(p) so the response size is bounded by
+/// `max_points` regardless of trait size.
+#[utoipa::path(
+    get,
+    path = "/api/phenotype/{analysis_id}/qq",
+    tag = "phenotype",
+    params(("analysis_id" = String, Path, description = "Analysis (phenotype) ID"), QQQuery),
+    responses(
+        (status = 200, description = "Q-Q plot points", body = Vec<QQRow>),
+        (status = 500, description = "ClickHouse query failed", body = ErrorResponse)
+    )
+)]
 pub async fn get_qq_plot(
     State(state): State<Arc<AppState>>,
     Path(analysis_id): Path<String>,
@@ -37,38 +66,196 @@ pub async fn get_qq_plot(
     let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
     let sequencing_type = params.sequencing_type.unwrap_or_else(|| "genomes".to_string());
 
-    let base_query = if params.contig.is_some() {
-        r#"
+    let max_points = match params.max_points.unwrap_or(DEFAULT_MAX_POINTS) {
+        0 => None,
+        n => Some(n.min(MAX_MAX_POINTS)),
+    };
+
+    // loci_variants stores GRCh38 names; accept "X", "chrx", "MT", ...
+    let contig = params
+        .contig
+        .as_deref()
+        .map(str::parse::<Contig>)
+        .transpose()?;
+
+    let rows = fetch_qq_points(
+        &state,
+        &analysis_id,
+        &ancestry,
+        &sequencing_type,
+        contig.map(Contig::chr),
+        max_points,
+    )
+    .await?;
+
+    Ok(Json(rows))
+}
+
+/// Query parameters for the QQ image endpoint
+#[derive(Debug, Deserialize)]
+pub struct QQImageQuery {
+    /// Ancestry group filter (default: "meta")
+    pub ancestry: Option<String>,
+    /// Sequencing type filter (default: "genomes")
+    pub sequencing_type: Option<String>,
+    /// Image width and height in pixels (default: 600, max: 2000)
+    pub size: Option<u32>,
+}
+
+/// GET /api/phenotype/:analysis_id/qq/image
+///
+/// Renders the QQ plot as a PNG with the lambda GC value from metadata.
+/// The lambda value is also returned in the `X-Lambda-GC` header.
+pub async fn get_qq_image(
+    State(state): State<Arc<AppState>>,
+    Path(analysis_id): Path<String>,
+    Query(params): Query<QQImageQuery>,
+) -> Result<Response, AppError> {
+    let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
+    let sequencing_type = params.sequencing_type.unwrap_or_else(|| "genomes".to_string());
+    let size = params.size.unwrap_or(600).clamp(200, 2000);
+
+    let lambda_gc = {
+        let metadata = state.metadata.read().await;
+        metadata
+            .iter()
+            .find(|m| m.analysis_id == analysis_id && m.ancestry_group.eq_ignore_ascii_case(&ancestry))
+            .and_then(|m| {
+                if sequencing_type.starts_with("exome") {
+                    m.lambda_gc_exome
+                } else {
+                    m.lambda_gc_acaf
+                }
+            })
+    };
+
+    let dv = state.data_version.as_deref().unwrap_or("none");
+    let cache_key = format!(
+        "qq-image:{}:{}:{}:{}:{}",
+        analysis_id, ancestry, sequencing_type, size, dv
+    );
+
+    let bytes = match state.api_cache.get(&cache_key).await {
+        Some(bytes) => bytes,
+        None => {
+            // One bucket per half pixel is indistinguishable from the full point set
+            let rows = fetch_qq_points(
+                &state,
+                &analysis_id,
+                &ancestry,
+                &sequencing_type,
+                None,
+                Some(size * 2),
+            )
+            .await?;
+            if rows.is_empty() {
+                return Err(AppError::NotFound(format!(
+                    "No QQ points for phenotype '{}' ({}, {})",
+                    analysis_id, ancestry, sequencing_type
+                )));
+            }
+
+            let points: Vec<(f64, f64)> = rows
+                .iter()
+                .map(|r| (r.pvalue_expected_log10, r.pvalue_log10))
+                .collect();
+
+            let bytes = tokio::task::spawn_blocking(move || {
+                let mut renderer = QQRenderer::new(size, QQRenderer::axis_max(&points));
+                renderer.draw_axes();
+                renderer.draw_points(&points);
+                if let Some(lambda) = lambda_gc {
+                    renderer.draw_lambda_label(lambda);
+                }
+                renderer.encode_png()
+            })
+            .await
+            .map_err(|e| AppError::Internal(format!("Render task failed: {}", e)))??;
+
+            state.api_cache.insert(cache_key, bytes.clone()).await;
+            bytes
+        }
+    };
+
+    let mut response = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "image/png");
+    if let Some(lambda) = lambda_gc {
+        response = response.header("x-lambda-gc", format!("{:.4}", lambda));
+    }
+
+    Ok(response.body(Body::from(bytes)).unwrap())
+}
+
+/// Fetch QQ points ordered by expected -log10(p)
+///
+/// With `max_points`, the expected -log10(p) range is split into that many
+/// equal-width buckets and only the most significant point of each bucket is
+/// kept. QQ curves are monotone, so this preserves the shape while collapsing
+/// the dense null region near the origin.
+pub(crate) async fn fetch_qq_points(
+    state: &AppState,
+    analysis_id: &str,
+    ancestry: &str,
+    sequencing_type: &str,
+    contig: Option<&str>,
+    max_points: Option<u32>,
+) -> Result<Vec<QQRow>, AppError> {
+    let filter = if contig.is_some() {
+        "phenotype = ? AND ancestry = ? AND sequencing_type = ? AND contig = ?"
+    } else {
+        "phenotype = ? AND ancestry = ? AND sequencing_type = ?"
+    };
+
+    let query = match max_points {
+        None => format!(
+            r#"
             SELECT phenotype, ancestry, sequencing_type, contig, position,
                    ref, alt, pvalue_log10, pvalue_expected_log10
             FROM qq_points
-            WHERE phenotype = ? AND ancestry = ? AND sequencing_type = ? AND contig = ?
+            WHERE {filter}
             ORDER BY pvalue_expected_log10 ASC
-        "#.to_string()
-    } else {
-        r#"
+        "#
+        ),
+        Some(_) => format!(
+            r#"
+            WITH (
+                SELECT greatest(max(pvalue_expected_log10), 1e-9)
+                FROM qq_points
+                WHERE {filter}
+            ) AS max_expected
             SELECT phenotype, ancestry, sequencing_type, contig, position,
                    ref, alt, pvalue_log10, pvalue_expected_log10
-            FROM qq_points
-            WHERE phenotype = ? AND ancestry = ? AND sequencing_type = ?
+            FROM (
+                SELECT phenotype, ancestry, sequencing_type, contig, position,
+                       ref, alt, pvalue_log10, pvalue_expected_log10
+                FROM qq_points
+                WHERE {filter}
+                ORDER BY pvalue_log10 DESC
+                LIMIT 1 BY toUInt32(floor(pvalue_expected_log10 / max_expected * (? - 1)))
+            )
             ORDER BY pvalue_expected_log10 ASC
-        "#.to_string()
+        "#
+        ),
     };
 
-    let mut query = state.clickhouse.query(&base_query);
-    query = query
-        .bind(&analysis_id)
-        .bind(&ancestry)
-        .bind(&sequencing_type);
-
-    if let Some(ref contig) = params.contig {
-        query = query.bind(contig);
+    let mut query = state.clickhouse.query(&query);
+    // The filter appears twice when binning (max_expected subquery + main query)
+    let filter_repeats = if max_points.is_some() { 2 } else { 1 };
+    for _ in 0..filter_repeats {
+        query = query
+            .bind(analysis_id)
+            .bind(ancestry)
+            .bind(sequencing_type);
+        if let Some(contig) = contig {
+            query = query.bind(contig);
+        }
+    }
+    if let Some(max_points) = max_points {
+        query = query.bind(max_points.max(2));
     }
 
-    let rows = query
-        .fetch_all::<QQRow>()
+    query
+        .fetch_all_with::<QQRow>(&state.executor)
         .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
-
-    Ok(Json(rows))
 }
@@ -0,0 +1,202 @@
+//! Server-side Q-Q plot rendering
+//!
+//! Rasterizes `qq_points` into a square PNG with the y = x reference line and
+//! a lambda GC label, so clients on slow connections do not need to download
+//! tens of thousands of points.
+
+use crate::error::AppError;
+use tiny_skia::{Color, Paint, PathBuilder, Pixmap, Rect, Stroke, Transform};
+
+/// Padding around the plot area in pixels
+const MARGIN: f32 = 24.0;
+/// Point half-size in pixels
+const POINT_RADIUS: f32 = 1.5;
+/// Pixels per bitmap-font pixel for the lambda label
+const LABEL_SCALE: f32 = 3.0;
+
+/// Renders a Q-Q plot of observed vs expected -log10(p)
+pub struct QQRenderer {
+    pixmap: Pixmap,
+    size: f32,
+    max_value: f64,
+}
+
+impl QQRenderer {
+    /// Create a renderer whose axes span `[0, max_value]` on both sides
+    pub fn new(size: u32, max_value: f64) -> Self {
+        let mut pixmap = Pixmap::new(size, size)
+            .expect("Failed to allocate pixmap. Dimensions may be too large.");
+        pixmap.fill(Color::WHITE);
+
+        Self {
+            pixmap,
+            size: size as f32,
+            max_value: max_value.max(1.0),
+        }
+    }
+
+    /// Axis maximum covering all points with 5% headroom
+    pub fn axis_max(points: &[(f64, f64)]) -> f64 {
+        points
+            .iter()
+            .flat_map(|&(expected, observed)| [expected, observed])
+            .filter(|v| v.is_finite())
+            .fold(1.0_f64, f64::max)
+            * 1.05
+    }
+
+    fn to_pixel(&self, expected: f64, observed: f64) -> (f32, f32) {
+        let span = self.size - 2.0 * MARGIN;
+        let x = MARGIN + (expected / self.max_value) as f32 * span;
+        let y = self.size - MARGIN - (observed / self.max_value) as f32 * span;
+        (x, y)
+    }
+
+    fn stroke_line(&mut self, from: (f32, f32), to: (f32, f32), color: Color, width: f32) {
+        let mut paint = Paint::default();
+        paint.set_color(color);
+        paint.anti_alias = true;
+
+        let mut stroke = Stroke::default();
+        stroke.width = width;
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(from.0, from.1);
+        pb.line_to(to.0, to.1);
+
+        if let Some(path) = pb.finish() {
+            self.pixmap
+                .stroke_path(&path, &paint, &stroke, Transform::identity(), None);
+        }
+    }
+
+    /// Draw the x/y axes and the y = x reference line
+    pub fn draw_axes(&mut self) {
+        let origin = self.to_pixel(0.0, 0.0);
+        let x_end = self.to_pixel(self.max_value, 0.0);
+        let y_end = self.to_pixel(0.0, self.max_value);
+        let grey = Color::from_rgba8(120, 120, 120, 255);
+
+        self.stroke_line(origin, x_end, grey, 1.0);
+        self.stroke_line(origin, y_end, grey, 1.0);
+        self.stroke_line(
+            origin,
+            self.to_pixel(self.max_value, self.max_value),
+            Color::from_rgba8(220, 38, 38, 255),
+            1.5,
+        );
+    }
+
+    /// Draw (expected, observed) -log10(p) points
+    pub fn draw_points(&mut self, points: &[(f64, f64)]) {
+        let mut paint = Paint::default();
+        paint.set_color_rgba8(38, 84, 166, 200);
+
+        for &(expected, observed) in points {
+            if !expected.is_finite() || !observed.is_finite() {
+                continue;
+            }
+            let (x, y) = self.to_pixel(expected, observed);
+            if let Some(rect) = Rect::from_xywh(
+                x - POINT_RADIUS,
+                y - POINT_RADIUS,
+                POINT_RADIUS * 2.0,
+                POINT_RADIUS * 2.0,
+            ) {
+                self.pixmap
+                    .fill_rect(rect, &paint, Transform::identity(), None);
+            }
+        }
+    }
+
+    /// Draw "λGC = x.xxx" in the top-left corner of the plot area
+    pub fn draw_lambda_label(&mut self, lambda_gc: f64) {
+        let text = format!("λGC = {:.3}", lambda_gc);
+        let mut paint = Paint::default();
+        paint.set_color_rgba8(33, 33, 33, 255);
+
+        let mut cursor_x = MARGIN + 2.0 * LABEL_SCALE;
+        let top = MARGIN;
+        for ch in text.chars() {
+            if let Some(rows) = glyph(ch) {
+                for (row, bits) in rows.iter().enumerate() {
+                    for col in 0..5 {
+                        if bits & (0x10 >> col) == 0 {
+                            continue;
+                        }
+                        if let Some(rect) = Rect::from_xywh(
+                            cursor_x + col as f32 * LABEL_SCALE,
+                            top + row as f32 * LABEL_SCALE,
+                            LABEL_SCALE,
+                            LABEL_SCALE,
+                        ) {
+                            self.pixmap
+                                .fill_rect(rect, &paint, Transform::identity(), None);
+                        }
+                    }
+                }
+            }
+            cursor_x += 6.0 * LABEL_SCALE;
+        }
+    }
+
+    /// Encode the rendered pixmap to PNG bytes
+    pub fn encode_png(&self) -> Result<Vec<u8>, AppError> {
+        self.pixmap
+            .encode_png()
+            .map_err(|e| AppError::Internal(format!("PNG encoding failed: {}", e)))
+    }
+}
+
+/// 5x7 bitmap glyphs for the characters used in the lambda label
+fn glyph(ch: char) -> Option<[u8; 7]> {
+    let rows = match ch {
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        '=' => [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'λ' => [0x08, 0x04, 0x04, 0x0A, 0x0A, 0x11, 0x11],
+        _ => return None,
+    };
+    Some(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn axis_max_covers_points() {
+        assert_eq!(QQRenderer::axis_max(&[]), 1.05);
+        let max = QQRenderer::axis_max(&[(3.0, 8.0), (2.0, f64::INFINITY)]);
+        assert!((max - 8.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn label_glyphs_exist() {
+        for ch in "λGC = 1.234567890".chars().filter(|c| *c != ' ') {
+            assert!(glyph(ch).is_some(), "missing glyph for {:?}", ch);
+        }
+    }
+
+    #[test]
+    fn renders_png() {
+        let points = vec![(0.5, 0.4), (2.0, 2.5), (4.0, 9.0)];
+        let mut renderer = QQRenderer::new(400, QQRenderer::axis_max(&points));
+        renderer.draw_axes();
+        renderer.draw_points(&points);
+        renderer.draw_lambda_label(1.042);
+        let png = renderer.encode_png().unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+    }
+}
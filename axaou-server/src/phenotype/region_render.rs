@@ -4,8 +4,10 @@
 //! for the region view. Replaces client-side canvas rendering for large regions.
 
 use crate::api::AppState;
+use crate::clickhouse::QueryExt;
 use crate::clickhouse::xpos::compute_xpos;
 use crate::error::AppError;
+use crate::genomics::Contig;
 use crate::phenotype::loci::{ImageDimensions, LocusPlotSidecar, ThresholdMarker, YAxisConfig};
 use crate::phenotype::manhattan::{HitType, SignificantHit};
 use crate::phenotype::render::{LocusPlotConfig, LocusRenderer, RenderVariant, YScale};
@@ -112,15 +114,11 @@ async fn fetch_region_variants(
     query_mode: Option<&str>,
 ) -> Result<Vec<RegionVariantRow>, AppError> {
     let force_slow = query_mode == Some("slow");
-    let chr_contig = if contig.starts_with("chr") {
-        contig.to_string()
-    } else {
-        format!("chr{}", contig)
-    };
+    let chr_contig = contig.parse::<Contig>()?.chr();
 
     let mut all_variants = if !force_slow {
-        let xstart = compute_xpos(&chr_contig, start as u32);
-        let xstop = compute_xpos(&chr_contig, stop as u32);
+        let xstart = compute_xpos(chr_contig, start as u32);
+        let xstop = compute_xpos(chr_contig, stop as u32);
 
         // Query exome and genome variants concurrently, each with annotation JOIN
         let fetch_seq_type = |seq_type: &'static str, ann_table: &'static str| {
@@ -163,7 +161,7 @@ async fn fetch_region_variants(
                 .bind(seq_type)
                 .bind(xstart)
                 .bind(xstop)
-                .fetch_all::<RegionVariantRow>()
+                .fetch_all_with::<RegionVariantRow>(&state.executor)
         };
 
         let (exome_res, genome_res) = tokio::join!(
@@ -171,11 +169,8 @@ async fn fetch_region_variants(
             fetch_seq_type("genome", "genome_annotations")
         );
 
-        let mut variants = exome_res
-            .map_err(|e| AppError::DataTransformError(format!("Exome query error: {}", e)))?;
-        let genome_variants = genome_res
-            .map_err(|e| AppError::DataTransformError(format!("Genome query error: {}", e)))?;
-        variants.extend(genome_variants);
+        let mut variants = exome_res?;
+        variants.extend(genome_res?);
         variants
     } else {
         Vec::new()
@@ -218,23 +213,20 @@ async fn fetch_region_variants(
                 .collect()
         };
 
-        let ancestry_upper = ancestry.to_uppercase();
-        let exome_path = format!(
-            "gs://aou_results/414k/ht_results/{}/phenotype_{}/exome_variant_results.ht",
-            ancestry_upper, analysis_id
-        );
-        let genome_path = format!(
-            "gs://aou_results/414k/ht_results/{}/phenotype_{}/genome_variant_results.ht",
-            ancestry_upper, analysis_id
-        );
+        let exome_path = state
+            .config
+            .variant_results_uri(ancestry, analysis_id, "exome");
+        let genome_path = state
+            .config
+            .variant_results_uri(ancestry, analysis_id, "genome");
 
         let (exome_res, genome_res) = tokio::join!(
             state
                 .hail_client
-                .query_interval_typed(&exome_path, &chr_contig, start, stop),
+                .query_interval_typed(&exome_path, chr_contig, start, stop),
             state
                 .hail_client
-                .query_interval_typed(&genome_path, &chr_contig, start, stop)
+                .query_interval_typed(&genome_path, chr_contig, start, stop)
         );
 
         match exome_res {
@@ -308,7 +300,6 @@ pub async fn render_region_plot(
         return Ok(Response::builder()
             .status(StatusCode::OK)
             .header(header::CONTENT_TYPE, "image/png")
-            .header(header::CACHE_CONTROL, "public, max-age=300")
             .body(Body::from(cached_bytes))
             .unwrap());
     }
@@ -357,7 +348,7 @@ pub async fn render_region_plot(
         renderer.encode_png()
     })
     .await
-    .map_err(|e| AppError::DataTransformError(format!("Render task failed: {}", e)))??;
+    .map_err(|e| AppError::Internal(format!("Render task failed: {}", e)))??;
 
     // Cache the result
     state
@@ -368,7 +359,6 @@ pub async fn render_region_plot(
     Ok(Response::builder()
         .status(StatusCode::OK)
         .header(header::CONTENT_TYPE, "image/png")
-        .header(header::CACHE_CONTROL, "public, max-age=300")
         .body(Body::from(png_bytes))
         .unwrap())
 }
@@ -400,7 +390,7 @@ pub async fn render_region_overlay(
     if let Some(cached_bytes) = state.api_cache.get(&cache_key).await {
         debug!("Cache hit for region overlay: {}", cache_key);
         let resp: RegionOverlayResponse = serde_json::from_slice(&cached_bytes)
-            .map_err(|e| AppError::DataTransformError(format!("Deserialize error: {}", e)))?;
+            .map_err(|e| AppError::Internal(format!("Deserialize error: {}", e)))?;
         return Ok(Json(resp));
     }
 
@@ -420,11 +410,7 @@ pub async fn render_region_overlay(
     let total_variant_count = variants.len();
 
     // Filter to significant variants for the overlay
-    let chr_contig = if params.contig.starts_with("chr") {
-        params.contig.clone()
-    } else {
-        format!("chr{}", params.contig)
-    };
+    let chr_contig = params.contig.parse::<Contig>()?.chr();
 
     let significant_hits: Vec<SignificantHit> = variants
         .iter()
@@ -438,7 +424,7 @@ pub async fn render_region_overlay(
                 hit_type: HitType::Variant,
                 id: variant_id.clone(),
                 label: variant_id,
-                contig: chr_contig.clone(),
+                contig: chr_contig.to_string(),
                 position: v.position,
                 pvalue: v.pvalue,
                 neg_log10_p: Some(v.neg_log10_p as f64),
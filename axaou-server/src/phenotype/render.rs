@@ -84,6 +84,16 @@ impl YScale {
         }
     }
 
+    /// Scale whose top is `max_neg_log_p` instead of the fixed 300, for plots
+    /// scaled to their data
+    pub fn with_max(height: u32, max_neg_log_p: f64) -> Self {
+        let scale = Self::new(height);
+        Self {
+            max_log_val: max_neg_log_p.max(scale.log_threshold),
+            ..scale
+        }
+    }
+
     pub fn get_y(&self, pvalue: f64, neg_log10_p: Option<f64>) -> f32 {
         let neg_log_p = if let Some(nlp) = neg_log10_p {
             nlp
@@ -354,7 +364,7 @@ impl LocusRenderer {
     pub fn encode_png(&self) -> Result<Vec<u8>, AppError> {
         self.pixmap
             .encode_png()
-            .map_err(|e| AppError::DataTransformError(format!("PNG encoding failed: {}", e)))
+            .map_err(|e| AppError::Internal(format!("PNG encoding failed: {}", e)))
     }
 }
 
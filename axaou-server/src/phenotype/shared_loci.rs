@@ -0,0 +1,247 @@
+//! Shared-signal handler
+//!
+//! Lists, for each locus of a phenotype, the other phenotypes with
+//! significant variants in the same 1Mb bins. Co-occurring signals are
+//! colocalization candidates; no fine-mapping is done here.
+
+use crate::api::AppState;
+use crate::clickhouse::models::{LocusRow, LOCUS_COLUMNS};
+use crate::clickhouse::QueryExt;
+use crate::correlations::metadata_by_analysis;
+use crate::error::{AppError, ErrorResponse};
+use crate::models::AnalysisMetadata;
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+/// Query parameters for the shared-loci endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SharedLociQuery {
+    /// Ancestry group (default: "meta")
+    pub ancestry: Option<String>,
+    /// Only report phenotypes whose best p-value in the locus is at most this
+    pub max_p: Option<f64>,
+    /// Maximum number of phenotypes per locus (default: 50, max: 500)
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize, Row)]
+struct SharedSignalRow {
+    locus_id: String,
+    phenotype: String,
+    min_pvalue: f64,
+    variant_count: u64,
+}
+
+/// Another phenotype with significant variants in a locus
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SharedPhenotype {
+    pub analysis_id: String,
+    /// Phenotype description (display name applied)
+    pub description: String,
+    pub category: String,
+    /// Best p-value of the phenotype within the locus bins
+    pub min_pvalue: f64,
+    /// Significant variants of the phenotype within the locus bins
+    pub variant_count: u64,
+}
+
+/// Locus of the requested phenotype with its co-occurring signals
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SharedLocus {
+    pub locus_id: String,
+    pub contig: String,
+    pub start: i32,
+    pub stop: i32,
+    pub lead_variant: String,
+    pub lead_pvalue: f64,
+    /// Other phenotypes, most significant first
+    pub phenotypes: Vec<SharedPhenotype>,
+}
+
+/// Attach signal rows to their loci; loci keep lead p-value order and each
+/// locus lists at most `limit` phenotypes
+fn build_shared_loci(
+    loci: Vec<LocusRow>,
+    rows: Vec<SharedSignalRow>,
+    metadata: &HashMap<&str, &AnalysisMetadata>,
+    limit: usize,
+) -> Vec<SharedLocus> {
+    let mut by_locus: HashMap<String, Vec<SharedPhenotype>> = HashMap::new();
+    for row in rows {
+        let meta = metadata.get(row.phenotype.as_str());
+        let description = crate::phenotype_display_names::apply_display_name(
+            &row.phenotype,
+            meta.map(|m| m.description.as_str())
+                .unwrap_or(&row.phenotype),
+        );
+        by_locus
+            .entry(row.locus_id)
+            .or_default()
+            .push(SharedPhenotype {
+                description,
+                category: meta.map(|m| m.category.clone()).unwrap_or_default(),
+                analysis_id: row.phenotype,
+                min_pvalue: row.min_pvalue,
+                variant_count: row.variant_count,
+            });
+    }
+
+    loci.into_iter()
+        .map(|locus| {
+            let mut phenotypes = by_locus.remove(&locus.locus_id).unwrap_or_default();
+            phenotypes.sort_by(|a, b| {
+                a.min_pvalue
+                    .total_cmp(&b.min_pvalue)
+                    .then_with(|| a.analysis_id.cmp(&b.analysis_id))
+            });
+            phenotypes.truncate(limit);
+            SharedLocus {
+                locus_id: locus.locus_id,
+                contig: locus.contig,
+                start: locus.start,
+                stop: locus.stop,
+                lead_variant: locus.lead_variant,
+                lead_pvalue: locus.lead_pvalue,
+                phenotypes,
+            }
+        })
+        .collect()
+}
+
+/// GET /api/phenotype/:analysis_id/shared-loci
+///
+/// For each locus of the phenotype (most significant first), the other
+/// phenotypes with significant variants in the 1Mb bins the locus spans,
+/// from `significant_variants` in a single GROUP BY.
+#[utoipa::path(
+    get,
+    path = "/api/phenotype/{analysis_id}/shared-loci",
+    tag = "phenotype",
+    params(("analysis_id" = String, Path, description = "Analysis (phenotype) ID"), SharedLociQuery),
+    responses(
+        (status = 200, description = "Loci with co-occurring signals", body = Vec<SharedLocus>),
+        (status = 500, description = "ClickHouse query failed", body = ErrorResponse)
+    )
+)]
+pub async fn get_shared_loci(
+    State(state): State<Arc<AppState>>,
+    Path(analysis_id): Path<String>,
+    Query(params): Query<SharedLociQuery>,
+) -> Result<Json<Vec<SharedLocus>>, AppError> {
+    let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
+    let limit = params.limit.unwrap_or(50).clamp(1, 500);
+
+    let loci_query = format!(
+        r#"
+        SELECT {}
+        FROM loci
+        WHERE phenotype = ? AND ancestry = ?
+        ORDER BY lead_pvalue ASC, xstart ASC
+        "#,
+        LOCUS_COLUMNS
+    );
+    let loci = state
+        .clickhouse
+        .query(&loci_query)
+        .bind(&analysis_id)
+        .bind(&ancestry)
+        .fetch_all_with::<LocusRow>(&state.executor)
+        .await?;
+    if loci.is_empty() {
+        return Ok(Json(Vec::new()));
+    }
+
+    let shared_query = format!(
+        r#"
+        SELECT
+            lb.locus_id AS locus_id,
+            sv.phenotype AS phenotype,
+            min(sv.pvalue) AS min_pvalue,
+            count() AS variant_count
+        FROM (
+            SELECT phenotype, contig, toUInt32(intDiv(position, 1000000)) AS bin_1mb, pvalue
+            FROM significant_variants
+            WHERE ancestry = ? AND phenotype != ?
+        ) AS sv
+        INNER JOIN (
+            SELECT
+                locus_id,
+                contig,
+                arrayJoin(range(
+                    toUInt32(intDiv(start, 1000000)),
+                    toUInt32(intDiv(stop, 1000000)) + 1
+                )) AS bin_1mb
+            FROM loci
+            WHERE phenotype = ? AND ancestry = ?
+        ) AS lb ON sv.contig = lb.contig AND sv.bin_1mb = lb.bin_1mb
+        GROUP BY locus_id, phenotype
+        {}
+        "#,
+        if params.max_p.is_some() {
+            "HAVING min_pvalue <= ?"
+        } else {
+            ""
+        }
+    );
+    let mut q = state
+        .clickhouse
+        .query(&shared_query)
+        .bind(&ancestry)
+        .bind(&analysis_id)
+        .bind(&analysis_id)
+        .bind(&ancestry);
+    if let Some(max_p) = params.max_p {
+        q = q.bind(max_p);
+    }
+    let rows = q.fetch_all_with::<SharedSignalRow>(&state.executor).await?;
+
+    let metadata = state.metadata.read().await;
+    let by_analysis = metadata_by_analysis(&metadata, &ancestry);
+    Ok(Json(build_shared_loci(loci, rows, &by_analysis, limit)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::factories::locus;
+
+    fn signal(locus_id: &str, phenotype: &str, min_pvalue: f64) -> SharedSignalRow {
+        SharedSignalRow {
+            locus_id: locus_id.to_string(),
+            phenotype: phenotype.to_string(),
+            min_pvalue,
+            variant_count: 3,
+        }
+    }
+
+    #[test]
+    fn test_build_shared_loci() {
+        let loci = vec![
+            locus("l1", "chr1", 1_000_000, 2_500_000, 1e-30),
+            locus("l2", "chr1", 1_000_000, 2_500_000, 1e-9),
+        ];
+        let rows = vec![
+            signal("l1", "bmi", 1e-8),
+            signal("l1", "weight", 1e-12),
+            signal("l1", "hip", 1e-10),
+            signal("l3", "bmi", 1e-9),
+        ];
+        let shared = build_shared_loci(loci, rows, &HashMap::new(), 2);
+        assert_eq!(shared.len(), 2);
+        let ids: Vec<_> = shared[0]
+            .phenotypes
+            .iter()
+            .map(|p| p.analysis_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["weight", "hip"]);
+        assert!(shared[1].phenotypes.is_empty());
+    }
+}
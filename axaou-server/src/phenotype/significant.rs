@@ -1,19 +1,28 @@
-//! Significant variants handler
+//! Significant variants handlers
 //!
-//! Provides endpoint for retrieving variants that pass significance thresholds.
+//! Provides endpoints for retrieving variants that pass significance thresholds
+//! and the annotated top variants ("Top hits") of a phenotype.
 
 use crate::api::AppState;
+use crate::clickhouse::QueryExt;
 use crate::clickhouse::models::LocusVariantExtendedRow;
-use crate::error::AppError;
+use crate::clickhouse::xpos::make_variant_id;
+use crate::error::{AppError, ErrorResponse};
+use crate::genomics::consequence::ConsequenceFilter;
+use crate::response::ResponseFormat;
 use axum::{
     extract::{Path, Query, State},
+    response::Response,
     Json,
 };
-use serde::Deserialize;
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
 
 /// Query parameters for significant variants endpoint
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct SignificantQuery {
     /// Ancestry group filter (default: "meta")
     pub ancestry: Option<String>,
@@ -21,17 +30,30 @@ pub struct SignificantQuery {
     pub sequencing_type: Option<String>,
     /// Maximum number of results (default: 50000)
     pub limit: Option<u64>,
+    /// Response layout: "rows" (default), "columnar", "arrow" or "parquet"
+    pub format: Option<ResponseFormat>,
 }
 
 /// GET /api/phenotype/:analysis_id/significant
 ///
 /// Returns only significant variants across all loci for a phenotype.
-/// Useful for highlighting peaks on Manhattan plots.
+/// Useful for highlighting peaks on Manhattan plots. With `format=columnar`
+/// the variants come back as [`crate::response::VariantColumns`].
+#[utoipa::path(
+    get,
+    path = "/api/phenotype/{analysis_id}/significant",
+    tag = "phenotype",
+    params(("analysis_id" = String, Path, description = "Analysis (phenotype) ID"), SignificantQuery),
+    responses(
+        (status = 200, description = "Significant variants (VariantColumns with format=columnar)", body = Vec<LocusVariantExtendedRow>),
+        (status = 500, description = "ClickHouse query failed", body = ErrorResponse)
+    )
+)]
 pub async fn get_significant_variants(
     State(state): State<Arc<AppState>>,
     Path(analysis_id): Path<String>,
     Query(params): Query<SignificantQuery>,
-) -> Result<Json<Vec<LocusVariantExtendedRow>>, AppError> {
+) -> Result<Response, AppError> {
     let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
     let limit = params.limit.unwrap_or(50000);
 
@@ -53,9 +75,8 @@ pub async fn get_significant_variants(
             .bind(&ancestry)
             .bind(seq_type)
             .bind(limit)
-            .fetch_all::<LocusVariantExtendedRow>()
-            .await
-            .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?
+            .fetch_all_with::<LocusVariantExtendedRow>(&state.executor)
+            .await?
     } else {
         let query = r#"
             SELECT locus_id, xpos, position, pvalue, neg_log10_p, is_significant
@@ -72,10 +93,174 @@ pub async fn get_significant_variants(
             .bind(&analysis_id)
             .bind(&ancestry)
             .bind(limit)
-            .fetch_all::<LocusVariantExtendedRow>()
-            .await
-            .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?
+            .fetch_all_with::<LocusVariantExtendedRow>(&state.executor)
+            .await?
     };
 
-    Ok(Json(rows))
+    params.format.unwrap_or_default().variant_response(rows)
+}
+
+/// Query parameters for the top variants endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TopVariantsQuery {
+    /// Ancestry group filter (default: "meta")
+    pub ancestry: Option<String>,
+    /// Sequencing type: "exome" or "genome" (default: "genome")
+    pub sequencing_type: Option<String>,
+    /// Consequence classes (lof, missense, synonymous, splice, other) and/or VEP terms, comma-separated
+    pub consequence: Option<String>,
+    /// Maximum allele frequency in the association (e.g. 0.01 for rare variants)
+    pub max_af: Option<f64>,
+    /// Maximum number of results (default: 100, max: 1000)
+    pub limit: Option<u64>,
+}
+
+/// Significant variant joined with its annotation
+#[derive(Debug, Clone, Serialize, Deserialize, Row)]
+struct TopVariantRow {
+    contig: String,
+    position: i32,
+    #[serde(rename = "ref")]
+    ref_allele: String,
+    alt: String,
+    pvalue: f64,
+    beta: f64,
+    se: f64,
+    af: f64,
+    gene_id: Option<String>,
+    gene_symbol: Option<String>,
+    consequence: Option<String>,
+    hgvsc: Option<String>,
+    hgvsp: Option<String>,
+}
+
+/// One row of the phenotype page "Top hits" table
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TopVariant {
+    pub variant_id: String,
+    pub contig: String,
+    pub position: i32,
+    #[serde(rename = "ref")]
+    pub ref_allele: String,
+    pub alt: String,
+    pub pvalue: f64,
+    pub beta: f64,
+    pub se: f64,
+    pub af: f64,
+    pub gene_id: Option<String>,
+    pub gene_symbol: Option<String>,
+    pub consequence: Option<String>,
+    pub hgvsc: Option<String>,
+    pub hgvsp: Option<String>,
+}
+
+impl From<TopVariantRow> for TopVariant {
+    fn from(row: TopVariantRow) -> Self {
+        Self {
+            variant_id: make_variant_id(&row.contig, row.position as u32, &row.ref_allele, &row.alt),
+            contig: row.contig,
+            position: row.position,
+            ref_allele: row.ref_allele,
+            alt: row.alt,
+            pvalue: row.pvalue,
+            beta: row.beta,
+            se: row.se,
+            af: row.af,
+            gene_id: row.gene_id,
+            gene_symbol: row.gene_symbol,
+            consequence: row.consequence,
+            hgvsc: row.hgvsc,
+            hgvsp: row.hgvsp,
+        }
+    }
+}
+
+/// GET /api/phenotype/:analysis_id/top-variants
+///
+/// Most significant variants for a phenotype from `significant_variants`,
+/// annotated from the exome/genome annotation table, for the "Top hits"
+/// table. `consequence=` and `max_af=` narrow the list before `limit`.
+#[utoipa::path(
+    get,
+    path = "/api/phenotype/{analysis_id}/top-variants",
+    tag = "phenotype",
+    params(("analysis_id" = String, Path, description = "Analysis (phenotype) ID"), TopVariantsQuery),
+    responses(
+        (status = 200, description = "Top variants, most significant first", body = Vec<TopVariant>),
+        (status = 400, description = "Invalid sequencing type or consequence", body = ErrorResponse)
+    )
+)]
+pub async fn get_top_variants(
+    State(state): State<Arc<AppState>>,
+    Path(analysis_id): Path<String>,
+    Query(params): Query<TopVariantsQuery>,
+) -> Result<Json<Vec<TopVariant>>, AppError> {
+    let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
+    let (sequencing_type, annotation_table) = match params.sequencing_type.as_deref() {
+        None | Some("genome") => ("genome", "genome_annotations"),
+        Some("exome") => ("exome", "exome_annotations"),
+        Some(other) => {
+            return Err(AppError::BadRequest(format!(
+                "Unknown sequencing_type '{}'. Expected exome or genome",
+                other
+            )))
+        }
+    };
+    let limit = params.limit.unwrap_or(100).clamp(1, 1000);
+    let consequence = ConsequenceFilter::parse(params.consequence.as_deref())?;
+
+    let mut filters = String::new();
+    if let Some(consequence) = &consequence {
+        filters.push_str(&format!(" AND {}", consequence.sql("ann.consequence")));
+    }
+    if params.max_af.is_some() {
+        filters.push_str(" AND sv.af <= ?");
+    }
+
+    let query = format!(
+        r#"
+        SELECT sv.contig, sv.position, sv.ref, sv.alt, sv.pvalue, sv.beta, sv.se, sv.af,
+               ann.gene_id, ann.gene_symbol, ann.consequence, ann.hgvsc, ann.hgvsp
+        FROM significant_variants sv
+        LEFT JOIN (
+            SELECT xpos, ref, alt, gene_id, gene_symbol, consequence, hgvsc, hgvsp
+            FROM {annotation_table}
+            WHERE xpos IN (
+                SELECT xpos FROM significant_variants
+                WHERE phenotype = ? AND ancestry = ? AND sequencing_type = ?
+            )
+        ) ann ON sv.xpos = ann.xpos AND sv.ref = ann.ref AND sv.alt = ann.alt
+        WHERE sv.phenotype = ? AND sv.ancestry = ? AND sv.sequencing_type = ?{filters}
+        ORDER BY sv.pvalue ASC
+        LIMIT ?
+        "#,
+        annotation_table = annotation_table,
+        filters = filters
+    );
+
+    let mut q = state
+        .clickhouse
+        .query(&query)
+        // IN subquery
+        .bind(&analysis_id)
+        .bind(&ancestry)
+        .bind(sequencing_type)
+        // outer WHERE
+        .bind(&analysis_id)
+        .bind(&ancestry)
+        .bind(sequencing_type);
+    if let Some(consequence) = &consequence {
+        q = q.bind(consequence.terms());
+    }
+    if let Some(max_af) = params.max_af {
+        q = q.bind(max_af);
+    }
+
+    let rows = q
+        .bind(limit)
+        .fetch_all_with::<TopVariantRow>(&state.executor)
+        .await?;
+
+    Ok(Json(rows.into_iter().map(TopVariant::from).collect()))
 }
@@ -3,6 +3,7 @@
 //! Returns the phenotype_summary derived table for the All Phenotypes directory view.
 
 use crate::api::AppState;
+use crate::clickhouse::QueryExt;
 use crate::clickhouse::models::PhenotypeSummaryRow;
 use crate::error::AppError;
 use crate::response::{LookupResult, QueryTimer};
@@ -34,9 +35,8 @@ pub async fn get_phenotypes_summary(
     let mut rows = state
         .clickhouse
         .query(query)
-        .fetch_all::<PhenotypeSummaryRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with::<PhenotypeSummaryRow>(&state.executor)
+        .await?;
 
     for row in &mut rows {
         row.description = crate::phenotype_display_names::apply_display_name(
@@ -45,9 +45,9 @@ pub async fn get_phenotypes_summary(
         );
     }
 
-    let result = LookupResult::new(rows, timer.elapsed());
+    let result = LookupResult::new(rows, &timer);
     let json_bytes =
-        serde_json::to_vec(&result).map_err(|e| AppError::DataTransformError(e.to_string()))?;
+        serde_json::to_vec(&result).map_err(|e| AppError::Internal(e.to_string()))?;
 
     state
         .api_cache
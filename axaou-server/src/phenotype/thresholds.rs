@@ -0,0 +1,185 @@
+//! Multiple-testing significance thresholds
+//!
+//! Serves the Bonferroni cutoffs of a phenotype from the test counts recorded
+//! at ingest in `analysis_test_counts`, so every client draws the same
+//! significance lines. Result sets without recorded counts fall back to the
+//! conventional 5e-8 (variants) and 2.5e-6 (gene burden).
+
+use crate::api::AppState;
+use crate::clickhouse::QueryExt;
+use crate::error::{AppError, ErrorResponse};
+use crate::phenotype::manhattan::ancestry_param;
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+/// Family-wise error rate the thresholds control by default
+const DEFAULT_ALPHA: f64 = 0.05;
+
+/// Effective variant tests behind the conventional genome-wide 5e-8
+const DEFAULT_VARIANT_TESTS: f64 = 1e6;
+
+/// Effective gene tests behind the conventional burden 2.5e-6
+const DEFAULT_GENE_TESTS: f64 = 2e4;
+
+/// Query parameters for the thresholds endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ThresholdsQuery {
+    /// Ancestry group (default: "meta")
+    pub ancestry: Option<String>,
+    /// Family-wise error rate (default: 0.05)
+    pub alpha: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Row)]
+struct TestCountRow {
+    test_type: String,
+    n_tests: u64,
+    effective_tests: f64,
+}
+
+/// Bonferroni threshold for one result set
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SignificanceThreshold {
+    /// alpha / effective_tests
+    pub pvalue: f64,
+    /// Tests recorded at ingest; null when falling back to the default
+    pub n_tests: Option<u64>,
+    pub effective_tests: f64,
+    /// "ingest" or "default"
+    pub source: String,
+}
+
+impl SignificanceThreshold {
+    fn new(alpha: f64, count: Option<&TestCountRow>, default_tests: f64) -> Self {
+        match count.filter(|c| c.effective_tests >= 1.0) {
+            Some(count) => SignificanceThreshold {
+                pvalue: alpha / count.effective_tests,
+                n_tests: Some(count.n_tests),
+                effective_tests: count.effective_tests,
+                source: "ingest".to_string(),
+            },
+            None => SignificanceThreshold {
+                pvalue: alpha / default_tests,
+                n_tests: None,
+                effective_tests: default_tests,
+                source: "default".to_string(),
+            },
+        }
+    }
+}
+
+/// Significance thresholds of one phenotype
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SignificanceThresholds {
+    pub analysis_id: String,
+    pub ancestry: String,
+    pub alpha: f64,
+    /// Genome variant results
+    pub genome_wide: SignificanceThreshold,
+    /// Exome variant results
+    pub exome_wide: SignificanceThreshold,
+    /// Gene burden (SKAT-O / burden / SKAT) results
+    pub gene_burden: SignificanceThreshold,
+}
+
+impl SignificanceThresholds {
+    fn from_counts(analysis_id: String, ancestry: String, alpha: f64, rows: &[TestCountRow]) -> Self {
+        let count = |test_type: &str| rows.iter().find(|r| r.test_type == test_type);
+        SignificanceThresholds {
+            genome_wide: SignificanceThreshold::new(alpha, count("genome"), DEFAULT_VARIANT_TESTS),
+            exome_wide: SignificanceThreshold::new(alpha, count("exome"), DEFAULT_VARIANT_TESTS),
+            gene_burden: SignificanceThreshold::new(alpha, count("gene"), DEFAULT_GENE_TESTS),
+            analysis_id,
+            ancestry,
+            alpha,
+        }
+    }
+}
+
+/// GET /api/phenotype/:analysis_id/thresholds
+///
+/// Genome-wide, exome-wide and gene-burden Bonferroni thresholds of a
+/// phenotype, from the effective test counts recorded at ingest.
+#[utoipa::path(
+    get,
+    path = "/api/phenotype/{analysis_id}/thresholds",
+    tag = "phenotype",
+    params(("analysis_id" = String, Path, description = "Analysis (phenotype) ID"), ThresholdsQuery),
+    responses(
+        (status = 200, description = "Bonferroni thresholds per result set", body = SignificanceThresholds),
+        (status = 400, description = "Invalid ancestry or alpha", body = ErrorResponse)
+    )
+)]
+pub async fn get_thresholds(
+    State(state): State<Arc<AppState>>,
+    Path(analysis_id): Path<String>,
+    Query(params): Query<ThresholdsQuery>,
+) -> Result<Json<SignificanceThresholds>, AppError> {
+    let ancestry = ancestry_param(params.ancestry.as_deref())?.to_string();
+    let alpha = params.alpha.unwrap_or(DEFAULT_ALPHA);
+    if !(alpha > 0.0 && alpha < 1.0) {
+        return Err(AppError::BadRequest(format!(
+            "alpha must be between 0 and 1, got {}",
+            alpha
+        )));
+    }
+
+    let query = r#"
+        SELECT test_type, n_tests, effective_tests
+        FROM analysis_test_counts FINAL
+        WHERE phenotype = ? AND ancestry = ?
+    "#;
+    let rows = state
+        .clickhouse
+        .query(query)
+        .bind(&analysis_id)
+        .bind(&ancestry)
+        .fetch_all_with::<TestCountRow>(&state.executor)
+        .await?;
+
+    Ok(Json(SignificanceThresholds::from_counts(
+        analysis_id,
+        ancestry,
+        alpha,
+        &rows,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thresholds_from_counts() {
+        let rows = vec![
+            TestCountRow {
+                test_type: "exome".to_string(),
+                n_tests: 400_000,
+                effective_tests: 400_000.0,
+            },
+            TestCountRow {
+                test_type: "gene".to_string(),
+                n_tests: 120_000,
+                effective_tests: 18_000.0,
+            },
+        ];
+        let t = SignificanceThresholds::from_counts("height".into(), "meta".into(), 0.05, &rows);
+
+        assert_eq!(t.genome_wide.source, "default");
+        assert!((t.genome_wide.pvalue - 5e-8).abs() < 1e-20);
+        assert_eq!(t.genome_wide.n_tests, None);
+
+        assert_eq!(t.exome_wide.source, "ingest");
+        assert!((t.exome_wide.pvalue - 1.25e-7).abs() < 1e-20);
+
+        assert_eq!(t.gene_burden.n_tests, Some(120_000));
+        assert!((t.gene_burden.pvalue - 0.05 / 18_000.0).abs() < 1e-20);
+    }
+}
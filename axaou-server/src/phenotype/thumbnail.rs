@@ -0,0 +1,186 @@
+//! Manhattan plot thumbnails for the phenotype gallery
+//!
+//! The gallery shows hundreds of phenotypes at once; downloading each
+//! multi-megabyte Manhattan PNG to draw a tile a few hundred pixels wide is
+//! wasteful. Thumbnails are downscaled on the server from the full-size image
+//! (pre-rendered in GCS or rendered from ClickHouse) and kept in the API cache.
+//!
+//! Requested widths are rounded up to one of [`WIDTHS`], so each plot has a
+//! handful of cached thumbnails. Thumbnails of a render fallback expire with
+//! it (see `manhattan_render::RENDERED_TTL`).
+
+use crate::api::AppState;
+use crate::error::AppError;
+use crate::phenotype::manhattan::{ancestry_param, contig_param, manhattan_png};
+use crate::phenotype::manhattan_render::rendered_cache_key;
+use crate::phenotype::plot_delivery::png_response;
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::Response,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use tiny_skia::{FilterQuality, Pixmap, PixmapPaint, Transform};
+use tracing::debug;
+
+/// Thumbnail width when `width` is not given
+const DEFAULT_WIDTH: u32 = 400;
+/// Accepted `width` range in pixels
+const MIN_WIDTH: u32 = 64;
+const MAX_WIDTH: u32 = 1200;
+/// Widths thumbnails are rendered at; requests are rounded up to the next one
+pub const WIDTHS: &[u32] = &[160, 240, 400, 800, MAX_WIDTH];
+
+/// Query parameters for `/manhattan/thumbnail`
+#[derive(Debug, Deserialize)]
+pub struct ThumbnailQuery {
+    /// Ancestry (default "meta")
+    pub ancestry: Option<String>,
+    /// Plot type (default "genome_manhattan")
+    pub plot_type: Option<String>,
+    /// Chromosome (default "all")
+    pub contig: Option<String>,
+    /// Data version for cache-busting
+    pub v: Option<String>,
+    /// Thumbnail width in pixels; height keeps the aspect ratio
+    pub width: Option<u32>,
+}
+
+/// GET /api/phenotype/:analysis_id/manhattan/thumbnail
+///
+/// Streams a downscaled Manhattan PNG for gallery tiles, `width` (default
+/// 400) rounded up to one of [`WIDTHS`] pixels wide. Images already narrower
+/// are returned as is.
+pub async fn get_manhattan_thumbnail(
+    State(state): State<Arc<AppState>>,
+    Path(analysis_id): Path<String>,
+    Query(params): Query<ThumbnailQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let width = snap_width(params.width.unwrap_or(DEFAULT_WIDTH))?;
+    let ancestry = ancestry_param(params.ancestry.as_deref())?.to_string();
+    let ancestry = ancestry.as_str();
+    let plot_type = params.plot_type.as_deref().unwrap_or("genome_manhattan");
+    let contig = contig_param(params.contig.as_deref())?;
+    let data_version = params.v.as_deref().unwrap_or("");
+
+    let cache_key = format!(
+        "{}-{}-{}-{}-{}-thumbnail-{}",
+        analysis_id, ancestry, plot_type, contig, data_version, width
+    );
+    if let Some(cached_bytes) = state.api_cache.get(&cache_key).await {
+        debug!("Cache hit for Manhattan thumbnail: {}", cache_key);
+        return Ok(png_response(cached_bytes, &headers));
+    }
+
+    let rendered_key = rendered_cache_key(&cache_key);
+    if let Some(cached_bytes) = state.api_cache.get(&rendered_key).await {
+        return Ok(png_response(cached_bytes, &headers));
+    }
+
+    let bytes = state
+        .inflight
+        .run(&cache_key, || async {
+            let (full, rendered) =
+                manhattan_png(&state, &analysis_id, ancestry, plot_type, contig, data_version).await?;
+            let bytes = tokio::task::spawn_blocking(move || thumbnail_png(&full, width)).await??;
+            let key = if rendered { rendered_key.clone() } else { cache_key.clone() };
+            state.api_cache.insert(key, bytes.clone()).await;
+            debug!("Cached Manhattan thumbnail: {}", cache_key);
+            Ok::<_, AppError>(bytes)
+        })
+        .await?;
+
+    Ok(png_response(bytes, &headers))
+}
+
+/// Round a requested width up to the nearest of [`WIDTHS`]
+fn snap_width(width: u32) -> Result<u32, AppError> {
+    if !(MIN_WIDTH..=MAX_WIDTH).contains(&width) {
+        return Err(AppError::BadRequest(format!(
+            "width must be between {} and {}, got {}",
+            MIN_WIDTH, MAX_WIDTH, width
+        )));
+    }
+    Ok(WIDTHS.iter().copied().find(|&w| w >= width).unwrap_or(MAX_WIDTH))
+}
+
+/// Downscale a PNG to `width` pixels wide, keeping the aspect ratio
+pub fn thumbnail_png(png: &[u8], width: u32) -> Result<Vec<u8>, AppError> {
+    let mut image = Pixmap::decode_png(png)
+        .map_err(|e| AppError::Internal(format!("Failed to decode plot PNG: {}", e)))?;
+    if image.width() <= width {
+        return Ok(png.to_vec());
+    }
+
+    // Halve first: one large filtered step skips most source pixels, and
+    // single-pixel points of the plot would drop out
+    while image.width() >= width * 2 {
+        image = scale(&image, image.width() / 2)?;
+    }
+    if image.width() != width {
+        image = scale(&image, width)?;
+    }
+    image
+        .encode_png()
+        .map_err(|e| AppError::Internal(format!("Failed to encode thumbnail: {}", e)))
+}
+
+fn scale(image: &Pixmap, width: u32) -> Result<Pixmap, AppError> {
+    let sx = width as f32 / image.width() as f32;
+    let height = ((image.height() as f32 * sx).round() as u32).max(1);
+    let sy = height as f32 / image.height() as f32;
+    let mut scaled = Pixmap::new(width, height)
+        .ok_or_else(|| AppError::Internal(format!("Invalid thumbnail size {}x{}", width, height)))?;
+    let paint = PixmapPaint {
+        quality: FilterQuality::Bilinear,
+        ..PixmapPaint::default()
+    };
+    scaled.draw_pixmap(0, 0, image.as_ref(), &paint, Transform::from_scale(sx, sy), None);
+    Ok(scaled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiny_skia::Color;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut pixmap = Pixmap::new(width, height).unwrap();
+        pixmap.fill(Color::from_rgba8(30, 60, 200, 255));
+        pixmap.encode_png().unwrap()
+    }
+
+    #[test]
+    fn test_thumbnail_keeps_aspect_ratio() {
+        let thumb = Pixmap::decode_png(&thumbnail_png(&png(2000, 600), 400).unwrap()).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (400, 120));
+        // Filtering a solid image keeps its colour
+        let pixel = thumb.pixel(200, 60).unwrap();
+        assert_eq!((pixel.red(), pixel.green(), pixel.blue()), (30, 60, 200));
+
+        let thumb = Pixmap::decode_png(&thumbnail_png(&png(1000, 300), 300).unwrap()).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (300, 90));
+    }
+
+    #[test]
+    fn test_snap_width() {
+        assert_eq!(snap_width(64).unwrap(), 160);
+        assert_eq!(snap_width(400).unwrap(), 400);
+        assert_eq!(snap_width(401).unwrap(), 800);
+        assert_eq!(snap_width(1200).unwrap(), 1200);
+        assert!(snap_width(63).is_err());
+        assert!(snap_width(1201).is_err());
+        let distinct: std::collections::HashSet<u32> =
+            (MIN_WIDTH..=MAX_WIDTH).map(|w| snap_width(w).unwrap()).collect();
+        assert_eq!(distinct.len(), WIDTHS.len());
+    }
+
+    #[test]
+    fn test_small_images_are_not_upscaled() {
+        let small = png(200, 60);
+        assert_eq!(thumbnail_png(&small, 400).unwrap(), small);
+        assert!(thumbnail_png(b"not a png", 400).is_err());
+    }
+}
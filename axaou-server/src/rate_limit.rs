@@ -0,0 +1,314 @@
+//! Token-bucket rate limiting middleware
+//!
+//! Each client gets a bucket keyed by API key (when the `x-api-key` header
+//! carries a configured key) or by client IP. Requests that find an empty
+//! bucket are rejected with 429 and a `Retry-After` header, so one client's
+//! heavy PheWAS scans cannot starve everyone else.
+//!
+//! The client IP is the TCP peer address. `X-Forwarded-For` is client
+//! controlled, so it is only read when `--trusted-proxy-hops` says how many
+//! proxies (e.g. 1 behind the Cloud Run frontend) append to it, and then the
+//! address is taken that many hops from the right.
+//!
+//! Whole-table tar downloads (`/downloads/:analysis_id/:asset_id/tar`) proxy
+//! gigabytes from GCS per request, so a second limiter with a much smaller
+//! per-client quota (`--download-rate-per-hour`) guards them and stays on
+//! when the general limit is disabled.
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Header used to present an API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Buckets idle for longer than this are dropped by the cleanup task
+const IDLE_BUCKET_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Token bucket quota: sustained rate plus burst capacity
+#[derive(Debug, Clone, Copy)]
+pub struct Quota {
+    /// Tokens added per second
+    pub per_second: f64,
+    /// Maximum tokens (burst size)
+    pub burst: f64,
+}
+
+/// Rate limiter settings (from CLI flags)
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    /// Quota for anonymous clients, keyed by IP
+    pub ip_quota: Quota,
+    /// Quota for clients presenting a known API key
+    pub key_quota: Quota,
+    /// API keys granted `key_quota`; unknown keys fall back to the IP quota
+    pub api_keys: HashSet<String>,
+    /// Proxies in front of the server that append to `X-Forwarded-For`
+    /// (0 ignores the header)
+    pub trusted_proxy_hops: usize,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl Bucket {
+    fn full(quota: Quota, now: Instant) -> Self {
+        Self {
+            tokens: quota.burst,
+            last_refill: now,
+        }
+    }
+
+    /// Take one token, or return how long until one is available
+    fn try_take(&mut self, quota: Quota, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * quota.per_second).min(quota.burst);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            let wait = (1.0 - self.tokens) / quota.per_second;
+            Err(Duration::from_secs_f64(wait))
+        }
+    }
+}
+
+/// Shared limiter state
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Arc<Self> {
+        Arc::new(Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Check a request for `client_key` against `quota`
+    fn check(&self, client_key: &str, quota: Quota, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        buckets
+            .entry(client_key.to_string())
+            .or_insert_with(|| Bucket::full(quota, now))
+            .try_take(quota, now)
+    }
+
+    /// Resolve the bucket key and quota for a request
+    fn classify(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> (String, Quota) {
+        if let Some(key) = headers
+            .get(API_KEY_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .filter(|k| self.config.api_keys.contains(*k))
+        {
+            return (format!("key:{}", key), self.config.key_quota);
+        }
+        let ip = client_ip(headers, peer, self.config.trusted_proxy_hops);
+        (format!("ip:{}", ip), self.config.ip_quota)
+    }
+
+    /// Take a token for the request's client, or build the 429 response
+    fn admit(&self, request: &Request<Body>) -> Result<(), Response> {
+        let peer = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|c| c.0);
+        let (client_key, quota) = self.classify(request.headers(), peer);
+
+        self.check(&client_key, quota, Instant::now()).map_err(|wait| {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            tracing::debug!("Rate limited {} (retry after {}s)", client_key, retry_after);
+            let mut response = (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(serde_json::json!({ "code": "rate_limited", "error": "Rate limit exceeded" })),
+            )
+                .into_response();
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(retry_after),
+            );
+            response
+        })
+    }
+
+    /// Periodically drop idle buckets so the map does not grow without bound
+    pub fn spawn_cleanup(self: &Arc<Self>) {
+        let limiter = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                let now = Instant::now();
+                let mut buckets = limiter.buckets.lock().unwrap_or_else(|e| e.into_inner());
+                buckets.retain(|_, b| now.saturating_duration_since(b.last_refill) < IDLE_BUCKET_TTL);
+            }
+        });
+    }
+}
+
+/// Client IP: the address `trusted_proxy_hops` entries from the right of
+/// `X-Forwarded-For` (each trusted proxy appends the address it saw), else
+/// the peer address
+fn client_ip(headers: &HeaderMap, peer: Option<SocketAddr>, trusted_proxy_hops: usize) -> String {
+    let forwarded = headers
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .filter(|_| trusted_proxy_hops > 0)
+        .and_then(|v| {
+            let hops: Vec<&str> = v.split(',').map(str::trim).collect();
+            // Fewer entries than trusted proxies: every entry was appended by one
+            let ip = hops.iter().rev().nth(trusted_proxy_hops - 1).or(hops.first())?;
+            Some(ip.to_string())
+        })
+        .filter(|ip| !ip.is_empty());
+    forwarded
+        .or_else(|| peer.map(|p| p.ip().to_string()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Middleware enforcing the rate limit
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    match limiter.admit(&request) {
+        Ok(()) => next.run(request).await,
+        Err(response) => response,
+    }
+}
+
+/// Middleware enforcing a download quota on whole-table tar downloads only
+pub async fn rate_limit_downloads(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    if !is_tar_download(request.uri().path()) {
+        return next.run(request).await;
+    }
+    match limiter.admit(&request) {
+        Ok(()) => next.run(request).await,
+        Err(response) => response,
+    }
+}
+
+/// `/api[/v/:dataset]/downloads/:analysis_id/:asset_id/tar`
+fn is_tar_download(path: &str) -> bool {
+    path.contains("/downloads/") && path.ends_with("/tar")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(api_keys: &[&str]) -> Arc<RateLimiter> {
+        limiter_behind_proxies(api_keys, 0)
+    }
+
+    fn limiter_behind_proxies(api_keys: &[&str], trusted_proxy_hops: usize) -> Arc<RateLimiter> {
+        RateLimiter::new(RateLimitConfig {
+            ip_quota: Quota {
+                per_second: 1.0,
+                burst: 2.0,
+            },
+            key_quota: Quota {
+                per_second: 10.0,
+                burst: 20.0,
+            },
+            api_keys: api_keys.iter().map(|k| k.to_string()).collect(),
+            trusted_proxy_hops,
+        })
+    }
+
+    #[test]
+    fn test_burst_then_reject_then_refill() {
+        let limiter = limiter(&[]);
+        let quota = limiter.config.ip_quota;
+        let t0 = Instant::now();
+        assert!(limiter.check("ip:1.2.3.4", quota, t0).is_ok());
+        assert!(limiter.check("ip:1.2.3.4", quota, t0).is_ok());
+        let wait = limiter.check("ip:1.2.3.4", quota, t0).unwrap_err();
+        assert!(wait <= Duration::from_secs(1));
+        // Other clients are unaffected
+        assert!(limiter.check("ip:5.6.7.8", quota, t0).is_ok());
+        // One token refills after a second
+        assert!(limiter
+            .check("ip:1.2.3.4", quota, t0 + Duration::from_secs(1))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_classify_known_api_key() {
+        let limiter = limiter(&["secret"]);
+        let mut headers = HeaderMap::new();
+        headers.insert(API_KEY_HEADER, HeaderValue::from_static("secret"));
+        let (key, quota) = limiter.classify(&headers, None);
+        assert_eq!(key, "key:secret");
+        assert_eq!(quota.burst, 20.0);
+    }
+
+    #[test]
+    fn test_classify_unknown_key_uses_ip() {
+        let limiter = limiter(&["secret"]);
+        let peer: SocketAddr = "10.0.0.2:5000".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(API_KEY_HEADER, HeaderValue::from_static("guess"));
+        headers.insert("x-forwarded-for", HeaderValue::from_static("9.9.9.9"));
+        let (key, quota) = limiter.classify(&headers, Some(peer));
+        // No trusted proxies: the forwarded header is ignored
+        assert_eq!(key, "ip:10.0.0.2");
+        assert_eq!(quota.burst, 2.0);
+    }
+
+    #[test]
+    fn test_client_ip_counts_trusted_hops_from_the_right() {
+        let peer: SocketAddr = "10.0.0.2:5000".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        // Client-supplied 1.1.1.1, real client 9.9.9.9, then an internal proxy
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("1.1.1.1, 9.9.9.9, 10.0.0.1"),
+        );
+        assert_eq!(client_ip(&headers, Some(peer), 0), "10.0.0.2");
+        assert_eq!(client_ip(&headers, Some(peer), 1), "10.0.0.1");
+        assert_eq!(client_ip(&headers, Some(peer), 2), "9.9.9.9");
+        // Rotating the spoofed prefix does not change the bucket
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("2.2.2.2, 9.9.9.9, 10.0.0.1"),
+        );
+        assert_eq!(client_ip(&headers, Some(peer), 2), "9.9.9.9");
+
+        let limiter = limiter_behind_proxies(&[], 1);
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_static("8.8.8.8"));
+        assert_eq!(limiter.classify(&headers, Some(peer)).0, "ip:8.8.8.8");
+        // No header: the peer address
+        assert_eq!(limiter.classify(&HeaderMap::new(), Some(peer)).0, "ip:10.0.0.2");
+    }
+
+    #[test]
+    fn test_is_tar_download() {
+        assert!(is_tar_download("/api/downloads/height/a1/tar"));
+        assert!(is_tar_download("/api/v/414k/downloads/height/a1/tar"));
+        assert!(!is_tar_download("/api/downloads/height"));
+        assert!(!is_tar_download("/api/phenotype/height/loci"));
+    }
+}
@@ -3,7 +3,21 @@
 //! These types provide consistent response envelopes that match
 //! the frontend's expected `LookupResult<T>` interface.
 
-use serde::Serialize;
+use crate::clickhouse::executor::DbUsage;
+use crate::clickhouse::models::{LocusVariantExtendedRow, LocusVariantLdRow, LocusVariantRow};
+use crate::error::AppError;
+use crate::export::TableEncoding;
+use crate::models::{GeneAssociationApi, VariantAnnotationApi, VariantAssociationApi};
+use axum::{
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::{IntoParams, ToSchema};
 
 /// Standard response envelope that wraps list data.
 ///
@@ -14,9 +28,23 @@ use serde::Serialize;
 ///   data: T[]
 ///   storage_source: string
 ///   time: number
+///   db_time: number
+///   transform_time: number
+///   serialize_time: number
+///   db_queries: number
 /// }
 /// ```
-#[derive(Debug, Serialize)]
+///
+/// `serialize_time` is not a field: it is measured while `data` is written
+/// and emitted after it.
+///
+/// Concrete instantiations are registered as OpenAPI schema aliases.
+#[derive(Debug, ToSchema)]
+#[aliases(
+    GeneAssociationLookup = LookupResult<GeneAssociationApi>,
+    VariantAssociationLookup = LookupResult<VariantAssociationApi>,
+    VariantAnnotationLookup = LookupResult<VariantAnnotationApi>
+)]
 pub struct LookupResult<T> {
     /// The data payload
     pub data: Vec<T>,
@@ -26,6 +54,14 @@ pub struct LookupResult<T> {
     pub storage_source: String,
     /// Query execution time in seconds
     pub time: f64,
+    /// Seconds of `time` spent waiting on ClickHouse
+    pub db_time: f64,
+    /// Seconds of `time` spent outside ClickHouse (`time - db_time`)
+    pub transform_time: f64,
+    /// Number of ClickHouse queries issued
+    pub db_queries: u32,
+    /// Scale of `beta`, for responses about a single analysis
+    pub effect: Option<EffectMetadata>,
 }
 
 impl<T> LookupResult<T> {
@@ -33,43 +69,491 @@ impl<T> LookupResult<T> {
     ///
     /// # Arguments
     /// * `data` - The vector of results
-    /// * `time` - Query execution time in seconds
-    pub fn new(data: Vec<T>, time: f64) -> Self {
-        Self {
-            count: data.len(),
-            data,
-            storage_source: "clickhouse".to_string(),
-            time,
-        }
+    /// * `timing` - A [`QueryTimer`] for the full breakdown, or the total
+    ///   execution time in seconds
+    pub fn new(data: Vec<T>, timing: impl Into<Timing>) -> Self {
+        Self::with_source(data, timing, "clickhouse")
     }
 
     /// Create a LookupResult from an iterator with execution time.
-    pub fn from_iter<I: IntoIterator<Item = T>>(iter: I, time: f64) -> Self {
+    pub fn from_iter<I: IntoIterator<Item = T>>(iter: I, timing: impl Into<Timing>) -> Self {
         let data: Vec<T> = iter.into_iter().collect();
-        Self::new(data, time)
+        Self::new(data, timing)
     }
 
     /// Create a LookupResult with a specific storage source
-    pub fn with_source(data: Vec<T>, time: f64, source: &str) -> Self {
+    pub fn with_source(data: Vec<T>, timing: impl Into<Timing>, source: &str) -> Self {
+        let timing = timing.into();
         Self {
             count: data.len(),
             data,
             storage_source: source.to_string(),
+            time: timing.time,
+            db_time: timing.db_time,
+            transform_time: (timing.time - timing.db_time).max(0.0),
+            db_queries: timing.db_queries,
+            effect: None,
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for LookupResult<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut out = serializer.serialize_struct("LookupResult", 9)?;
+        out.serialize_field("count", &self.count)?;
+        out.serialize_field("storage_source", &self.storage_source)?;
+        out.serialize_field("time", &self.time)?;
+        out.serialize_field("db_time", &self.db_time)?;
+        out.serialize_field("transform_time", &self.transform_time)?;
+        out.serialize_field("db_queries", &self.db_queries)?;
+        match &self.effect {
+            Some(effect) => out.serialize_field("effect", effect)?,
+            None => out.skip_field("effect")?,
+        }
+        let start = std::time::Instant::now();
+        out.serialize_field("data", &self.data)?;
+        out.serialize_field("serialize_time", &start.elapsed().as_secs_f64())?;
+        out.end()
+    }
+}
+
+impl<T: EffectSize> LookupResult<T> {
+    /// Describe the scale of the betas, converting them to odds ratios when
+    /// `effect` says so
+    pub fn with_effect(mut self, effect: Option<EffectMetadata>) -> Self {
+        if let Some(effect) = &effect {
+            for row in &mut self.data {
+                let beta = row.beta_mut();
+                *beta = effect.unit.convert(*beta);
+            }
+        }
+        self.effect = effect;
+        self
+    }
+}
+
+/// Effect scale requested with `effect=`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EffectScale {
+    /// Betas as estimated (default)
+    #[default]
+    Beta,
+    /// Odds ratios for binary traits; other traits keep their betas
+    Or,
+}
+
+/// Unit of the `beta` values in a response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EffectUnit {
+    /// Log odds ratio (binary traits)
+    LogOdds,
+    /// Odds ratio, exp(beta); `se` stays on the log-odds scale
+    OddsRatio,
+    /// Units of the (possibly transformed) trait (continuous traits)
+    Raw,
+}
+
+impl EffectUnit {
+    /// Convert a beta as estimated to this unit
+    pub fn convert(self, beta: f64) -> f64 {
+        match self {
+            EffectUnit::OddsRatio => beta.exp(),
+            EffectUnit::LogOdds | EffectUnit::Raw => beta,
+        }
+    }
+}
+
+/// Effect-size metadata of a single-analysis response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EffectMetadata {
+    /// `trait_type` of the analysis ("continuous", "binary", ...)
+    pub trait_type: String,
+    pub unit: EffectUnit,
+}
+
+impl EffectMetadata {
+    /// Metadata for an analysis of `trait_type`, with betas on `scale`
+    pub fn new(trait_type: &str, scale: EffectScale) -> Self {
+        let binary = matches!(
+            trait_type.to_ascii_lowercase().as_str(),
+            "binary" | "categorical"
+        );
+        let unit = match (binary, scale) {
+            (true, EffectScale::Or) => EffectUnit::OddsRatio,
+            (true, EffectScale::Beta) => EffectUnit::LogOdds,
+            (false, _) => EffectUnit::Raw,
+        };
+        Self {
+            trait_type: trait_type.to_string(),
+            unit,
+        }
+    }
+}
+
+/// Rows carrying a `beta` that `LookupResult::with_effect` can rescale
+pub trait EffectSize {
+    fn beta_mut(&mut self) -> &mut f64;
+}
+
+impl EffectSize for VariantAssociationApi {
+    fn beta_mut(&mut self) -> &mut f64 {
+        &mut self.beta
+    }
+}
+
+/// Response of `/count` endpoints: how many rows the matching list endpoint
+/// would return with the same filters (ignoring `limit`/`offset`)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CountResult {
+    /// Number of matching rows
+    pub count: u64,
+    /// Query execution time in seconds
+    pub time: f64,
+}
+
+/// Query parameter for endpoints that support sparse fieldsets
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FieldsQuery {
+    /// Comma-separated fields to return, e.g. "gene_id,symbol,start,stop".
+    /// Dotted paths select inside nested objects ("transcripts.transcript_id").
+    pub fields: Option<String>,
+}
+
+/// Sparse fieldset requested with `fields=`
+///
+/// Projects serialized responses down to the listed keys. A key keeps its
+/// whole subtree; a dotted path keeps only the named descendants, applied to
+/// every element when the value is an array. Unknown keys select nothing.
+/// An empty selection keeps everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldSelection {
+    paths: Vec<Vec<String>>,
+}
+
+impl FieldSelection {
+    /// Parse a comma-separated `fields` parameter
+    pub fn parse(fields: Option<&str>) -> Self {
+        let mut selection = Self::default();
+        for field in fields.unwrap_or("").split(',') {
+            selection.push(field);
+        }
+        selection
+    }
+
+    /// Add one (possibly dotted) path to the selection
+    pub fn push(&mut self, field: &str) {
+        let path: Vec<String> = field
+            .trim()
+            .split('.')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .map(str::to_string)
+            .collect();
+        if !path.is_empty() {
+            self.paths.push(path);
+        }
+    }
+
+    /// True when no fields were requested, i.e. the full response is returned
+    pub fn is_all(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Serialize `value` and keep only the selected fields
+    pub fn project<T: Serialize>(&self, value: &T) -> Result<Value, AppError> {
+        let value = serde_json::to_value(value)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize response: {}", e)))?;
+        if self.is_all() {
+            return Ok(value);
+        }
+        let paths: Vec<&[String]> = self.paths.iter().map(Vec::as_slice).collect();
+        Ok(project_value(value, &paths))
+    }
+
+    /// Project each item of a list response
+    pub fn project_all<T: Serialize>(&self, items: &[T]) -> Result<Vec<Value>, AppError> {
+        items.iter().map(|item| self.project(item)).collect()
+    }
+}
+
+fn project_value(value: Value, paths: &[&[String]]) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| project_value(item, paths))
+                .collect(),
+        ),
+        Value::Object(map) => {
+            let mut projected = Map::new();
+            for (key, child) in map {
+                let rest: Vec<&[String]> = paths
+                    .iter()
+                    .filter(|path| path[0] == key)
+                    .map(|path| &path[1..])
+                    .collect();
+                if rest.is_empty() {
+                    continue;
+                }
+                if rest.iter().any(|path| path.is_empty()) {
+                    projected.insert(key, child);
+                } else {
+                    projected.insert(key, project_value(child, &rest));
+                }
+            }
+            Value::Object(projected)
+        }
+        scalar => scalar,
+    }
+}
+
+/// Response layout requested with `format=`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseFormat {
+    /// Array of objects (default)
+    #[default]
+    Rows,
+    /// Struct of arrays, see [`VariantColumns`]
+    Columnar,
+    /// Arrow IPC stream of the bare rows
+    Arrow,
+    /// Parquet file of the bare rows
+    Parquet,
+    /// Sites-only VCF (variant annotation intervals only)
+    Vcf,
+}
+
+/// Query parameter for endpoints with binary table output
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FormatQuery {
+    /// "rows" (JSON, default), "arrow" or "parquet". Without it, an `Accept`
+    /// of application/vnd.apache.arrow.stream or application/vnd.apache.parquet
+    /// selects the binary format.
+    pub format: Option<ResponseFormat>,
+}
+
+impl ResponseFormat {
+    /// The `format=` parameter if given, else the format the `Accept` header asks for
+    pub fn negotiate(format: Option<ResponseFormat>, headers: &HeaderMap) -> Self {
+        format.unwrap_or_else(|| match TableEncoding::from_accept(headers) {
+            Some(TableEncoding::ArrowStream) => ResponseFormat::Arrow,
+            Some(TableEncoding::Parquet) => ResponseFormat::Parquet,
+            None => ResponseFormat::Rows,
+        })
+    }
+
+    fn table_encoding(self) -> Option<TableEncoding> {
+        match self {
+            ResponseFormat::Arrow => Some(TableEncoding::ArrowStream),
+            ResponseFormat::Parquet => Some(TableEncoding::Parquet),
+            ResponseFormat::Rows | ResponseFormat::Columnar | ResponseFormat::Vcf => None,
+        }
+    }
+
+    /// Error for a format the endpoint cannot produce
+    pub fn unsupported(self) -> AppError {
+        let name = match self {
+            ResponseFormat::Rows => "rows",
+            ResponseFormat::Columnar => "columnar",
+            ResponseFormat::Arrow => "arrow",
+            ResponseFormat::Parquet => "parquet",
+            ResponseFormat::Vcf => "vcf",
+        };
+        AppError::BadRequest(format!("format={} is not supported by this endpoint", name))
+    }
+
+    /// Respond with variant rows in this layout
+    pub fn variant_response<T>(self, rows: Vec<T>) -> Result<Response, AppError>
+    where
+        T: Serialize,
+        VariantColumns: FromIterator<T>,
+    {
+        match self {
+            ResponseFormat::Rows => Ok(Json(rows).into_response()),
+            ResponseFormat::Columnar => {
+                Ok(Json(rows.into_iter().collect::<VariantColumns>()).into_response())
+            }
+            ResponseFormat::Arrow | ResponseFormat::Parquet => {
+                self.table_encoding().unwrap().respond(&rows)
+            }
+            ResponseFormat::Vcf => Err(self.unsupported()),
+        }
+    }
+
+    /// Respond with a plain JSON array, or the rows as a binary table
+    pub fn rows_response<T: Serialize>(self, rows: Vec<T>) -> Result<Response, AppError> {
+        match self.table_encoding() {
+            Some(encoding) => encoding.respond(&rows),
+            None if self == ResponseFormat::Rows => Ok(Json(rows).into_response()),
+            None => Err(self.unsupported()),
+        }
+    }
+
+    /// Respond with a `LookupResult` envelope, or its rows as a binary table
+    pub fn lookup_response<T: Serialize>(
+        self,
+        result: LookupResult<T>,
+    ) -> Result<Response, AppError> {
+        match self.table_encoding() {
+            Some(encoding) => encoding.respond(&result.data),
+            None if self == ResponseFormat::Rows => Ok(Json(result).into_response()),
+            None => Err(self.unsupported()),
+        }
+    }
+}
+
+/// Middleware for routes whose body format follows `Accept` (see
+/// [`ResponseFormat::negotiate`]): adds `Vary: Accept` so shared caches keep
+/// the JSON and binary representations of a URL apart.
+pub async fn vary_accept(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    response
+        .headers_mut()
+        .append(header::VARY, HeaderValue::from_static("accept"));
+    response
+}
+
+/// `flags` bit set for significant variants
+pub const FLAG_SIGNIFICANT: u8 = 1;
+/// `flags` bit set for variants in LD with the locus lead variant
+pub const FLAG_LEAD_LD: u8 = 2;
+
+/// Struct-of-arrays form of variant lists for plots (`format=columnar`)
+///
+/// Every array has `count` elements; element `i` of each describes the same
+/// variant. Several times smaller than the row form for 50k-point plots.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, ToSchema)]
+pub struct VariantColumns {
+    pub count: usize,
+    pub xpos: Vec<i64>,
+    pub position: Vec<i32>,
+    pub neg_log10_p: Vec<f32>,
+    /// Bit set per variant: 1 = significant, 2 = in LD with the lead variant
+    pub flags: Vec<u8>,
+    /// Locus of each variant, for lists spanning several loci
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locus_id: Option<Vec<String>>,
+    /// Fine-mapping PIP of each variant, when the locus was fine-mapped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pip: Option<Vec<Option<f64>>>,
+}
+
+impl VariantColumns {
+    pub fn push(&mut self, xpos: i64, position: i32, neg_log10_p: f32, flags: u8) {
+        self.count += 1;
+        self.xpos.push(xpos);
+        self.position.push(position);
+        self.neg_log10_p.push(neg_log10_p);
+        self.flags.push(flags);
+    }
+}
+
+fn significant_flag(is_significant: bool) -> u8 {
+    if is_significant {
+        FLAG_SIGNIFICANT
+    } else {
+        0
+    }
+}
+
+impl FromIterator<LocusVariantRow> for VariantColumns {
+    fn from_iter<I: IntoIterator<Item = LocusVariantRow>>(rows: I) -> Self {
+        let mut columns = Self::default();
+        for row in rows {
+            let flags = significant_flag(row.is_significant);
+            columns.push(row.xpos, row.position, row.neg_log10_p, flags);
+        }
+        columns
+    }
+}
+
+impl FromIterator<LocusVariantLdRow> for VariantColumns {
+    fn from_iter<I: IntoIterator<Item = LocusVariantLdRow>>(rows: I) -> Self {
+        let mut columns = Self::default();
+        let mut pips = Vec::new();
+        for row in rows {
+            let mut flags = significant_flag(row.is_significant);
+            if row.lead_r2.is_some() {
+                flags |= FLAG_LEAD_LD;
+            }
+            columns.push(row.xpos, row.position, row.neg_log10_p, flags);
+            pips.push(row.pip);
+        }
+        if pips.iter().any(Option::is_some) {
+            columns.pip = Some(pips);
+        }
+        columns
+    }
+}
+
+impl FromIterator<LocusVariantExtendedRow> for VariantColumns {
+    fn from_iter<I: IntoIterator<Item = LocusVariantExtendedRow>>(rows: I) -> Self {
+        let mut columns = Self::default();
+        let mut locus_ids = Vec::new();
+        for row in rows {
+            let flags = significant_flag(row.is_significant);
+            columns.push(row.xpos, row.position, row.neg_log10_p, flags);
+            locus_ids.push(row.locus_id);
+        }
+        columns.locus_id = Some(locus_ids);
+        columns
+    }
+}
+
+/// Execution time of a handler, split into ClickHouse and other work
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Timing {
+    /// Total seconds
+    pub time: f64,
+    /// Seconds waiting on ClickHouse
+    pub db_time: f64,
+    /// ClickHouse queries issued
+    pub db_queries: u32,
+}
+
+/// A total without a breakdown
+impl From<f64> for Timing {
+    fn from(time: f64) -> Self {
+        Self {
             time,
+            ..Default::default()
         }
     }
 }
 
+impl From<&QueryTimer> for Timing {
+    fn from(timer: &QueryTimer) -> Self {
+        timer.timing()
+    }
+}
+
 /// Helper trait for measuring query execution time
+///
+/// Within a request the timer also reads the ClickHouse time and query count
+/// tracked by [`track_db_usage`](crate::clickhouse::executor::track_db_usage)
+/// since it was started.
 pub struct QueryTimer {
     start: std::time::Instant,
+    db: Option<(std::sync::Arc<DbUsage>, f64, u32)>,
 }
 
 impl QueryTimer {
     /// Start a new timer
     pub fn start() -> Self {
+        let db = DbUsage::current().map(|usage| {
+            let (db_time, queries) = (usage.db_time(), usage.queries());
+            (usage, db_time, queries)
+        });
         Self {
             start: std::time::Instant::now(),
+            db,
         }
     }
 
@@ -77,6 +561,19 @@ impl QueryTimer {
     pub fn elapsed(&self) -> f64 {
         self.start.elapsed().as_secs_f64()
     }
+
+    /// Elapsed time with the ClickHouse share
+    pub fn timing(&self) -> Timing {
+        let time = self.elapsed();
+        match &self.db {
+            Some((usage, db_time, queries)) => Timing {
+                time,
+                db_time: (usage.db_time() - db_time).min(time),
+                db_queries: usage.queries() - queries,
+            },
+            None => Timing::from(time),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -92,6 +589,153 @@ mod tests {
         assert_eq!(result.data, vec![1, 2, 3]);
         assert_eq!(result.storage_source, "clickhouse");
         assert!((result.time - 0.123).abs() < 0.001);
+        assert_eq!(result.db_queries, 0);
+        assert!((result.transform_time - 0.123).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_lookup_result_timing_breakdown() {
+        let timing = Timing {
+            time: 0.5,
+            db_time: 0.375,
+            db_queries: 3,
+        };
+        let json = serde_json::to_value(LookupResult::new(vec!["a"], timing)).unwrap();
+        assert_eq!(json["db_time"], 0.375);
+        assert_eq!(json["transform_time"], 0.125);
+        assert_eq!(json["db_queries"], 3);
+        assert_eq!(json["data"], serde_json::json!(["a"]));
+        assert!(json["serialize_time"].as_f64().unwrap() >= 0.0);
+
+        // Outside a request the timer has no ClickHouse share
+        let timing = QueryTimer::start().timing();
+        assert_eq!((timing.db_time, timing.db_queries), (0.0, 0));
+    }
+
+    #[test]
+    fn test_effect_metadata() {
+        assert_eq!(
+            EffectMetadata::new("binary", EffectScale::Beta).unit,
+            EffectUnit::LogOdds
+        );
+        assert_eq!(
+            EffectMetadata::new("categorical", EffectScale::Or).unit,
+            EffectUnit::OddsRatio
+        );
+        assert_eq!(
+            EffectMetadata::new("continuous", EffectScale::Or).unit,
+            EffectUnit::Raw
+        );
+        assert_eq!(EffectUnit::OddsRatio.convert(0.0), 1.0);
+        assert_eq!(EffectUnit::Raw.convert(0.5), 0.5);
+
+        let plain = serde_json::to_value(LookupResult::new(vec![1], 0.0)).unwrap();
+        assert!(plain.get("effect").is_none());
+    }
+
+    #[test]
+    fn test_field_selection_projects_nested_paths() {
+        let gene = serde_json::json!({
+            "gene_id": "ENSG00000139618",
+            "symbol": "BRCA2",
+            "start": 32315508,
+            "exons": [{"start": 1, "stop": 2}],
+            "transcripts": [
+                {"transcript_id": "ENST00000380152", "exons": [{"start": 1}]},
+                {"transcript_id": "ENST00000544455", "exons": []}
+            ]
+        });
+
+        let selection = FieldSelection::parse(Some("symbol, start,transcripts.transcript_id,nope"));
+        assert_eq!(
+            selection.project(&gene).unwrap(),
+            serde_json::json!({
+                "symbol": "BRCA2",
+                "start": 32315508,
+                "transcripts": [
+                    {"transcript_id": "ENST00000380152"},
+                    {"transcript_id": "ENST00000544455"}
+                ]
+            })
+        );
+
+        let all = FieldSelection::parse(Some(" , "));
+        assert!(all.is_all());
+        assert_eq!(all.project(&gene).unwrap(), gene);
+    }
+
+    #[test]
+    fn test_variant_columns() {
+        let rows = vec![
+            LocusVariantLdRow {
+                xpos: 1_000_000_100,
+                ref_allele: "A".to_string(),
+                alt: "G".to_string(),
+                position: 100,
+                pvalue: 1e-10,
+                neg_log10_p: 10.0,
+                is_significant: true,
+                lead_r2: Some(0.9),
+                pip: None,
+                credible_set: None,
+            },
+            LocusVariantLdRow {
+                xpos: 1_000_000_200,
+                ref_allele: "A".to_string(),
+                alt: "G".to_string(),
+                position: 200,
+                pvalue: 0.5,
+                neg_log10_p: 0.3,
+                is_significant: false,
+                lead_r2: None,
+                pip: None,
+                credible_set: None,
+            },
+        ];
+        let columns: VariantColumns = rows.into_iter().collect();
+        assert_eq!(columns.count, 2);
+        assert_eq!(columns.position, vec![100, 200]);
+        assert_eq!(columns.flags, vec![FLAG_SIGNIFICANT | FLAG_LEAD_LD, 0]);
+        assert!(columns.locus_id.is_none());
+        assert!(columns.pip.is_none());
+        assert!(!serde_json::to_string(&columns)
+            .unwrap()
+            .contains("locus_id"));
+    }
+
+    #[test]
+    fn test_negotiate_format() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            ResponseFormat::negotiate(None, &headers),
+            ResponseFormat::Rows
+        );
+        headers.insert(
+            axum::http::header::ACCEPT,
+            crate::export::PARQUET_MIME.parse().unwrap(),
+        );
+        assert_eq!(
+            ResponseFormat::negotiate(None, &headers),
+            ResponseFormat::Parquet
+        );
+        assert_eq!(
+            ResponseFormat::negotiate(Some(ResponseFormat::Rows), &headers),
+            ResponseFormat::Rows
+        );
+        assert!(ResponseFormat::Columnar.rows_response(vec![1, 2]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_vary_accept() {
+        use tower::ServiceExt;
+        let app = axum::Router::new()
+            .route("/", axum::routing::get(|| async { "rows" }))
+            .layer(axum::middleware::from_fn(vary_accept));
+        let response = app
+            .oneshot(axum::http::Request::get("/").body(axum::body::Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::VARY], "accept");
     }
 
     #[test]
@@ -0,0 +1,97 @@
+//! Coalescing of identical concurrent requests
+//!
+//! When many users open the same phenotype at once, every request misses the
+//! API cache together and would run the same expensive queries side by side.
+//! [`SingleFlight::run`] lets the first caller for a key do the work while
+//! later callers for the same key wait for its result. Keys are the handler's
+//! API cache key, so they already encode the endpoint and its parameters.
+//!
+//! A flight's result is handed only to callers that joined while it was
+//! running; callers arriving afterwards are expected to find it in the API
+//! cache. If the leading call fails or its client disconnects, one of the
+//! waiters takes over and runs the work itself.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+/// In-flight calls keyed by request fingerprint
+pub struct SingleFlight<T> {
+    calls: Mutex<HashMap<String, Arc<OnceCell<T>>>>,
+}
+
+impl<T> Default for SingleFlight<T> {
+    fn default() -> Self {
+        Self {
+            calls: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T: Clone> SingleFlight<T> {
+    /// Run `work` for `key`, or wait for the call already running for it
+    pub async fn run<F, Fut, E>(&self, key: &str, work: F) -> Result<T, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let cell = Arc::clone(self.calls.lock().unwrap().entry(key.to_string()).or_default());
+        let result = cell.get_or_try_init(work).await.cloned();
+
+        // Landed: the next caller starts a new flight
+        let mut calls = self.calls.lock().unwrap();
+        if calls.get(key).is_some_and(|current| Arc::ptr_eq(current, &cell)) {
+            calls.remove(key);
+        }
+        result
+    }
+
+    /// Keys with a call in flight
+    pub fn in_flight(&self) -> usize {
+        self.calls.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_concurrent_calls_share_one_run() {
+        let flights: Arc<SingleFlight<u64>> = Arc::default();
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let calls = (0..50).map(|_| {
+            let flights = Arc::clone(&flights);
+            let runs = Arc::clone(&runs);
+            tokio::spawn(async move {
+                flights
+                    .run("height-meta-overlay", || async {
+                        runs.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        Ok::<_, String>(42)
+                    })
+                    .await
+            })
+        });
+        let results = futures::future::join_all(calls).await;
+
+        assert!(results.into_iter().all(|r| r.unwrap() == Ok(42)));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(flights.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_call_is_not_shared() {
+        let flights = SingleFlight::<u64>::default();
+        let failed = flights.run("k", || async { Err::<u64, _>("boom") }).await;
+        assert_eq!(failed, Err("boom"));
+
+        let retried = flights.run("k", || async { Ok::<_, &str>(7) }).await;
+        assert_eq!(retried, Ok(7));
+        assert_eq!(flights.in_flight(), 0);
+    }
+}
@@ -0,0 +1,11 @@
+-- Snapshot of the per-phenotype result tables found by `discover
+-- --to-clickhouse`, read at startup instead of scanning GCS.
+CREATE TABLE IF NOT EXISTS analysis_assets (
+    analysis_id String,
+    ancestry_group LowCardinality(String),
+    asset_type LowCardinality(String),
+    sequencing_type LowCardinality(Nullable(String)),
+    uri String,
+    discovered_at DateTime DEFAULT now()
+) ENGINE = MergeTree()
+ORDER BY (analysis_id, ancestry_group, asset_type)
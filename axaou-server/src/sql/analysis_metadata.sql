@@ -30,6 +30,11 @@ CREATE TABLE IF NOT EXISTS analysis_metadata (
     keep_pheno_skat      UInt8 DEFAULT 1,
     keep_pheno_skato     UInt8 DEFAULT 1,
 
+    -- SNP heritability, when estimated for the analysis
+    heritability         Nullable(Float64),            -- ldsc_h2, else saige_h2 from source
+    heritability_se      Nullable(Float64),            -- ldsc_h2_se (LDSC only)
+    heritability_method  Nullable(String),             -- 'ldsc' or 'saige'
+
     -- Additional fields from source (for reference/debugging)
     disease_category     Nullable(String),
     lambda_gc_exome_raw  Nullable(Float64),
@@ -0,0 +1,21 @@
+-- DDL for analysis_test_counts table
+-- Number of association tests behind each phenotype/ancestry result set, for
+-- the Bonferroni thresholds served by /api/phenotype/:analysis_id/thresholds
+--
+-- Populated per phenotype by `ingest variant-results` ("genome" / "exome")
+-- and `ingest gene-associations` ("gene"); a re-ingest replaces the row
+
+CREATE TABLE IF NOT EXISTS analysis_test_counts (
+    phenotype            String,
+    ancestry             LowCardinality(String),
+    -- "genome", "exome" or "gene"
+    test_type            LowCardinality(String),
+    -- Variants or genes with a finite p-value
+    n_tests              UInt64,
+    -- Independent tests the threshold corrects for: variant counts capped
+    -- for LD, distinct genes for burden masks
+    effective_tests      Float64,
+    ingested_at          DateTime DEFAULT now()
+)
+ENGINE = ReplacingMergeTree(ingested_at)
+ORDER BY (phenotype, ancestry, test_type);
@@ -0,0 +1,30 @@
+-- DDL for conditional_variants table
+-- Stepwise conditional analysis results inside each locus of `loci`. Round 1
+-- is the unconditioned scan; round n is conditioned on the index variants of
+-- rounds 1..n-1, so the best variant of each round is one independent signal.
+--
+-- Populated per phenotype by `ingest variant-results` when the phenotype has
+-- conditional result tables; the loader replaces whole (ancestry, sequencing_type)
+-- partitions
+
+CREATE TABLE IF NOT EXISTS conditional_variants (
+    phenotype            String,
+    ancestry             LowCardinality(String),
+    sequencing_type      LowCardinality(String),
+    locus_id             String,
+    round                UInt8,
+    -- Variant IDs (chr-pos-ref-alt) conditioned on in this round
+    conditioned_on       Array(String),
+    xpos                 Int64,
+    position             Int32,
+    ref                  String,
+    alt                  String,
+    pvalue               Float64,
+    neg_log10_p          Float32,
+    beta                 Nullable(Float64),
+    se                   Nullable(Float64)
+)
+ENGINE = MergeTree()
+PARTITION BY (ancestry, sequencing_type)
+ORDER BY (phenotype, ancestry, sequencing_type, locus_id, round, xpos)
+SETTINGS index_granularity = 8192;
@@ -0,0 +1,54 @@
+-- Transform SQL for conditional_variants
+-- Assigns each staged conditional result ({staging}) to the locus containing
+-- it, as for loci_variants; rows outside every locus are dropped
+--
+-- Bind order: phenotype, ancestry, sequencing_type, phenotype, ancestry (for
+-- the loci lookup)
+-- Source fields are the SAIGE variant results schema plus `round` and
+-- `conditioned_on` (array of chr:pos:ref:alt strings)
+
+INSERT INTO conditional_variants
+SELECT
+    ? AS phenotype,
+    ? AS ancestry,
+    ? AS sequencing_type,
+    l.locus_id,
+    v.round,
+    v.conditioned_on,
+    v.xpos,
+    v.position,
+    v.ref,
+    v.alt,
+    v.pvalue,
+    toFloat32(if(v.pvalue > 0, -log10(v.pvalue), 350)) AS neg_log10_p,
+    v.beta,
+    v.se
+FROM (
+    SELECT
+        1 AS join_key,
+        multiIf(
+            locus.contig = 'chrX', 23,
+            locus.contig = 'chrY', 24,
+            locus.contig = 'chrM', 25,
+            toUInt8OrZero(substring(locus.contig, 4))
+        ) * 1000000000 + locus.position AS xpos,
+        locus.position AS position,
+        alleles[1] AS ref,
+        alleles[2] AS alt,
+        toUInt8(round) AS round,
+        arrayMap(v -> replaceAll(v, ':', '-'), conditioned_on) AS conditioned_on,
+        Pvalue AS pvalue,
+        BETA AS beta,
+        SE AS se
+    FROM {staging}
+    WHERE isNotNull(Pvalue)
+) AS v
+-- Range join: the locus with the greatest xstart <= xpos, kept if it also
+-- covers xpos
+ASOF INNER JOIN (
+    SELECT 1 AS join_key, locus_id, xstart, xstop
+    FROM loci
+    WHERE phenotype = ? AND ancestry = ?
+) AS l
+ON v.join_key = l.join_key AND v.xpos >= l.xstart
+WHERE v.xpos <= l.xstop
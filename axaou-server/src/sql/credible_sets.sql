@@ -0,0 +1,30 @@
+-- DDL for credible_sets table
+-- Fine-mapping (SuSiE or FINEMAP) results inside each locus of `loci`: the
+-- posterior inclusion probability (PIP) of every fine-mapped variant and the
+-- credible set it belongs to, if any
+--
+-- Populated per phenotype by `ingest variant-results` when the phenotype has
+-- credible set tables; the loader replaces whole (ancestry, sequencing_type)
+-- partitions
+
+CREATE TABLE IF NOT EXISTS credible_sets (
+    phenotype            String,
+    ancestry             LowCardinality(String),
+    sequencing_type      LowCardinality(String),
+    locus_id             String,
+    -- "susie" or "finemap"
+    method               LowCardinality(String),
+    xpos                 Int64,
+    position             Int32,
+    ref                  String,
+    alt                  String,
+    pip                  Float64,
+    -- Credible set index within the locus; null outside every set
+    cs_id                Nullable(Int32),
+    -- Coverage target of the set (e.g. 0.95)
+    cs_coverage          Nullable(Float32)
+)
+ENGINE = MergeTree()
+PARTITION BY (ancestry, sequencing_type)
+ORDER BY (phenotype, ancestry, sequencing_type, locus_id, xpos)
+SETTINGS index_granularity = 8192;
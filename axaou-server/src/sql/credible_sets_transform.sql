@@ -0,0 +1,51 @@
+-- Transform SQL for credible_sets
+-- Assigns each staged fine-mapped variant ({staging}) to the locus containing
+-- it, as for loci_variants; variants outside every locus are dropped
+--
+-- Bind order: phenotype, ancestry, sequencing_type, phenotype, ancestry (for
+-- the loci lookup)
+-- Source fields: locus, alleles, method, pip, cs_id (missing outside every
+-- credible set) and cs_coverage
+
+INSERT INTO credible_sets
+SELECT
+    ? AS phenotype,
+    ? AS ancestry,
+    ? AS sequencing_type,
+    l.locus_id,
+    v.method,
+    v.xpos,
+    v.position,
+    v.ref,
+    v.alt,
+    v.pip,
+    v.cs_id,
+    v.cs_coverage
+FROM (
+    SELECT
+        1 AS join_key,
+        multiIf(
+            locus.contig = 'chrX', 23,
+            locus.contig = 'chrY', 24,
+            locus.contig = 'chrM', 25,
+            toUInt8OrZero(substring(locus.contig, 4))
+        ) * 1000000000 + locus.position AS xpos,
+        locus.position AS position,
+        alleles[1] AS ref,
+        alleles[2] AS alt,
+        lower(method) AS method,
+        pip,
+        toNullable(toInt32(cs_id)) AS cs_id,
+        toNullable(toFloat32(cs_coverage)) AS cs_coverage
+    FROM {staging}
+    WHERE isNotNull(pip)
+) AS v
+-- Range join: the locus with the greatest xstart <= xpos, kept if it also
+-- covers xpos
+ASOF INNER JOIN (
+    SELECT 1 AS join_key, locus_id, xstart, xstop
+    FROM loci
+    WHERE phenotype = ? AND ancestry = ?
+) AS l
+ON v.join_key = l.join_key AND v.xpos >= l.xstart
+WHERE v.xpos <= l.xstop
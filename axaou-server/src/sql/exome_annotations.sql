@@ -12,12 +12,35 @@ CREATE TABLE IF NOT EXISTS exome_annotations (
     ref                  String,
     alt                  String,
 
-    -- Population frequencies (ALL only)
+    -- dbSNP IDs
+    rsids                Array(String),
+
+    -- Population frequencies (ALL)
     ac                   Nullable(UInt32),
     af                   Nullable(Float64),
     an                   Nullable(UInt32),
     hom                  Nullable(UInt32),
 
+    -- Per-ancestry frequencies
+    ac_afr               Nullable(UInt32),
+    af_afr               Nullable(Float64),
+    an_afr               Nullable(UInt32),
+    ac_amr               Nullable(UInt32),
+    af_amr               Nullable(Float64),
+    an_amr               Nullable(UInt32),
+    ac_eas               Nullable(UInt32),
+    af_eas               Nullable(Float64),
+    an_eas               Nullable(UInt32),
+    ac_eur               Nullable(UInt32),
+    af_eur               Nullable(Float64),
+    an_eur               Nullable(UInt32),
+    ac_mid               Nullable(UInt32),
+    af_mid               Nullable(Float64),
+    an_mid               Nullable(UInt32),
+    ac_sas               Nullable(UInt32),
+    af_sas               Nullable(Float64),
+    an_sas               Nullable(UInt32),
+
     -- Functional annotations (from canonical VEP transcript)
     gene_id              Nullable(String),
     gene_symbol          Nullable(String),
@@ -28,8 +51,17 @@ CREATE TABLE IF NOT EXISTS exome_annotations (
     polyphen2            Nullable(String),
     lof                  Nullable(String),
 
+    -- In-silico predictors
+    cadd_phred           Nullable(Float32),
+    revel_max            Nullable(Float32),
+    spliceai_ds_max      Nullable(Float32),
+    alphamissense_score  Nullable(Float32),
+    alphamissense_class  Nullable(String),
+
     -- Filters
-    filters              Array(String)
+    filters              Array(String),
+
+    INDEX idx_rsids (rsids) TYPE bloom_filter(0.01) GRANULARITY 1
 )
 ENGINE = MergeTree()
 PARTITION BY substring(contig, 4, 2)
@@ -41,11 +41,32 @@ SELECT
     alleles[1] AS ref,
     alleles[2] AS alt,
 
+    -- Convert Set to Array for rsIDs
+    arrayMap(x -> x, rsid) AS rsids,
+
     -- Frequency from nested struct (index 2 = alt allele, 1-indexed in ClickHouse)
     freq.`ALL`.AC[2] AS ac,
     freq.`ALL`.AF[2] AS af,
     freq.`ALL`.AN AS an,
     freq.`ALL`.homozygote_count[2] AS hom,
+    freq.`AFR`.AC[2] AS ac_afr,
+    freq.`AFR`.AF[2] AS af_afr,
+    freq.`AFR`.AN AS an_afr,
+    freq.`AMR`.AC[2] AS ac_amr,
+    freq.`AMR`.AF[2] AS af_amr,
+    freq.`AMR`.AN AS an_amr,
+    freq.`EAS`.AC[2] AS ac_eas,
+    freq.`EAS`.AF[2] AS af_eas,
+    freq.`EAS`.AN AS an_eas,
+    freq.`EUR`.AC[2] AS ac_eur,
+    freq.`EUR`.AF[2] AS af_eur,
+    freq.`EUR`.AN AS an_eur,
+    freq.`MID`.AC[2] AS ac_mid,
+    freq.`MID`.AF[2] AS af_mid,
+    freq.`MID`.AN AS an_mid,
+    freq.`SAS`.AC[2] AS ac_sas,
+    freq.`SAS`.AF[2] AS af_sas,
+    freq.`SAS`.AN AS an_sas,
 
     -- VEP transcript extraction: pick the best transcript for annotation fields.
     -- Priority: 1) canonical transcript matching most_severe_consequence
@@ -113,6 +134,28 @@ SELECT
         )
     ).lof AS lof,
 
+    -- In-silico predictors: variant-level maxima, AlphaMissense from the same
+    -- transcript as the other VEP fields
+    in_silico_predictors.cadd.phred AS cadd_phred,
+    in_silico_predictors.revel_max AS revel_max,
+    in_silico_predictors.spliceai_ds_max AS spliceai_ds_max,
+    arrayFirst(x -> x.canonical = 1 AND has(x.consequence_terms, vep.most_severe_consequence),
+        arrayConcat(
+            arrayFilter(x -> x.canonical = 1 AND has(x.consequence_terms, vep.most_severe_consequence), vep.transcript_consequences),
+            arrayFilter(x -> x.canonical = 1, vep.transcript_consequences),
+            arrayFilter(x -> has(x.consequence_terms, vep.most_severe_consequence), vep.transcript_consequences),
+            vep.transcript_consequences
+        )
+    ).am_pathogenicity AS alphamissense_score,
+    arrayFirst(x -> x.canonical = 1 AND has(x.consequence_terms, vep.most_severe_consequence),
+        arrayConcat(
+            arrayFilter(x -> x.canonical = 1 AND has(x.consequence_terms, vep.most_severe_consequence), vep.transcript_consequences),
+            arrayFilter(x -> x.canonical = 1, vep.transcript_consequences),
+            arrayFilter(x -> has(x.consequence_terms, vep.most_severe_consequence), vep.transcript_consequences),
+            vep.transcript_consequences
+        )
+    ).am_class AS alphamissense_class,
+
     -- Convert Set to Array for filters
     arrayMap(x -> x, filters) AS filters
 FROM staging_exome_raw
@@ -0,0 +1,22 @@
+-- Gene-level burden/SKAT-O results per phenotype and ancestry.
+-- Partitioned by ancestry: `ingest gene-associations` rebuilds each partition
+-- it touches and swaps it in with REPLACE PARTITION (see partition_load.rs).
+CREATE TABLE IF NOT EXISTS gene_associations (
+    gene_id String,
+    gene_symbol String,
+    annotation LowCardinality(String),
+    max_maf Float64,
+    phenotype String,
+    ancestry LowCardinality(String),
+    pvalue Nullable(Float64),
+    pvalue_burden Nullable(Float64),
+    pvalue_skat Nullable(Float64),
+    beta_burden Nullable(Float64),
+    mac Nullable(Int64),
+    contig LowCardinality(String),
+    gene_start_position Int32,
+    xpos Int64,
+    INDEX idx_gene_id gene_id TYPE bloom_filter(0.01) GRANULARITY 4
+) ENGINE = MergeTree
+PARTITION BY ancestry
+ORDER BY (phenotype, ancestry, annotation, gene_id, max_maf)
@@ -0,0 +1,31 @@
+-- Transform SQL for gene_associations
+-- Maps one phenotype/ancestry's staged gene_results.ht ({staging}) onto the
+-- gene_associations schema. NaN statistics become NULL.
+--
+-- Bind order: phenotype, ancestry
+-- Source fields follow the SAIGE-GENE results schema
+-- (gene_id, gene_symbol, annotation, max_MAF, Pvalue, Pvalue_Burden,
+-- Pvalue_SKAT, BETA_Burden, MAC, CHR, POS)
+
+INSERT INTO gene_associations
+SELECT
+    gene_id,
+    gene_symbol,
+    annotation,
+    max_MAF AS max_maf,
+    ? AS phenotype,
+    ? AS ancestry,
+    if(isFinite(Pvalue), Pvalue, NULL) AS pvalue,
+    if(isFinite(Pvalue_Burden), Pvalue_Burden, NULL) AS pvalue_burden,
+    if(isFinite(Pvalue_SKAT), Pvalue_SKAT, NULL) AS pvalue_skat,
+    if(isFinite(BETA_Burden), BETA_Burden, NULL) AS beta_burden,
+    MAC AS mac,
+    if(startsWith(ifNull(CHR, ''), 'chr'), CHR, concat('chr', ifNull(CHR, ''))) AS contig,
+    ifNull(POS, 0) AS gene_start_position,
+    multiIf(
+        contig = 'chrX', 23,
+        contig = 'chrY', 24,
+        contig = 'chrM', 25,
+        toUInt8OrZero(substring(contig, 4))
+    ) * 1000000000 + gene_start_position AS xpos
+FROM {staging}
@@ -78,11 +78,15 @@ CREATE TABLE IF NOT EXISTS gene_models (
 
     -- Transcripts: JSON (nested-of-nested too complex for Nested type)
     transcripts_json     String,
+    -- Transcript IDs of transcripts_json, for transcript lookups
+    transcript_ids       Array(String) MATERIALIZED
+        arrayMap(t -> JSONExtractString(t, 'transcript_id'), JSONExtractArrayRaw(transcripts_json)),
 
     -- Secondary indexes
     INDEX idx_symbol (symbol_upper_case) TYPE bloom_filter GRANULARITY 1,
     INDEX idx_xstart (xstart) TYPE minmax GRANULARITY 1,
-    INDEX idx_xstop (xstop) TYPE minmax GRANULARITY 1
+    INDEX idx_xstop (xstop) TYPE minmax GRANULARITY 1,
+    INDEX idx_transcript_ids (transcript_ids) TYPE bloom_filter(0.01) GRANULARITY 1
 )
 ENGINE = MergeTree()
 ORDER BY (gene_id)
@@ -0,0 +1,16 @@
+-- Transform SQL for analysis_test_counts (gene results)
+-- Counts the mask tests of one phenotype's staged gene_results.ht
+-- ({staging}). Masks of a gene are correlated, so the effective count is the
+-- number of distinct genes; Cauchy rows (max_MAF = -1) are not counted.
+--
+-- Bind order: phenotype, ancestry
+
+INSERT INTO analysis_test_counts (phenotype, ancestry, test_type, n_tests, effective_tests)
+SELECT
+    ? AS phenotype,
+    ? AS ancestry,
+    'gene' AS test_type,
+    count() AS n_tests,
+    toFloat64(uniqExact(gene_id)) AS effective_tests
+FROM {staging}
+WHERE max_MAF >= 0 AND isFinite(Pvalue)
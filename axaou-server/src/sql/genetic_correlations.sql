@@ -0,0 +1,18 @@
+-- DDL for genetic_correlations table
+-- Pairwise LDSC genetic correlations between phenotypes
+--
+-- Each pair is stored in both directions so lookups by phenotype1 read one
+-- range of the sort key.
+
+CREATE TABLE IF NOT EXISTS genetic_correlations (
+    phenotype1           String,
+    phenotype2           String,
+    ancestry             LowCardinality(String),
+    rg                   Float64,
+    rg_se                Nullable(Float64),
+    z                    Nullable(Float64),
+    pvalue               Nullable(Float64)
+)
+ENGINE = MergeTree()
+ORDER BY (ancestry, phenotype1, phenotype2)
+SETTINGS index_granularity = 8192;
@@ -0,0 +1,31 @@
+-- Transform SQL for genetic_correlations
+-- Transforms staging_genetic_correlations_raw -> genetic_correlations
+--
+-- Source rows are one per unordered pair (phenoname1, phenoname2, ancestry,
+-- rg, rg_se, z, p); each is written in both directions. Pairs where LDSC did
+-- not converge (non-finite rg) are dropped.
+
+INSERT INTO genetic_correlations
+SELECT
+    phenoname1 AS phenotype1,
+    phenoname2 AS phenotype2,
+    lower(ancestry) AS ancestry,
+    rg,
+    rg_se,
+    z,
+    p AS pvalue
+FROM staging_genetic_correlations_raw
+WHERE isFinite(rg) AND phenoname1 != phenoname2
+
+UNION ALL
+
+SELECT
+    phenoname2 AS phenotype1,
+    phenoname1 AS phenotype2,
+    lower(ancestry) AS ancestry,
+    rg,
+    rg_se,
+    z,
+    p AS pvalue
+FROM staging_genetic_correlations_raw
+WHERE isFinite(rg) AND phenoname1 != phenoname2;
@@ -0,0 +1,18 @@
+-- Provenance record of every ingest run: which source populated which table,
+-- when, by whom and with which build.
+CREATE TABLE IF NOT EXISTS ingest_runs (
+    run_id String,
+    table_name LowCardinality(String),
+    source_uri String,
+    init_strategy LowCardinality(String),
+    status LowCardinality(String),
+    row_count UInt64,
+    duration_secs Float64,
+    hail_decoder_version String,
+    git_sha String,
+    operator String,
+    error_message Nullable(String),
+    started_at DateTime,
+    finished_at DateTime DEFAULT now()
+) ENGINE = MergeTree()
+ORDER BY (table_name, started_at)
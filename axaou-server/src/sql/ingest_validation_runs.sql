@@ -0,0 +1,9 @@
+-- Row counts recorded by `ingest validate`, used to flag loads whose size
+-- changed unexpectedly relative to the previous validated load.
+CREATE TABLE IF NOT EXISTS ingest_validation_runs (
+    table_name String,
+    row_count UInt64,
+    passed UInt8,
+    validated_at DateTime DEFAULT now()
+) ENGINE = MergeTree()
+ORDER BY (table_name, validated_at)
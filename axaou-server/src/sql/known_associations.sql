@@ -0,0 +1,28 @@
+-- Previously reported associations from external catalogs (GWAS Catalog),
+-- loaded by `ingest known-hits` and used to mark known loci on the
+-- Manhattan view. Rows are replaced per `source` on each load.
+CREATE TABLE IF NOT EXISTS known_associations (
+    source LowCardinality(String),
+    study_accession String,
+    pubmed_id String,
+    reported_trait String,
+    mapped_traits Array(String),
+    -- Ontology term IDs of mapped_traits, e.g. EFO_0004340
+    efo_ids Array(String),
+    rsid String,
+    contig LowCardinality(String),
+    position UInt32,
+    xpos Int64,
+    risk_allele String,
+    pvalue Float64,
+    reported_genes Array(String)
+) ENGINE = MergeTree
+ORDER BY (xpos, source);
+
+-- Which ontology terms correspond to each AxAoU phenotype, loaded from the
+-- `--trait-map` TSV of `ingest known-hits`
+CREATE TABLE IF NOT EXISTS known_association_traits (
+    phenotype String,
+    efo_id String
+) ENGINE = MergeTree
+ORDER BY (phenotype, efo_id);
@@ -0,0 +1,20 @@
+-- DDL for ld_pairs table
+-- Precomputed pairwise LD (r²) between variants, per ancestry
+--
+-- Each pair is stored in both directions so lookups by the first variant
+-- read one range of the sort key. Pairs below the export threshold are not
+-- present and should be treated as r² ~ 0.
+
+CREATE TABLE IF NOT EXISTS ld_pairs (
+    ancestry             LowCardinality(String),
+    xpos1                Int64,
+    ref1                 String,
+    alt1                 String,
+    xpos2                Int64,
+    ref2                 String,
+    alt2                 String,
+    r2                   Float32
+)
+ENGINE = MergeTree()
+ORDER BY (ancestry, xpos1, ref1, alt1, xpos2)
+SETTINGS index_granularity = 8192;
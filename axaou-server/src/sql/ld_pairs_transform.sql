@@ -0,0 +1,37 @@
+-- Transform SQL for ld_pairs
+-- Transforms staging_ld_pairs_raw -> ld_pairs
+--
+-- Source rows are one per unordered pair (ancestry, locus1, alleles1, locus2,
+-- alleles2, r2); each is written in both directions. xpos is computed as in
+-- the annotation transforms (chromosome index * 1B + position).
+
+INSERT INTO ld_pairs
+SELECT
+    lower(ancestry) AS ancestry,
+    (multiIf(locus1.contig = 'chrX', 23, locus1.contig = 'chrY', 24, locus1.contig = 'chrM', 25,
+        toUInt8OrZero(replaceOne(locus1.contig, 'chr', ''))) * 1000000000 + locus1.position) AS xpos1,
+    alleles1[1] AS ref1,
+    alleles1[2] AS alt1,
+    (multiIf(locus2.contig = 'chrX', 23, locus2.contig = 'chrY', 24, locus2.contig = 'chrM', 25,
+        toUInt8OrZero(replaceOne(locus2.contig, 'chr', ''))) * 1000000000 + locus2.position) AS xpos2,
+    alleles2[1] AS ref2,
+    alleles2[2] AS alt2,
+    r2
+FROM staging_ld_pairs_raw
+WHERE isFinite(r2)
+
+UNION ALL
+
+SELECT
+    lower(ancestry) AS ancestry,
+    (multiIf(locus2.contig = 'chrX', 23, locus2.contig = 'chrY', 24, locus2.contig = 'chrM', 25,
+        toUInt8OrZero(replaceOne(locus2.contig, 'chr', ''))) * 1000000000 + locus2.position) AS xpos1,
+    alleles2[1] AS ref1,
+    alleles2[2] AS alt1,
+    (multiIf(locus1.contig = 'chrX', 23, locus1.contig = 'chrY', 24, locus1.contig = 'chrM', 25,
+        toUInt8OrZero(replaceOne(locus1.contig, 'chr', ''))) * 1000000000 + locus1.position) AS xpos2,
+    alleles1[1] AS ref2,
+    alleles1[2] AS alt2,
+    r2
+FROM staging_ld_pairs_raw
+WHERE isFinite(r2);
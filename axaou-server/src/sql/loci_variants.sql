@@ -0,0 +1,29 @@
+-- DDL for loci_variants table
+-- Every variant inside a locus from the `loci` table, used for locus plots,
+-- region renders and peak-only Manhattan plots
+--
+-- Populated per phenotype by `ingest variant-results`, which replaces
+-- whole (ancestry, sequencing_type) partitions
+
+CREATE TABLE IF NOT EXISTS loci_variants (
+    phenotype            String,
+    ancestry             LowCardinality(String),
+    sequencing_type      LowCardinality(String),
+    locus_id             String,
+    contig               LowCardinality(String),
+    xpos                 Int64,
+    position             Int32,
+    ref                  String,
+    alt                  String,
+    pvalue               Float64,
+    neg_log10_p          Float32,
+    is_significant       Bool,
+    beta                 Nullable(Float64),
+    se                   Nullable(Float64),
+    af                   Nullable(Float64),
+    association_ac       Nullable(Float64)
+)
+ENGINE = MergeTree()
+PARTITION BY (ancestry, sequencing_type)
+ORDER BY (phenotype, ancestry, sequencing_type, locus_id, xpos)
+SETTINGS index_granularity = 8192;
@@ -0,0 +1,54 @@
+-- Transform SQL for loci_variants
+-- Assigns each staged variant ({staging}) to the locus containing it, using
+-- the phenotype's rows in `loci`; variants outside every locus are dropped
+--
+-- Bind order: phenotype, ancestry, sequencing_type, pvalue threshold,
+-- phenotype, ancestry (for the loci lookup)
+
+INSERT INTO loci_variants
+SELECT
+    ? AS phenotype,
+    ? AS ancestry,
+    ? AS sequencing_type,
+    l.locus_id,
+    v.contig,
+    v.xpos,
+    v.position,
+    v.ref,
+    v.alt,
+    v.pvalue,
+    toFloat32(if(v.pvalue > 0, -log10(v.pvalue), 350)) AS neg_log10_p,
+    v.pvalue < ? AS is_significant,
+    v.beta,
+    v.se,
+    v.af,
+    v.association_ac
+FROM (
+    SELECT
+        1 AS join_key,
+        multiIf(
+            locus.contig = 'chrX', 23,
+            locus.contig = 'chrY', 24,
+            locus.contig = 'chrM', 25,
+            toUInt8OrZero(substring(locus.contig, 4))
+        ) * 1000000000 + locus.position AS xpos,
+        locus.contig AS contig,
+        locus.position AS position,
+        alleles[1] AS ref,
+        alleles[2] AS alt,
+        Pvalue AS pvalue,
+        BETA AS beta,
+        SE AS se,
+        AF_Allele2 AS af,
+        AC_Allele2 AS association_ac
+    FROM {staging}
+) AS v
+-- Range join: the locus with the greatest xstart <= xpos, kept if it also
+-- covers xpos
+ASOF INNER JOIN (
+    SELECT 1 AS join_key, locus_id, xstart, xstop
+    FROM loci
+    WHERE phenotype = ? AND ancestry = ?
+) AS l
+ON v.join_key = l.join_key AND v.xpos >= l.xstart
+WHERE v.xpos <= l.xstop
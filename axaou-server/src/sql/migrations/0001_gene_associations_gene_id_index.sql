@@ -0,0 +1,6 @@
+-- Skip index so WHERE gene_id = ? on gene_associations can prune granules
+-- instead of scanning every phenotype partition.
+ALTER TABLE gene_associations
+    ADD INDEX IF NOT EXISTS idx_gene_id gene_id TYPE bloom_filter(0.01) GRANULARITY 4;
+
+ALTER TABLE gene_associations MATERIALIZE INDEX idx_gene_id
@@ -0,0 +1,31 @@
+-- Backfills for the pre-aggregated top gene associations and Manhattan
+-- peaks tables. The tables themselves come from the ingest DDL
+-- (top_gene_associations.sql, phenotype_peaks.sql), which `MIGRATIONS` runs
+-- ahead of this file. Backfills only run into empty tables, so a database
+-- whose tables were already created and populated by ingest is left
+-- unchanged.
+
+INSERT INTO top_gene_associations
+SELECT
+    gene_id, gene_symbol, annotation, max_maf, phenotype, ancestry,
+    pvalue, pvalue_burden, pvalue_skat, beta_burden, mac,
+    contig, gene_start_position, xpos
+FROM gene_associations
+WHERE pvalue IS NOT NULL AND pvalue <= 1e-4
+  AND (SELECT count() FROM top_gene_associations) = 0;
+
+INSERT INTO phenotype_peaks
+SELECT
+    phenotype,
+    ancestry,
+    sequencing_type,
+    contig,
+    toUInt32(intDiv(position, 1000000)) AS bin_1mb,
+    locus_id,
+    toUInt64(count()) AS sig_variant_count,
+    min(pvalue) AS min_pvalue
+FROM loci_variants
+WHERE is_significant = true
+  AND (association_ac IS NULL OR association_ac >= 5)
+  AND (SELECT count() FROM phenotype_peaks) = 0
+GROUP BY phenotype, ancestry, sequencing_type, contig, bin_1mb, locus_id
@@ -0,0 +1,16 @@
+-- In-silico predictor scores on the annotation tables. Existing rows read as
+-- NULL until the table is re-ingested with `ingest exome-annotations` /
+-- `ingest genome-annotations`.
+ALTER TABLE exome_annotations
+    ADD COLUMN IF NOT EXISTS cadd_phred Nullable(Float32) AFTER lof,
+    ADD COLUMN IF NOT EXISTS revel_max Nullable(Float32) AFTER cadd_phred,
+    ADD COLUMN IF NOT EXISTS spliceai_ds_max Nullable(Float32) AFTER revel_max,
+    ADD COLUMN IF NOT EXISTS alphamissense_score Nullable(Float32) AFTER spliceai_ds_max,
+    ADD COLUMN IF NOT EXISTS alphamissense_class Nullable(String) AFTER alphamissense_score;
+
+ALTER TABLE genome_annotations
+    ADD COLUMN IF NOT EXISTS cadd_phred Nullable(Float32) AFTER lof,
+    ADD COLUMN IF NOT EXISTS revel_max Nullable(Float32) AFTER cadd_phred,
+    ADD COLUMN IF NOT EXISTS spliceai_ds_max Nullable(Float32) AFTER revel_max,
+    ADD COLUMN IF NOT EXISTS alphamissense_score Nullable(Float32) AFTER spliceai_ds_max,
+    ADD COLUMN IF NOT EXISTS alphamissense_class Nullable(String) AFTER alphamissense_score
@@ -0,0 +1,42 @@
+-- Per-ancestry allele counts and frequencies on the annotation tables, from
+-- the source tables' `freq` struct. Existing rows read as NULL until the table
+-- is re-ingested with `ingest exome-annotations` / `ingest genome-annotations`.
+ALTER TABLE exome_annotations
+    ADD COLUMN IF NOT EXISTS ac_afr Nullable(UInt32) AFTER hom,
+    ADD COLUMN IF NOT EXISTS af_afr Nullable(Float64) AFTER ac_afr,
+    ADD COLUMN IF NOT EXISTS an_afr Nullable(UInt32) AFTER af_afr,
+    ADD COLUMN IF NOT EXISTS ac_amr Nullable(UInt32) AFTER an_afr,
+    ADD COLUMN IF NOT EXISTS af_amr Nullable(Float64) AFTER ac_amr,
+    ADD COLUMN IF NOT EXISTS an_amr Nullable(UInt32) AFTER af_amr,
+    ADD COLUMN IF NOT EXISTS ac_eas Nullable(UInt32) AFTER an_amr,
+    ADD COLUMN IF NOT EXISTS af_eas Nullable(Float64) AFTER ac_eas,
+    ADD COLUMN IF NOT EXISTS an_eas Nullable(UInt32) AFTER af_eas,
+    ADD COLUMN IF NOT EXISTS ac_eur Nullable(UInt32) AFTER an_eas,
+    ADD COLUMN IF NOT EXISTS af_eur Nullable(Float64) AFTER ac_eur,
+    ADD COLUMN IF NOT EXISTS an_eur Nullable(UInt32) AFTER af_eur,
+    ADD COLUMN IF NOT EXISTS ac_mid Nullable(UInt32) AFTER an_eur,
+    ADD COLUMN IF NOT EXISTS af_mid Nullable(Float64) AFTER ac_mid,
+    ADD COLUMN IF NOT EXISTS an_mid Nullable(UInt32) AFTER af_mid,
+    ADD COLUMN IF NOT EXISTS ac_sas Nullable(UInt32) AFTER an_mid,
+    ADD COLUMN IF NOT EXISTS af_sas Nullable(Float64) AFTER ac_sas,
+    ADD COLUMN IF NOT EXISTS an_sas Nullable(UInt32) AFTER af_sas;
+
+ALTER TABLE genome_annotations
+    ADD COLUMN IF NOT EXISTS ac_afr Nullable(UInt32) AFTER hom,
+    ADD COLUMN IF NOT EXISTS af_afr Nullable(Float64) AFTER ac_afr,
+    ADD COLUMN IF NOT EXISTS an_afr Nullable(UInt32) AFTER af_afr,
+    ADD COLUMN IF NOT EXISTS ac_amr Nullable(UInt32) AFTER an_afr,
+    ADD COLUMN IF NOT EXISTS af_amr Nullable(Float64) AFTER ac_amr,
+    ADD COLUMN IF NOT EXISTS an_amr Nullable(UInt32) AFTER af_amr,
+    ADD COLUMN IF NOT EXISTS ac_eas Nullable(UInt32) AFTER an_amr,
+    ADD COLUMN IF NOT EXISTS af_eas Nullable(Float64) AFTER ac_eas,
+    ADD COLUMN IF NOT EXISTS an_eas Nullable(UInt32) AFTER af_eas,
+    ADD COLUMN IF NOT EXISTS ac_eur Nullable(UInt32) AFTER an_eas,
+    ADD COLUMN IF NOT EXISTS af_eur Nullable(Float64) AFTER ac_eur,
+    ADD COLUMN IF NOT EXISTS an_eur Nullable(UInt32) AFTER af_eur,
+    ADD COLUMN IF NOT EXISTS ac_mid Nullable(UInt32) AFTER an_eur,
+    ADD COLUMN IF NOT EXISTS af_mid Nullable(Float64) AFTER ac_mid,
+    ADD COLUMN IF NOT EXISTS an_mid Nullable(UInt32) AFTER af_mid,
+    ADD COLUMN IF NOT EXISTS ac_sas Nullable(UInt32) AFTER an_mid,
+    ADD COLUMN IF NOT EXISTS af_sas Nullable(Float64) AFTER ac_sas,
+    ADD COLUMN IF NOT EXISTS an_sas Nullable(UInt32) AFTER af_sas
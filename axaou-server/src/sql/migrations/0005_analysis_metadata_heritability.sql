@@ -0,0 +1,7 @@
+-- SNP heritability estimates on analysis_metadata, from the source table's
+-- ldsc_h2 / saige_h2 fields. Existing rows read as NULL until metadata is
+-- re-ingested with `ingest analysis-metadata`.
+ALTER TABLE analysis_metadata
+    ADD COLUMN IF NOT EXISTS heritability Nullable(Float64) AFTER keep_pheno_skato,
+    ADD COLUMN IF NOT EXISTS heritability_se Nullable(Float64) AFTER heritability,
+    ADD COLUMN IF NOT EXISTS heritability_method Nullable(String) AFTER heritability_se
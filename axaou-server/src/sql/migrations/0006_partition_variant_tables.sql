@@ -0,0 +1,70 @@
+-- Partition the variant serving tables by (ancestry, sequencing_type) so
+-- `ingest variant-results` can swap rebuilt partitions in with REPLACE
+-- PARTITION instead of deleting and re-inserting a phenotype's rows.
+-- phenotype_peaks is now built by the loader alongside loci_variants, so its
+-- materialized view is dropped; REPLACE PARTITION would not trigger it.
+--
+-- Each table is copied into a fresh partitioned twin that is renamed into
+-- place, so a retried run only repeats the copy.
+
+DROP VIEW IF EXISTS phenotype_peaks_mv;
+
+DROP TABLE IF EXISTS significant_variants__unpartitioned;
+DROP TABLE IF EXISTS significant_variants__partitioned;
+CREATE TABLE significant_variants__partitioned AS significant_variants
+ENGINE = MergeTree()
+PARTITION BY (ancestry, sequencing_type)
+ORDER BY (phenotype, ancestry, sequencing_type, xpos, ref, alt);
+INSERT INTO significant_variants__partitioned
+SELECT * FROM significant_variants;
+RENAME TABLE significant_variants TO significant_variants__unpartitioned,
+    significant_variants__partitioned TO significant_variants;
+DROP TABLE significant_variants__unpartitioned;
+
+DROP TABLE IF EXISTS loci_variants__unpartitioned;
+DROP TABLE IF EXISTS loci_variants__partitioned;
+CREATE TABLE loci_variants__partitioned AS loci_variants
+ENGINE = MergeTree()
+PARTITION BY (ancestry, sequencing_type)
+ORDER BY (phenotype, ancestry, sequencing_type, locus_id, xpos);
+INSERT INTO loci_variants__partitioned
+SELECT * FROM loci_variants;
+RENAME TABLE loci_variants TO loci_variants__unpartitioned,
+    loci_variants__partitioned TO loci_variants;
+DROP TABLE loci_variants__unpartitioned;
+
+DROP TABLE IF EXISTS phenotype_peaks__unpartitioned;
+DROP TABLE IF EXISTS phenotype_peaks__partitioned;
+CREATE TABLE phenotype_peaks__partitioned AS phenotype_peaks
+ENGINE = AggregatingMergeTree
+PARTITION BY (ancestry, sequencing_type)
+ORDER BY (phenotype, ancestry, sequencing_type, contig, bin_1mb, locus_id);
+INSERT INTO phenotype_peaks__partitioned
+SELECT * FROM phenotype_peaks;
+RENAME TABLE phenotype_peaks TO phenotype_peaks__unpartitioned,
+    phenotype_peaks__partitioned TO phenotype_peaks;
+DROP TABLE phenotype_peaks__unpartitioned;
+
+DROP TABLE IF EXISTS conditional_variants__unpartitioned;
+DROP TABLE IF EXISTS conditional_variants__partitioned;
+CREATE TABLE conditional_variants__partitioned AS conditional_variants
+ENGINE = MergeTree()
+PARTITION BY (ancestry, sequencing_type)
+ORDER BY (phenotype, ancestry, sequencing_type, locus_id, round, xpos);
+INSERT INTO conditional_variants__partitioned
+SELECT * FROM conditional_variants;
+RENAME TABLE conditional_variants TO conditional_variants__unpartitioned,
+    conditional_variants__partitioned TO conditional_variants;
+DROP TABLE conditional_variants__unpartitioned;
+
+DROP TABLE IF EXISTS credible_sets__unpartitioned;
+DROP TABLE IF EXISTS credible_sets__partitioned;
+CREATE TABLE credible_sets__partitioned AS credible_sets
+ENGINE = MergeTree()
+PARTITION BY (ancestry, sequencing_type)
+ORDER BY (phenotype, ancestry, sequencing_type, locus_id, xpos);
+INSERT INTO credible_sets__partitioned
+SELECT * FROM credible_sets;
+RENAME TABLE credible_sets TO credible_sets__unpartitioned,
+    credible_sets__partitioned TO credible_sets;
+DROP TABLE credible_sets__unpartitioned
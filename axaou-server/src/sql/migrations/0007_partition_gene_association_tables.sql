@@ -0,0 +1,36 @@
+-- Partition gene_associations and top_gene_associations by ancestry so
+-- `ingest gene-associations` can swap rebuilt partitions in with REPLACE
+-- PARTITION instead of deleting and re-inserting a phenotype's rows.
+-- gene_associations was partitioned by phenotype, one tiny partition per
+-- phenotype. top_gene_associations is now built by the loader, so its
+-- materialized view is dropped; REPLACE PARTITION would not trigger it.
+--
+-- Each table is copied into a fresh partitioned twin that is renamed into
+-- place, so a retried run only repeats the copy.
+
+DROP VIEW IF EXISTS top_gene_associations_mv;
+
+DROP TABLE IF EXISTS gene_associations__unpartitioned;
+DROP TABLE IF EXISTS gene_associations__partitioned;
+CREATE TABLE gene_associations__partitioned AS gene_associations
+ENGINE = MergeTree
+PARTITION BY ancestry
+ORDER BY (phenotype, ancestry, annotation, gene_id, max_maf);
+INSERT INTO gene_associations__partitioned
+SELECT * FROM gene_associations;
+RENAME TABLE gene_associations TO gene_associations__unpartitioned,
+    gene_associations__partitioned TO gene_associations;
+DROP TABLE gene_associations__unpartitioned;
+
+DROP TABLE IF EXISTS top_gene_associations__unpartitioned;
+DROP TABLE IF EXISTS top_gene_associations__partitioned;
+CREATE TABLE top_gene_associations__partitioned AS top_gene_associations
+ENGINE = MergeTree
+PARTITION BY ancestry
+ORDER BY (ancestry, annotation, pvalue)
+SETTINGS allow_nullable_key = 1;
+INSERT INTO top_gene_associations__partitioned
+SELECT * FROM top_gene_associations;
+RENAME TABLE top_gene_associations TO top_gene_associations__unpartitioned,
+    top_gene_associations__partitioned TO top_gene_associations;
+DROP TABLE top_gene_associations__unpartitioned
@@ -0,0 +1,10 @@
+-- dbSNP IDs on the annotation tables, with a skip index for rsID lookups.
+-- Existing rows read as empty until the table is re-ingested with
+-- `ingest exome-annotations` / `ingest genome-annotations`.
+ALTER TABLE exome_annotations
+    ADD COLUMN IF NOT EXISTS rsids Array(String) AFTER alt,
+    ADD INDEX IF NOT EXISTS idx_rsids rsids TYPE bloom_filter(0.01) GRANULARITY 1;
+
+ALTER TABLE genome_annotations
+    ADD COLUMN IF NOT EXISTS rsids Array(String) AFTER alt,
+    ADD INDEX IF NOT EXISTS idx_rsids rsids TYPE bloom_filter(0.01) GRANULARITY 1
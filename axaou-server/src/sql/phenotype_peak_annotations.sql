@@ -0,0 +1,17 @@
+-- Annotated Manhattan peaks, one row per locus, written by
+-- `ingest variant-results` after each shard's loci_variants load.
+-- peak_json holds the serialized Peak (genes in locus, coding variant
+-- counts, burden results) so the Manhattan overlay is a keyed read.
+-- The server falls back to computing peaks live when no rows exist.
+CREATE TABLE IF NOT EXISTS phenotype_peak_annotations (
+    phenotype String,
+    ancestry LowCardinality(String),
+    sequencing_type LowCardinality(String),
+    locus_id String,
+    contig LowCardinality(String),
+    position Int32,
+    pvalue Float64,
+    peak_json String,
+    computed_at DateTime DEFAULT now()
+) ENGINE = ReplacingMergeTree(computed_at)
+ORDER BY (phenotype, ancestry, sequencing_type, locus_id)
@@ -0,0 +1,19 @@
+-- Significant variants per locus in 1Mb bins, pre-aggregated from
+-- loci_variants (phenotype_peaks_transform.sql). Replaces the per-request
+-- scan of loci_variants in the Manhattan peaks query.
+--
+-- `ingest variant-results` rebuilds each (ancestry, sequencing_type)
+-- partition together with loci_variants' and swaps both in. Read with
+-- sum()/min() and GROUP BY, since parts merge lazily.
+CREATE TABLE IF NOT EXISTS phenotype_peaks (
+    phenotype String,
+    ancestry LowCardinality(String),
+    sequencing_type LowCardinality(String),
+    contig LowCardinality(String),
+    bin_1mb UInt32,
+    locus_id String,
+    sig_variant_count SimpleAggregateFunction(sum, UInt64),
+    min_pvalue SimpleAggregateFunction(min, Float64)
+) ENGINE = AggregatingMergeTree
+PARTITION BY (ancestry, sequencing_type)
+ORDER BY (phenotype, ancestry, sequencing_type, contig, bin_1mb, locus_id);
@@ -0,0 +1,21 @@
+-- Transform SQL for phenotype_peaks
+-- Aggregates one phenotype's staged loci_variants rows ({staging}) into
+-- significant variant counts per locus and 1Mb bin
+--
+-- Bind order: phenotype
+
+INSERT INTO phenotype_peaks
+SELECT
+    phenotype,
+    ancestry,
+    sequencing_type,
+    contig,
+    toUInt32(intDiv(position, 1000000)) AS bin_1mb,
+    locus_id,
+    toUInt64(count()) AS sig_variant_count,
+    min(pvalue) AS min_pvalue
+FROM {staging}
+WHERE phenotype = ?
+  AND is_significant = true
+  AND (association_ac IS NULL OR association_ac >= 5)
+GROUP BY phenotype, ancestry, sequencing_type, contig, bin_1mb, locus_id
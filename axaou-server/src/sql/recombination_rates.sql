@@ -0,0 +1,13 @@
+-- Recombination rate map (GRCh38), loaded by `ingest recombination-rates`
+-- from a HapMap-style genetic map and drawn under LocusZoom panels. Each row
+-- gives the rate from `position` up to the next row of the same contig.
+CREATE TABLE IF NOT EXISTS recombination_rates (
+    contig LowCardinality(String),
+    position UInt32,
+    xpos Int64,
+    -- Recombination rate in cM/Mb
+    rate Float64,
+    -- Cumulative genetic map position in cM
+    genetic_map_cm Float64
+) ENGINE = MergeTree
+ORDER BY xpos;
@@ -0,0 +1,10 @@
+-- Applied schema migrations (see `migrate`). One row per migration version;
+-- the checksum detects migration files edited after they were applied.
+CREATE TABLE IF NOT EXISTS schema_migrations (
+    version UInt32,
+    name String,
+    checksum String,
+    applied_by String,
+    applied_at DateTime DEFAULT now()
+) ENGINE = ReplacingMergeTree(applied_at)
+ORDER BY version
@@ -0,0 +1,25 @@
+-- DDL for significant_variants table
+-- Genome-wide significant variant associations, one row per
+-- (phenotype, ancestry, sequencing type, variant)
+--
+-- Populated per phenotype by `ingest variant-results`, which replaces
+-- whole (ancestry, sequencing_type) partitions
+
+CREATE TABLE IF NOT EXISTS significant_variants (
+    phenotype            String,
+    ancestry             LowCardinality(String),
+    sequencing_type      LowCardinality(String),
+    xpos                 Int64,
+    contig               LowCardinality(String),
+    position             Int32,
+    ref                  String,
+    alt                  String,
+    pvalue               Float64,
+    beta                 Float64,
+    se                   Float64,
+    af                   Float64
+)
+ENGINE = MergeTree()
+PARTITION BY (ancestry, sequencing_type)
+ORDER BY (phenotype, ancestry, sequencing_type, xpos, ref, alt)
+SETTINGS index_granularity = 8192;
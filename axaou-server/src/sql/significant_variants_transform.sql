@@ -0,0 +1,29 @@
+-- Transform SQL for significant_variants
+-- Copies variants below the significance threshold from one phenotype's
+-- staged variant results ({staging}) into significant_variants
+--
+-- Bind order: phenotype, ancestry, sequencing_type, pvalue threshold
+-- Source fields follow the SAIGE variant results schema
+-- (locus, alleles, Pvalue, BETA, SE, AF_Allele2, AC_Allele2)
+
+INSERT INTO significant_variants
+SELECT
+    ? AS phenotype,
+    ? AS ancestry,
+    ? AS sequencing_type,
+    multiIf(
+        locus.contig = 'chrX', 23,
+        locus.contig = 'chrY', 24,
+        locus.contig = 'chrM', 25,
+        toUInt8OrZero(substring(locus.contig, 4))
+    ) * 1000000000 + locus.position AS xpos,
+    locus.contig AS contig,
+    locus.position AS position,
+    alleles[1] AS ref,
+    alleles[2] AS alt,
+    Pvalue AS pvalue,
+    BETA AS beta,
+    SE AS se,
+    AF_Allele2 AS af
+FROM {staging}
+WHERE Pvalue < ?
@@ -0,0 +1,24 @@
+-- Top gene associations: gene_associations rows with pvalue <= 1e-4, sorted
+-- for the /api/genes/top-associations scan. Built from the staged
+-- gene_associations rows (top_gene_associations_transform.sql); `ingest
+-- gene-associations` swaps each ancestry partition in together with
+-- gene_associations'.
+CREATE TABLE IF NOT EXISTS top_gene_associations (
+    gene_id String,
+    gene_symbol String,
+    annotation LowCardinality(String),
+    max_maf Float64,
+    phenotype String,
+    ancestry LowCardinality(String),
+    pvalue Nullable(Float64),
+    pvalue_burden Nullable(Float64),
+    pvalue_skat Nullable(Float64),
+    beta_burden Nullable(Float64),
+    mac Nullable(Int64),
+    contig LowCardinality(String),
+    gene_start_position Int32,
+    xpos Int64
+) ENGINE = MergeTree
+PARTITION BY ancestry
+ORDER BY (ancestry, annotation, pvalue)
+SETTINGS allow_nullable_key = 1
@@ -0,0 +1,15 @@
+-- Transform SQL for top_gene_associations
+-- Copies one phenotype's staged gene_associations rows ({staging}) within the
+-- p-value cutoff of top_gene_associations
+--
+-- Bind order: phenotype
+
+INSERT INTO top_gene_associations
+SELECT
+    gene_id, gene_symbol, annotation, max_maf, phenotype, ancestry,
+    pvalue, pvalue_burden, pvalue_skat, beta_burden, mac,
+    contig, gene_start_position, xpos
+FROM {staging}
+WHERE phenotype = ?
+  AND pvalue IS NOT NULL
+  AND pvalue <= 1e-4
@@ -0,0 +1,15 @@
+-- Transform SQL for analysis_test_counts (variant results)
+-- Counts the tested variants of one phenotype's staged variant results
+-- ({staging}); the effective count is capped for LD
+--
+-- Bind order: phenotype, ancestry, sequencing_type, effective test cap
+
+INSERT INTO analysis_test_counts (phenotype, ancestry, test_type, n_tests, effective_tests)
+SELECT
+    ? AS phenotype,
+    ? AS ancestry,
+    ? AS test_type,
+    count() AS n_tests,
+    least(toFloat64(count()), ?) AS effective_tests
+FROM {staging}
+WHERE isFinite(Pvalue)
@@ -0,0 +1,218 @@
+//! Freshness gauges for ClickHouse serving tables
+//!
+//! A failed or partial ingest can leave a serving table empty or stale while
+//! every endpoint keeps answering. `serve` samples each dataset's MergeTree
+//! tables every `--table-stats-interval-secs` and `GET /metrics` exposes the
+//! latest sample in the Prometheus text format:
+//!
+//! - `axaou_table_rows{dataset,table}`: row count
+//! - `axaou_table_bytes{dataset,table}`: compressed size on disk
+//! - `axaou_table_last_modified_seconds{dataset,table}`: Unix time of the
+//!   newest data part or table swap (`EXCHANGE`/`RENAME` after an ingest)
+//! - `axaou_table_stats_last_success_seconds{dataset}`: when sampling last
+//!   succeeded, to alert on the sampler itself
+
+use crate::api::AppState;
+use crate::clickhouse::QueryExt;
+use crate::error::AppError;
+use crate::health::ProbeState;
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use std::fmt::Write;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, warn};
+
+/// Media type of the Prometheus text exposition format
+const PROMETHEUS_MIME: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+const TABLE_STATS_QUERY: &str = r#"
+    SELECT
+        t.name AS table,
+        ifNull(t.total_rows, 0) AS rows,
+        ifNull(t.total_bytes, 0) AS bytes,
+        toUInt32(toUnixTimestamp(greatest(t.metadata_modification_time, p.last_modified))) AS last_modified
+    FROM system.tables AS t
+    LEFT JOIN (
+        SELECT table, max(modification_time) AS last_modified
+        FROM system.parts
+        WHERE active AND database = currentDatabase()
+        GROUP BY table
+    ) AS p ON p.table = t.name
+    WHERE t.database = currentDatabase() AND t.engine LIKE '%MergeTree'
+    ORDER BY t.name
+"#;
+
+/// Size and freshness of one table
+#[derive(Debug, Clone, PartialEq, Deserialize, clickhouse::Row)]
+pub struct TableStats {
+    pub table: String,
+    pub rows: u64,
+    pub bytes: u64,
+    /// Unix seconds
+    pub last_modified: u32,
+}
+
+/// Latest sample of a dataset's tables
+#[derive(Debug, Clone, Default)]
+pub struct TableStatsSnapshot {
+    /// Tables from the last successful sample
+    pub tables: Vec<TableStats>,
+    pub last_success: Option<SystemTime>,
+}
+
+/// Table stats shared between the sampler and `/metrics`
+#[derive(Debug, Default)]
+pub struct TableStatsCache {
+    latest: RwLock<TableStatsSnapshot>,
+}
+
+impl TableStatsCache {
+    pub fn snapshot(&self) -> TableStatsSnapshot {
+        self.latest.read().unwrap().clone()
+    }
+
+    fn record(&self, tables: Vec<TableStats>) {
+        *self.latest.write().unwrap() = TableStatsSnapshot {
+            tables,
+            last_success: Some(SystemTime::now()),
+        };
+    }
+}
+
+/// Read row counts and modification times of the dataset's tables
+pub async fn sample(state: &AppState) -> Result<Vec<TableStats>, AppError> {
+    state
+        .clickhouse
+        .query(TABLE_STATS_QUERY)
+        .fetch_all_with::<TableStats>(&state.executor)
+        .await
+}
+
+/// Sample `state`'s tables now and then every `interval`
+///
+/// A failed sample keeps the previous one; its age shows in
+/// `axaou_table_stats_last_success_seconds`.
+pub fn spawn_sampler(state: Arc<AppState>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match sample(&state).await {
+                Ok(tables) => {
+                    debug!("Sampled {} ClickHouse tables", tables.len());
+                    state.table_stats.record(tables);
+                }
+                Err(e) => warn!("Failed to sample ClickHouse table stats: {}", e),
+            }
+        }
+    });
+}
+
+/// GET /metrics
+///
+/// Table gauges of every mounted dataset in the Prometheus text format.
+pub async fn metrics(State(probe): State<Arc<ProbeState>>) -> Response {
+    let snapshots: Vec<(String, TableStatsSnapshot)> = probe
+        .datasets
+        .iter()
+        .map(|(name, state)| (name.clone(), state.table_stats.snapshot()))
+        .collect();
+    ([(header::CONTENT_TYPE, PROMETHEUS_MIME)], render(&snapshots)).into_response()
+}
+
+/// Prometheus text exposition of the snapshots
+fn render(snapshots: &[(String, TableStatsSnapshot)]) -> String {
+    let mut out = String::new();
+    let gauges: [(&str, &str, fn(&TableStats) -> u64); 3] = [
+        ("axaou_table_rows", "Rows in a ClickHouse table", |t| t.rows),
+        ("axaou_table_bytes", "Compressed bytes of a ClickHouse table", |t| t.bytes),
+        (
+            "axaou_table_last_modified_seconds",
+            "Unix time a ClickHouse table last received data or was swapped in",
+            |t| t.last_modified as u64,
+        ),
+    ];
+    for (name, help, value) in gauges {
+        let _ = writeln!(out, "# HELP {} {}\n# TYPE {} gauge", name, help, name);
+        for (dataset, snapshot) in snapshots {
+            for table in &snapshot.tables {
+                let _ = writeln!(
+                    out,
+                    "{}{{dataset=\"{}\",table=\"{}\"}} {}",
+                    name,
+                    escape_label(dataset),
+                    escape_label(&table.table),
+                    value(table)
+                );
+            }
+        }
+    }
+
+    let name = "axaou_table_stats_last_success_seconds";
+    let _ = writeln!(
+        out,
+        "# HELP {} Unix time table stats were last sampled successfully\n# TYPE {} gauge",
+        name, name
+    );
+    for (dataset, snapshot) in snapshots {
+        if let Some(at) = snapshot.last_success {
+            let secs = at
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let _ = writeln!(out, "{}{{dataset=\"{}\"}} {}", name, escape_label(dataset), secs);
+        }
+    }
+    out
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let snapshot = TableStatsSnapshot {
+            tables: vec![
+                TableStats {
+                    table: "gene_models".to_string(),
+                    rows: 62000,
+                    bytes: 1 << 20,
+                    last_modified: 1_700_000_000,
+                },
+                TableStats {
+                    table: "loci".to_string(),
+                    rows: 0,
+                    bytes: 0,
+                    last_modified: 1_690_000_000,
+                },
+            ],
+            last_success: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_100)),
+        };
+        let text = render(&[("414k".to_string(), snapshot)]);
+        assert!(text.contains("# TYPE axaou_table_rows gauge\n"));
+        assert!(text.contains("axaou_table_rows{dataset=\"414k\",table=\"gene_models\"} 62000\n"));
+        assert!(text.contains("axaou_table_rows{dataset=\"414k\",table=\"loci\"} 0\n"));
+        assert!(text.contains(
+            "axaou_table_last_modified_seconds{dataset=\"414k\",table=\"gene_models\"} 1700000000\n"
+        ));
+        assert!(text.contains("axaou_table_stats_last_success_seconds{dataset=\"414k\"} 1700000100\n"));
+
+        // Never sampled: type lines only
+        let empty = render(&[("v9".to_string(), TableStatsSnapshot::default())]);
+        assert!(!empty.contains("dataset=\"v9\""));
+        assert_eq!(escape_label("a\"b\\c"), "a\\\"b\\\\c");
+    }
+}
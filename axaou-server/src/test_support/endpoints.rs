@@ -0,0 +1,332 @@
+//! Endpoint tests against the fixture database (see [`super::TestApp`])
+
+use super::snapshots::{check_snapshot, matches_snapshot};
+use super::TestApp;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::Value;
+use tower::ServiceExt;
+
+fn field<'a>(rows: &'a Value, key: &str) -> Vec<&'a Value> {
+    rows.as_array()
+        .expect("expected a JSON array")
+        .iter()
+        .map(|row| &row[key])
+        .collect()
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn test_analysis_endpoints() {
+    let app = TestApp::start().await;
+    assert_eq!(app.state.metadata.read().await.len(), 3);
+
+    let analyses = app.get_ok("/api/analyses").await;
+    assert_eq!(analyses.as_array().unwrap().len(), 3);
+
+    let height = app.get_ok("/api/analyses/height?ancestry_group=eur").await;
+    assert_eq!(field(&height, "ancestry_group"), vec!["eur"]);
+    assert_eq!(height[0]["n_cases"], 190000);
+
+    let (status, body) = app.get("/api/analyses/not_a_phenotype").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "not_found");
+
+    let categories = app.get_ok("/api/categories").await;
+    let mut names = field(&categories, "category");
+    names.sort_by_key(|v| v.as_str().map(str::to_string));
+    assert_eq!(names, vec!["endocrine", "physical_measurement"]);
+
+    let summary = app.get_ok("/api/phenotypes/summary").await;
+    assert_eq!(summary["count"], 2);
+    assert_eq!(summary["data"][0]["analysis_id"], "height");
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn test_phenotype_endpoints() {
+    let app = TestApp::start().await;
+
+    let thresholds = app.get_ok("/api/phenotype/height/thresholds").await;
+    assert_eq!(thresholds["exome_wide"]["source"], "ingest");
+    assert_eq!(thresholds["exome_wide"]["n_tests"], 400000);
+    assert_eq!(thresholds["genome_wide"]["source"], "default");
+
+    let significant = app
+        .get_ok("/api/phenotype/height/significant?sequencing_type=exome")
+        .await;
+    assert_eq!(field(&significant, "xpos"), vec![1055039974i64]);
+
+    let genes = app.get_ok("/api/phenotype/height/genes").await;
+    assert_eq!(field(&genes, "gene_symbol"), vec!["PCSK9", "APOB"]);
+
+    let count = app
+        .get_ok("/api/phenotype/height/genes/count?annotation=pLoF")
+        .await;
+    assert_eq!(count["count"], 2);
+
+    let (status, body) = app.get("/api/phenotype/height/thresholds?alpha=2").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "bad_request");
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn test_variant_endpoints() {
+    let app = TestApp::start().await;
+
+    let association = app
+        .get_ok("/api/variants/associations/variant/1-55039974-G-T?analysis_id=height")
+        .await;
+    assert_eq!(association["count"], 1);
+    assert_eq!(association["data"][0]["pvalue"], 2e-12);
+
+    let missing = app
+        .get_ok("/api/variants/associations/variant/1-55039974-G-C?analysis_id=height")
+        .await;
+    assert_eq!(missing["count"], 0);
+
+    let (status, _) = app
+        .get("/api/variants/associations/variant/not-a-variant?analysis_id=height")
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+/// The Manhattan overlay, overview and gene association routes (see the
+/// routing test in `main.rs` for the plot routes backed by GCS)
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn test_phenotype_plot_endpoints() {
+    let app = TestApp::start().await;
+
+    // Gene hits below p = 0.05, both PCSK9 masks but not APOB; genome-wide
+    // overlays carry peaks only
+    let genes = app
+        .get_ok("/api/phenotype/height/manhattan/overlay?plot_type=gene_manhattan")
+        .await;
+    assert_eq!(genes["hit_count"], 2);
+    assert_eq!(genes["significant_hits"], Value::Array(Vec::new()));
+    assert_eq!(genes["peaks"][0]["locus_id"], "burden-ENSG00000169174");
+    let chr1 = app
+        .get_ok("/api/phenotype/height/manhattan/overlay?plot_type=gene_manhattan&contig=chr1")
+        .await;
+    let hits = &chr1["significant_hits"];
+    assert_eq!(field(hits, "label"), vec!["PCSK9", "PCSK9"]);
+    assert_eq!(hits[0]["hit_type"], "gene");
+    assert_eq!(hits[0]["pvalue"], 3e-7);
+
+    // Genome-wide variant overlays only count hits; per-chromosome ones list them
+    let exome = app
+        .get_ok("/api/phenotype/height/manhattan/overlay?plot_type=exome_manhattan")
+        .await;
+    assert_eq!(exome["hit_count"], 1);
+    assert_eq!(exome["significant_hits"], Value::Array(Vec::new()));
+    let chr1 = app
+        .get_ok("/api/phenotype/height/manhattan/overlay?plot_type=exome_manhattan&contig=chr1")
+        .await;
+    let hits = &chr1["significant_hits"];
+    assert_eq!(field(hits, "id"), vec!["chr1-55039974-G-T"]);
+    assert_eq!(hits[0]["gene_symbol"], "PCSK9");
+    assert_eq!(hits[0]["consequence"], "missense_variant");
+
+    let overview = app.get_ok("/api/phenotype/height/overview").await;
+    assert_eq!(
+        overview["exome_image_url"],
+        "/api/phenotype/height/manhattan/image?ancestry=meta&plot_type=exome_manhattan"
+    );
+    let pcsk9 = overview["unified_loci"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|locus| locus["genes"].as_array().unwrap())
+        .find(|gene| gene["gene_symbol"] == "PCSK9")
+        .expect("PCSK9 burden hit in the overview");
+    assert_eq!(field(&pcsk9["burden_results"], "annotation"), vec!["pLoF"]);
+
+    let associations = app
+        .get_ok("/api/genes/associations?gene_id=ENSG00000169174&analysis_id=height&ancestry_group=meta")
+        .await;
+    assert_eq!(field(&associations, "annotation"), vec!["pLoF", "missenseLC"]);
+    assert_eq!(associations[0]["analysis_id"], "height");
+
+    let top = app.get_ok("/api/genes/top-associations?ancestry=meta").await;
+    assert_eq!(top["count"], 1);
+    assert_eq!(top["data"][0]["gene_symbol"], "PCSK9");
+    assert_eq!(top["data"][0]["annotation"], "pLoF");
+
+    let (status, body) = app
+        .get("/api/phenotype/height/manhattan/overlay?ancestry=nfe")
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "bad_request");
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn test_gene_endpoints() {
+    let app = TestApp::start().await;
+
+    let model = app.get_ok("/api/genes/model/ENSG00000169174").await;
+    assert_eq!(field(&model, "symbol"), vec!["PCSK9"]);
+    assert_eq!(model[0]["chrom"], "1");
+
+    let (status, _) = app.get("/api/genes/model/ENSG00000000000").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let summary = app.get_ok("/api/genes/summary").await;
+    assert_eq!(summary["data"][0]["gene_symbol"], "PCSK9");
+
+    let version = app.get_ok("/api/version").await;
+    let tables = version["tables"].as_array().unwrap();
+    let gene_models = tables
+        .iter()
+        .find(|t| t["table"] == "gene_models")
+        .expect("gene_models listed in /api/version");
+    assert_eq!(gene_models["rows"], 2);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn test_gene_lookup_endpoints() {
+    let app = TestApp::start().await;
+
+    let in_interval = app
+        .get_ok("/api/genes/model/interval/chr1:55000000-55100000")
+        .await;
+    assert_eq!(field(&in_interval, "gene_id"), vec!["ENSG00000169174"]);
+
+    // Gene names resolve to the gene's own interval
+    let by_symbol = app.get_ok("/api/genes/model/interval/APOB").await;
+    assert_eq!(field(&by_symbol, "symbol"), vec!["APOB"]);
+
+    let (status, _) = app.get("/api/genes/model/interval/NOTAGENE").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let models = app.get_ok("/api/genes/model/search?q=apob&fields=gene_id,symbol").await;
+    assert_eq!(models[0]["symbol"], "APOB");
+    assert!(models[0].get("chrom").is_none());
+
+    let suggestions = app.get_ok("/api/genes/search?q=pcs").await;
+    assert_eq!(field(&suggestions, "symbol"), vec!["PCSK9"]);
+    assert_eq!(suggestions[0]["match_type"], "symbol_prefix");
+
+    let (status, body) = app.get("/api/genes/search?q=%20").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "bad_request");
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn test_all_symbols_endpoint() {
+    let app = TestApp::start().await;
+
+    // One row per symbol, alphabetical, although PCSK9 has two annotations
+    let symbols = app.get_ok("/api/genes/all-symbols").await;
+    assert_eq!(field(&symbols, "gene_symbol"), vec!["APOB", "PCSK9"]);
+    assert_eq!(
+        field(&symbols, "gene_id"),
+        vec!["ENSG00000084674", "ENSG00000169174"]
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn test_analysis_search_endpoint() {
+    let app = TestApp::start().await;
+
+    let hits = app.get_ok("/api/analyses/search?q=diabetes").await;
+    assert_eq!(hits[0]["analysis_id"], "T2D");
+
+    // One record per analysis by default, the meta-analysis one
+    let height = app.get_ok("/api/analyses/search?q=height").await;
+    assert_eq!(field(&height, "ancestry_group"), vec!["meta"]);
+    let eur = app
+        .get_ok("/api/analyses/search?q=height&ancestry_group=eur")
+        .await;
+    assert_eq!(field(&eur, "n_cases"), vec![190000]);
+
+    let (status, _) = app.get("/api/health").await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+/// `db_queries` counts every query of a request, including the gene model
+/// lookup that precedes the annotation query
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn test_reported_db_queries() {
+    let app = TestApp::start().await;
+
+    let annotations = app
+        .get_ok("/api/variants/annotations/gene/ENSG00000169174?extended=true")
+        .await;
+    assert_eq!(annotations["count"], 1);
+    assert_eq!(annotations["data"][0]["gene_symbol"], "PCSK9");
+    assert_eq!(annotations["db_queries"], 2);
+
+    // Unknown genes stop after the gene model lookup
+    let unknown = app
+        .get_ok("/api/variants/annotations/gene/ENSG00000000000?extended=true")
+        .await;
+    assert_eq!(unknown["count"], 0);
+    assert_eq!(unknown["db_queries"], 1);
+}
+
+/// Endpoints whose responses are pinned by golden snapshots, by snapshot name
+const SNAPSHOT_ENDPOINTS: &[(&str, &str)] = &[
+    ("analyses", "/api/analyses"),
+    ("analysis_by_id", "/api/analyses/height"),
+    ("categories", "/api/categories"),
+    ("phenotypes_summary", "/api/phenotypes/summary"),
+    ("phenotype_thresholds", "/api/phenotype/height/thresholds"),
+    ("phenotype_significant", "/api/phenotype/height/significant?sequencing_type=exome"),
+    ("phenotype_genes", "/api/phenotype/height/genes"),
+    ("phenotype_genes_count", "/api/phenotype/height/genes/count"),
+    (
+        "variant_association",
+        "/api/variants/associations/variant/1-55039974-G-T?analysis_id=height",
+    ),
+    ("gene_model", "/api/genes/model/ENSG00000169174"),
+    ("genes_summary", "/api/genes/summary"),
+    ("config", "/api/config"),
+];
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn test_response_snapshots() {
+    let app = TestApp::start().await;
+
+    let mut failures = Vec::new();
+    for (name, uri) in SNAPSHOT_ENDPOINTS {
+        let body = app.get_ok(uri).await;
+        if let Err(e) = check_snapshot(name, &body) {
+            failures.push(e);
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+/// The shipped `serve --fixtures` responses follow the same contract, so the
+/// frontend's offline fixtures cannot drift from the API
+#[tokio::test]
+async fn test_shipped_fixtures_match_snapshots() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    let router = crate::fixtures::fixture_router(dir);
+
+    let mut failures = Vec::new();
+    for (name, uri) in SNAPSHOT_ENDPOINTS {
+        let response = router
+            .clone()
+            .oneshot(Request::get(*uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "no shipped fixture for {}", uri);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        if let Err(e) = matches_snapshot(name, &body) {
+            failures.push(e);
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,84 @@
+//! Row factories shared by unit tests
+//!
+//! Each builds a fully-populated value from the few fields tests vary; adjust
+//! anything else with struct update syntax. Unlike [`super::TestApp`], these
+//! need no ClickHouse.
+
+use crate::clickhouse::models::LocusRow;
+use crate::genomics::Contig;
+use crate::models::{
+    AnalysisAsset, AnalysisAssetType, AnalysisMetadata, AncestryGroup, SequencingType,
+};
+
+/// Genome locus of "height" (meta) spanning `start..=stop` on `contig`, led
+/// by `{contig}-{start + 10}-A-T`
+pub fn locus(locus_id: &str, contig: &str, start: i32, stop: i32, lead_pvalue: f64) -> LocusRow {
+    let contig_xpos = Contig::parse(contig).unwrap().xpos(0);
+    LocusRow {
+        locus_id: locus_id.to_string(),
+        phenotype: "height".to_string(),
+        ancestry: "meta".to_string(),
+        contig: contig.to_string(),
+        start,
+        stop,
+        xstart: contig_xpos + start as i64,
+        xstop: contig_xpos + stop as i64,
+        source: "genome".to_string(),
+        lead_variant: format!("{}-{}-A-T", contig, start + 10),
+        lead_pvalue,
+        exome_count: 0,
+        genome_count: 0,
+        plot_gcs_uri: String::new(),
+    }
+}
+
+/// Result table of `analysis_id` at its 414k discovery path
+pub fn asset(
+    analysis_id: &str,
+    ancestry: AncestryGroup,
+    asset_type: AnalysisAssetType,
+    sequencing_type: Option<SequencingType>,
+) -> AnalysisAsset {
+    AnalysisAsset {
+        ancestry_group: ancestry,
+        analysis_id: analysis_id.to_string(),
+        uri: format!(
+            "gs://aou_results/414k/ht_results/{}/phenotype_{}/{}",
+            ancestry.dir_name(),
+            analysis_id,
+            asset_type.filename(sequencing_type)
+        ),
+        asset_type,
+        sequencing_type,
+    }
+}
+
+/// Metadata record described by its `analysis_id`, with no controls and no
+/// lambda GC or heritability estimates
+pub fn analysis_metadata(
+    analysis_id: &str,
+    ancestry: &str,
+    trait_type: &str,
+    n_cases: i64,
+) -> AnalysisMetadata {
+    AnalysisMetadata {
+        analysis_id: analysis_id.to_string(),
+        ancestry_group: ancestry.to_string(),
+        category: "lab_measurement".to_string(),
+        description: analysis_id.to_string(),
+        description_more: String::new(),
+        keep_pheno_burden: true,
+        keep_pheno_skat: true,
+        keep_pheno_skato: true,
+        heritability: None,
+        heritability_se: None,
+        heritability_method: None,
+        lambda_gc_acaf: None,
+        lambda_gc_exome: None,
+        lambda_gc_gene_burden_001: None,
+        n_cases,
+        n_controls: None,
+        pheno_sex: "both_sexes".to_string(),
+        trait_type: trait_type.to_string(),
+    }
+}
@@ -0,0 +1,56 @@
+//! `migrate up` against a throwaway ClickHouse (see [`super::start_clickhouse`])
+
+use super::start_clickhouse;
+use crate::cli::migrate::{run_migrate, MigrateArgs, MigrateCommand, MIGRATIONS};
+use crate::cli::sql::SqlClient;
+
+/// Tables the migrations alter or copy, as ingest creates them
+const MIGRATED_TABLES: &[&str] = &[
+    include_str!("../sql/analysis_metadata.sql"),
+    include_str!("../sql/gene_models.sql"),
+    include_str!("../sql/exome_annotations.sql"),
+    include_str!("../sql/genome_annotations.sql"),
+    include_str!("../sql/gene_associations.sql"),
+    include_str!("../sql/top_gene_associations.sql"),
+    include_str!("../sql/significant_variants.sql"),
+    include_str!("../sql/loci_variants.sql"),
+    include_str!("../sql/conditional_variants.sql"),
+    include_str!("../sql/credible_sets.sql"),
+    include_str!("../sql/phenotype_peaks.sql"),
+];
+
+fn up(url: &str) -> MigrateCommand {
+    MigrateCommand::Up(MigrateArgs {
+        clickhouse_url: url.to_string(),
+        database: "default".to_string(),
+        target: None,
+        dry_run: false,
+    })
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn test_migrations_apply_to_ingested_schema() {
+    let (_clickhouse, url) = start_clickhouse().await;
+    let sql = SqlClient::new(&url, "default");
+    for ddl in MIGRATED_TABLES {
+        sql.execute(ddl).await.unwrap();
+    }
+
+    run_migrate(up(&url)).await.unwrap();
+    let applied = sql
+        .query("SELECT count() FROM schema_migrations FINAL")
+        .fetch_one::<u64>()
+        .await
+        .unwrap();
+    assert_eq!(applied, MIGRATIONS.len() as u64);
+
+    // Nothing is pending on a second run
+    run_migrate(up(&url)).await.unwrap();
+    let applied = sql
+        .query("SELECT count() FROM schema_migrations")
+        .fetch_one::<u64>()
+        .await
+        .unwrap();
+    assert_eq!(applied, MIGRATIONS.len() as u64);
+}
@@ -0,0 +1,189 @@
+//! Request-level test harness backed by a throwaway ClickHouse
+//!
+//! [`TestApp::start`] runs ClickHouse in Docker (testcontainers), applies the
+//! embedded DDL from `src/sql`, loads the small JSONEachRow datasets under
+//! `fixtures/` and mounts the dataset router at `/api`, exactly as `serve`
+//! does. Tests then issue requests with [`TestApp::get`].
+//!
+//! The tests need Docker and are `#[ignore]`d by default:
+//!
+//! ```text
+//! cargo test test_support -- --ignored
+//! ```
+//!
+//! CI runs them in the `integration` job of `.github/workflows/server.yml`.
+//!
+//! Fixture rows only name the columns a test relies on; ClickHouse fills the
+//! rest with column defaults. Response contracts are pinned by golden
+//! snapshots (see [`snapshots`]); the shipped `serve --fixtures` responses are
+//! checked against the same snapshots without Docker.
+//!
+//! Unit tests build rows with the shared [`factories`], which need no Docker.
+
+mod endpoints;
+pub mod factories;
+mod migrations;
+mod snapshots;
+
+use crate::api::AppState;
+use crate::cli::sql::SqlClient;
+use crate::gene_models::GeneModelsBackendKind;
+use crate::models::AnalysisAssets;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use std::sync::Arc;
+use testcontainers::core::IntoContainerPort;
+use testcontainers::runners::AsyncRunner;
+use testcontainers::ContainerAsync;
+use testcontainers_modules::clickhouse::ClickHouse;
+use tower::ServiceExt;
+
+/// Tables created for every test app, in creation order, with their fixture
+/// rows (JSONEachRow). Views must follow the tables they read; rows are
+/// loaded once every table and view exists, as during ingest.
+const TABLES: &[(&str, &str, &str)] = &[
+    (
+        "analysis_metadata",
+        include_str!("../sql/analysis_metadata.sql"),
+        include_str!("fixtures/analysis_metadata.jsonl"),
+    ),
+    (
+        "analysis_test_counts",
+        include_str!("../sql/analysis_test_counts.sql"),
+        include_str!("fixtures/analysis_test_counts.jsonl"),
+    ),
+    (
+        "gene_models",
+        include_str!("../sql/gene_models.sql"),
+        include_str!("fixtures/gene_models.jsonl"),
+    ),
+    (
+        "exome_annotations",
+        include_str!("../sql/exome_annotations.sql"),
+        include_str!("fixtures/exome_annotations.jsonl"),
+    ),
+    (
+        "gene_associations",
+        include_str!("../sql/gene_associations.sql"),
+        include_str!("fixtures/gene_associations.jsonl"),
+    ),
+    (
+        "top_gene_associations",
+        include_str!("../sql/top_gene_associations.sql"),
+        include_str!("fixtures/top_gene_associations.jsonl"),
+    ),
+    (
+        "loci_variants",
+        include_str!("../sql/loci_variants.sql"),
+        include_str!("fixtures/loci_variants.jsonl"),
+    ),
+    (
+        "significant_variants",
+        include_str!("../sql/significant_variants.sql"),
+        include_str!("fixtures/significant_variants.jsonl"),
+    ),
+    (
+        "phenotype_summary",
+        include_str!("../sql/phenotype_summary.sql"),
+        include_str!("fixtures/phenotype_summary.jsonl"),
+    ),
+    (
+        "gene_summary",
+        include_str!("../sql/gene_summary.sql"),
+        include_str!("fixtures/gene_summary.jsonl"),
+    ),
+    ("ingest_runs", include_str!("../sql/ingest_runs.sql"), ""),
+];
+
+/// The API router over a fixture-loaded ClickHouse
+pub struct TestApp {
+    // Dropping the container stops it
+    _clickhouse: ContainerAsync<ClickHouse>,
+    pub state: Arc<AppState>,
+    router: Router,
+}
+
+impl TestApp {
+    /// Start ClickHouse, load the fixtures and build the router
+    pub async fn start() -> Self {
+        let (container, url) = start_clickhouse().await;
+        let sql = SqlClient::new(&url, "default");
+        for (table, ddl, _) in TABLES {
+            sql.execute(ddl)
+                .await
+                .unwrap_or_else(|e| panic!("Failed to create {}: {:#}", table, e));
+        }
+        for (table, _, rows) in TABLES {
+            insert_rows(&sql, table, rows).await;
+        }
+
+        let state = crate::build_state_with_client(
+            crate::config::Config::default(),
+            None,
+            GeneModelsBackendKind::ClickHouse,
+            sql.client().clone(),
+        );
+        let metadata = crate::metadata::MetadataClickHouse::new(&state.clickhouse, &state.executor)
+            .load_all()
+            .await
+            .expect("Failed to load fixture metadata");
+        crate::metadata::install(&state, metadata).await;
+        // Keep asset-backed handlers off GCS
+        *state.assets.write().await = Some(AnalysisAssets::default());
+
+        let router = Router::new().nest("/api", crate::dataset_router(&state));
+        Self {
+            _clickhouse: container,
+            state,
+            router,
+        }
+    }
+
+    /// GET `uri`, returning the status and the body parsed as JSON
+    /// (`Value::Null` for non-JSON bodies)
+    pub async fn get(&self, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = self
+            .router
+            .clone()
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or_default())
+    }
+
+    /// GET `uri`, asserting a 200 and returning the JSON body
+    pub async fn get_ok(&self, uri: &str) -> serde_json::Value {
+        let (status, body) = self.get(uri).await;
+        assert_eq!(status, StatusCode::OK, "GET {} returned {}: {}", uri, status, body);
+        body
+    }
+}
+
+/// Start an empty ClickHouse, returning the container and its HTTP URL
+async fn start_clickhouse() -> (ContainerAsync<ClickHouse>, String) {
+    let container = ClickHouse::default()
+        .start()
+        .await
+        .expect("Failed to start ClickHouse container (is Docker running?)");
+    let host = container.get_host().await.expect("container host");
+    let port = container
+        .get_host_port_ipv4(8123.tcp())
+        .await
+        .expect("ClickHouse HTTP port");
+    let url = format!("http://{}:{}", host, port);
+    (container, url)
+}
+
+async fn insert_rows(sql: &SqlClient, table: &str, rows: &str) {
+    if rows.trim().is_empty() {
+        return;
+    }
+    sql.execute_statement(&format!("INSERT INTO {} FORMAT JSONEachRow\n{}", table, rows))
+        .await
+        .unwrap_or_else(|e| panic!("Failed to load {} fixtures: {:#}", table, e));
+}
@@ -0,0 +1,224 @@
+//! Golden-response snapshots of the API contract
+//!
+//! Each endpoint's response over the fixture database is recorded as
+//! canonical JSON (sorted keys, volatile fields redacted) in
+//! `src/test_support/snapshots/<name>.json`. Tests compare the *shape* of a
+//! new response with the recorded one: renamed, removed or added fields and
+//! type changes fail, while value changes from fixture edits do not.
+//!
+//! Missing snapshots are recorded on first run, except under `CI`, where they
+//! fail. Set `UPDATE_SNAPSHOTS=1` to re-record after an intended change and
+//! review the diff like any other contract change to the frontend types.
+//!
+//! Recording needs the ClickHouse container, but the shipped `serve
+//! --fixtures` responses are checked against the committed snapshots on every
+//! `cargo test`, so a contract change also has to update those fixtures.
+
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+
+/// Fields whose values change from run to run
+const REDACTED_FIELDS: &[&str] = &["time", "generated_at", "last_ingest", "git_sha"];
+
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/test_support/snapshots")
+        .join(format!("{}.json", name))
+}
+
+/// Replace volatile values so recorded snapshots are stable
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if REDACTED_FIELDS.contains(&key.as_str()) && !v.is_null() {
+                    *v = Value::String("[redacted]".to_string());
+                } else {
+                    redact(v);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Structural type of a JSON value; array elements are merged into one shape
+fn shape(value: &Value) -> Value {
+    match value {
+        Value::Null => Value::from("null"),
+        Value::Bool(_) => Value::from("boolean"),
+        Value::Number(_) => Value::from("number"),
+        Value::String(_) => Value::from("string"),
+        Value::Array(items) => {
+            let merged = items.iter().map(shape).reduce(|a, b| merge(&a, &b));
+            Value::Array(merged.into_iter().collect())
+        }
+        Value::Object(map) => Value::Object(map.iter().map(|(k, v)| (k.clone(), shape(v))).collect()),
+    }
+}
+
+/// Union of two shapes; fields missing from one side become "|missing"
+fn merge(a: &Value, b: &Value) -> Value {
+    match (a, b) {
+        _ if a == b => a.clone(),
+        (Value::Object(a), Value::Object(b)) => {
+            let mut merged = Map::new();
+            for key in a.keys().chain(b.keys()) {
+                let missing = Value::from("missing");
+                let left = a.get(key).unwrap_or(&missing);
+                let right = b.get(key).unwrap_or(&missing);
+                merged.insert(key.clone(), merge(left, right));
+            }
+            Value::Object(merged)
+        }
+        (Value::Array(a), Value::Array(b)) => match (a.first(), b.first()) {
+            (Some(x), Some(y)) => Value::Array(vec![merge(x, y)]),
+            (Some(x), None) | (None, Some(x)) => Value::Array(vec![x.clone()]),
+            (None, None) => Value::Array(Vec::new()),
+        },
+        _ => {
+            let mut types: Vec<String> = [a, b]
+                .iter()
+                .flat_map(|v| match v {
+                    Value::String(s) => s.split('|').map(str::to_string).collect(),
+                    other => vec![other.to_string()],
+                })
+                .collect();
+            types.sort();
+            types.dedup();
+            Value::from(types.join("|"))
+        }
+    }
+}
+
+/// Contract differences between a recorded and a new shape
+fn shape_diff(path: &str, old: &Value, new: &Value, out: &mut Vec<String>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_shape) in old {
+                let field = format!("{}.{}", path, key);
+                match new.get(key) {
+                    Some(new_shape) => shape_diff(&field, old_shape, new_shape, out),
+                    None => out.push(format!("{}: removed", field)),
+                }
+            }
+            for key in new.keys().filter(|k| !old.contains_key(*k)) {
+                out.push(format!("{}.{}: added", path, key));
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            // An empty array on either side carries no element shape
+            if let (Some(old), Some(new)) = (old.first(), new.first()) {
+                shape_diff(&format!("{}[]", path), old, new, out);
+            }
+        }
+        _ if old == new => {}
+        _ => out.push(format!("{}: {} -> {}", path, old, new)),
+    }
+}
+
+/// Compare `response` with the recorded snapshot `name`, recording it when
+/// missing (outside CI) or when `UPDATE_SNAPSHOTS` is set
+pub fn check_snapshot(name: &str, response: &Value) -> Result<(), String> {
+    let mut canonical = response.clone();
+    redact(&mut canonical);
+    let path = snapshot_path(name);
+
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let recorded = match std::fs::read_to_string(&path) {
+        Ok(contents) if !update => contents,
+        Err(_) if !update && std::env::var_os("CI").is_some() => {
+            return Err(format!(
+                "{}: no snapshot at {}; record it with UPDATE_SNAPSHOTS=1",
+                name,
+                path.display()
+            ));
+        }
+        _ => {
+            let json = serde_json::to_string_pretty(&canonical).unwrap();
+            std::fs::write(&path, json + "\n")
+                .map_err(|e| format!("{}: failed to write {}: {}", name, path.display(), e))?;
+            return Ok(());
+        }
+    };
+
+    compare(name, &path, &recorded, &canonical)
+}
+
+/// Compare `response` with the recorded snapshot `name` without recording
+pub fn matches_snapshot(name: &str, response: &Value) -> Result<(), String> {
+    let mut canonical = response.clone();
+    redact(&mut canonical);
+    let path = snapshot_path(name);
+    let recorded = std::fs::read_to_string(&path)
+        .map_err(|e| format!("{}: no snapshot at {}: {}", name, path.display(), e))?;
+    compare(name, &path, &recorded, &canonical)
+}
+
+fn compare(name: &str, path: &std::path::Path, recorded: &str, canonical: &Value) -> Result<(), String> {
+    let recorded: Value = serde_json::from_str(recorded)
+        .map_err(|e| format!("{}: unreadable snapshot {}: {}", name, path.display(), e))?;
+    let mut changes = Vec::new();
+    shape_diff("$", &shape(&recorded), &shape(canonical), &mut changes);
+    if changes.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{}: response shape changed\n  {}", name, changes.join("\n  ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_shape_merges_array_elements() {
+        let value = json!([
+            {"gene_id": "ENSG1", "pvalue": 1e-8, "beta": null},
+            {"gene_id": "ENSG2", "pvalue": 0.2, "beta": 0.1, "flag": true}
+        ]);
+        assert_eq!(
+            shape(&value),
+            json!([{
+                "gene_id": "string",
+                "pvalue": "number",
+                "beta": "null|number",
+                "flag": "boolean|missing"
+            }])
+        );
+    }
+
+    #[test]
+    fn test_shape_diff() {
+        let old = shape(&json!({"data": [{"pvalue": 0.1, "ref": "A"}], "count": 1, "time": 0.2}));
+        let new = shape(&json!({"data": [{"pvalue": "0.1", "ref_allele": "A"}], "count": 1}));
+        let mut changes = Vec::new();
+        shape_diff("$", &old, &new, &mut changes);
+        changes.sort();
+        assert_eq!(
+            changes,
+            vec![
+                "$.data[].pvalue: \"number\" -> \"string\"",
+                "$.data[].ref: removed",
+                "$.data[].ref_allele: added",
+                "$.time: removed",
+            ]
+        );
+
+        let mut none = Vec::new();
+        shape_diff("$", &old, &shape(&json!({"data": [], "count": 0, "time": 1.0})), &mut none);
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn test_redact() {
+        let mut value = json!({"data": [], "time": 0.013, "build": {"git_sha": "abc"}, "last_ingest": null});
+        redact(&mut value);
+        assert_eq!(
+            value,
+            json!({"data": [], "time": "[redacted]", "build": {"git_sha": "[redacted]"}, "last_ingest": null})
+        );
+    }
+}
@@ -6,20 +6,30 @@
 //! - Legacy: Single `variant_annotations` table
 //! - New: Separate `exome_annotations` and `genome_annotations` tables
 
-use crate::api::AppState;
+use crate::api::{effect_metadata, AppState};
 use crate::clickhouse::models::{
-    LocusVariantFullRow, LocusVariantFullRowWithStats, SignificantVariantRow,
+    AncestryFrequencyRow, LocusVariantFullRow, LocusVariantFullRowWithStats, SignificantVariantRow,
     VariantAnnotationExtendedRow, VariantAnnotationRow,
 };
-use crate::clickhouse::xpos::{compute_xpos, parse_interval_to_xpos, parse_variant_id};
+use crate::clickhouse::xpos::{make_variant_id_from_xpos, parse_variant_id};
+use crate::clickhouse::QueryExt;
 use crate::error::AppError;
+use crate::genomics::consequence::ConsequenceFilter;
+use crate::genomics::{resolve_region, Contig};
 use crate::models::{VariantAnnotationApi, VariantAssociationApi};
-use crate::response::{LookupResult, QueryTimer};
+use crate::response::{
+    CountResult, EffectScale, FieldSelection, FormatQuery, LookupResult, QueryTimer, ResponseFormat,
+};
+use crate::variants::vcf::{render_vcf, VcfAssociation, VCF_CONTENT_TYPE};
 use axum::{
     extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
     Json,
 };
+use clickhouse::Row;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 /// Sequencing type for selecting annotation table
@@ -31,6 +41,143 @@ pub enum SequencingTypeParam {
     Genome,
 }
 
+/// Columns of `exome_annotations` / `genome_annotations`, in
+/// `VariantAnnotationExtendedRow` order
+const EXTENDED_ANNOTATION_COLUMNS: &str = "xpos, contig, position, ref, alt, ac, af, an, hom, \
+    gene_id, gene_symbol, consequence, hgvsc, hgvsp, amino_acids, polyphen2, lof, \
+    cadd_phred, revel_max, spliceai_ds_max, alphamissense_score, alphamissense_class, filters";
+
+/// Per-ancestry columns of the same tables, in `AncestryFrequencyRow` order;
+/// only read for the single-variant endpoint
+const ANCESTRY_FREQUENCY_COLUMNS: &str =
+    "ac_afr, af_afr, an_afr, ac_amr, af_amr, an_amr, ac_eas, af_eas, an_eas, \
+    ac_eur, af_eur, an_eur, ac_mid, af_mid, an_mid, ac_sas, af_sas, an_sas";
+
+/// `consequence=` / `max_af=` filters pushed into annotation queries
+#[derive(Debug, Clone, Default)]
+pub struct AnnotationFilters {
+    consequence: Option<ConsequenceFilter>,
+    max_af: Option<f64>,
+}
+
+impl AnnotationFilters {
+    pub fn parse(consequence: Option<&str>, max_af: Option<f64>) -> Result<Self, AppError> {
+        Ok(Self {
+            consequence: ConsequenceFilter::parse(consequence)?,
+            max_af,
+        })
+    }
+
+    /// " AND ..." conditions to append to a WHERE clause; `af_column` is `af`
+    /// in the extended tables and `af_all` in `variant_annotations`
+    pub fn sql(&self, af_column: &str) -> String {
+        let mut sql = String::new();
+        if let Some(consequence) = &self.consequence {
+            sql.push_str(" AND ");
+            sql.push_str(&consequence.sql("consequence"));
+        }
+        if self.max_af.is_some() {
+            sql.push_str(&format!(" AND {} <= ?", af_column));
+        }
+        sql
+    }
+
+    /// Bind the parameters of [`Self::sql`], after the preceding ones
+    pub fn bind(&self, mut query: clickhouse::query::Query) -> clickhouse::query::Query {
+        if let Some(consequence) = &self.consequence {
+            query = query.bind(consequence.terms());
+        }
+        if let Some(max_af) = self.max_af {
+            query = query.bind(max_af);
+        }
+        query
+    }
+}
+
+/// In-silico predictor scores selected with `fields=`
+///
+/// Predictor names are `cadd`, `revel`, `spliceai`, `alphamissense` (or their
+/// column names), or `predictors` for all four; unselected scores are omitted.
+/// Any other names form a [`FieldSelection`] projecting each row, so
+/// `fields=variant_id,consequence,cadd` returns just those three keys.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PredictorFields {
+    pub cadd: bool,
+    pub revel: bool,
+    pub spliceai: bool,
+    pub alphamissense: bool,
+}
+
+impl PredictorFields {
+    /// Split a `fields` parameter into predictor scores and a row projection
+    pub fn parse(fields: Option<&str>) -> (Self, FieldSelection) {
+        let mut selected = Self::default();
+        let mut projection = FieldSelection::default();
+        for field in fields.unwrap_or("").split(',').map(str::trim) {
+            match field.to_ascii_lowercase().as_str() {
+                "" => {}
+                "cadd" | "cadd_phred" => selected.cadd = true,
+                "revel" | "revel_max" => selected.revel = true,
+                "spliceai" | "spliceai_ds_max" => selected.spliceai = true,
+                "alphamissense" | "alphamissense_score" | "alphamissense_class" => {
+                    selected.alphamissense = true
+                }
+                "predictors" => {
+                    selected = Self {
+                        cadd: true,
+                        revel: true,
+                        spliceai: true,
+                        alphamissense: true,
+                    }
+                }
+                _ => projection.push(field),
+            }
+        }
+        if !projection.is_all() {
+            for column in selected.columns() {
+                projection.push(column);
+            }
+        }
+        (selected, projection)
+    }
+
+    /// Response keys of the selected scores
+    fn columns(&self) -> Vec<&'static str> {
+        let mut columns = Vec::new();
+        if self.cadd {
+            columns.push("cadd_phred");
+        }
+        if self.revel {
+            columns.push("revel_max");
+        }
+        if self.spliceai {
+            columns.push("spliceai_ds_max");
+        }
+        if self.alphamissense {
+            columns.extend(["alphamissense_score", "alphamissense_class"]);
+        }
+        columns
+    }
+
+    /// Clear the scores that were not requested
+    pub fn select(&self, mut api: VariantAnnotationApi) -> VariantAnnotationApi {
+        if !self.cadd {
+            api.cadd_phred = None;
+        }
+        if !self.revel {
+            api.revel_max = None;
+        }
+        if !self.spliceai {
+            api.spliceai_ds_max = None;
+        }
+        if !self.alphamissense {
+            api.alphamissense_score = None;
+            api.alphamissense_class = None;
+        }
+        api
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct VariantSearchQuery {
     pub q: String,
@@ -83,7 +230,12 @@ pub struct VariantSearchResultApi {
 impl VariantSearchResultRow {
     pub fn to_api(&self) -> VariantSearchResultApi {
         VariantSearchResultApi {
-            variant_id: crate::clickhouse::xpos::make_variant_id(&self.contig, self.position, &self.ref_allele, &self.alt),
+            variant_id: crate::clickhouse::xpos::make_variant_id(
+                &self.contig,
+                self.position,
+                &self.ref_allele,
+                &self.alt,
+            ),
             gene_id: self.gene_id.clone(),
             gene_symbol: self.gene_symbol.clone(),
             consequence: self.consequence.clone(),
@@ -97,7 +249,12 @@ impl VariantSearchResultRow {
 impl TopVariantSearchRow {
     pub fn to_api(&self) -> VariantSearchResultApi {
         VariantSearchResultApi {
-            variant_id: crate::clickhouse::xpos::make_variant_id(&self.contig, self.position as u32, &self.ref_allele, &self.alt),
+            variant_id: crate::clickhouse::xpos::make_variant_id(
+                &self.contig,
+                self.position as u32,
+                &self.ref_allele,
+                &self.alt,
+            ),
             gene_id: self.gene_id.clone(),
             gene_symbol: self.gene_symbol.clone(),
             consequence: self.consequence.clone(),
@@ -128,14 +285,15 @@ pub async fn search_variants(
         return Ok(Json(vec![]));
     };
 
-    // Both contig formats: "chr19" and "19" (tables may differ)
-    let raw_contig = parsed.contig.trim_start_matches("chr");
-    let chr_contig = format!("chr{}", raw_contig);
+    // Annotation tables store GRCh38 names ("chr19", "chrX")
+    let Some(contig) = Contig::parse(&parsed.contig) else {
+        return Ok(Json(vec![]));
+    };
     let pos_pattern = format!("{}%", parsed.position_prefix);
 
     // Build WHERE clause using contig + position prefix (LIKE-based)
     let mut conditions = vec![
-        "contig IN (?, ?)".to_string(),
+        "contig = ?".to_string(),
         "toString(position) LIKE ?".to_string(),
     ];
     if !parsed.ref_allele.is_empty() {
@@ -147,16 +305,29 @@ pub async fn search_variants(
     let where_clause = conditions.join(" AND ");
 
     let bind_common = |q: &str| -> clickhouse::query::Query {
-        let mut query = state.clickhouse.query(q)
-            .bind(raw_contig).bind(&chr_contig).bind(&pos_pattern);
-        if !parsed.ref_allele.is_empty() { query = query.bind(format!("{}%", parsed.ref_allele)); }
-        if !parsed.alt_allele.is_empty() { query = query.bind(format!("{}%", parsed.alt_allele)); }
+        let mut query = state
+            .clickhouse
+            .query(q)
+            .bind(contig.chr())
+            .bind(&pos_pattern);
+        if !parsed.ref_allele.is_empty() {
+            query = query.bind(format!("{}%", parsed.ref_allele));
+        }
+        if !parsed.alt_allele.is_empty() {
+            query = query.bind(format!("{}%", parsed.alt_allele));
+        }
         query
     };
 
     let annotation_cols = "xpos, contig, position, ref, alt, gene_id, gene_symbol, consequence";
-    let query_exome = format!("SELECT {} FROM exome_annotations WHERE {} LIMIT 15", annotation_cols, where_clause);
-    let query_genome = format!("SELECT {} FROM genome_annotations WHERE {} LIMIT 15", annotation_cols, where_clause);
+    let query_exome = format!(
+        "SELECT {} FROM exome_annotations WHERE {} LIMIT 15",
+        annotation_cols, where_clause
+    );
+    let query_genome = format!(
+        "SELECT {} FROM genome_annotations WHERE {} LIMIT 15",
+        annotation_cols, where_clause
+    );
 
     let exome_q = bind_common(&query_exome);
     let genome_q = bind_common(&query_genome);
@@ -171,9 +342,9 @@ pub async fn search_variants(
     let top_q = bind_common(&query_top);
 
     let (exome_res, genome_res, top_res) = tokio::join!(
-        exome_q.fetch_all::<VariantSearchResultRow>(),
-        genome_q.fetch_all::<VariantSearchResultRow>(),
-        top_q.fetch_all::<TopVariantSearchRow>()
+        exome_q.fetch_all_with::<VariantSearchResultRow>(&state.executor),
+        genome_q.fetch_all_with::<VariantSearchResultRow>(&state.executor),
+        top_q.fetch_all_with::<TopVariantSearchRow>(&state.executor)
     );
 
     // Start with ALL top_variants results (already sorted by num_associations desc)
@@ -191,8 +362,12 @@ pub async fn search_variants(
     // Then add annotation-only results, enriching with any top_variant data
     // These fill remaining slots with variants that aren't significant but match the position
     let mut annotation_rows = Vec::new();
-    if let Ok(rows) = exome_res { annotation_rows.extend(rows); }
-    if let Ok(rows) = genome_res { annotation_rows.extend(rows); }
+    if let Ok(rows) = exome_res {
+        annotation_rows.extend(rows);
+    }
+    if let Ok(rows) = genome_res {
+        annotation_rows.extend(rows);
+    }
     for row in annotation_rows {
         let api_row = row.to_api();
         if seen.insert(api_row.variant_id.clone()) {
@@ -201,13 +376,11 @@ pub async fn search_variants(
     }
 
     // Sort: variants with associations bubble up, then by num_associations desc
-    api_rows.sort_by(|a, b| {
-        match (&a.num_associations, &b.num_associations) {
-            (Some(an), Some(bn)) => bn.cmp(an),
-            (Some(_), None) => std::cmp::Ordering::Less,
-            (None, Some(_)) => std::cmp::Ordering::Greater,
-            (None, None) => std::cmp::Ordering::Equal,
-        }
+    api_rows.sort_by(|a, b| match (&a.num_associations, &b.num_associations) {
+        (Some(an), Some(bn)) => bn.cmp(an),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
     });
 
     api_rows.truncate(15);
@@ -222,22 +395,35 @@ pub struct SingleAnnotationQuery {
 
     /// Use extended schema (new tables with full VEP annotations)
     pub extended: Option<bool>,
+
+    /// Build of the variant ID (default GRCh38; "GRCh37"/"hg19" lifts first)
+    pub build: Option<String>,
+
+    /// Predictor scores (extended only) and response keys, see [`PredictorFields`]
+    pub fields: Option<String>,
 }
 
 /// GET /api/variants/annotations/:variant_id
 ///
-/// Returns annotation data for a single variant by ID.
+/// Returns annotation data for a single variant by ID; extended results also
+/// carry `ancestry_frequencies` (AC/AN/AF for afr, amr, eas, eur, mid, sas).
 /// Variant ID format: "chr1-12345-A-T" or "1-12345-A-T"
 ///
 /// Query parameters:
 /// - `sequencing_type`: "exome" or "genome" (when using extended, defaults to checking both)
 /// - `extended`: Use new extended tables (default: false)
+/// - `fields`: Predictor scores and/or response keys, e.g. "predictors" or "variant_id,cadd"
+/// - `build`: Build of the variant ID (default: GRCh38)
 pub async fn get_annotation_by_id(
     State(state): State<Arc<AppState>>,
     Path(variant_id): Path<String>,
     Query(params): Query<SingleAnnotationQuery>,
-) -> Result<Json<Option<VariantAnnotationApi>>, AppError> {
+) -> Result<Json<Option<serde_json::Value>>, AppError> {
+    let variant_id = state
+        .liftover
+        .to_grch38_variant_id(&variant_id, params.build.as_deref())?;
     let (xpos, ref_allele, alt_allele) = parse_variant_id(&variant_id)?;
+    let (predictors, projection) = PredictorFields::parse(params.fields.as_deref());
     let use_extended = params.extended.unwrap_or(false);
 
     if use_extended {
@@ -252,12 +438,13 @@ pub async fn get_annotation_by_id(
         for table in tables {
             let query = format!(
                 r#"
-                SELECT xpos, contig, position, ref, alt, ac, af, an, hom, gene_id, gene_symbol, consequence, hgvsc, hgvsp, amino_acids, polyphen2, lof, filters
-                FROM {}
+                SELECT {columns}
+                FROM {table}
                 WHERE xpos = ? AND ref = ? AND alt = ?
                 LIMIT 1
                 "#,
-                table
+                columns = EXTENDED_ANNOTATION_COLUMNS,
+                table = table
             );
 
             let row = state
@@ -266,14 +453,25 @@ pub async fn get_annotation_by_id(
                 .bind(xpos)
                 .bind(&ref_allele)
                 .bind(&alt_allele)
-                .fetch_optional::<VariantAnnotationExtendedRow>()
-                .await
-                .map_err(|e| {
-                    AppError::DataTransformError(format!("ClickHouse query error: {}", e))
-                })?;
+                .fetch_optional_with::<VariantAnnotationExtendedRow>(&state.executor)
+                .await?;
 
             if let Some(r) = row {
-                return Ok(Json(Some(r.to_api())));
+                let mut api = predictors.select(r.to_api());
+                let frequencies_query = format!(
+                    "SELECT {} FROM {} WHERE xpos = ? AND ref = ? AND alt = ? LIMIT 1",
+                    ANCESTRY_FREQUENCY_COLUMNS, table
+                );
+                api.ancestry_frequencies = state
+                    .clickhouse
+                    .query(&frequencies_query)
+                    .bind(xpos)
+                    .bind(&ref_allele)
+                    .bind(&alt_allele)
+                    .fetch_optional_with::<AncestryFrequencyRow>(&state.executor)
+                    .await?
+                    .map(|f| f.ancestry_frequencies());
+                return Ok(Json(Some(projection.project(&api)?)));
             }
         }
 
@@ -293,11 +491,12 @@ pub async fn get_annotation_by_id(
             .bind(xpos)
             .bind(&ref_allele)
             .bind(&alt_allele)
-            .fetch_optional::<VariantAnnotationRow>()
-            .await
-            .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+            .fetch_optional_with::<VariantAnnotationRow>(&state.executor)
+            .await?;
 
-        Ok(Json(row.map(|r| r.to_api())))
+        row.map(|r| projection.project(&r.to_api()))
+            .transpose()
+            .map(Json)
     }
 }
 
@@ -316,27 +515,128 @@ pub struct AnnotationQuery {
     /// When false (default), queries legacy variant_annotations
     pub extended: Option<bool>,
 
+    /// Predictor scores (extended only) and response keys, see [`PredictorFields`]
+    pub fields: Option<String>,
+
+    /// Consequence classes (lof, missense, synonymous, splice, other) and/or VEP terms
+    pub consequence: Option<String>,
+
+    /// Maximum allele frequency
+    pub max_af: Option<f64>,
+
     /// Query mode (fast/slow) - accepted but currently ignored
     #[serde(default)]
     pub query_mode: Option<String>,
+
+    /// Analysis whose association stats are added to `format=vcf` output
+    pub analysis_id: Option<String>,
+
+    /// Ancestry of those association stats (default: "meta")
+    pub ancestry: Option<String>,
+}
+
+/// Association stats joined into VCF exports
+#[derive(Debug, Clone, Deserialize, Row)]
+struct VcfAssociationRow {
+    xpos: i64,
+    #[serde(rename = "ref")]
+    ref_allele: String,
+    alt: String,
+    pvalue: f64,
+    beta: Option<f64>,
+    se: Option<f64>,
+}
+
+/// `format=vcf` response for an interval, with the stats of `analysis_id=`
+/// from `loci_variants` when given
+async fn annotations_vcf(
+    state: &AppState,
+    interval: &str,
+    params: &AnnotationQuery,
+    rows: &[VariantAnnotationApi],
+    (xpos_start, xpos_end): (i64, i64),
+) -> Result<Response, AppError> {
+    let mut associations = HashMap::new();
+    if let Some(analysis_id) = &params.analysis_id {
+        let sequencing_type = match params.sequencing_type.unwrap_or_default() {
+            SequencingTypeParam::Exome => "exome",
+            SequencingTypeParam::Genome => "genome",
+        };
+        let query = r#"
+            SELECT xpos, ref, alt, pvalue, beta, se
+            FROM loci_variants
+            WHERE phenotype = ? AND ancestry = ? AND sequencing_type = ?
+              AND xpos >= ? AND xpos <= ?
+        "#;
+        let stats = state
+            .clickhouse
+            .query(query)
+            .bind(analysis_id)
+            .bind(params.ancestry.as_deref().unwrap_or("meta"))
+            .bind(sequencing_type)
+            .bind(xpos_start)
+            .bind(xpos_end)
+            .fetch_all_with::<VcfAssociationRow>(&state.executor)
+            .await?;
+        for row in stats {
+            associations.insert(
+                make_variant_id_from_xpos(row.xpos, &row.ref_allele, &row.alt),
+                VcfAssociation {
+                    pvalue: row.pvalue,
+                    beta: row.beta,
+                    se: row.se,
+                },
+            );
+        }
+    }
+
+    let body = render_vcf(rows, params.analysis_id.as_deref(), &associations);
+    let filename: String = interval
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    Ok((
+        [
+            (header::CONTENT_TYPE, VCF_CONTENT_TYPE.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"{}.vcf\"", filename),
+            ),
+        ],
+        body,
+    )
+        .into_response())
 }
 
 /// GET /api/variants/annotations/interval/:interval
 ///
 /// Returns all variant annotations within a genomic interval.
-/// Interval format: "chr1:12345-67890" or "1:12345-67890"
+/// Interval format: "chr1:12345-67890" or "1:12345-67890", or any form accepted by
+/// `genomics::parse_region` (gene symbol, ENSG ID, position +/- flank, chromosome)
 ///
 /// Query parameters:
 /// - `limit`: Maximum number of results (default: 1000)
 /// - `sequencing_type`: "exome" or "genome" (default: genome)
 /// - `extended`: Use new extended tables (default: false for backward compatibility)
+/// - `fields`: Predictor scores and/or response keys, e.g. "predictors" or "variant_id,cadd"
+/// - `consequence`: Classes and/or VEP terms, e.g. "lof,missense"
+/// - `max_af`: Maximum allele frequency
+/// - `format`: "vcf" for a sites-only VCF 4.2 (with `analysis_id`/`ancestry`
+///   adding PVAL/BETA/SE), or "arrow"/"parquet"
 pub async fn get_annotations_by_interval(
     State(state): State<Arc<AppState>>,
     Path(interval): Path<String>,
     Query(params): Query<AnnotationQuery>,
-) -> Result<Json<LookupResult<VariantAnnotationApi>>, AppError> {
+    Query(format): Query<FormatQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
     let timer = QueryTimer::start();
-    let (xpos_start, xpos_end) = parse_interval_to_xpos(&interval)?;
+    let format = ResponseFormat::negotiate(format.format, &headers);
+    let (xpos_start, xpos_end) = resolve_region(state.gene_models.as_ref(), &interval)
+        .await?
+        .xpos_range();
+    let (predictors, projection) = PredictorFields::parse(params.fields.as_deref());
+    let filters = AnnotationFilters::parse(params.consequence.as_deref(), params.max_af)?;
     let use_extended = params.extended.unwrap_or(false);
 
     let api_rows: Vec<VariantAnnotationApi> = if use_extended {
@@ -348,43 +648,109 @@ pub async fn get_annotations_by_interval(
 
         let query = format!(
             r#"
-            SELECT xpos, contig, position, ref, alt, ac, af, an, hom, gene_id, gene_symbol, consequence, hgvsc, hgvsp, amino_acids, polyphen2, lof, filters
-            FROM {}
-            WHERE xpos >= ? AND xpos <= ?
+            SELECT {columns}
+            FROM {table}
+            WHERE xpos >= ? AND xpos <= ?{filters}
             "#,
-            table
+            columns = EXTENDED_ANNOTATION_COLUMNS,
+            table = table,
+            filters = filters.sql("af")
         );
 
-        let rows = state
+        let query = state
             .clickhouse
             .query(&query)
             .bind(xpos_start)
-            .bind(xpos_end)
-            .fetch_all::<VariantAnnotationExtendedRow>()
-            .await
-            .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
-
-        rows.into_iter().map(|r| r.to_api()).collect()
+            .bind(xpos_end);
+        let rows = filters
+            .bind(query)
+            .fetch_all_with::<VariantAnnotationExtendedRow>(&state.executor)
+            .await?;
+
+        rows.into_iter()
+            .map(|r| predictors.select(r.to_api()))
+            .collect()
     } else {
         // Use legacy single table
-        let query = r#"
+        let query = format!(
+            r#"
             SELECT xpos, contig, position, ref, alt, gene_symbol, consequence, af_all
             FROM variant_annotations
-            WHERE xpos >= ? AND xpos <= ?
-        "#;
+            WHERE xpos >= ? AND xpos <= ?{}
+            "#,
+            filters.sql("af_all")
+        );
 
-        let rows = state
+        let query = state
             .clickhouse
-            .query(query)
+            .query(&query)
             .bind(xpos_start)
-            .bind(xpos_end)
-            .fetch_all::<VariantAnnotationRow>()
-            .await
-            .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+            .bind(xpos_end);
+        let rows = filters
+            .bind(query)
+            .fetch_all_with::<VariantAnnotationRow>(&state.executor)
+            .await?;
 
         rows.into_iter().map(|r| r.to_api()).collect()
     };
-    Ok(Json(LookupResult::new(api_rows, timer.elapsed())))
+    if format == ResponseFormat::Vcf {
+        return annotations_vcf(
+            &state,
+            &interval,
+            &params,
+            &api_rows,
+            (xpos_start, xpos_end),
+        )
+        .await;
+    }
+    let data = projection.project_all(&api_rows)?;
+    format.lookup_response(LookupResult::new(data, &timer))
+}
+
+/// GET /api/variants/annotations/interval/:interval/count
+///
+/// Number of annotations `/api/variants/annotations/interval/:interval` would
+/// return for the same `sequencing_type`/`extended`/`consequence`/`max_af`
+/// (ignoring `limit`), so the UI can decide whether to fetch the rows.
+pub async fn count_annotations_by_interval(
+    State(state): State<Arc<AppState>>,
+    Path(interval): Path<String>,
+    Query(params): Query<AnnotationQuery>,
+) -> Result<Json<CountResult>, AppError> {
+    let timer = QueryTimer::start();
+    let (xpos_start, xpos_end) = resolve_region(state.gene_models.as_ref(), &interval)
+        .await?
+        .xpos_range();
+    let filters = AnnotationFilters::parse(params.consequence.as_deref(), params.max_af)?;
+
+    let (table, af_column) = if params.extended.unwrap_or(false) {
+        match params.sequencing_type.unwrap_or_default() {
+            SequencingTypeParam::Exome => ("exome_annotations", "af"),
+            SequencingTypeParam::Genome => ("genome_annotations", "af"),
+        }
+    } else {
+        ("variant_annotations", "af_all")
+    };
+    let query = format!(
+        "SELECT count() FROM {} WHERE xpos >= ? AND xpos <= ?{}",
+        table,
+        filters.sql(af_column)
+    );
+
+    let query = state
+        .clickhouse
+        .query(&query)
+        .bind(xpos_start)
+        .bind(xpos_end);
+    let count = filters
+        .bind(query)
+        .fetch_one_with::<u64>(&state.executor)
+        .await?;
+
+    Ok(Json(CountResult {
+        count,
+        time: timer.elapsed(),
+    }))
 }
 
 /// Query parameters for gene annotation endpoint
@@ -396,6 +762,15 @@ pub struct GeneAnnotationQuery {
     /// Use extended schema (new tables with full VEP annotations)
     pub extended: Option<bool>,
 
+    /// Predictor scores (extended only) and response keys, see [`PredictorFields`]
+    pub fields: Option<String>,
+
+    /// Consequence classes (lof, missense, synonymous, splice, other) and/or VEP terms
+    pub consequence: Option<String>,
+
+    /// Maximum allele frequency
+    pub max_af: Option<f64>,
+
     /// Query mode (fast/slow) - accepted but currently ignored
     #[serde(default)]
     pub query_mode: Option<String>,
@@ -409,37 +784,41 @@ pub struct GeneAnnotationQuery {
 /// Query parameters:
 /// - `sequencing_type`: "exome" or "genome" (default: genome)
 /// - `extended`: Use new extended tables (default: false)
+/// - `fields`: Predictor scores and/or response keys, e.g. "predictors" or "variant_id,cadd"
+/// - `consequence`: Classes and/or VEP terms, e.g. "lof,missense"
+/// - `max_af`: Maximum allele frequency
 pub async fn get_annotations_by_gene(
     State(state): State<Arc<AppState>>,
     Path(gene_id): Path<String>,
     Query(params): Query<GeneAnnotationQuery>,
-) -> Result<Json<LookupResult<VariantAnnotationApi>>, AppError> {
+) -> Result<Json<LookupResult<serde_json::Value>>, AppError> {
     let timer = QueryTimer::start();
 
-    // Step 1: Get gene model from ClickHouse
-    let gene_models = crate::gene_models::GeneModelsClickHouse::new(state.clickhouse.clone());
-    let gene = gene_models.get_by_gene_id(&gene_id).await?;
+    // Step 1: Get gene model
+    let gene = state.gene_models.get_by_gene_id(&gene_id).await?;
 
     let Some(gene) = gene else {
-        return Ok(Json(LookupResult::new(vec![], timer.elapsed())));
+        return Ok(Json(LookupResult::new(vec![], &timer)));
     };
 
     // Step 2: Build query for exon ranges
     if gene.exons.is_empty() {
-        return Ok(Json(LookupResult::new(vec![], timer.elapsed())));
+        return Ok(Json(LookupResult::new(vec![], &timer)));
     }
 
-    let contig = gene.chrom.trim_start_matches("chr");
+    let contig = gene.chrom.parse::<Contig>()?;
 
     // Build OR clauses for each exon
     let mut conditions = Vec::new();
     for exon in &gene.exons {
-        let start_xpos = compute_xpos(contig, exon.start as u32);
-        let end_xpos = compute_xpos(contig, exon.stop as u32);
+        let start_xpos = contig.xpos(exon.start as u32);
+        let end_xpos = contig.xpos(exon.stop as u32);
         conditions.push(format!("(xpos >= {} AND xpos <= {})", start_xpos, end_xpos));
     }
 
     let where_clause = conditions.join(" OR ");
+    let (predictors, projection) = PredictorFields::parse(params.fields.as_deref());
+    let filters = AnnotationFilters::parse(params.consequence.as_deref(), params.max_af)?;
     let use_extended = params.extended.unwrap_or(false);
 
     let api_rows: Vec<VariantAnnotationApi> = if use_extended {
@@ -449,37 +828,280 @@ pub async fn get_annotations_by_gene(
         };
         let query = format!(
             r#"
-            SELECT xpos, contig, position, ref, alt, ac, af, an, hom, gene_id, gene_symbol, consequence, hgvsc, hgvsp, amino_acids, polyphen2, lof, filters
-            FROM {}
-            WHERE {}
+            SELECT {columns}
+            FROM {table}
+            WHERE ({where_clause}){filters}
             "#,
-            table, where_clause
+            columns = EXTENDED_ANNOTATION_COLUMNS,
+            table = table,
+            where_clause = where_clause,
+            filters = filters.sql("af")
         );
-        let rows = state
-            .clickhouse
-            .query(&query)
-            .fetch_all::<VariantAnnotationExtendedRow>()
-            .await
-            .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
-        rows.into_iter().map(|r| r.to_api()).collect()
+        let rows = filters
+            .bind(state.clickhouse.query(&query))
+            .fetch_all_with::<VariantAnnotationExtendedRow>(&state.executor)
+            .await?;
+        rows.into_iter()
+            .map(|r| predictors.select(r.to_api()))
+            .collect()
     } else {
         let query = format!(
             r#"
             SELECT xpos, contig, position, ref, alt, gene_symbol, consequence, af_all
             FROM variant_annotations
-            WHERE {}
+            WHERE ({}){}
             "#,
-            where_clause
+            where_clause,
+            filters.sql("af_all")
+        );
+        let rows = filters
+            .bind(state.clickhouse.query(&query))
+            .fetch_all_with::<VariantAnnotationRow>(&state.executor)
+            .await?;
+        rows.into_iter().map(|r| r.to_api()).collect()
+    };
+    let data = projection.project_all(&api_rows)?;
+    Ok(Json(LookupResult::new(data, &timer)))
+}
+
+/// Most variant IDs and rsIDs accepted by one batch request
+const MAX_BATCH_VARIANTS: usize = 1000;
+
+/// Body of POST /api/variants/annotations/batch
+#[derive(Debug, Deserialize)]
+pub struct BatchAnnotationRequest {
+    /// Variant IDs, e.g. "chr1-55051215-G-GA"
+    #[serde(default)]
+    pub variant_ids: Vec<String>,
+
+    /// Comma-separated rsIDs, e.g. "rs11591147,rs505151"; 1000 inputs at most
+    /// together with `variant_ids`
+    pub rsids: Option<String>,
+
+    /// Sequencing type (extended only); both tables, exome first, when omitted
+    pub sequencing_type: Option<SequencingTypeParam>,
+
+    /// Use extended schema (new tables with full VEP annotations)
+    pub extended: Option<bool>,
+
+    /// Predictor scores (extended only) and response keys, see [`PredictorFields`]
+    pub fields: Option<String>,
+
+    /// Build of the variant IDs (default GRCh38; "GRCh37"/"hg19" lifts first)
+    pub build: Option<String>,
+}
+
+/// Response for POST /api/variants/annotations/batch
+#[derive(Debug, serde::Serialize)]
+pub struct BatchAnnotationResponse {
+    /// Annotations in request order
+    pub data: Vec<serde_json::Value>,
+    /// Number of annotations returned
+    pub count: usize,
+    /// Inputs with no annotation, in request order. Includes variant IDs that
+    /// don't parse, GRCh37 IDs that don't lift over and unknown rsIDs.
+    pub not_found: Vec<String>,
+    pub storage_source: String,
+    /// Query execution time in seconds
+    pub time: f64,
+}
+
+type VariantKey = (i64, String, String);
+
+/// Split the comma-separated `rsids` of a batch request, lowercased
+fn parse_rsids(rsids: Option<&str>) -> Result<Vec<String>, AppError> {
+    let mut parsed = Vec::new();
+    for rsid in rsids.unwrap_or("").split(',').map(str::trim) {
+        if rsid.is_empty() {
+            continue;
+        }
+        let rsid = rsid.to_ascii_lowercase();
+        let valid = rsid
+            .strip_prefix("rs")
+            .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+        if !valid {
+            return Err(AppError::BadRequest(format!(
+                "Invalid rsID '{}'. Expected e.g. rs11591147",
+                rsid
+            )));
+        }
+        parsed.push(rsid);
+    }
+    Ok(parsed)
+}
+
+/// Variant of an rsID in an annotation table
+#[derive(Debug, Deserialize, Row)]
+struct RsidVariantRow {
+    rsid: String,
+    xpos: i64,
+    #[serde(rename = "ref")]
+    ref_allele: String,
+    alt: String,
+}
+
+/// Variants of each rsID in `tables`; an rsID can name several alleles
+async fn resolve_rsids(
+    state: &AppState,
+    tables: &[&str],
+    rsids: &[String],
+) -> Result<HashMap<String, Vec<VariantKey>>, AppError> {
+    let mut variants: HashMap<String, Vec<VariantKey>> = HashMap::new();
+    if rsids.is_empty() {
+        return Ok(variants);
+    }
+    for table in tables {
+        let sql = format!(
+            "SELECT arrayJoin(arrayIntersect(rsids, ?)) AS rsid, xpos, ref, alt \
+             FROM {} WHERE hasAny(rsids, ?)",
+            table
         );
         let rows = state
             .clickhouse
-            .query(&query)
-            .fetch_all::<VariantAnnotationRow>()
-            .await
-            .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
-        rows.into_iter().map(|r| r.to_api()).collect()
+            .query(&sql)
+            .bind(rsids)
+            .bind(rsids)
+            .fetch_all_with::<RsidVariantRow>(&state.executor)
+            .await?;
+        for row in rows {
+            let keys = variants.entry(row.rsid).or_default();
+            let key = (row.xpos, row.ref_allele, row.alt);
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+    for keys in variants.values_mut() {
+        keys.sort();
+    }
+    Ok(variants)
+}
+
+/// `SELECT ... WHERE (xpos, ref, alt) IN (...)` with one bound tuple per key
+fn batch_query(
+    state: &AppState,
+    columns: &str,
+    table: &str,
+    keys: &[&VariantKey],
+) -> clickhouse::query::Query {
+    let tuples = vec!["(?, ?, ?)"; keys.len()].join(", ");
+    let sql = format!(
+        "SELECT {} FROM {} WHERE (xpos, ref, alt) IN ({})",
+        columns, table, tuples
+    );
+    keys.iter().fold(
+        state.clickhouse.query(&sql),
+        |query, (xpos, ref_allele, alt)| query.bind(*xpos).bind(ref_allele).bind(alt),
+    )
+}
+
+/// POST /api/variants/annotations/batch
+///
+/// Annotates up to 1000 variants with one `IN` query per table instead of one
+/// request per variant. Variants are given as `variant_ids` and/or a
+/// comma-separated `rsids` list, which is resolved through the `rsids` column
+/// of the exome/genome annotation tables first. Accepts the `extended`,
+/// `sequencing_type`, `fields` and `build` options of the single-variant
+/// endpoint in the JSON body.
+pub async fn get_annotations_batch(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<BatchAnnotationRequest>,
+) -> Result<Json<BatchAnnotationResponse>, AppError> {
+    let timer = QueryTimer::start();
+    let rsids = parse_rsids(request.rsids.as_deref())?;
+    let inputs = request.variant_ids.len() + rsids.len();
+    if inputs == 0 {
+        return Err(AppError::BadRequest(
+            "variant_ids or rsids must not be empty".to_string(),
+        ));
+    }
+    if inputs > MAX_BATCH_VARIANTS {
+        return Err(AppError::BadRequest(format!(
+            "At most {} variant_ids and rsids per request, got {}",
+            MAX_BATCH_VARIANTS, inputs
+        )));
+    }
+    if let Some(build) = request.build.as_deref() {
+        crate::liftover::Build::parse(build)?;
+    }
+    let (predictors, projection) = PredictorFields::parse(request.fields.as_deref());
+
+    let tables = match request.sequencing_type {
+        Some(SequencingTypeParam::Exome) => vec!["exome_annotations"],
+        Some(SequencingTypeParam::Genome) => vec!["genome_annotations"],
+        None => vec!["exome_annotations", "genome_annotations"],
     };
-    Ok(Json(LookupResult::new(api_rows, timer.elapsed())))
+
+    // Inputs that don't parse (or lift) simply go unmatched
+    let mut resolved: Vec<(&str, Vec<VariantKey>)> = request
+        .variant_ids
+        .iter()
+        .map(|input| {
+            let key = state
+                .liftover
+                .to_grch38_variant_id(input.trim(), request.build.as_deref())
+                .and_then(|id| parse_variant_id(&id))
+                .ok();
+            (input.as_str(), key.into_iter().collect())
+        })
+        .collect();
+    let rsid_variants = resolve_rsids(&state, &tables, &rsids).await?;
+    for rsid in &rsids {
+        let keys = rsid_variants.get(rsid).cloned().unwrap_or_default();
+        resolved.push((rsid.as_str(), keys));
+    }
+    let mut pending: Vec<&VariantKey> = resolved.iter().flat_map(|(_, keys)| keys).collect();
+    pending.sort();
+    pending.dedup();
+
+    let mut found: HashMap<VariantKey, VariantAnnotationApi> = HashMap::new();
+    if request.extended.unwrap_or(false) {
+        for table in tables {
+            if pending.is_empty() {
+                break;
+            }
+            let rows = batch_query(&state, EXTENDED_ANNOTATION_COLUMNS, table, &pending)
+                .fetch_all_with::<VariantAnnotationExtendedRow>(&state.executor)
+                .await?;
+            for row in rows {
+                let key = (row.xpos, row.ref_allele.clone(), row.alt.clone());
+                found
+                    .entry(key)
+                    .or_insert_with(|| predictors.select(row.to_api()));
+            }
+            pending.retain(|key| !found.contains_key(*key));
+        }
+    } else if !pending.is_empty() {
+        let columns = "xpos, contig, position, ref, alt, gene_symbol, consequence, af_all";
+        let rows = batch_query(&state, columns, "variant_annotations", &pending)
+            .fetch_all_with::<VariantAnnotationRow>(&state.executor)
+            .await?;
+        for row in rows {
+            let key = (row.xpos, row.ref_allele.clone(), row.alt.clone());
+            found.entry(key).or_insert_with(|| row.to_api());
+        }
+    }
+
+    let mut data = Vec::new();
+    let mut not_found = Vec::new();
+    for (input, keys) in &resolved {
+        let apis: Vec<&VariantAnnotationApi> =
+            keys.iter().filter_map(|key| found.get(key)).collect();
+        if apis.is_empty() {
+            not_found.push(input.to_string());
+        }
+        for api in apis {
+            data.push(projection.project(api)?);
+        }
+    }
+
+    Ok(Json(BatchAnnotationResponse {
+        count: data.len(),
+        data,
+        not_found,
+        storage_source: "clickhouse".to_string(),
+        time: timer.elapsed(),
+    }))
 }
 
 // ============================================================================
@@ -503,18 +1125,30 @@ pub struct AssociationQuery {
     /// Query mode (fast/slow) - accepted but currently ignored
     #[serde(default)]
     pub query_mode: Option<String>,
+
+    /// Build of the variant ID for single-variant lookups (default GRCh38)
+    #[serde(default)]
+    pub build: Option<String>,
+
+    /// Effect scale: "beta" (default) or "or" for odds ratios of binary traits
+    #[serde(default)]
+    pub effect: Option<EffectScale>,
 }
 
 /// GET /api/variants/associations/variant/:variant_id
 ///
 /// Returns association stats for a specific variant in a specific phenotype.
 /// Only returns data if the variant is in the significant_variants table.
+/// `effect` in the envelope gives the unit of `beta` (see `effect=`).
 pub async fn get_association_by_variant(
     State(state): State<Arc<AppState>>,
     Path(variant_id): Path<String>,
     Query(params): Query<AssociationQuery>,
 ) -> Result<Json<LookupResult<VariantAssociationApi>>, AppError> {
     let timer = QueryTimer::start();
+    let variant_id = state
+        .liftover
+        .to_grch38_variant_id(&variant_id, params.build.as_deref())?;
     let (xpos, ref_allele, alt_allele) = parse_variant_id(&variant_id)?;
 
     let query = r#"
@@ -532,12 +1166,19 @@ pub async fn get_association_by_variant(
         .bind(xpos)
         .bind(&ref_allele)
         .bind(&alt_allele)
-        .fetch_optional::<SignificantVariantRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
-
-    let api_rows: Vec<VariantAssociationApi> = row.into_iter().map(|r| r.to_api()).collect();
-    Ok(Json(LookupResult::new(api_rows, timer.elapsed())))
+        .fetch_optional_with::<SignificantVariantRow>(&state.executor)
+        .await?;
+
+    let ancestry = row.as_ref().map_or("meta", |r| r.ancestry.as_str());
+    let effect = effect_metadata(
+        &state,
+        &params.analysis_id,
+        ancestry,
+        params.effect.unwrap_or_default(),
+    )
+    .await?;
+    let api_rows: Vec<VariantAssociationApi> = row.iter().map(|r| r.to_api()).collect();
+    Ok(Json(LookupResult::new(api_rows, &timer).with_effect(effect)))
 }
 
 /// GET /api/variants/associations/interval/:interval
@@ -547,12 +1188,19 @@ pub async fn get_association_by_variant(
 /// Query modes:
 /// - `fast` (default): Uses ClickHouse loci_variants table (pre-filtered data)
 /// - `slow`: Queries Hail Tables directly from GCS (complete per-phenotype data)
+///
+/// `format=arrow|parquet` (or the matching `Accept`) returns the rows as an
+/// Arrow IPC stream or Parquet file. With `effect=or`, betas of binary traits
+/// are odds ratios; the envelope's `effect` gives the unit either way.
 pub async fn get_associations_by_interval(
     State(state): State<Arc<AppState>>,
     Path(interval): Path<String>,
     Query(params): Query<AssociationQuery>,
-) -> Result<Json<LookupResult<VariantAssociationApi>>, AppError> {
+    Query(format): Query<FormatQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
     let timer = QueryTimer::start();
+    let format = ResponseFormat::negotiate(format.format, &headers);
 
     let ancestry = params.ancestry_group.as_deref().unwrap_or("meta");
     let sequencing_type = params.sequencing_type.as_deref().unwrap_or("genome");
@@ -563,10 +1211,17 @@ pub async fn get_associations_by_interval(
     } else {
         sequencing_type
     };
+    let effect = effect_metadata(
+        &state,
+        &params.analysis_id,
+        ancestry,
+        params.effect.unwrap_or_default(),
+    )
+    .await?;
 
     // Check for slow-path query mode (direct GCS Hail Table access)
     if params.query_mode.as_deref() == Some("slow") {
-        return get_associations_from_hail(
+        let Json(result) = get_associations_from_hail(
             &state,
             &interval,
             &params.analysis_id,
@@ -574,11 +1229,14 @@ pub async fn get_associations_by_interval(
             seq_type_normalized,
             timer,
         )
-        .await;
+        .await?;
+        return format.lookup_response(result.with_effect(effect));
     }
 
     // Fast path: ClickHouse query
-    let (xpos_start, xpos_end) = parse_interval_to_xpos(&interval)?;
+    let (xpos_start, xpos_end) = resolve_region(state.gene_models.as_ref(), &interval)
+        .await?
+        .xpos_range();
 
     let query = r#"
         SELECT phenotype, ancestry, sequencing_type, contig, xpos, position,
@@ -597,12 +1255,11 @@ pub async fn get_associations_by_interval(
         .bind(seq_type_normalized)
         .bind(xpos_start)
         .bind(xpos_end)
-        .fetch_all::<LocusVariantFullRowWithStats>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with::<LocusVariantFullRowWithStats>(&state.executor)
+        .await?;
 
     let api_rows: Vec<VariantAssociationApi> = rows.into_iter().map(|r| r.to_api()).collect();
-    Ok(Json(LookupResult::new(api_rows, timer.elapsed())))
+    format.lookup_response(LookupResult::new(api_rows, &timer).with_effect(effect))
 }
 
 /// Slow-path: Query Hail Table directly from GCS
@@ -616,23 +1273,22 @@ async fn get_associations_from_hail(
 ) -> Result<Json<LookupResult<VariantAssociationApi>>, AppError> {
     use crate::models::Locus;
 
-    // Parse interval (e.g., "chr1:12345-67890" or "1:12345-67890")
-    let (contig, start, end) = parse_interval(interval)?;
+    // Resolve interval (e.g., "chr1:12345-67890", "1:12345-67890" or a gene)
+    let region = resolve_region(state.gene_models.as_ref(), interval).await?;
+    let (contig, start, end) = (region.contig(), region.start as i32, region.stop as i32);
 
     // Build GCS path to the Hail Table
-    // Format: gs://aou_results/414k/ht_results/{ANCESTRY}/phenotype_{analysis_id}/{seq_type}_variant_results.ht
-    // Note: ancestry codes in GCS are uppercase (META, EUR, AFR, etc.)
-    let ht_path = format!(
-        "gs://aou_results/414k/ht_results/{}/phenotype_{}/{}_variant_results.ht",
-        ancestry.to_uppercase(), analysis_id, sequencing_type
-    );
+    // Format: gs://{bucket}/{results_prefix}/{ANCESTRY}/phenotype_{analysis_id}/{seq_type}_variant_results.ht
+    let ht_path = state
+        .config
+        .variant_results_uri(ancestry, analysis_id, sequencing_type);
 
     // Query the Hail Table
     let associations = state
         .hail_client
         .query_interval_typed(&ht_path, &contig, start, end)
         .await
-        .map_err(|e| AppError::DataTransformError(format!("Hail query error: {}", e)))?;
+        .map_err(|e| AppError::Internal(format!("Hail query error: {}", e)))?;
 
     // Convert to API format
     let api_rows: Vec<VariantAssociationApi> = associations
@@ -655,37 +1311,55 @@ async fn get_associations_from_hail(
         })
         .collect();
 
-    Ok(Json(LookupResult::with_source(api_rows, timer.elapsed(), "hail_gcs")))
+    Ok(Json(LookupResult::with_source(
+        api_rows,
+        &timer,
+        "hail_gcs",
+    )))
 }
 
-/// Parse interval string like "chr1:12345-67890" into (contig, start, end)
-/// For GRCh38 Hail Tables, contig format is "chr1", "chr2", etc.
-fn parse_interval(interval: &str) -> Result<(String, i32, i32), AppError> {
-    let parts: Vec<&str> = interval.split(':').collect();
-    if parts.len() != 2 {
-        return Err(AppError::InvalidInterval(format!(
-            "Invalid interval format: {}",
-            interval
-        )));
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Normalize to GRCh38 format: "chr1", "chr2", etc.
-    let raw_contig = parts[0].trim_start_matches("chr");
-    let contig = format!("chr{}", raw_contig);
-    let range_parts: Vec<&str> = parts[1].split('-').collect();
-    if range_parts.len() != 2 {
-        return Err(AppError::InvalidInterval(format!(
-            "Invalid interval range: {}",
-            interval
-        )));
+    #[test]
+    fn test_predictor_fields_parse() {
+        let (fields, projection) = PredictorFields::parse(None);
+        assert_eq!(fields, PredictorFields::default());
+        assert!(projection.is_all());
+
+        let (fields, projection) = PredictorFields::parse(Some("cadd, REVEL"));
+        assert!(fields.cadd && fields.revel);
+        assert!(!fields.spliceai && !fields.alphamissense);
+        assert!(projection.is_all());
+
+        let (all, _) = PredictorFields::parse(Some("predictors"));
+        assert!(all.cadd && all.revel && all.spliceai && all.alphamissense);
     }
 
-    let start: i32 = range_parts[0]
-        .parse()
-        .map_err(|_| AppError::InvalidInterval(format!("Invalid start position: {}", range_parts[0])))?;
-    let end: i32 = range_parts[1]
-        .parse()
-        .map_err(|_| AppError::InvalidInterval(format!("Invalid end position: {}", range_parts[1])))?;
+    #[test]
+    fn test_parse_rsids() {
+        assert!(parse_rsids(None).unwrap().is_empty());
+        assert_eq!(
+            parse_rsids(Some("rs11591147, RS505151,,")).unwrap(),
+            vec!["rs11591147", "rs505151"]
+        );
+        assert!(parse_rsids(Some("rs11591147,chr1-55039974-G-T")).is_err());
+        assert!(parse_rsids(Some("rs")).is_err());
+    }
 
-    Ok((contig, start, end))
+    #[test]
+    fn test_predictor_fields_with_projection() {
+        let (fields, projection) = PredictorFields::parse(Some("variant_id,spliceai"));
+        assert!(fields.spliceai && !fields.cadd);
+        let row = serde_json::json!({
+            "variant_id": "1-55051215-G-GA",
+            "consequence": "missense_variant",
+            "spliceai_ds_max": 0.5
+        });
+        assert_eq!(
+            projection.project(&row).unwrap(),
+            serde_json::json!({"variant_id": "1-55051215-G-GA", "spliceai_ds_max": 0.5})
+        );
+    }
 }
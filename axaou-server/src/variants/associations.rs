@@ -2,14 +2,17 @@
 //!
 //! Provides endpoints for gene-centric variant queries and Manhattan top-N.
 
-use crate::api::AppState;
+use crate::api::{effect_metadata, AppState};
+use crate::clickhouse::QueryExt;
 use crate::clickhouse::models::LocusVariantRow;
-use crate::clickhouse::xpos::compute_xpos;
 use crate::error::AppError;
+use crate::genomics::consequence::ConsequenceFilter;
+use crate::genomics::Contig;
 use crate::models::Locus;
-use crate::response::{LookupResult, QueryTimer};
+use crate::response::{EffectScale, EffectSize, LookupResult, QueryTimer, ResponseFormat};
 use axum::{
     extract::{Path, Query, State},
+    response::{IntoResponse, Response},
     Json,
 };
 use clickhouse::Row;
@@ -82,6 +85,12 @@ pub struct VariantAssociationExtendedApi {
     pub association_af: Option<f64>,
 }
 
+impl EffectSize for VariantAssociationExtendedApi {
+    fn beta_mut(&mut self) -> &mut f64 {
+        &mut self.beta
+    }
+}
+
 impl GeneVariantRow {
     pub fn to_api(&self) -> VariantAssociationExtendedApi {
         let variant_id = format!(
@@ -132,9 +141,16 @@ pub struct VariantGeneQuery {
     pub sequencing_type: Option<String>,
     /// Maximum number of results (default: 10000)
     pub limit: Option<u64>,
+    /// Consequence classes (lof, missense, synonymous, splice, other) and/or VEP terms
+    pub consequence: Option<String>,
+    /// Maximum allele frequency
+    pub max_af: Option<f64>,
     /// Query mode (fast/slow) - accepted but currently ignored
     #[serde(default)]
     pub query_mode: Option<String>,
+    /// Effect scale: "beta" (default) or "or" for odds ratios of binary traits
+    #[serde(default)]
+    pub effect: Option<EffectScale>,
 }
 
 /// GET /api/variants/associations/gene/:gene_id
@@ -145,6 +161,9 @@ pub struct VariantGeneQuery {
 /// 2. Queries ClickHouse for variants in that region from significant_variants_enriched
 ///
 /// The gene_id can be either an Ensembl ID (ENSG...) or a gene symbol.
+/// `consequence=` and `max_af=` filter in ClickHouse; the slow path has no
+/// annotations, so it only supports `max_af=`. `effect=or` reports odds
+/// ratios for binary traits, with the unit in the envelope's `effect`.
 pub async fn get_variants_by_gene(
     State(state): State<Arc<AppState>>,
     Path(gene_id): Path<String>,
@@ -160,6 +179,14 @@ pub async fn get_variants_by_gene(
         .sequencing_type
         .unwrap_or_else(|| "exomes".to_string());
     let limit = params.limit.unwrap_or(10000);
+    let consequence = ConsequenceFilter::parse(params.consequence.as_deref())?;
+    let effect = effect_metadata(
+        &state,
+        &params.analysis_id,
+        &ancestry,
+        params.effect.unwrap_or_default(),
+    )
+    .await?;
 
     // Step 1: Resolve gene to coordinates using ClickHouse gene_models table
     let gene_query = if gene_id.starts_with("ENSG") {
@@ -180,18 +207,16 @@ pub async fn get_variants_by_gene(
             .clickhouse
             .query(gene_query)
             .bind(&gene_id)
-            .fetch_optional()
-            .await
-            .map_err(|e| AppError::DataTransformError(format!("Gene lookup error: {}", e)))?
+            .fetch_optional_with(&state.executor)
+            .await?
     } else {
         state
             .clickhouse
             .query(gene_query)
             .bind(&gene_id)
             .bind(&gene_id.to_uppercase())
-            .fetch_optional()
-            .await
-            .map_err(|e| AppError::DataTransformError(format!("Gene lookup error: {}", e)))?
+            .fetch_optional_with(&state.executor)
+            .await?
     };
 
     let gene = gene_coords.ok_or(AppError::NotFound(format!("Gene {} not found", gene_id)))?;
@@ -200,12 +225,18 @@ pub async fn get_variants_by_gene(
     let buffer = 1000; // 1kb buffer
     let start_pos = (gene.start - buffer).max(0);
     let stop_pos = gene.stop + buffer;
-    let xstart = compute_xpos(&gene.chrom, start_pos as u32);
-    let xstop = compute_xpos(&gene.chrom, stop_pos as u32);
+    let contig = gene.chrom.parse::<Contig>()?;
+    let xstart = contig.xpos(start_pos as u32);
+    let xstop = contig.xpos(stop_pos as u32);
 
     // Check for slow-path query mode (direct GCS Hail Table access)
     if params.query_mode.as_deref() == Some("slow") {
-        return get_gene_variants_from_hail(
+        if consequence.is_some() {
+            return Err(AppError::BadRequest(
+                "consequence filters are not supported with query_mode=slow".to_string(),
+            ));
+        }
+        let Json(result) = get_gene_variants_from_hail(
             &state,
             &gene.chrom,
             start_pos,
@@ -213,10 +244,12 @@ pub async fn get_variants_by_gene(
             &params.analysis_id,
             &ancestry,
             &sequencing_type,
+            params.max_af,
             limit,
             timer,
         )
-        .await;
+        .await?;
+        return Ok(Json(result.with_effect(effect)));
     }
 
     // Step 3: Query loci_variants joined with annotations
@@ -234,6 +267,14 @@ pub async fn get_variants_by_gene(
         &sequencing_type
     };
 
+    let mut filters = String::new();
+    if let Some(consequence) = &consequence {
+        filters.push_str(&format!("\n          AND {}", consequence.sql("ann.consequence")));
+    }
+    if params.max_af.is_some() {
+        filters.push_str("\n          AND coalesce(lv.af, ann.af) <= ?");
+    }
+
     let query = format!(
         r#"
         SELECT
@@ -269,28 +310,35 @@ pub async fn get_variants_by_gene(
           AND lv.sequencing_type = ?
           AND lv.xpos >= ?
           AND lv.xpos <= ?
-          AND (lv.association_ac IS NULL OR lv.association_ac >= 5)
+          AND (lv.association_ac IS NULL OR lv.association_ac >= 5){}
         ORDER BY lv.pvalue ASC
         LIMIT ?
         "#,
-        annotations_table
+        annotations_table,
+        filters
     );
 
-    let rows = state
+    let mut query = state
         .clickhouse
         .query(&query)
         .bind(&params.analysis_id)
         .bind(&ancestry)
         .bind(seq_type_normalized)
         .bind(xstart)
-        .bind(xstop)
+        .bind(xstop);
+    if let Some(consequence) = &consequence {
+        query = query.bind(consequence.terms());
+    }
+    if let Some(max_af) = params.max_af {
+        query = query.bind(max_af);
+    }
+    let rows = query
         .bind(limit)
-        .fetch_all::<GeneVariantRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with::<GeneVariantRow>(&state.executor)
+        .await?;
 
     let api_rows: Vec<VariantAssociationExtendedApi> = rows.into_iter().map(|r| r.to_api()).collect();
-    Ok(Json(LookupResult::new(api_rows, timer.elapsed())))
+    Ok(Json(LookupResult::new(api_rows, &timer).with_effect(effect)))
 }
 
 /// Query parameters for Manhattan top-N endpoint
@@ -305,17 +353,21 @@ pub struct ManhattanTopQuery {
     /// Query mode (fast/slow) - accepted but currently ignored
     #[serde(default)]
     pub query_mode: Option<String>,
+    /// Response layout: "rows" (default), "columnar", "arrow" or "parquet"
+    pub format: Option<ResponseFormat>,
 }
 
 /// GET /api/variants/associations/manhattan/:analysis_id/top
 ///
 /// Returns the top N variants by p-value for a phenotype, regardless of locus.
 /// Useful as a fallback or supplement to the locus-based Manhattan plot.
+/// Other `format`s (columnar, arrow, parquet) return the bare rows in that
+/// layout instead of a `LookupResult` envelope.
 pub async fn get_manhattan_top(
     State(state): State<Arc<AppState>>,
     Path(analysis_id): Path<String>,
     Query(params): Query<ManhattanTopQuery>,
-) -> Result<Json<LookupResult<LocusVariantRow>>, AppError> {
+) -> Result<Response, AppError> {
     let timer = QueryTimer::start();
     let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
     let sequencing_type = params.sequencing_type.unwrap_or_else(|| "genomes".to_string());
@@ -337,11 +389,13 @@ pub async fn get_manhattan_top(
         .bind(&ancestry)
         .bind(&sequencing_type)
         .bind(limit)
-        .fetch_all::<LocusVariantRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with::<LocusVariantRow>(&state.executor)
+        .await?;
 
-    Ok(Json(LookupResult::new(rows, timer.elapsed())))
+    match params.format.unwrap_or_default() {
+        ResponseFormat::Rows => Ok(Json(LookupResult::new(rows, &timer)).into_response()),
+        format => format.variant_response(rows),
+    }
 }
 
 /// Slow-path: Query Hail Table directly from GCS for gene variants
@@ -353,6 +407,7 @@ async fn get_gene_variants_from_hail(
     analysis_id: &str,
     ancestry: &str,
     sequencing_type: &str,
+    max_af: Option<f64>,
     limit: u64,
     timer: QueryTimer,
 ) -> Result<Json<LookupResult<VariantAssociationExtendedApi>>, AppError> {
@@ -364,32 +419,26 @@ async fn get_gene_variants_from_hail(
     };
 
     // Build GCS path to the Hail Table
-    // Format: gs://aou_results/414k/ht_results/{ANCESTRY}/phenotype_{analysis_id}/{seq_type}_variant_results.ht
-    let ht_path = format!(
-        "gs://aou_results/414k/ht_results/{}/phenotype_{}/{}_variant_results.ht",
-        ancestry.to_uppercase(),
-        analysis_id,
-        seq_type_normalized
-    );
+    // Format: gs://{bucket}/{results_prefix}/{ANCESTRY}/phenotype_{analysis_id}/{seq_type}_variant_results.ht
+    let ht_path = state
+        .config
+        .variant_results_uri(ancestry, analysis_id, seq_type_normalized);
 
-    // Normalize contig to GRCh38 format (chr1, chr2, etc.)
-    let contig = if chrom.starts_with("chr") {
-        chrom.to_string()
-    } else {
-        format!("chr{}", chrom)
-    };
+    // Hail tables use GRCh38 names (chr1, chrX, ...)
+    let contig = chrom.parse::<Contig>()?.chr();
 
     // Query the Hail Table
     let associations = state
         .hail_client
-        .query_interval_typed(&ht_path, &contig, start, stop)
+        .query_interval_typed(&ht_path, contig, start, stop)
         .await
-        .map_err(|e| AppError::DataTransformError(format!("Hail query error: {}", e)))?;
+        .map_err(|e| AppError::Internal(format!("Hail query error: {}", e)))?;
 
     // Convert to API format (take up to limit), filtering AC >= 5
     let api_rows: Vec<VariantAssociationExtendedApi> = associations
         .into_iter()
         .filter(|a| a.ac.map_or(true, |ac| ac >= 5))
+        .filter(|a| max_af.map_or(true, |max| a.af.map_or(false, |af| af <= max)))
         .take(limit as usize)
         .map(|a| VariantAssociationExtendedApi {
             variant_id: a.variant_id(),
@@ -424,7 +473,7 @@ async fn get_gene_variants_from_hail(
 
     Ok(Json(LookupResult::with_source(
         api_rows,
-        timer.elapsed(),
+        &timer,
         "hail_gcs",
     )))
 }
@@ -0,0 +1,176 @@
+//! Forest plot data handler
+//!
+//! Returns per-ancestry effect estimates for a variant-phenotype pair together
+//! with a fixed-effect meta-analysis and heterogeneity statistics.
+
+use crate::api::AppState;
+use crate::clickhouse::QueryExt;
+use crate::clickhouse::xpos::parse_variant_id;
+use crate::error::AppError;
+use crate::variants::meta_analysis::{self, Effect, Heterogeneity, PooledEffect};
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Query parameters for the forest plot endpoint
+#[derive(Debug, Deserialize)]
+pub struct ForestQuery {
+    /// Analysis ID / phenotype (required)
+    pub analysis_id: String,
+    /// Sequencing type (exome/genome, default: genome)
+    pub sequencing_type: Option<String>,
+    /// Build of the variant ID (default GRCh38; "GRCh37"/"hg19" lifts first)
+    pub build: Option<String>,
+}
+
+/// Per-ancestry association stats from loci_variants
+#[derive(Debug, Clone, Deserialize, Row)]
+struct ForestRow {
+    pub ancestry: String,
+    pub pvalue: f64,
+    pub beta: Option<f64>,
+    pub se: Option<f64>,
+    pub af: Option<f64>,
+}
+
+/// One row of the forest plot
+#[derive(Debug, Serialize)]
+pub struct AncestryEffect {
+    pub ancestry: String,
+    pub beta: Option<f64>,
+    pub se: Option<f64>,
+    pub ci_lower: Option<f64>,
+    pub ci_upper: Option<f64>,
+    pub pvalue: f64,
+    pub af: Option<f64>,
+    /// Share of the inverse-variance weight in the pooled estimate
+    pub weight: Option<f64>,
+}
+
+/// Response for GET /api/variants/:variant_id/forest
+#[derive(Debug, Serialize)]
+pub struct ForestPlotResponse {
+    pub variant_id: String,
+    pub analysis_id: String,
+    pub sequencing_type: String,
+    /// Per-ancestry effects (excluding the precomputed "meta" row)
+    pub effects: Vec<AncestryEffect>,
+    /// The meta-analysis stored with the results, if present
+    pub reported_meta: Option<AncestryEffect>,
+    /// Fixed-effect estimate recomputed from `effects`
+    pub pooled: Option<PooledEffect>,
+    /// Cochran's Q and I² across `effects` (requires at least two ancestries)
+    pub heterogeneity: Option<Heterogeneity>,
+}
+
+impl AncestryEffect {
+    fn from_row(row: &ForestRow) -> Self {
+        let ci = match (row.beta, row.se) {
+            (Some(beta), Some(se)) if se > 0.0 => Some(meta_analysis::confidence_interval(beta, se)),
+            _ => None,
+        };
+        Self {
+            ancestry: row.ancestry.clone(),
+            beta: row.beta,
+            se: row.se,
+            ci_lower: ci.map(|c| c.0),
+            ci_upper: ci.map(|c| c.1),
+            pvalue: row.pvalue,
+            af: row.af,
+            weight: None,
+        }
+    }
+
+    fn effect(&self) -> Option<Effect> {
+        Some(Effect {
+            beta: self.beta?,
+            se: self.se?,
+        })
+    }
+}
+
+/// GET /api/variants/:variant_id/forest
+///
+/// Returns per-ancestry effects for one phenotype plus the pooled fixed-effect
+/// estimate, Cochran's Q and I². Only ancestries where the variant falls in a
+/// stored locus (`loci_variants`) contribute.
+pub async fn get_forest_plot(
+    State(state): State<Arc<AppState>>,
+    Path(variant_id): Path<String>,
+    Query(params): Query<ForestQuery>,
+) -> Result<Json<ForestPlotResponse>, AppError> {
+    let variant_id = state
+        .liftover
+        .to_grch38_variant_id(&variant_id, params.build.as_deref())?;
+    let (xpos, ref_allele, alt_allele) = parse_variant_id(&variant_id)?;
+
+    // loci_variants uses "exome"/"genome" (frontend may send the plural)
+    let sequencing_type = match params.sequencing_type.as_deref() {
+        Some("exome") | Some("exomes") => "exome",
+        _ => "genome",
+    };
+
+    let query = r#"
+        SELECT ancestry, pvalue, beta, se, af
+        FROM loci_variants
+        WHERE phenotype = ? AND sequencing_type = ? AND xpos = ? AND ref = ? AND alt = ?
+        ORDER BY ancestry, pvalue ASC
+        LIMIT 1 BY ancestry
+    "#;
+
+    let rows = state
+        .clickhouse
+        .query(query)
+        .bind(&params.analysis_id)
+        .bind(sequencing_type)
+        .bind(xpos)
+        .bind(&ref_allele)
+        .bind(&alt_allele)
+        .fetch_all_with::<ForestRow>(&state.executor)
+        .await?;
+
+    if rows.is_empty() {
+        return Err(AppError::NotFound(format!(
+            "No {} associations for variant {} in phenotype '{}'",
+            sequencing_type, variant_id, params.analysis_id
+        )));
+    }
+
+    let (meta_rows, ancestry_rows): (Vec<_>, Vec<_>) = rows
+        .iter()
+        .partition(|r| r.ancestry.eq_ignore_ascii_case("meta"));
+
+    let mut effects: Vec<AncestryEffect> = ancestry_rows.into_iter().map(AncestryEffect::from_row).collect();
+    let inputs: Vec<Effect> = effects.iter().filter_map(|e| e.effect()).collect();
+    let meta = meta_analysis::fixed_effect(&inputs);
+
+    // Relative weights for marker sizing
+    let total_weight: f64 = inputs.iter().filter_map(meta_analysis::weight).sum();
+    if total_weight > 0.0 {
+        for effect in &mut effects {
+            effect.weight = effect
+                .effect()
+                .and_then(|e| meta_analysis::weight(&e))
+                .map(|w| w / total_weight);
+        }
+    }
+
+    let (pooled, heterogeneity) = match meta {
+        Some(m) => (Some(m.pooled), m.heterogeneity),
+        None => (None, None),
+    };
+
+    Ok(Json(ForestPlotResponse {
+        variant_id,
+        analysis_id: params.analysis_id,
+        sequencing_type: sequencing_type.to_string(),
+        effects,
+        reported_meta: meta_rows.first().map(|r| AncestryEffect::from_row(r)),
+        pooled,
+        heterogeneity,
+    }))
+}
@@ -0,0 +1,109 @@
+//! LD proxy handler
+//!
+//! Looks up variants in linkage disequilibrium with a variant from the
+//! precomputed `ld_pairs` table (loaded by `ingest ld-pairs`). Pairs below the
+//! export threshold are absent, so a variant without proxies may simply have
+//! none above it.
+
+use crate::api::AppState;
+use crate::clickhouse::xpos::{make_variant_id_from_xpos, parse_variant_id};
+use crate::clickhouse::QueryExt;
+use crate::error::{AppError, ErrorResponse};
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+/// Query parameters for the LD proxy endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LdQuery {
+    /// Ancestry of the LD reference (default: "meta")
+    pub ancestry: Option<String>,
+    /// Minimum r² (default: 0.2)
+    pub r2: Option<f32>,
+    /// Maximum number of proxies (default: 500, max: 5000)
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Row)]
+struct LdPairRow {
+    xpos2: i64,
+    ref2: String,
+    alt2: String,
+    r2: f32,
+}
+
+/// Variant in LD with the queried variant
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LdProxy {
+    pub variant_id: String,
+    pub xpos: i64,
+    pub r2: f32,
+    /// Distance in bp from the queried variant (negative when upstream)
+    pub distance: i64,
+}
+
+/// GET /api/variants/:variant_id/ld
+///
+/// LD proxies of a variant with r² at or above `r2`, strongest first.
+#[utoipa::path(
+    get,
+    path = "/api/variants/{variant_id}/ld",
+    tag = "variants",
+    params(
+        ("variant_id" = String, Path, description = "Variant ID, e.g. chr1-12345-A-T"),
+        LdQuery
+    ),
+    responses(
+        (status = 200, description = "LD proxies, highest r² first", body = Vec<LdProxy>),
+        (status = 400, description = "Invalid variant ID", body = ErrorResponse)
+    )
+)]
+pub async fn get_ld_proxies(
+    State(state): State<Arc<AppState>>,
+    Path(variant_id): Path<String>,
+    Query(params): Query<LdQuery>,
+) -> Result<Json<Vec<LdProxy>>, AppError> {
+    let (xpos, ref_allele, alt_allele) = parse_variant_id(&variant_id)?;
+    let ancestry = params
+        .ancestry
+        .unwrap_or_else(|| "meta".to_string())
+        .to_lowercase();
+    let min_r2 = params.r2.unwrap_or(0.2);
+    let limit = params.limit.unwrap_or(500).clamp(1, 5000);
+
+    let query = r#"
+        SELECT xpos2, ref2, alt2, r2
+        FROM ld_pairs
+        WHERE ancestry = ? AND xpos1 = ? AND ref1 = ? AND alt1 = ? AND r2 >= ?
+        ORDER BY r2 DESC, xpos2 ASC
+        LIMIT ?
+    "#;
+    let rows = state
+        .clickhouse
+        .query(query)
+        .bind(&ancestry)
+        .bind(xpos)
+        .bind(&ref_allele)
+        .bind(&alt_allele)
+        .bind(min_r2)
+        .bind(limit)
+        .fetch_all_with::<LdPairRow>(&state.executor)
+        .await?;
+
+    let proxies = rows
+        .into_iter()
+        .map(|row| LdProxy {
+            variant_id: make_variant_id_from_xpos(row.xpos2, &row.ref2, &row.alt2),
+            xpos: row.xpos2,
+            r2: row.r2,
+            distance: row.xpos2 - xpos,
+        })
+        .collect();
+    Ok(Json(proxies))
+}
@@ -0,0 +1,79 @@
+//! Variant liftover handler
+//!
+//! Maps a variant ID between GRCh38 (the browser's build) and GRCh37 using
+//! the chain files loaded into `AppState::liftover`.
+
+use crate::api::AppState;
+use crate::error::{AppError, ErrorResponse};
+use crate::liftover::{Build, LiftedVariant};
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+/// Query parameters for the liftover endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LiftoverQuery {
+    /// Build to lift to: "GRCh37"/"hg19" (default) or "GRCh38"/"hg38"
+    pub target: Option<String>,
+    /// Build of `variant_id` (default: the other build)
+    pub source: Option<String>,
+}
+
+/// Response for GET /api/variants/:variant_id/liftover
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VariantLiftoverResponse {
+    pub source_build: Build,
+    pub source_variant_id: String,
+    pub target_build: Build,
+    /// Lifted variant ID, e.g. "chr1-55051215-G-GA"
+    pub variant_id: String,
+    pub lifted: LiftedVariant,
+}
+
+/// GET /api/variants/:variant_id/liftover
+///
+/// Lifts a variant between GRCh38 and GRCh37. Returns 404 when the variant
+/// falls outside the chain alignment, 400 when the direction is not configured.
+#[utoipa::path(
+    get,
+    path = "/api/variants/{variant_id}/liftover",
+    tag = "variants",
+    params(
+        ("variant_id" = String, Path, description = "Variant ID, e.g. chr1-12345-A-T"),
+        LiftoverQuery
+    ),
+    responses(
+        (status = 200, description = "Lifted variant", body = VariantLiftoverResponse),
+        (status = 400, description = "Invalid variant ID or liftover not configured", body = ErrorResponse),
+        (status = 404, description = "Variant does not lift over", body = ErrorResponse)
+    )
+)]
+pub async fn get_variant_liftover(
+    State(state): State<Arc<AppState>>,
+    Path(variant_id): Path<String>,
+    Query(params): Query<LiftoverQuery>,
+) -> Result<Json<VariantLiftoverResponse>, AppError> {
+    let target = match params.target.as_deref() {
+        Some(build) => Build::parse(build)?,
+        None => Build::GRCh37,
+    };
+    let source = match params.source.as_deref() {
+        Some(build) => Build::parse(build)?,
+        None if target == Build::GRCh37 => Build::GRCh38,
+        None => Build::GRCh37,
+    };
+
+    let lifted = state.liftover.lift_variant(&variant_id, source, target)?;
+    Ok(Json(VariantLiftoverResponse {
+        source_build: source,
+        source_variant_id: variant_id,
+        target_build: target,
+        variant_id: lifted.variant_id(),
+        lifted,
+    }))
+}
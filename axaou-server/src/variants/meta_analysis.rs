@@ -0,0 +1,245 @@
+//! Inverse-variance fixed-effect meta-analysis
+//!
+//! Combines per-ancestry effect estimates and measures their heterogeneity
+//! with Cochran's Q and I².
+
+use serde::Serialize;
+
+/// A single study's effect estimate
+#[derive(Debug, Clone, Copy)]
+pub struct Effect {
+    pub beta: f64,
+    pub se: f64,
+}
+
+/// Pooled fixed-effect estimate
+#[derive(Debug, Clone, Serialize)]
+pub struct PooledEffect {
+    pub beta: f64,
+    pub se: f64,
+    pub ci_lower: f64,
+    pub ci_upper: f64,
+    pub pvalue: f64,
+}
+
+/// Between-study heterogeneity statistics
+#[derive(Debug, Clone, Serialize)]
+pub struct Heterogeneity {
+    /// Cochran's Q
+    pub q: f64,
+    /// Degrees of freedom (number of studies - 1)
+    pub df: u32,
+    /// P-value of Q under the chi-square(df) null
+    pub pvalue: f64,
+    /// I² as a fraction in [0, 1]
+    pub i_squared: f64,
+}
+
+/// Meta-analysis result
+#[derive(Debug, Clone, Serialize)]
+pub struct MetaAnalysis {
+    pub pooled: PooledEffect,
+    /// None when fewer than two studies contribute
+    pub heterogeneity: Option<Heterogeneity>,
+}
+
+/// 97.5th percentile of the standard normal distribution
+const Z_95: f64 = 1.959_963_984_540_054;
+
+/// Inverse-variance weight of an effect (1 / se²); None for unusable estimates
+pub fn weight(effect: &Effect) -> Option<f64> {
+    (effect.beta.is_finite() && effect.se.is_finite() && effect.se > 0.0)
+        .then(|| 1.0 / (effect.se * effect.se))
+}
+
+/// 95% confidence interval of an effect
+pub fn confidence_interval(beta: f64, se: f64) -> (f64, f64) {
+    (beta - Z_95 * se, beta + Z_95 * se)
+}
+
+/// Fixed-effect meta-analysis of the usable effects (finite beta, se > 0)
+pub fn fixed_effect(effects: &[Effect]) -> Option<MetaAnalysis> {
+    let weighted: Vec<(f64, f64)> = effects
+        .iter()
+        .filter_map(|e| weight(e).map(|w| (e.beta, w)))
+        .collect();
+    if weighted.is_empty() {
+        return None;
+    }
+
+    let sum_w: f64 = weighted.iter().map(|(_, w)| w).sum();
+    let beta = weighted.iter().map(|(b, w)| b * w).sum::<f64>() / sum_w;
+    let se = (1.0 / sum_w).sqrt();
+    let z = beta / se;
+    let (ci_lower, ci_upper) = confidence_interval(beta, se);
+
+    let pooled = PooledEffect {
+        beta,
+        se,
+        ci_lower,
+        ci_upper,
+        pvalue: chi_square_sf(z * z, 1),
+    };
+
+    let heterogeneity = (weighted.len() >= 2).then(|| {
+        let q: f64 = weighted.iter().map(|(b, w)| w * (b - beta).powi(2)).sum();
+        let df = (weighted.len() - 1) as u32;
+        let i_squared = if q > 0.0 {
+            ((q - df as f64) / q).max(0.0)
+        } else {
+            0.0
+        };
+        Heterogeneity {
+            q,
+            df,
+            pvalue: chi_square_sf(q, df),
+            i_squared,
+        }
+    });
+
+    Some(MetaAnalysis {
+        pooled,
+        heterogeneity,
+    })
+}
+
+/// Survival function of the chi-square distribution, P(X > x)
+pub fn chi_square_sf(x: f64, df: u32) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    upper_regularized_gamma(df as f64 / 2.0, x / 2.0)
+}
+
+/// Regularized upper incomplete gamma function Q(a, x)
+///
+/// Series expansion for x < a + 1, continued fraction otherwise
+/// (Numerical Recipes, §6.2).
+fn upper_regularized_gamma(a: f64, x: f64) -> f64 {
+    const MAX_ITER: usize = 500;
+    const EPS: f64 = 1e-14;
+    const FPMIN: f64 = 1e-300;
+
+    let log_prefactor = a * x.ln() - x - ln_gamma(a);
+
+    if x < a + 1.0 {
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        let mut ap = a;
+        for _ in 0..MAX_ITER {
+            ap += 1.0;
+            term *= x / ap;
+            sum += term;
+            if term.abs() < sum.abs() * EPS {
+                break;
+            }
+        }
+        (1.0 - sum * log_prefactor.exp()).clamp(0.0, 1.0)
+    } else {
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / FPMIN;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..=MAX_ITER {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < FPMIN {
+                d = FPMIN;
+            }
+            c = b + an / c;
+            if c.abs() < FPMIN {
+                c = FPMIN;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < EPS {
+                break;
+            }
+        }
+        (log_prefactor.exp() * h).clamp(0.0, 1.0)
+    }
+}
+
+/// Natural log of the gamma function (Lanczos approximation)
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFS: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.120_865_097_386_617_9e-2,
+        -0.539_523_938_495_3e-5,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut ser = 1.000_000_000_190_015;
+    let mut y = x;
+    for c in COEFFS {
+        y += 1.0;
+        ser += c / y;
+    }
+    -tmp + (2.506_628_274_631_000_5 * ser / x).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chi_square_reference_values() {
+        // P(chi2_1 > 3.841459) = 0.05
+        assert!((chi_square_sf(3.841_459, 1) - 0.05).abs() < 1e-6);
+        // P(chi2_2 > 5.991465) = 0.05
+        assert!((chi_square_sf(5.991_465, 2) - 0.05).abs() < 1e-6);
+        // Large statistic uses the continued fraction branch
+        assert!((chi_square_sf(29.716_75, 1) - 5e-8).abs() < 1e-10);
+        assert_eq!(chi_square_sf(0.0, 3), 1.0);
+    }
+
+    #[test]
+    fn identical_effects_are_homogeneous() {
+        let effects = [
+            Effect { beta: 0.2, se: 0.1 },
+            Effect { beta: 0.2, se: 0.05 },
+        ];
+        let meta = fixed_effect(&effects).unwrap();
+        assert!((meta.pooled.beta - 0.2).abs() < 1e-12);
+        let het = meta.heterogeneity.unwrap();
+        assert!(het.q.abs() < 1e-12);
+        assert_eq!(het.i_squared, 0.0);
+        assert_eq!(het.df, 1);
+    }
+
+    #[test]
+    fn heterogeneous_effects() {
+        let effects = [
+            Effect { beta: 0.5, se: 0.1 },
+            Effect { beta: -0.5, se: 0.1 },
+            Effect { beta: 0.0, se: 0.1 },
+        ];
+        let meta = fixed_effect(&effects).unwrap();
+        assert!(meta.pooled.beta.abs() < 1e-12);
+        // se = sqrt(1 / (3 * 100))
+        assert!((meta.pooled.se - (1.0_f64 / 300.0).sqrt()).abs() < 1e-12);
+        let het = meta.heterogeneity.unwrap();
+        // Q = 100 * (0.25 + 0.25 + 0) = 50, I² = (50 - 2) / 50
+        assert!((het.q - 50.0).abs() < 1e-9);
+        assert!((het.i_squared - 0.96).abs() < 1e-9);
+        assert!(het.pvalue < 1e-10);
+    }
+
+    #[test]
+    fn unusable_effects_are_skipped() {
+        let effects = [
+            Effect { beta: 0.1, se: 0.0 },
+            Effect { beta: f64::NAN, se: 0.1 },
+            Effect { beta: 0.3, se: 0.1 },
+        ];
+        let meta = fixed_effect(&effects).unwrap();
+        assert!((meta.pooled.beta - 0.3).abs() < 1e-12);
+        assert!(meta.heterogeneity.is_none());
+        assert!(fixed_effect(&[]).is_none());
+    }
+}
@@ -1,7 +1,14 @@
 //! Variant query route handlers
 //!
-//! Provides endpoints for variant annotations, associations, and PheWAS queries.
+//! Provides endpoints for variant annotations, associations, PheWAS queries,
+//! forest plots with cross-ancestry meta-analysis, GRCh37 liftover, LD proxies,
+//! and VCF export.
 
 pub mod annotations;
 pub mod associations;
+pub mod forest;
+pub mod ld;
+pub mod liftover;
+pub mod meta_analysis;
 pub mod phewas;
+pub mod vcf;
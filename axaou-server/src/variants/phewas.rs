@@ -3,13 +3,20 @@
 //! Provides endpoints for cross-phenotype queries.
 
 use crate::api::AppState;
+use crate::clickhouse::QueryExt;
 use crate::clickhouse::models::SignificantVariantRow;
-use crate::clickhouse::xpos::{parse_interval_to_xpos, parse_variant_id};
-use crate::error::AppError;
+use crate::clickhouse::xpos::parse_variant_id;
+use crate::genomics::resolve_region;
+use crate::error::{AppError, ErrorResponse};
+use crate::liftover::BuildQuery;
 use crate::models::VariantAssociationApi;
-use crate::response::{LookupResult, QueryTimer};
+use crate::response::{
+    FormatQuery, LookupResult, QueryTimer, ResponseFormat, VariantAssociationLookup,
+};
 use axum::{
     extract::{Path, Query, State},
+    http::HeaderMap,
+    response::Response,
     Json,
 };
 use serde::Deserialize;
@@ -19,11 +26,34 @@ use std::sync::Arc;
 ///
 /// Returns all phenotypes where this variant is significant (fan-out query).
 /// This is the PheWAS endpoint for exploring variant associations across traits.
+/// `format=arrow|parquet` (or the matching `Accept`) returns the rows as an
+/// Arrow IPC stream or Parquet file.
+#[utoipa::path(
+    get,
+    path = "/api/variants/associations/phewas/{variant_id}",
+    tag = "variants",
+    params(
+        ("variant_id" = String, Path, description = "Variant ID, e.g. chr1-12345-A-T"),
+        BuildQuery,
+        FormatQuery
+    ),
+    responses(
+        (status = 200, description = "Associations across phenotypes", body = VariantAssociationLookup),
+        (status = 500, description = "ClickHouse query failed", body = ErrorResponse)
+    )
+)]
 pub async fn get_phewas_by_variant(
     State(state): State<Arc<AppState>>,
     Path(variant_id): Path<String>,
-) -> Result<Json<LookupResult<VariantAssociationApi>>, AppError> {
+    Query(params): Query<BuildQuery>,
+    Query(format): Query<FormatQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
     let timer = QueryTimer::start();
+    let format = ResponseFormat::negotiate(format.format, &headers);
+    let variant_id = state
+        .liftover
+        .to_grch38_variant_id(&variant_id, params.build.as_deref())?;
     let (xpos, ref_allele, alt_allele) = parse_variant_id(&variant_id)?;
 
     let query = r#"
@@ -43,9 +73,8 @@ pub async fn get_phewas_by_variant(
         .bind(xpos)
         .bind(&ref_allele)
         .bind(&alt_allele)
-        .fetch_all::<SignificantVariantRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with::<SignificantVariantRow>(&state.executor)
+        .await?;
 
     // Deduplicate by phenotype, keeping the row with the lowest pvalue
     let mut seen = std::collections::HashMap::new();
@@ -62,7 +91,7 @@ pub async fn get_phewas_by_variant(
     let mut api_rows: Vec<VariantAssociationApi> = seen.into_values().collect();
     api_rows.sort_by(|a, b| a.pvalue.partial_cmp(&b.pvalue).unwrap_or(std::cmp::Ordering::Equal));
 
-    Ok(Json(LookupResult::new(api_rows, timer.elapsed())))
+    format.lookup_response(LookupResult::new(api_rows, &timer))
 }
 
 /// Query parameters for top variants endpoint
@@ -110,12 +139,11 @@ pub async fn get_top_variants(
         .bind(min_p)
         .bind(max_p)
         .bind(limit)
-        .fetch_all::<SignificantVariantRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with::<SignificantVariantRow>(&state.executor)
+        .await?;
 
     let api_rows: Vec<VariantAssociationApi> = rows.into_iter().map(|r| r.to_api()).collect();
-    Ok(Json(LookupResult::new(api_rows, timer.elapsed())))
+    Ok(Json(LookupResult::new(api_rows, &timer)))
 }
 
 /// Query parameters for PheWAS interval endpoint
@@ -133,14 +161,16 @@ pub struct PhewasIntervalQuery {
 /// GET /api/variants/associations/phewas/interval/:interval
 ///
 /// Returns all significant variants within a genomic interval across all phenotypes.
-/// Interval format: "chr1:12345-67890"
+/// Interval format: "chr1:12345-67890", or any form accepted by `genomics::parse_region`
 pub async fn get_phewas_by_interval(
     State(state): State<Arc<AppState>>,
     Path(interval): Path<String>,
     Query(params): Query<PhewasIntervalQuery>,
 ) -> Result<Json<LookupResult<VariantAssociationApi>>, AppError> {
     let timer = QueryTimer::start();
-    let (xpos_start, xpos_end) = parse_interval_to_xpos(&interval)?;
+    let (xpos_start, xpos_end) = resolve_region(state.gene_models.as_ref(), &interval)
+        .await?
+        .xpos_range();
     let ancestry = params.ancestry.unwrap_or_else(|| "meta".to_string());
     let limit = params.limit.unwrap_or(10000);
 
@@ -160,12 +190,11 @@ pub async fn get_phewas_by_interval(
         .bind(xpos_end)
         .bind(&ancestry)
         .bind(limit)
-        .fetch_all::<SignificantVariantRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with::<SignificantVariantRow>(&state.executor)
+        .await?;
 
     let api_rows: Vec<VariantAssociationApi> = rows.into_iter().map(|r| r.to_api()).collect();
-    Ok(Json(LookupResult::new(api_rows, timer.elapsed())))
+    Ok(Json(LookupResult::new(api_rows, &timer)))
 }
 
 /// Query parameters for aggregated top variants endpoint
@@ -263,9 +292,8 @@ pub async fn get_top_variants_aggregated(
                 let gene_query = "SELECT xstart, xstop FROM gene_models WHERE symbol_upper_case = ? LIMIT 1";
                 let gene_coords = state.clickhouse.query(gene_query)
                     .bind(s_trim.to_uppercase())
-                    .fetch_optional::<GeneXCoords>()
-                    .await
-                    .map_err(|e| AppError::DataTransformError(format!("Gene lookup error: {}", e)))?;
+                    .fetch_optional_with::<GeneXCoords>(&state.executor)
+                    .await?;
 
                 if let Some(coords) = gene_coords {
                     where_sql.push_str(" AND tva.xpos >= ? AND tva.xpos <= ?");
@@ -356,16 +384,15 @@ pub async fn get_top_variants_aggregated(
     }
 
     let rows = query
-        .fetch_all::<crate::clickhouse::models::AggregatedVariantRow>()
-        .await
-        .map_err(|e| AppError::DataTransformError(format!("ClickHouse query error: {}", e)))?;
+        .fetch_all_with::<crate::clickhouse::models::AggregatedVariantRow>(&state.executor)
+        .await?;
 
     let api_rows: Vec<crate::models::AggregatedVariantApi> =
         rows.into_iter().map(|r| r.to_api()).collect();
 
-    let result = LookupResult::new(api_rows, timer.elapsed());
+    let result = LookupResult::new(api_rows, &timer);
     let json_bytes =
-        serde_json::to_vec(&result).map_err(|e| AppError::DataTransformError(e.to_string()))?;
+        serde_json::to_vec(&result).map_err(|e| AppError::Internal(e.to_string()))?;
 
     state
         .api_cache
@@ -0,0 +1,210 @@
+//! Minimal VCF 4.2 rendering of variant annotations
+//!
+//! Sites-only VCF (no samples) for loading interval annotations into IGV or
+//! bcftools. INFO carries gene, consequence and allele frequency, plus the
+//! association stats of one analysis when they are supplied.
+
+use crate::genomics::Contig;
+use crate::models::VariantAnnotationApi;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+/// Media type of VCF responses (served as text so browsers display it)
+pub const VCF_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+/// Association stats of one variant in the exported analysis
+#[derive(Debug, Clone, PartialEq)]
+pub struct VcfAssociation {
+    pub pvalue: f64,
+    pub beta: Option<f64>,
+    pub se: Option<f64>,
+}
+
+/// Escape characters that are not allowed in INFO values (VCF 4.3 style)
+fn escape_info(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '%' => escaped.push_str("%25"),
+            ';' => escaped.push_str("%3B"),
+            '=' => escaped.push_str("%3D"),
+            ',' => escaped.push_str("%2C"),
+            ' ' => escaped.push_str("%20"),
+            '\t' => escaped.push_str("%09"),
+            '\n' => escaped.push_str("%0A"),
+            '\r' => escaped.push_str("%0D"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Genome-wide sort key; unknown contigs sort last
+fn sort_key(row: &VariantAnnotationApi) -> (i64, u32) {
+    let xpos = Contig::parse(&row.locus.contig)
+        .map(|c| c.xpos(row.locus.position))
+        .unwrap_or(i64::MAX);
+    (xpos, row.locus.position)
+}
+
+/// Render `rows` as VCF, sorted by position
+///
+/// `associations` maps variant IDs to the stats of `analysis_id`; the PVAL,
+/// BETA and SE INFO fields are declared only when an analysis is given.
+pub fn render_vcf(
+    rows: &[VariantAnnotationApi],
+    analysis_id: Option<&str>,
+    associations: &HashMap<String, VcfAssociation>,
+) -> String {
+    let mut sorted: Vec<&VariantAnnotationApi> = rows.iter().collect();
+    sorted.sort_by_key(|row| sort_key(row));
+
+    let mut out = String::new();
+    out.push_str("##fileformat=VCFv4.2\n");
+    out.push_str("##source=axaou-server\n");
+    out.push_str("##reference=GRCh38\n");
+    let contigs: BTreeSet<Contig> = rows
+        .iter()
+        .filter_map(|row| Contig::parse(&row.locus.contig))
+        .collect();
+    for contig in contigs {
+        let _ = writeln!(
+            out,
+            "##contig=<ID={},length={},assembly=GRCh38>",
+            contig.chr(),
+            contig.length()
+        );
+    }
+    out.push_str("##INFO=<ID=GENE,Number=1,Type=String,Description=\"Gene symbol\">\n");
+    out.push_str("##INFO=<ID=GENE_ID,Number=1,Type=String,Description=\"Ensembl gene ID\">\n");
+    out.push_str(
+        "##INFO=<ID=CSQ,Number=1,Type=String,Description=\"Most severe VEP consequence\">\n",
+    );
+    out.push_str("##INFO=<ID=AF,Number=A,Type=Float,Description=\"Allele frequency\">\n");
+    if let Some(analysis_id) = analysis_id {
+        let analysis = escape_info(analysis_id).replace('"', "'");
+        let _ = writeln!(
+            out,
+            "##INFO=<ID=PVAL,Number=A,Type=Float,Description=\"Association p-value ({})\">",
+            analysis
+        );
+        let _ = writeln!(
+            out,
+            "##INFO=<ID=BETA,Number=A,Type=Float,Description=\"Effect size ({})\">",
+            analysis
+        );
+        let _ = writeln!(
+            out,
+            "##INFO=<ID=SE,Number=A,Type=Float,Description=\"Standard error of BETA ({})\">",
+            analysis
+        );
+    }
+    out.push_str("#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n");
+
+    for row in sorted {
+        let mut info: Vec<String> = Vec::new();
+        if let Some(gene) = row.gene_symbol.as_deref().filter(|g| !g.is_empty()) {
+            info.push(format!("GENE={}", escape_info(gene)));
+        }
+        if let Some(gene_id) = row.gene_id.as_deref().filter(|g| !g.is_empty()) {
+            info.push(format!("GENE_ID={}", escape_info(gene_id)));
+        }
+        if let Some(csq) = row.consequence.as_deref().filter(|c| !c.is_empty()) {
+            info.push(format!("CSQ={}", escape_info(csq)));
+        }
+        if let Some(af) = row.allele_frequency {
+            info.push(format!("AF={}", af));
+        }
+        if analysis_id.is_some() {
+            if let Some(assoc) = associations.get(&row.variant_id) {
+                info.push(format!("PVAL={:e}", assoc.pvalue));
+                if let Some(beta) = assoc.beta {
+                    info.push(format!("BETA={}", beta));
+                }
+                if let Some(se) = assoc.se {
+                    info.push(format!("SE={}", se));
+                }
+            }
+        }
+        let info = if info.is_empty() {
+            ".".to_string()
+        } else {
+            info.join(";")
+        };
+        let chrom = Contig::parse(&row.locus.contig)
+            .map(|c| c.chr().to_string())
+            .unwrap_or_else(|| row.locus.contig.clone());
+        let _ = writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{}\t.\t.\t{}",
+            chrom, row.locus.position, row.variant_id, row.ref_allele, row.alt, info
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Locus;
+
+    fn annotation(contig: &str, position: u32, gene: Option<&str>) -> VariantAnnotationApi {
+        VariantAnnotationApi {
+            variant_id: format!("{}-{}-A-T", contig, position),
+            locus: Locus::new(contig.to_string(), position),
+            ref_allele: "A".to_string(),
+            alt: "T".to_string(),
+            gene_symbol: gene.map(str::to_string),
+            gene_id: None,
+            consequence: Some("missense_variant".to_string()),
+            allele_frequency: Some(0.25),
+            hgvsc: None,
+            hgvsp: None,
+            allele_count: None,
+            allele_number: None,
+            homozygote_count: None,
+            polyphen2: None,
+            amino_acids: None,
+            lof: None,
+            cadd_phred: None,
+            revel_max: None,
+            spliceai_ds_max: None,
+            alphamissense_score: None,
+            alphamissense_class: None,
+            ancestry_frequencies: None,
+        }
+    }
+
+    #[test]
+    fn test_render_vcf() {
+        let rows = vec![
+            annotation("chr2", 50, None),
+            annotation("chr1", 200, Some("GENE A;B")),
+        ];
+        let mut associations = HashMap::new();
+        associations.insert(
+            "chr1-200-A-T".to_string(),
+            VcfAssociation {
+                pvalue: 1e-9,
+                beta: Some(0.5),
+                se: None,
+            },
+        );
+
+        let vcf = render_vcf(&rows, Some("height"), &associations);
+        assert!(vcf.starts_with("##fileformat=VCFv4.2\n"));
+        assert!(vcf.contains("##contig=<ID=chr1,length="));
+        assert!(vcf.contains("##INFO=<ID=PVAL"));
+        let records: Vec<&str> = vcf.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(
+            records,
+            vec![
+                "chr1\t200\tchr1-200-A-T\tA\tT\t.\t.\tGENE=GENE%20A%3BB;CSQ=missense_variant;AF=0.25;PVAL=1e-9;BETA=0.5",
+                "chr2\t50\tchr2-50-A-T\tA\tT\t.\t.\tCSQ=missense_variant;AF=0.25",
+            ]
+        );
+
+        let plain = render_vcf(&rows, None, &associations);
+        assert!(!plain.contains("PVAL"));
+    }
+}
@@ -0,0 +1,180 @@
+//! Build and data provenance endpoint
+//!
+//! `GET /api/version` reports the server build (crate version, git commit),
+//! the dataset freeze being served, and per-table row counts with the time of
+//! the last successful ingest from `ingest_runs`, so the frontend footer and
+//! support tickets can pin the exact data behind a response.
+
+use crate::api::AppState;
+use crate::clickhouse::QueryExt;
+use crate::error::{AppError, ErrorResponse};
+use axum::{extract::State, Json};
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, LazyLock};
+use utoipa::ToSchema;
+
+/// Git commit of this build, resolved once (may shell out to `git`)
+static GIT_SHA: LazyLock<String> = LazyLock::new(crate::cli::history::git_sha);
+
+#[derive(Debug, Clone, Deserialize, Row)]
+struct TableRowsRow {
+    table: String,
+    rows: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Row)]
+struct LastIngestRow {
+    table_name: String,
+    last_ingest: String,
+}
+
+/// Server build information
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BuildInfo {
+    pub crate_version: String,
+    pub git_sha: String,
+}
+
+/// Row count and last ingest time of one ClickHouse table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TableVersion {
+    pub table: String,
+    pub rows: u64,
+    /// Finish time of the last successful `ingest` run, when recorded
+    pub last_ingest: Option<String>,
+}
+
+/// Response for GET /api/version
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VersionInfo {
+    pub build: BuildInfo,
+    /// Data freeze identifier (e.g., "414k")
+    pub dataset_version: String,
+    pub data_freeze_date: Option<String>,
+    /// Pipeline output version from phenotype-data.toml
+    pub data_version: Option<String>,
+    /// Tables of the dataset's database, by name
+    pub tables: Vec<TableVersion>,
+}
+
+/// Merge row counts with last ingest times; tables known only from
+/// `ingest_runs` (since dropped) report 0 rows
+fn table_versions(rows: Vec<TableRowsRow>, ingests: Vec<LastIngestRow>) -> Vec<TableVersion> {
+    let mut tables: BTreeMap<String, TableVersion> = rows
+        .into_iter()
+        .map(|r| {
+            let version = TableVersion {
+                table: r.table.clone(),
+                rows: r.rows,
+                last_ingest: None,
+            };
+            (r.table, version)
+        })
+        .collect();
+    for ingest in ingests {
+        tables
+            .entry(ingest.table_name.clone())
+            .or_insert_with(|| TableVersion {
+                table: ingest.table_name,
+                rows: 0,
+                last_ingest: None,
+            })
+            .last_ingest = Some(ingest.last_ingest);
+    }
+    tables.into_values().collect()
+}
+
+/// GET /api/version
+///
+/// Server build, dataset freeze, and per-table row counts and last ingest
+/// times. Ingest staging tables (`staging_*`) are left out.
+#[utoipa::path(
+    get,
+    path = "/api/version",
+    tag = "config",
+    responses(
+        (status = 200, description = "Build and data provenance", body = VersionInfo),
+        (status = 500, description = "ClickHouse query failed", body = ErrorResponse)
+    )
+)]
+pub async fn get_version(State(state): State<Arc<AppState>>) -> Result<Json<VersionInfo>, AppError> {
+    let rows_query = r#"
+        SELECT table, sum(rows) AS rows
+        FROM system.parts
+        WHERE active AND database = currentDatabase() AND NOT startsWith(table, 'staging_')
+        GROUP BY table
+    "#;
+    let rows = state
+        .clickhouse
+        .query(rows_query)
+        .fetch_all_with::<TableRowsRow>(&state.executor)
+        .await?;
+
+    let ingest_query = r#"
+        SELECT table_name, toString(max(finished_at)) AS last_ingest
+        FROM ingest_runs
+        WHERE status = 'succeeded'
+        GROUP BY table_name
+    "#;
+    let ingests = match state
+        .clickhouse
+        .query(ingest_query)
+        .fetch_all_with::<LastIngestRow>(&state.executor)
+        .await
+    {
+        Ok(ingests) => ingests,
+        // UNKNOWN_TABLE: nothing has been ingested with provenance yet
+        Err(AppError::UpstreamClickHouse(msg)) if msg.contains("Code: 60.") => Vec::new(),
+        Err(e) => return Err(e),
+    };
+
+    Ok(Json(VersionInfo {
+        build: BuildInfo {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha: GIT_SHA.clone(),
+        },
+        dataset_version: state.config.dataset_version.clone(),
+        data_freeze_date: state.config.data_freeze_date.clone(),
+        data_version: state.data_version.clone(),
+        tables: table_versions(rows, ingests),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_versions() {
+        let rows = vec![
+            TableRowsRow {
+                table: "gene_models".to_string(),
+                rows: 60_000,
+            },
+            TableRowsRow {
+                table: "analysis_metadata".to_string(),
+                rows: 25_000,
+            },
+        ];
+        let ingests = vec![
+            LastIngestRow {
+                table_name: "gene_models".to_string(),
+                last_ingest: "2026-02-02 09:42:00".to_string(),
+            },
+            LastIngestRow {
+                table_name: "ld_pairs".to_string(),
+                last_ingest: "2026-01-15 12:00:00".to_string(),
+            },
+        ];
+        let tables = table_versions(rows, ingests);
+        assert_eq!(
+            tables.iter().map(|t| t.table.as_str()).collect::<Vec<_>>(),
+            vec!["analysis_metadata", "gene_models", "ld_pairs"]
+        );
+        assert_eq!(tables[0].last_ingest, None);
+        assert_eq!(tables[1].last_ingest.as_deref(), Some("2026-02-02 09:42:00"));
+        assert_eq!(tables[2].rows, 0);
+    }
+}
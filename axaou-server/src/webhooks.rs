@@ -0,0 +1,371 @@
+//! Outbound webhook notifications
+//!
+//! Posts a JSON event to every configured URL when an ingest run finishes,
+//! asset re-discovery finds added or removed assets, or a dataset's readiness
+//! changes, so Slack and ops tooling can react without polling
+//! `ingest status` or `/readyz`:
+//!
+//! ```toml
+//! [webhooks]
+//! urls = ["https://hooks.slack.com/services/..."]
+//! # Optional, HMAC-SHA256 key for the X-Axaou-Signature header
+//! secret = "..."
+//! # Optional, events to send (default: all)
+//! events = ["ingest.finished", "assets.changed", "health.degraded", "health.recovered"]
+//! timeout_secs = 10
+//! # How often `serve` checks readiness for health events
+//! health_check_secs = 60
+//! ```
+//!
+//! `AXAOU_WEBHOOK_URLS` (comma-separated) and `AXAOU_WEBHOOK_SECRET` override
+//! the file. Each delivery carries `X-Axaou-Event`, `X-Axaou-Timestamp` (Unix
+//! seconds) and, with a secret, `X-Axaou-Signature: sha256=<hex>`: the
+//! HMAC-SHA256 of `"{timestamp}.{body}"`. Deliveries are retried a few times
+//! and failures are only logged. The server posts through [`spawn`] so a slow
+//! endpoint never holds up asset re-discovery or the health monitor.
+
+use crate::config::{Config, CONFIG_PATH_ENV};
+use crate::health::DatasetReadiness;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Attempts per URL before a delivery is given up
+const MAX_ATTEMPTS: u32 = 3;
+
+/// `[webhooks]` section of the dataset config
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// Endpoints every event is posted to (default: webhooks disabled)
+    pub urls: Vec<String>,
+    /// HMAC-SHA256 signing key
+    pub secret: Option<String>,
+    /// Event names to send (default: all)
+    pub events: Vec<String>,
+    /// Per-request timeout in seconds
+    pub timeout_secs: u64,
+    /// Readiness check interval for health events, in seconds
+    pub health_check_secs: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            urls: Vec::new(),
+            secret: None,
+            events: Vec::new(),
+            timeout_secs: 10,
+            health_check_secs: 60,
+        }
+    }
+}
+
+impl WebhookConfig {
+    /// Webhooks for CLI commands without a loaded `Config`: the section of
+    /// the `AXAOU_CONFIG` file, if any, plus environment overrides
+    pub fn from_environment() -> Self {
+        let mut config = std::env::var(CONFIG_PATH_ENV)
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| Config::from_toml(&contents).ok())
+            .map(|config| config.webhooks)
+            .unwrap_or_default();
+        config.apply_env_overrides(|key| std::env::var(key).ok());
+        config
+    }
+
+    pub(crate) fn apply_env_overrides(&mut self, get: impl Fn(&str) -> Option<String>) {
+        if let Some(v) = get("AXAOU_WEBHOOK_URLS") {
+            self.urls = v
+                .split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Some(v) = get("AXAOU_WEBHOOK_SECRET") {
+            self.secret = Some(v);
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.urls.is_empty()
+    }
+
+    fn wants(&self, event: &Event) -> bool {
+        self.enabled() && (self.events.is_empty() || self.events.iter().any(|e| e == event.name()))
+    }
+}
+
+/// Event payloads, serialized under `data`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", content = "data")]
+pub enum Event {
+    #[serde(rename = "ingest.finished")]
+    IngestFinished {
+        run_id: String,
+        table: String,
+        source_uri: String,
+        /// "succeeded" or "failed"
+        status: String,
+        rows: u64,
+        duration_secs: f64,
+        error: Option<String>,
+    },
+    #[serde(rename = "assets.changed")]
+    AssetsChanged {
+        dataset: String,
+        added: usize,
+        removed: usize,
+        total: usize,
+        /// Phenotypes with added assets (at most 100)
+        added_phenotypes: Vec<String>,
+        /// Phenotypes with removed assets (at most 100)
+        removed_phenotypes: Vec<String>,
+    },
+    #[serde(rename = "health.degraded")]
+    HealthDegraded(DatasetReadiness),
+    #[serde(rename = "health.recovered")]
+    HealthRecovered(DatasetReadiness),
+}
+
+impl Event {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::IngestFinished { .. } => "ingest.finished",
+            Event::AssetsChanged { .. } => "assets.changed",
+            Event::HealthDegraded(_) => "health.degraded",
+            Event::HealthRecovered(_) => "health.recovered",
+        }
+    }
+
+    /// One-line summary, shown by Slack incoming webhooks
+    fn text(&self) -> String {
+        match self {
+            Event::IngestFinished { table, status, rows, error, .. } => match error {
+                Some(error) => format!("Ingest of {} {}: {}", table, status, error),
+                None => format!("Ingest of {} {} ({} rows)", table, status, rows),
+            },
+            Event::AssetsChanged { dataset, added, removed, total, .. } => format!(
+                "Assets of {} changed: {} added, {} removed ({} total)",
+                dataset, added, removed, total
+            ),
+            Event::HealthDegraded(r) => format!(
+                "Dataset {} is not ready (metadata loaded: {}, ClickHouse reachable: {}, assets discovered: {})",
+                r.dataset, r.metadata_loaded, r.clickhouse_reachable, r.assets_discovered
+            ),
+            Event::HealthRecovered(r) => format!("Dataset {} is ready again", r.dataset),
+        }
+    }
+}
+
+/// Body posted for every event
+#[derive(Debug, Serialize)]
+struct Delivery<'a> {
+    id: String,
+    timestamp: String,
+    text: String,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+/// Post `event` in the background, for callers that must not wait on delivery
+pub fn spawn(config: &WebhookConfig, event: Event) {
+    if !config.wants(&event) {
+        return;
+    }
+    let config = config.clone();
+    tokio::spawn(async move { send(&config, event).await });
+}
+
+/// Post `event` to every configured URL; never fails, delivery errors are logged
+pub async fn send(config: &WebhookConfig, event: Event) {
+    if !config.wants(&event) {
+        return;
+    }
+    let delivery = Delivery {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        text: event.text(),
+        event: &event,
+    };
+    let body = match serde_json::to_vec(&delivery) {
+        Ok(body) => body,
+        Err(e) => {
+            warn!("Failed to serialize {} webhook: {}", event.name(), e);
+            return;
+        }
+    };
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(config.timeout_secs.max(1)))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Failed to create webhook client: {}", e);
+            return;
+        }
+    };
+
+    let unix_secs = chrono::Utc::now().timestamp().to_string();
+    let signature = config
+        .secret
+        .as_deref()
+        .map(|secret| format!("sha256={}", sign(secret, &unix_secs, &body)));
+    let deliveries = config.urls.iter().map(|url| {
+        deliver(&client, url, event.name(), &unix_secs, signature.as_deref(), &body)
+    });
+    futures::future::join_all(deliveries).await;
+}
+
+async fn deliver(
+    client: &reqwest::Client,
+    url: &str,
+    event: &str,
+    unix_secs: &str,
+    signature: Option<&str>,
+    body: &[u8],
+) {
+    for attempt in 1..=MAX_ATTEMPTS {
+        let mut request = client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Axaou-Event", event)
+            .header("X-Axaou-Timestamp", unix_secs)
+            .body(body.to_vec());
+        if let Some(signature) = signature {
+            request = request.header("X-Axaou-Signature", signature);
+        }
+        let error = match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                debug!("Delivered {} webhook to {}", event, url);
+                return;
+            }
+            Ok(response) => format!("HTTP {}", response.status()),
+            Err(e) => e.to_string(),
+        };
+        if attempt == MAX_ATTEMPTS {
+            warn!("Giving up on {} webhook to {}: {}", event, url, error);
+        } else {
+            debug!("{} webhook to {} failed ({}), retrying", event, url, error);
+            tokio::time::sleep(Duration::from_secs(1 << (attempt - 1))).await;
+        }
+    }
+}
+
+/// Hex HMAC-SHA256 of `"{timestamp}.{body}"`
+pub fn sign(secret: &str, timestamp: &str, body: &[u8]) -> String {
+    let mut message = Vec::with_capacity(timestamp.len() + 1 + body.len());
+    message.extend_from_slice(timestamp.as_bytes());
+    message.push(b'.');
+    message.extend_from_slice(body);
+    hmac_sha256_hex(secret.as_bytes(), &message)
+}
+
+fn hmac_sha256_hex(key: &[u8], message: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hmac_sha256() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            hmac_sha256_hex(b"Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            sign("Jefe", "1700000000", b"{}"),
+            hmac_sha256_hex(b"Jefe", b"1700000000.{}")
+        );
+    }
+
+    #[test]
+    fn test_delivery_body() {
+        let event = Event::AssetsChanged {
+            dataset: "414k".to_string(),
+            added: 2,
+            removed: 0,
+            total: 10,
+            added_phenotypes: vec!["height".to_string()],
+            removed_phenotypes: Vec::new(),
+        };
+        let delivery = Delivery {
+            id: "1".to_string(),
+            timestamp: "2025-01-01T00:00:00Z".to_string(),
+            text: event.text(),
+            event: &event,
+        };
+        let body = serde_json::to_value(&delivery).unwrap();
+        assert_eq!(body["event"], "assets.changed");
+        assert_eq!(body["data"]["added"], 2);
+        assert_eq!(body["data"]["added_phenotypes"][0], "height");
+        assert_eq!(body["text"], "Assets of 414k changed: 2 added, 0 removed (10 total)");
+    }
+
+    #[test]
+    fn test_config() {
+        let config: Config = Config::from_toml(
+            "[webhooks]\nurls = [\"https://example.org/hook\"]\nevents = [\"health.degraded\"]\n",
+        )
+        .unwrap();
+        let webhooks = config.webhooks;
+        assert_eq!(webhooks.timeout_secs, 10);
+        let ingest = Event::IngestFinished {
+            run_id: "r".to_string(),
+            table: "gene_models".to_string(),
+            source_uri: "gs://b/t.ht".to_string(),
+            status: "succeeded".to_string(),
+            rows: 1,
+            duration_secs: 1.0,
+            error: None,
+        };
+        assert!(!webhooks.wants(&ingest));
+        assert!(!WebhookConfig::default().wants(&ingest));
+
+        let mut webhooks = WebhookConfig::default();
+        webhooks.apply_env_overrides(|key| match key {
+            "AXAOU_WEBHOOK_URLS" => Some("https://a.example/hook, https://b.example/hook".to_string()),
+            _ => None,
+        });
+        assert_eq!(webhooks.urls.len(), 2);
+        assert!(webhooks.wants(&ingest));
+    }
+
+    #[tokio::test]
+    async fn test_spawn_does_not_wait_for_endpoint() {
+        // An endpoint that accepts the connection but never responds
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = WebhookConfig {
+            urls: vec![format!("http://{}/hook", listener.local_addr().unwrap())],
+            ..WebhookConfig::default()
+        };
+        let event = Event::HealthRecovered(DatasetReadiness {
+            dataset: "414k".to_string(),
+            ready: true,
+            metadata_loaded: true,
+            metadata_count: 1,
+            clickhouse_reachable: true,
+            assets_discovered: true,
+        });
+        let started = std::time::Instant::now();
+        spawn(&config, event);
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // Delivery still happens in the background
+        let accepted = tokio::time::timeout(Duration::from_secs(5), listener.accept()).await;
+        assert!(accepted.unwrap().is_ok());
+    }
+}
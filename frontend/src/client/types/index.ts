@@ -105,6 +105,14 @@ interface LookupResult<T = any> {
   data: T[]
   storage_source: string
   time: number
+  /** Seconds of `time` waiting on ClickHouse */
+  db_time?: number
+  /** Seconds of `time` outside ClickHouse */
+  transform_time?: number
+  /** Seconds spent serializing `data` */
+  serialize_time?: number
+  /** ClickHouse queries issued */
+  db_queries?: number
   compressed_data_column: boolean
 }
 